@@ -0,0 +1,49 @@
+//! Measure how much a precomputed [AES key
+//! schedule](literate_crypto::AesKeySchedule) saves on a long message in [CTR
+//! mode](literate_crypto::Ctr), compared to expanding the key for every block.
+//!
+//! ```text
+//! cargo run --release --example aes
+//! cargo run --release --example aes -- 16
+//! ```
+//!
+//! The optional argument is the length of the message in MiB, 1 by default.
+//! Expanding an AES-128 key costs about as much as encrypting one block, so the
+//! speedup should approach 2x.
+
+use {
+    literate_crypto::{Aes128, CipherEncrypt, Ctr},
+    std::{env, hint, time::Instant},
+};
+
+const KEY: [u8; 16] = *b"a sixteen-b key!";
+
+fn main() {
+    let mib: usize = env::args()
+        .nth(1)
+        .map_or(1, |arg| arg.parse().expect("length of the message in MiB"));
+    let data: Vec<u8> = (0..mib << 20).map(|i| i as u8).collect();
+
+    let ctr = Ctr::new(Aes128::default(), 7).unwrap();
+    let start = Instant::now();
+    let ciphertext = ctr.encrypt_from(&data, KEY).unwrap();
+    let baseline = start.elapsed();
+
+    let start = Instant::now();
+    let ctr = Ctr::new(Aes128::default().schedule(KEY), 7).unwrap();
+    let precomputed = ctr.encrypt_from(&data, ()).unwrap();
+    let elapsed = start.elapsed();
+    assert_eq!(hint::black_box(ciphertext), hint::black_box(precomputed));
+
+    for (name, time) in [("Aes128", baseline), ("AesKeySchedule", elapsed)] {
+        println!(
+            "{name:>14}: {:>8.1} ms, {:>6.1} MiB/s",
+            time.as_secs_f64() * 1000.0,
+            mib as f64 / time.as_secs_f64()
+        );
+    }
+    println!(
+        "speedup {:.2}x",
+        baseline.as_secs_f64() / elapsed.as_secs_f64()
+    );
+}
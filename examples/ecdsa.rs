@@ -0,0 +1,90 @@
+//! Measure how long [ECDSA](literate_crypto::Ecdsa) takes to sign and verify
+//! a message on [secp256k1](literate_crypto::Secp256k1).
+//!
+//! ```text
+//! cargo run --release --example ecdsa
+//! cargo run --release --example ecdsa -- 100
+//! ```
+//!
+//! The optional argument is the number of messages, 10 by default. Almost all
+//! of the time goes into [scalar multiplication](literate_crypto::ecc::Group),
+//! so this is mostly a measure of the field arithmetic.
+//!
+//! Verification computes `uG + vP`. The last lines compare the two products
+//! added together with [Shamir's trick](literate_crypto::ecc::mul_double),
+//! which shares the doublings between them and should take about half as
+//! long.
+
+use {
+    literate_crypto::{
+        ecc::{self, Curve, Num, PrivateKey},
+        Ecdsa,
+        Secp256k1,
+        Sha256,
+        SignatureScheme,
+    },
+    std::{env, hint, time::Instant},
+};
+
+fn main() {
+    let count: u64 = env::args()
+        .nth(1)
+        .map_or(10, |arg| arg.parse().expect("number of messages"));
+    let key = PrivateKey::<Secp256k1>::new(Num::from_le_words([
+        0x0123_4567_89ab_cdef,
+        0xfedc_ba98_7654_3210,
+        0x0f1e_2d3c_4b5a_6978,
+        0x1234_5678_9abc_def0,
+    ]))
+    .unwrap();
+    let pubkey = key.derive();
+    let messages: Vec<_> = (0..count).map(u64::to_le_bytes).collect();
+    let mut ecdsa = Ecdsa::new(Secp256k1::default(), Sha256::default());
+
+    let start = Instant::now();
+    let sigs: Vec<_> = messages
+        .iter()
+        .map(|msg| ecdsa.sign(key.clone(), msg))
+        .collect();
+    let signing = start.elapsed();
+
+    let start = Instant::now();
+    for (msg, sig) in messages.iter().zip(&sigs) {
+        ecdsa.verify(pubkey, msg, sig).unwrap();
+    }
+    let verifying = start.elapsed();
+
+    for (name, time) in [("sign", signing), ("verify", verifying)] {
+        println!(
+            "{name:>8}: {:>8.2} ms per message",
+            time.as_secs_f64() * 1000.0 / count as f64
+        );
+    }
+
+    // The sum uG + vP which verification computes, with made-up u and v.
+    let (g, p) = (Secp256k1::g(), pubkey.point());
+    let u = Num::from_le_words([0x5555_5555_5555_5555; 4]);
+    let v = Num::from_le_words([0x0f0f_0f0f_0f0f_0f0f; 4]);
+    let start = Instant::now();
+    for _ in 0..count {
+        hint::black_box(u * g + v * p);
+    }
+    let separate = start.elapsed();
+
+    let start = Instant::now();
+    for _ in 0..count {
+        hint::black_box(ecc::mul_double(u, g, v, p));
+    }
+    let shamir = start.elapsed();
+
+    for (name, time) in [("separate", separate), ("shamir", shamir)] {
+        println!(
+            "{name:>8}: {:>8.2} ms per uG + vP",
+            time.as_secs_f64() * 1000.0 / count as f64
+        );
+    }
+    println!(
+        "speedup {:.2}x",
+        separate.as_secs_f64() / shamir.as_secs_f64()
+    );
+}
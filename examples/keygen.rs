@@ -0,0 +1,52 @@
+//! Generate a fresh secp256k1 keypair and print it in hex.
+//!
+//! ```text
+//! cargo run --example keygen
+//! ```
+//!
+//! The private key is drawn from [Fortuna](literate_crypto::Fortuna), seeded by
+//! the [operating system](literate_crypto::OsEntropy). The public key is
+//! printed in the SEC1 compressed and uncompressed encodings, which can be
+//! checked against other tools, e.g. by importing the private key into OpenSSL.
+
+use {
+    literate_crypto::{
+        ecc::{Curve, Num, PrivateKey},
+        Csprng,
+        Fortuna,
+        Secp256k1,
+    },
+    std::fmt::Write,
+};
+
+fn main() {
+    let mut rng = Fortuna::from_os();
+
+    // Draw random numbers until one of them is a valid private key.
+    let (n, key) = loop {
+        let mut bytes = [0; Num::BYTES];
+        rng.fill_bytes(&mut bytes);
+        let n = Num::from_le_bytes(bytes);
+        if let Ok(key) = PrivateKey::<Secp256k1>::new(n) {
+            break (n, key);
+        }
+    };
+    let pubkey = key.derive();
+
+    let x = pubkey.x().to_be_bytes();
+    let y = pubkey.y().to_be_bytes();
+    let prefix = if pubkey.y().get_bit(0) { 0x03 } else { 0x02 };
+
+    println!("curve: secp256k1");
+    println!("order: {}", hex(&Secp256k1::N.to_be_bytes()));
+    println!("private key: {}", hex(&n.to_be_bytes()));
+    println!("public key (compressed): {:02x}{}", prefix, hex(&x));
+    println!("public key (uncompressed): 04{}{}", hex(&x), hex(&y));
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().fold(String::new(), |mut s, b| {
+        write!(s, "{b:02x}").unwrap();
+        s
+    })
+}
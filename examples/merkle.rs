@@ -0,0 +1,41 @@
+//! Measure how building a [Merkle tree](literate_crypto::MerkleTree) scales
+//! with the number of threads.
+//!
+//! ```text
+//! cargo run --release --example merkle
+//! cargo run --release --example merkle -- 256
+//! ```
+//!
+//! The optional argument is the size of the data in MiB, which is split into
+//! 4 KiB chunks. The root is printed for every thread count, and it is always
+//! the same.
+
+use {
+    literate_crypto::{hash_chunked_parallel, hex, Sha256},
+    std::{env, thread, time::Instant},
+};
+
+const CHUNK_SIZE: usize = 4096;
+
+fn main() {
+    let mib: usize = env::args()
+        .nth(1)
+        .map_or(64, |arg| arg.parse().expect("size in MiB"));
+    let data: Vec<u8> = (0..mib << 20).map(|i| (i % 251) as u8).collect();
+    let max_threads = thread::available_parallelism().map_or(4, |n| n.get());
+    let hash = Sha256::default();
+
+    let mut baseline = None;
+    for threads in (0..).map(|i| 1 << i).take_while(|&t| t <= max_threads) {
+        let start = Instant::now();
+        let root = hash_chunked_parallel(&hash, &data, CHUNK_SIZE, threads);
+        let elapsed = start.elapsed();
+        let baseline = *baseline.get_or_insert(elapsed);
+        println!(
+            "{threads:>3} threads: {:>8.1} ms, speedup {:.2}x, root {}",
+            elapsed.as_secs_f64() * 1000.0,
+            baseline.as_secs_f64() / elapsed.as_secs_f64(),
+            hex::encode(&root)
+        );
+    }
+}
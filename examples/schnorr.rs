@@ -0,0 +1,141 @@
+//! Measure how long [Schnorr](literate_crypto::Schnorr) takes to sign and
+//! verify a message on [secp256k1](literate_crypto::Secp256k1), and how much
+//! faster multiplying the generator is in Jacobian coordinates than with
+//! affine additions, and with a [precomputed
+//! table](literate_crypto::ecc::Curve::mul_g) than without.
+//!
+//! ```text
+//! cargo run --release --example schnorr
+//! cargo run --release --example schnorr -- 100
+//! ```
+//!
+//! The optional argument is the number of messages, 10 by default. The
+//! messages are also signed with [BIP-340](literate_crypto::SchnorrBip340),
+//! and the signatures verified one by one and as a
+//! [batch](literate_crypto::SchnorrBip340::verify_batch).
+//!
+//! Every affine addition runs a modular inversion, while the Jacobian formulas
+//! invert once at the end. Signing and key derivation used to go through the
+//! Montgomery ladder, which doubles 256 times and adds 256 times, while the
+//! table replaces all of that with 64 additions.
+
+use {
+    literate_crypto::{
+        ecc::{Curve, Group, Num, Point, PrivateKey},
+        Schnorr,
+        SchnorrBip340,
+        Secp256k1,
+        Sha256,
+        SignatureScheme,
+        XOnlyPublicKey,
+    },
+    std::{env, hint, time::Instant},
+};
+
+/// A way to multiply the generator point.
+type Multiply = fn(Num) -> Point<Secp256k1>;
+
+fn main() {
+    let count: u64 = env::args()
+        .nth(1)
+        .map_or(10, |arg| arg.parse().expect("number of messages"));
+    let scalar = Num::from_le_words([
+        0x0123_4567_89ab_cdef,
+        0xfedc_ba98_7654_3210,
+        0x0f1e_2d3c_4b5a_6978,
+        0x1234_5678_9abc_def0,
+    ]);
+    let start = Instant::now();
+    Secp256k1::mul_g(Num::ONE);
+    println!(
+        "   table: {:>8.2} ms to build",
+        start.elapsed().as_secs_f64() * 1000.0
+    );
+
+    let key = PrivateKey::<Secp256k1>::new(scalar).unwrap();
+    let pubkey = key.derive();
+    let messages: Vec<_> = (0..count).map(u64::to_le_bytes).collect();
+    let mut schnorr = Schnorr::new(Secp256k1::default(), Sha256::default());
+
+    let start = Instant::now();
+    let sigs: Vec<_> = messages
+        .iter()
+        .map(|msg| schnorr.sign(key.clone(), msg))
+        .collect();
+    let signing = start.elapsed();
+
+    let start = Instant::now();
+    for (msg, sig) in messages.iter().zip(&sigs) {
+        schnorr.verify(pubkey, msg, sig).unwrap();
+    }
+    let verifying = start.elapsed();
+
+    for (name, time) in [("sign", signing), ("verify", verifying)] {
+        println!(
+            "{name:>8}: {:>8.2} ms per message",
+            time.as_secs_f64() * 1000.0 / count as f64
+        );
+    }
+
+    let mut bip340 = SchnorrBip340::new();
+    let xonly = XOnlyPublicKey::from(pubkey);
+    let items: Vec<_> = messages
+        .iter()
+        .map(|msg| (xonly, &msg[..], bip340.sign(key.clone(), msg)))
+        .collect();
+    let start = Instant::now();
+    for (key, msg, sig) in &items {
+        bip340.verify(*key, msg, sig).unwrap();
+    }
+    let single = start.elapsed();
+
+    let start = Instant::now();
+    bip340.verify_batch(&items).unwrap();
+    let batch = start.elapsed();
+
+    for (name, time) in [("single", single), ("batch", batch)] {
+        println!(
+            "{name:>8}: {:>8.2} ms per BIP-340 signature",
+            time.as_secs_f64() * 1000.0 / count as f64
+        );
+    }
+    println!(
+        "speedup {:.2}x for the batch",
+        single.as_secs_f64() / batch.as_secs_f64()
+    );
+
+    let g = Secp256k1::g();
+    let expected = affine_scale(g, scalar);
+    let methods: [(&str, Multiply); 4] = [
+        ("affine", |n| affine_scale(Secp256k1::g(), n)),
+        ("jacobian", |n| Secp256k1::g().scale(n)),
+        ("ladder", |n| Secp256k1::g().scale_ct(n)),
+        ("table", Secp256k1::mul_g),
+    ];
+    let times = methods.map(|(name, method)| {
+        let start = Instant::now();
+        for _ in 0..count {
+            assert_eq!(hint::black_box(method(scalar)), expected);
+        }
+        let time = start.elapsed().as_secs_f64() * 1000.0 / count as f64;
+        println!("{name:>8}: {time:>8.2} ms per kG");
+        time
+    });
+    println!(
+        "speedup {:.2}x over affine, {:.2}x over the ladder",
+        times[0] / times[3],
+        times[2] / times[3]
+    );
+}
+
+/// Double-and-add with affine point additions, one inversion per addition.
+fn affine_scale(a: Point<Secp256k1>, n: Num) -> Point<Secp256k1> {
+    let (mut s, mut result) = (a, Point::infinity());
+    for i in 0..Num::BITS {
+        if n.get_bit(i) {
+            result += s;
+        }
+        s += s;
+    }
+    result
+}
@@ -0,0 +1,101 @@
+//! Print test vectors for a hex-encoded input, so that they can be diffed
+//! against other implementations.
+//!
+//! ```text
+//! cargo run --example vectors -- 616263
+//! echo 616263 | cargo run --example vectors
+//! ```
+//!
+//! All keys are fixed, so the output is reproducible. The same values can be
+//! computed with OpenSSL:
+//!
+//! ```text
+//! echo -n 616263 | xxd -r -p | openssl dgst -sha256
+//! echo -n 616263 | xxd -r -p | openssl dgst -sha3-256
+//! echo -n 616263 | xxd -r -p | openssl dgst -sha256 -mac HMAC -macopt hexkey:000102030405060708090a0b0c0d0e0f
+//! echo -n 616263 | xxd -r -p \
+//!     | openssl enc -aes-128-cbc -K 000102030405060708090a0b0c0d0e0f -iv f0f1f2f3f4f5f6f7f8f9fafbfcfdfeff \
+//!     | xxd -p
+//! ```
+//!
+//! `cargo test --example vectors` checks the output for `616263` against the
+//! values computed this way.
+
+use {
+    literate_crypto::{Aes128, Cbc, CipherEncrypt, Hash, Hmac, Mac, Pkcs7, Sha256, Sha3_256},
+    std::{env, fmt::Write, io},
+};
+
+/// The key used for HMAC and AES.
+const KEY: [u8; 16] = [
+    0x00, 0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07, 0x08, 0x09, 0x0a, 0x0b, 0x0c, 0x0d, 0x0e, 0x0f,
+];
+
+/// The IV used for AES-CBC.
+const IV: [u8; 16] = [
+    0xf0, 0xf1, 0xf2, 0xf3, 0xf4, 0xf5, 0xf6, 0xf7, 0xf8, 0xf9, 0xfa, 0xfb, 0xfc, 0xfd, 0xfe, 0xff,
+];
+
+fn main() {
+    // Read the input from the first argument, or from stdin if there is none.
+    let input = env::args().nth(1).unwrap_or_else(|| {
+        let mut line = String::new();
+        io::stdin().read_line(&mut line).unwrap();
+        line
+    });
+    let Some(input) = unhex(input.trim()) else {
+        eprintln!("input must be hex-encoded");
+        std::process::exit(1);
+    };
+
+    print!("{}", vectors(&input));
+}
+
+/// The test vectors for the input, one per line.
+fn vectors(input: &[u8]) -> String {
+    let cbc = Cbc::new(Aes128::default(), Pkcs7::default(), IV);
+    [
+        ("input", input.to_vec()),
+        ("sha256", Sha256::default().hash(input).to_vec()),
+        ("sha3-256", Sha3_256::default().hash(input).to_vec()),
+        (
+            "hmac-sha256",
+            Hmac::new(Sha256::default()).mac(input, &KEY).to_vec(),
+        ),
+        ("aes-128-cbc", cbc.encrypt_from(input, KEY).unwrap()),
+    ]
+    .iter()
+    .fold(String::new(), |mut s, (name, value)| {
+        writeln!(s, "{name}: {}", hex(value)).unwrap();
+        s
+    })
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().fold(String::new(), |mut s, b| {
+        write!(s, "{b:02x}").unwrap();
+        s
+    })
+}
+
+fn unhex(s: &str) -> Option<Vec<u8>> {
+    if s.len() % 2 != 0 {
+        return None;
+    }
+    (0..s.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(s.get(i..i + 2)?, 16).ok())
+        .collect()
+}
+
+/// Compare against the values computed with OpenSSL.
+#[test]
+fn openssl() {
+    let expected = "input: 616263
+sha256: ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad
+sha3-256: 3a985da74fe225b2045c172d6bd390bd855f086e3e9d525b46bfe24511431532
+hmac-sha256: d601cc177559b0248459787f7e804ed7f27689b5995c59b661802d9682fdf8d2
+aes-128-cbc: 811b6440da670cff57854320463213ed
+";
+    assert_eq!(vectors(&unhex("616263").unwrap()), expected);
+}
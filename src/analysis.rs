@@ -0,0 +1,134 @@
+//! Utilities for cryptanalysis experiments.
+//!
+//! The two classical attacks on block ciphers both look for statistical
+//! patterns which a random permutation would not have.
+//!
+//! _Differential cryptanalysis_ studies how a difference between two inputs
+//! propagates to a difference between the outputs. If some input difference
+//! leads to some output difference much more often than chance would allow, the
+//! cipher can be told apart from a random permutation, and often the key can be
+//! recovered bit by bit. The [difference distribution table](ddt) of an S-box
+//! records exactly these probabilities for a single S-box.
+//!
+//! _Linear cryptanalysis_ looks for linear equations between the bits of the
+//! input and the bits of the output which hold more or less often than half
+//! the time. The [linear approximation table](lat) of an S-box records how
+//! biased each such equation is.
+//!
+//! A good cipher also shows the [avalanche effect](avalanche_matrix): flipping
+//! a single input bit flips every output bit with probability close to one
+//! half. This is how [diffusion](crate::doc::encryption#diffusion) is measured
+//! in practice.
+//!
+//! Throughout this module, bit $i$ of a byte string is bit $i \bmod 8$ of byte
+//! $\lfloor i / 8 \rfloor$, counting from the least significant bit.
+
+use {
+    crate::{random, BlockEncrypt, Csprng},
+    docext::docext,
+};
+
+/// Count the bits in which two byte strings differ.
+///
+/// Panics if the strings have different lengths.
+pub fn hamming_distance(a: &[u8], b: &[u8]) -> u32 {
+    assert_eq!(a.len(), b.len(), "strings must have equal lengths");
+    a.iter().zip(b).map(|(x, y)| (x ^ y).count_ones()).sum()
+}
+
+/// Measure the avalanche effect of a block cipher.
+///
+/// Entry $m_{ij}$ of the returned matrix is the fraction of samples in which
+/// flipping input bit $i$ flipped output bit $j$. Each sample encrypts a
+/// random block under a random key, drawn from the given source of
+/// randomness, and then encrypts the block once more for every flipped input
+/// bit. An ideal cipher has every entry close to $\frac{1}{2}$.
+///
+/// Panics if `samples` is zero.
+#[docext]
+pub fn avalanche_matrix<C>(cipher: &C, samples: usize, rng: &mut impl Csprng) -> Vec<Vec<f64>>
+where
+    C: BlockEncrypt,
+    C::EncryptionBlock: Default + Clone + AsRef<[u8]> + AsMut<[u8]>,
+    C::EncryptionKey: Default + Clone + AsMut<[u8]>,
+{
+    assert!(samples > 0, "at least one sample is required");
+    let bits = C::EncryptionBlock::default().as_ref().len() * 8;
+    let mut flips = vec![vec![0usize; bits]; bits];
+    for _ in 0..samples {
+        let block: C::EncryptionBlock = random::random_bytes(rng);
+        let key: C::EncryptionKey = random::random_bytes(rng);
+        let output = cipher.encrypt(block.clone(), key.clone());
+        for (i, row) in flips.iter_mut().enumerate() {
+            let mut flipped = block.clone();
+            flipped.as_mut()[i / 8] ^= 1 << (i % 8);
+            let flipped = cipher.encrypt(flipped, key.clone());
+            for (j, count) in row.iter_mut().enumerate() {
+                let diff = output.as_ref()[j / 8] ^ flipped.as_ref()[j / 8];
+                *count += usize::from(diff >> (j % 8) & 1);
+            }
+        }
+    }
+    flips
+        .into_iter()
+        .map(|row| {
+            row.into_iter()
+                .map(|count| count as f64 / samples as f64)
+                .collect()
+        })
+        .collect()
+}
+
+/// Compute the difference distribution table of an 8-bit S-box.
+///
+/// Entry $(\Delta x, \Delta y)$ counts the inputs $x$ for which
+///
+/// $$
+/// S(x) \oplus S(x \oplus \Delta x) = \Delta y
+/// $$
+///
+/// Every row sums to 256, and row zero trivially has all of its weight in
+/// column zero. The largest entry outside row zero, divided by 256, is the
+/// highest probability with which any differential holds through the S-box.
+/// For the [AES S-box](crate::aes::S_BOX) this maximum is 4, the lowest known
+/// for an 8-bit permutation.
+#[docext]
+pub fn ddt(sbox: &[u8; 256]) -> [[u16; 256]; 256] {
+    let mut table = [[0; 256]; 256];
+    for (dx, row) in table.iter_mut().enumerate() {
+        for x in 0..256 {
+            let dy = sbox[x] ^ sbox[x ^ dx];
+            row[usize::from(dy)] += 1;
+        }
+    }
+    table
+}
+
+/// Compute the linear approximation table of an 8-bit S-box.
+///
+/// Entry $(a, b)$ is the bias of the linear approximation with input mask $a$
+/// and output mask $b$: the number of inputs $x$ for which
+///
+/// $$
+/// a \cdot x = b \cdot S(x)
+/// $$
+///
+/// minus 128, where $\cdot$ is the parity of the bitwise AND. An unbiased
+/// approximation holds for exactly half the inputs and has entry zero. Entry
+/// $(0, 0)$ trivially equals 128. For the [AES S-box](crate::aes::S_BOX),
+/// every other entry lies between -16 and 16.
+#[docext]
+pub fn lat(sbox: &[u8; 256]) -> [[i16; 256]; 256] {
+    let mut table = [[0; 256]; 256];
+    for (a, row) in table.iter_mut().enumerate() {
+        for (b, entry) in row.iter_mut().enumerate() {
+            let matches = (0..256)
+                .filter(|&x| {
+                    (a & x).count_ones() % 2 == (b & usize::from(sbox[x])).count_ones() % 2
+                })
+                .count();
+            *entry = i16::try_from(matches).unwrap() - 128;
+        }
+    }
+    table
+}
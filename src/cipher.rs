@@ -1,4 +1,7 @@
-use docext::docext;
+use {
+    crate::{random, Csprng},
+    docext::docext,
+};
 
 mod block;
 mod onetimepad;
@@ -6,17 +9,34 @@ mod onetimepad;
 pub use {
     block::{
         aes,
+        gf128_mul,
+        ghash,
+        kcv,
         Aes128,
         Aes192,
         Aes256,
+        AesKeySchedule,
+        AuthenticationFailed,
         BlockCipher,
         BlockDecrypt,
         BlockEncrypt,
         BlockMode,
-        BlockSizeTooSmall,
         Cbc,
+        CbcCts,
+        CbcDecryptIter,
+        CounterPosition,
+        CounterWidth,
         Ctr,
+        CtrConfigError,
+        CtrDecryptIter,
+        CtrError,
+        CtsTooShort,
+        DerivedNonce,
+        DerivedNonceError,
         Ecb,
+        Gcm,
+        GcmTag,
+        NonceMode,
         Padding,
         Pkcs7,
     },
@@ -44,6 +64,19 @@ pub trait Cipher:
     CipherEncrypt<EncryptionKey = Self::Key> + CipherDecrypt<DecryptionKey = Self::Key>
 {
     type Key;
+
+    /// Generate a random key using the given source of randomness, typically a
+    /// [CSPRNG](crate::Csprng).
+    ///
+    /// The default implementation fills the key with random bytes, which is
+    /// appropriate for most ciphers. It is not available for ciphers whose keys
+    /// are not plain byte arrays, such as the [one-time pad](OneTimePad).
+    fn generate_key(rng: &mut impl Csprng) -> Self::Key
+    where
+        Self::Key: Default + AsMut<[u8]>,
+    {
+        random::random_bytes(rng)
+    }
 }
 
 /// The encryption half of a [cipher](Cipher).
@@ -57,6 +90,18 @@ pub trait CipherEncrypt {
         data: Vec<u8>,
         key: Self::EncryptionKey,
     ) -> Result<Vec<u8>, Self::EncryptionErr>;
+
+    /// Encrypt the plaintext without taking ownership of it.
+    ///
+    /// The default implementation copies the plaintext and calls
+    /// [`encrypt`](CipherEncrypt::encrypt).
+    fn encrypt_from(
+        &self,
+        data: &[u8],
+        key: Self::EncryptionKey,
+    ) -> Result<Vec<u8>, Self::EncryptionErr> {
+        self.encrypt(data.to_vec(), key)
+    }
 }
 
 /// The decryption half of a [cipher](Cipher).
@@ -71,4 +116,18 @@ pub trait CipherDecrypt {
         data: Vec<u8>,
         key: Self::DecryptionKey,
     ) -> Result<Vec<u8>, Self::DecryptionErr>;
+
+    /// Decrypt the ciphertext without taking ownership of it.
+    ///
+    /// The default implementation copies the ciphertext and calls
+    /// [`decrypt`](CipherDecrypt::decrypt). Modes which decrypt in place, such
+    /// as [CBC](Cbc) and [ECB](Ecb), keep it: the plaintext is never longer
+    /// than the ciphertext, so the copy is the only allocation either way.
+    fn decrypt_from(
+        &self,
+        data: &[u8],
+        key: Self::DecryptionKey,
+    ) -> Result<Vec<u8>, Self::DecryptionErr> {
+        self.decrypt(data.to_vec(), key)
+    }
 }
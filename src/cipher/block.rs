@@ -1,10 +1,36 @@
+use {
+    crate::{random, Csprng},
+    std::sync::Arc,
+};
+
 pub mod aes;
 mod modes;
 mod padding;
 
 pub use {
-    aes::{Aes128, Aes192, Aes256},
-    modes::{BlockMode, BlockSizeTooSmall, Cbc, Ctr, Ecb},
+    aes::{Aes128, Aes192, Aes256, AesKeySchedule},
+    modes::{
+        gf128_mul,
+        ghash,
+        AuthenticationFailed,
+        BlockMode,
+        Cbc,
+        CbcCts,
+        CbcDecryptIter,
+        CounterPosition,
+        CounterWidth,
+        Ctr,
+        CtrConfigError,
+        CtrDecryptIter,
+        CtrError,
+        CtsTooShort,
+        DerivedNonce,
+        DerivedNonceError,
+        Ecb,
+        Gcm,
+        GcmTag,
+        NonceMode,
+    },
     padding::{Padding, Pkcs7},
 };
 
@@ -36,6 +62,87 @@ pub trait BlockEncrypt {
         data: Self::EncryptionBlock,
         key: Self::EncryptionKey,
     ) -> Self::EncryptionBlock;
+
+    /// Generate a random key using the given source of randomness, typically a
+    /// [CSPRNG](crate::Csprng).
+    ///
+    /// The default implementation fills the key with random bytes. Block
+    /// ciphers with weak keys or other key constraints should override it.
+    /// This lives on the encryption half so that modes which only encrypt,
+    /// such as [CTR](crate::Ctr), can generate keys as well.
+    fn generate_key(rng: &mut impl Csprng) -> Self::EncryptionKey
+    where
+        Self: Sized,
+        Self::EncryptionKey: Default + AsMut<[u8]>,
+    {
+        random::random_bytes(rng)
+    }
+}
+
+/// Forward to the shared [block cipher](BlockCipher), including its [key
+/// generation](BlockEncrypt::generate_key), which is why the cipher must be
+/// sized.
+impl<T: BlockEncrypt> BlockEncrypt for &T {
+    type EncryptionBlock = T::EncryptionBlock;
+    type EncryptionKey = T::EncryptionKey;
+
+    fn encrypt(
+        &self,
+        data: Self::EncryptionBlock,
+        key: Self::EncryptionKey,
+    ) -> Self::EncryptionBlock {
+        T::encrypt(self, data, key)
+    }
+
+    fn generate_key(rng: &mut impl Csprng) -> Self::EncryptionKey
+    where
+        Self: Sized,
+        Self::EncryptionKey: Default + AsMut<[u8]>,
+    {
+        T::generate_key(rng)
+    }
+}
+
+impl<T: BlockEncrypt> BlockEncrypt for Box<T> {
+    type EncryptionBlock = T::EncryptionBlock;
+    type EncryptionKey = T::EncryptionKey;
+
+    fn encrypt(
+        &self,
+        data: Self::EncryptionBlock,
+        key: Self::EncryptionKey,
+    ) -> Self::EncryptionBlock {
+        T::encrypt(self, data, key)
+    }
+
+    fn generate_key(rng: &mut impl Csprng) -> Self::EncryptionKey
+    where
+        Self: Sized,
+        Self::EncryptionKey: Default + AsMut<[u8]>,
+    {
+        T::generate_key(rng)
+    }
+}
+
+impl<T: BlockEncrypt> BlockEncrypt for Arc<T> {
+    type EncryptionBlock = T::EncryptionBlock;
+    type EncryptionKey = T::EncryptionKey;
+
+    fn encrypt(
+        &self,
+        data: Self::EncryptionBlock,
+        key: Self::EncryptionKey,
+    ) -> Self::EncryptionBlock {
+        T::encrypt(self, data, key)
+    }
+
+    fn generate_key(rng: &mut impl Csprng) -> Self::EncryptionKey
+    where
+        Self: Sized,
+        Self::EncryptionKey: Default + AsMut<[u8]>,
+    {
+        T::generate_key(rng)
+    }
 }
 
 /// The decryption half of a [block cipher](BlockCipher).
@@ -50,3 +157,62 @@ pub trait BlockDecrypt {
         key: Self::DecryptionKey,
     ) -> Self::DecryptionBlock;
 }
+
+/// Forward to the shared [block cipher](BlockCipher).
+impl<T: BlockDecrypt + ?Sized> BlockDecrypt for &T {
+    type DecryptionBlock = T::DecryptionBlock;
+    type DecryptionKey = T::DecryptionKey;
+
+    fn decrypt(
+        &self,
+        data: Self::DecryptionBlock,
+        key: Self::DecryptionKey,
+    ) -> Self::DecryptionBlock {
+        T::decrypt(self, data, key)
+    }
+}
+
+impl<T: BlockDecrypt + ?Sized> BlockDecrypt for Box<T> {
+    type DecryptionBlock = T::DecryptionBlock;
+    type DecryptionKey = T::DecryptionKey;
+
+    fn decrypt(
+        &self,
+        data: Self::DecryptionBlock,
+        key: Self::DecryptionKey,
+    ) -> Self::DecryptionBlock {
+        T::decrypt(self, data, key)
+    }
+}
+
+impl<T: BlockDecrypt + ?Sized> BlockDecrypt for Arc<T> {
+    type DecryptionBlock = T::DecryptionBlock;
+    type DecryptionKey = T::DecryptionKey;
+
+    fn decrypt(
+        &self,
+        data: Self::DecryptionBlock,
+        key: Self::DecryptionKey,
+    ) -> Self::DecryptionBlock {
+        T::decrypt(self, data, key)
+    }
+}
+
+/// Compute the classic key check value (KCV) of a block cipher key: the first
+/// three bytes of the encryption of the all-zero block.
+///
+/// A KCV lets two parties confirm that they hold the same key without
+/// revealing it, and is widely used in banking systems. It is not
+/// key-committing in any strong sense, since three bytes collide after a few
+/// thousand keys, and it reveals a known plaintext-ciphertext pair. Prefer
+/// [`key_fingerprint`](crate::key_fingerprint) in new designs.
+pub fn kcv<Enc>(enc: &Enc, key: Enc::EncryptionKey) -> [u8; 3]
+where
+    Enc: BlockEncrypt,
+    Enc::EncryptionBlock: Default + AsRef<[u8]>,
+{
+    let block = enc.encrypt(Enc::EncryptionBlock::default(), key);
+    let mut result = [0; 3];
+    result.copy_from_slice(&block.as_ref()[..3]);
+    result
+}
@@ -21,8 +21,9 @@
 //! [`decrypt`] methods.
 
 use {
-    crate::{BlockCipher, BlockDecrypt, BlockEncrypt},
+    crate::{trace::Trace, util::Zeroize, BlockCipher, BlockDecrypt, BlockEncrypt},
     docext::docext,
+    std::{fmt, ops},
 };
 
 /// AES word size in bytes.
@@ -130,11 +131,83 @@ impl BlockDecrypt for Aes128 {
     }
 }
 
+impl Aes128 {
+    /// Expand the key once, for encrypting or decrypting many blocks with it.
+    pub fn schedule(
+        &self,
+        key: [u8; AES128_NK * WORD_SIZE],
+    ) -> AesKeySchedule<AES128_NR, AES128_EXPANSION_BYTES> {
+        AesKeySchedule::new::<AES128_NK, AES128_KEY_BYTES>(key)
+    }
+
+    /// Report the state after every round of encryption to the
+    /// [tracer](Trace), under the name `AES-128`.
+    pub fn with_trace<T: Trace>(tracer: T) -> Traced<Self, T> {
+        Traced {
+            cipher: Self::default(),
+            tracer,
+        }
+    }
+
+    /// Encrypt a block while recording every intermediate state, see
+    /// [`encrypt_traced`].
+    pub fn encrypt_traced(
+        &self,
+        data: [u8; NB * WORD_SIZE],
+        key: [u8; AES128_NK * WORD_SIZE],
+    ) -> ([u8; NB * WORD_SIZE], Vec<RoundTrace>) {
+        encrypt_traced::<
+            AES128_NK,
+            AES128_NR,
+            AES128_BLOCK_BYTES,
+            AES128_KEY_BYTES,
+            AES128_EXPANSION_BYTES,
+        >(data, key)
+    }
+}
+
 impl BlockCipher for Aes128 {
     type Block = [u8; NB * WORD_SIZE];
     type Key = [u8; AES128_NK * WORD_SIZE];
 }
 
+impl<T: Trace> BlockEncrypt for Traced<Aes128, T> {
+    type EncryptionBlock = [u8; NB * WORD_SIZE];
+    type EncryptionKey = [u8; AES128_NK * WORD_SIZE];
+
+    fn encrypt(
+        &self,
+        data: Self::EncryptionBlock,
+        key: Self::EncryptionKey,
+    ) -> Self::EncryptionBlock {
+        encrypt_with::<
+            AES128_NK,
+            AES128_NR,
+            AES128_BLOCK_BYTES,
+            AES128_KEY_BYTES,
+            AES128_EXPANSION_BYTES,
+        >(data, key, |t| self.trace_round("AES-128", t))
+    }
+}
+
+impl<T> BlockDecrypt for Traced<Aes128, T> {
+    type DecryptionBlock = [u8; NB * WORD_SIZE];
+    type DecryptionKey = [u8; AES128_NK * WORD_SIZE];
+
+    fn decrypt(
+        &self,
+        data: Self::DecryptionBlock,
+        key: Self::DecryptionKey,
+    ) -> Self::DecryptionBlock {
+        self.cipher.decrypt(data, key)
+    }
+}
+
+impl<T: Trace> BlockCipher for Traced<Aes128, T> {
+    type Block = [u8; NB * WORD_SIZE];
+    type Key = [u8; AES128_NK * WORD_SIZE];
+}
+
 /// [AES block cipher](self) with 192-bit keys.
 #[derive(Debug, Default)]
 pub struct Aes192(());
@@ -169,11 +242,83 @@ impl BlockDecrypt for Aes192 {
     }
 }
 
+impl Aes192 {
+    /// Expand the key once, for encrypting or decrypting many blocks with it.
+    pub fn schedule(
+        &self,
+        key: [u8; AES192_NK * WORD_SIZE],
+    ) -> AesKeySchedule<AES192_NR, AES192_EXPANSION_BYTES> {
+        AesKeySchedule::new::<AES192_NK, AES192_KEY_BYTES>(key)
+    }
+
+    /// Report the state after every round of encryption to the
+    /// [tracer](Trace), under the name `AES-192`.
+    pub fn with_trace<T: Trace>(tracer: T) -> Traced<Self, T> {
+        Traced {
+            cipher: Self::default(),
+            tracer,
+        }
+    }
+
+    /// Encrypt a block while recording every intermediate state, see
+    /// [`encrypt_traced`].
+    pub fn encrypt_traced(
+        &self,
+        data: [u8; NB * WORD_SIZE],
+        key: [u8; AES192_NK * WORD_SIZE],
+    ) -> ([u8; NB * WORD_SIZE], Vec<RoundTrace>) {
+        encrypt_traced::<
+            AES192_NK,
+            AES192_NR,
+            AES192_BLOCK_BYTES,
+            AES192_KEY_BYTES,
+            AES192_EXPANSION_BYTES,
+        >(data, key)
+    }
+}
+
 impl BlockCipher for Aes192 {
     type Block = [u8; NB * WORD_SIZE];
     type Key = [u8; AES192_NK * WORD_SIZE];
 }
 
+impl<T: Trace> BlockEncrypt for Traced<Aes192, T> {
+    type EncryptionBlock = [u8; NB * WORD_SIZE];
+    type EncryptionKey = [u8; AES192_NK * WORD_SIZE];
+
+    fn encrypt(
+        &self,
+        data: Self::EncryptionBlock,
+        key: Self::EncryptionKey,
+    ) -> Self::EncryptionBlock {
+        encrypt_with::<
+            AES192_NK,
+            AES192_NR,
+            AES192_BLOCK_BYTES,
+            AES192_KEY_BYTES,
+            AES192_EXPANSION_BYTES,
+        >(data, key, |t| self.trace_round("AES-192", t))
+    }
+}
+
+impl<T> BlockDecrypt for Traced<Aes192, T> {
+    type DecryptionBlock = [u8; NB * WORD_SIZE];
+    type DecryptionKey = [u8; AES192_NK * WORD_SIZE];
+
+    fn decrypt(
+        &self,
+        data: Self::DecryptionBlock,
+        key: Self::DecryptionKey,
+    ) -> Self::DecryptionBlock {
+        self.cipher.decrypt(data, key)
+    }
+}
+
+impl<T: Trace> BlockCipher for Traced<Aes192, T> {
+    type Block = [u8; NB * WORD_SIZE];
+    type Key = [u8; AES192_NK * WORD_SIZE];
+}
+
 /// [AES block cipher](self) with 256-bit keys.
 #[derive(Debug, Default)]
 pub struct Aes256(());
@@ -208,21 +353,343 @@ impl BlockDecrypt for Aes256 {
     }
 }
 
+impl Aes256 {
+    /// Expand the key once, for encrypting or decrypting many blocks with it.
+    pub fn schedule(
+        &self,
+        key: [u8; AES256_NK * WORD_SIZE],
+    ) -> AesKeySchedule<AES256_NR, AES256_EXPANSION_BYTES> {
+        AesKeySchedule::new::<AES256_NK, AES256_KEY_BYTES>(key)
+    }
+
+    /// Report the state after every round of encryption to the
+    /// [tracer](Trace), under the name `AES-256`.
+    pub fn with_trace<T: Trace>(tracer: T) -> Traced<Self, T> {
+        Traced {
+            cipher: Self::default(),
+            tracer,
+        }
+    }
+
+    /// Encrypt a block while recording every intermediate state, see
+    /// [`encrypt_traced`].
+    pub fn encrypt_traced(
+        &self,
+        data: [u8; NB * WORD_SIZE],
+        key: [u8; AES256_NK * WORD_SIZE],
+    ) -> ([u8; NB * WORD_SIZE], Vec<RoundTrace>) {
+        encrypt_traced::<
+            AES256_NK,
+            AES256_NR,
+            AES256_BLOCK_BYTES,
+            AES256_KEY_BYTES,
+            AES256_EXPANSION_BYTES,
+        >(data, key)
+    }
+}
+
 impl BlockCipher for Aes256 {
     type Block = [u8; NB * WORD_SIZE];
     type Key = [u8; AES256_NK * WORD_SIZE];
 }
 
+impl<T: Trace> BlockEncrypt for Traced<Aes256, T> {
+    type EncryptionBlock = [u8; NB * WORD_SIZE];
+    type EncryptionKey = [u8; AES256_NK * WORD_SIZE];
+
+    fn encrypt(
+        &self,
+        data: Self::EncryptionBlock,
+        key: Self::EncryptionKey,
+    ) -> Self::EncryptionBlock {
+        encrypt_with::<
+            AES256_NK,
+            AES256_NR,
+            AES256_BLOCK_BYTES,
+            AES256_KEY_BYTES,
+            AES256_EXPANSION_BYTES,
+        >(data, key, |t| self.trace_round("AES-256", t))
+    }
+}
+
+impl<T> BlockDecrypt for Traced<Aes256, T> {
+    type DecryptionBlock = [u8; NB * WORD_SIZE];
+    type DecryptionKey = [u8; AES256_NK * WORD_SIZE];
+
+    fn decrypt(
+        &self,
+        data: Self::DecryptionBlock,
+        key: Self::DecryptionKey,
+    ) -> Self::DecryptionBlock {
+        self.cipher.decrypt(data, key)
+    }
+}
+
+impl<T: Trace> BlockCipher for Traced<Aes256, T> {
+    type Block = [u8; NB * WORD_SIZE];
+    type Key = [u8; AES256_NK * WORD_SIZE];
+}
+
+/// An [AES](self) cipher which reports the [state](State) at the end of every
+/// round of encryption to a [tracer](Trace), constructed with e.g.
+/// [`Aes128::with_trace`].
+///
+/// A round ends with [AddRoundKey](add_round_key), and round 0 consists of
+/// nothing else, so the state reported for round $r$ is the "start of round"
+/// state of round $r + 1$ in Appendix B of the AES specification. Decryption
+/// is not traced.
+#[docext]
+#[derive(Debug)]
+pub struct Traced<C, T> {
+    cipher: C,
+    tracer: T,
+}
+
+impl<C, T: Trace> Traced<C, T> {
+    fn trace_round(&self, algorithm: &str, t: RoundTrace) {
+        if t.transformation == Transformation::AddRoundKey {
+            self.tracer.on_round(algorithm, t.round, t.state.as_ref());
+        }
+    }
+}
+
+/// An [AES](self) key, expanded once into its key schedule by e.g.
+/// [`Aes128::schedule`].
+///
+/// [`Aes128`] and its siblings run the [KeyExpansion](key_expansion) routine
+/// for every block they encrypt, so a block mode like [CTR](crate::Ctr)
+/// expands the same key again every 16 bytes, which takes about as long as
+/// encrypting the block itself. The schedule only does this once. The
+/// ciphertexts are the same either way.
+///
+/// Since the schedule already holds the key, it's also a [block
+/// cipher](BlockCipher) whose key is `()`, which can be used with any [block
+/// mode](crate::BlockMode). The schedule is wiped when it's dropped, and its
+/// [`Debug`](fmt::Debug) output leaves it out.
+#[derive(Clone)]
+pub struct AesKeySchedule<const NR: usize, const EXPANSION_BYTES: usize>([u8; EXPANSION_BYTES]);
+
+impl<const NR: usize, const EXPANSION_BYTES: usize> AesKeySchedule<NR, EXPANSION_BYTES> {
+    fn new<const NK: usize, const KEY_BYTES: usize>(mut key: [u8; KEY_BYTES]) -> Self {
+        let w = key_expansion::<NK, NR, KEY_BYTES, EXPANSION_BYTES>(key);
+        key.zeroize();
+        Self(w)
+    }
+
+    /// Encrypt a block, like [`encrypt`] but without expanding the key.
+    pub fn encrypt_block(&self, data: [u8; NB * WORD_SIZE]) -> [u8; NB * WORD_SIZE] {
+        encrypt_rounds::<NR, { NB * WORD_SIZE }>(data, &self.0, |_| {})
+    }
+
+    /// Decrypt a block, like [`decrypt`] but without expanding the key.
+    pub fn decrypt_block(&self, data: [u8; NB * WORD_SIZE]) -> [u8; NB * WORD_SIZE] {
+        decrypt_rounds::<NR, { NB * WORD_SIZE }>(data, &self.0)
+    }
+}
+
+impl<const NR: usize, const EXPANSION_BYTES: usize> Drop for AesKeySchedule<NR, EXPANSION_BYTES> {
+    fn drop(&mut self) {
+        self.0.zeroize();
+    }
+}
+
+impl<const NR: usize, const EXPANSION_BYTES: usize> fmt::Debug
+    for AesKeySchedule<NR, EXPANSION_BYTES>
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("AesKeySchedule")
+            .field("rounds", &NR)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<const NR: usize, const EXPANSION_BYTES: usize> BlockEncrypt
+    for AesKeySchedule<NR, EXPANSION_BYTES>
+{
+    type EncryptionBlock = [u8; NB * WORD_SIZE];
+    type EncryptionKey = ();
+
+    fn encrypt(&self, data: Self::EncryptionBlock, (): ()) -> Self::EncryptionBlock {
+        self.encrypt_block(data)
+    }
+}
+
+impl<const NR: usize, const EXPANSION_BYTES: usize> BlockDecrypt
+    for AesKeySchedule<NR, EXPANSION_BYTES>
+{
+    type DecryptionBlock = [u8; NB * WORD_SIZE];
+    type DecryptionKey = ();
+
+    fn decrypt(&self, data: Self::DecryptionBlock, (): ()) -> Self::DecryptionBlock {
+        self.decrypt_block(data)
+    }
+}
+
+impl<const NR: usize, const EXPANSION_BYTES: usize> BlockCipher
+    for AesKeySchedule<NR, EXPANSION_BYTES>
+{
+    type Block = [u8; NB * WORD_SIZE];
+    type Key = ();
+}
+
+/// The AES state, defined in Section 3.4 of the AES specification.
+///
+/// The state is a 4x4 matrix of bytes on which all AES transformations operate.
+/// The bytes of a block fill the state column by column, so byte $i$ of the
+/// block is stored in row $i \bmod 4$ and column $\lfloor i / 4 \rfloor$:
+///
+/// $$
+/// \begin{bmatrix}
+/// s_0 & s_4 & s_8 & s_{12} \\
+/// s_1 & s_5 & s_9 & s_{13} \\
+/// s_2 & s_6 & s_{10} & s_{14} \\
+/// s_3 & s_7 & s_{11} & s_{15}
+/// \end{bmatrix}
+/// $$
+///
+/// The state is indexed by `(row, column)` pairs. Both
+/// [`Display`](fmt::Display) and [`Debug`](fmt::Debug) print it as a hex matrix
+/// in the same layout as the examples in the specification.
+#[docext]
+#[derive(Clone, Copy, PartialEq, Eq, Default)]
+pub struct State([u8; NB * WORD_SIZE]);
+
+impl State {
+    /// Create the state from a block of any size, panicking if the block is not
+    /// exactly 16 bytes long.
+    fn from_slice(block: &[u8]) -> Self {
+        let mut state = Self::default();
+        state.0.copy_from_slice(block);
+        state
+    }
+
+    fn row(&self, r: usize) -> [u8; NB] {
+        [self[(r, 0)], self[(r, 1)], self[(r, 2)], self[(r, 3)]]
+    }
+
+    /// The [AddRoundKey](add_round_key) transformation.
+    pub fn add_round_key(&mut self, w: &[u8], round: usize) {
+        add_round_key(&mut self.0, w, round);
+    }
+
+    /// The [SubBytes](sub_bytes) transformation.
+    pub fn sub_bytes(&mut self) {
+        sub_bytes(&mut self.0);
+    }
+
+    /// The [InvSubBytes](inv_sub_bytes) transformation.
+    pub fn inv_sub_bytes(&mut self) {
+        inv_sub_bytes(&mut self.0);
+    }
+
+    /// The [ShiftRows](shift_rows) transformation. Row $r$ is rotated left by
+    /// $r$ positions.
+    #[docext]
+    pub fn shift_rows(&mut self) {
+        shift_rows(&mut self.0);
+    }
+
+    /// The [InvShiftRows](inv_shift_rows) transformation. Row $r$ is rotated
+    /// right by $r$ positions.
+    #[docext]
+    pub fn inv_shift_rows(&mut self) {
+        inv_shift_rows(&mut self.0);
+    }
+
+    /// The [MixColumns](mix_columns) transformation.
+    pub fn mix_columns(&mut self) {
+        mix_columns(&mut self.0);
+    }
+
+    /// The [InvMixColumns](inv_mix_columns) transformation.
+    pub fn inv_mix_columns(&mut self) {
+        inv_mix_columns(&mut self.0);
+    }
+}
+
+impl From<[u8; NB * WORD_SIZE]> for State {
+    fn from(block: [u8; NB * WORD_SIZE]) -> Self {
+        Self(block)
+    }
+}
+
+impl From<State> for [u8; NB * WORD_SIZE] {
+    fn from(state: State) -> Self {
+        state.0
+    }
+}
+
+impl AsRef<[u8]> for State {
+    fn as_ref(&self) -> &[u8] {
+        &self.0
+    }
+}
+
+impl ops::Index<(usize, usize)> for State {
+    type Output = u8;
+
+    fn index(&self, (r, c): (usize, usize)) -> &Self::Output {
+        &self.0[r + WORD_SIZE * c]
+    }
+}
+
+impl ops::IndexMut<(usize, usize)> for State {
+    fn index_mut(&mut self, (r, c): (usize, usize)) -> &mut Self::Output {
+        &mut self.0[r + WORD_SIZE * c]
+    }
+}
+
+impl fmt::Display for State {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for r in 0..WORD_SIZE {
+            if r != 0 {
+                writeln!(f)?;
+            }
+            let [a, b, c, d] = self.row(r);
+            write!(f, "{a:02x} {b:02x} {c:02x} {d:02x}")?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Debug for State {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(self, f)
+    }
+}
+
+/// A transformation applied to the [state](State) during
+/// [encryption](encrypt_traced).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Transformation {
+    SubBytes,
+    ShiftRows,
+    MixColumns,
+    AddRoundKey,
+}
+
+/// The [state](State) right after a [transformation](Transformation) was
+/// applied during a given round, as recorded by [`encrypt_traced`].
+///
+/// Round 0 consists only of the initial [AddRoundKey](add_round_key).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RoundTrace {
+    pub round: usize,
+    pub transformation: Transformation,
+    pub state: State,
+}
+
 /// AES encryption routine defined in Section 5.1 of the AES specification.
 ///
 /// Applies the [SubBytes](sub_bytes), [ShiftRows](shift_rows),
 /// [MixColumns](mix_columns), and [AddRoundKey](add_round_key)
-/// transformations to the internal state in each round, and returns the
-/// resulting state as the ciphertext. The initial state is simply the plaintext
-/// block.
+/// transformations to the internal [state](State) in each round, and returns
+/// the resulting state as the ciphertext. The initial state is simply the
+/// plaintext block.
 ///
 /// The encryption key is expanded into round keys using the
-/// [KeyExpansion](key_expansion) routine.
+/// [KeyExpansion](key_expansion) routine. Both the key and the round keys are
+/// overwritten with zeros before returning.
 #[docext]
 pub fn encrypt<
     const NK: usize,              // Key size in words.
@@ -234,35 +701,101 @@ pub fn encrypt<
     data: [u8; BLOCK_BYTES],
     key: [u8; KEY_BYTES],
 ) -> [u8; BLOCK_BYTES] {
-    let mut state = data;
-    let w = key_expansion::<NK, NR, KEY_BYTES, EXPANSION_BYTES>(key);
-    add_round_key(&mut state, &w, 0);
+    encrypt_with::<NK, NR, BLOCK_BYTES, KEY_BYTES, EXPANSION_BYTES>(data, key, |_| {})
+}
+
+/// The same as [`encrypt`], but also records the [state](State) after every
+/// transformation.
+///
+/// This makes it possible to follow the encryption round by round, as done in
+/// Appendix B of the AES specification.
+pub fn encrypt_traced<
+    const NK: usize,              // Key size in words.
+    const NR: usize,              // Number of rounds.
+    const BLOCK_BYTES: usize,     // NB * WORD_SIZE.
+    const KEY_BYTES: usize,       // NK * WORD_SIZE.
+    const EXPANSION_BYTES: usize, // NB * (NR + 1) * WORD_SIZE.
+>(
+    data: [u8; BLOCK_BYTES],
+    key: [u8; KEY_BYTES],
+) -> ([u8; BLOCK_BYTES], Vec<RoundTrace>) {
+    let mut trace = Vec::new();
+    let block = encrypt_with::<NK, NR, BLOCK_BYTES, KEY_BYTES, EXPANSION_BYTES>(data, key, |t| {
+        trace.push(t)
+    });
+    (block, trace)
+}
+
+fn encrypt_with<
+    const NK: usize,
+    const NR: usize,
+    const BLOCK_BYTES: usize,
+    const KEY_BYTES: usize,
+    const EXPANSION_BYTES: usize,
+>(
+    data: [u8; BLOCK_BYTES],
+    mut key: [u8; KEY_BYTES],
+    trace: impl FnMut(RoundTrace),
+) -> [u8; BLOCK_BYTES] {
+    let mut w = key_expansion::<NK, NR, KEY_BYTES, EXPANSION_BYTES>(key);
+    let result = encrypt_rounds::<NR, BLOCK_BYTES>(data, &w, trace);
+
+    // The key schedule reveals the key, so don't leave either of them behind.
+    key.zeroize();
+    w.zeroize();
+    result
+}
+
+/// The rounds of [encryption](encrypt), given the expanded key schedule.
+fn encrypt_rounds<const NR: usize, const BLOCK_BYTES: usize>(
+    data: [u8; BLOCK_BYTES],
+    w: &[u8],
+    mut trace: impl FnMut(RoundTrace),
+) -> [u8; BLOCK_BYTES] {
+    let mut state = State::from_slice(&data);
+    let mut apply = |state: &mut State, round: usize, transformation: Transformation| {
+        match transformation {
+            Transformation::SubBytes => state.sub_bytes(),
+            Transformation::ShiftRows => state.shift_rows(),
+            Transformation::MixColumns => state.mix_columns(),
+            Transformation::AddRoundKey => state.add_round_key(w, round),
+        }
+        trace(RoundTrace {
+            round,
+            transformation,
+            state: *state,
+        });
+    };
+
+    apply(&mut state, 0, Transformation::AddRoundKey);
 
     for round in 1..NR {
-        sub_bytes(&mut state);
-        shift_rows(&mut state);
-        mix_columns(&mut state);
-        add_round_key(&mut state, &w, round);
+        apply(&mut state, round, Transformation::SubBytes);
+        apply(&mut state, round, Transformation::ShiftRows);
+        apply(&mut state, round, Transformation::MixColumns);
+        apply(&mut state, round, Transformation::AddRoundKey);
     }
 
-    sub_bytes(&mut state);
-    shift_rows(&mut state);
-    add_round_key(&mut state, &w, NR);
+    apply(&mut state, NR, Transformation::SubBytes);
+    apply(&mut state, NR, Transformation::ShiftRows);
+    apply(&mut state, NR, Transformation::AddRoundKey);
 
-    state
+    let mut result = [0; BLOCK_BYTES];
+    result.copy_from_slice(state.as_ref());
+    result
 }
 
 /// AES decryption routine defined in Section 5.3 of the AES specification.
 ///
 /// Applies the [InvSubBytes](inv_sub_bytes),
 /// [InvShiftRows](inv_shift_rows), [InvMixColumns](inv_mix_columns), and
-/// [AddRoundKey](add_round_key) transformations to the internal state in each
-/// round, and returns the resulting state as the ciphertext. The initial state
-/// is simply the ciphertext block. The operations are applied in the opposite
-/// order from [encryption](encrypt).
+/// [AddRoundKey](add_round_key) transformations to the internal
+/// [state](State) in each round, and returns the resulting state as the
+/// ciphertext. The initial state is simply the ciphertext block. The
+/// operations are applied in the opposite order from [encryption](encrypt).
 ///
 /// Just like encryption, the decryption key is expanded into round keys using
-/// the [KeyExpansion](key_expansion) routine.
+/// the [KeyExpansion](key_expansion) routine, and both are wiped afterwards.
 #[docext]
 pub fn decrypt<
     const NK: usize,              // Key size in words.
@@ -272,24 +805,37 @@ pub fn decrypt<
     const EXPANSION_BYTES: usize, // NB * (NR + 1) * WORD_SIZE.
 >(
     data: [u8; BLOCK_BYTES],
-    key: [u8; KEY_BYTES],
+    mut key: [u8; KEY_BYTES],
 ) -> [u8; BLOCK_BYTES] {
-    let mut state = data;
-    let w = key_expansion::<NK, NR, KEY_BYTES, EXPANSION_BYTES>(key);
-    add_round_key(&mut state, &w, NR);
+    let mut w = key_expansion::<NK, NR, KEY_BYTES, EXPANSION_BYTES>(key);
+    let result = decrypt_rounds::<NR, BLOCK_BYTES>(data, &w);
+    key.zeroize();
+    w.zeroize();
+    result
+}
+
+/// The rounds of [decryption](decrypt), given the expanded key schedule.
+fn decrypt_rounds<const NR: usize, const BLOCK_BYTES: usize>(
+    data: [u8; BLOCK_BYTES],
+    w: &[u8],
+) -> [u8; BLOCK_BYTES] {
+    let mut state = State::from_slice(&data);
+    state.add_round_key(w, NR);
 
     for round in (1..NR).rev() {
-        inv_shift_rows(&mut state);
-        inv_sub_bytes(&mut state);
-        add_round_key(&mut state, &w, round);
-        inv_mix_columns(&mut state);
+        state.inv_shift_rows();
+        state.inv_sub_bytes();
+        state.add_round_key(w, round);
+        state.inv_mix_columns();
     }
 
-    inv_shift_rows(&mut state);
-    inv_sub_bytes(&mut state);
-    add_round_key(&mut state, &w, 0);
+    state.inv_shift_rows();
+    state.inv_sub_bytes();
+    state.add_round_key(w, 0);
 
-    state
+    let mut result = [0; BLOCK_BYTES];
+    result.copy_from_slice(state.as_ref());
+    result
 }
 
 /// The AddRoundKey transformation defined in Section 5.1.4 of the AES
@@ -335,7 +881,12 @@ pub fn inv_sub_bytes(bytes: &mut [u8]) {
 /// The ShiftRows transformation defined in Section 5.1.2 of the AES
 /// specification.
 ///
-/// Rotates all rows by a certain offset, except the first one.
+/// Rotates all rows of the [state](State) by a certain offset, except the
+/// first one.
+///
+/// # Panics
+///
+/// If the state is shorter than 16 bytes. Only the first 16 bytes are shifted.
 #[docext]
 pub fn shift_rows(state: &mut [u8]) {
     // Shift second row.
@@ -356,8 +907,12 @@ pub fn shift_rows(state: &mut [u8]) {
 /// The InvShiftRows transformation defined in Section 5.3.1 of the AES
 /// specification.
 ///
-/// Rotates all rows by a certain offset, except the first one, in the opposite
-/// direction of [ShiftRows](shift_rows).
+/// Rotates all rows of the [state](State) by a certain offset, except the
+/// first one, in the opposite direction of [ShiftRows](shift_rows).
+///
+/// # Panics
+///
+/// If the state is shorter than 16 bytes. Only the first 16 bytes are shifted.
 #[docext]
 pub fn inv_shift_rows(state: &mut [u8]) {
     // Shift second row.
@@ -378,12 +933,19 @@ pub fn inv_shift_rows(state: &mut [u8]) {
 /// The MixColumns transformation defined in Section 5.1.3 of the AES
 /// specification.
 ///
-/// Multiplies each column of the state array (represented as a column vector of
-/// $GF(2^8)$ polynomials) by a fixed matrix. The matrix is designed to cause a
-/// nonlinear correlation between the elements of the column, mixing them
-/// together.
+/// Multiplies each column of the [state](State) (represented as a column
+/// vector of $GF(2^8)$ polynomials) by a fixed matrix. The matrix is designed
+/// to cause a nonlinear correlation between the elements of the column, mixing
+/// them together.
 ///
 /// The multiplications are carried out via [`times_02`] and related functions.
+///
+/// Every four bytes of the input are mixed as one column, so the input can hold
+/// any number of columns.
+///
+/// # Panics
+///
+/// If the length of the input is not a multiple of 4.
 #[docext]
 pub fn mix_columns<const BLOCK_BYTES: usize>(state: &mut [u8; BLOCK_BYTES]) {
     let copy = *state;
@@ -398,7 +960,12 @@ pub fn mix_columns<const BLOCK_BYTES: usize>(state: &mut [u8; BLOCK_BYTES]) {
 /// The InvMixColumns transformation defined in Section 5.3.1 of the AES
 /// specification.
 ///
-/// Multiplies the state array by the inverse matrix of that used in
+/// Multiplies the [state](State) by the inverse matrix of that used in
+/// [MixColumns](mix_columns).
+///
+/// # Panics
+///
+/// If the length of the input is not a multiple of 4, like
 /// [MixColumns](mix_columns).
 #[docext]
 pub fn inv_mix_columns<const BLOCK_BYTES: usize>(state: &mut [u8; BLOCK_BYTES]) {
@@ -411,6 +978,105 @@ pub fn inv_mix_columns<const BLOCK_BYTES: usize>(state: &mut [u8; BLOCK_BYTES])
     });
 }
 
+/// An element of the Galois field $GF(2^8)$ used by AES, described in Section
+/// 4 of the AES specification.
+///
+/// The byte $abcdefgh_2$ stands for the polynomial $a x^7 + b x^6 + \dots + g x
+/// + h$ with coefficients modulo 2. [Addition](ops::Add) is the XOR of the
+/// bytes, and [multiplication](ops::Mul) is the product of the polynomials
+/// modulo the irreducible $m(x) = x^8 + x^4 + x^3 + x + 1 \equiv
+/// \mathrm{11b_{16}}$, as explained in detail for [`times_02`].
+///
+/// Because $m(x)$ is irreducible, every nonzero element has a multiplicative
+/// [inverse](Gf256::inv). The nonzero elements form a group of order 255, so
+/// $a^{255} = 1$ and the inverse is $a^{254}$.
+///
+/// The [S-box](S_BOX) is built from this arithmetic: each byte is replaced by
+/// its inverse, and then passed through a fixed affine transformation.
+#[docext]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Gf256(pub u8);
+
+impl Gf256 {
+    pub const ZERO: Self = Self(0);
+    pub const ONE: Self = Self(1);
+
+    /// Multiply by $x \equiv 02_{16}$, a left shift followed by a reduction
+    /// modulo $m(x)$ if the $x^8$ term is set. See [`times_02`].
+    #[docext]
+    pub fn times_x(self) -> Self {
+        // As the FIP explains, this is implemented via a bit shift and conditional XOR
+        // with 0x1b if the high bit is set.
+        let mut r = self.0 << 1;
+        // The high bit will be set in the shifted bitset if the high bit was set
+        // in the original bitset before the shift.
+        if self.0 & 0x80 != 0 {
+            r ^= 0x1b;
+        }
+        Self(r)
+    }
+
+    /// Raise the element to the power $n$, by square-and-multiply.
+    ///
+    /// By convention $0^0 = 1$.
+    #[docext]
+    pub fn pow(self, mut n: u32) -> Self {
+        let mut result = Self::ONE;
+        let mut base = self;
+        while n > 0 {
+            if n & 1 == 1 {
+                result = result * base;
+            }
+            base = base * base;
+            n >>= 1;
+        }
+        result
+    }
+
+    /// The multiplicative inverse $a^{-1} = a^{254}$.
+    ///
+    /// Zero has no inverse, and is mapped to itself, as in the definition of
+    /// the [S-box](S_BOX).
+    #[docext]
+    pub fn inv(self) -> Self {
+        self.pow(254)
+    }
+}
+
+/// Addition of polynomials with coefficients modulo 2, the XOR of the bytes.
+/// Subtraction is the same operation.
+impl ops::Add for Gf256 {
+    type Output = Self;
+
+    #[allow(clippy::suspicious_arithmetic_impl)]
+    fn add(self, rhs: Self) -> Self {
+        Self(self.0 ^ rhs.0)
+    }
+}
+
+/// Multiplication of polynomials modulo $m(x)$.
+///
+/// The product $a \cdot b$ is the sum of $a \cdot x^i$ over the bits $i$
+/// which are set in $b$. The terms $a \cdot x^i$ are computed one after
+/// another with [`times_x`](Gf256::times_x), so the product never needs more
+/// than eight bits.
+#[docext]
+impl ops::Mul for Gf256 {
+    type Output = Self;
+
+    fn mul(self, rhs: Self) -> Self {
+        let mut result = Self::ZERO;
+        let mut term = self;
+        for i in 0..8 {
+            if rhs.0 >> i & 1 == 1 {
+                result = result + term;
+            }
+            term = term.times_x();
+        }
+        result
+    }
+}
+
 /// Multiply `b` by 0x02 in the Galois field $GF(2^8)$.
 ///
 /// This operations is required by the
@@ -468,20 +1134,13 @@ pub fn inv_mix_columns<const BLOCK_BYTES: usize>(state: &mut [u8; BLOCK_BYTES])
 ///
 /// This is useful because multiplication by any polynomial can be represented
 /// as a series of multiplications by $x$ and additions of the resulting terms.
-/// This is how multiplications in related functions ([`times_03`],
-/// [`times_0e`], etc.) are defined: as a series of [`times_02`] and XOR
-/// operations.
+/// This is how [multiplication of field elements](Gf256) is defined, and the
+/// related functions ([`times_03`], [`times_0e`], etc.) are its
+/// specializations to the constants used by AES: each of them is a series of
+/// [`times_02`] and XOR operations.
 #[docext]
 pub fn times_02(b: u8) -> u8 {
-    // As the FIP explains, this is implemented via a bit shift and conditional XOR
-    // with 0x1b if the high bit is set.
-    let mut r = b << 1;
-    // The high bit will be set in the shifted bitset if the high bit was set
-    // in the original bitset before the shift.
-    if b & 0x80 != 0 {
-        r ^= 0x1b;
-    }
-    r
+    Gf256(b).times_x().0
 }
 
 /// Multiply `b` by `0x03` in the Galois field $GF(2^8)$.
@@ -497,7 +1156,7 @@ pub fn times_02(b: u8) -> u8 {
 /// Which is equivalent to `times_02(b) ^ b`.
 #[docext]
 pub fn times_03(b: u8) -> u8 {
-    times_02(b) ^ b
+    (Gf256(b) * Gf256(0x03)).0
 }
 
 /// Multiplication by `0x04` in the Galois field $GF(2^8)$.
@@ -511,7 +1170,7 @@ pub fn times_03(b: u8) -> u8 {
 /// Which is equivalent to `times_02(times_02(b))`.
 #[docext]
 pub fn times_04(b: u8) -> u8 {
-    times_02(times_02(b))
+    (Gf256(b) * Gf256(0x04)).0
 }
 
 /// Multiplication by `0x08` in the Galois field $GF(2^8)$.
@@ -526,7 +1185,7 @@ pub fn times_04(b: u8) -> u8 {
 /// Which is equivalent to `times_02(times_04(b))`.
 #[docext]
 pub fn times_08(b: u8) -> u8 {
-    times_02(times_04(b))
+    (Gf256(b) * Gf256(0x08)).0
 }
 
 /// Multiplication by `0x09` in the Galois field $GF(2^8)$.
@@ -542,7 +1201,7 @@ pub fn times_08(b: u8) -> u8 {
 /// Which is equivalent to `times_08(b) ^ b`.
 #[docext]
 pub fn times_09(b: u8) -> u8 {
-    times_08(b) ^ b
+    (Gf256(b) * Gf256(0x09)).0
 }
 
 /// Multiplication by `0x0b` in the Galois field $GF(2^8)$.
@@ -557,7 +1216,7 @@ pub fn times_09(b: u8) -> u8 {
 /// Which is equivalent to `times_08(b) ^ times_03(b)`.
 #[docext]
 pub fn times_0b(b: u8) -> u8 {
-    times_08(b) ^ times_03(b)
+    (Gf256(b) * Gf256(0x0b)).0
 }
 
 /// Multiplication by `0x0d` in the Galois field $GF(2^8)$.
@@ -573,7 +1232,7 @@ pub fn times_0b(b: u8) -> u8 {
 /// Which is equivalent to `times_08(b) ^ times_04(b) ^ b`.
 #[docext]
 pub fn times_0d(b: u8) -> u8 {
-    times_08(b) ^ times_04(b) ^ b
+    (Gf256(b) * Gf256(0x0d)).0
 }
 
 /// Multiplication by `0x0e` in the Galois field $GF(2^8)$.
@@ -589,7 +1248,7 @@ pub fn times_0d(b: u8) -> u8 {
 /// Which is equivalent to `times_08(b) ^ times_04(b) ^ times_02(b)`.
 #[docext]
 pub fn times_0e(b: u8) -> u8 {
-    times_08(b) ^ times_04(b) ^ times_02(b)
+    (Gf256(b) * Gf256(0x0e)).0
 }
 
 /// The KeyExpansion routine defined in Section 5.2 of the AES specification.
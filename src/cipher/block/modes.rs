@@ -2,10 +2,10 @@ use crate::Cipher;
 
 mod cbc;
 mod ctr;
+mod cts;
+mod derived;
 mod ecb;
-
-// TODO Implement GCM, start from mathematical foundations for polynomials,
-// those MIGHT also be useful for ZKP or something else, or maybe not.
+mod gcm;
 
 /// A way to execute a [block cipher](crate::BlockCipher) on data of arbitrary
 /// length.
@@ -19,7 +19,10 @@ mod ecb;
 pub trait BlockMode: Cipher {}
 
 pub use {
-    cbc::Cbc,
-    ctr::{BlockSizeTooSmall, Ctr},
+    cbc::{Cbc, CbcDecryptIter},
+    ctr::{CounterPosition, CounterWidth, Ctr, CtrConfigError, CtrDecryptIter, CtrError},
+    cts::{CbcCts, CtsTooShort},
+    derived::{DerivedNonce, DerivedNonceError, NonceMode},
     ecb::Ecb,
+    gcm::{gf128_mul, ghash, AuthenticationFailed, Gcm, GcmTag},
 };
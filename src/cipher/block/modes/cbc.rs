@@ -1,5 +1,6 @@
 use {
     crate::{
+        random,
         BlockCipher,
         BlockDecrypt,
         BlockEncrypt,
@@ -7,10 +8,12 @@ use {
         Cipher,
         CipherDecrypt,
         CipherEncrypt,
+        Csprng,
+        NonceMode,
         Padding,
     },
     docext::docext,
-    std::{convert::Infallible, fmt, mem::size_of},
+    std::{cell::Cell, convert::Infallible, fmt, mem::size_of, slice},
 };
 
 /// Cipher block chaining mode is the most common [mode of
@@ -43,16 +46,60 @@ use {
 ///
 /// Because the same plaintext with a different IV will encrypt to a different
 /// ciphertext, CBC solves the issues of [ECB mode](crate::Ecb#security).
+///
+/// # IV Reuse
+///
+/// The IV passed to [`new`](Cbc::new) is used by every call to
+/// [`encrypt`](CipherEncrypt::encrypt), so encrypting two messages with the
+/// same instance and the same key reuses the IV. Then $C_1 = E(P_1 \oplus
+/// C_0)$ is the same for both messages whenever $P_1$ is, and so on for every
+/// block of a common prefix: the ciphertexts leak how many blocks the
+/// plaintexts share, just like [ECB mode](crate::Ecb#security) does.
+///
+/// To encrypt several messages, pass a fresh IV for each of them to
+/// [`encrypt_with_iv`](Cbc::encrypt_with_iv), e.g. one made by
+/// [`generate_iv`](Cbc::generate_iv). To catch accidental reuse of the
+/// configured IV, an instance can be restricted with
+/// [`encrypt_once`](Cbc::encrypt_once), so that it panics when asked to
+/// encrypt a second message with it.
 #[docext]
 pub struct Cbc<Cip, Pad, Block> {
     cip: Cip,
     pad: Pad,
     iv: Block,
+    once: bool,
+    /// Whether the configured IV was used to encrypt a message.
+    used: Cell<bool>,
 }
 
 impl<Cip, Pad, Block> Cbc<Cip, Pad, Block> {
     pub fn new(cip: Cip, pad: Pad, iv: Block) -> Self {
-        Self { cip, pad, iv }
+        Self {
+            cip,
+            pad,
+            iv,
+            once: false,
+            used: Cell::new(false),
+        }
+    }
+
+    /// Allow the configured IV to encrypt only one message, see [IV
+    /// reuse](Cbc#iv-reuse). Decryption and encryption with an explicit IV are
+    /// not restricted.
+    pub fn encrypt_once(self) -> Self {
+        Self { once: true, ..self }
+    }
+}
+
+impl<Cip: BlockCipher, Pad> Cbc<Cip, Pad, Cip::Block>
+where
+    Cip::Block: Default + AsMut<[u8]>,
+{
+    /// Generate a random IV using the given source of randomness, typically a
+    /// [CSPRNG](crate::Csprng). See [the section on IVs](Cbc#iv) for why the IV
+    /// must be unique for each message.
+    pub fn generate_iv(rng: &mut impl Csprng) -> Cip::Block {
+        random::random_bytes(rng)
     }
 }
 
@@ -66,6 +113,14 @@ where
     Cip::Key: Clone,
 {
     type Key = Cip::Key;
+
+    /// Generate a key for the underlying block cipher.
+    fn generate_key(rng: &mut impl Csprng) -> Self::Key
+    where
+        Self::Key: Default + AsMut<[u8]>,
+    {
+        Cip::generate_key(rng)
+    }
 }
 
 impl<Cip: BlockCipher, Pad: Padding> BlockMode for Cbc<Cip, Pad, Cip::Block>
@@ -91,15 +146,247 @@ where
     type EncryptionErr = Infallible;
     type EncryptionKey = Enc::EncryptionKey;
 
+    /// Encrypt with the configured IV.
+    ///
+    /// # Panics
+    ///
+    /// If the instance was restricted with [`encrypt_once`](Cbc::encrypt_once)
+    /// and already encrypted a message.
     fn encrypt(
         &self,
         data: Vec<u8>,
         key: Self::EncryptionKey,
     ) -> Result<Vec<u8>, Self::EncryptionErr> {
+        let reused = self.used.replace(true);
+        assert!(
+            !(self.once && reused),
+            "CBC IV reused: encrypt_once allows a single message, use encrypt_with_iv with a \
+             fresh IV"
+        );
+        Ok(self.encrypt_with_iv(data, key, self.iv.clone()))
+    }
+
+    fn encrypt_from(
+        &self,
+        data: &[u8],
+        key: Self::EncryptionKey,
+    ) -> Result<Vec<u8>, Self::EncryptionErr> {
+        // Reserve space for the padding up front, so that the copy of the
+        // plaintext doesn't have to be reallocated when it gets padded.
+        let mut copy = Vec::with_capacity(data.len() + size_of::<Enc::EncryptionBlock>());
+        copy.extend_from_slice(data);
+        self.encrypt(copy, key)
+    }
+}
+
+impl<Dec: BlockDecrypt, Pad: Padding> CipherDecrypt for Cbc<Dec, Pad, Dec::DecryptionBlock>
+where
+    Dec::DecryptionBlock: for<'a> TryFrom<&'a mut [u8], Error: fmt::Debug>
+        + AsRef<[u8]>
+        + AsMut<[u8]>
+        + IntoIterator<Item = u8>
+        + Clone,
+    Dec::DecryptionKey: Clone,
+{
+    type DecryptionErr = Pad::Err;
+    type DecryptionKey = Dec::DecryptionKey;
+
+    fn decrypt(
+        &self,
+        data: Vec<u8>,
+        key: Self::DecryptionKey,
+    ) -> Result<Vec<u8>, Self::DecryptionErr> {
+        self.decrypt_with_iv(data, key, self.iv.clone())
+    }
+}
+
+impl<Dec: BlockDecrypt, Pad: Padding> Cbc<Dec, Pad, Dec::DecryptionBlock>
+where
+    Dec::DecryptionBlock: for<'a> TryFrom<&'a mut [u8], Error: fmt::Debug>
+        + AsRef<[u8]>
+        + AsMut<[u8]>
+        + IntoIterator<Item = u8>
+        + Clone,
+    Dec::DecryptionKey: Clone,
+{
+    /// Decrypt lazily with the configured IV, one block at a time, see
+    /// [`CbcDecryptIter`].
+    pub fn decrypt_iter<'a>(
+        &'a self,
+        data: &'a [u8],
+        key: Dec::DecryptionKey,
+    ) -> CbcDecryptIter<'a, Dec, Pad> {
+        self.decrypt_iter_with_iv(data, key, self.iv.clone())
+    }
+
+    /// Decrypt lazily with the given IV instead of the configured one.
+    pub fn decrypt_iter_with_iv<'a>(
+        &'a self,
+        data: &'a [u8],
+        key: Dec::DecryptionKey,
+        iv: Dec::DecryptionBlock,
+    ) -> CbcDecryptIter<'a, Dec, Pad> {
+        let block_size = size_of::<Dec::DecryptionBlock>();
+        let whole = if data.len() % block_size == 0 {
+            data.len()
+        } else {
+            // Don't decrypt anything, and let the padding reject the partial
+            // block at the end.
+            0
+        };
+        CbcDecryptIter {
+            cbc: self,
+            blocks: data[..whole].chunks(block_size),
+            partial: &data[data.len() - data.len() % block_size..],
+            key,
+            prev: iv,
+            pending: None,
+            done: false,
+        }
+    }
+}
+
+/// Lazy [CBC](Cbc) decryption of a ciphertext, e.g. one mapped from a file
+/// which is too large to decrypt at once.
+///
+/// Every item is a block of plaintext, except for the last one, which is what
+/// is left of the last block after the padding is removed. If the padding is
+/// invalid, the last item is the error instead. Since the padding can only be
+/// checked once the last block is reached, a decrypted block is held back until
+/// the next one is known to exist. Other than that, only the previous block of
+/// ciphertext is kept in memory.
+///
+/// The padding is removed from the last block alone, which works for
+/// [PKCS #7](crate::Pkcs7) and any other scheme which adds at most one block of
+/// padding.
+///
+/// Note that every block except the last is yielded before the padding is
+/// checked, and the plaintext of a ciphertext with invalid padding should be
+/// discarded. Acting on the blocks of a tampered ciphertext before the end is
+/// reached exposes the same [padding oracle](Padding) as acting on the
+/// error does.
+pub struct CbcDecryptIter<'a, Dec: BlockDecrypt, Pad> {
+    cbc: &'a Cbc<Dec, Pad, Dec::DecryptionBlock>,
+    blocks: slice::Chunks<'a, u8>,
+    /// The trailing bytes of a ciphertext which isn't made of whole blocks.
+    partial: &'a [u8],
+    key: Dec::DecryptionKey,
+    /// The previous block of ciphertext.
+    prev: Dec::DecryptionBlock,
+    /// The last decrypted block, held back in case it is the last one.
+    pending: Option<Dec::DecryptionBlock>,
+    done: bool,
+}
+
+impl<Dec: BlockDecrypt, Pad: Padding> Iterator for CbcDecryptIter<'_, Dec, Pad>
+where
+    Dec::DecryptionBlock: for<'a> TryFrom<&'a mut [u8], Error: fmt::Debug>
+        + AsRef<[u8]>
+        + AsMut<[u8]>
+        + IntoIterator<Item = u8>
+        + Clone,
+    Dec::DecryptionKey: Clone,
+{
+    type Item = Result<Vec<u8>, Pad::Err>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        for chunk in self.blocks.by_ref() {
+            // The chunks are whole blocks.
+            let block: Dec::DecryptionBlock = chunk.to_vec().as_mut_slice().try_into().unwrap();
+            let mut plaintext = self.cbc.cip.decrypt(block.clone(), self.key.clone());
+            plaintext
+                .as_mut()
+                .iter_mut()
+                .zip(self.prev.clone())
+                .for_each(|(a, b): (&mut u8, _)| *a ^= b);
+            self.prev = block;
+            if let Some(prev) = self.pending.replace(plaintext) {
+                return Some(Ok(prev.as_ref().to_vec()));
+            }
+        }
+
+        // The last block was reached, remove the padding.
+        self.done = true;
+        let last = match self.pending.take() {
+            Some(block) => block.as_ref().to_vec(),
+            None => self.partial.to_vec(),
+        };
+        let block_size = size_of::<Dec::DecryptionBlock>();
+        match self.cbc.pad.unpad(last, block_size) {
+            // A block of padding leaves nothing to yield.
+            Ok(last) if last.is_empty() => None,
+            result => Some(result),
+        }
+    }
+}
+
+/// The derived nonce is the IV.
+impl<Cip: BlockCipher, Pad: Padding> NonceMode for Cbc<Cip, Pad, Cip::Block>
+where
+    Cip::Block: for<'a> TryFrom<&'a mut [u8], Error: fmt::Debug>
+        + AsRef<[u8]>
+        + AsMut<[u8]>
+        + IntoIterator<Item = u8>
+        + Clone,
+    Cip::Key: Clone,
+{
+    const NONCE_SIZE: usize = size_of::<Cip::Block>();
+
+    fn encrypt_with_nonce(
+        &self,
+        data: Vec<u8>,
+        key: Self::Key,
+        nonce: &[u8],
+    ) -> Result<Vec<u8>, Self::EncryptionErr> {
+        let iv = nonce
+            .to_vec()
+            .as_mut_slice()
+            .try_into()
+            .expect("nonce of NONCE_SIZE bytes");
+        Ok(self.encrypt_with_iv(data, key, iv))
+    }
+
+    fn decrypt_with_nonce(
+        &self,
+        data: Vec<u8>,
+        key: Self::Key,
+        nonce: &[u8],
+    ) -> Result<Vec<u8>, Self::DecryptionErr> {
+        let iv = nonce
+            .to_vec()
+            .as_mut_slice()
+            .try_into()
+            .expect("nonce of NONCE_SIZE bytes");
+        self.decrypt_with_iv(data, key, iv)
+    }
+}
+
+impl<Enc: BlockEncrypt, Pad: Padding> Cbc<Enc, Pad, Enc::EncryptionBlock>
+where
+    Enc::EncryptionBlock: for<'a> TryFrom<&'a mut [u8], Error: fmt::Debug>
+        + AsRef<[u8]>
+        + AsMut<[u8]>
+        + IntoIterator<Item = u8>
+        + Clone,
+    Enc::EncryptionKey: Clone,
+{
+    /// Encrypt with the given IV instead of the configured one. The IV must be
+    /// unique for each message encrypted with the same key, see [IV
+    /// reuse](Cbc#iv-reuse).
+    pub fn encrypt_with_iv(
+        &self,
+        data: Vec<u8>,
+        key: Enc::EncryptionKey,
+        iv: Enc::EncryptionBlock,
+    ) -> Vec<u8> {
         let block_size = size_of::<Enc::EncryptionBlock>();
-        let mut prev = self.iv.clone();
+        let mut prev = iv;
         let mut data = self.pad.pad(data, block_size);
-        // Encrypt the blocks in-place, using the input vector.
+        // Encrypt the blocks in-place, using the input vector. The padded data
+        // is made of whole blocks.
         for chunk in data.chunks_mut(block_size) {
             let mut block: Enc::EncryptionBlock = chunk.try_into().unwrap();
             block
@@ -111,11 +398,11 @@ where
             chunk.copy_from_slice(ciphertext.as_ref());
             prev = ciphertext;
         }
-        Ok(data)
+        data
     }
 }
 
-impl<Dec: BlockDecrypt, Pad: Padding> CipherDecrypt for Cbc<Dec, Pad, Dec::DecryptionBlock>
+impl<Dec: BlockDecrypt, Pad: Padding> Cbc<Dec, Pad, Dec::DecryptionBlock>
 where
     Dec::DecryptionBlock: for<'a> TryFrom<&'a mut [u8], Error: fmt::Debug>
         + AsRef<[u8]>
@@ -124,16 +411,21 @@ where
         + Clone,
     Dec::DecryptionKey: Clone,
 {
-    type DecryptionErr = Pad::Err;
-    type DecryptionKey = Dec::DecryptionKey;
-
-    fn decrypt(
+    /// Decrypt with the given IV instead of the configured one, i.e. the IV the
+    /// message was [encrypted](Cbc::encrypt_with_iv) with.
+    pub fn decrypt_with_iv(
         &self,
         mut data: Vec<u8>,
-        key: Self::DecryptionKey,
-    ) -> Result<Vec<u8>, Self::DecryptionErr> {
+        key: Dec::DecryptionKey,
+        iv: Dec::DecryptionBlock,
+    ) -> Result<Vec<u8>, Pad::Err> {
         let block_size = size_of::<Dec::DecryptionBlock>();
-        let mut prev = self.iv.clone();
+        if data.len() % block_size != 0 {
+            // The ciphertext is not made of whole blocks, so it can't be padded
+            // correctly, and the padding reports the error.
+            return self.pad.unpad(data, block_size);
+        }
+        let mut prev = iv;
         // Decrypt the blocks in-place, using the input vector.
         for chunk in data.chunks_mut(block_size) {
             let block: Dec::DecryptionBlock = chunk.try_into().unwrap();
@@ -1,7 +1,17 @@
 use {
-    crate::{BlockEncrypt, BlockMode, Cipher, CipherDecrypt, CipherEncrypt, OneTimePad},
+    crate::{
+        ecc,
+        BlockEncrypt,
+        BlockMode,
+        Cipher,
+        CipherDecrypt,
+        CipherEncrypt,
+        Csprng,
+        NonceMode,
+        OneTimePad,
+    },
     docext::docext,
-    std::{convert::Infallible, fmt, iter, mem},
+    std::{fmt, mem, ops::Range, slice},
 };
 
 /// Block counter [mode](crate::BlockMode) is a block chaining mode which turns
@@ -33,26 +43,112 @@ use {
 /// The operation of counter mode essentially represents a [one-time
 /// pad](crate::OneTimePad), where the keystream is generated using the
 /// underlying block cipher and the block counter.
+///
+/// # Keystream Access
+///
+/// Since the keystream only depends on the key and the counter, any part of it
+/// can be computed directly with [`keystream_bytes`](Ctr::keystream_bytes),
+/// without computing what comes before it. Nothing about the keystream or the
+/// key is kept between calls, so a message encrypted in several pieces
+/// encrypts the block shared by two neighboring pieces twice.
+///
+/// # Counter Block
+///
+/// The block which is encrypted is called the _counter block_. Part of it is
+/// the counter, a little-endian number at the start of the block
+/// ([prefix](CounterPosition::Prefix)) or a big-endian number at the end of it
+/// ([suffix](CounterPosition::Suffix)), which is 32, 64 or 128 bits
+/// [wide](CounterWidth). The rest of the block stays the same for every block
+/// of the keystream, and usually holds a nonce.
+///
+/// [`new`](Ctr::new) uses a 64-bit prefix counter starting at the given nonce,
+/// with the rest of the block set to zero. [`with_iv`](Ctr::with_iv) uses the
+/// whole block as a big-endian counter, as most other implementations do. Other
+/// layouts, e.g. the 32-bit suffix counter of many protocols, or a 128-bit
+/// counter for ciphers with large blocks, are set up with
+/// [`with_counter_block`](Ctr::with_counter_block).
+///
+/// # Counter Wraparound
+///
+/// The counter of block $i$ of the keystream is $c + i$, where $c$ is the
+/// initial counter. If this addition overflowed the width of the counter, the
+/// counter would wrap around to zero and the keystream would repeat blocks that
+/// were already produced for smaller counters. Two plaintexts encrypted with
+/// the same keystream leak their XOR, so instead of wrapping, encryption fails
+/// with [`CtrError::CounterWrap`]. With a 64-bit counter starting at zero, the
+/// limit is $2^{64}$ blocks, which is never reached in practice, but an initial
+/// counter close to the limit leaves room for only a few blocks.
+///
+/// Protocols which split the counter block into a nonce part and a smaller
+/// counter part, e.g. a 32-bit block counter, need a tighter limit on the
+/// number of bytes encrypted under one nonce. Such a limit can be set with
+/// [`with_limit`](Ctr::with_limit), and encrypting more bytes fails with
+/// [`CtrError::LimitExceeded`].
 #[docext]
 #[derive(Debug, Clone)]
-pub struct Ctr<Enc> {
+pub struct Ctr<Enc: BlockEncrypt> {
     enc: Enc,
-    nonce: u64,
+    /// The counter block of the first block of the keystream.
+    initial: Vec<u8>,
+    width: CounterWidth,
+    position: CounterPosition,
+    limit: Option<u64>,
+}
+
+/// The width of the counter in the [counter block](Ctr#counter-block).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CounterWidth {
+    Bits32,
+    Bits64,
+    Bits128,
+}
+
+impl CounterWidth {
+    /// The width in bytes.
+    pub fn bytes(self) -> usize {
+        match self {
+            Self::Bits32 => 4,
+            Self::Bits64 => 8,
+            Self::Bits128 => 16,
+        }
+    }
+}
+
+/// Where the counter is in the [counter block](Ctr#counter-block).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CounterPosition {
+    /// A little-endian number in the first bytes of the block.
+    Prefix,
+    /// A big-endian number in the last bytes of the block, as in [NIST SP
+    /// 800-38A](https://doi.org/10.6028/NIST.SP.800-38A) and GCM.
+    Suffix,
 }
 
+/// A counter value, as little-endian 64-bit words, wide enough for every
+/// [counter width](CounterWidth).
+type Counter = [u64; 2];
+
 impl<Enc> Cipher for Ctr<Enc>
 where
     Enc: BlockEncrypt,
-    Enc::EncryptionBlock: IntoIterator<Item = u8> + AsMut<[u8]> + Default,
+    Enc::EncryptionBlock: IntoIterator<Item = u8> + AsMut<[u8]> + Default + Clone,
     Enc::EncryptionKey: 'static + Clone,
 {
     type Key = Enc::EncryptionKey;
+
+    /// Generate a key for the underlying block cipher.
+    fn generate_key(rng: &mut impl Csprng) -> Self::Key
+    where
+        Self::Key: Default + AsMut<[u8]>,
+    {
+        Enc::generate_key(rng)
+    }
 }
 
 impl<Enc> BlockMode for Ctr<Enc>
 where
     Enc: BlockEncrypt,
-    Enc::EncryptionBlock: IntoIterator<Item = u8> + AsMut<[u8]> + Default,
+    Enc::EncryptionBlock: IntoIterator<Item = u8> + AsMut<[u8]> + Default + Clone,
     Enc::EncryptionKey: 'static + Clone,
 {
 }
@@ -61,22 +157,178 @@ impl<Enc, const BLOCK_SIZE: usize> Ctr<Enc>
 where
     Enc: BlockEncrypt<EncryptionBlock = [u8; BLOCK_SIZE]>,
 {
-    pub fn new(enc: Enc, nonce: u64) -> Result<Self, BlockSizeTooSmall> {
-        if BLOCK_SIZE < mem::size_of_val(&nonce) {
-            Err(BlockSizeTooSmall)
-        } else {
-            Ok(Self { enc, nonce })
+    /// Use a 64-bit [prefix](CounterPosition::Prefix) counter starting at the
+    /// nonce, see [counter block](Ctr#counter-block).
+    pub fn new(enc: Enc, nonce: u64) -> Result<Self, CtrConfigError> {
+        let mut block = [0; BLOCK_SIZE];
+        block
+            .iter_mut()
+            .zip(nonce.to_le_bytes())
+            .for_each(|(b, n)| *b = n);
+        Self::with_counter_block(enc, block, CounterWidth::Bits64, CounterPosition::Prefix)
+    }
+
+    /// Use the whole IV as a big-endian [suffix](CounterPosition::Suffix)
+    /// counter, incremented with carry across the entire block, as in [NIST SP
+    /// 800-38A](https://doi.org/10.6028/NIST.SP.800-38A) and OpenSSL.
+    ///
+    /// For blocks larger than 128 bits, the counter is the last 128 bits, the
+    /// widest [counter width](CounterWidth). For smaller blocks it is the
+    /// widest one which fits, so that only blocks smaller than 32 bits are
+    /// rejected.
+    pub fn with_iv(enc: Enc, iv: [u8; BLOCK_SIZE]) -> Result<Self, CtrConfigError> {
+        let width = [CounterWidth::Bits128, CounterWidth::Bits64]
+            .into_iter()
+            .find(|width| width.bytes() <= BLOCK_SIZE)
+            .unwrap_or(CounterWidth::Bits32);
+        Self::with_counter_block(enc, iv, width, CounterPosition::Suffix)
+    }
+
+    /// Use the given initial [counter block](Ctr#counter-block), with a counter
+    /// of the given width and position. The counter starts at the value it has
+    /// in the block, and the rest of the block is kept as is.
+    pub fn with_counter_block(
+        enc: Enc,
+        block: [u8; BLOCK_SIZE],
+        width: CounterWidth,
+        position: CounterPosition,
+    ) -> Result<Self, CtrConfigError> {
+        if BLOCK_SIZE < width.bytes() {
+            return Err(CtrConfigError::BlockTooSmall {
+                block: BLOCK_SIZE,
+                required: width.bytes(),
+            });
+        }
+        Ok(Self {
+            enc,
+            initial: block.to_vec(),
+            width,
+            position,
+            limit: None,
+        })
+    }
+}
+
+impl<Enc: BlockEncrypt> Ctr<Enc> {
+    /// The underlying block cipher.
+    pub(super) fn enc(&self) -> &Enc {
+        &self.enc
+    }
+
+    /// Limit the number of bytes which can be encrypted under the nonce, see
+    /// [counter wraparound](Ctr#counter-wraparound).
+    pub fn with_limit(self, max_bytes: u64) -> Result<Self, CtrConfigError> {
+        if max_bytes == 0 {
+            return Err(CtrConfigError::ZeroLimit);
+        }
+        Ok(Self {
+            limit: Some(max_bytes),
+            ..self
+        })
+    }
+}
+
+impl<Enc> Ctr<Enc>
+where
+    Enc: BlockEncrypt,
+    Enc::EncryptionBlock: IntoIterator<Item = u8> + AsMut<[u8]> + Default + Clone,
+    Enc::EncryptionKey: Clone,
+{
+    /// Compute the bytes of the keystream in the given range, where byte 0 is
+    /// the first byte of the encrypted initial counter block.
+    ///
+    /// Computing the keystream in consecutive ranges gives the same bytes as
+    /// computing it all at once. Fails if the range goes past the
+    /// [limit](Ctr::with_limit) or would make the counter [wrap
+    /// around](Ctr#counter-wraparound).
+    pub fn keystream_bytes(
+        &self,
+        key: Enc::EncryptionKey,
+        range: Range<u64>,
+    ) -> Result<Vec<u8>, CtrError> {
+        let counter = read_counter(&self.initial[self.counter_range()], self.position);
+        self.keystream_bytes_from(key, counter, range)
+    }
+
+    /// Compute the bytes of the keystream starting at the given counter instead
+    /// of the configured one.
+    fn keystream_bytes_from(
+        &self,
+        key: Enc::EncryptionKey,
+        initial: Counter,
+        range: Range<u64>,
+    ) -> Result<Vec<u8>, CtrError> {
+        if range.is_empty() {
+            return Ok(Vec::new());
+        }
+        if let Some(limit) = self.limit.filter(|&limit| range.end > limit) {
+            return Err(CtrError::LimitExceeded {
+                len: range.end,
+                limit,
+            });
+        }
+        let block_size = u64::try_from(Enc::EncryptionBlock::default().as_mut().len()).unwrap();
+        let first = range.start / block_size;
+        let last = (range.end - 1) / block_size;
+        if self.wraps(initial, last) {
+            return Err(CtrError::CounterWrap {
+                nonce: u128::from(initial[0]) | u128::from(initial[1]) << u64::BITS,
+                blocks: last + 1,
+            });
+        }
+        let mut result = Vec::new();
+        for i in first..=last {
+            // The counter doesn't wrap, which was checked above.
+            let counter = ecc::add_words(initial, [i, 0]).0;
+            result.extend(self.keystream_block(key.clone(), counter));
         }
+        let skip = usize::try_from(range.start - first * block_size).unwrap();
+        let len = usize::try_from(range.end - range.start).unwrap();
+        result.drain(..skip);
+        result.truncate(len);
+        Ok(result)
+    }
+
+    /// Whether adding `i` to the counter overflows its width.
+    fn wraps(&self, counter: Counter, i: u64) -> bool {
+        let (sum, carry) = ecc::add_words(counter, [i, 0]);
+        match self.width {
+            CounterWidth::Bits32 => carry.0 || sum[0] > u64::from(u32::MAX) || sum[1] != 0,
+            CounterWidth::Bits64 => carry.0 || sum[1] != 0,
+            CounterWidth::Bits128 => carry.0,
+        }
+    }
+
+    /// The range of the counter within a counter block.
+    fn counter_range(&self) -> Range<usize> {
+        let len = self.initial.len();
+        match self.position {
+            CounterPosition::Prefix => 0..self.width.bytes(),
+            CounterPosition::Suffix => len - self.width.bytes()..len,
+        }
+    }
+
+    /// Encrypt a single counter block.
+    fn keystream_block(&self, key: Enc::EncryptionKey, counter: Counter) -> Enc::EncryptionBlock {
+        // Copy the counter into the initial block and encrypt it.
+        let mut ctr_block = Enc::EncryptionBlock::default();
+        ctr_block.as_mut().copy_from_slice(&self.initial);
+        write_counter(
+            &mut ctr_block.as_mut()[self.counter_range()],
+            self.position,
+            counter,
+        );
+        self.enc.encrypt(ctr_block, key)
     }
 }
 
 impl<Enc> CipherEncrypt for Ctr<Enc>
 where
     Enc: BlockEncrypt,
-    Enc::EncryptionBlock: IntoIterator<Item = u8> + AsMut<[u8]> + Default,
+    Enc::EncryptionBlock: IntoIterator<Item = u8> + AsMut<[u8]> + Default + Clone,
     Enc::EncryptionKey: 'static + Clone,
 {
-    type EncryptionErr = Infallible;
+    type EncryptionErr = CtrError;
     type EncryptionKey = Enc::EncryptionKey;
 
     fn encrypt(
@@ -84,19 +336,30 @@ where
         data: Vec<u8>,
         key: Self::EncryptionKey,
     ) -> Result<Vec<u8>, Self::EncryptionErr> {
+        let keystream = self.keystream_bytes(key, 0..u64::try_from(data.len()).unwrap())?;
         Ok(OneTimePad::default()
-            .encrypt(data, keystream(&self.enc, key, self.nonce))
-            .expect("infinite keystream"))
+            .encrypt(data, keystream.into_iter())
+            .expect("keystream as long as the data"))
+    }
+
+    /// XOR the plaintext into the keystream, which is already a buffer of the
+    /// right size, so that the plaintext is never copied.
+    fn encrypt_from(
+        &self,
+        data: &[u8],
+        key: Self::EncryptionKey,
+    ) -> Result<Vec<u8>, Self::EncryptionErr> {
+        self.xor_keystream(data, key)
     }
 }
 
 impl<Enc> CipherDecrypt for Ctr<Enc>
 where
     Enc: BlockEncrypt,
-    Enc::EncryptionBlock: IntoIterator<Item = u8> + AsMut<[u8]> + Default,
+    Enc::EncryptionBlock: IntoIterator<Item = u8> + AsMut<[u8]> + Default + Clone,
     Enc::EncryptionKey: 'static + Clone,
 {
-    type DecryptionErr = Infallible;
+    type DecryptionErr = CtrError;
     type DecryptionKey = Enc::EncryptionKey;
 
     fn decrypt(
@@ -104,35 +367,215 @@ where
         data: Vec<u8>,
         key: Self::DecryptionKey,
     ) -> Result<Vec<u8>, Self::DecryptionErr> {
+        let keystream = self.keystream_bytes(key, 0..u64::try_from(data.len()).unwrap())?;
         Ok(OneTimePad::default()
-            .decrypt(data, keystream(&self.enc, key, self.nonce))
-            .expect("infinite keystream"))
+            .decrypt(data, keystream.into_iter())
+            .expect("keystream as long as the data"))
+    }
+
+    /// XOR the ciphertext into the keystream, like
+    /// [`encrypt_from`](CipherEncrypt::encrypt_from).
+    fn decrypt_from(
+        &self,
+        data: &[u8],
+        key: Self::DecryptionKey,
+    ) -> Result<Vec<u8>, Self::DecryptionErr> {
+        self.xor_keystream(data, key)
     }
 }
 
-fn keystream<Enc>(enc: &Enc, key: Enc::EncryptionKey, nonce: u64) -> impl Iterator<Item = u8> + '_
+impl<Enc> Ctr<Enc>
 where
     Enc: BlockEncrypt,
-    Enc::EncryptionBlock: IntoIterator<Item = u8> + AsMut<[u8]> + Default,
+    Enc::EncryptionBlock: IntoIterator<Item = u8> + AsMut<[u8]> + Default + Clone,
     Enc::EncryptionKey: 'static + Clone,
 {
-    iter::successors(Some(nonce), |ctr| Some(ctr.wrapping_add(1))).flat_map(move |ctr| {
-        // Copy the counter bytes into a block and encrypt it.
-        let mut ctr_block = Enc::EncryptionBlock::default();
-        ctr_block
-            .as_mut()
-            .iter_mut()
-            .zip(ctr.to_le_bytes())
-            .for_each(|(b, n)| *b = n);
-        enc.encrypt(ctr_block, key.clone()).into_iter()
-    })
+    /// XOR the data into the keystream of the same length.
+    fn xor_keystream(&self, data: &[u8], key: Enc::EncryptionKey) -> Result<Vec<u8>, CtrError> {
+        let mut keystream = self.keystream_bytes(key, 0..u64::try_from(data.len()).unwrap())?;
+        keystream.iter_mut().zip(data).for_each(|(k, d)| *k ^= d);
+        Ok(keystream)
+    }
+
+    /// Decrypt lazily, one block at a time, see [`CtrDecryptIter`].
+    pub fn decrypt_iter<'a>(
+        &'a self,
+        data: &'a [u8],
+        key: Enc::EncryptionKey,
+    ) -> CtrDecryptIter<'a, Enc> {
+        let block_size = Enc::EncryptionBlock::default().as_mut().len();
+        CtrDecryptIter {
+            ctr: self,
+            blocks: data.chunks(block_size),
+            key,
+            offset: 0,
+            done: false,
+        }
+    }
+}
+
+/// Lazy [CTR](Ctr) decryption of a ciphertext, e.g. one mapped from a file
+/// which is too large to decrypt at once.
+///
+/// Every item is a block of plaintext, except for the last one, which is as
+/// short as the last block of ciphertext. Since counter mode needs no padding,
+/// each block is yielded as soon as it is decrypted.
+///
+/// If the ciphertext is longer than the keystream allows, the item for the
+/// first block past the [limit](Ctr::with_limit) or the [counter
+/// wraparound](Ctr#counter-wraparound) is the error, and it is the last item.
+/// Unlike [`decrypt`](CipherDecrypt::decrypt), which fails before decrypting
+/// anything, the blocks before it have already been yielded by then.
+pub struct CtrDecryptIter<'a, Enc: BlockEncrypt> {
+    ctr: &'a Ctr<Enc>,
+    blocks: slice::Chunks<'a, u8>,
+    key: Enc::EncryptionKey,
+    /// The position of the next block in the keystream.
+    offset: u64,
+    done: bool,
 }
 
-#[derive(Debug)]
-pub struct BlockSizeTooSmall;
+impl<Enc> Iterator for CtrDecryptIter<'_, Enc>
+where
+    Enc: BlockEncrypt,
+    Enc::EncryptionBlock: IntoIterator<Item = u8> + AsMut<[u8]> + Default + Clone,
+    Enc::EncryptionKey: 'static + Clone,
+{
+    type Item = Result<Vec<u8>, CtrError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        let block = self.blocks.next()?;
+        let end = self.offset + u64::try_from(block.len()).unwrap();
+        let keystream = match self.ctr.keystream_bytes(self.key.clone(), self.offset..end) {
+            Ok(keystream) => keystream,
+            Err(err) => {
+                self.done = true;
+                return Some(Err(err));
+            }
+        };
+        self.offset = end;
+        Some(Ok(OneTimePad::default()
+            .decrypt(block.to_vec(), keystream.into_iter())
+            .expect("keystream as long as the data")))
+    }
+}
 
-impl fmt::Display for BlockSizeTooSmall {
+/// The derived nonce is the initial counter, read as a little-endian number.
+/// The rest of the [counter block](Ctr#counter-block) is kept, and a nonce
+/// which doesn't fit into the width of the counter fails like a [counter
+/// wraparound](Ctr#counter-wraparound).
+impl<Enc> NonceMode for Ctr<Enc>
+where
+    Enc: BlockEncrypt,
+    Enc::EncryptionBlock: IntoIterator<Item = u8> + AsMut<[u8]> + Default + Clone,
+    Enc::EncryptionKey: 'static + Clone,
+{
+    const NONCE_SIZE: usize = mem::size_of::<u64>();
+
+    fn encrypt_with_nonce(
+        &self,
+        data: Vec<u8>,
+        key: Self::Key,
+        nonce: &[u8],
+    ) -> Result<Vec<u8>, Self::EncryptionErr> {
+        let nonce = u64::from_le_bytes(nonce.try_into().expect("nonce of NONCE_SIZE bytes"));
+        let keystream =
+            self.keystream_bytes_from(key, [nonce, 0], 0..u64::try_from(data.len()).unwrap())?;
+        Ok(OneTimePad::default()
+            .encrypt(data, keystream.into_iter())
+            .expect("keystream as long as the data"))
+    }
+
+    fn decrypt_with_nonce(
+        &self,
+        data: Vec<u8>,
+        key: Self::Key,
+        nonce: &[u8],
+    ) -> Result<Vec<u8>, Self::DecryptionErr> {
+        let nonce = u64::from_le_bytes(nonce.try_into().expect("nonce of NONCE_SIZE bytes"));
+        let keystream =
+            self.keystream_bytes_from(key, [nonce, 0], 0..u64::try_from(data.len()).unwrap())?;
+        Ok(OneTimePad::default()
+            .decrypt(data, keystream.into_iter())
+            .expect("keystream as long as the data"))
+    }
+}
+
+/// Read a counter from its bytes in the [counter block](Ctr#counter-block).
+fn read_counter(field: &[u8], position: CounterPosition) -> Counter {
+    let mut bytes = [0; 16];
+    bytes[..field.len()].copy_from_slice(field);
+    if position == CounterPosition::Suffix {
+        bytes[..field.len()].reverse();
+    }
+    let (lo, hi) = bytes.split_at(8);
+    [
+        u64::from_le_bytes(lo.try_into().unwrap()),
+        u64::from_le_bytes(hi.try_into().unwrap()),
+    ]
+}
+
+/// Write a counter into its bytes in the [counter block](Ctr#counter-block).
+/// The counter must fit into the field.
+fn write_counter(field: &mut [u8], position: CounterPosition, counter: Counter) {
+    let bytes = counter.map(u64::to_le_bytes).concat();
+    field.copy_from_slice(&bytes[..field.len()]);
+    if position == CounterPosition::Suffix {
+        field.reverse();
+    }
+}
+
+/// Error returned when configuring [CTR mode](Ctr) with invalid parameters.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CtrConfigError {
+    /// The block of the underlying cipher is too small to fit the counter.
+    BlockTooSmall { block: usize, required: usize },
+    /// The [limit](Ctr::with_limit) is zero, which would make every non-empty
+    /// encryption fail.
+    ZeroLimit,
+}
+
+impl fmt::Display for CtrConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::BlockTooSmall { block, required } => write!(
+                f,
+                "block size of {block} bytes too small to fit {required}-byte counter"
+            ),
+            Self::ZeroLimit => f.write_str("CTR byte limit must not be zero"),
+        }
+    }
+}
+
+impl std::error::Error for CtrConfigError {}
+
+/// Error returned when [CTR mode](Ctr) can't produce enough keystream.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CtrError {
+    /// The message needs `len` bytes of keystream, more than the
+    /// [limit](Ctr::with_limit) allows under one nonce.
+    LimitExceeded { len: u64, limit: u64 },
+    /// The message needs `blocks` keystream blocks, and the counter would
+    /// [wrap around](Ctr#counter-wraparound) when starting from the initial
+    /// counter `nonce`.
+    CounterWrap { nonce: u128, blocks: u64 },
+}
+
+impl fmt::Display for CtrError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        f.write_str("block size too small to fit counter")
+        match self {
+            Self::LimitExceeded { len, limit } => {
+                write!(f, "{len} bytes exceed the limit of {limit} bytes per nonce")
+            }
+            Self::CounterWrap { nonce, blocks } => write!(
+                f,
+                "{blocks} blocks starting at nonce {nonce} would wrap the counter"
+            ),
+        }
     }
 }
+
+impl std::error::Error for CtrError {}
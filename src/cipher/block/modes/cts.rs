@@ -0,0 +1,273 @@
+use {
+    crate::{
+        BlockCipher,
+        BlockDecrypt,
+        BlockEncrypt,
+        BlockMode,
+        Cipher,
+        CipherDecrypt,
+        CipherEncrypt,
+        Csprng,
+    },
+    docext::docext,
+    std::{fmt, mem::size_of},
+};
+
+/// [CBC mode](crate::Cbc) with _ciphertext stealing_, which encrypts messages
+/// of at least one block without padding, so that the ciphertext is exactly as
+/// long as the plaintext. This is the CBC-CS3 variant from the [addendum to
+/// NIST SP 800-38A](https://doi.org/10.6028/NIST.SP.800-38A-Add), which is
+/// also the one used by Kerberos.
+///
+/// Given plaintext $P = P_1 \parallel P_2 \parallel \dots \parallel P_n$, where
+/// the last block $P_n$ has $d$ bytes, $1 \leq d \leq b$ for the block size
+/// $b$, the last block is padded with zeros, and the message is encrypted in
+/// CBC mode:
+///
+/// $$
+/// C_i = E(P_i \oplus C_{i-1}),\\
+/// C_n = E((P_n \parallel 0^{b - d}) \oplus C_{n-1})
+/// $$
+///
+/// Decrypting $C_n$ gives $(P_n \parallel 0^{b - d}) \oplus C_{n-1}$, whose
+/// last $b - d$ bytes are the last $b - d$ bytes of $C_{n-1}$. So those bytes
+/// don't need to be sent: they are _stolen_ from $C_{n-1}$, leaving only its
+/// first $d$ bytes $C_{n-1}^*$. The last two blocks are swapped, so that the
+/// truncated block comes last and the decryption can tell where it starts:
+///
+/// $$
+/// C = C_1 \parallel \dots \parallel C_{n-2} \parallel C_n \parallel C_{n-1}^*
+/// $$
+///
+/// CS3 swaps the last two blocks even when $d = b$ and nothing is stolen. A
+/// message of a single block is encrypted as in plain CBC mode, and a message
+/// shorter than one block can't be encrypted at all, which is reported as
+/// [`CtsTooShort`].
+///
+/// The [IV](crate::Cbc#iv) has the same requirements as in CBC mode, and
+/// [reusing it](crate::Cbc#iv-reuse) leaks the same information.
+#[docext]
+pub struct CbcCts<Cip, Block> {
+    cip: Cip,
+    iv: Block,
+}
+
+impl<Cip, Block> CbcCts<Cip, Block> {
+    pub fn new(cip: Cip, iv: Block) -> Self {
+        Self { cip, iv }
+    }
+}
+
+impl<Cip: BlockCipher> Cipher for CbcCts<Cip, Cip::Block>
+where
+    Cip::Block: for<'a> TryFrom<&'a mut [u8], Error: fmt::Debug>
+        + AsRef<[u8]>
+        + AsMut<[u8]>
+        + IntoIterator<Item = u8>
+        + Clone,
+    Cip::Key: Clone,
+{
+    type Key = Cip::Key;
+
+    /// Generate a key for the underlying block cipher.
+    fn generate_key(rng: &mut impl Csprng) -> Self::Key
+    where
+        Self::Key: Default + AsMut<[u8]>,
+    {
+        Cip::generate_key(rng)
+    }
+}
+
+impl<Cip: BlockCipher> BlockMode for CbcCts<Cip, Cip::Block>
+where
+    Cip::Block: for<'a> TryFrom<&'a mut [u8], Error: fmt::Debug>
+        + AsRef<[u8]>
+        + AsMut<[u8]>
+        + IntoIterator<Item = u8>
+        + Clone,
+    Cip::Key: Clone,
+{
+}
+
+impl<Enc: BlockEncrypt> CipherEncrypt for CbcCts<Enc, Enc::EncryptionBlock>
+where
+    Enc::EncryptionBlock: for<'a> TryFrom<&'a mut [u8], Error: fmt::Debug>
+        + AsRef<[u8]>
+        + AsMut<[u8]>
+        + IntoIterator<Item = u8>
+        + Clone,
+    Enc::EncryptionKey: Clone,
+{
+    type EncryptionErr = CtsTooShort;
+    type EncryptionKey = Enc::EncryptionKey;
+
+    /// Encrypt with the configured IV.
+    fn encrypt(
+        &self,
+        data: Vec<u8>,
+        key: Self::EncryptionKey,
+    ) -> Result<Vec<u8>, Self::EncryptionErr> {
+        self.encrypt_with_iv(data, key, self.iv.clone())
+    }
+
+    fn encrypt_from(
+        &self,
+        data: &[u8],
+        key: Self::EncryptionKey,
+    ) -> Result<Vec<u8>, Self::EncryptionErr> {
+        // Reserve space for the zero padding up front, so that the copy of the
+        // plaintext doesn't have to be reallocated when it gets padded.
+        let mut copy = Vec::with_capacity(data.len() + size_of::<Enc::EncryptionBlock>());
+        copy.extend_from_slice(data);
+        self.encrypt(copy, key)
+    }
+}
+
+impl<Dec: BlockDecrypt> CipherDecrypt for CbcCts<Dec, Dec::DecryptionBlock>
+where
+    Dec::DecryptionBlock: for<'a> TryFrom<&'a mut [u8], Error: fmt::Debug>
+        + AsRef<[u8]>
+        + AsMut<[u8]>
+        + IntoIterator<Item = u8>
+        + Clone,
+    Dec::DecryptionKey: Clone,
+{
+    type DecryptionErr = CtsTooShort;
+    type DecryptionKey = Dec::DecryptionKey;
+
+    fn decrypt(
+        &self,
+        data: Vec<u8>,
+        key: Self::DecryptionKey,
+    ) -> Result<Vec<u8>, Self::DecryptionErr> {
+        self.decrypt_with_iv(data, key, self.iv.clone())
+    }
+}
+
+impl<Enc: BlockEncrypt> CbcCts<Enc, Enc::EncryptionBlock>
+where
+    Enc::EncryptionBlock: for<'a> TryFrom<&'a mut [u8], Error: fmt::Debug>
+        + AsRef<[u8]>
+        + AsMut<[u8]>
+        + IntoIterator<Item = u8>
+        + Clone,
+    Enc::EncryptionKey: Clone,
+{
+    /// Encrypt with the given IV instead of the configured one.
+    pub fn encrypt_with_iv(
+        &self,
+        mut data: Vec<u8>,
+        key: Enc::EncryptionKey,
+        iv: Enc::EncryptionBlock,
+    ) -> Result<Vec<u8>, CtsTooShort> {
+        let block_size = size_of::<Enc::EncryptionBlock>();
+        let len = check_len(data.len(), block_size)?;
+        data.resize(len.next_multiple_of(block_size), 0);
+        let mut prev = iv;
+        // Encrypt the zero-padded blocks in-place, as in CBC mode.
+        for chunk in data.chunks_mut(block_size) {
+            let mut block: Enc::EncryptionBlock = chunk.try_into().unwrap();
+            block
+                .as_mut()
+                .iter_mut()
+                .zip(prev.into_iter())
+                .for_each(|(a, b)| *a ^= b);
+            let ciphertext = self.cip.encrypt(block, key.clone());
+            chunk.copy_from_slice(ciphertext.as_ref());
+            prev = ciphertext;
+        }
+        // Swap the last two blocks, and drop the stolen bytes at the end.
+        if let Some(start) = data.len().checked_sub(2 * block_size) {
+            let (first, second) = data[start..].split_at_mut(block_size);
+            first.swap_with_slice(second);
+        }
+        data.truncate(len);
+        Ok(data)
+    }
+}
+
+impl<Dec: BlockDecrypt> CbcCts<Dec, Dec::DecryptionBlock>
+where
+    Dec::DecryptionBlock: for<'a> TryFrom<&'a mut [u8], Error: fmt::Debug>
+        + AsRef<[u8]>
+        + AsMut<[u8]>
+        + IntoIterator<Item = u8>
+        + Clone,
+    Dec::DecryptionKey: Clone,
+{
+    /// Decrypt with the given IV instead of the configured one, i.e. the IV the
+    /// message was [encrypted](CbcCts::encrypt_with_iv) with.
+    pub fn decrypt_with_iv(
+        &self,
+        mut data: Vec<u8>,
+        key: Dec::DecryptionKey,
+        iv: Dec::DecryptionBlock,
+    ) -> Result<Vec<u8>, CtsTooShort> {
+        let block_size = size_of::<Dec::DecryptionBlock>();
+        let len = check_len(data.len(), block_size)?;
+        let mut last = Vec::new();
+        if let Some(start) = len.next_multiple_of(block_size).checked_sub(2 * block_size) {
+            // Decrypt C_n, which comes right before the truncated C_{n-1}*.
+            let truncated = data.split_off(start + block_size);
+            let block: Dec::DecryptionBlock = (&mut data[start..]).try_into().unwrap();
+            let mut decrypted = self.cip.decrypt(block, key.clone());
+            // The decrypted block is P_n XORed with C_{n-1}, followed by the
+            // bytes stolen from C_{n-1}. Recover P_n, and put C_{n-1} back
+            // together in its place.
+            let decrypted = decrypted.as_mut();
+            last = decrypted
+                .iter()
+                .zip(&truncated)
+                .map(|(a, b)| a ^ b)
+                .collect();
+            decrypted[..truncated.len()].copy_from_slice(&truncated);
+            data[start..].copy_from_slice(decrypted);
+        }
+        let mut prev = iv;
+        // Decrypt the remaining whole blocks in-place, as in CBC mode.
+        for chunk in data.chunks_mut(block_size) {
+            let block: Dec::DecryptionBlock = chunk.try_into().unwrap();
+            let mut plaintext = self.cip.decrypt(block.clone(), key.clone());
+            plaintext
+                .as_mut()
+                .iter_mut()
+                .zip(prev.into_iter())
+                .for_each(|(a, b): (&mut u8, _)| *a ^= b);
+            chunk.copy_from_slice(plaintext.as_ref());
+            prev = block;
+        }
+        data.extend(last);
+        Ok(data)
+    }
+}
+
+/// Check that the message has at least one block to steal ciphertext from.
+fn check_len(len: usize, block_size: usize) -> Result<usize, CtsTooShort> {
+    if len < block_size {
+        Err(CtsTooShort { len, block_size })
+    } else {
+        Ok(len)
+    }
+}
+
+/// Error returned by [CBC-CS3](CbcCts) for a message shorter than one block,
+/// which has no block to steal ciphertext from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CtsTooShort {
+    /// The length of the message in bytes.
+    pub len: usize,
+    /// The block size of the cipher in bytes.
+    pub block_size: usize,
+}
+
+impl fmt::Display for CtsTooShort {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "ciphertext stealing needs at least one block of {} bytes, got {} bytes",
+            self.block_size, self.len
+        )
+    }
+}
+
+impl std::error::Error for CtsTooShort {}
@@ -0,0 +1,234 @@
+use {
+    crate::{Cipher, CipherDecrypt, CipherEncrypt, Csprng, Hkdf, Sha256},
+    docext::docext,
+    std::fmt,
+};
+
+/// Domain separation for the derivation of the per-message key and nonce.
+const DERIVATION_INFO: &[u8] = b"literate-crypto derived nonce v1";
+
+/// A [block mode](crate::BlockMode) whose nonce or IV can be chosen for each
+/// message, rather than being fixed when the mode is created.
+///
+/// This is what [`DerivedNonce`] needs to encrypt every message under its own
+/// derived nonce. The nonce is given as [`NONCE_SIZE`](NonceMode::NONCE_SIZE)
+/// uniformly random bytes, which the mode interprets however it needs, e.g. as
+/// the initial [counter](crate::Ctr) or as the [IV](crate::Cbc#iv).
+pub trait NonceMode: Cipher {
+    /// The number of bytes needed to build a nonce.
+    const NONCE_SIZE: usize;
+
+    /// Encrypt the plaintext, using the given nonce instead of the configured
+    /// one.
+    ///
+    /// # Panics
+    ///
+    /// If the nonce isn't [`NONCE_SIZE`](NonceMode::NONCE_SIZE) bytes long.
+    fn encrypt_with_nonce(
+        &self,
+        data: Vec<u8>,
+        key: Self::Key,
+        nonce: &[u8],
+    ) -> Result<Vec<u8>, Self::EncryptionErr>;
+
+    /// Decrypt the ciphertext, using the given nonce instead of the configured
+    /// one.
+    ///
+    /// # Panics
+    ///
+    /// If the nonce isn't [`NONCE_SIZE`](NonceMode::NONCE_SIZE) bytes long.
+    fn decrypt_with_nonce(
+        &self,
+        data: Vec<u8>,
+        key: Self::Key,
+        nonce: &[u8],
+    ) -> Result<Vec<u8>, Self::DecryptionErr>;
+}
+
+/// Derive the nonce of a [block mode](crate::BlockMode) from a long random
+/// nonce, so that random nonces can be used safely for many messages under
+/// the same key.
+///
+/// A nonce must never repeat under the same key. Choosing nonces at random is
+/// the simplest way to achieve that without keeping state, but by the birthday
+/// bound, among $q$ random $b$-bit nonces some two are equal with probability
+/// about
+///
+/// $$
+/// \frac{q^2}{2^{b + 1}}
+/// $$
+///
+/// For the 64-bit nonce of [CTR mode](crate::Ctr), this is already one in a
+/// million after about six million messages. Worse, in CTR mode two messages
+/// share keystream not only if their nonces are equal, but whenever their
+/// ranges of counters overlap.
+///
+/// # Construction
+///
+/// This wrapper takes an $N$-byte nonce $n$, where $N$ must be at least 16
+/// (128 bits) and defaults to 24 (192 bits), like in XChaCha20. The key $k$
+/// and $n$ are fed into [HKDF](Hkdf) with [HMAC-SHA256](crate::Hmac) to derive
+/// a fresh key $k_n$ for the underlying cipher, along with the nonce $c_n$ of
+/// the underlying mode:
+///
+/// $$
+/// k_n \parallel c_n = \mathrm{HKDF}(k, info \parallel n)
+/// $$
+///
+/// The message $m$ is then encrypted by the underlying mode as usual, and the
+/// long nonce is prepended to the ciphertext so that the recipient can derive
+/// the same $k_n$ and $c_n$:
+///
+/// $$
+/// n \parallel E_{k_n, c_n}(m)
+/// $$
+///
+/// # Security
+///
+/// HKDF is a pseudorandom function of $k$, so as long as $k$ is secret, the
+/// pairs $(k_n, c_n)$ for distinct $n$ look like independent random values.
+/// Every message with a distinct long nonce is therefore encrypted under its
+/// own independent key, and the blocks of keystream of two such messages are
+/// unrelated no matter where their counters start. The only way for two
+/// messages to share keystream is for their long nonces to be equal, which
+/// for $q$ random 192-bit nonces happens with probability about $q^2 /
+/// 2^{193}$. Even after $2^{64}$ messages, this is less than $2^{-64}$.
+///
+/// The derived nonce $c_n$ is still useful: for [CBC mode](crate::Cbc), it is
+/// the unpredictable IV that the mode requires.
+///
+/// The nonce or IV configured on the underlying mode is never used, and this
+/// wrapper doesn't provide authentication. Combine it with a
+/// [MAC](crate::EncryptThenMac) to detect tampering.
+#[docext]
+#[derive(Debug)]
+pub struct DerivedNonce<M, const N: usize = 24> {
+    mode: M,
+    nonce: [u8; N],
+}
+
+impl<M, const N: usize> DerivedNonce<M, N> {
+    /// The long nonce must have at least 128 bits.
+    const VALID_SIZE: () = assert!(N >= 16, "the long nonce must have at least 16 bytes");
+
+    /// Wrap the mode, using the given long nonce for encryption. The nonce
+    /// must be unique for each message encrypted with the same key, so it
+    /// should be [generated](DerivedNonce::generate_nonce) at random.
+    pub fn new(mode: M, nonce: [u8; N]) -> Self {
+        #[allow(clippy::let_unit_value)]
+        let () = Self::VALID_SIZE;
+        Self { mode, nonce }
+    }
+
+    /// Generate a random long nonce using the given source of randomness,
+    /// typically a [CSPRNG](crate::Csprng).
+    pub fn generate_nonce(rng: &mut impl Csprng) -> [u8; N] {
+        let mut nonce = [0; N];
+        rng.fill_bytes(&mut nonce);
+        nonce
+    }
+}
+
+impl<M, const N: usize> DerivedNonce<M, N>
+where
+    M: NonceMode,
+    M::Key: Default + AsRef<[u8]> + AsMut<[u8]>,
+{
+    /// Derive the key and the nonce of the underlying mode.
+    pub(crate) fn derive(key: &M::Key, nonce: &[u8]) -> (M::Key, Vec<u8>) {
+        let mut derived_key = M::Key::default();
+        let key_size = derived_key.as_mut().len();
+        let mut info = DERIVATION_INFO.to_vec();
+        info.extend(nonce);
+        // A key and a nonce are much shorter than the longest HKDF output.
+        let mut okm = Hkdf::new(Sha256::default())
+            .derive(&[], key.as_ref(), &info, key_size + M::NONCE_SIZE)
+            .unwrap();
+        derived_key.as_mut().copy_from_slice(&okm[..key_size]);
+        (derived_key, okm.split_off(key_size))
+    }
+}
+
+impl<M, const N: usize> Cipher for DerivedNonce<M, N>
+where
+    M: NonceMode,
+    M::Key: Default + AsRef<[u8]> + AsMut<[u8]>,
+{
+    type Key = M::Key;
+
+    /// Generate a key for the underlying mode.
+    fn generate_key(rng: &mut impl Csprng) -> Self::Key
+    where
+        Self::Key: Default + AsMut<[u8]>,
+    {
+        M::generate_key(rng)
+    }
+}
+
+impl<M, const N: usize> CipherEncrypt for DerivedNonce<M, N>
+where
+    M: NonceMode,
+    M::Key: Default + AsRef<[u8]> + AsMut<[u8]>,
+{
+    type EncryptionErr = M::EncryptionErr;
+    type EncryptionKey = M::Key;
+
+    fn encrypt(
+        &self,
+        data: Vec<u8>,
+        key: Self::EncryptionKey,
+    ) -> Result<Vec<u8>, Self::EncryptionErr> {
+        let (key, nonce) = Self::derive(&key, &self.nonce);
+        let ciphertext = self.mode.encrypt_with_nonce(data, key, &nonce)?;
+        let mut result = Vec::with_capacity(N + ciphertext.len());
+        result.extend(self.nonce);
+        result.extend(ciphertext);
+        Ok(result)
+    }
+}
+
+impl<M, const N: usize> CipherDecrypt for DerivedNonce<M, N>
+where
+    M: NonceMode,
+    M::Key: Default + AsRef<[u8]> + AsMut<[u8]>,
+{
+    type DecryptionErr = DerivedNonceError<M::DecryptionErr>;
+    type DecryptionKey = M::Key;
+
+    /// Decrypt the ciphertext, using the long nonce at its start. The
+    /// configured nonce is ignored.
+    fn decrypt(
+        &self,
+        mut data: Vec<u8>,
+        key: Self::DecryptionKey,
+    ) -> Result<Vec<u8>, Self::DecryptionErr> {
+        if data.len() < N {
+            return Err(DerivedNonceError::MissingNonce);
+        }
+        let ciphertext = data.split_off(N);
+        let (key, nonce) = Self::derive(&key, &data);
+        self.mode
+            .decrypt_with_nonce(ciphertext, key, &nonce)
+            .map_err(DerivedNonceError::Mode)
+    }
+}
+
+/// Error returned when decrypting with a [`DerivedNonce`] fails.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DerivedNonceError<E> {
+    /// The ciphertext is shorter than the long nonce it should start with.
+    MissingNonce,
+    /// The underlying mode failed to decrypt.
+    Mode(E),
+}
+
+impl<E: fmt::Display> fmt::Display for DerivedNonceError<E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::MissingNonce => f.write_str("ciphertext too short to contain the nonce"),
+            Self::Mode(e) => e.fmt(f),
+        }
+    }
+}
+
+impl<E: fmt::Debug + fmt::Display> std::error::Error for DerivedNonceError<E> {}
@@ -7,6 +7,7 @@ use {
         Cipher,
         CipherDecrypt,
         CipherEncrypt,
+        Csprng,
         Padding,
     },
     std::{convert::Infallible, fmt},
@@ -44,6 +45,14 @@ where
     Cip::Key: Clone,
 {
     type Key = Cip::Key;
+
+    /// Generate a key for the underlying block cipher.
+    fn generate_key(rng: &mut impl Csprng) -> Self::Key
+    where
+        Self::Key: Default + AsMut<[u8]>,
+    {
+        Cip::generate_key(rng)
+    }
 }
 
 impl<Cip: BlockCipher, Pad: Padding> BlockMode for Ecb<Cip, Pad>
@@ -68,6 +77,7 @@ where
     ) -> Result<Vec<u8>, Self::EncryptionErr> {
         // Encrypt the blocks in-place, using the input vector.
         let block_size = std::mem::size_of::<Enc::EncryptionBlock>();
+        // The padded data is made of whole blocks.
         let mut data = self.pad.pad(data, block_size);
         for chunk in data.chunks_mut(block_size) {
             let block = chunk.try_into().unwrap();
@@ -75,6 +85,18 @@ where
         }
         Ok(data)
     }
+
+    fn encrypt_from(
+        &self,
+        data: &[u8],
+        key: Self::EncryptionKey,
+    ) -> Result<Vec<u8>, Self::EncryptionErr> {
+        // Reserve space for the padding up front, so that the copy of the
+        // plaintext doesn't have to be reallocated when it gets padded.
+        let mut copy = Vec::with_capacity(data.len() + std::mem::size_of::<Enc::EncryptionBlock>());
+        copy.extend_from_slice(data);
+        self.encrypt(copy, key)
+    }
 }
 
 impl<Dec: BlockDecrypt, Pad: Padding> CipherDecrypt for Ecb<Dec, Pad>
@@ -92,6 +114,11 @@ where
     ) -> Result<Vec<u8>, Self::DecryptionErr> {
         // Decrypt the blocks in-place, using the input vector.
         let block_size = std::mem::size_of::<Dec::DecryptionBlock>();
+        if data.len() % block_size != 0 {
+            // The ciphertext is not made of whole blocks, so it can't be padded
+            // correctly, and the padding reports the error.
+            return self.pad.unpad(data, block_size);
+        }
         for chunk in data.chunks_mut(block_size) {
             let block = chunk.try_into().unwrap();
             chunk.copy_from_slice(self.cip.decrypt(block, key.clone()).as_ref());
@@ -0,0 +1,212 @@
+use {
+    crate::{util, BlockEncrypt, CounterPosition, CounterWidth, Ctr, CtrError},
+    docext::docext,
+    std::fmt,
+};
+
+/// Galois/Counter Mode, an authenticated [mode of
+/// operation](crate::BlockMode) for block ciphers with 128-bit blocks, as
+/// specified in [NIST SP 800-38D](https://doi.org/10.6028/NIST.SP.800-38D).
+///
+/// [CTR mode](Ctr) hides the plaintext, but it doesn't stop an attacker from
+/// changing it: flipping a bit of the ciphertext flips the same bit of the
+/// plaintext. GCM encrypts with CTR mode and additionally computes a
+/// [tag](GcmTag) over the ciphertext, which the receiver checks before
+/// decrypting anything. The tag also covers _additional authenticated data_
+/// (AAD), which is sent in the clear, e.g. the header of a packet.
+///
+/// # Counter Block
+///
+/// GCM uses a 96-bit nonce. The initial counter block $J_0$ is the nonce
+/// followed by a 32-bit big-endian counter set to $1$. The keystream for the
+/// data starts at the next counter block, and $E_K(J_0)$ is reserved for
+/// masking the tag. Since the counter is 32 bits wide, a single message can be
+/// at most $2^{32} - 2$ blocks long.
+///
+/// # Tag
+///
+/// The tag is computed with [GHASH](ghash), a polynomial evaluated in
+/// $GF(2^{128})$ at the hash key $H = E_K(0^{128})$:
+/// $$
+/// T = E_K(J_0) \oplus \operatorname{GHASH}_H(A, C)
+/// $$
+/// where $A$ is the AAD and $C$ the ciphertext.
+///
+/// # Nonce Reuse
+///
+/// A `Gcm` is bound to a single nonce, and a nonce must never be used twice
+/// with the same key. Besides repeating the keystream like [CTR mode](Ctr),
+/// two messages under the same nonce leak enough to recover $H$, after which
+/// the attacker can forge tags for any message.
+#[docext]
+pub struct Gcm<Enc: BlockEncrypt> {
+    ctr: Ctr<Enc>,
+}
+
+/// The 128-bit authentication tag of [GCM](Gcm).
+pub type GcmTag = [u8; 16];
+
+/// The size of the GCM nonce in bytes.
+const NONCE_SIZE: usize = 12;
+
+/// The size of a GCM block in bytes.
+const BLOCK_SIZE: u64 = 16;
+
+impl<Enc> Gcm<Enc>
+where
+    Enc: BlockEncrypt<EncryptionBlock = [u8; 16]>,
+    Enc::EncryptionKey: Clone,
+{
+    /// Use the given 96-bit nonce, see [counter block](Gcm#counter-block).
+    pub fn new(enc: Enc, nonce: [u8; NONCE_SIZE]) -> Self {
+        let mut j0 = [0; 16];
+        j0[..NONCE_SIZE].copy_from_slice(&nonce);
+        j0[15] = 1;
+        let ctr = Ctr::with_counter_block(enc, j0, CounterWidth::Bits32, CounterPosition::Suffix)
+            .expect("32-bit counter fits into a 128-bit block");
+        Self { ctr }
+    }
+
+    /// Encrypt the plaintext and compute the tag over the ciphertext and the
+    /// AAD. Fails if the plaintext is too long for the [32-bit
+    /// counter](Gcm#counter-block).
+    pub fn encrypt(
+        &self,
+        plaintext: &[u8],
+        aad: &[u8],
+        key: Enc::EncryptionKey,
+    ) -> Result<(Vec<u8>, GcmTag), CtrError> {
+        let keystream = self.keystream(key.clone(), plaintext.len())?;
+        let ciphertext: Vec<u8> = plaintext
+            .iter()
+            .zip(&keystream[BLOCK_SIZE as usize..])
+            .map(|(p, k)| p ^ k)
+            .collect();
+        let tag = self.tag(key, &keystream, aad, &ciphertext);
+        Ok((ciphertext, tag))
+    }
+
+    /// Check the tag and decrypt the ciphertext. Nothing is decrypted unless
+    /// the tag is valid.
+    pub fn decrypt(
+        &self,
+        ciphertext: &[u8],
+        aad: &[u8],
+        tag: &GcmTag,
+        key: Enc::EncryptionKey,
+    ) -> Result<Vec<u8>, AuthenticationFailed> {
+        // A ciphertext too long for the counter can't have been produced by
+        // encrypt, so it can't be authentic either.
+        let keystream = self
+            .keystream(key.clone(), ciphertext.len())
+            .map_err(|_| AuthenticationFailed)?;
+        if !util::tags_equal(&self.tag(key, &keystream, aad, ciphertext), tag) {
+            return Err(AuthenticationFailed);
+        }
+        Ok(ciphertext
+            .iter()
+            .zip(&keystream[BLOCK_SIZE as usize..])
+            .map(|(c, k)| c ^ k)
+            .collect())
+    }
+
+    /// The keystream for a message of the given length, starting with the
+    /// block which masks the [tag](Gcm#tag).
+    fn keystream(&self, key: Enc::EncryptionKey, len: usize) -> Result<Vec<u8>, CtrError> {
+        self.ctr
+            .keystream_bytes(key, 0..BLOCK_SIZE + u64::try_from(len).unwrap())
+    }
+
+    /// Compute the [tag](Gcm#tag), using the tag mask at the start of the
+    /// keystream.
+    fn tag(
+        &self,
+        key: Enc::EncryptionKey,
+        keystream: &[u8],
+        aad: &[u8],
+        ciphertext: &[u8],
+    ) -> GcmTag {
+        let h = u128::from_be_bytes(self.ctr.enc().encrypt([0; 16], key));
+        let mask = u128::from_be_bytes(keystream[..16].try_into().unwrap());
+        (ghash(h, aad, ciphertext) ^ mask).to_be_bytes()
+    }
+}
+
+impl<Enc: BlockEncrypt + Clone> Clone for Gcm<Enc> {
+    fn clone(&self) -> Self {
+        Self {
+            ctr: self.ctr.clone(),
+        }
+    }
+}
+
+impl<Enc: BlockEncrypt + fmt::Debug> fmt::Debug for Gcm<Enc> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Gcm").field("ctr", &self.ctr).finish()
+    }
+}
+
+/// The GHASH function of [GCM](Gcm), keyed with the hash key $H$.
+///
+/// The AAD and the ciphertext are each padded with zeros to a multiple of 16
+/// bytes and split into blocks $X_1, \dots, X_m$, followed by a final block
+/// holding the bit lengths of the AAD and the ciphertext as 64-bit big-endian
+/// numbers. The result is
+/// $$
+/// Y_m = (Y_{m-1} \oplus X_m) \cdot H, \quad Y_0 = 0
+/// $$
+/// which is the polynomial $X_1 H^m \oplus X_2 H^{m-1} \oplus \dots \oplus X_m
+/// H$ in $GF(2^{128})$, see [`gf128_mul`].
+#[docext]
+pub fn ghash(h: u128, aad: &[u8], ciphertext: &[u8]) -> u128 {
+    let mut y = 0;
+    for data in [aad, ciphertext] {
+        for chunk in data.chunks(16) {
+            let mut block = [0; 16];
+            block[..chunk.len()].copy_from_slice(chunk);
+            y = gf128_mul(y ^ u128::from_be_bytes(block), h);
+        }
+    }
+    let bits = |data: &[u8]| u128::try_from(data.len()).unwrap() * 8;
+    gf128_mul(y ^ (bits(aad) << 64 | bits(ciphertext)), h)
+}
+
+/// Multiply two elements of $GF(2^{128})$, the field used by [GHASH](ghash).
+///
+/// The elements are polynomials of degree less than $128$ with coefficients in
+/// $GF(2)$, multiplied modulo $x^{128} + x^7 + x^2 + x + 1$. GCM stores the
+/// coefficients in reflected order: the _most_ significant bit of the
+/// big-endian block is the coefficient of $x^0$. In this order, multiplying by
+/// $x$ is a right shift, and reducing the $x^{128}$ term that falls off the
+/// end means XORing in $R = \texttt{0xe1} \ll 120$, the reflected low terms
+/// of the modulus.
+///
+/// The multiplication runs through all $128$ bits of `x`, and selects what to
+/// add with masks instead of branches, so that its timing doesn't depend on
+/// the hash key or the data.
+#[docext]
+pub fn gf128_mul(x: u128, y: u128) -> u128 {
+    const R: u128 = 0xe1 << 120;
+    let mut z = 0;
+    let mut v = y;
+    for i in (0..128).rev() {
+        // Add v if the coefficient of x^(127 - i) in x is set.
+        z ^= v & ((x >> i) & 1).wrapping_neg();
+        // Multiply v by x, reducing if the coefficient of x^127 was set.
+        v = (v >> 1) ^ (R & (v & 1).wrapping_neg());
+    }
+    z
+}
+
+/// Error returned when [GCM](Gcm) decryption finds that the ciphertext, the
+/// AAD or the tag were changed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AuthenticationFailed;
+
+impl fmt::Display for AuthenticationFailed {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "authentication failed")
+    }
+}
+
+impl std::error::Error for AuthenticationFailed {}
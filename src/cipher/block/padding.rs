@@ -1,3 +1,5 @@
+use std::sync::Arc;
+
 mod pkcs7;
 
 pub use pkcs7::Pkcs7;
@@ -30,5 +32,46 @@ pub trait Padding {
 
     /// Remove the padding from the input data. Return an error if the padding
     /// is invalid.
+    ///
+    /// Data whose length is not a multiple of `n` is never validly padded, and
+    /// must be rejected with an error, not a panic. The block modes rely on
+    /// this to reject ciphertexts which aren't made of whole blocks.
     fn unpad(&self, data: Vec<u8>, n: usize) -> Result<Vec<u8>, Self::Err>;
 }
+
+/// Forward to the shared [padding scheme](Padding).
+impl<T: Padding + ?Sized> Padding for &T {
+    type Err = T::Err;
+
+    fn pad(&self, data: Vec<u8>, n: usize) -> Vec<u8> {
+        T::pad(self, data, n)
+    }
+
+    fn unpad(&self, data: Vec<u8>, n: usize) -> Result<Vec<u8>, Self::Err> {
+        T::unpad(self, data, n)
+    }
+}
+
+impl<T: Padding + ?Sized> Padding for Box<T> {
+    type Err = T::Err;
+
+    fn pad(&self, data: Vec<u8>, n: usize) -> Vec<u8> {
+        T::pad(self, data, n)
+    }
+
+    fn unpad(&self, data: Vec<u8>, n: usize) -> Result<Vec<u8>, Self::Err> {
+        T::unpad(self, data, n)
+    }
+}
+
+impl<T: Padding + ?Sized> Padding for Arc<T> {
+    type Err = T::Err;
+
+    fn pad(&self, data: Vec<u8>, n: usize) -> Vec<u8> {
+        T::pad(self, data, n)
+    }
+
+    fn unpad(&self, data: Vec<u8>, n: usize) -> Result<Vec<u8>, Self::Err> {
+        T::unpad(self, data, n)
+    }
+}
@@ -28,6 +28,8 @@ impl Padding for Pkcs7 {
         // padding is needed.
         let m = if m == 0 { n } else { m };
         // Add the padding.
+        // The padding length is at most n, which is below 256, so it fits in a byte.
+        debug_assert!(m <= n);
         data.resize(data.len() + m, m.try_into().unwrap());
         data
     }
@@ -37,17 +39,16 @@ impl Padding for Pkcs7 {
             panic!("Pkcs7 does not work for block sizes >= 256");
         }
 
-        let m: usize = data
-            .last()
-            .ok_or(InvalidPadding)?
-            .to_owned()
-            .try_into()
-            .unwrap();
+        // Padded data always consists of whole blocks.
+        if data.len() % n != 0 {
+            return Err(InvalidPadding);
+        }
+        let m: usize = data.last().ok_or(InvalidPadding)?.to_owned().into();
         if m == 0 || m > n {
             return Err(InvalidPadding);
         }
         let padding = &data[data.len() - m..];
-        if !padding.iter().all(|&b| usize::try_from(b).unwrap() == m) {
+        if !padding.iter().all(|&b| usize::from(b) == m) {
             return Err(InvalidPadding);
         }
         data.truncate(data.len() - m);
@@ -0,0 +1,21 @@
+//! Worked examples of implementing the traits of this crate.
+//!
+//! The primitives in this crate are built out of small traits, and every
+//! construction only relies on the traits, never on the concrete types. A
+//! [mode of operation](crate::BlockMode) works with any [block
+//! cipher](crate::BlockCipher), the [Merkle-Damgard
+//! construction](crate::MerkleDamgard) works with any [compression
+//! function](crate::CompressionFn), and so on. The best way to understand a
+//! trait is therefore to implement it and plug the implementation into the
+//! existing constructions.
+//!
+//! Each example is a complete program, compiled and run as a test, so the
+//! examples are guaranteed to keep working as the crate changes. None of the
+//! implementations are secure: they are as small as possible, to show what
+//! the traits require rather than how to build a good primitive.
+
+pub mod block_cipher;
+pub mod compression;
+pub mod curve;
+pub mod entropy;
+pub mod padding;
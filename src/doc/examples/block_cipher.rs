@@ -0,0 +1,81 @@
+//! A custom [block cipher](crate::BlockCipher).
+//!
+//! A block cipher consists of two halves, [encryption](crate::BlockEncrypt)
+//! and [decryption](crate::BlockDecrypt), which must be inverses of each other
+//! for any key. The [`BlockCipher`](crate::BlockCipher) trait ties the two
+//! halves together, requiring that they agree on the block and key types.
+//! Some constructions only need one half: [CTR mode](crate::Ctr) and
+//! [Fortuna](crate::Fortuna) only ever encrypt, so they only require
+//! [`BlockEncrypt`](crate::BlockEncrypt).
+//!
+//! The simplest block cipher XORs the block with the key. It is trivially
+//! broken, since XORing a plaintext block with its ciphertext block gives away
+//! the key, but it satisfies the contract of the traits and is handy for
+//! checking the modes of operation by hand.
+//!
+//! ```
+//! use literate_crypto::{
+//!     BlockCipher,
+//!     BlockDecrypt,
+//!     BlockEncrypt,
+//!     Cbc,
+//!     CipherDecrypt,
+//!     CipherEncrypt,
+//!     Ctr,
+//!     Ecb,
+//!     Pkcs7,
+//! };
+//!
+//! /// A block cipher which XORs 8-byte blocks with the key.
+//! #[derive(Debug, Clone, Copy)]
+//! struct XorCipher;
+//!
+//! impl BlockEncrypt for XorCipher {
+//!     type EncryptionBlock = [u8; 8];
+//!     type EncryptionKey = [u8; 8];
+//!
+//!     fn encrypt(&self, data: [u8; 8], key: [u8; 8]) -> [u8; 8] {
+//!         std::array::from_fn(|i| data[i] ^ key[i])
+//!     }
+//! }
+//!
+//! impl BlockDecrypt for XorCipher {
+//!     type DecryptionBlock = [u8; 8];
+//!     type DecryptionKey = [u8; 8];
+//!
+//!     fn decrypt(&self, data: [u8; 8], key: [u8; 8]) -> [u8; 8] {
+//!         // XOR is its own inverse.
+//!         std::array::from_fn(|i| data[i] ^ key[i])
+//!     }
+//! }
+//!
+//! impl BlockCipher for XorCipher {
+//!     type Block = [u8; 8];
+//!     type Key = [u8; 8];
+//! }
+//!
+//! let key = [0x55; 8];
+//! let block = *b"abcdefgh";
+//! assert_eq!(XorCipher.decrypt(XorCipher.encrypt(block, key), key), block);
+//!
+//! // The cipher works with every mode of operation.
+//! let msg = b"a message longer than one block".to_vec();
+//!
+//! let ecb = Ecb::new(XorCipher, Pkcs7::default());
+//! let ciphertext = ecb.encrypt(msg.clone(), key).unwrap();
+//! assert_eq!(ecb.decrypt(ciphertext, key).unwrap(), msg);
+//!
+//! let cbc = Cbc::new(XorCipher, Pkcs7::default(), [1; 8]);
+//! let ciphertext = cbc.encrypt(msg.clone(), key).unwrap();
+//! assert_eq!(cbc.decrypt(ciphertext, key).unwrap(), msg);
+//!
+//! // An 8-byte block is just large enough for the 64-bit counter of CTR mode.
+//! let ctr = Ctr::new(XorCipher, 0).unwrap();
+//! let ciphertext = ctr.encrypt(msg.clone(), key).unwrap();
+//! assert_eq!(ctr.decrypt(ciphertext, key).unwrap(), msg);
+//!
+//! // The weakness of the cipher shows in ECB mode: equal plaintext blocks
+//! // give equal ciphertext blocks.
+//! let ciphertext = ecb.encrypt([block, block].concat(), key).unwrap();
+//! assert_eq!(ciphertext[..8], ciphertext[8..16]);
+//! ```
@@ -0,0 +1,55 @@
+//! A custom [compression function](crate::CompressionFn).
+//!
+//! The [Merkle-Damgard construction](crate::MerkleDamgard) turns a function
+//! which compresses a fixed-size block into a fixed-size state into a hash
+//! function for messages of any length. Only the compression function and the
+//! [padding](crate::MerkleDamgardPad) are specific to a hash function; the
+//! construction takes care of splitting the message, chaining the states, and
+//! [incremental hashing](crate::IncrementalHash).
+//!
+//! The compression function below mixes each 32-bit word of the block into a
+//! 64-bit state with a multiplication and a rotation. It is far too simple to
+//! be collision resistant, but with the [SHA-2 length
+//! padding](crate::sha2::LengthPadding) it yields a working hash function
+//! with 512-bit blocks and 8-byte digests.
+//!
+//! ```
+//! use literate_crypto::{
+//!     sha2::LengthPadding,
+//!     CompressionFn,
+//!     Hash,
+//!     IncrementalHash,
+//!     MerkleDamgard,
+//! };
+//!
+//! /// A toy compression function with a 64-bit state.
+//! #[derive(Debug)]
+//! struct MulRotate;
+//!
+//! impl CompressionFn for MulRotate {
+//!     type Block = [u8; 64];
+//!     type State = [u8; 8];
+//!
+//!     fn compress(&self, state: [u8; 8], block: &[u8; 64]) -> [u8; 8] {
+//!         let mut h = u64::from_be_bytes(state);
+//!         for word in block.chunks_exact(4) {
+//!             h ^= u64::from(u32::from_be_bytes(word.try_into().unwrap()));
+//!             h = h.wrapping_mul(0x9e37_79b9_7f4a_7c15).rotate_left(31);
+//!         }
+//!         h.to_be_bytes()
+//!     }
+//! }
+//!
+//! // The initial state is an arbitrary constant.
+//! let hash = MerkleDamgard::new(MulRotate, LengthPadding::default(), *b"literate");
+//!
+//! let digest = hash.hash(b"hello world");
+//! assert_eq!(digest.len(), 8);
+//! assert_ne!(digest, hash.hash(b"hello world!"));
+//!
+//! // Incremental hashing comes for free with the construction.
+//! let mut state = hash.init();
+//! hash.update(&mut state, b"hello ");
+//! hash.update(&mut state, b"world");
+//! assert_eq!(hash.finalize(state), digest);
+//! ```
@@ -0,0 +1,68 @@
+//! A custom [elliptic curve](crate::ecc::Curve).
+//!
+//! A curve is defined by a handful of constants: the order $P$ of the prime
+//! field, the coefficients $a$ and $b$ of the equation $y^2 = x^3 + ax + b$,
+//! a generator point $G$, and its order $N$. Everything else, from point
+//! arithmetic to the signature schemes, is generic over the curve.
+//!
+//! The toy curve below is $y^2 = x^3 + 6$ over the field of $P = 65437$
+//! elements. It has $N = 65929$ points, a prime number, so every point other
+//! than $\infty$ generates the whole curve, and the cofactor is one. The
+//! constants were found by counting the points of $y^2 = x^3 + b$ for small
+//! $b$ until the count came out prime, which is feasible because the field is
+//! tiny. For the same reason, the discrete logarithm on this curve can be
+//! brute-forced in an instant, so the curve is only good for experiments.
+//!
+//! ```
+//! use literate_crypto::{
+//!     ecc::{Curve, Num, Point, PrivateKey},
+//!     Aes128,
+//!     Ecdsa,
+//!     Ecies,
+//!     Fortuna,
+//!     Schnorr,
+//!     Sha256,
+//!     SignatureScheme,
+//! };
+//!
+//! #[derive(Debug, Default)]
+//! struct Toy;
+//!
+//! impl Curve for Toy {
+//!     // N is a 17-bit number, so it takes three bytes.
+//!     const SIZE: usize = 3;
+//!     const P: Num = Num::from_le_words([65437, 0, 0, 0]);
+//!     const N: Num = Num::from_le_words([65929, 0, 0, 0]);
+//!     const A: Num = Num::ZERO;
+//!     const B: Num = Num::from_le_words([6, 0, 0, 0]);
+//!
+//!     fn g() -> Point<Self> {
+//!         Point::new(Num::ONE, Num::from_le_words([8488, 0, 0, 0])).unwrap()
+//!     }
+//! }
+//!
+//! // Check the constants for typos.
+//! assert_eq!(Toy::validate(), Ok(()));
+//!
+//! // The group law holds: N * G is the point at infinity.
+//! assert_eq!(Toy::N * Toy::g(), Point::infinity());
+//!
+//! // Signatures work on any curve.
+//! let key = PrivateKey::<Toy>::new(Num::from_le_words([1234, 0, 0, 0])).unwrap();
+//! let mut ecdsa = Ecdsa::new(Toy, Sha256::default());
+//! let sig = ecdsa.sign(key.clone(), b"hello");
+//! assert!(ecdsa.verify(key.derive(), b"hello", &sig).is_ok());
+//! assert!(ecdsa.verify(key.derive(), b"goodbye", &sig).is_err());
+//!
+//! let mut schnorr = Schnorr::new(Toy, Sha256::default());
+//! for i in 0..4u8 {
+//!     let sig = schnorr.sign(key.clone(), &[i]);
+//!     assert!(schnorr.verify(key.derive(), &[i], &sig).is_ok());
+//! }
+//!
+//! // So does encryption.
+//! let mut ecies = Ecies::<_, _, Aes128>::new(Toy, Sha256::default());
+//! let mut rng = Fortuna::from_os();
+//! let ciphertext = ecies.encrypt(key.derive(), b"attack at dawn", &mut rng);
+//! assert_eq!(ecies.decrypt(key, &ciphertext).unwrap(), b"attack at dawn");
+//! ```
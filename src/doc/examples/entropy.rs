@@ -0,0 +1,66 @@
+//! A custom [source of entropy](crate::Entropy).
+//!
+//! An entropy source only has to fill a buffer with unpredictable bytes. On
+//! Unix-like systems, the operating system exposes its entropy pool as the
+//! file `/dev/urandom`, so any [reader](std::io::Read) makes a natural entropy
+//! source. The same source can replay a fixed recording of bytes, which makes
+//! randomized code reproducible in tests.
+//!
+//! The source is then plugged into a [CSPRNG](crate::Csprng) such as
+//! [Fortuna](crate::Fortuna), which stretches the entropy into an endless
+//! stream of random bytes, or combined with other sources in an
+//! [`EntropyPool`](crate::EntropyPool).
+//!
+//! ```
+//! use {
+//!     literate_crypto::{Aes256, Entropy, EntropyPool, Fortuna, Sha256},
+//!     std::io::Read,
+//! };
+//!
+//! /// Entropy read from any source of bytes, e.g. `/dev/urandom`.
+//! struct ReaderEntropy<R>(R);
+//!
+//! impl<R: Read> Entropy for ReaderEntropy<R> {
+//!     fn get(&mut self, buf: &mut [u8]) {
+//!         // An entropy source must never silently return predictable bytes,
+//!         // so running out of input is a hard error.
+//!         self.0.read_exact(buf).expect("entropy source exhausted");
+//!     }
+//! }
+//!
+//! // Replay a recorded stream of bytes.
+//! let recording: Vec<u8> = (0..=255).collect();
+//! let mut entropy = ReaderEntropy(recording.as_slice());
+//! let mut buf = [0; 4];
+//! entropy.get(&mut buf);
+//! assert_eq!(buf, [0, 1, 2, 3]);
+//!
+//! // The same recording always produces the same random stream. Fortuna
+//! // draws 32 bytes of entropy for every 2 KiB of output.
+//! let stream = |recording: &[u8]| -> Vec<u8> {
+//!     Fortuna::new(
+//!         ReaderEntropy(recording),
+//!         Aes256::default(),
+//!         Sha256::default(),
+//!     )
+//!     .unwrap()
+//!     .into_iter()
+//!     .take(32)
+//!     .collect()
+//! };
+//! assert_eq!(stream(&recording), stream(&recording));
+//! assert_ne!(stream(&recording), stream(&[0; 32]));
+//!
+//! // On Unix, the operating system provides the entropy.
+//! #[cfg(unix)]
+//! {
+//!     let urandom = std::fs::File::open("/dev/urandom").unwrap();
+//!     let pool = EntropyPool::new().add_source(Box::new(ReaderEntropy(urandom)), 1);
+//!     let random: Vec<u8> = Fortuna::new(pool, Aes256::default(), Sha256::default())
+//!         .unwrap()
+//!         .into_iter()
+//!         .take(16)
+//!         .collect();
+//!     assert_eq!(random.len(), 16);
+//! }
+//! ```
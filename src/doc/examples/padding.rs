@@ -0,0 +1,67 @@
+//! A custom [padding scheme](crate::Padding).
+//!
+//! ISO/IEC 7816-4 padding, also known as _bit padding_, appends a single byte
+//! `80` (a one bit followed by zero bits) and then as many zero bytes as
+//! needed to reach a multiple of the block size. Unlike [PKCS#7](crate::Pkcs7),
+//! the padding doesn't encode its own length. Instead, it is removed by
+//! stripping the trailing zeros and then the `80` byte. At least one byte of
+//! padding is always added, otherwise a message ending in `80 00` would lose
+//! its last bytes when unpadded.
+//!
+//! Like any padding scheme, bit padding must not leak whether the padding of a
+//! decrypted message was valid, see [padding oracle
+//! attacks](crate::Padding).
+//!
+//! ```
+//! use literate_crypto::{Aes128, Cbc, CipherDecrypt, CipherEncrypt, Padding};
+//!
+//! /// ISO/IEC 7816-4 bit padding.
+//! struct BitPadding;
+//!
+//! /// Error returned when the padding is invalid.
+//! #[derive(Debug)]
+//! struct InvalidPadding;
+//!
+//! impl Padding for BitPadding {
+//!     type Err = InvalidPadding;
+//!
+//!     fn pad(&self, mut data: Vec<u8>, n: usize) -> Vec<u8> {
+//!         data.push(0x80);
+//!         data.resize(data.len().next_multiple_of(n), 0);
+//!         data
+//!     }
+//!
+//!     fn unpad(&self, mut data: Vec<u8>, n: usize) -> Result<Vec<u8>, Self::Err> {
+//!         if data.is_empty() || data.len() % n != 0 {
+//!             return Err(InvalidPadding);
+//!         }
+//!         // The padding lies entirely within the last block.
+//!         let last_block = data.len() - n;
+//!         let marker = data[last_block..]
+//!             .iter()
+//!             .rposition(|&b| b != 0)
+//!             .map(|i| last_block + i)
+//!             .ok_or(InvalidPadding)?;
+//!         if data[marker] != 0x80 {
+//!             return Err(InvalidPadding);
+//!         }
+//!         data.truncate(marker);
+//!         Ok(data)
+//!     }
+//! }
+//!
+//! assert_eq!(BitPadding.pad(vec![1, 2, 3], 4), [1, 2, 3, 0x80]);
+//! assert_eq!(
+//!     BitPadding.pad(vec![1, 2, 3, 4], 4),
+//!     [1, 2, 3, 4, 0x80, 0, 0, 0]
+//! );
+//! assert_eq!(BitPadding.unpad(vec![1, 0x80, 0, 0], 4).unwrap(), [1]);
+//! assert!(BitPadding.unpad(vec![1, 2, 0, 0], 4).is_err());
+//!
+//! // The padding scheme can be used with any block mode which needs padding.
+//! let key = [7; 16];
+//! let cbc = Cbc::new(Aes128::default(), BitPadding, [0; 16]);
+//! let ciphertext = cbc.encrypt(b"attack at dawn".to_vec(), key).unwrap();
+//! assert_eq!(ciphertext.len(), 16);
+//! assert_eq!(cbc.decrypt(ciphertext, key).unwrap(), b"attack at dawn");
+//! ```
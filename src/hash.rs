@@ -1,13 +1,29 @@
-use docext::docext;
+use {docext::docext, std::sync::Arc};
 
+mod adapters;
 mod merkledamgard;
+mod merkletree;
+pub mod sha1cd;
 pub mod sha2;
 pub mod sha3;
+pub mod sm3;
 
 pub use {
-    merkledamgard::{CompressionFn, DaviesMeyer, DaviesMeyerStep, MerkleDamgard, MerkleDamgardPad},
-    sha2::{Sha1, Sha224, Sha256},
-    sha3::{Sha3_224, Sha3_256, Sha3_384, Sha3_512},
+    adapters::{StdHasher, WriteHasher},
+    merkledamgard::{
+        CompressionFn,
+        DaviesMeyer,
+        DaviesMeyerStep,
+        MerkleDamgard,
+        MerkleDamgardPad,
+        MerkleDamgardState,
+        MerkleDamgardTrace,
+    },
+    merkletree::{hash_chunked_parallel, MerkleTree},
+    sha1cd::Sha1Cd,
+    sha2::{Sha1, Sha224, Sha256, Sha384, Sha512, Sha512_224, Sha512_256},
+    sha3::{Keccak256, Keccak512, Sha3_224, Sha3_256, Sha3_384, Sha3_512},
+    sm3::Sm3,
 };
 
 /// A cryptographic hash function maps a _preimage_ of arbitrary length into a
@@ -34,3 +50,74 @@ pub trait Hash {
 
     fn hash(&self, preimage: &[u8]) -> Self::Digest;
 }
+
+/// A shared [hash function](Hash) is still a hash function, so that one
+/// instance can be passed to several constructions at once, e.g. to an
+/// [HMAC](crate::Hmac) and to a [signature scheme](crate::SignatureScheme).
+impl<T: Hash + ?Sized> Hash for &T {
+    type Digest = T::Digest;
+    type Block = T::Block;
+
+    fn hash(&self, preimage: &[u8]) -> Self::Digest {
+        T::hash(self, preimage)
+    }
+}
+
+impl<T: Hash + ?Sized> Hash for Box<T> {
+    type Digest = T::Digest;
+    type Block = T::Block;
+
+    fn hash(&self, preimage: &[u8]) -> Self::Digest {
+        T::hash(self, preimage)
+    }
+}
+
+impl<T: Hash + ?Sized> Hash for Arc<T> {
+    type Digest = T::Digest;
+    type Block = T::Block;
+
+    fn hash(&self, preimage: &[u8]) -> Self::Digest {
+        T::hash(self, preimage)
+    }
+}
+
+/// A [hash function](Hash) which can process its preimage in pieces.
+///
+/// This is useful when the preimage doesn't fit into memory, or arrives over
+/// time, e.g. from a file or a network connection. Hashing proceeds in three
+/// steps: [`init`](IncrementalHash::init) creates an empty state,
+/// [`update`](IncrementalHash::update) feeds the next piece of the preimage
+/// into the state, and [`finalize`](IncrementalHash::finalize) computes the
+/// digest. The digest must be equal to the [one-shot](Hash::hash) digest of
+/// all pieces concatenated, regardless of how the preimage was split.
+///
+/// The state is separate from the hash function itself, so the same hash
+/// function can be used to hash many preimages at once. Cloning the state
+/// makes it possible to compute the digest of a prefix and keep hashing.
+pub trait IncrementalHash: Hash {
+    type State: Clone;
+
+    /// Create the initial state, corresponding to an empty preimage.
+    fn init(&self) -> Self::State;
+
+    /// Feed the next piece of the preimage into the state.
+    fn update(&self, state: &mut Self::State, data: &[u8]);
+
+    /// Compute the digest of everything fed into the state.
+    fn finalize(&self, state: Self::State) -> Self::Digest;
+}
+
+/// A [hash function](Hash) defined over strings of bits, rather than bytes.
+///
+/// Most hash functions are specified for bit strings, although in practice the
+/// input almost always consists of whole bytes. This trait allows hashing
+/// inputs with any number of bits, which is needed e.g. for the official
+/// [SHA-3](sha3) test vectors. Hashing $8n$ bits of an $n$-byte preimage must
+/// give the same digest as [`Hash::hash`].
+///
+/// The order of the bits within a byte is defined by the hash function.
+#[docext]
+pub trait BitHash: Hash {
+    /// Hash the first `bit_len` bits of the preimage.
+    fn hash_bits(&self, preimage: &[u8], bit_len: usize) -> Self::Digest;
+}
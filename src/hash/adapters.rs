@@ -0,0 +1,108 @@
+use {
+    crate::IncrementalHash,
+    std::{fmt, hash::Hasher, io},
+};
+
+/// Adapter which implements [`io::Write`] for any [incremental
+/// hash](IncrementalHash).
+///
+/// This makes it possible to stream data into a hash function with the
+/// standard library I/O utilities, e.g. hashing a file with [`io::copy`]
+/// without reading it into memory first.
+pub struct WriteHasher<H: IncrementalHash> {
+    hash: H,
+    state: H::State,
+}
+
+impl<H: IncrementalHash> WriteHasher<H> {
+    pub fn new(hash: H) -> Self {
+        let state = hash.init();
+        Self { hash, state }
+    }
+
+    /// Compute the digest of all data written so far.
+    pub fn finalize(self) -> H::Digest {
+        self.hash.finalize(self.state)
+    }
+}
+
+impl<H: IncrementalHash> io::Write for WriteHasher<H> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.hash.update(&mut self.state, buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl<H: IncrementalHash + fmt::Debug> fmt::Debug for WriteHasher<H> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("WriteHasher")
+            .field("hash", &self.hash)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Adapter which implements [`std::hash::Hasher`] for any [incremental
+/// hash](IncrementalHash).
+///
+/// [`finish`](Hasher::finish) returns the first eight bytes of the digest,
+/// interpreted as a little-endian integer. Combined with
+/// [`BuildHasherDefault`](std::hash::BuildHasherDefault), this makes it
+/// possible to use a cryptographic hash with
+/// [`HashMap`](std::collections::HashMap) and friends.
+///
+/// # Security
+///
+/// Although the underlying hash is cryptographic, the hasher is not keyed, so
+/// it provides no resistance against HashDoS attacks on hash tables: anyone
+/// can compute the bucket of a key offline and craft colliding keys. It is
+/// also much slower than the standard library hasher. It is handy for stable,
+/// content-addressed values, e.g. deduplication in tests.
+pub struct StdHasher<H: IncrementalHash> {
+    hash: H,
+    state: H::State,
+}
+
+impl<H: IncrementalHash> StdHasher<H> {
+    pub fn new(hash: H) -> Self {
+        let state = hash.init();
+        Self { hash, state }
+    }
+}
+
+impl<H: IncrementalHash + Default> Default for StdHasher<H> {
+    fn default() -> Self {
+        Self::new(H::default())
+    }
+}
+
+impl<H> Hasher for StdHasher<H>
+where
+    H: IncrementalHash,
+    H::Digest: AsRef<[u8]>,
+{
+    fn finish(&self) -> u64 {
+        let digest = self.hash.finalize(self.state.clone());
+        let mut result = [0; 8];
+        result
+            .iter_mut()
+            .zip(digest.as_ref())
+            .for_each(|(r, d)| *r = *d);
+        u64::from_le_bytes(result)
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        self.hash.update(&mut self.state, bytes);
+    }
+}
+
+impl<H: IncrementalHash + fmt::Debug> fmt::Debug for StdHasher<H> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("StdHasher")
+            .field("hash", &self.hash)
+            .finish_non_exhaustive()
+    }
+}
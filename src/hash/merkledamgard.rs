@@ -1,4 +1,8 @@
-use {crate::Hash, docext::docext};
+use {
+    crate::{trace::NoTrace, Hash, IncrementalHash},
+    docext::docext,
+    std::sync::Arc,
+};
 
 mod daviesmeyer;
 
@@ -58,6 +62,12 @@ pub use daviesmeyer::{DaviesMeyer, DaviesMeyerStep};
 ///
 /// [SHA-256](crate::Sha256) is a widely used hash function vulnerable to this
 /// attack.
+///
+/// # Tracing
+///
+/// The state after every compressed block can be reported to a
+/// [tracer](MerkleDamgardTrace), by constructing the hash with
+/// [`with_trace`](MerkleDamgard::with_trace).
 #[docext]
 #[derive(Debug)]
 pub struct MerkleDamgard<
@@ -65,10 +75,12 @@ pub struct MerkleDamgard<
     Block,
     F: CompressionFn<State = State, Block = Block>,
     Pad: MerkleDamgardPad<Block = Block>,
+    Tr = NoTrace,
 > {
     f: F,
     pad: Pad,
     iv: State,
+    trace: Tr,
 }
 
 /// A compression function used internally by the [Merkle-Damgard
@@ -84,7 +96,37 @@ pub trait CompressionFn {
     type Block;
     type State;
 
-    fn compress(&self, state: Self::State, block: Self::Block) -> Self::State;
+    /// Compress the block into the state. The block is borrowed, so that full
+    /// blocks of the preimage can be compressed without being copied out of it.
+    fn compress(&self, state: Self::State, block: &Self::Block) -> Self::State;
+}
+
+/// Forward to the shared [compression function](CompressionFn).
+impl<T: CompressionFn + ?Sized> CompressionFn for &T {
+    type Block = T::Block;
+    type State = T::State;
+
+    fn compress(&self, state: Self::State, block: &Self::Block) -> Self::State {
+        T::compress(self, state, block)
+    }
+}
+
+impl<T: CompressionFn + ?Sized> CompressionFn for Box<T> {
+    type Block = T::Block;
+    type State = T::State;
+
+    fn compress(&self, state: Self::State, block: &Self::Block) -> Self::State {
+        T::compress(self, state, block)
+    }
+}
+
+impl<T: CompressionFn + ?Sized> CompressionFn for Arc<T> {
+    type Block = T::Block;
+    type State = T::State;
+
+    fn compress(&self, state: Self::State, block: &Self::Block) -> Self::State {
+        T::compress(self, state, block)
+    }
 }
 
 /// The padding scheme used by the [Merkle-Damgard construction](MerkleDamgard).
@@ -98,11 +140,53 @@ pub trait CompressionFn {
 ///    $len(Pad(M_1)) = len(Pad(M_2))$.
 /// 3. If $len(M_1) \neq len(M_2)$, then the last blocks of $Pad(M_1)$ and
 ///    $Pad(M_2)$ must be different.
+///
+/// Only the end of the message is actually modified by padding, so the
+/// padding is applied in two steps. First, [`split`](MerkleDamgardPad::split)
+/// splits the message into full blocks, which are borrowed straight from the
+/// message, and a tail shorter than a block. Then,
+/// [`pad_tail`](MerkleDamgardPad::pad_tail) pads the tail into the final one
+/// or more blocks. $Pad(M)$ is the concatenation of these blocks. This way,
+/// hashing a large message only copies the few bytes of the tail.
 #[docext]
 pub trait MerkleDamgardPad {
     type Block;
 
-    fn pad(&self, preimage: &[u8]) -> impl Iterator<Item = Self::Block>;
+    /// Split the preimage into full blocks and the remaining tail, which must
+    /// be shorter than a block.
+    fn split<'a>(&self, preimage: &'a [u8]) -> (impl Iterator<Item = &'a Self::Block>, &'a [u8])
+    where
+        Self::Block: 'a;
+
+    /// Pad the tail of the preimage following the last full block. `len` is the
+    /// length of the entire preimage in bytes.
+    ///
+    /// The preimage doesn't have to be available all at once, as is the case
+    /// with [incremental hashing](crate::IncrementalHash): only the tail and
+    /// the length are needed.
+    ///
+    /// A padding which encodes the length into a fixed number of bits may panic
+    /// if the length doesn't fit, like [SHA-2
+    /// padding](crate::sha2::LengthPadding) does.
+    fn pad_tail(&self, tail: &[u8], len: u64) -> impl Iterator<Item = Self::Block>;
+}
+
+/// Observes the state of a [Merkle-Damgard](MerkleDamgard) hash after every
+/// compressed block.
+///
+/// The state of the construction can be of any type, so unlike a
+/// [tracer](crate::trace::Trace), which only sees bytes, this trait is
+/// generic over the state. Hash functions such as [SHA-256](crate::Sha256)
+/// implement it by encoding their state and forwarding it to
+/// [`Trace::on_block`](crate::trace::Trace::on_block).
+pub trait MerkleDamgardTrace<State> {
+    /// Called with the state after compressing the block with the given
+    /// index. The blocks are numbered from 0, including the padding blocks.
+    fn on_block(&self, block: usize, state: &State);
+}
+
+impl<State> MerkleDamgardTrace<State> for NoTrace {
+    fn on_block(&self, _block: usize, _state: &State) {}
 }
 
 impl<
@@ -113,7 +197,29 @@ impl<
     > MerkleDamgard<State, Block, F, Pad>
 {
     pub fn new(f: F, pad: Pad, iv: State) -> Self {
-        Self { f, pad, iv }
+        Self::with_trace(f, pad, iv, NoTrace)
+    }
+}
+
+impl<
+        State,
+        Block,
+        F: CompressionFn<State = State, Block = Block>,
+        Pad: MerkleDamgardPad<Block = Block>,
+        Tr: MerkleDamgardTrace<State>,
+    > MerkleDamgard<State, Block, F, Pad, Tr>
+{
+    /// Construct the hash, reporting the state after every block to the
+    /// tracer.
+    pub fn with_trace(f: F, pad: Pad, iv: State, trace: Tr) -> Self {
+        Self { f, pad, iv, trace }
+    }
+
+    /// Compress the block with the given index into the state.
+    fn compress(&self, state: State, block: &Block, index: usize) -> State {
+        let state = self.f.compress(state, block);
+        self.trace.on_block(index, &state);
+        state
     }
 }
 
@@ -123,16 +229,104 @@ impl<
         Block,
         F: CompressionFn<State = State, Block = Block>,
         Pad: MerkleDamgardPad<Block = Block>,
-    > Hash for MerkleDamgard<State, Block, F, Pad>
+        Tr: MerkleDamgardTrace<State>,
+    > Hash for MerkleDamgard<State, Block, F, Pad, Tr>
 {
     type Digest = State;
     type Block = Block;
 
     fn hash(&self, preimage: &[u8]) -> Self::Digest {
+        let (blocks, tail) = self.pad.split(preimage);
+        let mut index = 0;
+        let mut compress = |state, block: &Block| {
+            index += 1;
+            self.compress(state, block, index - 1)
+        };
+        let state = blocks.fold(self.iv.clone(), &mut compress);
+        self.pad
+            .pad_tail(tail, u64::try_from(preimage.len()).unwrap())
+            .fold(state, |state, block| compress(state, &block))
+    }
+}
+
+/// The intermediate state of an [incremental](crate::IncrementalHash)
+/// [Merkle-Damgard](MerkleDamgard) hash.
+///
+/// Apart from the state of the construction itself, this keeps the preimage
+/// bytes which don't yet form a full block, and the total length of the
+/// preimage, which is needed for [padding](MerkleDamgardPad::pad_tail).
+#[derive(Debug, Clone)]
+pub struct MerkleDamgardState<State> {
+    state: State,
+    buffer: Vec<u8>,
+    len: u64,
+}
+
+impl<State> MerkleDamgardState<State> {
+    /// The number of blocks compressed so far. Every byte which isn't
+    /// buffered has been compressed.
+    fn blocks<const BLOCK_SIZE: usize>(&self) -> usize {
+        let buffered = u64::try_from(self.buffer.len()).unwrap();
+        let block_size = u64::try_from(BLOCK_SIZE).unwrap();
+        usize::try_from((self.len - buffered) / block_size).unwrap()
+    }
+}
+
+impl<State, F, Pad, Tr, const BLOCK_SIZE: usize> IncrementalHash
+    for MerkleDamgard<State, [u8; BLOCK_SIZE], F, Pad, Tr>
+where
+    State: Clone,
+    F: CompressionFn<State = State, Block = [u8; BLOCK_SIZE]>,
+    Pad: MerkleDamgardPad<Block = [u8; BLOCK_SIZE]>,
+    Tr: MerkleDamgardTrace<State>,
+{
+    type State = MerkleDamgardState<State>;
+
+    fn init(&self) -> Self::State {
+        MerkleDamgardState {
+            state: self.iv.clone(),
+            buffer: Vec::with_capacity(BLOCK_SIZE),
+            len: 0,
+        }
+    }
+
+    fn update(&self, state: &mut Self::State, mut data: &[u8]) {
+        let mut index = state.blocks::<BLOCK_SIZE>();
+        state.len = state
+            .len
+            .checked_add(u64::try_from(data.len()).unwrap())
+            .expect("preimage of 2^64 bytes or more");
+
+        // Complete the buffered block first, if there is one.
+        if !state.buffer.is_empty() {
+            let n = data.len().min(BLOCK_SIZE - state.buffer.len());
+            state.buffer.extend(&data[..n]);
+            data = &data[n..];
+            if state.buffer.len() < BLOCK_SIZE {
+                return;
+            }
+            let block = state.buffer.as_slice().try_into().unwrap();
+            state.state = self.compress(state.state.clone(), &block, index);
+            state.buffer.clear();
+            index += 1;
+        }
+
+        // Compress the full blocks straight from the input, and buffer the rest.
+        let mut blocks = data.array_chunks();
+        for block in blocks.by_ref() {
+            state.state = self.compress(state.state.clone(), block, index);
+            index += 1;
+        }
+        state.buffer.extend(blocks.remainder());
+    }
+
+    fn finalize(&self, state: Self::State) -> Self::Digest {
+        let index = state.blocks::<BLOCK_SIZE>();
         self.pad
-            .pad(preimage)
-            .fold(self.iv.clone(), |state, block| {
-                self.f.compress(state, block)
+            .pad_tail(&state.buffer, state.len)
+            .zip(index..)
+            .fold(state.state, |state, (block, index)| {
+                self.compress(state, &block, index)
             })
     }
 }
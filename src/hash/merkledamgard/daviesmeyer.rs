@@ -52,12 +52,15 @@ impl<Enc: BlockEncrypt, Step: DaviesMeyerStep<State = Enc::EncryptionBlock>> Com
     for DaviesMeyer<Enc, Step>
 where
     Enc::EncryptionBlock: Clone,
+    Enc::EncryptionKey: Clone,
 {
     type Block = Enc::EncryptionKey;
     type State = Enc::EncryptionBlock;
 
-    fn compress(&self, state: Self::State, block: Self::Block) -> Self::State {
+    fn compress(&self, state: Self::State, block: &Self::Block) -> Self::State {
+        // Block ciphers take their key by value. This copy is local to a single
+        // compression, so it stays on the stack.
         self.step
-            .step(state.clone(), self.enc.encrypt(state, block))
+            .step(state.clone(), self.enc.encrypt(state, block.clone()))
     }
 }
@@ -0,0 +1,189 @@
+use {crate::Hash, docext::docext, std::thread};
+
+/// Prefix of the leaf hashes, see [`MerkleTree`].
+const LEAF: u8 = 0x00;
+
+/// Prefix of the inner node hashes, see [`MerkleTree`].
+const NODE: u8 = 0x01;
+
+/// A Merkle tree commits to a list of leaves with a single [hash](Hash)
+/// digest, the _root_.
+///
+/// Each leaf is hashed, and then neighbouring hashes are hashed together in
+/// pairs, level by level, until only the root is left. Changing any leaf
+/// changes its hash, which changes the hash of its parent, and so on all the
+/// way up to the root. Unlike hashing all leaves in one go, the tree allows
+/// proving that a leaf is part of the root by revealing only the $\log_2 n$
+/// sibling hashes on the path from the leaf to the root.
+///
+/// This tree follows the layout of RFC 6962. The hash of a list of $n$ leaves
+/// $D_0, \dots, D_{n-1}$ is defined recursively:
+///
+/// - The empty list hashes to $Hash(\varnothing)$, the hash of the empty
+///   preimage.
+/// - A single leaf hashes to $Hash(\mathtt{00} \parallel D_0)$.
+/// - For $n > 1$, let $k$ be the largest power of two smaller than $n$. Then
+///   the list hashes to $Hash(\mathtt{01} \parallel L \parallel R)$, where $L$
+///   is the hash of $D_0, \dots, D_{k-1}$ and $R$ is the hash of $D_k, \dots,
+///   D_{n-1}$.
+///
+/// The different prefixes for leaves and inner nodes make sure that an inner
+/// node can never be passed off as a leaf, or vice versa. Otherwise, the two
+/// hashes $L \parallel R$ of an inner node would make a valid leaf with the
+/// same hash.
+///
+/// # Parallelism
+///
+/// The left and right subtrees of a node don't depend on each other, so they
+/// can be hashed at the same time.
+/// [`build_parallel`](MerkleTree::build_parallel) splits the tree at the top
+/// levels, hashes the subtrees on separate threads, and then merges the subtree
+/// roots. Since the split follows the same recursion as above, the root is
+/// exactly the same as the one computed by [`build`](MerkleTree::build).
+#[docext]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MerkleTree<D> {
+    root: D,
+    leaves: usize,
+}
+
+impl<D> MerkleTree<D> {
+    /// Build the tree over the given leaves.
+    pub fn build<H, L>(hash: &H, leaves: &[L]) -> Self
+    where
+        H: Hash<Digest = D>,
+        D: AsRef<[u8]>,
+        L: AsRef<[u8]>,
+    {
+        let root = if leaves.is_empty() {
+            hash.hash(&[])
+        } else {
+            subtree(hash, leaves)
+        };
+        Self {
+            root,
+            leaves: leaves.len(),
+        }
+    }
+
+    /// Build the tree over the given leaves, using up to `threads` threads.
+    /// The root is the same as the one computed by
+    /// [`build`](MerkleTree::build), regardless of the number of threads.
+    pub fn build_parallel<H, L>(hash: &H, leaves: &[L], threads: usize) -> Self
+    where
+        H: Hash<Digest = D> + Sync,
+        D: AsRef<[u8]> + Send,
+        L: AsRef<[u8]> + Sync,
+    {
+        let root = if leaves.is_empty() {
+            hash.hash(&[])
+        } else {
+            subtree_parallel(hash, leaves, threads)
+        };
+        Self {
+            root,
+            leaves: leaves.len(),
+        }
+    }
+
+    pub fn root(&self) -> &D {
+        &self.root
+    }
+
+    pub fn into_root(self) -> D {
+        self.root
+    }
+
+    /// The number of leaves in the tree.
+    pub fn len(&self) -> usize {
+        self.leaves
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.leaves == 0
+    }
+}
+
+/// Split the data into chunks of `chunk_size` bytes and compute the root of the
+/// [Merkle tree](MerkleTree) over the chunks, using up to `threads` threads.
+///
+/// Note that this is a _tree hash_: the result is NOT equal to the hash of the
+/// data, and it depends on the chunk size. Both sides need to agree on the
+/// chunk size to get the same digest.
+///
+/// # Panics
+///
+/// If the chunk size is zero.
+pub fn hash_chunked_parallel<H>(
+    hash: &H,
+    data: &[u8],
+    chunk_size: usize,
+    threads: usize,
+) -> H::Digest
+where
+    H: Hash + Sync,
+    H::Digest: AsRef<[u8]> + Send,
+{
+    assert!(chunk_size > 0, "chunk size must not be zero");
+    let chunks: Vec<_> = data.chunks(chunk_size).collect();
+    MerkleTree::build_parallel(hash, &chunks, threads).into_root()
+}
+
+/// Hash a non-empty list of leaves.
+fn subtree<H, L>(hash: &H, leaves: &[L]) -> H::Digest
+where
+    H: Hash,
+    H::Digest: AsRef<[u8]>,
+    L: AsRef<[u8]>,
+{
+    if let [leaf] = leaves {
+        return leaf_hash(hash, leaf.as_ref());
+    }
+    let (left, right) = leaves.split_at(split(leaves.len()));
+    node_hash(hash, subtree(hash, left), subtree(hash, right))
+}
+
+/// Hash a non-empty list of leaves, splitting the work between `threads`
+/// threads.
+fn subtree_parallel<H, L>(hash: &H, leaves: &[L], threads: usize) -> H::Digest
+where
+    H: Hash + Sync,
+    H::Digest: AsRef<[u8]> + Send,
+    L: AsRef<[u8]> + Sync,
+{
+    if threads <= 1 || leaves.len() == 1 {
+        return subtree(hash, leaves);
+    }
+    let (left, right) = leaves.split_at(split(leaves.len()));
+    // Hash the left subtree on a new thread, and the right subtree on this
+    // thread. The threads are divided in proportion to the number of leaves.
+    let left_threads = (threads * left.len() / leaves.len()).clamp(1, threads - 1);
+    let (left, right) = thread::scope(|s| {
+        let left = s.spawn(|| subtree_parallel(hash, left, left_threads));
+        let right = subtree_parallel(hash, right, threads - left_threads);
+        (left.join().unwrap(), right)
+    });
+    node_hash(hash, left, right)
+}
+
+/// The number of leaves in the left subtree of a node with `n > 1` leaves: the
+/// largest power of two smaller than `n`, which is the highest bit of `n - 1`.
+fn split(n: usize) -> usize {
+    1 << (n - 1).ilog2()
+}
+
+fn leaf_hash<H: Hash>(hash: &H, leaf: &[u8]) -> H::Digest {
+    let mut preimage = vec![LEAF];
+    preimage.extend(leaf);
+    hash.hash(&preimage)
+}
+
+fn node_hash<H: Hash>(hash: &H, left: H::Digest, right: H::Digest) -> H::Digest
+where
+    H::Digest: AsRef<[u8]>,
+{
+    let mut preimage = vec![NODE];
+    preimage.extend(left.as_ref());
+    preimage.extend(right.as_ref());
+    hash.hash(&preimage)
+}
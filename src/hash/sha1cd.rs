@@ -0,0 +1,281 @@
+//! [SHA-1](crate::Sha1) with detection of collision attacks, a technique
+//! called _counter-cryptanalysis_.
+//!
+//! SHA-1 is broken: the SHAttered attack produced two PDF files with the same
+//! SHA-1 hash in 2017, and later attacks made chosen-prefix collisions
+//! practical. Yet SHA-1 is still around, e.g. in Git. Instead of waiting for
+//! it to disappear, Marc Stevens observed that all known collision attacks
+//! share a structure which can be recognized while hashing, even when only one
+//! of the two colliding messages is available.
+//!
+//! # Disturbance Vectors
+//!
+//! Every practical collision attack on SHA-1 is built from _local
+//! collisions_: a small difference is introduced into one word of the message
+//! schedule and cancelled out by corrections in the following five words. The
+//! positions of the local collisions form the _disturbance vector_ (DV), and
+//! because the message schedule is linear, the disturbance vector determines
+//! an XOR difference $\delta W_t$ for all 80 words of the schedule.
+//!
+//! An attack consists of two blocks: the first one produces a _near
+//! collision_, a small difference in the state, and the second one cancels
+//! that difference out. In both blocks, the state difference is zero after
+//! some step $t$ of the compression, since the remaining disturbances lie in
+//! the last rounds where they are handled probabilistically.
+//!
+//! # Detection
+//!
+//! Given a block $W$ and its states $S_0, \dots, S_{80}$, the detection asks:
+//! does the _other_ block $W' = W \oplus \delta W$ of an attack using this
+//! disturbance vector exist? To find out, start from $S_t$, which would be
+//! shared by both blocks, and compute the compression of $W'$ backwards to
+//! get the input state $S'_0$ and forwards to get $S'_{80}$. If
+//!
+//! $$
+//! S_0 + S_{80} = S'_0 + S'_{80}
+//! $$
+//!
+//! then the two blocks collide: this block is the second half of a collision
+//! attack, and the hashed message has a colliding sibling. For an innocent
+//! block, this is as unlikely as finding a SHA-1 collision by chance.
+//!
+//! # Unavoidable Bit Conditions
+//!
+//! Recomputing the compression for every disturbance vector would make the
+//! hash several times slower. However, for a disturbance vector to lead to a
+//! collision with a feasible probability, some bits of the message schedule
+//! must satisfy linear relations, e.g. bit 29 of $W_{46}$ must equal bit 29 of
+//! $W_{47}$. These _unavoidable bit conditions_ hold for every attack using
+//! the disturbance vector, and they only involve the message, so they are
+//! cheap to check. A random block fails them with overwhelming probability,
+//! and only the blocks which pass them are recompressed. This speedup is due
+//! to Stevens and Shumow.
+//!
+//! # Coverage
+//!
+//! The reference implementation, `sha1collisiondetection`, checks the 32
+//! disturbance vectors which lead to the cheapest known attacks. This module
+//! only checks the two with $K = 52$, of types I and II. II(52,0) is the one
+//! used by both SHAttered and the chosen-prefix collision "SHA-1 is a
+//! Shambles". The message differences and bit conditions are taken from
+//! `sha1collisiondetection`, and checking another disturbance vector is a
+//! matter of adding its entry to the `DISTURBANCE_VECTORS` table.
+
+use {
+    super::sha2::{self, consts, ft, kt, Block, LengthPadding, Sha1State},
+    crate::{Hash, MerkleDamgardPad},
+    docext::docext,
+};
+
+/// SHA-1 with [collision detection](self).
+///
+/// The digests are the same as those of [SHA-1](crate::Sha1). Use
+/// [`hash_with_detection`](Sha1Cd::hash_with_detection) to find out whether the
+/// preimage contains a collision attack. The [`Hash`] implementation ignores
+/// the detection, so that this type can stand in for SHA-1 anywhere.
+#[derive(Debug, Default)]
+pub struct Sha1Cd(());
+
+impl Sha1Cd {
+    /// Hash the preimage, also returning whether it contains a block of a
+    /// known collision attack.
+    pub fn hash_with_detection(&self, preimage: &[u8]) -> ([u8; 20], bool) {
+        let pad = LengthPadding::default();
+        let (blocks, tail) = pad.split(preimage);
+        let len = u64::try_from(preimage.len()).unwrap();
+        let (state, detected) = blocks.copied().chain(pad.pad_tail(tail, len)).fold(
+            (consts::H0_1, false),
+            |(state, detected), block| {
+                let (state, found) = compress(state, &block);
+                (state, detected || found)
+            },
+        );
+        (sha2::to_be_bytes(state), detected)
+    }
+}
+
+impl Hash for Sha1Cd {
+    type Digest = [u8; 20];
+    type Block = Block;
+
+    fn hash(&self, preimage: &[u8]) -> Self::Digest {
+        self.hash_with_detection(preimage).0
+    }
+}
+
+/// A disturbance vector checked by the [collision detection](self).
+struct DisturbanceVector {
+    /// The step after which the state difference of an attack is zero.
+    test_step: usize,
+    /// The XOR difference of the expanded message block.
+    message_difference: [u32; 80],
+    /// The unavoidable bit conditions.
+    conditions: &'static [BitCondition],
+}
+
+/// A condition that bit `a.1` of $W_{a.0}$ is equal to, or differs from, bit
+/// `b.1` of $W_{b.0}$.
+#[docext]
+struct BitCondition {
+    a: (usize, u32),
+    b: (usize, u32),
+    differ: bool,
+}
+
+impl BitCondition {
+    const fn equal(a: (usize, u32), b: (usize, u32)) -> Self {
+        Self {
+            a,
+            b,
+            differ: false,
+        }
+    }
+
+    const fn differ(a: (usize, u32), b: (usize, u32)) -> Self {
+        Self { a, b, differ: true }
+    }
+
+    fn holds(&self, w: &[u32; 80]) -> bool {
+        let bit = |(i, b): (usize, u32)| w[i] >> b & 1 == 1;
+        (bit(self.a) != bit(self.b)) == self.differ
+    }
+}
+
+/// The checked [disturbance vectors](self#disturbance-vectors).
+const DISTURBANCE_VECTORS: [DisturbanceVector; 2] = [
+    // I(52,0)
+    DisturbanceVector {
+        test_step: 65,
+        message_difference: expand([
+            0x04000010, 0xe8000000, 0x0800000c, 0x18000000, 0xb800000a, 0xc8000010, 0x2c000010,
+            0xf4000014, 0xb4000008, 0x08000000, 0x9800000c, 0xd8000010, 0x08000010, 0xb8000010,
+            0x98000000, 0x60000000,
+        ]),
+        conditions: &[
+            BitCondition::differ((38, 4), (39, 4)),
+            BitCondition::equal((39, 4), (43, 29)),
+            BitCondition::equal((42, 4), (45, 29)),
+            BitCondition::equal((44, 29), (45, 29)),
+            BitCondition::equal((45, 29), (46, 29)),
+            BitCondition::equal((46, 4), (49, 29)),
+            BitCondition::equal((48, 29), (49, 29)),
+            BitCondition::equal((48, 4), (51, 29)),
+            BitCondition::differ((48, 29), (55, 29)),
+            BitCondition::differ((49, 29), (51, 29)),
+            BitCondition::equal((50, 4), (53, 29)),
+            BitCondition::equal((52, 4), (55, 29)),
+            BitCondition::differ((53, 29), (56, 29)),
+            BitCondition::equal((55, 29), (56, 29)),
+        ],
+    },
+    // II(52,0)
+    DisturbanceVector {
+        test_step: 65,
+        message_difference: expand([
+            0x0c000002, 0xc0000010, 0xb400001c, 0x3c000004, 0xbc00001a, 0x20000010, 0x2400001c,
+            0xec000014, 0x0c000002, 0xc0000010, 0xb400001c, 0x2c000004, 0xbc000018, 0xb0000010,
+            0x0000000c, 0xb8000010,
+        ]),
+        conditions: &[
+            BitCondition::differ((36, 4), (38, 4)),
+            BitCondition::differ((39, 30), (44, 28)),
+            BitCondition::equal((40, 3), (44, 28)),
+            BitCondition::equal((40, 4), (44, 29)),
+            BitCondition::equal((43, 4), (46, 29)),
+            BitCondition::equal((45, 29), (46, 29)),
+            BitCondition::equal((46, 29), (47, 29)),
+            BitCondition::equal((48, 4), (51, 29)),
+            BitCondition::equal((50, 29), (51, 29)),
+            BitCondition::equal((51, 29), (52, 29)),
+            BitCondition::equal((54, 4), (57, 29)),
+            BitCondition::equal((56, 29), (57, 29)),
+            BitCondition::equal((56, 4), (59, 29)),
+            BitCondition::differ((56, 29), (59, 29)),
+            BitCondition::equal((59, 29), (60, 29)),
+        ],
+    },
+];
+
+/// Compress the block into the state, returning the new state and whether the
+/// block completes a collision attack.
+fn compress(state: Sha1State, block: &Block) -> (Sha1State, bool) {
+    let w = expand(sha2::from_be_bytes(block));
+
+    let mut states = [state; 81];
+    for t in 0..80 {
+        states[t + 1] = step(t, states[t], w[t]);
+    }
+    let result = add(state, states[80]);
+
+    let detected = DISTURBANCE_VECTORS.iter().any(|dv| {
+        dv.conditions.iter().all(|c| c.holds(&w)) && {
+            let mut other = w;
+            other
+                .iter_mut()
+                .zip(dv.message_difference)
+                .for_each(|(w, d)| *w ^= d);
+            recompress(dv.test_step, &other, states[dv.test_step]) == result
+        }
+    });
+    (result, detected)
+}
+
+/// Compute the output of the compression of the block whose state after
+/// `t` steps is `state`, without knowing its input state.
+fn recompress(t: usize, w: &[u32; 80], state: Sha1State) -> Sha1State {
+    let input = (0..t).rev().fold(state, |s, i| unstep(i, s, w[i]));
+    let output = (t..80).fold(state, |s, i| step(i, s, w[i]));
+    add(input, output)
+}
+
+/// Expand a message block into the full SHA-1 message schedule, as described
+/// for [SHACAL-1](sha2::Shacal1). The expansion is linear, so it also expands
+/// XOR differences of message blocks.
+const fn expand(block: [u32; 16]) -> [u32; 80] {
+    let mut w = [0; 80];
+    let mut t = 0;
+    while t < 80 {
+        w[t] = if t < 16 {
+            block[t]
+        } else {
+            (w[t - 3] ^ w[t - 8] ^ w[t - 14] ^ w[t - 16]).rotate_left(1)
+        };
+        t += 1;
+    }
+    w
+}
+
+/// Step $t$ of the [SHA-1](sha2::Shacal1) compression function.
+#[docext]
+fn step(t: usize, [a, b, c, d, e]: Sha1State, w: u32) -> Sha1State {
+    let t = u32::try_from(t).unwrap();
+    let temp = a
+        .rotate_left(5)
+        .wrapping_add(ft(t, b, c, d))
+        .wrapping_add(e)
+        .wrapping_add(kt(t))
+        .wrapping_add(w);
+    [temp, a, b.rotate_left(30), c, d]
+}
+
+/// Undo [step](step) $t$, recovering the state before it.
+#[docext]
+fn unstep(t: usize, [temp, a, b, c, d]: Sha1State, w: u32) -> Sha1State {
+    let t = u32::try_from(t).unwrap();
+    let b = b.rotate_right(30);
+    let e = temp
+        .wrapping_sub(a.rotate_left(5))
+        .wrapping_sub(ft(t, b, c, d))
+        .wrapping_sub(kt(t))
+        .wrapping_sub(w);
+    [a, b, c, d, e]
+}
+
+fn add(a: Sha1State, b: Sha1State) -> Sha1State {
+    let mut result = a;
+    result
+        .iter_mut()
+        .zip(b)
+        .for_each(|(r, b)| *r = r.wrapping_add(b));
+    result
+}
@@ -7,38 +7,75 @@
 //! [SHACAL-2](Shacal2) respectively. The block ciphers are used to mix the
 //! internal state of the hash function with padded preimage blocks. The
 //! final state (optionally truncated to a smaller size) is the hash digest.
+//!
+//! SHA-512 and SHA-384 are the same design scaled up to 64-bit words: their
+//! [block cipher](Shacal2_64) runs 80 rounds over 128-byte blocks, and their
+//! [padding](LengthPadding128) ends with a 128-bit length. SHA-512/224 and
+//! SHA-512/256 truncate SHA-512 to the digest sizes of SHA-224 and SHA-256,
+//! starting from [initial states](sha512_t_iv) of their own.
 
 use {
-    crate::{BlockEncrypt, DaviesMeyer, DaviesMeyerStep, Hash, MerkleDamgard, MerkleDamgardPad},
+    crate::{
+        trace::{NoTrace, Trace},
+        BlockEncrypt,
+        DaviesMeyer,
+        DaviesMeyerStep,
+        Hash,
+        IncrementalHash,
+        MerkleDamgard,
+        MerkleDamgardPad,
+        MerkleDamgardState,
+        MerkleDamgardTrace,
+    },
     docext::docext,
-    std::{iter, marker::PhantomData},
+    std::{
+        marker::PhantomData,
+        ops::{BitAnd, BitXor, Not},
+    },
 };
 
-/// The $K_t^{256}$ constants for [SHA-256](Sha256).
-#[docext]
-pub const KT_256: [u32; 64] = [
-    0x428a2f98, 0x71374491, 0xb5c0fbcf, 0xe9b5dba5, 0x3956c25b, 0x59f111f1, 0x923f82a4, 0xab1c5ed5,
-    0xd807aa98, 0x12835b01, 0x243185be, 0x550c7dc3, 0x72be5d74, 0x80deb1fe, 0x9bdc06a7, 0xc19bf174,
-    0xe49b69c1, 0xefbe4786, 0x0fc19dc6, 0x240ca1cc, 0x2de92c6f, 0x4a7484aa, 0x5cb0a9dc, 0x76f988da,
-    0x983e5152, 0xa831c66d, 0xb00327c8, 0xbf597fc7, 0xc6e00bf3, 0xd5a79147, 0x06ca6351, 0x14292967,
-    0x27b70a85, 0x2e1b2138, 0x4d2c6dfc, 0x53380d13, 0x650a7354, 0x766a0abb, 0x81c2c92e, 0x92722c85,
-    0xa2bfe8a1, 0xa81a664b, 0xc24b8b70, 0xc76c51a3, 0xd192e819, 0xd6990624, 0xf40e3585, 0x106aa070,
-    0x19a4c116, 0x1e376c08, 0x2748774c, 0x34b0bcb5, 0x391c0cb3, 0x4ed8aa4a, 0x5b9cca4f, 0x682e6ff3,
-    0x748f82ee, 0x78a5636f, 0x84c87814, 0x8cc70208, 0x90befffa, 0xa4506ceb, 0xbef9a3f7, 0xc67178f2,
-];
+pub mod consts;
 
 /// The block size in bytes.
 const BLOCK_SIZE: usize = 64;
 
+/// The block size of SHA-512 and SHA-384 in bytes.
+const BLOCK_SIZE_128: usize = 128;
+
 /// A preimage block.
 pub type Block = [u8; BLOCK_SIZE];
 
+/// A preimage block of [SHA-512](Sha512) and [SHA-384](Sha384).
+pub type Block128 = [u8; BLOCK_SIZE_128];
+
 /// The internal state of [SHA-1](Sha1).
 pub type Sha1State = [u32; 5];
 
 /// The internal state of [SHA-256](Sha256) and [SHA-224](Sha224).
 pub type Sha2State = [u32; 8];
 
+/// The internal state of [SHA-512](Sha512) and [SHA-384](Sha384).
+pub type Sha512State = [u64; 8];
+
+/// The [Merkle-Damgard](MerkleDamgard) construction shared by SHA-1 and
+/// SHA-2, over a SHACAL cipher with the given state.
+type Construction<State, Cipher, T> = MerkleDamgard<
+    State,
+    Block,
+    DaviesMeyer<Cipher, ModularAddition<State>>,
+    LengthPadding,
+    WordTrace<T>,
+>;
+
+/// The [Merkle-Damgard](MerkleDamgard) construction of SHA-512 and SHA-384.
+type Construction64<T> = MerkleDamgard<
+    Sha512State,
+    Block128,
+    DaviesMeyer<Shacal2_64<T>, ModularAddition<Sha512State>>,
+    LengthPadding128,
+    WordTrace<T>,
+>;
+
 /// SHA-1 hash specified by [FIPS
 /// 180-4](https://nvlpubs.nist.gov/nistpubs/FIPS/NIST.FIPS.180-4.pdf).
 ///
@@ -48,14 +85,7 @@ pub type Sha2State = [u32; 8];
 ///
 /// For more details, see the [module documentation](self).
 #[derive(Debug)]
-pub struct Sha1(
-    MerkleDamgard<
-        Sha1State,
-        Block,
-        DaviesMeyer<Shacal1, ModularAddition<Sha1State>>,
-        LengthPadding,
-    >,
-);
+pub struct Sha1<T: Trace = NoTrace>(Construction<Sha1State, Shacal1<T>, T>);
 
 /// SHA-256 hash specified by [FIPS
 /// 180-4](https://nvlpubs.nist.gov/nistpubs/FIPS/NIST.FIPS.180-4.pdf).
@@ -65,14 +95,7 @@ pub struct Sha1(
 ///
 /// For more details, see the [module documentation](self).
 #[derive(Debug)]
-pub struct Sha256(
-    MerkleDamgard<
-        Sha2State,
-        Block,
-        DaviesMeyer<Shacal2, ModularAddition<Sha2State>>,
-        LengthPadding,
-    >,
-);
+pub struct Sha256<T: Trace = NoTrace>(Construction<Sha2State, Shacal2<T>, T>);
 
 /// SHA-224 hash specified by [FIPS
 /// 180-4](https://nvlpubs.nist.gov/nistpubs/FIPS/NIST.FIPS.180-4.pdf).
@@ -84,14 +107,57 @@ pub struct Sha256(
 ///
 /// For more details, see the [module documentation](self).
 #[derive(Debug)]
-pub struct Sha224(
-    MerkleDamgard<
-        Sha2State,
-        Block,
-        DaviesMeyer<Shacal2, ModularAddition<Sha2State>>,
-        LengthPadding,
-    >,
-);
+pub struct Sha224<T: Trace = NoTrace>(Construction<Sha2State, Shacal2<T>, T>);
+
+/// SHA-512 hash specified by [FIPS
+/// 180-4](https://nvlpubs.nist.gov/nistpubs/FIPS/NIST.FIPS.180-4.pdf).
+///
+/// SHA-512 is vulnerable to [length-extension
+/// attacks](MerkleDamgard#length-extension-attacks).
+///
+/// For more details, see the [module documentation](self).
+#[derive(Debug)]
+pub struct Sha512<T: Trace = NoTrace>(Construction64<T>);
+
+/// SHA-384 hash specified by [FIPS
+/// 180-4](https://nvlpubs.nist.gov/nistpubs/FIPS/NIST.FIPS.180-4.pdf).
+///
+/// SHA-384 is the same as [SHA-512](Sha512) with a different initial state,
+/// and with the hash digest truncated to 384 bits. Due to the truncation,
+/// SHA-384 is not vulnerable to [length-extension
+/// attacks](MerkleDamgard#length-extension-attacks).
+///
+/// For more details, see the [module documentation](self).
+#[derive(Debug)]
+pub struct Sha384<T: Trace = NoTrace>(Construction64<T>);
+
+/// SHA-512/224 hash specified by [FIPS
+/// 180-4](https://nvlpubs.nist.gov/nistpubs/FIPS/NIST.FIPS.180-4.pdf).
+///
+/// SHA-512/224 is the same as [SHA-512](Sha512) with a [different initial
+/// state](consts::H0_512_224), and with the hash digest truncated to 224 bits.
+/// Its digest has the same size as that of [SHA-224](Sha224), but it is
+/// usually faster on 64-bit machines. Due to the truncation, it is not
+/// vulnerable to [length-extension
+/// attacks](MerkleDamgard#length-extension-attacks).
+///
+/// For more details, see the [module documentation](self).
+#[derive(Debug)]
+pub struct Sha512_224<T: Trace = NoTrace>(Construction64<T>);
+
+/// SHA-512/256 hash specified by [FIPS
+/// 180-4](https://nvlpubs.nist.gov/nistpubs/FIPS/NIST.FIPS.180-4.pdf).
+///
+/// SHA-512/256 is the same as [SHA-512](Sha512) with a [different initial
+/// state](consts::H0_512_256), and with the hash digest truncated to 256 bits.
+/// Unlike [SHA-256](Sha256), which has a digest of the same size, it is not
+/// vulnerable to [length-extension
+/// attacks](MerkleDamgard#length-extension-attacks), since the attacker only
+/// sees half of the final state.
+///
+/// For more details, see the [module documentation](self).
+#[derive(Debug)]
+pub struct Sha512_256<T: Trace = NoTrace>(Construction64<T>);
 
 /// The underlying block cipher used by [SHA-1](Sha1).
 ///
@@ -127,7 +193,7 @@ pub struct Sha224(
 /// number of rounds.
 #[docext]
 #[derive(Debug)]
-pub struct Shacal1(());
+pub struct Shacal1<T = NoTrace>(T);
 
 /// The underlying block cipher used by [SHA-265](Sha256) and [SHA-224](Sha224).
 ///
@@ -136,8 +202,8 @@ pub struct Shacal1(());
 /// schedule (described later), [$\Sigma_0^{256}$](uppercase_sigma_0),
 /// [$\Sigma_1^{256}$](uppercase_sigma_1),
 /// [$\sigma_0^{256}$](lowercase_sigma_0), [$\sigma_1^{256}$](lowercase_sigma_1)
-/// [$Ch$](ch), and [$Maj$](maj) are helper functions, and [$K_t^{256}$](KT_256)
-/// are the round constants:
+/// [$Ch$](ch), and [$Maj$](maj) are helper functions, and
+/// [$K_t^{256}$](consts::KT_256) are the round constants:
 ///
 /// $$
 /// T_1 = h + \Sigma_1^{256}(e) + Ch(e, f, g) + K_t^{256} + W_0
@@ -171,93 +237,364 @@ pub struct Shacal1(());
 /// number of rounds.
 #[docext]
 #[derive(Debug)]
-pub struct Shacal2(());
+pub struct Shacal2<T = NoTrace>(T);
+
+/// The underlying block cipher used by [SHA-512](Sha512) and
+/// [SHA-384](Sha384).
+///
+/// This is [SHACAL-2](Shacal2) with 64-bit words: the state is eight 64-bit
+/// words, the key is a 128-byte block read as a message schedule of sixteen
+/// 64-bit words, and there are 80 rounds instead of 64. The rounds and the
+/// message schedule are updated by the same formulas, except that the helper
+/// functions [$\Sigma_0^{512}$](uppercase_sigma_0_512),
+/// [$\Sigma_1^{512}$](uppercase_sigma_1_512),
+/// [$\sigma_0^{512}$](lowercase_sigma_0_512) and
+/// [$\sigma_1^{512}$](lowercase_sigma_1_512) rotate and shift by different
+/// amounts, the round constants are [$K_t^{512}$](consts::KT_512), and the
+/// additions are modulo $2^{64}$.
+#[docext]
+#[derive(Debug)]
+pub struct Shacal2_64<T = NoTrace>(T);
 
 impl Default for Sha1 {
     fn default() -> Self {
-        Self(MerkleDamgard::new(
-            DaviesMeyer::new(Shacal1(()), ModularAddition(Default::default())),
+        Self::with_trace(NoTrace)
+    }
+}
+
+impl Sha1 {
+    /// Report the rounds of SHACAL-1 and the state after every block,
+    /// under the name `SHA-1`, to the [tracer](Trace).
+    pub fn with_trace<T: Trace + Clone>(tracer: T) -> Sha1<T> {
+        Sha1(MerkleDamgard::with_trace(
+            DaviesMeyer::new(Shacal1(tracer.clone()), ModularAddition(Default::default())),
             LengthPadding(()),
-            [0x67452301, 0xefcdab89, 0x98badcfe, 0x10325476, 0xc3d2e1f0],
+            consts::H0_1,
+            WordTrace {
+                algorithm: "SHA-1",
+                tracer,
+            },
         ))
     }
 }
 
-impl Hash for Sha1 {
+impl<T: Trace> Hash for Sha1<T> {
     type Digest = [u8; 20];
     type Block = Block;
 
     fn hash(&self, preimage: &[u8]) -> Self::Digest {
-        let mut result = [0; 20];
-        self.0
-            .hash(preimage)
-            .into_iter()
-            .flat_map(u32::to_be_bytes)
-            .zip(result.iter_mut())
-            .for_each(|(b, r)| *r = b);
-        result
+        to_be_bytes(self.0.hash(preimage))
+    }
+}
+
+impl<T: Trace> IncrementalHash for Sha1<T> {
+    type State = MerkleDamgardState<Sha1State>;
+
+    fn init(&self) -> Self::State {
+        self.0.init()
+    }
+
+    fn update(&self, state: &mut Self::State, data: &[u8]) {
+        self.0.update(state, data)
+    }
+
+    fn finalize(&self, state: Self::State) -> Self::Digest {
+        to_be_bytes(self.0.finalize(state))
     }
 }
 
 impl Default for Sha256 {
     fn default() -> Self {
-        Self(MerkleDamgard::new(
-            DaviesMeyer::new(Shacal2(()), ModularAddition(Default::default())),
+        Self::with_trace(NoTrace)
+    }
+}
+
+impl Sha256 {
+    /// Report the rounds of SHACAL-2 and the state after every block,
+    /// under the name `SHA-256`, to the [tracer](Trace).
+    pub fn with_trace<T: Trace + Clone>(tracer: T) -> Sha256<T> {
+        Sha256(MerkleDamgard::with_trace(
+            DaviesMeyer::new(Shacal2(tracer.clone()), ModularAddition(Default::default())),
             LengthPadding(()),
-            [
-                0x6a09e667, 0xbb67ae85, 0x3c6ef372, 0xa54ff53a, 0x510e527f, 0x9b05688c, 0x1f83d9ab,
-                0x5be0cd19,
-            ],
+            consts::H0_256,
+            WordTrace {
+                algorithm: "SHA-256",
+                tracer,
+            },
         ))
     }
 }
 
-impl Hash for Sha256 {
+impl<T: Trace> Hash for Sha256<T> {
     type Digest = [u8; 32];
     type Block = Block;
 
     fn hash(&self, preimage: &[u8]) -> Self::Digest {
-        let mut result = [0; 32];
-        self.0
-            .hash(preimage)
-            .into_iter()
-            .flat_map(u32::to_be_bytes)
-            .zip(result.iter_mut())
-            .for_each(|(b, r)| *r = b);
-        result
+        to_be_bytes(self.0.hash(preimage))
+    }
+}
+
+impl<T: Trace> IncrementalHash for Sha256<T> {
+    type State = MerkleDamgardState<Sha2State>;
+
+    fn init(&self) -> Self::State {
+        self.0.init()
+    }
+
+    fn update(&self, state: &mut Self::State, data: &[u8]) {
+        self.0.update(state, data)
+    }
+
+    fn finalize(&self, state: Self::State) -> Self::Digest {
+        to_be_bytes(self.0.finalize(state))
     }
 }
 
 impl Default for Sha224 {
     fn default() -> Self {
-        Self(MerkleDamgard::new(
-            DaviesMeyer::new(Shacal2(()), ModularAddition(Default::default())),
+        Self::with_trace(NoTrace)
+    }
+}
+
+impl Sha224 {
+    /// Report the rounds of SHACAL-2 and the state after every block,
+    /// under the name `SHA-224`, to the [tracer](Trace).
+    pub fn with_trace<T: Trace + Clone>(tracer: T) -> Sha224<T> {
+        Sha224(MerkleDamgard::with_trace(
+            DaviesMeyer::new(Shacal2(tracer.clone()), ModularAddition(Default::default())),
             LengthPadding(()),
-            [
-                0xc1059ed8, 0x367cd507, 0x3070dd17, 0xf70e5939, 0xffc00b31, 0x68581511, 0x64f98fa7,
-                0xbefa4fa4,
-            ],
+            consts::H0_224,
+            WordTrace {
+                algorithm: "SHA-224",
+                tracer,
+            },
         ))
     }
 }
 
-impl Hash for Sha224 {
+impl<T: Trace> Hash for Sha224<T> {
     type Digest = [u8; 28];
     type Block = Block;
 
     fn hash(&self, preimage: &[u8]) -> Self::Digest {
-        let mut result = [0; 28];
-        self.0
-            .hash(preimage)
-            .into_iter()
-            .flat_map(u32::to_be_bytes)
-            .zip(result.iter_mut())
-            .for_each(|(b, r)| *r = b);
-        result
+        digest(self.0.hash(preimage))
     }
 }
 
-impl BlockEncrypt for Shacal1 {
+impl<T: Trace> IncrementalHash for Sha224<T> {
+    type State = MerkleDamgardState<Sha2State>;
+
+    fn init(&self) -> Self::State {
+        self.0.init()
+    }
+
+    fn update(&self, state: &mut Self::State, data: &[u8]) {
+        self.0.update(state, data)
+    }
+
+    fn finalize(&self, state: Self::State) -> Self::Digest {
+        digest(self.0.finalize(state))
+    }
+}
+
+impl Default for Sha512 {
+    fn default() -> Self {
+        Self::with_trace(NoTrace)
+    }
+}
+
+impl Sha512 {
+    /// Report the rounds of SHACAL-2 and the state after every block,
+    /// under the name `SHA-512`, to the [tracer](Trace).
+    pub fn with_trace<T: Trace + Clone>(tracer: T) -> Sha512<T> {
+        Sha512(construction64("SHA-512", consts::H0_512, tracer))
+    }
+}
+
+impl<T: Trace> Hash for Sha512<T> {
+    type Digest = [u8; 64];
+    type Block = Block128;
+
+    fn hash(&self, preimage: &[u8]) -> Self::Digest {
+        digest(self.0.hash(preimage))
+    }
+}
+
+impl<T: Trace> IncrementalHash for Sha512<T> {
+    type State = MerkleDamgardState<Sha512State>;
+
+    fn init(&self) -> Self::State {
+        self.0.init()
+    }
+
+    fn update(&self, state: &mut Self::State, data: &[u8]) {
+        self.0.update(state, data)
+    }
+
+    fn finalize(&self, state: Self::State) -> Self::Digest {
+        digest(self.0.finalize(state))
+    }
+}
+
+impl Default for Sha384 {
+    fn default() -> Self {
+        Self::with_trace(NoTrace)
+    }
+}
+
+impl Sha384 {
+    /// Report the rounds of SHACAL-2 and the state after every block,
+    /// under the name `SHA-384`, to the [tracer](Trace).
+    pub fn with_trace<T: Trace + Clone>(tracer: T) -> Sha384<T> {
+        Sha384(construction64("SHA-384", consts::H0_384, tracer))
+    }
+}
+
+impl<T: Trace> Hash for Sha384<T> {
+    type Digest = [u8; 48];
+    type Block = Block128;
+
+    fn hash(&self, preimage: &[u8]) -> Self::Digest {
+        digest(self.0.hash(preimage))
+    }
+}
+
+impl<T: Trace> IncrementalHash for Sha384<T> {
+    type State = MerkleDamgardState<Sha512State>;
+
+    fn init(&self) -> Self::State {
+        self.0.init()
+    }
+
+    fn update(&self, state: &mut Self::State, data: &[u8]) {
+        self.0.update(state, data)
+    }
+
+    fn finalize(&self, state: Self::State) -> Self::Digest {
+        digest(self.0.finalize(state))
+    }
+}
+
+impl Default for Sha512_224 {
+    fn default() -> Self {
+        Self::with_trace(NoTrace)
+    }
+}
+
+impl Sha512_224 {
+    /// Report the rounds of SHACAL-2 and the state after every block,
+    /// under the name `SHA-512/224`, to the [tracer](Trace).
+    pub fn with_trace<T: Trace + Clone>(tracer: T) -> Sha512_224<T> {
+        Sha512_224(construction64("SHA-512/224", consts::H0_512_224, tracer))
+    }
+}
+
+impl<T: Trace> Hash for Sha512_224<T> {
+    type Digest = [u8; 28];
+    type Block = Block128;
+
+    fn hash(&self, preimage: &[u8]) -> Self::Digest {
+        digest(self.0.hash(preimage))
+    }
+}
+
+impl<T: Trace> IncrementalHash for Sha512_224<T> {
+    type State = MerkleDamgardState<Sha512State>;
+
+    fn init(&self) -> Self::State {
+        self.0.init()
+    }
+
+    fn update(&self, state: &mut Self::State, data: &[u8]) {
+        self.0.update(state, data)
+    }
+
+    fn finalize(&self, state: Self::State) -> Self::Digest {
+        digest(self.0.finalize(state))
+    }
+}
+
+impl Default for Sha512_256 {
+    fn default() -> Self {
+        Self::with_trace(NoTrace)
+    }
+}
+
+impl Sha512_256 {
+    /// Report the rounds of SHACAL-2 and the state after every block,
+    /// under the name `SHA-512/256`, to the [tracer](Trace).
+    pub fn with_trace<T: Trace + Clone>(tracer: T) -> Sha512_256<T> {
+        Sha512_256(construction64("SHA-512/256", consts::H0_512_256, tracer))
+    }
+}
+
+impl<T: Trace> Hash for Sha512_256<T> {
+    type Digest = [u8; 32];
+    type Block = Block128;
+
+    fn hash(&self, preimage: &[u8]) -> Self::Digest {
+        digest(self.0.hash(preimage))
+    }
+}
+
+impl<T: Trace> IncrementalHash for Sha512_256<T> {
+    type State = MerkleDamgardState<Sha512State>;
+
+    fn init(&self) -> Self::State {
+        self.0.init()
+    }
+
+    fn update(&self, state: &mut Self::State, data: &[u8]) {
+        self.0.update(state, data)
+    }
+
+    fn finalize(&self, state: Self::State) -> Self::Digest {
+        digest(self.0.finalize(state))
+    }
+}
+
+/// The SHA-512/t IV generation function of FIPS 180-4, which derives the
+/// initial state of SHA-512 truncated to $t$ bits.
+///
+/// Every word of the [SHA-512 initial state](consts::H0_512) is XORed with
+/// `a5a5a5a5a5a5a5a5`, and the result is used as the initial state for
+/// hashing the ASCII string `SHA-512/t` with SHA-512, where $t$ is written in
+/// decimal. The final state of that hash is the initial state of
+/// SHA-512/$t$. Because the initial states differ, SHA-512/$t$ digests are
+/// unrelated to truncated SHA-512 digests, even for the same $t$.
+///
+/// # Panics
+///
+/// FIPS 180-4 only defines SHA-512/$t$ for $0 < t < 512$, and not for $t =
+/// 384$, which would clash with [SHA-384](Sha384).
+#[docext]
+pub fn sha512_t_iv(t: u32) -> Sha512State {
+    assert!(t > 0 && t < 512 && t != 384, "no SHA-512/{t}");
+    let iv = consts::H0_512.map(|w| w ^ 0xa5a5a5a5a5a5a5a5);
+    let hash = construction64("SHA-512", iv, NoTrace);
+    hash.hash(format!("SHA-512/{t}").as_bytes())
+}
+
+/// The [construction](Construction64) of a SHA-2 hash with 64-bit words,
+/// starting from the given initial state and reporting to the tracer under
+/// the given name.
+fn construction64<T: Trace + Clone>(
+    algorithm: &'static str,
+    iv: Sha512State,
+    tracer: T,
+) -> Construction64<T> {
+    MerkleDamgard::with_trace(
+        DaviesMeyer::new(
+            Shacal2_64(tracer.clone()),
+            ModularAddition(Default::default()),
+        ),
+        LengthPadding128(()),
+        iv,
+        WordTrace { algorithm, tracer },
+    )
+}
+
+impl<T: Trace> BlockEncrypt for Shacal1<T> {
     type EncryptionBlock = Sha1State;
     type EncryptionKey = Block;
 
@@ -270,11 +607,7 @@ impl BlockEncrypt for Shacal1 {
         let block = key;
 
         // Initialize the message schedule.
-        let mut schedule = [0; 16];
-        schedule
-            .iter_mut()
-            .zip(block.array_chunks::<4>())
-            .for_each(|(s, b)| *s = u32::from_be_bytes(*b));
+        let mut schedule: [u32; 16] = from_be_bytes(&block);
 
         // Execute the rounds.
         let mut a = state[0];
@@ -295,6 +628,11 @@ impl BlockEncrypt for Shacal1 {
             c = b.rotate_left(30);
             b = a;
             a = temp;
+            self.0.on_round(
+                "SHACAL-1",
+                usize::try_from(t).unwrap(),
+                &to_be_bytes::<5, 20>([a, b, c, d, e]),
+            );
 
             // Update the message schedule.
             let next = (schedule[13] ^ schedule[8] ^ schedule[2] ^ schedule[0]).rotate_left(1);
@@ -306,7 +644,7 @@ impl BlockEncrypt for Shacal1 {
     }
 }
 
-impl BlockEncrypt for Shacal2 {
+impl<T: Trace> BlockEncrypt for Shacal2<T> {
     type EncryptionBlock = Sha2State;
     type EncryptionKey = Block;
 
@@ -319,11 +657,7 @@ impl BlockEncrypt for Shacal2 {
         let block = key;
 
         // Initialize the message schedule.
-        let mut schedule = [0; 16];
-        schedule
-            .iter_mut()
-            .zip(block.array_chunks::<4>())
-            .for_each(|(s, b)| *s = u32::from_be_bytes(*b));
+        let mut schedule: [u32; 16] = from_be_bytes(&block);
 
         // Execute the rounds.
         let mut a = state[0];
@@ -340,7 +674,7 @@ impl BlockEncrypt for Shacal2 {
             let temp1 = h
                 .wrapping_add(uppercase_sigma_1(e))
                 .wrapping_add(ch(e, f, g))
-                .wrapping_add(KT_256[t])
+                .wrapping_add(consts::KT_256[t])
                 .wrapping_add(wt);
             let temp2 = uppercase_sigma_0(a).wrapping_add(maj(a, b, c));
             h = g;
@@ -351,6 +685,11 @@ impl BlockEncrypt for Shacal2 {
             c = b;
             b = a;
             a = temp1.wrapping_add(temp2);
+            self.0.on_round(
+                "SHACAL-2",
+                t,
+                &to_be_bytes::<8, 32>([a, b, c, d, e, f, g, h]),
+            );
 
             // Update the message schedule.
             let next = lowercase_sigma_1(schedule[14])
@@ -365,6 +704,59 @@ impl BlockEncrypt for Shacal2 {
     }
 }
 
+impl<T: Trace> BlockEncrypt for Shacal2_64<T> {
+    type EncryptionBlock = Sha512State;
+    type EncryptionKey = Block128;
+
+    fn encrypt(
+        &self,
+        data: Self::EncryptionBlock,
+        key: Self::EncryptionKey,
+    ) -> Self::EncryptionBlock {
+        let state = data;
+        let block = key;
+
+        // Initialize the message schedule.
+        let mut schedule: [u64; 16] = read_words(&block);
+
+        // Execute the rounds.
+        let [mut a, mut b, mut c, mut d, mut e, mut f, mut g, mut h] = state;
+        #[allow(clippy::needless_range_loop)]
+        for t in 0..80 {
+            let wt = schedule[0];
+            let temp1 = h
+                .wrapping_add(uppercase_sigma_1_512(e))
+                .wrapping_add(ch(e, f, g))
+                .wrapping_add(consts::KT_512[t])
+                .wrapping_add(wt);
+            let temp2 = uppercase_sigma_0_512(a).wrapping_add(maj(a, b, c));
+            h = g;
+            g = f;
+            f = e;
+            e = d.wrapping_add(temp1);
+            d = c;
+            c = b;
+            b = a;
+            a = temp1.wrapping_add(temp2);
+            self.0.on_round(
+                "SHACAL-2-64",
+                t,
+                &digest::<u64, 8, 64>([a, b, c, d, e, f, g, h]),
+            );
+
+            // Update the message schedule.
+            let next = lowercase_sigma_1_512(schedule[14])
+                .wrapping_add(schedule[9])
+                .wrapping_add(lowercase_sigma_0_512(schedule[1]))
+                .wrapping_add(schedule[0]);
+            schedule.rotate_left(1);
+            schedule[15] = next;
+        }
+
+        [a, b, c, d, e, f, g, h]
+    }
+}
+
 /// Helper function $f_t$ used by [SHA-1](Sha1).
 ///
 /// Uses [$Ch$](ch), [$Maj$](maj), and [$Parity$](parity) functions.
@@ -389,31 +781,32 @@ pub fn ft(t: u32, x: u32, y: u32, z: u32) -> u32 {
 /// Round constant $K_t$ used by [SHA-1](Sha1).
 #[docext]
 pub fn kt(t: u32) -> u32 {
-    match t {
-        0..=19 => 0x5a827999,
-        20..=39 => 0x6ed9eba1,
-        40..=59 => 0x8f1bbcdc,
-        _ => 0xca62c1d6,
-    }
+    consts::KT_1[usize::try_from(t.min(79) / 20).unwrap()]
 }
 
-/// Helper function $Ch$.
+/// Helper function $Ch$, on 32-bit or 64-bit words.
 ///
 /// $$
 /// Ch(x, y, z) = (x \land y) \oplus (\neg x \land z)
 /// $$
 #[docext]
-pub fn ch(x: u32, y: u32, z: u32) -> u32 {
+pub fn ch<W>(x: W, y: W, z: W) -> W
+where
+    W: Copy + BitAnd<Output = W> + BitXor<Output = W> + Not<Output = W>,
+{
     (x & y) ^ ((!x) & z)
 }
 
-/// Helper function $Maj$.
+/// Helper function $Maj$, on 32-bit or 64-bit words.
 ///
 /// $$
 /// Maj(x, y, z) = (x \land y) \oplus (x \land z) \oplus (y \land z)
 /// $$
 #[docext]
-pub fn maj(x: u32, y: u32, z: u32) -> u32 {
+pub fn maj<W>(x: W, y: W, z: W) -> W
+where
+    W: Copy + BitAnd<Output = W> + BitXor<Output = W>,
+{
     (x & y) ^ (x & z) ^ (y & z)
 }
 
@@ -477,27 +870,189 @@ pub fn lowercase_sigma_1(x: u32) -> u32 {
     x.rotate_right(17) ^ x.rotate_right(19) ^ (x >> 10)
 }
 
+/// Helper function $\Sigma_0^{512}$.
+///
+/// $$
+/// \Sigma_0^{512}(x) = \mathrm{ROTR}(x, 28) \oplus \mathrm{ROTR}(x, 34) \oplus
+/// \mathrm{ROTR}(x, 39) $$
+///
+/// Where $\mathrm{ROTR}$ is bitwise rotation to the right.
+#[docext]
+pub fn uppercase_sigma_0_512(x: u64) -> u64 {
+    x.rotate_right(28) ^ x.rotate_right(34) ^ x.rotate_right(39)
+}
+
+/// Helper function $\Sigma_1^{512}$.
+///
+/// $$
+/// \Sigma_1^{512}(x) = \mathrm{ROTR}(x, 14) \oplus \mathrm{ROTR}(x, 18) \oplus
+/// \mathrm{ROTR}(x, 41) $$
+///
+/// Where $\mathrm{ROTR}$ is bitwise rotation to the right.
+#[docext]
+pub fn uppercase_sigma_1_512(x: u64) -> u64 {
+    x.rotate_right(14) ^ x.rotate_right(18) ^ x.rotate_right(41)
+}
+
+/// Helper function $\sigma_0^{512}$.
+///
+/// $$
+/// \sigma_0^{512}(x) = \mathrm{ROTR}(x, 1) \oplus \mathrm{ROTR}(x, 8) \oplus
+/// (x \gg 7) $$
+///
+/// Where $\mathrm{ROTR}$ is bitwise rotation to the right, and $\gg$ is the
+/// bitwise right shift operation.
+#[docext]
+pub fn lowercase_sigma_0_512(x: u64) -> u64 {
+    x.rotate_right(1) ^ x.rotate_right(8) ^ (x >> 7)
+}
+
+/// Helper function $\sigma_1^{512}$.
+///
+/// $$
+/// \sigma_1^{512}(x) = \mathrm{ROTR}(x, 19) \oplus \mathrm{ROTR}(x, 61) \oplus
+/// (x \gg 6) $$
+///
+/// Where $\mathrm{ROTR}$ is bitwise rotation to the right, and $\gg$ is the
+/// bitwise right shift operation.
+#[docext]
+pub fn lowercase_sigma_1_512(x: u64) -> u64 {
+    x.rotate_right(19) ^ x.rotate_right(61) ^ (x >> 6)
+}
+
 /// Because the new state is derived by adding the "working variables" to the
 /// current state, the [Davies-Meyer step](DaviesMeyerStep) in SHA-1 and SHA-2
 /// is modular addition.
 #[derive(Debug)]
 pub struct ModularAddition<State>(PhantomData<State>);
 
-impl<State> DaviesMeyerStep for ModularAddition<State>
-where
-    State: AsMut<[u32]> + AsRef<[u32]>,
-{
-    type State = State;
+impl<W: Word, const N: usize> DaviesMeyerStep for ModularAddition<[W; N]> {
+    type State = [W; N];
 
     fn step(&self, prev: Self::State, mut new: Self::State) -> Self::State {
-        new.as_mut()
-            .iter_mut()
-            .zip(prev.as_ref().iter())
-            .for_each(|(n, p)| *n = n.wrapping_add(*p));
+        new.iter_mut()
+            .zip(prev)
+            .for_each(|(n, p)| *n = n.wrapping_add(p));
         new
     }
 }
 
+/// A word of the state, 32 bits for SHA-1 and SHA-256, or 64 bits for
+/// SHA-512.
+trait Word: Copy + Default {
+    /// The size of the word in bytes.
+    const BYTES: usize;
+
+    fn wrapping_add(self, rhs: Self) -> Self;
+
+    /// Write the first `out.len()` bytes of the big-endian encoding.
+    fn write_be(self, out: &mut [u8]);
+
+    /// Read a word from exactly [`BYTES`](Word::BYTES) big-endian bytes.
+    fn read_be(bytes: &[u8]) -> Self;
+}
+
+impl Word for u32 {
+    const BYTES: usize = 4;
+
+    fn wrapping_add(self, rhs: Self) -> Self {
+        u32::wrapping_add(self, rhs)
+    }
+
+    fn write_be(self, out: &mut [u8]) {
+        out.copy_from_slice(&self.to_be_bytes()[..out.len()]);
+    }
+
+    fn read_be(bytes: &[u8]) -> Self {
+        u32::from_be_bytes(bytes.try_into().unwrap())
+    }
+}
+
+impl Word for u64 {
+    const BYTES: usize = 8;
+
+    fn wrapping_add(self, rhs: Self) -> Self {
+        u64::wrapping_add(self, rhs)
+    }
+
+    fn write_be(self, out: &mut [u8]) {
+        out.copy_from_slice(&self.to_be_bytes()[..out.len()]);
+    }
+
+    fn read_be(bytes: &[u8]) -> Self {
+        u64::from_be_bytes(bytes.try_into().unwrap())
+    }
+}
+
+/// Encode the final state as a big-endian digest, truncating it to `N` bytes.
+fn digest<W: Word, const S: usize, const N: usize>(state: [W; S]) -> [u8; N] {
+    assert!(N <= W::BYTES * S, "digest longer than the state");
+    let mut result = [0; N];
+    result
+        .chunks_mut(W::BYTES)
+        .zip(state)
+        .for_each(|(r, w)| w.write_be(r));
+    result
+}
+
+/// Decode big-endian bytes into words. `B` must be a multiple of the word
+/// size.
+fn read_words<W: Word, const B: usize, const N: usize>(bytes: &[u8; B]) -> [W; N] {
+    assert_eq!(B, W::BYTES * N, "wrong number of bytes");
+    let mut words = [W::default(); N];
+    words
+        .iter_mut()
+        .zip(bytes.chunks_exact(W::BYTES))
+        .for_each(|(w, b)| *w = W::read_be(b));
+    words
+}
+
+/// Encode words, e.g. a [state](Sha2State), as bytes. Each word is encoded
+/// big-endian, and the words keep their order, so `B` must be `4 * N`.
+///
+/// This is the inverse of [`from_be_bytes`].
+pub fn to_be_bytes<const N: usize, const B: usize>(words: [u32; N]) -> [u8; B] {
+    assert_eq!(B, 4 * N, "wrong number of bytes");
+    digest(words)
+}
+
+/// Decode bytes, e.g. a [preimage block](Block), into words. Every 4 bytes are
+/// read as a big-endian word, so `B` must be `4 * N`.
+///
+/// This is the inverse of [`to_be_bytes`].
+pub fn from_be_bytes<const B: usize, const N: usize>(bytes: &[u8; B]) -> [u32; N] {
+    read_words(bytes)
+}
+
+/// Reports the [Merkle-Damgard](MerkleDamgard) state of SHA-1 and SHA-2 to a
+/// [tracer](Trace), encoded big-endian like the digest.
+#[derive(Debug)]
+struct WordTrace<T> {
+    algorithm: &'static str,
+    tracer: T,
+}
+
+impl<T: Trace> MerkleDamgardTrace<Sha1State> for WordTrace<T> {
+    fn on_block(&self, block: usize, state: &Sha1State) {
+        self.tracer
+            .on_block(self.algorithm, block, &to_be_bytes::<5, 20>(*state));
+    }
+}
+
+impl<T: Trace> MerkleDamgardTrace<Sha2State> for WordTrace<T> {
+    fn on_block(&self, block: usize, state: &Sha2State) {
+        self.tracer
+            .on_block(self.algorithm, block, &to_be_bytes::<8, 32>(*state));
+    }
+}
+
+impl<T: Trace> MerkleDamgardTrace<Sha512State> for WordTrace<T> {
+    fn on_block(&self, block: usize, state: &Sha512State) {
+        self.tracer
+            .on_block(self.algorithm, block, &digest::<u64, 8, 64>(*state));
+    }
+}
+
 /// SHA-2 length padding.
 ///
 /// The preimage is padded by appending a single 1 bit, followed by as many bits
@@ -505,46 +1060,91 @@ where
 /// length_ of the preimage encoded as an unsigned big-endian 64 bit integer.
 /// This results in a [Merkle-Damgard compliant padding](MerkleDamgardPad) into
 /// blocks of 512 bits.
-#[derive(Debug)]
+///
+/// # Panics
+///
+/// The bit length of the preimage must fit into 64 bits, so padding a preimage
+/// of $2^{61}$ bytes or more panics. FIPS 180-4 doesn't define SHA-1 or SHA-256
+/// for such preimages.
+#[docext]
+#[derive(Debug, Default)]
 pub struct LengthPadding(());
 
 impl MerkleDamgardPad for LengthPadding {
     type Block = Block;
 
-    fn pad(&self, preimage: &[u8]) -> impl Iterator<Item = Self::Block> {
-        preimage
-            .chunks(BLOCK_SIZE)
-            .chain(
-                // If the input is a multiple of the block size, a full block of padding needs to
-                // be added.
-                iter::once([].as_slice()).take(if preimage.len() % BLOCK_SIZE == 0 {
-                    1
-                } else {
-                    0
-                }),
-            )
-            .flat_map(|chunk| {
-                if chunk.len() == BLOCK_SIZE {
-                    // This block does not need padding.
-                    vec![chunk.try_into().unwrap()]
-                } else if BLOCK_SIZE - chunk.len() <= 8 {
-                    // This block requires an additional block of padding.
-                    let mut block = [0u8; BLOCK_SIZE];
-                    block[..chunk.len()].copy_from_slice(chunk);
-                    block[chunk.len()] = 0x80;
-                    let mut next = [0u8; BLOCK_SIZE];
-                    next[BLOCK_SIZE - 8..]
-                        .copy_from_slice(&u64::try_from(8 * preimage.len()).unwrap().to_be_bytes());
-                    vec![block, next]
-                } else {
-                    // This block needs to be padded.
-                    let mut block = [0u8; BLOCK_SIZE];
-                    block[..chunk.len()].copy_from_slice(chunk);
-                    block[chunk.len()] = 0x80;
-                    block[BLOCK_SIZE - 8..]
-                        .copy_from_slice(&u64::try_from(8 * preimage.len()).unwrap().to_be_bytes());
-                    vec![block]
-                }
-            })
+    fn split<'a>(&self, preimage: &'a [u8]) -> (impl Iterator<Item = &'a Self::Block>, &'a [u8])
+    where
+        Self::Block: 'a,
+    {
+        split_blocks(preimage)
+    }
+
+    fn pad_tail(&self, tail: &[u8], len: u64) -> impl Iterator<Item = Self::Block> {
+        pad_length::<BLOCK_SIZE, 8>(tail, len)
+    }
+}
+
+/// SHA-512 length padding.
+///
+/// The same as [SHA-256 padding](LengthPadding), except that the preimage is
+/// padded into blocks of 1024 bits, and the bit length is encoded as an
+/// unsigned big-endian 128-bit integer, following a multiple of 1024 - 128 =
+/// 896 bits. Every preimage length fits, so unlike SHA-256 padding this
+/// padding never panics.
+#[derive(Debug, Default)]
+pub struct LengthPadding128(());
+
+impl MerkleDamgardPad for LengthPadding128 {
+    type Block = Block128;
+
+    fn split<'a>(&self, preimage: &'a [u8]) -> (impl Iterator<Item = &'a Self::Block>, &'a [u8])
+    where
+        Self::Block: 'a,
+    {
+        split_blocks(preimage)
+    }
+
+    fn pad_tail(&self, tail: &[u8], len: u64) -> impl Iterator<Item = Self::Block> {
+        pad_length::<BLOCK_SIZE_128, 16>(tail, len)
+    }
+}
+
+/// Split the preimage into blocks of `N` bytes and the remaining tail.
+fn split_blocks<const N: usize>(preimage: &[u8]) -> (impl Iterator<Item = &[u8; N]>, &[u8]) {
+    let (blocks, tail) = preimage.split_at(preimage.len() - preimage.len() % N);
+    (blocks.array_chunks(), tail)
+}
+
+/// Pad the tail into one or two blocks of `N` bytes, ending with the bit
+/// length of the preimage as an `L`-byte big-endian integer. `len` is the
+/// length of the preimage in bytes.
+///
+/// The bit length is computed as a `u128`, which can't overflow. It only has to
+/// fit into `L` bytes, which is the case for every `len` if `L` is 16.
+fn pad_length<const N: usize, const L: usize>(
+    tail: &[u8],
+    len: u64,
+) -> impl Iterator<Item = [u8; N]> {
+    debug_assert!(tail.len() < N && L <= 16);
+    let bit_len = (u128::from(len) * 8).to_be_bytes();
+    let (high, bit_len) = bit_len.split_at(bit_len.len() - L);
+    assert!(
+        high.iter().all(|&b| b == 0),
+        "preimage of {len} bytes is too long for a {}-bit length",
+        8 * L
+    );
+
+    let mut block = [0u8; N];
+    block[..tail.len()].copy_from_slice(tail);
+    block[tail.len()] = 0x80;
+    let mut next = [0u8; N];
+    if N - tail.len() <= L {
+        // This block requires an additional block of padding.
+        next[N - L..].copy_from_slice(bit_len);
+        [block, next].into_iter().take(2)
+    } else {
+        block[N - L..].copy_from_slice(bit_len);
+        [block, next].into_iter().take(1)
     }
 }
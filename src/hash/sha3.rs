@@ -19,71 +19,358 @@
 //! if it is too small, the hash function becomes vulnerable to attacks.
 //!
 //! The internal permutation of the algorithm is [Keccak-p](keccak_p).
+//!
+//! Before standardizing SHA-3, NIST added a [domain
+//! separator](pad10star1_with_domain) to the padding. The original Keccak
+//! submission, without it, is still widely used, most notably by Ethereum, and
+//! is provided as [`Keccak256`] and [`Keccak512`].
+//!
+//! Keccak-p is defined for several [lane](Lane) widths. SHA-3 uses the largest
+//! one, but the smaller permutations [`keccak_p_400`] and [`keccak_p_800`],
+//! along with [a sponge](KeccakSponge) built on them, are provided for
+//! experimenting with states small enough to inspect by hand.
 
-use {super::Hash, crate::util::IterChunks, docext::docext, std::iter};
+use {
+    super::{BitHash, Hash, IncrementalHash},
+    crate::trace::{NoTrace, Trace},
+    docext::docext,
+    std::marker::PhantomData,
+};
 
+mod lane;
 mod rctable;
 
-pub use rctable::rctable;
+pub use {lane::Lane, rctable::rctable};
 
 /// [SHA-3 hash](self) with 224-bit output.
-#[derive(Debug, Default)]
-pub struct Sha3_224(());
+///
+/// The state after every round of [Keccak-p](keccak_p) can be
+/// [traced](crate::trace) by constructing the hash with
+/// [`with_trace`](Sha3_224::with_trace).
+#[derive(Debug)]
+pub struct Sha3_224<T = NoTrace>(T);
+
+impl Default for Sha3_224 {
+    fn default() -> Self {
+        Self(NoTrace)
+    }
+}
 
-impl Hash for Sha3_224 {
+impl Sha3_224 {
+    /// Report the state after every round of [Keccak-p](keccak_p) to the
+    /// [tracer](Trace), under the name [`KECCAK_P`].
+    pub fn with_trace<T: Trace>(tracer: T) -> Sha3_224<T> {
+        Sha3_224(tracer)
+    }
+}
+
+impl<T: Trace> Hash for Sha3_224<T> {
     type Digest = [u8; 28];
     type Block = [u8; 144];
 
     fn hash(&self, preimage: &[u8]) -> Self::Digest {
-        sponge::<144, 28>(preimage)
+        sponge_traced::<144, 28>(preimage, 8 * preimage.len(), SHA3_DOMAIN, &self.0)
+    }
+}
+
+impl<T: Trace> BitHash for Sha3_224<T> {
+    fn hash_bits(&self, preimage: &[u8], bit_len: usize) -> Self::Digest {
+        sponge_traced::<144, 28>(preimage, bit_len, SHA3_DOMAIN, &self.0)
+    }
+}
+
+impl<T: Trace> IncrementalHash for Sha3_224<T> {
+    type State = SpongeState<144>;
+
+    fn init(&self) -> Self::State {
+        SpongeState::new(SHA3_DOMAIN)
+    }
+
+    fn update(&self, state: &mut Self::State, data: &[u8]) {
+        state.update(data, &self.0)
+    }
+
+    fn finalize(&self, state: Self::State) -> Self::Digest {
+        state.finalize(&self.0)
     }
 }
 
 /// [SHA-3 hash](self) with 256-bit output.
-#[derive(Debug, Default)]
-pub struct Sha3_256(());
+///
+/// The state after every round of [Keccak-p](keccak_p) can be
+/// [traced](crate::trace) by constructing the hash with
+/// [`with_trace`](Sha3_256::with_trace).
+#[derive(Debug)]
+pub struct Sha3_256<T = NoTrace>(T);
+
+impl Default for Sha3_256 {
+    fn default() -> Self {
+        Self(NoTrace)
+    }
+}
 
-impl Hash for Sha3_256 {
+impl Sha3_256 {
+    /// Report the state after every round of [Keccak-p](keccak_p) to the
+    /// [tracer](Trace), under the name [`KECCAK_P`].
+    pub fn with_trace<T: Trace>(tracer: T) -> Sha3_256<T> {
+        Sha3_256(tracer)
+    }
+}
+
+impl<T: Trace> Hash for Sha3_256<T> {
     type Digest = [u8; 32];
     type Block = [u8; 136];
 
-    fn hash(&self, input: &[u8]) -> Self::Digest {
-        sponge::<136, 32>(input)
+    fn hash(&self, preimage: &[u8]) -> Self::Digest {
+        sponge_traced::<136, 32>(preimage, 8 * preimage.len(), SHA3_DOMAIN, &self.0)
+    }
+}
+
+impl<T: Trace> BitHash for Sha3_256<T> {
+    fn hash_bits(&self, preimage: &[u8], bit_len: usize) -> Self::Digest {
+        sponge_traced::<136, 32>(preimage, bit_len, SHA3_DOMAIN, &self.0)
+    }
+}
+
+impl<T: Trace> IncrementalHash for Sha3_256<T> {
+    type State = SpongeState<136>;
+
+    fn init(&self) -> Self::State {
+        SpongeState::new(SHA3_DOMAIN)
+    }
+
+    fn update(&self, state: &mut Self::State, data: &[u8]) {
+        state.update(data, &self.0)
+    }
+
+    fn finalize(&self, state: Self::State) -> Self::Digest {
+        state.finalize(&self.0)
     }
 }
 
 /// [SHA-3 hash](self) with 384-bit output.
-#[derive(Debug, Default)]
-pub struct Sha3_384(());
+///
+/// The state after every round of [Keccak-p](keccak_p) can be
+/// [traced](crate::trace) by constructing the hash with
+/// [`with_trace`](Sha3_384::with_trace).
+#[derive(Debug)]
+pub struct Sha3_384<T = NoTrace>(T);
 
-impl Hash for Sha3_384 {
+impl Default for Sha3_384 {
+    fn default() -> Self {
+        Self(NoTrace)
+    }
+}
+
+impl Sha3_384 {
+    /// Report the state after every round of [Keccak-p](keccak_p) to the
+    /// [tracer](Trace), under the name [`KECCAK_P`].
+    pub fn with_trace<T: Trace>(tracer: T) -> Sha3_384<T> {
+        Sha3_384(tracer)
+    }
+}
+
+impl<T: Trace> Hash for Sha3_384<T> {
     type Digest = [u8; 48];
     type Block = [u8; 104];
 
-    fn hash(&self, input: &[u8]) -> Self::Digest {
-        sponge::<104, 48>(input)
+    fn hash(&self, preimage: &[u8]) -> Self::Digest {
+        sponge_traced::<104, 48>(preimage, 8 * preimage.len(), SHA3_DOMAIN, &self.0)
+    }
+}
+
+impl<T: Trace> BitHash for Sha3_384<T> {
+    fn hash_bits(&self, preimage: &[u8], bit_len: usize) -> Self::Digest {
+        sponge_traced::<104, 48>(preimage, bit_len, SHA3_DOMAIN, &self.0)
+    }
+}
+
+impl<T: Trace> IncrementalHash for Sha3_384<T> {
+    type State = SpongeState<104>;
+
+    fn init(&self) -> Self::State {
+        SpongeState::new(SHA3_DOMAIN)
+    }
+
+    fn update(&self, state: &mut Self::State, data: &[u8]) {
+        state.update(data, &self.0)
+    }
+
+    fn finalize(&self, state: Self::State) -> Self::Digest {
+        state.finalize(&self.0)
     }
 }
 
 /// [SHA-3 hash](self) with 512-bit output.
-#[derive(Debug, Default)]
-pub struct Sha3_512(());
+///
+/// The state after every round of [Keccak-p](keccak_p) can be
+/// [traced](crate::trace) by constructing the hash with
+/// [`with_trace`](Sha3_512::with_trace).
+#[derive(Debug)]
+pub struct Sha3_512<T = NoTrace>(T);
+
+impl Default for Sha3_512 {
+    fn default() -> Self {
+        Self(NoTrace)
+    }
+}
 
-impl Hash for Sha3_512 {
+impl Sha3_512 {
+    /// Report the state after every round of [Keccak-p](keccak_p) to the
+    /// [tracer](Trace), under the name [`KECCAK_P`].
+    pub fn with_trace<T: Trace>(tracer: T) -> Sha3_512<T> {
+        Sha3_512(tracer)
+    }
+}
+
+impl<T: Trace> Hash for Sha3_512<T> {
     type Digest = [u8; 64];
     type Block = [u8; 72];
 
-    fn hash(&self, input: &[u8]) -> Self::Digest {
-        sponge::<72, 64>(input)
+    fn hash(&self, preimage: &[u8]) -> Self::Digest {
+        sponge_traced::<72, 64>(preimage, 8 * preimage.len(), SHA3_DOMAIN, &self.0)
+    }
+}
+
+impl<T: Trace> BitHash for Sha3_512<T> {
+    fn hash_bits(&self, preimage: &[u8], bit_len: usize) -> Self::Digest {
+        sponge_traced::<72, 64>(preimage, bit_len, SHA3_DOMAIN, &self.0)
+    }
+}
+
+impl<T: Trace> IncrementalHash for Sha3_512<T> {
+    type State = SpongeState<72>;
+
+    fn init(&self) -> Self::State {
+        SpongeState::new(SHA3_DOMAIN)
+    }
+
+    fn update(&self, state: &mut Self::State, data: &[u8]) {
+        state.update(data, &self.0)
+    }
+
+    fn finalize(&self, state: Self::State) -> Self::Digest {
+        state.finalize(&self.0)
+    }
+}
+
+/// The original [Keccak](self) hash with 256-bit output, as submitted to the
+/// SHA-3 competition and used by Ethereum.
+///
+/// This is [SHA3-256](Sha3_256) with the [domain separator](KECCAK_DOMAIN) of
+/// the submission instead of the one which FIPS 202 added, so the two give
+/// different digests for every input.
+///
+/// The state after every round of [Keccak-p](keccak_p) can be
+/// [traced](crate::trace) by constructing the hash with
+/// [`with_trace`](Keccak256::with_trace).
+#[derive(Debug)]
+pub struct Keccak256<T = NoTrace>(T);
+
+impl Default for Keccak256 {
+    fn default() -> Self {
+        Self(NoTrace)
+    }
+}
+
+impl Keccak256 {
+    /// Report the state after every round of [Keccak-p](keccak_p) to the
+    /// [tracer](Trace), under the name [`KECCAK_P`].
+    pub fn with_trace<T: Trace>(tracer: T) -> Keccak256<T> {
+        Keccak256(tracer)
+    }
+}
+
+impl<T: Trace> Hash for Keccak256<T> {
+    type Digest = [u8; 32];
+    type Block = [u8; 136];
+
+    fn hash(&self, preimage: &[u8]) -> Self::Digest {
+        sponge_traced::<136, 32>(preimage, 8 * preimage.len(), KECCAK_DOMAIN, &self.0)
+    }
+}
+
+impl<T: Trace> IncrementalHash for Keccak256<T> {
+    type State = SpongeState<136>;
+
+    fn init(&self) -> Self::State {
+        SpongeState::new(KECCAK_DOMAIN)
+    }
+
+    fn update(&self, state: &mut Self::State, data: &[u8]) {
+        state.update(data, &self.0)
+    }
+
+    fn finalize(&self, state: Self::State) -> Self::Digest {
+        state.finalize(&self.0)
+    }
+}
+
+/// The original [Keccak](self) hash with 512-bit output, as submitted to the
+/// SHA-3 competition and used by Ethereum.
+///
+/// This is [SHA3-512](Sha3_512) with the [domain separator](KECCAK_DOMAIN) of
+/// the submission instead of the one which FIPS 202 added, so the two give
+/// different digests for every input.
+///
+/// The state after every round of [Keccak-p](keccak_p) can be
+/// [traced](crate::trace) by constructing the hash with
+/// [`with_trace`](Keccak512::with_trace).
+#[derive(Debug)]
+pub struct Keccak512<T = NoTrace>(T);
+
+impl Default for Keccak512 {
+    fn default() -> Self {
+        Self(NoTrace)
+    }
+}
+
+impl Keccak512 {
+    /// Report the state after every round of [Keccak-p](keccak_p) to the
+    /// [tracer](Trace), under the name [`KECCAK_P`].
+    pub fn with_trace<T: Trace>(tracer: T) -> Keccak512<T> {
+        Keccak512(tracer)
+    }
+}
+
+impl<T: Trace> Hash for Keccak512<T> {
+    type Digest = [u8; 64];
+    type Block = [u8; 72];
+
+    fn hash(&self, preimage: &[u8]) -> Self::Digest {
+        sponge_traced::<72, 64>(preimage, 8 * preimage.len(), KECCAK_DOMAIN, &self.0)
+    }
+}
+
+impl<T: Trace> IncrementalHash for Keccak512<T> {
+    type State = SpongeState<72>;
+
+    fn init(&self) -> Self::State {
+        SpongeState::new(KECCAK_DOMAIN)
+    }
+
+    fn update(&self, state: &mut Self::State, data: &[u8]) {
+        state.update(data, &self.0)
+    }
+
+    fn finalize(&self, state: Self::State) -> Self::Digest {
+        state.finalize(&self.0)
     }
 }
 
 const NUM_ROWS: usize = 5;
 const NUM_COLS: usize = 5;
 
+/// The size of the [state](State) in bytes.
+const STATE_BYTES: usize = 8 * NUM_ROWS * NUM_COLS;
+
 /// Number of rounds in the [Keccak-p permutation](keccak_p).
 pub const NUM_ROUNDS: usize = 24;
 
+/// The name under which the rounds of [Keccak-p](keccak_p) are
+/// [traced](crate::trace).
+pub const KECCAK_P: &str = "Keccak-p[1600, 24]";
+
 /// The internal state of the [SHA-3 algorithm](self), also referred to as $A$.
 /// This is a 5x5 matrix of 64-bit words.
 ///
@@ -92,75 +379,234 @@ pub const NUM_ROUNDS: usize = 24;
 /// $z \in \{ 0, 1, \dots, 63 \}$. The bit can be omitted to access the entire
 /// word.
 #[docext]
-pub type State = [[u64; NUM_COLS]; NUM_ROWS];
+pub type State = KeccakState<u64>;
+
+/// The [state](State) of Keccak-p with a different [lane](Lane) width, used by
+/// [`keccak_p_400`] and [`keccak_p_800`]. All step functions work on any lane
+/// width, with $z \in \{0, 1, \dots, w - 1\}$.
+#[docext]
+pub type KeccakState<L> = [[L; NUM_COLS]; NUM_ROWS];
 
-/// Offsets used by the $\rho$ step.
+/// Offsets used by the $\rho$ step, as generated by [`rho_offsets`]. These are
+/// not reduced modulo the lane width, since the rotation is.
 #[docext]
-pub const RHO_OFFSETS: [[u32; NUM_COLS]; NUM_ROWS] = [
-    [0, 1, 190, 28, 91],
-    [36, 300, 6, 55, 276],
-    [3, 10, 171, 153, 231],
-    [105, 45, 15, 21, 136],
-    [210, 66, 253, 120, 78],
-];
-
-/// The round constants used by the $\iota$ step. These were generated via
+pub const RHO_OFFSETS: [[u32; NUM_COLS]; NUM_ROWS] = rho_offsets();
+
+/// The round constants used by the $\iota$ step, as generated by
 /// [`rctable`](rctable::rctable).
 #[docext]
-pub const RC: [u64; NUM_ROUNDS] = [
-    0x0000000000000001,
-    0x0000000000008082,
-    0x800000000000808a,
-    0x8000000080008000,
-    0x000000000000808b,
-    0x0000000080000001,
-    0x8000000080008081,
-    0x8000000000008009,
-    0x000000000000008a,
-    0x0000000000000088,
-    0x0000000080008009,
-    0x000000008000000a,
-    0x000000008000808b,
-    0x800000000000008b,
-    0x8000000000008089,
-    0x8000000000008003,
-    0x8000000000008002,
-    0x8000000000000080,
-    0x000000000000800a,
-    0x800000008000000a,
-    0x8000000080008081,
-    0x8000000000008080,
-    0x0000000080000001,
-    0x8000000080008008,
-];
+pub const RC: [u64; NUM_ROUNDS] = rctable();
+
+/// Generate the [offsets](RHO_OFFSETS) used by the $\rho$ step, as specified in
+/// Section 3.2.2 of the specification.
+///
+/// Starting at $(x, y) = (1, 0)$, the offset of the $t$-th position visited
+/// is the triangular number $(t + 1)(t + 2) / 2$, and the next position is $(y,
+/// 2x + 3y \bmod 5)$. This walk visits every position except $(0, 0)$, whose
+/// offset is zero.
+#[docext]
+pub const fn rho_offsets() -> [[u32; NUM_COLS]; NUM_ROWS] {
+    let mut offsets = [[0; NUM_COLS]; NUM_ROWS];
+    let (mut x, mut y) = (1, 0);
+    let mut t = 0;
+    while t < 24 {
+        offsets[y][x] = ((t + 1) * (t + 2) / 2) as u32;
+        (x, y) = (y, (2 * x + 3 * y) % NUM_ROWS);
+        t += 1;
+    }
+    offsets
+}
 
 /// The sponge construction with the rate (block size) `R` and output size `D`,
 /// and function [Keccak-p](keccak_p).
 ///
 /// This process is described in the [module documentation](self).
 pub fn sponge<const R: usize, const D: usize>(preimage: &[u8]) -> [u8; D] {
+    sponge_bits::<R, D>(preimage, 8 * preimage.len())
+}
+
+/// The [sponge construction](sponge) over the first `bit_len` bits of the
+/// preimage. See [`pad10star1_bits`] for how the bits are ordered.
+pub fn sponge_bits<const R: usize, const D: usize>(preimage: &[u8], bit_len: usize) -> [u8; D] {
+    sponge_traced::<R, D>(preimage, bit_len, SHA3_DOMAIN, &NoTrace)
+}
+
+/// The [sponge construction](sponge_bits) with the given [domain
+/// separator](pad10star1_with_domain), reporting every round of
+/// [Keccak-p](keccak_p) to the tracer.
+fn sponge_traced<const R: usize, const D: usize>(
+    preimage: &[u8],
+    bit_len: usize,
+    domain: u8,
+    trace: &impl Trace,
+) -> [u8; D] {
     let mut state = State::default();
 
     // Absorbing phase.
-    for block in pad10star1::<R>(preimage) {
-        block
-            .into_iter()
-            .chain(iter::repeat(0))
-            .chunks::<8>()
-            .zip(state.iter_mut().flatten())
-            .for_each(|(b, r)| *r ^= u64::from_le_bytes(b));
-        keccak_p(&mut state);
+    for block in pad10star1_with_domain::<R>(preimage, bit_len, domain) {
+        absorb(&mut state, block, trace);
     }
 
     // Squeezing phase.
+    squeeze::<R, D>(&state)
+}
+
+/// The [sponge construction](sponge) over Keccak-p with any [lane](Lane) width,
+/// with the rate (block size) `R` and output size `D` in bytes.
+///
+/// With 64-bit lanes and the parameters of SHA-3, this is SHA-3. With
+/// [`u16`] or [`u32`] lanes, the state is only 50 or 100 bytes, and the
+/// capacity, which is all that stands between the hash and its attacker, is
+/// smaller still. This makes it a toy for studying the sponge, e.g. by finding
+/// collisions through a tiny capacity, and not a hash to rely on.
+///
+/// The padding is [the one of SHA-3](pad10star1), including the domain
+/// separator. Unlike [`sponge`], the output can be longer than the rate: the
+/// state is permuted again for every further `R` bytes of output.
+#[derive(Debug, Default)]
+pub struct KeccakSponge<L, const R: usize, const D: usize>(PhantomData<L>);
+
+impl<L: Lane, const R: usize, const D: usize> Hash for KeccakSponge<L, R, D> {
+    type Digest = [u8; D];
+    type Block = [u8; R];
+
+    fn hash(&self, preimage: &[u8]) -> Self::Digest {
+        assert!(
+            R < NUM_ROWS * NUM_COLS * L::BYTES,
+            "rate leaves no capacity"
+        );
+        let mut state = KeccakState::<L>::default();
+        for block in pad10star1::<R>(preimage) {
+            absorb_lanes(&mut state, &block);
+            permute(&mut state, &NoTrace);
+        }
+        let mut output = [0; D];
+        for (i, chunk) in output.chunks_mut(R).enumerate() {
+            if i > 0 {
+                permute(&mut state, &NoTrace);
+            }
+            squeeze_lanes(&state, chunk);
+        }
+        output
+    }
+}
+
+/// The intermediate state of an [incremental](crate::IncrementalHash) [SHA-3
+/// hash](self) with the rate (block size) `R`.
+///
+/// Apart from the [Keccak-p state](State), this keeps the preimage bytes which
+/// don't yet form a full block. The padding only depends on these bytes, so
+/// unlike with [Merkle-Damgard](crate::MerkleDamgardState), the length of the
+/// preimage doesn't need to be tracked.
+#[derive(Debug, Clone)]
+pub struct SpongeState<const R: usize> {
+    state: State,
+    buffer: Vec<u8>,
+    domain: u8,
+}
+
+impl<const R: usize> SpongeState<R> {
+    fn new(domain: u8) -> Self {
+        Self {
+            state: State::default(),
+            buffer: Vec::with_capacity(R),
+            domain,
+        }
+    }
+
+    fn update(&mut self, mut data: &[u8], trace: &impl Trace) {
+        // Complete the buffered block first, if there is one.
+        if !self.buffer.is_empty() {
+            let n = data.len().min(R - self.buffer.len());
+            self.buffer.extend(&data[..n]);
+            data = &data[n..];
+            if self.buffer.len() < R {
+                return;
+            }
+            absorb::<R>(
+                &mut self.state,
+                self.buffer.as_slice().try_into().unwrap(),
+                trace,
+            );
+            self.buffer.clear();
+        }
+
+        // Absorb the full blocks straight from the input, and buffer the rest.
+        let mut chunks = data.array_chunks::<R>();
+        for block in chunks.by_ref() {
+            absorb(&mut self.state, *block, trace);
+        }
+        self.buffer.extend(chunks.remainder());
+    }
+
+    fn finalize<const D: usize>(mut self, trace: &impl Trace) -> [u8; D] {
+        let bit_len = 8 * self.buffer.len();
+        for block in pad10star1_with_domain::<R>(&self.buffer, bit_len, self.domain) {
+            absorb(&mut self.state, block, trace);
+        }
+        squeeze::<R, D>(&self.state)
+    }
+}
+
+/// Absorb a single block into the state.
+fn absorb<const R: usize>(state: &mut State, block: [u8; R], trace: &impl Trace) {
+    absorb_block(state, &block);
+    keccak_p_traced(state, trace);
+}
+
+/// Squeeze `D` bytes of output out of the state. `D` must not be larger than
+/// the rate `R`.
+fn squeeze<const R: usize, const D: usize>(state: &State) -> [u8; D] {
     let mut output = [0; D];
+    squeeze_bytes::<R>(state, &mut output);
+    output
+}
+
+/// XOR a block of `R` bytes into the first `R` bytes of the state, without
+/// applying [Keccak-p](keccak_p).
+///
+/// As described in Section B.1 of the specification, the bytes of the state
+/// are the words $A_{0, 0}, A_{1, 0}, \dots, A_{4, 0}, A_{0, 1}, \dots$, i.e.
+/// the row-major order of [`State`], and each word is encoded little-endian.
+/// The last `200 - R` bytes, the capacity, are never touched.
+///
+/// This is the inverse of [`squeeze_bytes`].
+#[docext]
+pub fn absorb_block<const R: usize>(state: &mut State, block: &[u8; R]) {
+    assert!(R <= STATE_BYTES, "rate larger than the state");
+    absorb_lanes(state, block);
+}
+
+/// [`absorb_block`] for any [lane](Lane) width.
+fn absorb_lanes<L: Lane>(state: &mut KeccakState<L>, block: &[u8]) {
+    block
+        .chunks(L::BYTES)
+        .zip(state.iter_mut().flatten())
+        .for_each(|(b, r)| {
+            // The last lane of the rate may be partial.
+            let mut lane = [0; 8];
+            lane[..b.len()].copy_from_slice(b);
+            *r ^= L::truncate(u64::from_le_bytes(lane));
+        });
+}
+
+/// Copy the first `output.len()` bytes of the state into `output`, using the
+/// byte order of [`absorb_block`]. Only the rate `R` is output, never the
+/// capacity, so `output` can't be longer than `R`.
+pub fn squeeze_bytes<const R: usize>(state: &State, output: &mut [u8]) {
+    assert!(R <= STATE_BYTES, "rate larger than the state");
+    assert!(output.len() <= R, "output longer than the rate");
+    squeeze_lanes(state, output);
+}
+
+/// [`squeeze_bytes`] for any [lane](Lane) width, without checking the rate.
+fn squeeze_lanes<L: Lane>(state: &KeccakState<L>, output: &mut [u8]) {
     state
         .iter()
         .flatten()
-        .flat_map(|b| b.to_le_bytes())
+        .flat_map(|b| b.widen().to_le_bytes().into_iter().take(L::BYTES))
         .zip(output.iter_mut())
         .for_each(|(s, r)| *r = s);
-    output
 }
 
 /// The Keccak-p permutation specified in Section 3.3 of the specification.
@@ -176,12 +622,42 @@ pub fn sponge<const R: usize, const D: usize>(preimage: &[u8]) -> [u8; D] {
 /// The $\iota$ step adds a round constant to the state.
 #[docext]
 pub fn keccak_p(state: &mut State) {
-    for ir in 0..NUM_ROUNDS {
+    keccak_p_traced(state, &NoTrace)
+}
+
+/// The [Keccak-p permutation](keccak_p), reporting the state after every round
+/// to the [tracer](Trace), under the name [`KECCAK_P`]. The state is encoded in
+/// the byte order of [`absorb_block`].
+pub fn keccak_p_traced(state: &mut State, trace: &impl Trace) {
+    permute(state, trace)
+}
+
+/// Keccak-p[400, 20], the [Keccak-p permutation](keccak_p) with 16-bit
+/// [lanes](Lane). This is Keccak-f\[400\], with the full 20 rounds.
+pub fn keccak_p_400(state: &mut KeccakState<u16>) {
+    permute(state, &NoTrace)
+}
+
+/// Keccak-p[800, 22], the [Keccak-p permutation](keccak_p) with 32-bit
+/// [lanes](Lane). This is Keccak-f\[800\], with the full 22 rounds.
+pub fn keccak_p_800(state: &mut KeccakState<u32>) {
+    permute(state, &NoTrace)
+}
+
+/// Apply all [rounds](Lane::ROUNDS) of Keccak-p for the lane width, tracing
+/// them under [its name](Lane::PERMUTATION).
+fn permute<L: Lane>(state: &mut KeccakState<L>, trace: &impl Trace) {
+    for ir in 0..L::ROUNDS {
         theta(state);
         rho(state);
         pi(state);
         chi(state);
         iota(state, ir);
+
+        let mut bytes = [0; STATE_BYTES];
+        let bytes = &mut bytes[..NUM_ROWS * NUM_COLS * L::BYTES];
+        squeeze_lanes(state, bytes);
+        trace.on_round(L::PERMUTATION, ir, bytes);
     }
 }
 
@@ -254,9 +730,9 @@ pub fn keccak_p(state: &mut State) {
 /// $$
 #[docext]
 #[allow(clippy::needless_range_loop)]
-pub fn theta(state: &mut State) {
+pub fn theta<L: Lane>(state: &mut KeccakState<L>) {
     // Set c[x] to the XOR of all rows at column x.
-    let mut c = [0u64; NUM_COLS];
+    let mut c = [L::default(); NUM_COLS];
     for y in 0..NUM_ROWS {
         for x in 0..NUM_COLS {
             c[x] ^= state[y][x];
@@ -287,7 +763,7 @@ pub fn theta(state: &mut State) {
 /// bits.
 #[docext]
 #[allow(clippy::needless_range_loop)]
-pub fn rho(state: &mut State) {
+pub fn rho<L: Lane>(state: &mut KeccakState<L>) {
     for y in 0..NUM_ROWS {
         for x in 0..NUM_COLS {
             state[y][x] = state[y][x].rotate_left(RHO_OFFSETS[y][x]);
@@ -308,7 +784,7 @@ pub fn rho(state: &mut State) {
 /// $$
 #[docext]
 #[allow(clippy::needless_range_loop)]
-pub fn pi(state: &mut State) {
+pub fn pi<L: Lane>(state: &mut KeccakState<L>) {
     let copy = *state;
     for y in 0..NUM_ROWS {
         for x in 0..NUM_COLS {
@@ -339,7 +815,7 @@ pub fn pi(state: &mut State) {
 /// $$
 #[docext]
 #[allow(clippy::needless_range_loop)]
-pub fn chi(state: &mut State) {
+pub fn chi<L: Lane>(state: &mut KeccakState<L>) {
     let copy = *state;
     for y in 0..NUM_ROWS {
         for x in 0..NUM_COLS {
@@ -357,9 +833,11 @@ pub fn chi(state: &mut State) {
 /// $$
 ///
 /// Round constant generation is implemented in [`rctable`](rctable::rctable).
+/// For lanes narrower than 64 bits, the constant is truncated to the lane
+/// width.
 #[docext]
-pub fn iota(state: &mut State, ir: usize) {
-    state[0][0] ^= RC[ir];
+pub fn iota<L: Lane>(state: &mut KeccakState<L>, ir: usize) {
+    state[0][0] ^= L::truncate(RC[ir]);
 }
 
 /// Pad the input data to a multiple of the block size (r, also known as rate)
@@ -369,45 +847,116 @@ pub fn iota(state: &mut State, ir: usize) {
 /// pad10*1. It pads the data by adding a single 1 bit, as many 0 bits as
 /// needed, and a final 1 bit.
 ///
-/// Additionally, the bit string "10" is appended to the data before padding.
+/// Additionally, the bit string "01" is appended to the data before padding.
 /// This is called the _domain separator_ and serves to disambiguate SHA-3's
-/// usage of Keccak-p from other uses of Keccak-p.
+/// usage of Keccak-p from other uses of Keccak-p. See
+/// [`pad10star1_with_domain`] for other domain separators.
+///
+/// This is [`pad10star1_bits`] for byte-aligned input.
 #[docext]
 pub fn pad10star1<const R: usize>(preimage: &[u8]) -> impl Iterator<Item = [u8; R]> + '_ {
-    preimage
-        .chunks(R)
-        .chain(
-            // If the data is a multiple of the block size, a full block of padding needs to be
-            // added.
-            iter::once([].as_slice()).take(if preimage.len() % R == 0 { 1 } else { 0 }),
-        )
-        .map(|block| {
-            if block.len() == R {
-                block.try_into().unwrap()
-            } else {
-                // This block is incomplete and needs padding.
-                let mut padded = [0; R];
-                block
-                    .iter()
-                    .copied()
-                    .chain(iter::repeat(0))
-                    .zip(padded.iter_mut())
-                    .enumerate()
-                    .for_each(|(i, (mut b, r))| {
-                        if i == block.len() {
-                            // This is the first byte of padding, so start with the domain separator
-                            // "10" and a leading "1" bit. The bit order used by the specification
-                            // (described in Section B.1) is the opposite of the bit order used by
-                            // computers, so these constants are reversed.
-                            b |= 0b00000110;
-                        }
-                        if i == R - 1 {
-                            // This is the last byte of padding, so add a final "1" bit.
-                            b |= 0b10000000;
-                        }
-                        *r = b;
-                    });
-                padded
-            }
-        })
+    pad10star1_bits(preimage, 8 * preimage.len())
+}
+
+/// [Pad](pad10star1) the first `bit_len` bits of the input data, which don't
+/// have to make up a whole number of bytes.
+///
+/// The specification treats the input as a string of bits, and converts bit
+/// strings to bytes as described in Section B.1: the first bit of the string
+/// is the _least_ significant bit of the first byte. This is the opposite of
+/// how numbers are usually written, with the most significant bit on the left.
+/// Accordingly, if the input ends in a partial byte, its bits are the least
+/// significant bits of the last byte, and the remaining bits are ignored. For
+/// example, the 5-bit string 11001 is the byte $\mathrm{13}_{16} =
+/// 00010011_2$.
+///
+/// The domain separator and padding bits are appended in the same order, right
+/// after the last bit of input. For byte-aligned input, the domain separator
+/// "01" and the leading "1" of the padding become the byte
+/// $\mathrm{06}_{16} = 00000110_2$, and the final "1" of the padding becomes
+/// $\mathrm{80}_{16} = 10000000_2$ in the last byte of the block. For other
+/// inputs, these bits are shifted left by the number of bits in the partial
+/// byte, possibly spilling into the next byte, or even into the next block.
+#[docext]
+pub fn pad10star1_bits<const R: usize>(
+    preimage: &[u8],
+    bit_len: usize,
+) -> impl Iterator<Item = [u8; R]> + '_ {
+    pad10star1_with_domain(preimage, bit_len, SHA3_DOMAIN)
+}
+
+/// The domain separator of SHA-3, the bits "01" followed by the leading "1" of
+/// the padding, in the [byte encoding](pad10star1_with_domain).
+pub const SHA3_DOMAIN: u8 = 0x06;
+
+/// The domain separator of the original Keccak submission, used by
+/// [`Keccak256`] and [`Keccak512`]: no separator bits, only the leading "1" of
+/// the padding, in the [byte encoding](pad10star1_with_domain).
+pub const KECCAK_DOMAIN: u8 = 0x01;
+
+/// [Pad](pad10star1_bits) the first `bit_len` bits of the input data, with any
+/// domain separator.
+///
+/// The domain separator is given as a byte which holds the separator bits
+/// followed by the leading "1" of the padding, in the [specification bit
+/// order](pad10star1_bits). These are the bits up to and including the most
+/// significant set bit of the byte. For SHA-3, the separator "01" gives
+/// [`SHA3_DOMAIN`], and the original Keccak, which has no separator, gives
+/// [`KECCAK_DOMAIN`]. This is the byte which follows byte-aligned input.
+///
+/// # Panics
+///
+/// If the domain separator is zero, since it must at least contain the leading
+/// "1" of the padding.
+pub fn pad10star1_with_domain<const R: usize>(
+    preimage: &[u8],
+    bit_len: usize,
+    domain: u8,
+) -> impl Iterator<Item = [u8; R]> + '_ {
+    assert!(bit_len <= 8 * preimage.len(), "bit length exceeds input");
+    assert_ne!(domain, 0, "domain separator without padding bit");
+    let domain_bits = usize::try_from(u8::BITS - domain.leading_zeros()).unwrap();
+
+    // Full blocks of input don't need padding.
+    let (blocks, rest) = preimage.split_at(bit_len / (8 * R) * R);
+    let tail_bits = bit_len % (8 * R);
+
+    // The remaining input bits are followed by the domain separator bits and at
+    // least two padding bits. If these don't fit into one block, another block is
+    // needed.
+    let mut tail = vec![
+        0;
+        if tail_bits + domain_bits < 8 * R {
+            R
+        } else {
+            2 * R
+        }
+    ];
+    let tail_bytes = tail_bits.div_ceil(8);
+    tail[..tail_bytes].copy_from_slice(&rest[..tail_bytes]);
+    if tail_bits % 8 != 0 {
+        // Clear the unused bits of the partial byte.
+        tail[tail_bytes - 1] &= (1 << (tail_bits % 8)) - 1;
+    }
+
+    // The domain separator, followed by the leading "1" bit of the padding.
+    for i in 0..domain_bits {
+        if (domain >> i) & 1 == 1 {
+            set_bit(&mut tail, tail_bits + i);
+        }
+    }
+    // The final "1" bit of the padding.
+    let last = 8 * tail.len() - 1;
+    set_bit(&mut tail, last);
+
+    blocks
+        .array_chunks()
+        .copied()
+        .chain(tail.array_chunks().copied().collect::<Vec<[u8; R]>>())
+}
+
+/// Set the bit at index `i` in a bit string stored in the [specification bit
+/// order](pad10star1_bits).
+fn set_bit(bits: &mut [u8], i: usize) {
+    bits[i / 8] |= 1 << (i % 8);
 }
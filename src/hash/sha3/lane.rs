@@ -0,0 +1,91 @@
+use {
+    docext::docext,
+    std::{
+        fmt,
+        ops::{BitAnd, BitXor, BitXorAssign, Not},
+    },
+};
+
+/// A word of the [Keccak-p state](super::KeccakState), called a _lane_ in the
+/// specification.
+///
+/// Keccak-p is defined for lanes of $w = 2^l$ bits, $l \in \{0, 1, \dots,
+/// 6\}$, giving states of $b = 25w$ bits. [SHA-3](super) uses 64-bit lanes,
+/// i.e. Keccak-p[1600]. The smaller permutations Keccak-p[400] and
+/// Keccak-p[800], with 16-bit and 32-bit lanes, have the same structure but
+/// states small enough to print and to attack by hand, which makes them useful
+/// for studying the permutation. See [`keccak_p_400`](super::keccak_p_400) and
+/// [`keccak_p_800`](super::keccak_p_800).
+///
+/// The lane width determines the number of rounds, $12 + 2l$, and the round
+/// constants and rotation offsets are reduced to $w$ bits. Lanes are encoded
+/// as little-endian bytes, so lanes smaller than a byte are not supported.
+///
+/// This trait is sealed, and implemented for [`u16`], [`u32`] and [`u64`].
+#[docext]
+pub trait Lane:
+    sealed::Sealed
+    + Copy
+    + Default
+    + Eq
+    + fmt::Debug
+    + BitXor<Output = Self>
+    + BitXorAssign
+    + BitAnd<Output = Self>
+    + Not<Output = Self>
+{
+    /// The lane width $w$ in bits.
+    #[docext]
+    const BITS: u32;
+
+    /// The lane width in bytes.
+    const BYTES: usize = Self::BITS as usize / 8;
+
+    /// The number of rounds $12 + 2l$ of Keccak-f, where $w = 2^l$.
+    #[docext]
+    const ROUNDS: usize = 12 + 2 * Self::BITS.trailing_zeros() as usize;
+
+    /// The name of Keccak-p with this lane width and number of rounds, under
+    /// which its rounds are [traced](crate::trace).
+    const PERMUTATION: &'static str;
+
+    /// Rotate left by `n` bits, modulo the lane width.
+    fn rotate_left(self, n: u32) -> Self;
+
+    /// Keep the low [`BITS`](Lane::BITS) bits of the number.
+    fn truncate(n: u64) -> Self;
+
+    /// Zero-extend the lane into a `u64`.
+    fn widen(self) -> u64;
+}
+
+macro_rules! lane {
+    ($t:ty, $name:expr) => {
+        impl sealed::Sealed for $t {}
+
+        impl Lane for $t {
+            const BITS: u32 = <$t>::BITS;
+            const PERMUTATION: &'static str = $name;
+
+            fn rotate_left(self, n: u32) -> Self {
+                <$t>::rotate_left(self, n)
+            }
+
+            fn truncate(n: u64) -> Self {
+                n as $t
+            }
+
+            fn widen(self) -> u64 {
+                self.into()
+            }
+        }
+    };
+}
+
+lane!(u16, "Keccak-p[400, 20]");
+lane!(u32, "Keccak-p[800, 22]");
+lane!(u64, super::KECCAK_P);
+
+mod sealed {
+    pub trait Sealed {}
+}
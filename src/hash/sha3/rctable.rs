@@ -2,25 +2,36 @@ use {super::NUM_ROUNDS, docext::docext};
 
 const L: usize = 6;
 
-/// Generate the round constants used by the $\iota$ step.
+/// Generate the round constants used by the $\iota$ step, as specified in
+/// Section 3.2.5 of the specification.
 ///
-/// This is not used in the actual implementation, since the constants are
-/// hardcoded in [`RC`](super::RC).
-#[allow(dead_code)]
+/// Bit $2^j - 1$ of the constant for round $i_r$ is $\mathrm{rc}(j + 7i_r)$,
+/// for $j \in \{0, 1, \dots, l\}$, and all other bits are zero. The function
+/// $\mathrm{rc}$ is the output of a linear feedback shift register.
+///
+/// The table is generated for 64-bit [lanes](super::Lane). For a lane width of
+/// $w = 2^l$ bits, only bits $2^j - 1$ with $j \le l$ are used, which are
+/// exactly the low $w$ bits of the 64-bit constants. So the constants of the
+/// smaller permutations are the 64-bit constants truncated to the lane width.
+/// This is the table [`RC`](super::RC).
 #[docext]
-pub fn rctable() -> [u64; NUM_ROUNDS] {
+pub const fn rctable() -> [u64; NUM_ROUNDS] {
     let mut table = [0u64; NUM_ROUNDS];
-    for (ir, val) in table.iter_mut().enumerate() {
-        for j in 0..=L {
+    let mut ir = 0;
+    while ir < NUM_ROUNDS {
+        let mut j = 0;
+        while j <= L {
             if rc(j + 7 * ir) {
-                *val |= 0x0000000000000001 << (2u32.pow(j as u32) - 1);
+                table[ir] |= 1 << ((1 << j) - 1);
             }
+            j += 1;
         }
+        ir += 1;
     }
     table
 }
 
-fn rc(t: usize) -> bool {
+const fn rc(t: usize) -> bool {
     let t = t % 255;
 
     if t == 0 {
@@ -28,12 +39,14 @@ fn rc(t: usize) -> bool {
     }
 
     let mut r = 0x80;
-    for _ in 1..=t {
+    let mut i = 1;
+    while i <= t {
         let low = r & 0x01 != 0;
         r >>= 1;
         if low {
             r ^= 0b10001110;
         }
+        i += 1;
     }
     r & 0x80 != 0
 }
@@ -0,0 +1,253 @@
+//! SM3 is a hash function specified by the Chinese national standard [GB/T
+//! 32905-2016](https://datatracker.ietf.org/doc/html/draft-sca-cfrg-sm3-02).
+//!
+//! Like [SHA-256](crate::Sha256), SM3 is based on the
+//! [Merkle-Damgard](crate::MerkleDamgard) and
+//! [Davies-Meyer](crate::DaviesMeyer) constructions, uses the same
+//! [padding](crate::sha2::LengthPadding), and produces a 256-bit digest. The
+//! internal [block cipher](Sm3Cipher) is different: it uses a different message
+//! expansion which produces two words per round, different boolean functions,
+//! and the [$P_0$](p0) and [$P_1$](p1) permutations.
+//!
+//! Another difference is the Davies-Meyer step. SHA-2 adds the new state to the
+//! previous one, while SM3 [XORs](Xor) them together, as in the original
+//! Davies-Meyer construction.
+
+use {
+    crate::{
+        sha2::{self, Block, LengthPadding},
+        BlockEncrypt,
+        DaviesMeyer,
+        DaviesMeyerStep,
+        Hash,
+        IncrementalHash,
+        MerkleDamgard,
+        MerkleDamgardState,
+    },
+    docext::docext,
+    std::marker::PhantomData,
+};
+
+/// The internal state of [SM3](Sm3).
+pub type Sm3State = [u32; 8];
+
+/// SM3 hash specified by [GB/T
+/// 32905-2016](https://datatracker.ietf.org/doc/html/draft-sca-cfrg-sm3-02).
+///
+/// SM3 is vulnerable to [length-extension
+/// attacks](MerkleDamgard#length-extension-attacks).
+///
+/// For more details, see the [module documentation](self).
+#[derive(Debug)]
+pub struct Sm3(
+    MerkleDamgard<Sm3State, Block, DaviesMeyer<Sm3Cipher, Xor<Sm3State>>, LengthPadding>,
+);
+
+/// The underlying block cipher used by [SM3](Sm3).
+///
+/// The message block is first expanded into 68 words $W_j$ and 64 words
+/// $W'_j$, where $W_0, \dots, W_{15}$ are the words of the block:
+///
+/// $$
+/// W_j = P_1(W_{j-16} \oplus W_{j-9} \oplus \mathrm{ROTL}(W_{j-3}, 15)) \oplus
+/// \mathrm{ROTL}(W_{j-13}, 7) \oplus W_{j-6}, \quad 16 \le j < 68 \\
+/// W'_j = W_j \oplus W_{j+4}, \quad 0 \le j < 64
+/// $$
+///
+/// Then, 64 rounds of the following permutation are applied, where $a, b, c,
+/// \dots$ represent the current state in 32-bit words, [$FF_j$](ff),
+/// [$GG_j$](gg), [$P_0$](p0) are helper functions, and [$T_j$](tj) are the
+/// round constants:
+///
+/// $$
+/// SS_1 = \mathrm{ROTL}(\mathrm{ROTL}(a, 12) + e + \mathrm{ROTL}(T_j, j), 7)
+/// \pmod{2^{32}}\\
+/// SS_2 = SS_1 \oplus \mathrm{ROTL}(a, 12)\\
+/// TT_1 = FF_j(a, b, c) + d + SS_2 + W'_j \pmod{2^{32}}\\
+/// TT_2 = GG_j(e, f, g) + h + SS_1 + W_j \pmod{2^{32}}\\
+/// d \gets c\\
+/// c \gets \mathrm{ROTL}(b, 9)\\
+/// b \gets a\\
+/// a \gets TT_1\\
+/// h \gets g\\
+/// g \gets \mathrm{ROTL}(f, 19)\\
+/// f \gets e\\
+/// e \gets P_0(TT_2)
+/// $$
+#[docext]
+#[derive(Debug)]
+pub struct Sm3Cipher(());
+
+impl Default for Sm3 {
+    fn default() -> Self {
+        Self(MerkleDamgard::new(
+            DaviesMeyer::new(Sm3Cipher(()), Xor(Default::default())),
+            LengthPadding::default(),
+            [
+                0x7380166f, 0x4914b2b9, 0x172442d7, 0xda8a0600, 0xa96f30bc, 0x163138aa, 0xe38dee4d,
+                0xb0fb0e4e,
+            ],
+        ))
+    }
+}
+
+impl Hash for Sm3 {
+    type Digest = [u8; 32];
+    type Block = Block;
+
+    fn hash(&self, preimage: &[u8]) -> Self::Digest {
+        sha2::to_be_bytes(self.0.hash(preimage))
+    }
+}
+
+impl IncrementalHash for Sm3 {
+    type State = MerkleDamgardState<Sm3State>;
+
+    fn init(&self) -> Self::State {
+        self.0.init()
+    }
+
+    fn update(&self, state: &mut Self::State, data: &[u8]) {
+        self.0.update(state, data)
+    }
+
+    fn finalize(&self, state: Self::State) -> Self::Digest {
+        sha2::to_be_bytes(self.0.finalize(state))
+    }
+}
+
+impl BlockEncrypt for Sm3Cipher {
+    type EncryptionBlock = Sm3State;
+    type EncryptionKey = Block;
+
+    fn encrypt(
+        &self,
+        data: Self::EncryptionBlock,
+        key: Self::EncryptionKey,
+    ) -> Self::EncryptionBlock {
+        let state = data;
+        let block = key;
+
+        // Expand the message.
+        let mut w = [0; 68];
+        w[..16].copy_from_slice(&sha2::from_be_bytes::<64, 16>(&block));
+        for j in 16..68 {
+            w[j] = p1(w[j - 16] ^ w[j - 9] ^ w[j - 3].rotate_left(15))
+                ^ w[j - 13].rotate_left(7)
+                ^ w[j - 6];
+        }
+
+        // Execute the rounds.
+        let [mut a, mut b, mut c, mut d, mut e, mut f, mut g, mut h] = state;
+        for j in 0..64 {
+            let ss1 = a
+                .rotate_left(12)
+                .wrapping_add(e)
+                .wrapping_add(tj(j).rotate_left(j % 32))
+                .rotate_left(7);
+            let ss2 = ss1 ^ a.rotate_left(12);
+            let j = usize::try_from(j).unwrap();
+            let tt1 = ff(j, a, b, c)
+                .wrapping_add(d)
+                .wrapping_add(ss2)
+                .wrapping_add(w[j] ^ w[j + 4]);
+            let tt2 = gg(j, e, f, g)
+                .wrapping_add(h)
+                .wrapping_add(ss1)
+                .wrapping_add(w[j]);
+            d = c;
+            c = b.rotate_left(9);
+            b = a;
+            a = tt1;
+            h = g;
+            g = f.rotate_left(19);
+            f = e;
+            e = p0(tt2);
+        }
+
+        [a, b, c, d, e, f, g, h]
+    }
+}
+
+/// Round constant $T_j$ used by [SM3](Sm3).
+#[docext]
+pub fn tj(j: u32) -> u32 {
+    match j {
+        0..=15 => 0x79cc4519,
+        _ => 0x7a879d8a,
+    }
+}
+
+/// Helper function $FF_j$.
+///
+/// $$
+/// FF_j(x, y, z) =
+/// \begin{cases}
+/// x \oplus y \oplus z & 0 \le j < 16\\
+/// (x \land y) \lor (x \land z) \lor (y \land z) & otherwise \\
+/// \end{cases}
+/// $$
+#[docext]
+pub fn ff(j: usize, x: u32, y: u32, z: u32) -> u32 {
+    match j {
+        0..=15 => x ^ y ^ z,
+        _ => (x & y) | (x & z) | (y & z),
+    }
+}
+
+/// Helper function $GG_j$.
+///
+/// $$
+/// GG_j(x, y, z) =
+/// \begin{cases}
+/// x \oplus y \oplus z & 0 \le j < 16\\
+/// (x \land y) \lor (\neg x \land z) & otherwise \\
+/// \end{cases}
+/// $$
+#[docext]
+pub fn gg(j: usize, x: u32, y: u32, z: u32) -> u32 {
+    match j {
+        0..=15 => x ^ y ^ z,
+        _ => (x & y) | (!x & z),
+    }
+}
+
+/// Permutation $P_0$, used in the compression function.
+///
+/// $$
+/// P_0(x) = x \oplus \mathrm{ROTL}(x, 9) \oplus \mathrm{ROTL}(x, 17)
+/// $$
+#[docext]
+pub fn p0(x: u32) -> u32 {
+    x ^ x.rotate_left(9) ^ x.rotate_left(17)
+}
+
+/// Permutation $P_1$, used in the message expansion.
+///
+/// $$
+/// P_1(x) = x \oplus \mathrm{ROTL}(x, 15) \oplus \mathrm{ROTL}(x, 23)
+/// $$
+#[docext]
+pub fn p1(x: u32) -> u32 {
+    x ^ x.rotate_left(15) ^ x.rotate_left(23)
+}
+
+/// The [Davies-Meyer step](DaviesMeyerStep) of SM3 XORs the new state with the
+/// previous state.
+#[derive(Debug)]
+pub struct Xor<State>(PhantomData<State>);
+
+impl<State> DaviesMeyerStep for Xor<State>
+where
+    State: AsMut<[u32]> + AsRef<[u32]>,
+{
+    type State = State;
+
+    fn step(&self, prev: Self::State, mut new: Self::State) -> Self::State {
+        new.as_mut()
+            .iter_mut()
+            .zip(prev.as_ref().iter())
+            .for_each(|(n, p)| *n ^= *p);
+        new
+    }
+}
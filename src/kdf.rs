@@ -0,0 +1,150 @@
+use {
+    crate::{util, Hash, Hmac, Mac},
+    docext::docext,
+    std::fmt,
+};
+
+/// The maximum number of [HMAC](Hmac) blocks which [HKDF](Hkdf) can expand a
+/// key into, since the block counter is a single byte.
+const MAX_BLOCKS: usize = 255;
+
+/// The HMAC-based key derivation function is a method for deriving any number
+/// of keys from a single shared secret, as specified in [RFC
+/// 5869](https://www.rfc-editor.org/rfc/rfc5869).
+///
+/// A shared secret, such as the result of a Diffie-Hellman exchange, is
+/// usually not uniformly random, and it's often needed as several keys, e.g.
+/// an encryption key and a MAC key. HKDF works in two steps, both based on
+/// [HMAC](Hmac). The first step _extracts_ a pseudorandom key (PRK) from the
+/// input key material (IKM):
+///
+/// $$
+/// PRK = HMAC(salt, ikm)
+/// $$
+///
+/// where the salt is an optional, non-secret random value. An empty salt is
+/// equivalent to a salt of zeros. The second step _expands_ the pseudorandom
+/// key into as many bytes as needed:
+///
+/// $$
+/// T_i = HMAC(PRK, T_{i-1} \parallel info \parallel i)
+/// $$
+///
+/// where $T_0$ is empty, $i$ is a single byte starting at $1$, and $info$ is
+/// context which binds the output to its purpose. The output is $T_1 \parallel
+/// T_2 \parallel \dots$ truncated to the requested length. Since $i$ is a
+/// single byte, the output is at most $255$ times the digest size of the hash.
+///
+/// Keys for different purposes should be expanded from the same PRK with
+/// different $info$, which makes them unrelated. The extract step can be
+/// skipped if the input is already a uniformly random key of the digest size,
+/// by passing it to [`expand`](Hkdf::expand) as a [`Prk`].
+#[docext]
+pub struct Hkdf<H>(Hmac<H>);
+
+/// The pseudorandom key extracted by [HKDF](Hkdf), as long as the digest of
+/// the hash.
+///
+/// The [`Debug`](fmt::Debug) output only shows a
+/// [fingerprint](crate::key_fingerprint) of the key.
+#[derive(Clone)]
+pub struct Prk<const N: usize>([u8; N]);
+
+impl<H> Hkdf<H> {
+    pub fn new(h: H) -> Self {
+        Self(Hmac::new(h))
+    }
+}
+
+impl<H, const BLOCK_SIZE: usize, const DIGEST_SIZE: usize> Hkdf<H>
+where
+    H: Hash<Block = [u8; BLOCK_SIZE], Digest = [u8; DIGEST_SIZE]>,
+{
+    /// The longest output [`expand`](Hkdf::expand) can produce.
+    pub const MAX_OUTPUT: usize = MAX_BLOCKS * DIGEST_SIZE;
+
+    /// The _extract_ step, computing the pseudorandom key from the input key
+    /// material.
+    pub fn extract(&mut self, salt: &[u8], ikm: &[u8]) -> Prk<DIGEST_SIZE> {
+        Prk(self.0.mac(ikm, salt))
+    }
+
+    /// The _expand_ step, deriving `len` bytes from the pseudorandom key.
+    /// Fails if `len` is more than [`MAX_OUTPUT`](Hkdf::MAX_OUTPUT).
+    pub fn expand(
+        &mut self,
+        prk: &Prk<DIGEST_SIZE>,
+        info: &[u8],
+        len: usize,
+    ) -> Result<Vec<u8>, HkdfError> {
+        if len > Self::MAX_OUTPUT {
+            return Err(HkdfError::OutputTooLong {
+                len,
+                max: Self::MAX_OUTPUT,
+            });
+        }
+        let mut okm = Vec::with_capacity(len);
+        let mut t = Vec::new();
+        // The number of blocks was checked against MAX_BLOCKS above.
+        for i in 1..=u8::try_from(len.div_ceil(DIGEST_SIZE)).unwrap() {
+            t.extend_from_slice(info);
+            t.push(i);
+            let block = self.0.mac(&t, &prk.0);
+            okm.extend_from_slice(&block);
+            t = block.to_vec();
+        }
+        okm.truncate(len);
+        Ok(okm)
+    }
+
+    /// Extract and expand in one go.
+    pub fn derive(
+        &mut self,
+        salt: &[u8],
+        ikm: &[u8],
+        info: &[u8],
+        len: usize,
+    ) -> Result<Vec<u8>, HkdfError> {
+        let prk = self.extract(salt, ikm);
+        self.expand(&prk, info, len)
+    }
+}
+
+impl<const N: usize> Prk<N> {
+    /// Use a uniformly random key as the pseudorandom key, skipping the
+    /// extract step.
+    pub fn from_bytes(bytes: [u8; N]) -> Self {
+        Self(bytes)
+    }
+
+    pub fn as_bytes(&self) -> &[u8; N] {
+        &self.0
+    }
+}
+
+impl<const N: usize> fmt::Debug for Prk<N> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("Prk")
+            .field(&util::Redacted(&self.0))
+            .finish()
+    }
+}
+
+/// Error returned when [HKDF](Hkdf) can't produce the requested output.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HkdfError {
+    /// The requested `len` is more than the `max` of 255 digests.
+    OutputTooLong { len: usize, max: usize },
+}
+
+impl fmt::Display for HkdfError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::OutputTooLong { len, max } => {
+                write!(f, "HKDF output of {len} bytes exceeds the maximum of {max}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for HkdfError {}
@@ -5,17 +5,50 @@
 //!         - [Block Mode](BlockMode)
 //!             - [ECB](Ecb)
 //!             - [CBC](Cbc)
+//!                 - [Ciphertext Stealing](CbcCts)
 //!             - [CTR](Ctr)
+//!             - [GCM](Gcm)
+//!             - [Derived Nonces](DerivedNonce)
 //! - [Hashing](Hash)
 //!     - [SHA-2](sha2)
+//!         - [SHA-1 Collision Detection](sha1cd)
 //!     - [SHA-3](sha3)
+//!         - [Keccak](Keccak256)
+//!     - [SM3](sm3)
+//!     - [Incremental Hashing](IncrementalHash)
+//!     - [Merkle Tree](MerkleTree)
 //! - [CSPRNG](Csprng)
 //!     - [Fortuna]
+//! - [Entropy]
+//!     - [Entropy Pool](EntropyPool)
+//!     - [Operating System](OsEntropy)
+//!     - [Timing Jitter](TimingJitter)
+//!     - [Health Tests](HealthTested)
 //! - [MAC](Mac)
 //!     - [HMAC](Hmac)
+//!     - [CMAC](Cmac)
+//!     - [Encrypt-then-MAC](EncryptThenMac)
+//! - [Key Derivation (HKDF)](Hkdf)
 //! - [Signature Scheme (Public Key Cryptography)](SignatureScheme)
 //!     - [Elliptic Curve Math](ecc::Curve)
+//!         - [P-256](Secp256r1)
 //!         - [ECDSA](Ecdsa)
+//!             - [JWK and JWS](ecc::jose)
+//!         - [Schnorr]
+//!             - [BIP-340](SchnorrBip340)
+//!         - [ECIES](Ecies)
+//!     - [Fiat-Shamir Transcript](Transcript)
+//! - [Key Encapsulation](Kem)
+//!     - [DHKEM](EciesKem)
+//!         - [HPKE](HpkeContext)
+//! - Encoding
+//!     - [Hex](hex)
+//!     - [Base64](base64)
+//!     - [Canonical Hash Inputs](canonical)
+//! - [Constant-time Selection](CtSelect)
+//! - [Cryptanalysis](analysis)
+//! - [Tracing Intermediate Values](trace)
+//! - [Test Doubles](testing)
 
 #![forbid(unsafe_code)]
 #![feature(return_position_impl_trait_in_trait)]
@@ -30,68 +63,169 @@ mod test;
 #[cfg(doc)]
 pub mod doc;
 
+pub mod analysis;
 mod cipher;
 mod hash;
+mod kdf;
 mod mac;
 mod pubkey;
 mod random;
+#[cfg(any(test, doc, feature = "testing"))]
+pub mod testing;
+pub mod trace;
 mod util;
 
+#[cfg(unix)]
+pub use random::OsEntropy;
 pub use {
     cipher::{
         aes,
+        gf128_mul,
+        ghash,
+        kcv,
         Aes128,
         Aes192,
         Aes256,
+        AesKeySchedule,
+        AuthenticationFailed,
         BlockCipher,
         BlockDecrypt,
         BlockEncrypt,
         BlockMode,
-        BlockSizeTooSmall,
         Cbc,
+        CbcCts,
+        CbcDecryptIter,
         Cipher,
         CipherDecrypt,
         CipherEncrypt,
+        CounterPosition,
+        CounterWidth,
         Ctr,
+        CtrConfigError,
+        CtrDecryptIter,
+        CtrError,
+        CtsTooShort,
+        DerivedNonce,
+        DerivedNonceError,
         Ecb,
+        Gcm,
+        GcmTag,
+        NonceMode,
         OneTimePad,
         Padding,
         Pkcs7,
     },
     hash::{
+        hash_chunked_parallel,
+        sha1cd,
         sha2,
         sha3,
+        sm3,
+        BitHash,
         CompressionFn,
         DaviesMeyer,
         DaviesMeyerStep,
         Hash,
+        IncrementalHash,
+        Keccak256,
+        Keccak512,
         MerkleDamgard,
         MerkleDamgardPad,
+        MerkleDamgardState,
+        MerkleDamgardTrace,
+        MerkleTree,
         Sha1,
+        Sha1Cd,
         Sha224,
         Sha256,
+        Sha384,
         Sha3_224,
         Sha3_256,
         Sha3_384,
         Sha3_512,
+        Sha512,
+        Sha512_224,
+        Sha512_256,
+        Sm3,
+        StdHasher,
+        WriteHasher,
     },
-    mac::{Hmac, Mac},
+    kdf::{Hkdf, HkdfError, Prk},
+    mac::{key_fingerprint, Cmac, EncryptThenMac, EnvelopeError, Hmac, HmacKey, Mac},
     pubkey::{
         ecc,
+        hpke_open,
+        hpke_seal,
+        AdaptorSignature,
+        Bip340Signature,
+        BlindSchnorr,
+        BlindSession,
+        Blinding,
+        DecapsError,
         Ecdsa,
         EcdsaSignature,
+        Ecies,
+        EciesCiphertext,
+        EciesError,
+        EciesKem,
+        HpkeContext,
+        HpkeError,
+        IncompleteSession,
+        InvalidBatch,
+        InvalidKeyAgg,
         InvalidPrivateKey,
+        InvalidRound,
+        InvalidSchnorrRandomness,
         InvalidSignature,
+        Kem,
+        KeyAggContext,
+        LinkableRingScheme,
+        MuSig2,
+        MuSig2Nonce,
+        MuSig2PublicNonce,
         MultiSchnorr,
         MultisigScheme,
+        NonceCommitment,
+        PartialAggregate,
+        PartialSignature,
+        RegisteredNonce,
         RingScheme,
         Schnorr,
+        SchnorrBip340,
+        SchnorrLsag,
+        SchnorrLsagSignature,
+        SchnorrNonce,
         SchnorrRandomness,
         SchnorrSag,
+        SchnorrSagError,
         SchnorrSagSignature,
         SchnorrSignature,
         Secp256k1,
+        Secp256r1,
+        SessionError,
+        SessionGuard,
+        SessionId,
+        SessionRegistry,
+        SessionRejected,
+        SharedSecret,
         SignatureScheme,
+        SignatureShare,
+        SigningSession,
+        TooManySessions,
+        Transcript,
+        XOnlyPublicKey,
     },
-    random::{shuffle, uniform_random, Csprng, Entropy, Fortuna},
+    random::{
+        shuffle,
+        uniform_random,
+        uniform_random_u64,
+        Csprng,
+        Entropy,
+        EntropyPool,
+        Fortuna,
+        HealthTestConfig,
+        HealthTested,
+        TimingJitter,
+    },
+    util::{base64, canonical, codec, hex, Choice, CtSelect},
 };
@@ -1,6 +1,12 @@
+mod cmac;
+mod etm;
 mod hmac;
 
-pub use hmac::Hmac;
+pub use {
+    cmac::Cmac,
+    etm::{key_fingerprint, EncryptThenMac, EnvelopeError},
+    hmac::{Hmac, HmacKey},
+};
 
 /// A message authentication code algorithm is a method for computing a keyed
 /// [hash](crate::Hash).
@@ -18,3 +24,21 @@ pub trait Mac {
 
     fn mac(&mut self, msg: &[u8], key: &[u8]) -> Self::Tag;
 }
+
+/// [`Mac::mac`] takes `&mut self`, so a MAC can only be forwarded through
+/// exclusive references and boxes, not through `&T` or `Arc<T>`.
+impl<T: Mac + ?Sized> Mac for &mut T {
+    type Tag = T::Tag;
+
+    fn mac(&mut self, msg: &[u8], key: &[u8]) -> Self::Tag {
+        T::mac(self, msg, key)
+    }
+}
+
+impl<T: Mac + ?Sized> Mac for Box<T> {
+    type Tag = T::Tag;
+
+    fn mac(&mut self, msg: &[u8], key: &[u8]) -> Self::Tag {
+        T::mac(self, msg, key)
+    }
+}
@@ -0,0 +1,128 @@
+use {
+    crate::{BlockCipher, Mac},
+    docext::docext,
+};
+
+/// The size of a CMAC block and tag in bytes.
+const BLOCK_SIZE: usize = 16;
+
+/// The low terms of the modulus used when [doubling](Cmac#subkeys) overflows.
+const RB: u128 = 0x87;
+
+/// A cipher-based message authentication code is a method for creating a
+/// [MAC](crate::Mac) from a [block cipher](crate::BlockCipher) with 128-bit
+/// blocks, as specified in [RFC 4493](https://www.rfc-editor.org/rfc/rfc4493)
+/// (also known as OMAC1).
+///
+/// # CBC-MAC
+///
+/// CMAC builds on CBC-MAC, which encrypts the message in [CBC
+/// mode](crate::Cbc) with a zero IV and uses the last block of ciphertext as
+/// the tag:
+///
+/// $$
+/// X_i = E_K(X_{i-1} \oplus M_i), \quad X_0 = 0
+/// $$
+///
+/// CBC-MAC is only secure for messages of a single fixed length. Given the tag
+/// $T$ of a one-block message $M$, the tag of the two-block message $M
+/// \parallel (M \oplus T)$ is again $T$, so an attacker can forge tags for
+/// longer messages. Messages which aren't a multiple of the block size also
+/// need padding, and a plain zero padding gives $M$ and $M \parallel 0$ the
+/// same tag.
+///
+/// # Subkeys
+///
+/// CMAC fixes both problems by XORing the last block with one of two subkeys
+/// before encrypting it. The subkeys are derived from $L = E_K(0^{128})$ by
+/// doubling in $GF(2^{128})$, where doubling is a left shift followed by
+/// XORing in $\mathrm{87}_{16}$ if the top bit fell off:
+///
+/// $$
+/// K_1 = 2 \cdot L, \quad K_2 = 2 \cdot K_1
+/// $$
+///
+/// If the last block is complete, it is XORed with $K_1$. Otherwise, it is
+/// padded with a single $1$ bit followed by zeros, and XORed with $K_2$. The
+/// empty message counts as a single incomplete block. Since the attacker
+/// doesn't know the subkeys, he can't predict the input of the last
+/// encryption, and since the two cases use different subkeys, a padded message
+/// can't collide with an unpadded one.
+///
+/// # Keys
+///
+/// The key passed to [`mac`](Mac::mac) is used directly as the key of the
+/// block cipher, so it must have exactly the key size of the cipher.
+/// Otherwise, `mac` panics.
+#[docext]
+pub struct Cmac<Cip>(Cip);
+
+impl<Cip> Cmac<Cip> {
+    pub fn new(cip: Cip) -> Self {
+        Self(cip)
+    }
+}
+
+impl<Cip> Cmac<Cip>
+where
+    Cip: BlockCipher<Block = [u8; BLOCK_SIZE]>,
+    Cip::Key: Clone,
+{
+    /// Derive the [subkeys](Cmac#subkeys) $K_1$ and $K_2$ of the key.
+    #[docext]
+    pub fn subkeys(&self, key: Cip::Key) -> ([u8; BLOCK_SIZE], [u8; BLOCK_SIZE]) {
+        let l = u128::from_be_bytes(self.0.encrypt([0; BLOCK_SIZE], key));
+        let k1 = double(l);
+        let k2 = double(k1);
+        (k1.to_be_bytes(), k2.to_be_bytes())
+    }
+}
+
+impl<Cip> Mac for Cmac<Cip>
+where
+    Cip: BlockCipher<Block = [u8; BLOCK_SIZE]>,
+    Cip::Key: Clone + for<'a> TryFrom<&'a [u8]>,
+{
+    type Tag = [u8; BLOCK_SIZE];
+
+    fn mac(&mut self, msg: &[u8], key: &[u8]) -> Self::Tag {
+        let key = Cip::Key::try_from(key)
+            .unwrap_or_else(|_| panic!("CMAC key of {} bytes is invalid", key.len()));
+        let (k1, k2) = self.subkeys(key.clone());
+
+        // Split off the last block, which is handled separately. The empty
+        // message has a single, empty last block.
+        let split = msg.len().saturating_sub(1) / BLOCK_SIZE * BLOCK_SIZE;
+        let (init, last) = msg.split_at(split);
+        let mut last_block = [0; BLOCK_SIZE];
+        last_block[..last.len()].copy_from_slice(last);
+        let subkey = if last.len() == BLOCK_SIZE {
+            k1
+        } else {
+            last_block[last.len()] = 0x80;
+            k2
+        };
+
+        // CBC-MAC over the complete blocks, then the last block masked with
+        // the subkey.
+        let x = init
+            .chunks_exact(BLOCK_SIZE)
+            .fold([0; BLOCK_SIZE], |x, block| {
+                self.0
+                    .encrypt(xor(x, block.try_into().unwrap()), key.clone())
+            });
+        self.0.encrypt(xor(xor(x, last_block), subkey), key)
+    }
+}
+
+/// Multiply by $x$ in $GF(2^{128})$, without branching on the top bit.
+#[docext]
+fn double(l: u128) -> u128 {
+    (l << 1) ^ (RB & (l >> 127).wrapping_neg())
+}
+
+fn xor(a: [u8; BLOCK_SIZE], b: [u8; BLOCK_SIZE]) -> [u8; BLOCK_SIZE] {
+    let mut result = a;
+    result.iter_mut().zip(b).for_each(|(r, b)| *r ^= b);
+    result
+}
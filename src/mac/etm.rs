@@ -0,0 +1,147 @@
+use {
+    crate::{
+        util::{self, SmallBuf},
+        Cipher,
+        Hmac,
+        Mac,
+        Sha256,
+    },
+    std::fmt,
+};
+
+const FINGERPRINT_DOMAIN: &[u8] = b"literate-crypto key fingerprint v1";
+const ENCRYPTION_DOMAIN: &[u8] = b"literate-crypto envelope encryption key v1";
+const AUTHENTICATION_DOMAIN: &[u8] = b"literate-crypto envelope authentication key v1";
+const FINGERPRINT_SIZE: usize = 8;
+
+/// Compute a short fingerprint of a key, suitable for checking that two
+/// parties hold the same key.
+///
+/// The fingerprint is the first eight bytes of an [HMAC-SHA256](Hmac) tag
+/// over a fixed domain string, keyed with the given key. Unlike the classic
+/// [key check value](crate::kcv), it doesn't reveal the output of the
+/// underlying cipher for any plaintext, and it is unrelated to any other use
+/// of the key as long as the domain string isn't reused.
+///
+/// The key length is appended to the domain string, because HMAC pads short
+/// keys with zeros and would otherwise give e.g. the empty key and a single
+/// zero byte the same fingerprint.
+pub fn key_fingerprint(key: &[u8]) -> [u8; FINGERPRINT_SIZE] {
+    let mut msg = SmallBuf::<{ FINGERPRINT_DOMAIN.len() + 8 }>::new();
+    msg.extend_from_slice(FINGERPRINT_DOMAIN);
+    msg.extend_from_slice(&u64::try_from(key.len()).unwrap().to_be_bytes());
+    let tag = Hmac::new(Sha256::default()).mac(&msg, key);
+    let mut result = [0; FINGERPRINT_SIZE];
+    result.copy_from_slice(&tag[..FINGERPRINT_SIZE]);
+    result
+}
+
+/// Encrypt-then-MAC is a method of combining a [cipher](crate::Cipher) with a
+/// [MAC](crate::Mac) into authenticated encryption.
+///
+/// The plaintext is first encrypted, and then the MAC tag is computed over the
+/// ciphertext. When decrypting, the tag is checked before the ciphertext is
+/// ever decrypted, so tampered messages are rejected without exposing the
+/// decryption routine (and e.g. its padding checks) to an attacker.
+///
+/// The cipher and MAC keys are derived from a single master key using
+/// [HMAC-SHA256](Hmac) with distinct domain strings, so the same bytes are
+/// never used as both an encryption and an authentication key. The sealed
+/// envelope has the layout
+///
+/// ```text
+/// fingerprint (8 bytes) || ciphertext || tag
+/// ```
+///
+/// where the fingerprint is the [key fingerprint](key_fingerprint) of the
+/// master key. It lets [`open`](EncryptThenMac::open) fail fast with
+/// [`EnvelopeError::WrongKey`] when the wrong key is used, instead of
+/// reporting that the data was tampered with. The fingerprint is also covered
+/// by the tag.
+///
+/// Any nonce or IV is part of the cipher configuration, so the same cipher
+/// instance must not be used to seal more than one message when its mode
+/// requires unique nonces, such as [CTR](crate::Ctr).
+#[derive(Debug)]
+pub struct EncryptThenMac<Cip, M> {
+    cip: Cip,
+    mac: M,
+}
+
+impl<Cip, M> EncryptThenMac<Cip, M> {
+    pub fn new(cip: Cip, mac: M) -> Self {
+        Self { cip, mac }
+    }
+}
+
+impl<Cip, M, const TAG_SIZE: usize> EncryptThenMac<Cip, M>
+where
+    Cip: Cipher,
+    Cip::Key: Default + AsMut<[u8]>,
+    M: Mac<Tag = [u8; TAG_SIZE]>,
+{
+    /// Encrypt and authenticate the plaintext with the given master key.
+    pub fn seal(&mut self, data: &[u8], key: &[u8]) -> Result<Vec<u8>, Cip::EncryptionErr> {
+        let (enc_key, mac_key) = derive_keys::<Cip::Key>(key);
+        let mut envelope = key_fingerprint(key).to_vec();
+        envelope.extend(self.cip.encrypt_from(data, enc_key)?);
+        let tag = self.mac.mac(&envelope, &mac_key);
+        envelope.extend(tag);
+        Ok(envelope)
+    }
+
+    /// Verify and decrypt an envelope created by
+    /// [`seal`](EncryptThenMac::seal).
+    pub fn open(&mut self, envelope: &[u8], key: &[u8]) -> Result<Vec<u8>, EnvelopeError> {
+        if envelope.len() < FINGERPRINT_SIZE + TAG_SIZE {
+            return Err(EnvelopeError::Tampered);
+        }
+        if envelope[..FINGERPRINT_SIZE] != key_fingerprint(key) {
+            return Err(EnvelopeError::WrongKey);
+        }
+
+        let (enc_key, mac_key) = derive_keys::<Cip::Key>(key);
+        let (authenticated, tag) = envelope.split_at(envelope.len() - TAG_SIZE);
+        if !util::tags_equal(&self.mac.mac(authenticated, &mac_key), tag) {
+            return Err(EnvelopeError::Tampered);
+        }
+        self.cip
+            .decrypt_from(&authenticated[FINGERPRINT_SIZE..], enc_key)
+            .map_err(|_| EnvelopeError::Tampered)
+    }
+}
+
+/// Error returned when opening an [`EncryptThenMac`] envelope fails.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EnvelopeError {
+    /// The envelope was sealed with a different key.
+    WrongKey,
+    /// The envelope was truncated or modified after it was sealed.
+    Tampered,
+}
+
+impl fmt::Display for EnvelopeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::WrongKey => f.write_str("envelope was sealed with a different key"),
+            Self::Tampered => f.write_str("envelope authentication failed"),
+        }
+    }
+}
+
+impl std::error::Error for EnvelopeError {}
+
+/// Derive the cipher key and the MAC key from the master key.
+fn derive_keys<K: Default + AsMut<[u8]>>(key: &[u8]) -> (K, [u8; 32]) {
+    let mut hmac = Hmac::new(Sha256::default());
+    let mut enc_key = K::default();
+    for (i, chunk) in enc_key.as_mut().chunks_mut(32).enumerate() {
+        let mut msg = SmallBuf::<{ ENCRYPTION_DOMAIN.len() + 4 }>::new();
+        msg.extend_from_slice(ENCRYPTION_DOMAIN);
+        msg.extend_from_slice(&u32::try_from(i).unwrap().to_be_bytes());
+        let block = hmac.mac(&msg, key);
+        chunk.copy_from_slice(&block[..chunk.len()]);
+    }
+    let mac_key = hmac.mac(AUTHENTICATION_DOMAIN, key);
+    (enc_key, mac_key)
+}
@@ -1,19 +1,63 @@
-use std::fmt;
+use {
+    crate::{util, Csprng},
+    std::{borrow::Cow, fmt},
+};
 
 pub mod ecc;
 
 use docext::docext;
 pub use ecc::{
+    hpke_open,
+    hpke_seal,
+    AdaptorSignature,
+    Bip340Signature,
+    BlindSchnorr,
+    BlindSession,
+    Blinding,
     Ecdsa,
     EcdsaSignature,
+    Ecies,
+    EciesCiphertext,
+    EciesError,
+    EciesKem,
+    HpkeContext,
+    HpkeError,
+    IncompleteSession,
+    InvalidKeyAgg,
     InvalidPrivateKey,
+    InvalidRound,
+    InvalidSchnorrRandomness,
+    KeyAggContext,
+    MuSig2,
+    MuSig2Nonce,
+    MuSig2PublicNonce,
     MultiSchnorr,
+    NonceCommitment,
+    PartialAggregate,
+    PartialSignature,
+    RegisteredNonce,
     Schnorr,
+    SchnorrBip340,
+    SchnorrLsag,
+    SchnorrLsagSignature,
+    SchnorrNonce,
     SchnorrRandomness,
     SchnorrSag,
+    SchnorrSagError,
     SchnorrSagSignature,
     SchnorrSignature,
     Secp256k1,
+    Secp256r1,
+    SessionError,
+    SessionGuard,
+    SessionId,
+    SessionRegistry,
+    SessionRejected,
+    SignatureShare,
+    SigningSession,
+    TooManySessions,
+    Transcript,
+    XOnlyPublicKey,
 };
 
 // TODO Probably split these interfaces into different modules
@@ -37,13 +81,55 @@ pub use ecc::{
 ///
 /// Signatures are typically short, and usually message [hashes](crate::Hash)
 /// are signed rather than the raw message text.
+///
+/// # Contexts
+///
+/// The same key is sometimes used by multiple protocols. If the protocols sign
+/// messages with a similar format, a signature made for one protocol can be
+/// replayed in another, where it might mean something entirely different. To
+/// prevent this, each protocol can sign under its own _context_, a short byte
+/// string such as the name of the protocol. A signature made under one context
+/// doesn't verify under any other context.
+///
+/// The context is not simply concatenated with the message, since then the
+/// context "ab" with the message "c" would be indistinguishable from the
+/// context "a" with the message "bc". Instead, the signed bytes are a fixed
+/// domain tag, followed by the length of the context, the context, and finally
+/// the message. The empty context is special: the message is signed as-is, so
+/// that [`sign`](SignatureScheme::sign) and
+/// [`verify`](SignatureScheme::verify) stay compatible with signatures made
+/// before contexts existed. Protocols that care about domain separation should
+/// therefore always use a non-empty context.
 pub trait SignatureScheme {
     type PublicKey;
     type PrivateKey;
     type Signature;
 
+    /// Sign the given message with the given private key under the given
+    /// [context](SignatureScheme#contexts).
+    fn sign_with_context(
+        &mut self,
+        key: Self::PrivateKey,
+        context: &[u8],
+        msg: &[u8],
+    ) -> Self::Signature;
+
+    /// Verify that the given message was signed under the given
+    /// [context](SignatureScheme#contexts) by the private key corresponding to
+    /// the given public key. If verification fails, an [`InvalidSignature`]
+    /// error is returned.
+    fn verify_with_context(
+        &mut self,
+        key: Self::PublicKey,
+        context: &[u8],
+        msg: &[u8],
+        sig: &Self::Signature,
+    ) -> Result<(), InvalidSignature>;
+
     /// Sign the given message with the given private key.
-    fn sign(&mut self, key: Self::PrivateKey, msg: &[u8]) -> Self::Signature;
+    fn sign(&mut self, key: Self::PrivateKey, msg: &[u8]) -> Self::Signature {
+        self.sign_with_context(key, &[], msg)
+    }
 
     /// Verify that the given message was signed by the private key
     /// corresponding to the given public key. If verification fails, an
@@ -53,7 +139,61 @@ pub trait SignatureScheme {
         key: Self::PublicKey,
         msg: &[u8],
         sig: &Self::Signature,
-    ) -> Result<(), InvalidSignature>;
+    ) -> Result<(), InvalidSignature> {
+        self.verify_with_context(key, &[], msg, sig)
+    }
+}
+
+/// Signing takes `&mut self`, so a [signature scheme](SignatureScheme) can
+/// only be forwarded through exclusive references and boxes.
+impl<T: SignatureScheme + ?Sized> SignatureScheme for &mut T {
+    type PublicKey = T::PublicKey;
+    type PrivateKey = T::PrivateKey;
+    type Signature = T::Signature;
+
+    fn sign_with_context(
+        &mut self,
+        key: Self::PrivateKey,
+        context: &[u8],
+        msg: &[u8],
+    ) -> Self::Signature {
+        T::sign_with_context(self, key, context, msg)
+    }
+
+    fn verify_with_context(
+        &mut self,
+        key: Self::PublicKey,
+        context: &[u8],
+        msg: &[u8],
+        sig: &Self::Signature,
+    ) -> Result<(), InvalidSignature> {
+        T::verify_with_context(self, key, context, msg, sig)
+    }
+}
+
+impl<T: SignatureScheme + ?Sized> SignatureScheme for Box<T> {
+    type PublicKey = T::PublicKey;
+    type PrivateKey = T::PrivateKey;
+    type Signature = T::Signature;
+
+    fn sign_with_context(
+        &mut self,
+        key: Self::PrivateKey,
+        context: &[u8],
+        msg: &[u8],
+    ) -> Self::Signature {
+        T::sign_with_context(self, key, context, msg)
+    }
+
+    fn verify_with_context(
+        &mut self,
+        key: Self::PublicKey,
+        context: &[u8],
+        msg: &[u8],
+        sig: &Self::Signature,
+    ) -> Result<(), InvalidSignature> {
+        T::verify_with_context(self, key, context, msg, sig)
+    }
 }
 
 /// A multisig scheme is similar to a [regular signature](SignatureScheme),
@@ -65,14 +205,109 @@ pub trait SignatureScheme {
 /// his signature. This is useful, for example, for implementing a shared bank
 /// account on which transactions can only go though with the approval of every
 /// owner.
+///
+/// # Rounds
+///
+/// Signing is an interactive protocol. Every actor takes part in two rounds,
+/// and each round ends with every actor broadcasting a message to the others:
+///
+/// 1. In [`round1`](MultisigScheme::round1), each actor picks a secret
+///    [nonce](MultisigScheme::Nonce), which he keeps to himself, and broadcasts
+///    a [message](MultisigScheme::Round1Msg) committing to it.
+/// 2. Once the first-round messages of all actors have arrived, each actor
+///    computes his individual signature in [`round2`](MultisigScheme::round2),
+///    consuming his nonce, and broadcasts it as a
+///    [message](MultisigScheme::Round2Msg).
+///
+/// Finally, anybody who has received the second-round messages of all actors
+/// can [aggregate](MultisigScheme::aggregate) them into a
+/// [multisig](MultisigScheme::Multisig). The aggregator doesn't need any
+/// secrets, so it doesn't have to be one of the actors.
+///
+/// Schemes which need fewer rounds can use `()` as the message of the unused
+/// round.
+///
+/// # Ordering
+///
+/// The messages of a round arrive in whatever order the network delivers
+/// them, so implementations must not depend on it: passing the messages of a
+/// round in any order must produce the same result. In particular, the
+/// multisig doesn't depend on the order in which the actors signed. Every
+/// actor must see the same set of first-round messages, and the set of
+/// pubkeys must be the same for everybody, including the verifier.
+///
+/// A multisig which is missing the individual signature of any actor must not
+/// verify. [`aggregate`](MultisigScheme::aggregate) rejects such multisigs
+/// outright.
 pub trait MultisigScheme {
-    type Multisig: Default;
+    type Multisig;
     type PublicKey;
     type PrivateKey;
+    /// The secret which an actor keeps between the two rounds. It is consumed
+    /// by the second round, so that it can't be used for two signatures.
+    type Nonce;
+    /// The message which each actor broadcasts at the end of the first round.
+    type Round1Msg;
+    /// The message which each actor broadcasts at the end of the second round,
+    /// containing his individual signature.
+    type Round2Msg;
+    /// The error returned by the second round if the first-round messages are
+    /// invalid.
+    type RoundError;
+
+    /// Pick a fresh nonce for the given private key using the given source of
+    /// randomness, returning the nonce along with the message to broadcast.
+    fn round1(
+        &mut self,
+        key: &Self::PrivateKey,
+        rng: &mut impl Csprng,
+    ) -> (Self::Nonce, Self::Round1Msg);
+
+    /// Sign the given message with the given private key under the given
+    /// [context](SignatureScheme#contexts), using the nonce from the first
+    /// round and the first-round messages of all actors, including the
+    /// actor's own message. All actors must sign under the same context.
+    fn round2_with_context(
+        &mut self,
+        key: &Self::PrivateKey,
+        nonce: Self::Nonce,
+        keys: &[Self::PublicKey],
+        round1: &[Self::Round1Msg],
+        context: &[u8],
+        msg: &[u8],
+    ) -> Result<Self::Round2Msg, Self::RoundError>;
+
+    /// Combine the second-round messages of all actors with the given pubkeys
+    /// into a multisig. Fails unless there is exactly one message from every
+    /// actor, and the messages are consistent with each other.
+    fn aggregate(
+        &self,
+        keys: &[Self::PublicKey],
+        round2: &[Self::Round2Msg],
+    ) -> Result<Self::Multisig, InvalidSignature>;
 
-    /// Sign the given message with the given private key and append the
-    /// individual signature to the given multisig.
-    fn sign(&mut self, key: Self::PrivateKey, msg: &[u8], sig: Self::Multisig) -> Self::Multisig;
+    /// Verify the given multisig under the given
+    /// [context](SignatureScheme#contexts).
+    fn verify_with_context(
+        &mut self,
+        keys: &[Self::PublicKey],
+        context: &[u8],
+        msg: &[u8],
+        sig: &Self::Multisig,
+    ) -> Result<(), InvalidSignature>;
+
+    /// Sign the given message, see
+    /// [`round2_with_context`](MultisigScheme::round2_with_context).
+    fn round2(
+        &mut self,
+        key: &Self::PrivateKey,
+        nonce: Self::Nonce,
+        keys: &[Self::PublicKey],
+        round1: &[Self::Round1Msg],
+        msg: &[u8],
+    ) -> Result<Self::Round2Msg, Self::RoundError> {
+        self.round2_with_context(key, nonce, keys, round1, &[], msg)
+    }
 
     /// Verify the given multisig.
     fn verify(
@@ -80,7 +315,9 @@ pub trait MultisigScheme {
         keys: &[Self::PublicKey],
         msg: &[u8],
         sig: &Self::Multisig,
-    ) -> Result<(), InvalidSignature>;
+    ) -> Result<(), InvalidSignature> {
+        self.verify_with_context(keys, &[], msg, sig)
+    }
 }
 
 /// Ring signature scheme.
@@ -104,14 +341,171 @@ pub trait RingScheme {
     type PublicKey;
     type PrivateKey;
 
-    fn sign(
+    /// Sign the given message under the given
+    /// [context](SignatureScheme#contexts), hiding the private key among the
+    /// decoys. Ring signatures are randomized, and the randomness is drawn
+    /// from the given source, typically a [CSPRNG](crate::Csprng).
+    fn sign_with_context(
         &mut self,
         key: Self::PrivateKey,
         decoys: &[Self::PublicKey],
+        context: &[u8],
         msg: &[u8],
+        rng: &mut impl Csprng,
     ) -> Self::RingSignature;
 
-    fn verify(&mut self, msg: &[u8], sig: &Self::RingSignature) -> Result<(), InvalidSignature>;
+    /// Verify the given ring signature under the given
+    /// [context](SignatureScheme#contexts).
+    fn verify_with_context(
+        &mut self,
+        context: &[u8],
+        msg: &[u8],
+        sig: &Self::RingSignature,
+    ) -> Result<(), InvalidSignature>;
+
+    fn sign(
+        &mut self,
+        key: Self::PrivateKey,
+        decoys: &[Self::PublicKey],
+        msg: &[u8],
+        rng: &mut impl Csprng,
+    ) -> Self::RingSignature {
+        self.sign_with_context(key, decoys, &[], msg, rng)
+    }
+
+    fn verify(&mut self, msg: &[u8], sig: &Self::RingSignature) -> Result<(), InvalidSignature> {
+        self.verify_with_context(&[], msg, sig)
+    }
+}
+
+/// Linkable ring signature scheme.
+///
+/// A [ring signature](RingScheme) hides which of the pubkeys signed, so two
+/// ring signatures by the same private key can't be told apart from two
+/// signatures by different keys. That rules out anything where a key may only
+/// act once, such as voting or spending a coin: nothing stops the same
+/// signer from signing over and over.
+///
+/// A linkable ring signature additionally carries a _key image_, which is
+/// derived from the private key alone. Every signature by the same private
+/// key has the same key image, whatever the message or the decoys, while the
+/// key image still doesn't reveal which pubkey it belongs to. A verifier
+/// keeps the key images it has seen, and rejects a signature whose key image
+/// is already among them.
+#[docext]
+pub trait LinkableRingScheme: RingScheme {
+    type KeyImage: PartialEq;
+
+    /// The key image of the given signature.
+    fn key_image(&self, sig: &Self::RingSignature) -> Self::KeyImage;
+
+    /// Check whether the two signatures were made by the same private key.
+    /// Only meaningful for signatures which [verify](RingScheme::verify).
+    fn linked(&self, a: &Self::RingSignature, b: &Self::RingSignature) -> bool {
+        self.key_image(a) == self.key_image(b)
+    }
+}
+
+/// A key encapsulation mechanism (KEM) establishes a shared secret with the
+/// holder of a private key.
+///
+/// The sender _encapsulates_ to the public key of the recipient, which yields
+/// a fresh random shared secret and a _ciphertext_. The ciphertext is sent to
+/// the recipient, who _decapsulates_ it with the private key to get the same
+/// shared secret. Nobody else can learn the secret from the ciphertext.
+///
+/// Unlike [encryption](crate::Ecies), a KEM doesn't transport a chosen message,
+/// only a secret chosen by the mechanism itself. The secret is then used as a
+/// key, e.g. for a [cipher](crate::Cipher) and a [MAC](crate::Mac). This
+/// narrower interface is the one shared by Diffie-Hellman and post-quantum
+/// schemes, so that protocols built on a KEM can switch between them.
+pub trait Kem {
+    type PublicKey;
+    type PrivateKey;
+    type Ciphertext;
+
+    /// Generate a shared secret for the given public key, drawing the
+    /// randomness from the given source, typically a
+    /// [CSPRNG](crate::Csprng). Returns the ciphertext to send to the
+    /// recipient, and the shared secret.
+    fn encapsulate(
+        &mut self,
+        key: &Self::PublicKey,
+        rng: &mut impl Csprng,
+    ) -> (Self::Ciphertext, SharedSecret);
+
+    /// Recover the shared secret from the ciphertext with the private key.
+    /// Fails if the ciphertext is malformed.
+    fn decapsulate(
+        &self,
+        key: &Self::PrivateKey,
+        ciphertext: &Self::Ciphertext,
+    ) -> Result<SharedSecret, DecapsError>;
+}
+
+/// A secret shared through a [KEM](Kem).
+///
+/// The [`Debug`](fmt::Debug) output only shows a
+/// [fingerprint](crate::key_fingerprint) of the secret, and comparisons don't
+/// exit early on the first differing byte.
+#[derive(Clone, Eq)]
+pub struct SharedSecret(Vec<u8>);
+
+impl SharedSecret {
+    pub(crate) fn new(secret: Vec<u8>) -> Self {
+        Self(secret)
+    }
+
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.0
+    }
+}
+
+impl PartialEq for SharedSecret {
+    fn eq(&self, other: &Self) -> bool {
+        util::tags_equal(&self.0, &other.0)
+    }
+}
+
+impl fmt::Debug for SharedSecret {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("SharedSecret")
+            .field(&util::Redacted(&self.0))
+            .finish()
+    }
+}
+
+/// Error indicating that a [KEM](Kem) ciphertext couldn't be decapsulated.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DecapsError;
+
+impl fmt::Display for DecapsError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid KEM ciphertext")
+    }
+}
+
+impl std::error::Error for DecapsError {}
+
+/// Domain tag preceding the context in the signed bytes.
+const CONTEXT_TAG: &[u8] = b"literate-crypto signature context";
+
+/// Frame the message with the given [context](SignatureScheme#contexts). The
+/// empty context leaves the message unchanged.
+pub(crate) fn with_context<'a>(context: &[u8], msg: &'a [u8]) -> Cow<'a, [u8]> {
+    if context.is_empty() {
+        return Cow::Borrowed(msg);
+    }
+    let len = u64::try_from(context.len()).unwrap().to_be_bytes();
+    Cow::Owned(
+        CONTEXT_TAG
+            .iter()
+            .chain(&len)
+            .chain(context)
+            .chain(msg)
+            .copied()
+            .collect(),
+    )
 }
 
 /// Error indicating that a signature is invalid.
@@ -125,3 +519,16 @@ impl fmt::Display for InvalidSignature {
 }
 
 impl std::error::Error for InvalidSignature {}
+
+/// Error indicating that some signatures in a batch are invalid, with the
+/// indices of those signatures in the batch, in increasing order.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InvalidBatch(pub Vec<usize>);
+
+impl fmt::Display for InvalidBatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid signatures at indices {:?}", self.0)
+    }
+}
+
+impl std::error::Error for InvalidBatch {}
@@ -1,38 +1,121 @@
 //! Elliptic curve cryptography.
 
-use std::{fmt, marker::PhantomData};
+use {
+    crate::{
+        util::{self, canonical, CollectVec, SmallBuf, Zeroize},
+        Csprng,
+        Hash,
+        Hkdf,
+        Sha256,
+    },
+    docext::docext,
+    std::{cmp, fmt, hash, marker::PhantomData},
+};
 
 mod curve;
 mod ecdsa;
+mod ecies;
+mod group;
+mod hpke;
+mod jacobian;
+pub mod jose;
+mod kem;
 mod num;
+mod prime;
+mod scalar;
 mod schnorr;
 mod secp256k1;
+mod secp256r1;
+mod table;
+mod transcript;
 
+pub(crate) use {curve::compressed, num::add as add_words, scalar::digest_scalar};
 pub use {
-    curve::{Coordinates, Curve, InvalidPoint, Point},
+    curve::{hash_to_point, mul_double, Coordinates, Curve, CurveError, InvalidPoint, Point},
     ecdsa::{Ecdsa, EcdsaSignature},
+    ecies::{Ecies, EciesCiphertext, EciesError},
+    group::Group,
+    hpke::{hpke_open, hpke_seal, HpkeContext, HpkeError},
+    kem::EciesKem,
     num::Num,
+    prime::{is_prime_u64, next_prime_u64},
+    scalar::{DigestSizeMismatch, Scalar},
     schnorr::{
+        AdaptorSignature,
+        Bip340Signature,
+        BlindSchnorr,
+        BlindSession,
+        Blinding,
+        IncompleteSession,
+        InvalidKeyAgg,
+        InvalidRound,
+        InvalidSchnorrRandomness,
+        KeyAggContext,
+        MuSig2,
+        MuSig2Nonce,
+        MuSig2PublicNonce,
         MultiSchnorr,
+        NonceCommitment,
+        PartialAggregate,
+        PartialSignature,
+        RegisteredNonce,
         Schnorr,
+        SchnorrBip340,
+        SchnorrLsag,
+        SchnorrLsagSignature,
+        SchnorrNonce,
         SchnorrRandomness,
         SchnorrSag,
+        SchnorrSagError,
         SchnorrSagSignature,
         SchnorrSignature,
+        SessionError,
+        SessionGuard,
+        SessionId,
+        SessionRegistry,
+        SessionRejected,
+        SignatureShare,
+        SigningSession,
+        TooManySessions,
+        XOnlyPublicKey,
     },
     secp256k1::Secp256k1,
+    secp256r1::Secp256r1,
+    transcript::Transcript,
 };
+#[cfg(test)]
+pub(crate) use {num::take_wiped, prime::miller_rabin};
 
-#[derive(Debug)]
+/// A private key, a nonzero scalar below the [order](Curve::N) of the curve.
+///
+/// The [`Debug`](fmt::Debug) output only shows a
+/// [fingerprint](crate::key_fingerprint) of the key, so that a stray `{:?}`
+/// doesn't leak it into the logs.
+///
+/// The scalar is wiped when the key is dropped. For that reason the key isn't
+/// `Copy`: every copy is an explicit [`clone`](Clone::clone), which is wiped
+/// on its own when it goes out of scope.
 pub struct PrivateKey<C>(num::Num, PhantomData<C>);
 
+impl<C> fmt::Debug for PrivateKey<C> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("PrivateKey")
+            .field(&util::Redacted(&self.0.to_be_bytes()))
+            .finish()
+    }
+}
+
 impl<C> Clone for PrivateKey<C> {
     fn clone(&self) -> Self {
-        *self
+        Self(self.0, PhantomData)
     }
 }
 
-impl<C> Copy for PrivateKey<C> {}
+impl<C> Drop for PrivateKey<C> {
+    fn drop(&mut self) {
+        self.0.wipe();
+    }
+}
 
 impl<C: Curve> PrivateKey<C> {
     pub fn new(n: num::Num) -> Result<Self, InvalidPrivateKey> {
@@ -44,12 +127,153 @@ impl<C: Curve> PrivateKey<C> {
         }
     }
 
+    /// The secret scalar, for debugging only.
+    #[cfg(feature = "insecure-reveal-secrets")]
+    pub fn reveal_secret_for_debug(&self) -> Num {
+        self.0
+    }
+
     /// Derive the [public key](PublicKey) from a private key.
     ///
     /// This is done by simply multiplying the private key with the [generator
-    /// point](crate::ecc::Curve::g).
+    /// point](crate::ecc::Curve::g), using the [precomputed
+    /// multiples](Curve::mul_g) of the generator.
     pub fn derive(&self) -> PublicKey<C> {
-        PublicKey::new(self.0 * C::g()).unwrap()
+        // The key is in [1, N), so the product is never the point at infinity.
+        debug_assert!(self.0 != Num::ZERO && self.0 < C::N);
+        PublicKey::new(C::mul_g(self.0)).unwrap()
+    }
+
+    /// Generate a uniformly random private key using the given source of
+    /// randomness.
+    ///
+    /// The key is [drawn below the curve order](Num::random_mod), and drawn
+    /// again in the unlikely case that it's zero.
+    pub fn random(rng: &mut impl Csprng) -> Self {
+        loop {
+            if let Ok(key) = Self::new(Num::random_mod(rng, C::N)) {
+                return key;
+            }
+        }
+    }
+
+    /// Derive an independent private key for the given purpose.
+    ///
+    /// Using one private key for everything means that a weakness in one
+    /// protocol can leak the key used by all the others. Instead, each
+    /// protocol should use its own subkey, with a label naming the protocol.
+    /// Subkeys with different labels are unrelated, and the original key
+    /// can't be recovered from a subkey.
+    ///
+    /// The subkey is derived with [HKDF](crate::Hkdf) based on
+    /// [SHA-256](crate::Sha256), using the private key as the input key
+    /// material, the [curve identifier](Curve::id) as the salt, and the label
+    /// as part of the info. The output is interpreted as a big-endian number
+    /// of [`Curve::SIZE`] bytes, and if it is not a valid private key, the
+    /// derivation is repeated with an incremented counter in the info.
+    pub fn derive_subkey(&self, label: &[u8]) -> PrivateKey<C> {
+        let mut hkdf = Hkdf::new(Sha256::default());
+        let mut info = SUBKEY_INFO.to_vec();
+        info.extend(label);
+        (0u32..)
+            .find_map(|counter| {
+                let mut info = info.clone();
+                info.extend(counter.to_be_bytes());
+                let mut okm = [0; Num::BYTES];
+                // A private key is much shorter than the longest HKDF output.
+                let key = hkdf
+                    .derive(&C::id(), &self.0.to_be_bytes(), &info, C::SIZE)
+                    .unwrap();
+                okm[Num::BYTES - C::SIZE..].copy_from_slice(&key);
+                PrivateKey::new(Num::from_be_bytes(okm)).ok()
+            })
+            .unwrap()
+    }
+}
+
+/// Domain separation tag for [`PrivateKey::derive_subkey`].
+const SUBKEY_INFO: &[u8] = b"literate-crypto subkey";
+
+/// Hash the [curve identifier](Curve::id), the private key and the given data
+/// into the seed of a signing nonce.
+///
+/// Since the curve identifier is part of the preimage, the same scalar signing
+/// the same message on two different curves gets unrelated nonces.
+pub(crate) fn nonce_seed<C: Curve, H: Hash>(
+    hash: &H,
+    key: &PrivateKey<C>,
+    data: &[&[u8]],
+) -> H::Digest {
+    let id = C::id();
+    let mut key = key.0.to_le_bytes();
+    let fields = [&id[..], &key]
+        .into_iter()
+        .chain(data.iter().copied())
+        .collect_vec();
+    let seed = canonical::hash_fields(&hash, &fields);
+    key.zeroize();
+    seed
+}
+
+/// A [private key](PrivateKey) together with its [public key](PublicKey) and
+/// the [identifier of the curve](Curve::id) it was created for.
+///
+/// The type parameter already ties a key to its curve at compile time. The
+/// origin tag keeps that information when the key leaves the type system, so
+/// that keys which end up on the wrong curve can be told apart.
+#[derive(Debug)]
+pub struct KeyPair<C> {
+    private: PrivateKey<C>,
+    public: PublicKey<C>,
+    origin: [u8; 32],
+}
+
+impl<C> Clone for KeyPair<C> {
+    fn clone(&self) -> Self {
+        Self {
+            private: self.private.clone(),
+            public: self.public,
+            origin: self.origin,
+        }
+    }
+}
+
+impl<C: Curve> KeyPair<C> {
+    pub fn new(private: PrivateKey<C>) -> Self {
+        Self {
+            public: private.derive(),
+            private,
+            origin: C::id(),
+        }
+    }
+
+    /// Generate the key pair of a [random private key](PrivateKey::random).
+    pub fn random(rng: &mut impl Csprng) -> Self {
+        Self::new(PrivateKey::random(rng))
+    }
+
+    pub fn private(&self) -> PrivateKey<C> {
+        self.private.clone()
+    }
+
+    pub fn public(&self) -> PublicKey<C> {
+        self.public
+    }
+
+    /// The [identifier of the curve](Curve::id) this key pair was created for.
+    pub fn origin(&self) -> [u8; 32] {
+        self.origin
+    }
+
+    /// Derive the key pair of a [subkey](PrivateKey::derive_subkey).
+    pub fn derive_subkey(&self, label: &[u8]) -> Self {
+        Self::new(self.private.derive_subkey(label))
+    }
+}
+
+impl<C: Curve> From<PrivateKey<C>> for KeyPair<C> {
+    fn from(private: PrivateKey<C>) -> Self {
+        Self::new(private)
     }
 }
 
@@ -68,10 +292,56 @@ impl<C> Clone for PublicKey<C> {
 
 impl<C> Copy for PublicKey<C> {}
 
+/// Public keys are compared in variable time. Unlike a private key or a MAC
+/// tag, a public key is not secret, so the time a comparison takes reveals
+/// nothing an attacker doesn't already know.
+impl<C> PartialEq for PublicKey<C> {
+    fn eq(&self, other: &Self) -> bool {
+        self.x == other.x && self.y == other.y
+    }
+}
+
+impl<C> Eq for PublicKey<C> {}
+
+/// Public keys are ordered like their
+/// [points](Point#impl-Ord-for-Point%3CC%3E), by their compressed SEC1
+/// encoding.
+impl<C> Ord for PublicKey<C> {
+    fn cmp(&self, other: &Self) -> cmp::Ordering {
+        self.compressed()
+            .as_slice()
+            .cmp(other.compressed().as_slice())
+    }
+}
+
+impl<C> PartialOrd for PublicKey<C> {
+    fn partial_cmp(&self, other: &Self) -> Option<cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// Public keys are hashed over their compressed SEC1 encoding, like their
+/// points.
+impl<C> hash::Hash for PublicKey<C> {
+    fn hash<S: hash::Hasher>(&self, state: &mut S) {
+        self.compressed().as_slice().hash(state)
+    }
+}
+
+impl<C> PublicKey<C> {
+    fn compressed(&self) -> SmallBuf<COMPRESSED_SIZE> {
+        curve::compressed(Coordinates::Finite(self.x, self.y))
+    }
+}
+
 impl<C: Curve> PublicKey<C> {
+    /// Create a public key from a point. The point at infinity and points
+    /// outside of the [subgroup](Point::in_subgroup) generated by the
+    /// [generator point](Curve::g) are rejected.
     pub fn new(p: Point<C>) -> Result<Self, InvalidPublicKey> {
         match p.coordinates() {
             Coordinates::Infinity => Err(InvalidPublicKey),
+            Coordinates::Finite(..) if !p.in_subgroup() => Err(InvalidPublicKey),
             Coordinates::Finite(x, y) => Ok(Self {
                 x,
                 y,
@@ -81,6 +351,7 @@ impl<C: Curve> PublicKey<C> {
     }
 
     pub fn point(&self) -> Point<C> {
+        // The coordinates were validated when the key was constructed.
         Point::new(self.x, self.y).unwrap()
     }
 
@@ -91,8 +362,104 @@ impl<C: Curve> PublicKey<C> {
     pub fn y(&self) -> Num {
         self.y
     }
+
+    /// Encode the public key as a SEC1 point, the format used by most other
+    /// libraries, e.g. OpenSSL and Bitcoin.
+    ///
+    /// The uncompressed form is the byte `04` followed by the big-endian $x$
+    /// and $y$ coordinates. The compressed form is `02` for even $y$ or `03`
+    /// for odd $y$, followed by the big-endian $x$. For every $x$ there are
+    /// at most two points, $(x, y)$ and $(x, P - y)$, and since $P$ is odd,
+    /// exactly one of them has an even $y$. So the parity of $y$ is all that
+    /// is needed to recover it from $x$.
+    #[docext]
+    pub fn to_sec1_bytes(&self, compressed: bool) -> Vec<u8> {
+        if compressed {
+            self.compressed().to_vec()
+        } else {
+            self.to_uncompressed().to_vec()
+        }
+    }
+
+    /// Decode a SEC1 point in either [form](PublicKey::to_sec1_bytes),
+    /// [validating](PublicKey::new) it.
+    ///
+    /// For the compressed form, $y$ is recovered as a [square
+    /// root](Num::sqrt) of $x^3 + ax + b$ modulo $P$, picking the root with
+    /// the encoded parity. If $x^3 + ax + b$ has no square root, there is no
+    /// point with the given $x$, and the key is rejected.
+    #[docext]
+    pub fn from_sec1_bytes(bytes: &[u8]) -> Result<Self, InvalidPublicKey> {
+        match bytes.first() {
+            Some(&UNCOMPRESSED) => Self::from_uncompressed(bytes),
+            Some(&prefix @ (COMPRESSED_EVEN | COMPRESSED_ODD))
+                if bytes.len() == COMPRESSED_SIZE =>
+            {
+                // The length was checked above, so the slice has the right size.
+                let x = Num::from_be_bytes(bytes[1..].try_into().unwrap());
+                if x >= C::P {
+                    return Err(InvalidPublicKey);
+                }
+                let rhs = x
+                    .mul(x, C::P)
+                    .mul(x, C::P)
+                    .add(C::A.mul(x, C::P), C::P)
+                    .add(C::B, C::P);
+                let mut y = rhs.sqrt(C::P).ok_or(InvalidPublicKey)?;
+                if y.get_bit(0) != (prefix == COMPRESSED_ODD) {
+                    y = y.neg_mod(C::P);
+                }
+                // For y = 0, both roots are even, so an odd y doesn't exist.
+                if y.get_bit(0) != (prefix == COMPRESSED_ODD) {
+                    return Err(InvalidPublicKey);
+                }
+                Point::new(x, y)
+                    .map_err(|_| InvalidPublicKey)
+                    .and_then(Self::new)
+            }
+            _ => Err(InvalidPublicKey),
+        }
+    }
+
+    /// Encode the public key as an uncompressed SEC1 point: the byte `04`
+    /// followed by the big-endian x and y coordinates.
+    pub(crate) fn to_uncompressed(self) -> [u8; UNCOMPRESSED_SIZE] {
+        let mut result = [0; UNCOMPRESSED_SIZE];
+        result[0] = UNCOMPRESSED;
+        result[1..][..Num::BYTES].copy_from_slice(&self.x.to_be_bytes());
+        result[1 + Num::BYTES..].copy_from_slice(&self.y.to_be_bytes());
+        result
+    }
+
+    /// Decode an uncompressed SEC1 point, [validating](PublicKey::new) it.
+    pub(crate) fn from_uncompressed(bytes: &[u8]) -> Result<Self, InvalidPublicKey> {
+        if bytes.len() != UNCOMPRESSED_SIZE || bytes[0] != UNCOMPRESSED {
+            return Err(InvalidPublicKey);
+        }
+        // The length was checked above, so the slices have the right sizes.
+        let x = Num::from_be_bytes(bytes[1..][..Num::BYTES].try_into().unwrap());
+        let y = Num::from_be_bytes(bytes[1 + Num::BYTES..].try_into().unwrap());
+        Point::new(x, y)
+            .map_err(|_| InvalidPublicKey)
+            .and_then(Self::new)
+    }
 }
 
+/// The prefix of an uncompressed point in the SEC1 encoding.
+const UNCOMPRESSED: u8 = 0x04;
+
+/// The prefix of a compressed point with even y in the SEC1 encoding.
+const COMPRESSED_EVEN: u8 = 0x02;
+
+/// The prefix of a compressed point with odd y in the SEC1 encoding.
+const COMPRESSED_ODD: u8 = 0x03;
+
+/// The size of an uncompressed point in the SEC1 encoding.
+pub(crate) const UNCOMPRESSED_SIZE: usize = 1 + 2 * Num::BYTES;
+
+/// The size of a compressed point in the SEC1 encoding.
+pub(crate) const COMPRESSED_SIZE: usize = 1 + Num::BYTES;
+
 /// Error indicating that a private key is invalid.
 #[derive(Debug, Clone, Copy)]
 pub struct InvalidPrivateKey;
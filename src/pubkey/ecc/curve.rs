@@ -1,7 +1,20 @@
 use {
-    super::num::Num,
+    super::{
+        group::Group,
+        jacobian::Jacobian,
+        num::Num,
+        table,
+        transcript::Transcript,
+        COMPRESSED_SIZE,
+    },
+    crate::{
+        canonical,
+        util::{Choice, CtSelect, SmallBuf},
+        Hash,
+        Sha256,
+    },
     docext::docext,
-    std::{fmt, marker::PhantomData, ops},
+    std::{cmp, fmt, hash, marker::PhantomData, ops},
 };
 
 /// An elliptic curve.
@@ -55,7 +68,7 @@ use {
 /// implementation), usually a prime field is used and operations are carried
 /// out via modular arithmetic.
 #[docext]
-pub trait Curve: Sized {
+pub trait Curve: Sized + 'static {
     /// Size of [`Curve::P`] and [`Curve::N`] in bytes.
     const SIZE: usize;
 
@@ -70,6 +83,19 @@ pub trait Curve: Sized {
     #[docext]
     const N: Num;
 
+    /// The cofactor $h$, the number of points on the curve divided by
+    /// [$N$](Curve::N).
+    ///
+    /// If $h = 1$, as is the case for secp256k1, every finite point on the
+    /// curve is a multiple of the [generator point](Curve::g). Otherwise the
+    /// curve also contains points of small order outside of the subgroup
+    /// generated by $G$. An attacker can submit such a point as a public key,
+    /// and learn the private key modulo its small order from the result of a
+    /// key exchange. Points received from outside must therefore be [checked
+    /// to lie in the subgroup](Point::in_subgroup).
+    #[docext]
+    const COFACTOR: Num = Num::ONE;
+
     /// The $a$ parameter for the elliptic curve equation $y^2 = x^3 + ax + b$.
     #[docext]
     const A: Num;
@@ -84,9 +110,105 @@ pub trait Curve: Sized {
     /// other points on the curve via point multiplication. This point
     /// must generate a cyclic subgroup of the curve. The [cardinality of the
     /// subgroup](Curve::N) should be as large as possible.
+    ///
+    /// The generator is needed by every signature and key derivation, often
+    /// several times, so this should be cheap. Built-in curves return a
+    /// constant instead of validating the coordinates on every call.
     fn g() -> Point<Self>;
+
+    /// Multiply the [generator point](Curve::g) by $n$.
+    ///
+    /// Signing and key derivation multiply the same point over and over, so
+    /// rather than [doubling](Group::scale) it from scratch every time, a
+    /// table of its multiples is computed on the first call and kept for the
+    /// rest of the program. Every later multiplication is a sum of 64 points
+    /// from the table, which is several times faster. The entries are looked
+    /// up without branching on $n$, so it may be secret.
+    ///
+    /// The result is the same as $n \cdot G$.
+    #[docext]
+    fn mul_g(n: Num) -> Point<Self> {
+        table::mul_g(n)
+    }
+
+    /// Check that the curve parameters are consistent.
+    ///
+    /// Curves are defined by hand-typed constants, and a typo in any of them
+    /// would silently produce wrong and insecure results. This method checks
+    /// that:
+    ///
+    /// - $P$ and $N$ are prime,
+    /// - the curve is not singular, i.e. $4a^3 + 27b^2 \neq 0$,
+    /// - $N \neq P$, otherwise the curve is _anomalous_ and discrete logarithms
+    ///   can be computed efficiently,
+    /// - the generator point is finite and lies on the curve, and
+    /// - $N \cdot G = \infty$.
+    ///
+    /// The scalar multiplication makes this method fairly slow, so it is only
+    /// run automatically in debug builds, when the signature schemes are
+    /// constructed.
+    #[docext]
+    fn validate() -> Result<(), CurveError> {
+        if !Self::P.is_probable_prime() {
+            return Err(CurveError::CompositeFieldOrder);
+        }
+        if !Self::N.is_probable_prime() {
+            return Err(CurveError::CompositeGroupOrder);
+        }
+
+        let four = Num::from_le_words([4, 0, 0, 0]).reduce(Self::P);
+        let twenty_seven = Num::from_le_words([27, 0, 0, 0]).reduce(Self::P);
+        let a3 = Self::A.mul(Self::A, Self::P).mul(Self::A, Self::P);
+        let b2 = Self::B.mul(Self::B, Self::P);
+        let discriminant = four
+            .mul(a3, Self::P)
+            .add(twenty_seven.mul(b2, Self::P), Self::P);
+        if discriminant == Num::ZERO {
+            return Err(CurveError::Singular);
+        }
+
+        if Self::N == Self::P {
+            return Err(CurveError::Anomalous);
+        }
+
+        let g = Self::g();
+        match g.coordinates() {
+            Coordinates::Finite(x, y) if on_curve::<Self>(x, y) => {}
+            _ => return Err(CurveError::InvalidGenerator),
+        }
+        if Self::N * g != Point::infinity() {
+            return Err(CurveError::WrongGeneratorOrder);
+        }
+
+        Ok(())
+    }
+
+    /// An identifier of the curve: the [SHA-256](crate::Sha256) hash of all of
+    /// its parameters.
+    ///
+    /// Nothing stops the same private key bytes from being used on two
+    /// different curves. The signature schemes mix this identifier into their
+    /// nonces, so that signing the same message with the same scalar on two
+    /// curves never produces related nonces.
+    fn id() -> [u8; 32] {
+        let (gx, gy) = match Self::g().coordinates() {
+            Coordinates::Finite(x, y) => (x, y),
+            Coordinates::Infinity => (Num::ZERO, Num::ZERO),
+        };
+        let params =
+            [Self::P, Self::N, Self::COFACTOR, Self::A, Self::B, gx, gy].map(|n| n.to_be_bytes());
+        let mut fields = [CURVE_ID_TAG; 8];
+        fields[1..]
+            .iter_mut()
+            .zip(&params)
+            .for_each(|(field, n)| *field = n);
+        canonical::hash_fields(&Sha256::default(), &fields)
+    }
 }
 
+/// Domain separation tag for [`Curve::id`].
+const CURVE_ID_TAG: &[u8] = b"literate-crypto curve";
+
 /// A point on an elliptic curve curve, possibly at infinity.
 #[derive(Debug)]
 pub struct Point<C>(Coordinates, PhantomData<C>);
@@ -107,6 +229,37 @@ impl<C> PartialEq for Point<C> {
 
 impl<C> Eq for Point<C> {}
 
+/// Points are ordered by their compressed SEC1 encoding, compared as bytes.
+/// The point at infinity, encoded as the single byte `00`, comes before every
+/// finite point, which is encoded as `02` or `03` depending on the parity of
+/// $y$, followed by the big-endian $x$. So finite points are ordered first by
+/// the parity of $y$, then by $x$.
+///
+/// The order means nothing in the group, but it is canonical: sorting gives
+/// any set of points the same order, no matter how it was listed.
+#[docext]
+impl<C> Ord for Point<C> {
+    fn cmp(&self, other: &Self) -> cmp::Ordering {
+        compressed(self.0)
+            .as_slice()
+            .cmp(compressed(other.0).as_slice())
+    }
+}
+
+impl<C> PartialOrd for Point<C> {
+    fn partial_cmp(&self, other: &Self) -> Option<cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// Points are hashed over their compressed SEC1 encoding, like they are
+/// [ordered](Point#impl-Ord-for-Point%3CC%3E).
+impl<C> hash::Hash for Point<C> {
+    fn hash<S: hash::Hasher>(&self, state: &mut S) {
+        compressed(self.0).as_slice().hash(state)
+    }
+}
+
 /// Finite point coordinates $(x, y)$ or infinity $\infty$.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 #[docext]
@@ -116,6 +269,55 @@ pub enum Coordinates {
     Finite(Num, Num),
 }
 
+/// Coordinates are selected as a flag for infinity together with $x$ and $y$,
+/// which are zero for the point at infinity, so that both variants go through
+/// the same word operations. Only turning the selected flag back into a
+/// variant branches, which reveals whether the result is infinity but nothing
+/// about the choice.
+#[docext]
+impl CtSelect for Coordinates {
+    fn ct_select(a: &Self, b: &Self, choice: Choice) -> Self {
+        let (a_inf, a_x, a_y) = a.parts();
+        let (b_inf, b_x, b_y) = b.parts();
+        let infinity = u64::ct_select(&a_inf, &b_inf, choice);
+        let x = Num::ct_select(&a_x, &b_x, choice);
+        let y = Num::ct_select(&a_y, &b_y, choice);
+        if infinity == 1 {
+            Self::Infinity
+        } else {
+            Self::Finite(x, y)
+        }
+    }
+
+    fn ct_eq(&self, other: &Self) -> Choice {
+        let (a_inf, a_x, a_y) = self.parts();
+        let (b_inf, b_x, b_y) = other.parts();
+        a_inf.ct_eq(&b_inf) & a_x.ct_eq(&b_x) & a_y.ct_eq(&b_y)
+    }
+}
+
+impl Coordinates {
+    /// The flag for infinity, and the coordinates or zero.
+    fn parts(&self) -> (u64, Num, Num) {
+        match *self {
+            Self::Infinity => (1, Num::ZERO, Num::ZERO),
+            Self::Finite(x, y) => (0, x, y),
+        }
+    }
+}
+
+/// Points are selected by their
+/// [coordinates](Coordinates#impl-CtSelect-for-Coordinates).
+impl<C> CtSelect for Point<C> {
+    fn ct_select(a: &Self, b: &Self, choice: Choice) -> Self {
+        Self(Coordinates::ct_select(&a.0, &b.0, choice), PhantomData)
+    }
+
+    fn ct_eq(&self, other: &Self) -> Choice {
+        self.0.ct_eq(&other.0)
+    }
+}
+
 /// [Elliptic curve](Curve) points are added together by first constructing a
 /// line through the two points, then finding the intersection of that line with
 /// the curve. The intersection is the result. If the two points are equal, a
@@ -151,9 +353,14 @@ impl<C: Curve> ops::Add for Point<C> {
                 let Some(inv) = Num::TWO.mul(y1, C::P).inv(C::P) else {
                     return Self(Coordinates::Infinity, Default::default());
                 };
-                let h = Num::THREE.mul(x1, C::P).mul(x1, C::P).mul(inv, C::P);
+                let h = Num::THREE
+                    .mul(x1, C::P)
+                    .mul(x1, C::P)
+                    .add(C::A, C::P)
+                    .mul(inv, C::P);
                 let x = h.mul(h, C::P).sub(Num::TWO.mul(x1, C::P), C::P);
                 let s = x1.sub(x, C::P);
+                // The sum of two points on the curve is on the curve.
                 Self::new(x, h.mul(s, C::P).sub(y1, C::P)).unwrap()
             }
             (Coordinates::Finite(x1, y1), Coordinates::Finite(x2, y2)) => {
@@ -164,12 +371,54 @@ impl<C: Curve> ops::Add for Point<C> {
                 let h = y2.sub(y1, C::P).mul(inv, C::P);
                 let x = h.mul(h, C::P).sub(x1, C::P).sub(x2, C::P);
                 let s = x1.sub(x, C::P);
+                // The sum of two points on the curve is on the curve.
                 Self::new(x, h.mul(s, C::P).sub(y1, C::P)).unwrap()
             }
         }
     }
 }
 
+/// Points form a [group](Group) under addition, with the point at infinity as
+/// the identity. The inverse of $(x, y)$ is its reflection $(x, -y)$ over the
+/// x-axis.
+///
+/// Scalar multiplication runs in Jacobian coordinates, which
+/// trade the inversion in every addition for a few more multiplications, and
+/// converts back to affine coordinates once at the end. The result is the
+/// same point as repeated affine addition would give.
+#[docext]
+impl<C: Curve> Group for Point<C> {
+    fn identity() -> Self {
+        Self::infinity()
+    }
+
+    fn op(self, rhs: Self) -> Self {
+        self + rhs
+    }
+
+    fn inverse(self) -> Self {
+        match self.0 {
+            Coordinates::Infinity => self,
+            Coordinates::Finite(x, y) => {
+                Self(Coordinates::Finite(x, y.neg_mod(C::P)), Default::default())
+            }
+        }
+    }
+
+    fn scale(self, n: Num) -> Self {
+        Jacobian::from(self).scale(n).into()
+    }
+
+    fn scale_ct(self, n: Num) -> Self {
+        Jacobian::from(self).scale_ct(n).into()
+    }
+
+    fn multi_scale(terms: &[(Num, Self)]) -> Self {
+        let terms: Vec<_> = terms.iter().map(|&(n, a)| (n, Jacobian::from(a))).collect();
+        Jacobian::multi_scale(&terms).into()
+    }
+}
+
 impl<C: Curve> ops::AddAssign for Point<C> {
     fn add_assign(&mut self, rhs: Self) {
         *self = *self + rhs;
@@ -177,18 +426,32 @@ impl<C: Curve> ops::AddAssign for Point<C> {
 }
 
 impl<C: Curve> Point<C> {
+    /// Create a finite point from its coordinates.
+    ///
+    /// Points received from outside, e.g. as a public key, could be crafted to
+    /// lie on a different curve, with the same $a$ but a different $b$. The
+    /// addition formulas don't depend on $b$, so such a point would be happily
+    /// multiplied by the private key, but on a weaker curve where the result
+    /// leaks information about the private key. This is known as an
+    /// _invalid-curve attack_, and the reason why the coordinates are checked
+    /// to be reduced modulo $P$ and to satisfy the curve equation.
+    #[docext]
     pub fn new(x: Num, y: Num) -> Result<Self, InvalidPoint> {
-        // Verify that (x, y) lies on the curve.
-        let y2 = y.mul(y, C::P);
-        let x3 = x.mul(x, C::P).mul(x, C::P);
-        let ax = C::A.mul(x, C::P);
-        if y2 == x3.add(ax, C::P).add(C::B, C::P) {
+        if x < C::P && y < C::P && on_curve::<C>(x, y) {
             Ok(Self(Coordinates::Finite(x, y), Default::default()))
         } else {
             Err(InvalidPoint)
         }
     }
 
+    /// Create a finite point without checking that it lies on the curve.
+    ///
+    /// Only meant for constant points whose validity is covered by
+    /// [`Curve::validate`], such as the generator of a built-in curve.
+    pub(crate) const fn new_unchecked(x: Num, y: Num) -> Self {
+        Self(Coordinates::Finite(x, y), PhantomData)
+    }
+
     pub fn infinity() -> Self {
         Self(Coordinates::Infinity, Default::default())
     }
@@ -197,19 +460,105 @@ impl<C: Curve> Point<C> {
         self.0
     }
 
-    pub(super) fn scale(&self, n: Num) -> Self {
-        let mut s = *self;
-        let mut result = Self::infinity();
-        for i in 0..Num::BITS {
-            if n.get_bit(i) {
-                result += s;
-            }
-            s += s;
+    /// Check whether the point lies in the subgroup generated by the
+    /// [generator point](Curve::g), i.e. whether $N \cdot P = \infty$. This is
+    /// always the case if the [cofactor](Curve::COFACTOR) is one.
+    #[docext]
+    pub fn in_subgroup(&self) -> bool {
+        C::COFACTOR == Num::ONE || C::N * *self == Self::infinity()
+    }
+}
+
+/// Compute $aP + bQ$, as needed to verify a signature, faster than the two
+/// products separately.
+///
+/// The products share their doublings through [Shamir's
+/// trick](https://en.wikipedia.org/wiki/Elliptic_curve_point_multiplication#Shamir's_trick),
+/// so this takes about as long as a single [scalar
+/// multiplication](Group::scale). The running time depends on the scalars,
+/// which must therefore be public.
+#[docext]
+pub fn mul_double<C: Curve>(a: Num, p: Point<C>, b: Num, q: Point<C>) -> Point<C> {
+    Jacobian::mul_double(a, p.into(), b, q.into()).into()
+}
+
+/// Hash the given data to a point in the subgroup generated by the [generator
+/// point](Curve::g), such that nobody knows its discrete logarithm.
+///
+/// Multiplying $G$ by a hashed scalar would also give a point which looks
+/// random, but whoever computes it knows the scalar $h$ with $hG$ equal to the
+/// point. Protocols such as [linkable ring signatures](crate::SchnorrLsag)
+/// need a second base point whose relation to $G$ is unknown to everyone.
+///
+/// This uses _try-and-increment_: a candidate $x$ is drawn from a
+/// [transcript](Transcript) of the data, and if $x^3 + ax + b$ is a square
+/// modulo $P$, the point $(x, y)$ with the even root $y$ is the result,
+/// [multiplied by the cofactor](Curve::COFACTOR) to land in the subgroup.
+/// Otherwise the next candidate is drawn. About half of the candidates are
+/// on the curve, so a couple of attempts are usually enough. The number of
+/// attempts depends on the data, so the data must be public.
+#[docext]
+pub fn hash_to_point<C, H, const DIGEST_SIZE: usize>(hash: &H, data: &[u8]) -> Point<C>
+where
+    C: Curve,
+    H: Hash<Digest = [u8; DIGEST_SIZE]>,
+{
+    let mut transcript = Transcript::new(hash, b"literate-crypto hash to point");
+    transcript.append(b"curve", &C::id());
+    transcript.append(b"data", data);
+    let mut bytes = [0; Num::BYTES];
+    loop {
+        transcript.challenge_bytes(b"x", &mut bytes[..C::SIZE]);
+        let x = Num::from_le_bytes(bytes);
+        if x >= C::P {
+            continue;
+        }
+        let rhs = x
+            .mul(x, C::P)
+            .mul(x, C::P)
+            .add(C::A.mul(x, C::P), C::P)
+            .add(C::B, C::P);
+        let Some(mut y) = rhs.sqrt(C::P) else {
+            continue;
+        };
+        if y.get_bit(0) {
+            y = y.neg_mod(C::P);
+        }
+        // The root is a square root of the right-hand side, so the point is
+        // on the curve.
+        let point = C::COFACTOR * Point::new(x, y).unwrap();
+        if point != Point::infinity() {
+            return point;
         }
-        result
     }
 }
 
+/// The compressed SEC1 encoding of a point: `00` for the point at infinity,
+/// otherwise `02` for even $y$ or `03` for odd $y$, followed by the big-endian
+/// $x$. Since $P$ is odd, $y$ and $P - y$ have different parities, so this
+/// identifies the point.
+#[docext]
+pub(crate) fn compressed(coordinates: Coordinates) -> SmallBuf<COMPRESSED_SIZE> {
+    let mut result = SmallBuf::new();
+    match coordinates {
+        Coordinates::Infinity => result.extend_from_slice(&[0x00]),
+        Coordinates::Finite(x, y) => {
+            result.extend_from_slice(&[0x02 | u8::from(y.get_bit(0))]);
+            result.extend_from_slice(&x.to_be_bytes());
+        }
+    }
+    result
+}
+
+/// Verify that $(x, y)$ lies on the curve.
+#[docext]
+fn on_curve<C: Curve>(x: Num, y: Num) -> bool {
+    let y2 = y.mul(y, C::P);
+    let x3 = x.mul(x, C::P).mul(x, C::P);
+    let ax = C::A.mul(x, C::P);
+    y2 == x3.add(ax, C::P).add(C::B, C::P)
+}
+
 #[derive(Debug, Clone, Copy)]
 pub struct InvalidPoint;
 
@@ -220,3 +569,36 @@ impl fmt::Display for InvalidPoint {
 }
 
 impl std::error::Error for InvalidPoint {}
+
+/// Error returned by [`Curve::validate`] when the curve parameters are
+/// inconsistent.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CurveError {
+    /// The field order [`Curve::P`] is not prime.
+    CompositeFieldOrder,
+    /// The generator order [`Curve::N`] is not prime.
+    CompositeGroupOrder,
+    /// The curve is singular, i.e. its discriminant is zero.
+    Singular,
+    /// The generator order is equal to the field order.
+    Anomalous,
+    /// The generator point is at infinity or doesn't lie on the curve.
+    InvalidGenerator,
+    /// Multiplying the generator point by its order does not give infinity.
+    WrongGeneratorOrder,
+}
+
+impl fmt::Display for CurveError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::CompositeFieldOrder => write!(f, "field order is not prime"),
+            Self::CompositeGroupOrder => write!(f, "generator order is not prime"),
+            Self::Singular => write!(f, "curve is singular"),
+            Self::Anomalous => write!(f, "generator order is equal to field order"),
+            Self::InvalidGenerator => write!(f, "generator is not a finite point on the curve"),
+            Self::WrongGeneratorOrder => write!(f, "generator order is incorrect"),
+        }
+    }
+}
+
+impl std::error::Error for CurveError {}
@@ -1,14 +1,17 @@
 use {
     crate::{
-        ecc::{Curve, PrivateKey, PublicKey},
-        pubkey::ecc::{Coordinates, Num},
-        util,
+        ecc::{self, Curve, PrivateKey, PublicKey},
+        pubkey::{
+            self,
+            ecc::{Coordinates, Num},
+        },
+        util::CollectVec,
         Hash,
         InvalidSignature,
         SignatureScheme,
     },
     docext::docext,
-    std::marker::PhantomData,
+    std::{collections::VecDeque, fmt, marker::PhantomData},
 };
 
 /// [Elliptic curve](crate::ecc::Curve) digital [signature
@@ -24,16 +27,18 @@ use {
 /// $G$](crate::ecc::Curve::g) of [order $n$](crate::ecc::Curve::N), and a [hash
 /// function $H$](crate::Hash), the algorithm to sign $m$ operates as follows:
 /// 1. Generate a random number $k \in [1, n-1]$ from the curve's prime field,
-///    or derive it deterministically from $m$ and $p$.
+///    or derive it deterministically from $m$, $p$ and the [curve
+///    identifier](crate::ecc::Curve::id).
 /// 2. Calculate $R = kG$, $r = R_x$, where $R_x$ is the x-coordinate of $R$. If
 ///    $R = \infty$, go back to step 1.
-/// 3. Hash the message: $e = H(m) \pmod n$.
+/// 3. Hash the message and [convert](crate::ecc::Scalar) the digest into $e \in
+///    [0, n - 1]$.
 /// 4. Calculate $s = k^{-1}(e + rp) \pmod n$. If $s = 0$, go back to step 1.
 /// 5. Return the pair $(r, s)$ as the message signature.
 ///
 /// The algorithm to verify signature $(r, s)$ with public key $P = pG$ operates
 /// as follows:
-/// 1. Hash the message: $e = H(m) \pmod n$.
+/// 1. Hash the message into $e$ in the same way.
 /// 2. Calculate $u = es^{-1} \pmod n$, $v = rs^{-1} \pmod n$
 /// 3. Calculate $R = uG + vP$.
 /// 4. Check that $R_x = r \pmod n$
@@ -50,22 +55,201 @@ use {
 /// $$
 ///
 /// Which is the original definition of R from the signing algorithm.
+///
+/// The digest is read as a big-endian number, as in the standard, and the hash
+/// function must have at least as many bits as $n$. Signing or verifying with
+/// a shorter hash function panics, see
+/// [`Scalar::from_digest`](crate::ecc::Scalar::from_digest).
+///
+/// # Nonce reuse
+///
+/// If the same $k$ is ever used to sign two different messages under the same
+/// key, the private key can be computed from the two signatures $(r, s_1)$ and
+/// $(r, s_2)$. Both signatures share $r$, since $r$ depends only on $k$, and
+/// subtracting the two signing equations gives
+///
+/// $$
+/// s_1 - s_2 = k^{-1}(e_1 + rp) - k^{-1}(e_2 + rp) = k^{-1}(e_1 - e_2) \
+/// k = (e_1 - e_2)(s_1 - s_2)^{-1} \pmod n
+/// $$
+///
+/// After which the private key follows directly from either signature:
+///
+/// $$
+/// p = (s_1 k - e_1)r^{-1} \pmod n
+/// $$
+///
+/// Deterministic nonces derived from the message and the private key protect
+/// against this, but as a defense in depth, each `Ecdsa` instance remembers the
+/// $r$ values it recently produced and retries signing if a nonce would repeat
+/// $r$ for a different message under the same key.
+/// [`detect_reused_nonce`](Ecdsa::detect_reused_nonce) carries out the
+/// attack described above.
+///
+/// # Malleability
+///
+/// If $(r, s)$ is a valid signature, then so is $(r, n - s)$: negating $s$
+/// negates $u$ and $v$, and thus $R$, which has the same x-coordinate. Anyone
+/// can turn a signature into a different valid signature of the same message,
+/// which breaks protocols that identify a message by the bytes of its
+/// signature, like Bitcoin transactions used to.
+///
+/// The fix is to only accept one of the two, by convention the one with the
+/// _low_ $s \le \lfloor n / 2 \rfloor$, see
+/// [`normalize_s`](EcdsaSignature::normalize_s). An instance created with
+/// [`new_low_s`](Ecdsa::new_low_s) only produces low-$s$ signatures and
+/// rejects high-$s$ signatures when verifying. An instance created with
+/// [`new`](Ecdsa::new) produces and accepts both.
 #[docext]
-#[derive(Debug)]
 pub struct Ecdsa<C, H> {
     _curve: C,
     hash: H,
+    recent: VecDeque<RecentNonce>,
+    low_s: bool,
 }
 
-impl<C, H> Ecdsa<C, H> {
+/// The number of recently produced nonces remembered by [`Ecdsa`].
+const RECENT_NONCES: usize = 16;
+
+/// A nonce recently used by [`Ecdsa`], identified by the $r$ value it produced.
+#[docext]
+struct RecentNonce {
+    /// A hash of the private key which was used for signing.
+    key: Num,
+    r: Num,
+    e: Num,
+}
+
+/// The recent nonces are derived from the private keys, so only their number
+/// is shown.
+impl<C: fmt::Debug, H: fmt::Debug> fmt::Debug for Ecdsa<C, H> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Ecdsa")
+            .field("curve", &self._curve)
+            .field("hash", &self.hash)
+            .field("recent", &self.recent.len())
+            .field("low_s", &self.low_s)
+            .finish()
+    }
+}
+
+impl<C: Curve, H> Ecdsa<C, H> {
     pub fn new(curve: C, hash: H) -> Self {
+        debug_assert_eq!(C::validate(), Ok(()), "invalid curve parameters");
         Self {
             _curve: curve,
             hash,
+            recent: VecDeque::with_capacity(RECENT_NONCES),
+            low_s: false,
+        }
+    }
+
+    /// Only produce and accept signatures with a low $s$, see
+    /// [malleability](Ecdsa#malleability).
+    #[docext]
+    pub fn new_low_s(curve: C, hash: H) -> Self {
+        Self {
+            low_s: true,
+            ..Self::new(curve, hash)
         }
     }
 }
 
+impl<C, H, const DIGEST_SIZE: usize> Ecdsa<C, H>
+where
+    H: Hash<Digest = [u8; DIGEST_SIZE]>,
+    C: Curve,
+{
+    /// Sign a message using the nonce $k$. Returns `None` if $k$ is unusable,
+    /// either because it doesn't produce a valid signature or because it
+    /// produces an $r$ which was recently used to sign a different message
+    /// under the same key.
+    #[docext]
+    pub(crate) fn sign_with_nonce(
+        &mut self,
+        key: &PrivateKey<C>,
+        msg: &[u8],
+        k: Num,
+    ) -> Option<EcdsaSignature<C, H>> {
+        let e = ecc::digest_scalar::<C>(&self.hash.hash(msg));
+        let key_hash = ecc::digest_scalar::<C>(&self.hash.hash(&key.0.to_le_bytes()));
+        // On curves where P > N, such as P-256, x may need reducing.
+        let r = match C::mul_g(k).coordinates() {
+            Coordinates::Infinity => return None,
+            Coordinates::Finite(x, _) => x.reduce(C::N),
+        };
+        if r == Num::ZERO {
+            return None;
+        }
+        if self
+            .recent
+            .iter()
+            .any(|n| n.key == key_hash && n.r == r && n.e != e)
+        {
+            return None;
+        }
+        let s = e.add(r.mul(key.0, C::N), C::N);
+        // k * G is finite, so k must not be zero and thus has an inverse.
+        // The nonce is a valid private key, so it is nonzero.
+        let s = k.inv(C::N).unwrap().mul(s, C::N);
+        if s == Num::ZERO {
+            return None;
+        }
+        if self.recent.len() == RECENT_NONCES {
+            self.recent.pop_front();
+        }
+        self.recent.push_back(RecentNonce {
+            key: key_hash,
+            r,
+            e,
+        });
+        let sig = EcdsaSignature {
+            r,
+            s,
+            _curve: Default::default(),
+            _hash: Default::default(),
+        };
+        Some(if self.low_s { sig.normalize_s() } else { sig })
+    }
+
+    /// Scan a batch of signatures made by the same key for a reused nonce,
+    /// and recover the private key if one is found.
+    ///
+    /// A reused nonce shows up as two signatures of different messages with the
+    /// same $r$. See [the section on nonce reuse](Ecdsa#nonce-reuse) for how
+    /// the key is recovered. The recovery assumes that $s$ wasn't
+    /// [normalized](EcdsaSignature::normalize_s), since normalizing loses the
+    /// sign of $s$.
+    #[docext]
+    pub fn detect_reused_nonce(
+        &self,
+        sigs: &[(&[u8], EcdsaSignature<C, H>)],
+    ) -> Option<PrivateKey<C>> {
+        let hashed = sigs
+            .iter()
+            .map(|(msg, sig)| (ecc::digest_scalar::<C>(&self.hash.hash(msg)), sig))
+            .collect_vec();
+        hashed.iter().enumerate().find_map(|(i, (e1, sig1))| {
+            hashed[i + 1..].iter().find_map(|(e2, sig2)| {
+                if sig1.r != sig2.r || (*e1).eq(*e2, C::N) {
+                    return None;
+                }
+                // k = (e1 - e2) / (s1 - s2)
+                let k = e1
+                    .sub(*e2, C::N)
+                    .mul(sig1.s.sub(sig2.s, C::N).inv(C::N)?, C::N);
+                // p = (s1 k - e1) / r
+                let p = sig1
+                    .s
+                    .mul(k, C::N)
+                    .sub(*e1, C::N)
+                    .mul(sig1.r.inv(C::N)?, C::N);
+                PrivateKey::new(p).ok()
+            })
+        })
+    }
+}
+
 impl<C, H, const DIGEST_SIZE: usize> SignatureScheme for Ecdsa<C, H>
 where
     H: Hash<Digest = [u8; DIGEST_SIZE]>,
@@ -75,49 +259,43 @@ where
     type PrivateKey = PrivateKey<C>;
     type Signature = EcdsaSignature<C, H>;
 
-    fn sign(&mut self, key: Self::PrivateKey, msg: &[u8]) -> Self::Signature {
-        assert!(DIGEST_SIZE >= C::SIZE);
-        let e = self.hash.hash(msg);
-        let e = Num::from_le_bytes(util::resize(e));
-        let mut preimage: Vec<u8> = Default::default();
-        preimage.extend(msg);
-        preimage.extend(key.0.to_le_bytes());
-        let mut k = Num::from_le_bytes(util::resize(self.hash.hash(&preimage)));
-        let mut r;
-        let mut s;
-        'retry: loop {
-            k = Num::from_le_bytes(util::resize(self.hash.hash(&k.to_le_bytes())));
-            r = match (k * C::g()).coordinates() {
-                Coordinates::Infinity => continue 'retry,
-                Coordinates::Finite(x, _) => x,
-            };
-            s = e.add(r.mul(key.0, C::N), C::N);
-            // k * G is finite, so k must not be zero and thus has an inverse.
-            s = k.inv(C::N).unwrap().mul(s, C::N);
-            if s == Num::ZERO {
-                continue 'retry;
+    fn sign_with_context(
+        &mut self,
+        key: Self::PrivateKey,
+        context: &[u8],
+        msg: &[u8],
+    ) -> Self::Signature {
+        let msg: &[u8] = &pubkey::with_context(context, msg);
+        let mut k = ecc::digest_scalar::<C>(&ecc::nonce_seed(&self.hash, &key, &[msg]));
+        loop {
+            k = ecc::digest_scalar::<C>(&self.hash.hash(&k.to_le_bytes()));
+            if let Some(sig) = self.sign_with_nonce(&key, msg, k) {
+                return sig;
             }
-            return EcdsaSignature {
-                r,
-                s,
-                _curve: Default::default(),
-                _hash: Default::default(),
-            };
         }
     }
 
-    fn verify(
+    fn verify_with_context(
         &mut self,
         key: Self::PublicKey,
+        context: &[u8],
         msg: &[u8],
         sig: &Self::Signature,
     ) -> Result<(), InvalidSignature> {
-        assert!(DIGEST_SIZE >= C::SIZE);
-        let e = Num::from_le_bytes(util::resize(self.hash.hash(msg)));
-        let i = sig.s.inv(C::N).unwrap();
+        let msg = pubkey::with_context(context, msg);
+        let e = ecc::digest_scalar::<C>(&self.hash.hash(&msg));
+        // The signature only guarantees that r and s are reduced, but both must
+        // also be nonzero.
+        if sig.r == Num::ZERO {
+            return Err(InvalidSignature);
+        }
+        if self.low_s && sig.s.is_high(C::N) {
+            return Err(InvalidSignature);
+        }
+        let i = sig.s.inv(C::N).ok_or(InvalidSignature)?;
         let u = e.mul(i, C::N);
         let v = sig.r.mul(i, C::N);
-        match (u * C::g() + v * key.point()).coordinates() {
+        match ecc::mul_double(u, C::g(), v, key.point()).coordinates() {
             Coordinates::Finite(x, _) => {
                 if x.eq(sig.r, C::N) {
                     Ok(())
@@ -168,4 +346,26 @@ impl<C: Curve, H> EcdsaSignature<C, H> {
     pub fn s(&self) -> Num {
         self.s
     }
+
+    /// Replace $s$ with $\min(s, n - s)$, the low one of the two equivalent
+    /// signatures, see [malleability](Ecdsa#malleability).
+    #[docext]
+    #[must_use]
+    pub fn normalize_s(self) -> Self {
+        if self.has_low_s() {
+            self
+        } else {
+            Self {
+                s: self.s.neg_mod(C::N),
+                ..self
+            }
+        }
+    }
+
+    /// Whether $s \le \lfloor n / 2 \rfloor$, i.e. whether the signature is
+    /// [normalized](EcdsaSignature::normalize_s).
+    #[docext]
+    pub fn has_low_s(&self) -> bool {
+        !self.s.is_high(C::N)
+    }
 }
@@ -0,0 +1,288 @@
+use {
+    crate::{
+        codec::{CodecError, Reader, Writer},
+        ecc::{Coordinates, Curve, Num, PrivateKey, PublicKey, UNCOMPRESSED_SIZE},
+        util::{self, SmallBuf},
+        BlockCipher,
+        Cbc,
+        CipherDecrypt,
+        CipherEncrypt,
+        Csprng,
+        Hash,
+        Hkdf,
+        Hmac,
+        Mac,
+        Pkcs7,
+    },
+    docext::docext,
+    std::{fmt, marker::PhantomData},
+};
+
+const KDF_INFO: &[u8] = b"literate-crypto ECIES v1";
+
+/// Elliptic curve integrated encryption scheme (ECIES) is a hybrid encryption
+/// scheme which uses an [elliptic curve](crate::ecc::Curve) key exchange to
+/// encrypt messages to a public key.
+///
+/// [Signatures](crate::SignatureScheme) prove that a message came from the
+/// holder of a private key. ECIES goes the other way around: anybody can
+/// encrypt a message with a public key, and only the holder of the
+/// corresponding private key can decrypt it.
+///
+/// To encrypt a message $m$ to the public key $P = pG$, where $G$ is the
+/// [generator point](crate::ecc::Curve::g):
+///
+/// 1. Generate a random _ephemeral_ private key $r$ and the corresponding
+///    public key $R = rG$.
+/// 2. Calculate the shared point $S = rP$, and let $z$ be its x-coordinate.
+/// 3. Derive an encryption key $k_E$ and a MAC key $k_M$ from $z$, using HKDF
+///    based on [HMAC](Hmac). The encoding of $R$ is bound into the derivation.
+/// 4. Encrypt the message with a [block cipher](crate::BlockCipher) in [CBC
+///    mode](Cbc) under $k_E$ and a random IV, yielding $c$.
+/// 5. Compute the tag $t = HMAC(k_M, IV \parallel c)$.
+///
+/// The resulting [ciphertext](EciesCiphertext) is $(R, IV, c, t)$. The
+/// recipient computes the same shared point as $S = pR$, because
+///
+/// $$
+/// pR = prG = rpG = rP
+/// $$
+///
+/// and then derives the same keys. The tag is checked before anything is
+/// decrypted, so that a tampered ciphertext never reaches the padding checks
+/// of the CBC decryption.
+///
+/// The ephemeral key is only used once, so each encryption uses fresh keys
+/// even when encrypting many messages to the same recipient. Nobody but the
+/// sender and the recipient can compute $S$ without solving the ECDLP.
+#[docext]
+pub struct Ecies<C, H, Cip> {
+    _curve: C,
+    hash: H,
+    _cipher: PhantomData<Cip>,
+}
+
+impl<C: Curve, H, Cip> Ecies<C, H, Cip> {
+    pub fn new(curve: C, hash: H) -> Self {
+        debug_assert_eq!(C::validate(), Ok(()), "invalid curve parameters");
+        Self {
+            _curve: curve,
+            hash,
+            _cipher: PhantomData,
+        }
+    }
+}
+
+impl<C, H, Cip, const BLOCK_SIZE: usize, const DIGEST_SIZE: usize, const HASH_BLOCK: usize>
+    Ecies<C, H, Cip>
+where
+    C: Curve,
+    H: Hash<Block = [u8; HASH_BLOCK], Digest = [u8; DIGEST_SIZE]>,
+    Cip: BlockCipher<Block = [u8; BLOCK_SIZE], Key: Default + AsMut<[u8]> + Clone> + Default,
+{
+    /// Encrypt the plaintext to the given recipient, drawing the ephemeral key
+    /// and the IV from the given source of randomness, typically a
+    /// [CSPRNG](crate::Csprng).
+    pub fn encrypt(
+        &mut self,
+        recipient: PublicKey<C>,
+        plaintext: &[u8],
+        rng: &mut impl Csprng,
+    ) -> EciesCiphertext<C, BLOCK_SIZE, DIGEST_SIZE> {
+        let (ephemeral, shared) = loop {
+            let r = PrivateKey::<C>::random(rng);
+            // The recipient key is a valid point of prime order, so rP is never
+            // infinity.
+            if let Some(shared) = shared_x(&r, recipient) {
+                break (r.derive(), shared);
+            }
+        };
+        let (enc_key, mac_key) = self.derive_keys(shared, ephemeral);
+        let mut iv = [0; BLOCK_SIZE];
+        rng.fill_bytes(&mut iv);
+        // CBC encryption only fails for a mismatched IV, and the IV is built
+        // from the block size.
+        let ciphertext = Cbc::new(Cip::default(), Pkcs7::default(), iv)
+            .encrypt_from(plaintext, enc_key)
+            .unwrap();
+        let tag = Hmac::new(&self.hash).mac(&authenticated(&iv, &ciphertext), &mac_key);
+        EciesCiphertext {
+            ephemeral,
+            iv,
+            ciphertext,
+            tag,
+        }
+    }
+
+    /// Verify and decrypt a ciphertext created by
+    /// [`encrypt`](Ecies::encrypt).
+    pub fn decrypt(
+        &mut self,
+        key: PrivateKey<C>,
+        ciphertext: &EciesCiphertext<C, BLOCK_SIZE, DIGEST_SIZE>,
+    ) -> Result<Vec<u8>, EciesError> {
+        let shared = shared_x(&key, ciphertext.ephemeral).ok_or(EciesError::Malformed)?;
+        let (enc_key, mac_key) = self.derive_keys(shared, ciphertext.ephemeral);
+        let tag = Hmac::new(&self.hash).mac(
+            &authenticated(&ciphertext.iv, &ciphertext.ciphertext),
+            &mac_key,
+        );
+        if !util::tags_equal(&tag, &ciphertext.tag) {
+            return Err(EciesError::Tampered);
+        }
+        Cbc::new(Cip::default(), Pkcs7::default(), ciphertext.iv)
+            .decrypt_from(&ciphertext.ciphertext, enc_key)
+            .map_err(|_| EciesError::Tampered)
+    }
+
+    /// Derive the encryption key and the MAC key from the x-coordinate of the
+    /// shared point, using HKDF (RFC 5869) with the ephemeral public key as
+    /// part of the context.
+    fn derive_keys(
+        &mut self,
+        shared: Num,
+        ephemeral: PublicKey<C>,
+    ) -> (Cip::Key, [u8; DIGEST_SIZE]) {
+        let mut info = SmallBuf::<{ KDF_INFO.len() + UNCOMPRESSED_SIZE }>::new();
+        info.extend_from_slice(KDF_INFO);
+        info.extend_from_slice(&ephemeral.to_uncompressed());
+        let mut enc_key = Cip::Key::default();
+        let len = enc_key.as_mut().len() + DIGEST_SIZE;
+        // A key and a digest are much shorter than the longest HKDF output.
+        let okm = Hkdf::new(&self.hash)
+            .derive(&[], &shared.to_be_bytes(), &info, len)
+            .unwrap();
+        let (enc, mac) = okm.split_at(okm.len() - DIGEST_SIZE);
+        enc_key.as_mut().copy_from_slice(enc);
+        // The MAC key is the last DIGEST_SIZE bytes of the key material.
+        let mac_key = mac.try_into().unwrap();
+        (enc_key, mac_key)
+    }
+}
+
+/// A message encrypted with [ECIES](Ecies).
+///
+/// The byte encoding is
+///
+/// ```text
+/// version (1 byte) || ephemeral public key (65 bytes) || IV
+///     || ciphertext length (4 bytes) || ciphertext || tag
+/// ```
+///
+/// where the version is [`EciesCiphertext::VERSION`], the ephemeral public key
+/// is in the uncompressed SEC1 encoding: the byte `04` followed by the
+/// big-endian x and y coordinates, and the length is big-endian. The length
+/// makes a truncated ciphertext fail to decode, instead of being decoded with
+/// the wrong bytes as the tag. See [`codec`](crate::codec).
+#[derive(Debug)]
+pub struct EciesCiphertext<C, const IV_SIZE: usize, const TAG_SIZE: usize> {
+    ephemeral: PublicKey<C>,
+    iv: [u8; IV_SIZE],
+    ciphertext: Vec<u8>,
+    tag: [u8; TAG_SIZE],
+}
+
+impl<C: Curve, const IV_SIZE: usize, const TAG_SIZE: usize> EciesCiphertext<C, IV_SIZE, TAG_SIZE> {
+    /// The current version of the ciphertext encoding.
+    pub const VERSION: u8 = 1;
+
+    /// Encode the ciphertext as bytes.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut writer = Writer::new(Self::VERSION);
+        writer.write_bytes(&self.ephemeral.to_uncompressed());
+        writer.write_bytes(&self.iv);
+        writer.write_len_prefixed(&self.ciphertext);
+        writer.write_bytes(&self.tag);
+        writer.into_bytes()
+    }
+
+    /// Decode a ciphertext from bytes. The ephemeral public key must be a
+    /// [valid public key](PublicKey::new), otherwise an attacker could use
+    /// points on a different curve or of small order to learn about the
+    /// private key of the recipient.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, EciesError> {
+        let mut reader = Reader::new(bytes);
+        reader.read_version(Self::VERSION)?;
+        let ephemeral = PublicKey::from_uncompressed(reader.read_bytes(UNCOMPRESSED_SIZE)?)
+            .map_err(|_| EciesError::Malformed)?;
+        let iv = reader.read_array()?;
+        let ciphertext = reader.read_len_prefixed()?.to_vec();
+        let tag = reader.read_array()?;
+        reader.finish()?;
+        Ok(Self {
+            ephemeral,
+            iv,
+            ciphertext,
+            tag,
+        })
+    }
+
+    pub fn ephemeral(&self) -> PublicKey<C> {
+        self.ephemeral
+    }
+
+    pub fn iv(&self) -> [u8; IV_SIZE] {
+        self.iv
+    }
+
+    pub fn ciphertext(&self) -> &[u8] {
+        &self.ciphertext
+    }
+
+    pub fn tag(&self) -> [u8; TAG_SIZE] {
+        self.tag
+    }
+}
+
+/// Error returned when decrypting an [ECIES](Ecies) ciphertext fails.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EciesError {
+    /// The ciphertext couldn't be decoded, or the ephemeral public key is not
+    /// a valid point.
+    Malformed,
+    /// The ciphertext was modified, or it was encrypted to a different key.
+    Tampered,
+    /// The encoded ciphertext has a version which is not supported.
+    UnknownVersion(u8),
+}
+
+impl fmt::Display for EciesError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Malformed => f.write_str("malformed ECIES ciphertext"),
+            Self::Tampered => f.write_str("ECIES ciphertext authentication failed"),
+            Self::UnknownVersion(v) => write!(f, "unknown ECIES ciphertext version {v}"),
+        }
+    }
+}
+
+impl std::error::Error for EciesError {}
+
+impl From<CodecError> for EciesError {
+    fn from(err: CodecError) -> Self {
+        match err {
+            CodecError::Truncated | CodecError::TrailingBytes => Self::Malformed,
+            CodecError::UnknownVersion(v) => Self::UnknownVersion(v),
+        }
+    }
+}
+
+impl<C, H, Cip> fmt::Debug for Ecies<C, H, Cip> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("Ecies").finish()
+    }
+}
+
+/// Compute the x-coordinate of the shared point $S = pQ$.
+#[docext]
+pub(super) fn shared_x<C: Curve>(p: &PrivateKey<C>, q: PublicKey<C>) -> Option<Num> {
+    match (p.0 * q.point()).coordinates() {
+        Coordinates::Infinity => None,
+        Coordinates::Finite(x, _) => Some(x),
+    }
+}
+
+/// The data covered by the tag.
+fn authenticated(iv: &[u8], ciphertext: &[u8]) -> Vec<u8> {
+    iv.iter().chain(ciphertext).copied().collect()
+}
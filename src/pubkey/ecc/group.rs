@@ -0,0 +1,126 @@
+use {
+    super::num::Num,
+    crate::util::{Choice, CtSelect},
+    docext::docext,
+};
+
+/// A group is a set of elements together with an operation $\circ$ which
+/// combines two elements into a third one, such that:
+///
+/// - The operation is associative, $(a \circ b) \circ c = a \circ (b \circ c)$.
+/// - There is an _identity_ element $e$ with $a \circ e = e \circ a = a$.
+/// - Every element $a$ has an _inverse_ $a^{-1}$ with $a \circ a^{-1} = e$.
+///
+/// All groups used in this crate are also _commutative_, $a \circ b = b \circ
+/// a$.
+///
+/// [Elliptic curve points](super::Point) form a group under point addition,
+/// with the point at infinity as the identity. The nonzero integers modulo a
+/// prime form a group under multiplication, with $1$ as the identity. The
+/// algorithms which are built out of the group operation, such as [scalar
+/// multiplication](Group::scale), don't care which group they run in, so they
+/// are written once against this trait.
+///
+/// Applying the operation $n$ times is written as $n \cdot a$ in additive
+/// groups like the curve points, and as $a^n$ in multiplicative groups. This
+/// trait uses the additive notation.
+#[docext]
+pub trait Group: Copy + Eq {
+    /// The identity element $e$.
+    #[docext]
+    fn identity() -> Self;
+
+    /// The group operation $a \circ b$.
+    #[docext]
+    fn op(self, rhs: Self) -> Self;
+
+    /// The inverse $a^{-1}$, such that $a \circ a^{-1} = e$.
+    #[docext]
+    fn inverse(self) -> Self;
+
+    /// Compute $n \cdot a$ using the double-and-add algorithm, the additive
+    /// counterpart of square-and-multiply.
+    ///
+    /// The bits of $n$ are processed from least to most significant. The
+    /// running value $a, 2a, 4a, \dots$ is doubled at every step, and added to
+    /// the result whenever the bit is set.
+    #[docext]
+    fn scale(self, n: Num) -> Self {
+        let mut s = self;
+        let mut result = Self::identity();
+        for i in 0..Num::BITS {
+            if n.get_bit(i) {
+                result = result.op(s);
+            }
+            s = s.op(s);
+        }
+        result
+    }
+
+    /// Compute $n \cdot a$ using the Montgomery ladder, for a secret $n$.
+    ///
+    /// [Double-and-add](Group::scale) only adds when a bit is set, so the
+    /// number of group operations, and their timing, gives away the bits of
+    /// $n$. The ladder instead keeps two values $r_0$ and $r_1 = r_0 + a$. For
+    /// every bit of $n$, from the most significant, the one is replaced by
+    /// their sum and the other is doubled: if the bit is 0, $r_1 = r_0 + r_1$
+    /// and $r_0 = 2r_0$, otherwise $r_0 = r_0 + r_1$ and $r_1 = 2r_1$. Either
+    /// way, $r_1 - r_0 = a$ stays true and $r_0$ becomes the multiple of $a$ by
+    /// the bits seen so far.
+    ///
+    /// Rather than branching on the bit, the values are [swapped](CtSelect)
+    /// whenever the bit differs from the previous one, so that the sum is
+    /// always stored into $r_1$ and the doubling into $r_0$. Every bit costs
+    /// the same operations, for all $2^{256}$ values of $n$.
+    ///
+    /// The ladder is only as constant-time as the group operation. [Curve
+    /// points](super::Point) run the ladder in Jacobian coordinates, which
+    /// avoid the inversion of affine addition until the very end, but the
+    /// addition still branches on the point at infinity and on equal inputs,
+    /// and the final inversion's timing depends on its input.
+    #[docext]
+    fn scale_ct(self, n: Num) -> Self
+    where
+        Self: CtSelect,
+    {
+        let (mut r0, mut r1) = (Self::identity(), self);
+        let mut swap = Choice::FALSE;
+        for i in (0..Num::BITS).rev() {
+            let bit = n.ct_bit(i);
+            Self::ct_swap(&mut r0, &mut r1, swap ^ bit);
+            swap = bit;
+            r1 = r0.op(r1);
+            r0 = r0.op(r0);
+        }
+        Self::ct_swap(&mut r0, &mut r1, swap);
+        r0
+    }
+
+    /// Compute the sum of multiples $n_1 a_1 + n_2 a_2 + \dots + n_k a_k$.
+    ///
+    /// Signature verification usually needs such a sum of two multiples, e.g.
+    /// $uG + vP$ in [ECDSA](crate::Ecdsa), and [batch
+    /// verification](crate::SchnorrBip340::verify_batch) needs one of many.
+    ///
+    /// Rather than computing every multiple on its own, the bits of all the
+    /// $n_i$ are processed together from the most significant, in the same way
+    /// as [Shamir's trick](crate::ecc::mul_double): the running sum is doubled
+    /// once per bit, and every $a_i$ whose $n_i$ has the bit set is added to
+    /// it. This is known as _Strauss' algorithm_. The $k$ multiples share a
+    /// single chain of doublings, so the sum of $k$ multiples costs about as
+    /// many doublings as one of them.
+    #[docext]
+    fn multi_scale(terms: &[(Num, Self)]) -> Self {
+        let bits = terms.iter().map(|(n, _)| n.bits()).max().unwrap_or(0);
+        let mut result = Self::identity();
+        for i in (0..bits).rev() {
+            result = result.op(result);
+            for &(n, a) in terms {
+                if n.get_bit(i) {
+                    result = result.op(a);
+                }
+            }
+        }
+        result
+    }
+}
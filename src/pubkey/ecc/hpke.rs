@@ -0,0 +1,369 @@
+use {
+    super::{
+        kem::{labeled_expand, labeled_extract},
+        PrivateKey,
+        PublicKey,
+        Secp256k1,
+        UNCOMPRESSED_SIZE,
+    },
+    crate::{
+        util,
+        Aes128,
+        CipherDecrypt,
+        CipherEncrypt,
+        Csprng,
+        Ctr,
+        EciesKem,
+        Hkdf,
+        HmacKey,
+        Kem,
+        Prk,
+        Sha256,
+    },
+    docext::docext,
+    std::fmt,
+};
+
+/// The KDF identifier of HKDF-SHA256.
+const KDF_ID: u16 = 0x0001;
+/// The AEAD identifier of [AES-128-CTR with HMAC-SHA256](HpkeContext#aead).
+/// It is not assigned by IANA, the value is taken from the top of the range so
+/// that it can't collide with a registered AEAD.
+const AEAD_ID: u16 = 0xfffe;
+/// The base mode, without a pre-shared key or sender authentication.
+const MODE_BASE: u8 = 0x00;
+
+const CIPHER_KEY_SIZE: usize = 16;
+const MAC_KEY_SIZE: usize = 32;
+/// $N_k$, the length of the AEAD key: the cipher key followed by the MAC key.
+const KEY_SIZE: usize = CIPHER_KEY_SIZE + MAC_KEY_SIZE;
+/// $N_n$, the length of the AEAD nonce.
+const NONCE_SIZE: usize = 8;
+/// $N_h$, the digest size of the KDF.
+const SECRET_SIZE: usize = 32;
+const TAG_SIZE: usize = 32;
+
+/// The number of messages which can be sealed or opened with one context, see
+/// [the AEAD](HpkeContext#aead).
+const MAX_MESSAGES: u64 = 1 << 32;
+/// The number of bytes which can be encrypted under one nonce, $2^{32}$ blocks
+/// of AES.
+const MAX_MESSAGE_SIZE: u64 = 1 << 36;
+
+/// Encrypt a single message to the recipient with [HPKE](HpkeContext) in base
+/// mode.
+///
+/// Returns the encapsulated key, which must be sent along with the ciphertext.
+/// Every call sets up a fresh [context](HpkeContext::sender), so the message
+/// limit never applies, only the limit on the [message
+/// size](HpkeError::MessageTooLong).
+pub fn hpke_seal(
+    recipient: &PublicKey<Secp256k1>,
+    info: &[u8],
+    aad: &[u8],
+    plaintext: &[u8],
+    rng: &mut impl Csprng,
+) -> Result<([u8; UNCOMPRESSED_SIZE], Vec<u8>), HpkeError> {
+    let (enc, mut ctx) = HpkeContext::sender(recipient, info, rng);
+    let ciphertext = ctx.seal(aad, plaintext)?;
+    Ok((enc, ciphertext))
+}
+
+/// Decrypt a single message sealed with [`hpke_seal`].
+///
+/// Fails with [`HpkeError::Decaps`] if the encapsulated key is invalid, and
+/// with [`HpkeError::Open`] if the ciphertext, the additional data, or the
+/// info string don't match the ones used to seal the message.
+pub fn hpke_open(
+    recipient: &PrivateKey<Secp256k1>,
+    enc: &[u8; UNCOMPRESSED_SIZE],
+    info: &[u8],
+    aad: &[u8],
+    ciphertext: &[u8],
+) -> Result<Vec<u8>, HpkeError> {
+    HpkeContext::receiver(recipient, enc, info)?.open(aad, ciphertext)
+}
+
+/// Hybrid public key encryption (HPKE) of [RFC
+/// 9180](https://www.rfc-editor.org/rfc/rfc9180), in the base mode.
+///
+/// HPKE combines a [KEM](Kem), a key derivation function and an AEAD into a
+/// scheme for encrypting messages to a public key. The sender encapsulates a
+/// shared secret to the recipient key, and both parties derive an encryption
+/// context from it. The context can seal (or open) any number of messages,
+/// and derive further secrets with [`export`](HpkeContext::export).
+///
+/// # Suite
+///
+/// Only one ciphersuite is supported, and it is _not_ interoperable with other
+/// HPKE implementations:
+///
+/// - the KEM is [DHKEM(secp256k1, HKDF-SHA256)](EciesKem::secp256k1), with the
+///   identifier `0x0016` from a draft which was never registered,
+/// - the KDF is HKDF-SHA256, the registered `0x0001`,
+/// - the AEAD is [AES-128-CTR with HMAC-SHA256](#aead), a construction made up
+///   for this crate, with the unregistered identifier `0xfffe`.
+///
+/// All three identifiers make up the _suite identifier_ `HPKE` $\parallel$
+/// `0x0016` $\parallel$ `0x0001` $\parallel$ `0xfffe`, which is part of every
+/// derivation, so that keys derived for this suite are unrelated to the keys
+/// of any standard suite.
+///
+/// # Key Schedule
+///
+/// With the shared secret $ss$ from the KEM, the key schedule derives the AEAD
+/// key, the base nonce and the exporter secret using the labeled functions of
+/// [DHKEM](EciesKem) with the suite identifier above:
+///
+/// $$
+/// \begin{aligned}
+/// context &= \texttt{0x00} \parallel \mathrm{LabeledExtract}(\varnothing,
+/// \texttt{"psk\_id\_hash"}, \varnothing) \parallel
+/// \mathrm{LabeledExtract}(\varnothing, \texttt{"info\_hash"}, info) \\
+/// secret &= \mathrm{LabeledExtract}(ss, \texttt{"secret"}, \varnothing) \\
+/// key &= \mathrm{LabeledExpand}(secret, \texttt{"key"}, context, N_k) \\
+/// base\_nonce &= \mathrm{LabeledExpand}(secret, \texttt{"base\_nonce"},
+/// context, N_n) \\
+/// exporter\_secret &= \mathrm{LabeledExpand}(secret, \texttt{"exp"},
+/// context, N_h)
+/// \end{aligned}
+/// $$
+///
+/// The leading zero byte is the mode, and the empty pre-shared key identifier
+/// is hashed in because the other modes of HPKE use the same schedule. Since
+/// $info$ goes into every derived key, a message only opens with the same
+/// info string it was sealed with.
+///
+/// Each message gets its own nonce: the $i$-th message is sealed with the
+/// nonce $base\_nonce \oplus i$, where $i$ is encoded as an $N_n$-byte
+/// big-endian number. The sender and the recipient count the messages
+/// independently, so messages must be opened in the order they were sealed.
+///
+/// # AEAD
+///
+/// There is no AEAD in this crate, so the suite uses encrypt-then-MAC with
+/// additional data. The 48-byte key is split into a 16-byte [AES](Aes128) key
+/// $k_E$ and a 32-byte [HMAC](crate::Hmac) key $k_M$, the nonce $n$ is 8 bytes
+/// long, and a message $m$ with additional data $a$ is sealed as
+///
+/// $$
+/// \begin{aligned}
+/// c &= \mathrm{CTR}(k_E, n, m) \\
+/// t &= \mathrm{HMAC}(k_M, n \parallel a \parallel c \parallel len(a))
+/// \end{aligned}
+/// $$
+///
+/// where $len(a)$ is the length of $a$ as an 8-byte big-endian number, which
+/// makes the split between $a$ and $c$ unambiguous. The ciphertext is $c
+/// \parallel t$.
+///
+/// [CTR mode](Ctr) reads the nonce as a little-endian initial counter, so the
+/// message number lands in the _top_ bits of the counter. While fewer than
+/// $2^{32}$ messages are sealed, all the initial counters agree in their low
+/// 32 bits, and are thus at least $2^{32}$ blocks apart. Limiting every
+/// message to $2^{32}$ blocks keeps the keystreams of different messages from
+/// ever overlapping, so both limits are enforced.
+#[docext]
+pub struct HpkeContext {
+    cipher_key: [u8; CIPHER_KEY_SIZE],
+    mac: HmacKey<Sha256>,
+    base_nonce: [u8; NONCE_SIZE],
+    exporter_secret: [u8; SECRET_SIZE],
+    seq: u64,
+}
+
+impl HpkeContext {
+    /// Encapsulate a shared secret to the recipient, and set up the context
+    /// for sealing messages to them.
+    pub fn sender(
+        recipient: &PublicKey<Secp256k1>,
+        info: &[u8],
+        rng: &mut impl Csprng,
+    ) -> ([u8; UNCOMPRESSED_SIZE], Self) {
+        let (enc, secret) = EciesKem::secp256k1().encapsulate(recipient, rng);
+        (enc, Self::key_schedule(secret.as_bytes(), info))
+    }
+
+    /// Decapsulate the shared secret, and set up the context for opening
+    /// messages from the sender.
+    pub fn receiver(
+        recipient: &PrivateKey<Secp256k1>,
+        enc: &[u8; UNCOMPRESSED_SIZE],
+        info: &[u8],
+    ) -> Result<Self, HpkeError> {
+        let secret = EciesKem::secp256k1()
+            .decapsulate(recipient, enc)
+            .map_err(|_| HpkeError::Decaps)?;
+        Ok(Self::key_schedule(secret.as_bytes(), info))
+    }
+
+    /// Seal the next message.
+    pub fn seal(&mut self, aad: &[u8], plaintext: &[u8]) -> Result<Vec<u8>, HpkeError> {
+        let nonce = self.next_nonce()?;
+        let mut ciphertext = self
+            .cipher(nonce)
+            .encrypt(plaintext.to_vec(), self.cipher_key)
+            .map_err(|_| HpkeError::MessageTooLong)?;
+        let tag = self.tag(nonce, aad, &ciphertext);
+        ciphertext.extend(tag);
+        self.seq += 1;
+        Ok(ciphertext)
+    }
+
+    /// Open the next message.
+    ///
+    /// A message which fails to open doesn't count towards the sequence, so
+    /// the next genuine message still opens.
+    pub fn open(&mut self, aad: &[u8], ciphertext: &[u8]) -> Result<Vec<u8>, HpkeError> {
+        let nonce = self.next_nonce()?;
+        if ciphertext.len() < TAG_SIZE {
+            return Err(HpkeError::Open);
+        }
+        let (ciphertext, tag) = ciphertext.split_at(ciphertext.len() - TAG_SIZE);
+        if !util::tags_equal(&self.tag(nonce, aad, ciphertext), tag) {
+            return Err(HpkeError::Open);
+        }
+        let plaintext = self
+            .cipher(nonce)
+            .decrypt(ciphertext.to_vec(), self.cipher_key)
+            .map_err(|_| HpkeError::Open)?;
+        self.seq += 1;
+        Ok(plaintext)
+    }
+
+    /// Derive a secret of the given length from the context, bound to the
+    /// exporter context string.
+    ///
+    /// Both the sender and the recipient derive the same secret, regardless of
+    /// how many messages were sealed or opened, and different exporter contexts
+    /// give unrelated secrets. Panics if the length is over $255 N_h = 8160$
+    /// bytes, the most HKDF can output.
+    pub fn export(&self, exporter_context: &[u8], len: usize) -> Vec<u8> {
+        let mut secret = vec![0; len];
+        labeled_expand(
+            &mut Hkdf::new(Sha256::default()),
+            &suite_id(),
+            &Prk::from_bytes(self.exporter_secret),
+            b"sec",
+            exporter_context,
+            &mut secret,
+        )
+        .expect("exported secret too long");
+        secret
+    }
+
+    /// The number of messages sealed or opened so far.
+    pub fn seq(&self) -> u64 {
+        self.seq
+    }
+
+    /// Derive the context from the shared secret and the info string.
+    fn key_schedule(shared_secret: &[u8], info: &[u8]) -> Self {
+        let mut hkdf = Hkdf::new(Sha256::default());
+        let suite = suite_id();
+        let psk_id_hash = labeled_extract(&mut hkdf, &suite, &[], b"psk_id_hash", &[]);
+        let info_hash = labeled_extract(&mut hkdf, &suite, &[], b"info_hash", info);
+        let context = [
+            &[MODE_BASE],
+            &psk_id_hash.as_bytes()[..],
+            &info_hash.as_bytes()[..],
+        ]
+        .concat();
+        let secret = labeled_extract(&mut hkdf, &suite, shared_secret, b"secret", &[]);
+
+        let mut key = [0; KEY_SIZE];
+        let mut base_nonce = [0; NONCE_SIZE];
+        let mut exporter_secret = [0; SECRET_SIZE];
+        for (label, okm) in [
+            (&b"key"[..], &mut key[..]),
+            (b"base_nonce", &mut base_nonce),
+            (b"exp", &mut exporter_secret),
+        ] {
+            // The key, nonce and secret are each at most one digest long.
+            labeled_expand(&mut hkdf, &suite, &secret, label, &context, okm).unwrap();
+        }
+
+        // The key material is split at a fixed offset, so the sizes always match.
+        let (cipher_key, mac_key) = key.split_at(CIPHER_KEY_SIZE);
+        Self {
+            cipher_key: cipher_key.try_into().unwrap(),
+            mac: HmacKey::new(Sha256::default(), mac_key),
+            base_nonce,
+            exporter_secret,
+            seq: 0,
+        }
+    }
+
+    /// The nonce of the next message, the base nonce XORed with the sequence
+    /// number.
+    fn next_nonce(&self) -> Result<[u8; NONCE_SIZE], HpkeError> {
+        if self.seq >= MAX_MESSAGES {
+            return Err(HpkeError::MessageLimitReached);
+        }
+        let mut nonce = self.base_nonce;
+        nonce
+            .iter_mut()
+            .zip(self.seq.to_be_bytes())
+            .for_each(|(n, s)| *n ^= s);
+        Ok(nonce)
+    }
+
+    fn cipher(&self, nonce: [u8; NONCE_SIZE]) -> Ctr<Aes128> {
+        Ctr::new(Aes128::default(), u64::from_le_bytes(nonce))
+            .expect("AES block fits the counter")
+            .with_limit(MAX_MESSAGE_SIZE)
+            .expect("nonzero limit")
+    }
+
+    fn tag(&self, nonce: [u8; NONCE_SIZE], aad: &[u8], ciphertext: &[u8]) -> [u8; TAG_SIZE] {
+        let aad_len = u64::try_from(aad.len()).unwrap().to_be_bytes();
+        self.mac.mac_parts(&[&nonce, aad, ciphertext, &aad_len])
+    }
+}
+
+impl fmt::Debug for HpkeContext {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("HpkeContext")
+            .field("seq", &self.seq)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Error returned by [HPKE](HpkeContext).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HpkeError {
+    /// The encapsulated key is not a valid point.
+    Decaps,
+    /// The ciphertext failed to authenticate: it was modified, or sealed with
+    /// different additional data, a different info string, or out of order.
+    Open,
+    /// The context has sealed or opened as many messages as it safely can.
+    MessageLimitReached,
+    /// The plaintext is longer than $2^{36}$ bytes, or the counter of [CTR
+    /// mode](Ctr) would wrap around, which only happens for a random base nonce
+    /// within a message length of the largest counter.
+    MessageTooLong,
+}
+
+impl fmt::Display for HpkeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Decaps => f.write_str("invalid encapsulated key"),
+            Self::Open => f.write_str("HPKE ciphertext authentication failed"),
+            Self::MessageLimitReached => f.write_str("HPKE message limit reached"),
+            Self::MessageTooLong => f.write_str("HPKE message too long"),
+        }
+    }
+}
+
+impl std::error::Error for HpkeError {}
+
+/// The suite identifier, `HPKE` followed by the KEM, KDF and AEAD identifiers.
+fn suite_id() -> [u8; 10] {
+    let mut suite = [0; 10];
+    suite[..4].copy_from_slice(b"HPKE");
+    suite[4..6].copy_from_slice(&EciesKem::secp256k1().id().to_be_bytes());
+    suite[6..8].copy_from_slice(&KDF_ID.to_be_bytes());
+    suite[8..].copy_from_slice(&AEAD_ID.to_be_bytes());
+    suite
+}
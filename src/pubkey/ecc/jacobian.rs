@@ -0,0 +1,243 @@
+use {
+    super::{
+        curve::{Coordinates, Curve, Point},
+        group::Group,
+        num::Num,
+    },
+    crate::util::{Choice, CtSelect},
+    docext::docext,
+    std::marker::PhantomData,
+};
+
+/// A [curve point](Point) in Jacobian coordinates $(X, Y, Z)$, standing for
+/// the affine point $(X / Z^2, Y / Z^3)$.
+///
+/// [Affine addition](Point#impl-Add-for-Point%3CC%3E) divides by $x_2 - x_1$
+/// or $2y_1$, and every division is a modular inversion, which costs as much
+/// as a hundred multiplications. Scalar multiplication runs through hundreds
+/// of additions and doublings, so the inversions dominate. Jacobian
+/// coordinates keep the denominator in $Z$ instead, so that the addition
+/// formulas below only multiply. A single inversion of $Z$ at the very end
+/// converts the result back into an affine point.
+///
+/// The same affine point has many representations, one for every nonzero
+/// $\lambda$:
+///
+/// $$
+/// (X, Y, Z) \sim (\lambda^2 X, \lambda^3 Y, \lambda Z)
+/// $$
+///
+/// The point at infinity is any representation with $Z = 0$.
+#[docext]
+pub(crate) struct Jacobian<C> {
+    x: Num,
+    y: Num,
+    z: Num,
+    _curve: PhantomData<C>,
+}
+
+impl<C> Clone for Jacobian<C> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<C> Copy for Jacobian<C> {}
+
+/// Two representations are the same point if their affine coordinates are
+/// equal, i.e. if $X_1 Z_2^2 = X_2 Z_1^2$ and $Y_1 Z_2^3 = Y_2 Z_1^3$, which
+/// can be checked without inverting anything.
+#[docext]
+impl<C: Curve> PartialEq for Jacobian<C> {
+    fn eq(&self, other: &Self) -> bool {
+        bool::from(self.ct_eq(other))
+    }
+}
+
+impl<C: Curve> Eq for Jacobian<C> {}
+
+impl<C: Curve> From<Point<C>> for Jacobian<C> {
+    fn from(point: Point<C>) -> Self {
+        Self::from_coordinates(point.coordinates())
+    }
+}
+
+/// The affine coordinates are $x = X / Z^2$ and $y = Y / Z^3$, which takes the
+/// one inversion.
+#[docext]
+impl<C: Curve> From<Jacobian<C>> for Point<C> {
+    fn from(point: Jacobian<C>) -> Self {
+        let Some(z_inv) = point.z.inv(C::P) else {
+            return Self::infinity();
+        };
+        let z_inv2 = z_inv.mul(z_inv, C::P);
+        let x = point.x.mul(z_inv2, C::P);
+        let y = point.y.mul(z_inv2, C::P).mul(z_inv, C::P);
+        // The formulas keep the point on the curve.
+        Self::new_unchecked(x, y)
+    }
+}
+
+impl<C: Curve> Jacobian<C> {
+    fn new(x: Num, y: Num, z: Num) -> Self {
+        Self {
+            x,
+            y,
+            z,
+            _curve: PhantomData,
+        }
+    }
+
+    /// The point with the given affine coordinates, which must lie on the
+    /// curve.
+    pub(crate) fn from_coordinates(coordinates: Coordinates) -> Self {
+        match coordinates {
+            Coordinates::Infinity => Self::identity(),
+            Coordinates::Finite(x, y) => Self::new(x, y, Num::ONE),
+        }
+    }
+
+    /// Compute $aP + bQ$ with one shared chain of doublings, known as
+    /// _Shamir's trick_.
+    ///
+    /// Computing the two products separately doubles once per bit of each
+    /// scalar. Instead, the bits of $a$ and $b$ are processed together from the
+    /// most significant: the running sum is doubled, and then $P$, $Q$ or
+    /// the precomputed $P + Q$ is added depending on the two bits, or nothing
+    /// if both are zero. This halves the doublings, and the additions drop
+    /// from one per set bit of either scalar to one per position where either
+    /// bit is set.
+    ///
+    /// The operations depend on the bits of the scalars, so they must be
+    /// public, as they are when verifying a signature.
+    #[docext]
+    pub(crate) fn mul_double(a: Num, p: Self, b: Num, q: Self) -> Self {
+        let table = [Self::identity(), p, q, p.op(q)];
+        let mut result = Self::identity();
+        for i in (0..a.bits().max(b.bits())).rev() {
+            result = result.op(result);
+            let index = usize::from(a.get_bit(i)) | usize::from(b.get_bit(i)) << 1;
+            if index != 0 {
+                result = result.op(table[index]);
+            }
+        }
+        result
+    }
+
+    fn is_infinity(&self) -> bool {
+        self.z == Num::ZERO
+    }
+
+    /// Double a point, using the tangent formula
+    ///
+    /// $$
+    /// S = 4XY^2 \\
+    /// M = 3X^2 + aZ^4 \\
+    /// X' = M^2 - 2S \\
+    /// Y' = M(S - X') - 8Y^4 \\
+    /// Z' = 2YZ
+    /// $$
+    ///
+    /// $M / (2YZ)$ is the slope $H$ of the affine formula. For $y = 0$ the
+    /// tangent is vertical, and $Z' = 0$ is the point at infinity.
+    #[docext]
+    fn double(self) -> Self {
+        let p = C::P;
+        let yy = self.y.mul(self.y, p);
+        let zz = self.z.mul(self.z, p);
+        let s = Num::from_le_words([4, 0, 0, 0]).mul(self.x, p).mul(yy, p);
+        let m = Num::THREE
+            .mul(self.x, p)
+            .mul(self.x, p)
+            .add(C::A.mul(zz, p).mul(zz, p), p);
+        let x = m.mul(m, p).sub(s.add(s, p), p);
+        let y = m
+            .mul(s.sub(x, p), p)
+            .sub(Num::from_le_words([8, 0, 0, 0]).mul(yy, p).mul(yy, p), p);
+        let z = Num::TWO.mul(self.y, p).mul(self.z, p);
+        Self::new(x, y, z)
+    }
+}
+
+/// Points are added by bringing both to the same denominator:
+///
+/// $$
+/// U_1 = X_1 Z_2^2, \quad U_2 = X_2 Z_1^2 \\
+/// S_1 = Y_1 Z_2^3, \quad S_2 = Y_2 Z_1^3 \\
+/// H = U_2 - U_1, \quad R = S_2 - S_1 \\
+/// X_3 = R^2 - H^3 - 2 U_1 H^2 \\
+/// Y_3 = R(U_1 H^2 - X_3) - S_1 H^3 \\
+/// Z_3 = Z_1 Z_2 H
+/// $$
+///
+/// $R / (Z_1 Z_2 H)$ is the slope of the affine formula. If $H = 0$, the
+/// points have the same $x$. They are then either equal, and
+/// [doubled](Jacobian::double) instead, or each other's negation, and add up
+/// to infinity.
+#[docext]
+impl<C: Curve> Group for Jacobian<C> {
+    fn identity() -> Self {
+        Self::new(Num::ONE, Num::ONE, Num::ZERO)
+    }
+
+    fn op(self, rhs: Self) -> Self {
+        if self.is_infinity() {
+            return rhs;
+        }
+        if rhs.is_infinity() {
+            return self;
+        }
+        let p = C::P;
+        let z1z1 = self.z.mul(self.z, p);
+        let z2z2 = rhs.z.mul(rhs.z, p);
+        let u1 = self.x.mul(z2z2, p);
+        let u2 = rhs.x.mul(z1z1, p);
+        let s1 = self.y.mul(z2z2, p).mul(rhs.z, p);
+        let s2 = rhs.y.mul(z1z1, p).mul(self.z, p);
+        let h = u2.sub(u1, p);
+        let r = s2.sub(s1, p);
+        if h == Num::ZERO {
+            return if r == Num::ZERO {
+                self.double()
+            } else {
+                Self::identity()
+            };
+        }
+        let hh = h.mul(h, p);
+        let hhh = hh.mul(h, p);
+        let v = u1.mul(hh, p);
+        let x = r.mul(r, p).sub(hhh, p).sub(v.add(v, p), p);
+        let y = r.mul(v.sub(x, p), p).sub(s1.mul(hhh, p), p);
+        let z = self.z.mul(rhs.z, p).mul(h, p);
+        Self::new(x, y, z)
+    }
+
+    fn inverse(self) -> Self {
+        Self::new(self.x, self.y.neg_mod(C::P), self.z)
+    }
+}
+
+impl<C: Curve> CtSelect for Jacobian<C> {
+    fn ct_select(a: &Self, b: &Self, choice: Choice) -> Self {
+        Self::new(
+            Num::ct_select(&a.x, &b.x, choice),
+            Num::ct_select(&a.y, &b.y, choice),
+            Num::ct_select(&a.z, &b.z, choice),
+        )
+    }
+
+    /// Compare the cross products from [`PartialEq`], and whether either
+    /// point is at infinity, where the cross products are all zero.
+    fn ct_eq(&self, other: &Self) -> Choice {
+        let p = C::P;
+        let z1z1 = self.z.mul(self.z, p);
+        let z2z2 = other.z.mul(other.z, p);
+        let x1 = self.x.mul(z2z2, p);
+        let x2 = other.x.mul(z1z1, p);
+        let y1 = self.y.mul(z2z2, p).mul(other.z, p);
+        let y2 = other.y.mul(z1z1, p).mul(self.z, p);
+        let inf1 = self.z.ct_eq(&Num::ZERO);
+        let inf2 = other.z.ct_eq(&Num::ZERO);
+        !(inf1 ^ inf2) & (inf1 | (x1.ct_eq(&x2) & y1.ct_eq(&y2)))
+    }
+}
@@ -0,0 +1,441 @@
+//! Minimal JOSE support: [JSON Web Keys](PublicKey::to_jwk) (RFC 7517) and
+//! [JSON Web Signatures](jws_es256k_sign) (RFC 7515) with ES256K, which is
+//! [ECDSA](crate::Ecdsa) over [secp256k1](Secp256k1) with
+//! [SHA-256](crate::Sha256) (RFC 8812).
+//!
+//! A JWK stores the coordinates of an elliptic curve point, and for private
+//! keys also the scalar, as base64url-encoded big-endian numbers:
+//!
+//! ```text
+//! {"kty":"EC","crv":"secp256k1","x":"...","y":"...","d":"..."}
+//! ```
+//!
+//! A JWS in the compact serialization consists of three base64url-encoded
+//! parts separated by dots: a JSON header naming the algorithm, the payload,
+//! and the signature:
+//!
+//! ```text
+//! base64url(header) . base64url(payload) . base64url(r || s)
+//! ```
+//!
+//! The signature is computed over the first two parts, including the dot.
+//! ES256K encodes it as the two 32-byte big-endian numbers $r$ and $s$ back to
+//! back, not in the DER encoding used elsewhere.
+//!
+//! Only the flat JSON objects used by these formats are supported: the values
+//! of the members must be strings, numbers, booleans or null, and nested
+//! objects or arrays are rejected. This keeps the parser small enough to be
+//! read in one sitting.
+
+use {
+    crate::{
+        base64::{self, Alphabet},
+        ecc::{Num, Point, PrivateKey, PublicKey, Secp256k1},
+        Ecdsa,
+        EcdsaSignature,
+        Sha256,
+        SignatureScheme,
+    },
+    docext::docext,
+    std::fmt,
+};
+
+/// The name of the algorithm in the JWS header.
+const ALGORITHM: &str = "ES256K";
+
+/// The name of [secp256k1](Secp256k1) in a JWK.
+const CURVE: &str = "secp256k1";
+
+impl PublicKey<Secp256k1> {
+    /// Export the public key as a JWK.
+    pub fn to_jwk(&self) -> String {
+        write_object(&[
+            ("kty", "EC"),
+            ("crv", CURVE),
+            ("x", &encode_num(self.x())),
+            ("y", &encode_num(self.y())),
+        ])
+    }
+
+    /// Import a public key from a JWK. Any `d` member is ignored, so the
+    /// public key can also be read from a private JWK.
+    pub fn from_jwk(jwk: &str) -> Result<Self, JoseError> {
+        let members = parse_object(jwk)?;
+        if member(&members, "kty")? != "EC" {
+            return Err(JoseError::InvalidMember("kty"));
+        }
+        if member(&members, "crv")? != CURVE {
+            return Err(JoseError::InvalidMember("crv"));
+        }
+        let x = decode_num(&members, "x")?;
+        let y = decode_num(&members, "y")?;
+        let point = Point::new(x, y).map_err(|_| JoseError::InvalidKey)?;
+        Self::new(point).map_err(|_| JoseError::InvalidKey)
+    }
+}
+
+impl PrivateKey<Secp256k1> {
+    /// Export the private key as a JWK, including the public key.
+    pub fn to_jwk(&self) -> String {
+        let public = self.derive();
+        write_object(&[
+            ("kty", "EC"),
+            ("crv", CURVE),
+            ("x", &encode_num(public.x())),
+            ("y", &encode_num(public.y())),
+            ("d", &encode_num(self.0)),
+        ])
+    }
+
+    /// Import a private key from a JWK. The public key in the JWK must belong
+    /// to the private key.
+    pub fn from_jwk(jwk: &str) -> Result<Self, JoseError> {
+        let public = PublicKey::from_jwk(jwk)?;
+        let d = decode_num(&parse_object(jwk)?, "d")?;
+        let key = Self::new(d).map_err(|_| JoseError::InvalidKey)?;
+        if key.derive() != public {
+            return Err(JoseError::InvalidKey);
+        }
+        Ok(key)
+    }
+}
+
+/// Sign the payload with ES256K, returning the JWS in the compact
+/// serialization.
+///
+/// The header must be a JSON object with `"alg":"ES256K"`. It is encoded
+/// exactly as given, so any other members such as `typ` or `kid` are kept.
+pub fn jws_es256k_sign(
+    key: PrivateKey<Secp256k1>,
+    header: &str,
+    payload: &[u8],
+) -> Result<String, JoseError> {
+    check_header(header)?;
+    let mut token = encode(header.as_bytes());
+    token.push('.');
+    token.push_str(&encode(payload));
+    let sig = es256k().sign(key, token.as_bytes());
+    let mut raw = sig.r().to_be_bytes().to_vec();
+    raw.extend(sig.s().to_be_bytes());
+    token.push('.');
+    token.push_str(&encode(&raw));
+    Ok(token)
+}
+
+/// Verify a JWS in the compact serialization, returning the payload.
+///
+/// The header must name the ES256K algorithm. Tokens with any other
+/// algorithm, including `none`, are rejected before the signature is even
+/// looked at.
+pub fn jws_es256k_verify(key: PublicKey<Secp256k1>, token: &str) -> Result<Vec<u8>, JoseError> {
+    let mut parts = token.split('.');
+    let (Some(header), Some(payload), Some(sig), None) =
+        (parts.next(), parts.next(), parts.next(), parts.next())
+    else {
+        return Err(JoseError::Malformed);
+    };
+    let header = decode(header).ok_or(JoseError::Malformed)?;
+    let header = String::from_utf8(header).map_err(|_| JoseError::Malformed)?;
+    check_header(&header)?;
+    let payload = decode(payload).ok_or(JoseError::Malformed)?;
+
+    let sig = decode(sig).ok_or(JoseError::Malformed)?;
+    if sig.len() != 2 * Num::BYTES {
+        return Err(JoseError::InvalidSignature);
+    }
+    // The length was checked above, so the halves have the right sizes.
+    let r = Num::from_be_bytes(sig[..Num::BYTES].try_into().unwrap());
+    let s = Num::from_be_bytes(sig[Num::BYTES..].try_into().unwrap());
+    if r == Num::ZERO || s == Num::ZERO {
+        return Err(JoseError::InvalidSignature);
+    }
+    let sig = EcdsaSignature::new(r, s).map_err(|_| JoseError::InvalidSignature)?;
+    // The token was split on dots above, so it contains one.
+    let signed = &token[..token.rfind('.').unwrap()];
+    es256k()
+        .verify(key, signed.as_bytes(), &sig)
+        .map_err(|_| JoseError::InvalidSignature)?;
+    Ok(payload)
+}
+
+fn es256k() -> Ecdsa<Secp256k1, Sha256> {
+    Ecdsa::new(Secp256k1::default(), Sha256::default())
+}
+
+/// Check that the JWS header is a JSON object selecting ES256K, without any
+/// critical extensions, none of which are supported.
+fn check_header(header: &str) -> Result<(), JoseError> {
+    let members = parse_object(header)?;
+    if member(&members, "alg")? != ALGORITHM {
+        return Err(JoseError::InvalidMember("alg"));
+    }
+    if members.iter().any(|(name, _)| name == "crit") {
+        return Err(JoseError::InvalidMember("crit"));
+    }
+    Ok(())
+}
+
+fn encode(bytes: &[u8]) -> String {
+    base64::encode(bytes, Alphabet::UrlSafe, false)
+}
+
+/// Decode unpadded base64url, as required by JOSE.
+fn decode(text: &str) -> Option<Vec<u8>> {
+    if text.contains('=') {
+        return None;
+    }
+    base64::decode(text, Alphabet::UrlSafe).ok()
+}
+
+fn encode_num(n: Num) -> String {
+    encode(&n.to_be_bytes())
+}
+
+/// Decode a member holding a 32-byte big-endian number. JWK requires the full
+/// length, even if the number has leading zero bytes.
+fn decode_num(members: &[Member], name: &'static str) -> Result<Num, JoseError> {
+    let bytes = decode(member(members, name)?).ok_or(JoseError::InvalidMember(name))?;
+    let bytes = bytes
+        .try_into()
+        .map_err(|_| JoseError::InvalidMember(name))?;
+    Ok(Num::from_be_bytes(bytes))
+}
+
+/// A member of a flat JSON object. The value is `None` unless it is a string.
+type Member = (String, Option<String>);
+
+/// Find the string value of a member.
+fn member<'a>(members: &'a [Member], name: &'static str) -> Result<&'a str, JoseError> {
+    match members.iter().find(|(n, _)| n == name) {
+        Some((_, Some(value))) => Ok(value),
+        Some((_, None)) => Err(JoseError::InvalidMember(name)),
+        None => Err(JoseError::MissingMember(name)),
+    }
+}
+
+/// Write a JSON object whose members all have string values.
+fn write_object(members: &[(&str, &str)]) -> String {
+    let mut result = String::from("{");
+    for (i, (name, value)) in members.iter().enumerate() {
+        if i > 0 {
+            result.push(',');
+        }
+        write_string(&mut result, name);
+        result.push(':');
+        write_string(&mut result, value);
+    }
+    result.push('}');
+    result
+}
+
+fn write_string(out: &mut String, s: &str) {
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            c if u32::from(c) < 0x20 => out.push_str(&format!("\\u{:04x}", u32::from(c))),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+}
+
+/// Parse a flat JSON object. Duplicate member names are rejected, since
+/// different parsers disagree on which of the values counts.
+fn parse_object(json: &str) -> Result<Vec<Member>, JoseError> {
+    let mut parser = Parser {
+        json: json.as_bytes(),
+        pos: 0,
+    };
+    let mut members: Vec<Member> = Vec::new();
+    parser.expect(b'{')?;
+    if !parser.accept(b'}') {
+        loop {
+            let name = parser.string()?;
+            parser.expect(b':')?;
+            let value = parser.value()?;
+            if members.iter().any(|(n, _)| *n == name) {
+                return Err(JoseError::Json);
+            }
+            members.push((name, value));
+            if parser.accept(b'}') {
+                break;
+            }
+            parser.expect(b',')?;
+        }
+    }
+    parser.skip_whitespace();
+    if parser.pos != json.len() {
+        return Err(JoseError::Json);
+    }
+    Ok(members)
+}
+
+struct Parser<'a> {
+    json: &'a [u8],
+    pos: usize,
+}
+
+impl Parser<'_> {
+    fn skip_whitespace(&mut self) {
+        while matches!(self.peek(), Some(b' ' | b'\t' | b'\n' | b'\r')) {
+            self.pos += 1;
+        }
+    }
+
+    fn peek(&self) -> Option<u8> {
+        self.json.get(self.pos).copied()
+    }
+
+    fn next(&mut self) -> Result<u8, JoseError> {
+        let c = self.peek().ok_or(JoseError::Json)?;
+        self.pos += 1;
+        Ok(c)
+    }
+
+    /// Skip whitespace and consume the character if it comes next.
+    fn accept(&mut self, c: u8) -> bool {
+        self.skip_whitespace();
+        let found = self.peek() == Some(c);
+        if found {
+            self.pos += 1;
+        }
+        found
+    }
+
+    fn expect(&mut self, c: u8) -> Result<(), JoseError> {
+        if self.accept(c) {
+            Ok(())
+        } else {
+            Err(JoseError::Json)
+        }
+    }
+
+    /// Parse a value, returning it if it is a string. Other scalars are
+    /// checked loosely and skipped.
+    fn value(&mut self) -> Result<Option<String>, JoseError> {
+        self.skip_whitespace();
+        match self.peek() {
+            Some(b'"') => self.string().map(Some),
+            Some(b't' | b'f' | b'n') => {
+                let rest = &self.json[self.pos..];
+                let literal = [&b"true"[..], b"false", b"null"]
+                    .into_iter()
+                    .find(|l| rest.starts_with(l))
+                    .ok_or(JoseError::Json)?;
+                self.pos += literal.len();
+                Ok(None)
+            }
+            Some(b'-' | b'0'..=b'9') => {
+                let start = self.pos;
+                while matches!(
+                    self.peek(),
+                    Some(b'-' | b'+' | b'.' | b'e' | b'E' | b'0'..=b'9')
+                ) {
+                    self.pos += 1;
+                }
+                if self.json[start..self.pos].iter().any(u8::is_ascii_digit) {
+                    Ok(None)
+                } else {
+                    Err(JoseError::Json)
+                }
+            }
+            _ => Err(JoseError::Json),
+        }
+    }
+
+    fn string(&mut self) -> Result<String, JoseError> {
+        self.expect(b'"')?;
+        let mut result = String::new();
+        loop {
+            let start = self.pos;
+            while !matches!(self.peek(), None | Some(b'"' | b'\\' | 0..=0x1f)) {
+                self.pos += 1;
+            }
+            // The input is a str, and the run stops at ASCII characters, so it is
+            // valid UTF-8 on its own.
+            result.push_str(std::str::from_utf8(&self.json[start..self.pos]).unwrap());
+            match self.next()? {
+                b'"' => return Ok(result),
+                b'\\' => result.push(self.escape()?),
+                _ => return Err(JoseError::Json),
+            }
+        }
+    }
+
+    fn escape(&mut self) -> Result<char, JoseError> {
+        Ok(match self.next()? {
+            b'"' => '"',
+            b'\\' => '\\',
+            b'/' => '/',
+            b'b' => '\u{8}',
+            b'f' => '\u{c}',
+            b'n' => '\n',
+            b'r' => '\r',
+            b't' => '\t',
+            b'u' => {
+                let high = self.hex4()?;
+                let code = if (0xd800..0xdc00).contains(&high) {
+                    // A high surrogate must be followed by an escaped low surrogate.
+                    if self.next()? != b'\\' || self.next()? != b'u' {
+                        return Err(JoseError::Json);
+                    }
+                    let low = self.hex4()?;
+                    if !(0xdc00..0xe000).contains(&low) {
+                        return Err(JoseError::Json);
+                    }
+                    0x10000 + ((high - 0xd800) << 10) + (low - 0xdc00)
+                } else {
+                    high
+                };
+                char::from_u32(code).ok_or(JoseError::Json)?
+            }
+            _ => return Err(JoseError::Json),
+        })
+    }
+
+    fn hex4(&mut self) -> Result<u32, JoseError> {
+        (0..4).try_fold(0, |acc, _| {
+            let digit = char::from(self.next()?)
+                .to_digit(16)
+                .ok_or(JoseError::Json)?;
+            Ok(acc << 4 | digit)
+        })
+    }
+}
+
+/// Error returned when reading a JWK or a JWS fails.
+#[docext]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum JoseError {
+    /// The JSON is malformed, or is not a flat object.
+    Json,
+    /// A required member of the object is missing.
+    MissingMember(&'static str),
+    /// A member has an unsupported or malformed value, e.g. a curve other than
+    /// secp256k1 or an algorithm other than ES256K.
+    InvalidMember(&'static str),
+    /// The JWK doesn't describe a valid key, e.g. the point is not on the
+    /// curve, or the private key doesn't match the public key.
+    InvalidKey,
+    /// The JWS is not made of three base64url-encoded parts.
+    Malformed,
+    /// The signature of the JWS doesn't verify.
+    InvalidSignature,
+}
+
+impl fmt::Display for JoseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Json => f.write_str("malformed or unsupported JSON"),
+            Self::MissingMember(name) => write!(f, "missing member \"{name}\""),
+            Self::InvalidMember(name) => write!(f, "invalid value of member \"{name}\""),
+            Self::InvalidKey => f.write_str("invalid key"),
+            Self::Malformed => f.write_str("malformed JWS"),
+            Self::InvalidSignature => f.write_str("invalid JWS signature"),
+        }
+    }
+}
+
+impl std::error::Error for JoseError {}
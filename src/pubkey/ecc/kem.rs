@@ -0,0 +1,220 @@
+use {
+    super::{ecies::shared_x, Curve, Num, PrivateKey, PublicKey, Secp256k1, UNCOMPRESSED_SIZE},
+    crate::{Csprng, DecapsError, Hash, Hkdf, HkdfError, Kem, Prk, Sha256, SharedSecret},
+    docext::docext,
+    std::fmt,
+};
+
+/// The version label of all labeled derivations in RFC 9180.
+const VERSION_LABEL: &[u8] = b"HPKE-v1";
+
+/// The Diffie-Hellman based [KEM](Kem) of [RFC
+/// 9180](https://www.rfc-editor.org/rfc/rfc9180#section-4.1), DHKEM, over an
+/// [elliptic curve](Curve), with [HKDF](Hkdf) based on [HMAC](crate::Hmac) as
+/// the key derivation function.
+///
+/// DHKEM is the key exchange at the heart of [ECIES](crate::Ecies), on its
+/// own. To encapsulate to the public key $P = pG$:
+///
+/// 1. Generate a random ephemeral private key $r$ and the corresponding public
+///    key $R = rG$. The ciphertext $enc$ is the uncompressed SEC1 encoding of
+///    $R$.
+/// 2. Compute the shared point $S = rP$, and let $dh$ be the big-endian
+///    encoding of its x-coordinate.
+/// 3. Derive the shared secret from $dh$ with HKDF, binding both public keys
+///    into the derivation:
+///
+/// $$
+/// \begin{aligned}
+/// prk &= \mathrm{LabeledExtract}(\varnothing, \texttt{"eae\_prk"}, dh) \\
+/// secret &= \mathrm{LabeledExpand}(prk, \texttt{"shared\_secret"}, enc
+/// \parallel P)
+/// \end{aligned}
+/// $$
+///
+/// The recipient computes the same $S = pR$ from $enc$, since $pR = prG =
+/// rP$. The labeled functions are HKDF with the version label `HPKE-v1` and
+/// the _suite identifier_ `KEM` followed by the two-byte KEM identifier
+/// prepended to the input, so that secrets derived by different KEMs and
+/// different protocols are unrelated:
+///
+/// $$
+/// \begin{aligned}
+/// \mathrm{LabeledExtract}(salt, label, ikm) &= \mathrm{Extract}(salt,
+/// \texttt{"HPKE-v1"} \parallel suite \parallel label \parallel ikm) \\
+/// \mathrm{LabeledExpand}(prk, label, info, L) &= \mathrm{Expand}(prk, L
+/// \parallel \texttt{"HPKE-v1"} \parallel suite \parallel label \parallel
+/// info, L)
+/// \end{aligned}
+/// $$
+///
+/// The shared secret is as long as the digest of the hash function.
+///
+/// Including $P$ in the derivation means that a ciphertext only decapsulates
+/// to the intended secret with the intended key. Since the ephemeral key is
+/// fresh for every encapsulation, encapsulating twice to the same key yields
+/// unrelated secrets.
+#[docext]
+pub struct EciesKem<C, H> {
+    _curve: C,
+    hash: H,
+    id: u16,
+}
+
+impl<C: Curve, H> EciesKem<C, H> {
+    /// Create the KEM with the given two-byte identifier, which is part of
+    /// every derivation. The identifiers are assigned by the [IANA HPKE
+    /// registry](https://www.iana.org/assignments/hpke/hpke.xhtml), e.g.
+    /// `0x0010` is DHKEM(P-256, HKDF-SHA256).
+    pub fn new(curve: C, hash: H, id: u16) -> Self {
+        debug_assert_eq!(C::validate(), Ok(()), "invalid curve parameters");
+        Self {
+            _curve: curve,
+            hash,
+            id,
+        }
+    }
+
+    /// The KEM identifier.
+    pub fn id(&self) -> u16 {
+        self.id
+    }
+}
+
+impl EciesKem<Secp256k1, Sha256> {
+    /// DHKEM(secp256k1, HKDF-SHA256), with the identifier `0x0016` from
+    /// [draft-wahby-cfrg-hpke-kem-secp256k1](https://datatracker.ietf.org/doc/draft-wahby-cfrg-hpke-kem-secp256k1/).
+    pub fn secp256k1() -> Self {
+        Self::new(Secp256k1::default(), Sha256::default(), 0x0016)
+    }
+}
+
+impl<C, H, const DIGEST_SIZE: usize, const HASH_BLOCK: usize> EciesKem<C, H>
+where
+    C: Curve,
+    H: Hash<Block = [u8; HASH_BLOCK], Digest = [u8; DIGEST_SIZE]>,
+{
+    /// Derive the shared secret from the x-coordinate of the shared point and
+    /// the encoded public keys.
+    fn extract_and_expand(&self, dh: Num, kem_context: &[u8]) -> SharedSecret {
+        let mut hkdf = Hkdf::new(&self.hash);
+        let suite = self.suite_id();
+        let dh = &dh.to_be_bytes()[Num::BYTES - C::SIZE..];
+        let prk = labeled_extract(&mut hkdf, &suite, &[], b"eae_prk", dh);
+        let mut secret = vec![0; DIGEST_SIZE];
+        // A single digest is always short enough.
+        labeled_expand(
+            &mut hkdf,
+            &suite,
+            &prk,
+            b"shared_secret",
+            kem_context,
+            &mut secret,
+        )
+        .unwrap();
+        SharedSecret::new(secret)
+    }
+
+    /// The suite identifier, `KEM` followed by the KEM identifier.
+    fn suite_id(&self) -> [u8; 5] {
+        let [hi, lo] = self.id.to_be_bytes();
+        [b'K', b'E', b'M', hi, lo]
+    }
+}
+
+impl<C, H, const DIGEST_SIZE: usize, const HASH_BLOCK: usize> Kem for EciesKem<C, H>
+where
+    C: Curve,
+    H: Hash<Block = [u8; HASH_BLOCK], Digest = [u8; DIGEST_SIZE]>,
+{
+    type PublicKey = PublicKey<C>;
+    type PrivateKey = PrivateKey<C>;
+    /// The ephemeral public key in the uncompressed SEC1 encoding.
+    type Ciphertext = [u8; UNCOMPRESSED_SIZE];
+
+    fn encapsulate(
+        &mut self,
+        key: &Self::PublicKey,
+        rng: &mut impl Csprng,
+    ) -> (Self::Ciphertext, SharedSecret) {
+        let (ephemeral, dh) = loop {
+            let r = PrivateKey::<C>::random(rng);
+            // The recipient key is a valid point of prime order, so rP is never
+            // infinity.
+            if let Some(dh) = shared_x(&r, *key) {
+                break (r.derive(), dh);
+            }
+        };
+        let enc = ephemeral.to_uncompressed();
+        let secret = self.extract_and_expand(dh, &kem_context(&enc, *key));
+        (enc, secret)
+    }
+
+    fn decapsulate(
+        &self,
+        key: &Self::PrivateKey,
+        ciphertext: &Self::Ciphertext,
+    ) -> Result<SharedSecret, DecapsError> {
+        // The ephemeral key must be validated, otherwise an attacker could use
+        // invalid points to learn about the private key.
+        let ephemeral = PublicKey::from_uncompressed(ciphertext).map_err(|_| DecapsError)?;
+        let dh = shared_x(key, ephemeral).ok_or(DecapsError)?;
+        Ok(self.extract_and_expand(dh, &kem_context(ciphertext, key.derive())))
+    }
+}
+
+impl<C, H> fmt::Debug for EciesKem<C, H> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("EciesKem")
+            .field("id", &self.id)
+            .finish_non_exhaustive()
+    }
+}
+
+/// The context bound into the shared secret: the ciphertext followed by the
+/// encoded public key of the recipient.
+fn kem_context<C: Curve>(enc: &[u8; UNCOMPRESSED_SIZE], key: PublicKey<C>) -> Vec<u8> {
+    enc.iter().chain(&key.to_uncompressed()).copied().collect()
+}
+
+/// The labeled extract step of [RFC 9180](EciesKem), for the given suite
+/// identifier.
+pub(crate) fn labeled_extract<H, const DIGEST_SIZE: usize, const HASH_BLOCK: usize>(
+    hkdf: &mut Hkdf<H>,
+    suite: &[u8],
+    salt: &[u8],
+    label: &[u8],
+    ikm: &[u8],
+) -> Prk<DIGEST_SIZE>
+where
+    H: Hash<Block = [u8; HASH_BLOCK], Digest = [u8; DIGEST_SIZE]>,
+{
+    let labeled_ikm: Vec<u8> = [VERSION_LABEL, suite, label, ikm].concat();
+    hkdf.extract(salt, &labeled_ikm)
+}
+
+/// The labeled expand step of [RFC 9180](EciesKem), for the given suite
+/// identifier, filling `okm`. Fails if `okm` is longer than
+/// [HKDF](Hkdf) can expand.
+pub(crate) fn labeled_expand<H, const DIGEST_SIZE: usize, const HASH_BLOCK: usize>(
+    hkdf: &mut Hkdf<H>,
+    suite: &[u8],
+    prk: &Prk<DIGEST_SIZE>,
+    label: &[u8],
+    info: &[u8],
+    okm: &mut [u8],
+) -> Result<(), HkdfError>
+where
+    H: Hash<Block = [u8; HASH_BLOCK], Digest = [u8; DIGEST_SIZE]>,
+{
+    let too_long = HkdfError::OutputTooLong {
+        len: okm.len(),
+        max: Hkdf::<H>::MAX_OUTPUT,
+    };
+    let len = u16::try_from(okm.len())
+        .map_err(|_| too_long)?
+        .to_be_bytes();
+    let labeled_info: Vec<u8> = [&len, VERSION_LABEL, suite, label, info].concat();
+    okm.copy_from_slice(&hkdf.expand(prk, &labeled_info, okm.len())?);
+    Ok(())
+}
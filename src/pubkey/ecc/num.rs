@@ -1,10 +1,16 @@
 use {
     crate::{
-        ecc::{Curve, Point},
-        util,
+        ecc::{
+            prime::{self, MillerRabin},
+            Curve,
+            Group,
+            Point,
+        },
+        util::{self, Choice, CtSelect},
+        Csprng,
     },
     docext::docext,
-    std::{cmp, iter, mem, ops},
+    std::{array, cmp, hint, iter, mem, ops},
 };
 
 /// Number used for modular arithmetic. Internally stored in little-endian
@@ -49,6 +55,20 @@ impl Num {
         result
     }
 
+    /// Read a number from big-endian (most-significant byte first) bytes, the
+    /// usual format for keys and coordinates in standards and other tools.
+    pub fn from_be_bytes(mut b: [u8; Self::BYTES]) -> Self {
+        b.reverse();
+        Self::from_le_bytes(b)
+    }
+
+    /// Encode the number as big-endian (most-significant byte first) bytes.
+    pub fn to_be_bytes(&self) -> [u8; Self::BYTES] {
+        let mut result = self.to_le_bytes();
+        result.reverse();
+        result
+    }
+
     /// Modular addition with modulus `p`.
     #[must_use]
     pub fn add(&self, n: Self, p: Self) -> Self {
@@ -69,40 +89,59 @@ impl Num {
     /// Modular subtraction with modulus `p`.
     #[must_use]
     pub fn sub(self, n: Self, p: Self) -> Self {
-        let (n, borrow) = sub(self.0, n.0);
+        let (diff, borrow) = sub(self.0, n.0);
         if borrow.0 {
             // If there was a borrow, then the result is negative, so add MOD to
-            // make it positive. This addition is guaranteed to result in a carry, and the
-            // carry and borrow bits "cancel" each other out. Note that adding
-            // MOD in a prime field modulus MOD is a no-op, and also note that
-            // self and rhs are both already reduced modulus MOD before the
-            // subtraction.
-            let (add, carry) = add(n, p.0);
-            assert!(carry.0);
-            Self(add)
+            // make it positive. If self and rhs are both reduced modulus MOD, this
+            // addition is guaranteed to result in a carry, and the carry and borrow
+            // bits "cancel" each other out. Note that adding MOD in a prime field
+            // modulus MOD is a no-op.
+            let (sum, carry) = add(diff, p.0);
+            if carry.0 {
+                Self(sum)
+            } else {
+                // The operands weren't reduced, so adding MOD once wasn't enough to
+                // make the result positive. Reduce them and try again, which is
+                // guaranteed to carry.
+                let (diff, _) = sub(self.reduce(p).0, n.reduce(p).0);
+                Self(add(diff, p.0).0)
+            }
         } else {
-            Self(n)
+            Self(diff)
         }
     }
 
+    /// Modular negation with modulus `p`, i.e. $p - a$ for nonzero $a$.
+    #[docext]
+    #[must_use]
+    pub fn neg_mod(self, p: Self) -> Self {
+        Self::ZERO.sub(self, p)
+    }
+
+    /// Whether the number is in the upper half of $[0, n)$, i.e. greater than
+    /// $\lfloor n / 2 \rfloor$. For odd $n$, exactly one of $a$ and $n - a$ is
+    /// high for every nonzero $a < n$.
+    #[docext]
+    pub fn is_high(&self, n: Self) -> bool {
+        *self > Self(shr(n.0))
+    }
+
     /// Modular multiplication with modulus `p`.
+    ///
+    /// Moduli just below $2^{256}$, such as the field primes [P](Curve::P)
+    /// and the group orders [N](Curve::N) of [secp256k1](crate::ecc::Secp256k1)
+    /// and [P-256](crate::ecc::Secp256r1), take a [faster
+    /// path](reduce_pseudo_mersenne) than the long division used for other
+    /// moduli.
+    #[docext]
     #[must_use]
     pub fn mul(self, n: Self, p: Self) -> Self {
-        // Same as multiplication on paper.
-        let mut prod = [0; Self::WIDTH * 2];
-        for (i, a) in self.0.into_iter().enumerate() {
-            let mut carry = 0u128;
-            for (j, b) in n.0.into_iter().enumerate() {
-                let m = prod[i + j] as u128 + a as u128 * b as u128 + carry;
-                // The upper u64::BITS are the carry part.
-                carry = (m & ((u64::MAX as u128) << u64::BITS)) >> u64::BITS;
-                // The lower u64::BITS are the digit to store at i + j.
-                prod[i + j] = u64::try_from(m & u64::MAX as u128).unwrap();
-            }
-            // The final carry becomes the next digit over.
-            prod[i + Self::WIDTH] = u64::try_from(carry).unwrap();
+        let prod = mul_wide(self.0, n.0);
+        if p.0[Self::WIDTH - 1] >> u32::BITS == u64::from(u32::MAX) {
+            Self(reduce_pseudo_mersenne(prod, p.0))
+        } else {
+            Self(reduce(prod, p.0))
         }
-        Self(reduce(prod, p.0))
     }
 
     /// Modular equality with modulus `p`.
@@ -116,8 +155,8 @@ impl Num {
     }
 
     /// Get the modular multiplicative inverse of the number by using the
-    /// extended Euclidean algorithm. Returns `None` for [`Num::ZERO`],
-    /// since 0 has no inverse.
+    /// extended Euclidean algorithm. Returns `None` for [`Num::ZERO`] and its
+    /// multiples, since 0 has no inverse.
     ///
     /// The non-extended Euclidean algorithm computes the greatest common
     /// divisor $gcd(a, b)$ given $a, b, a \leq b$. It relies on the following
@@ -210,11 +249,11 @@ impl Num {
     #[docext]
     #[must_use]
     pub fn inv(&self, p: Self) -> Option<Self> {
-        if *self == Self::ZERO {
+        let mut u = reduce(self.0, p.0);
+        // Multiples of p are zero in the field, so they have no inverse either.
+        if u == Self::ZERO.0 {
             return None;
         }
-
-        let mut u = reduce(self.0, p.0);
         let mut v = p.0;
         let mut x1 = Self::ONE;
         let mut x2 = Self::ZERO;
@@ -229,11 +268,276 @@ impl Num {
         Some(x2)
     }
 
+    /// Modular exponentiation with modulus `p`, using the square-and-multiply
+    /// method.
+    #[must_use]
+    pub fn pow(self, e: Self, p: Self) -> Self {
+        let mut result = Self::ONE.reduce(p);
+        let mut base = self.reduce(p);
+        for i in 0..Self::BITS {
+            if e.get_bit(i) {
+                result = result.mul(base, p);
+            }
+            base = base.mul(base, p);
+        }
+        result
+    }
+
+    /// Modular square root with odd prime modulus `p`: a number $r$ with $r^2
+    /// \equiv a \pmod p$, or `None` if $a$ is not a square modulo $p$. The
+    /// other root is $p - r$.
+    ///
+    /// By Euler's criterion, a nonzero $a$ is a square exactly when $a^{(p -
+    /// 1)/2} \equiv 1$. If $p \equiv 3 \pmod 4$, as is the case for
+    /// secp256k1, the root is simply
+    ///
+    /// $$
+    /// r = a^{(p + 1)/4}, \quad r^2 = a^{(p + 1)/2} = a \cdot a^{(p - 1)/2}
+    /// \equiv a
+    /// $$
+    ///
+    /// Other primes need the Tonelli-Shanks algorithm. Write $p - 1 = q 2^s$
+    /// with $q$ odd, and pick some non-square $z$. Starting from $r = a^{(q +
+    /// 1)/2}$, $t = a^q$ and $c = z^q$, the algorithm keeps $r^2 \equiv at$.
+    /// The order of $t$ is $2^i$ for some $i$, and each step multiplies $r$ by
+    /// a power $b$ of $c$ chosen so that multiplying $t$ by $b^2$ halves its
+    /// order. Once $t = 1$, $r$ is the root.
+    ///
+    /// This runs in variable time, so it is only meant for public values such
+    /// as [compressed points](crate::ecc::PublicKey::from_sec1_bytes).
+    #[docext]
+    #[must_use]
+    pub fn sqrt(self, p: Self) -> Option<Self> {
+        let a = self.reduce(p);
+        if a == Self::ZERO {
+            return Some(Self::ZERO);
+        }
+        let p1 = p.dec();
+        if a.pow(p1.shr(1), p) != Self::ONE {
+            return None;
+        }
+        if p.get_bit(1) {
+            // Since p = 4k + 3, (p + 1) / 4 = k + 1.
+            return Some(a.pow(Self(add(p.shr(2).0, Self::ONE.0).0), p));
+        }
+
+        let s = p1.trailing_zeros();
+        let q = p1.shr(s);
+        // Half of all nonzero numbers are non-squares, so the search ends
+        // quickly.
+        let z = (2..)
+            .map(Self::from_u64)
+            .find(|z| z.pow(p1.shr(1), p) == p1)
+            .unwrap();
+        let mut m = s;
+        let mut c = z.pow(q, p);
+        let mut t = a.pow(q, p);
+        // Since q is odd, (q + 1) / 2 = q / 2 + 1.
+        let mut r = a.pow(Self(add(q.shr(1).0, Self::ONE.0).0), p);
+        while t != Self::ONE {
+            // Find the order 2^i of t, which is less than 2^m.
+            let mut i = 0;
+            let mut t2 = t;
+            while t2 != Self::ONE {
+                t2 = t2.mul(t2, p);
+                i += 1;
+            }
+            let b = (0..m - i - 1).fold(c, |b, _| b.mul(b, p));
+            m = i;
+            c = b.mul(b, p);
+            t = t.mul(c, p);
+            r = r.mul(b, p);
+        }
+        Some(r)
+    }
+
+    /// Check if the number is prime using the Miller-Rabin primality test.
+    ///
+    /// Write $n - 1 = 2^s d$ with $d$ odd. If $n$ is prime, then by Fermat's
+    /// little theorem, for any base $a$ not divisible by $n$ the sequence
+    ///
+    /// $$
+    /// a^d, a^{2d}, a^{4d}, \dots, a^{2^s d} \pmod n
+    /// $$
+    ///
+    /// ends in 1, and since the only square roots of 1 modulo a prime are $1$
+    /// and $-1$, either $a^d \equiv 1$ or $-1$ appears somewhere in the
+    /// sequence. A base for which this doesn't hold is a _witness_ that $n$ is
+    /// composite.
+    ///
+    /// The test is run with the first twelve primes as bases. This is
+    /// deterministic for $n < 3.3 \cdot 10^{24}$, and for larger numbers a
+    /// composite passes with negligible probability unless it was specifically
+    /// constructed to fool these bases. That makes it suitable for catching
+    /// mistakes in curve constants, but not for testing adversarial input.
+    /// Numbers which fit into 64 bits can be tested with
+    /// [`is_prime_u64`](crate::ecc::is_prime_u64) instead.
+    #[docext]
+    pub fn is_probable_prime(&self) -> bool {
+        if *self < Self::TWO {
+            return false;
+        }
+        for b in prime::BASES {
+            let b = Self::from_u64(b);
+            if *self == b {
+                return true;
+            }
+            if self.reduce(b) == Self::ZERO {
+                return false;
+            }
+        }
+        prime::miller_rabin(*self, &prime::BASES)
+    }
+
     /// Get the bit at the given index. The rightmost (least significant) bit is
     /// at index 0.
     pub fn get_bit(&self, i: usize) -> bool {
         get_bit(self.0, i)
     }
+
+    /// Get the bit at the given index as a [choice](Choice), for selecting on
+    /// a secret number without branching.
+    pub fn ct_bit(&self, i: usize) -> Choice {
+        let word = self.0[i / u64::BITS as usize];
+        Choice::from_bit((word >> (i % u64::BITS as usize) & 1) as u8)
+    }
+
+    /// The number of bits needed to write the number down, i.e. the index of
+    /// the highest set bit plus one. Zero takes zero bits.
+    pub fn bits(&self) -> usize {
+        bits(self.0)
+    }
+
+    /// Convert a hash digest into a number modulo $n$, following Section 6.4.1
+    /// of [FIPS 186-5](https://doi.org/10.6028/NIST.FIPS.186-5): the digest is
+    /// read as a big-endian number, only its leftmost bits are kept if it has
+    /// more bits than $n$, and the result is reduced modulo $n$.
+    ///
+    /// E.g. a SHA-512 digest modulo a 256-bit $n$ keeps its first 32 bytes.
+    /// For $n$ the order of a curve, this is what
+    /// [`Scalar`](crate::ecc::Scalar) does, which also rejects digests
+    /// shorter than $n$.
+    #[docext]
+    pub fn from_digest<const D: usize>(digest: [u8; D], n: Self) -> Self {
+        Self::from_digest_slice(&digest, n)
+    }
+
+    /// [`Num::from_digest`] for a digest whose length isn't known at compile
+    /// time.
+    pub(super) fn from_digest_slice(digest: &[u8], n: Self) -> Self {
+        let bits = n.bits();
+        let len = digest.len().min(bits.div_ceil(8));
+        // The leftmost bytes, right-aligned in a big-endian number.
+        let mut bytes = [0; Self::BYTES];
+        bytes[Self::BYTES - len..].copy_from_slice(&digest[..len]);
+        // Drop the bits of the last byte which go past the bit length of n.
+        if let Some(excess) = (8 * len).checked_sub(bits).filter(|&excess| excess > 0) {
+            for i in (1..Self::BYTES).rev() {
+                bytes[i] = bytes[i] >> excess | bytes[i - 1] << (8 - excess);
+            }
+            bytes[0] >>= excess;
+        }
+        Self::from_be_bytes(bytes).reduce(n)
+    }
+
+    /// Draw a uniformly random number in $[0, p)$.
+    ///
+    /// Only as many bits as $p$ has are drawn for each attempt, so that an
+    /// attempt succeeds with probability at least one half. Attempts which
+    /// aren't below $p$ are drawn again rather than [reduced](Num::reduce),
+    /// since reducing them would make the smaller numbers more likely.
+    ///
+    /// # Panics
+    ///
+    /// If $p$ is zero, or if the source of randomness runs out.
+    #[docext]
+    pub fn random_mod(rng: &mut impl Csprng, p: Self) -> Self {
+        assert_ne!(p, Self::ZERO, "there are no numbers below zero");
+        let bits = p.bits();
+        let len = bits.div_ceil(8);
+        loop {
+            let mut bytes = [0; Self::BYTES];
+            rng.fill_bytes(&mut bytes[..len]);
+            bytes[len - 1] &= u8::MAX >> (8 * len - bits);
+            let n = Self::from_le_bytes(bytes);
+            if n < p {
+                return n;
+            }
+        }
+    }
+
+    /// Overwrite a secret number, such as a nonce, with zero once it is no
+    /// longer needed. The store goes through [`black_box`](hint::black_box),
+    /// so that the compiler can't drop it as dead even though the number is
+    /// about to go out of scope.
+    ///
+    /// In tests, every wipe is recorded, see [`take_wiped`].
+    pub(crate) fn wipe(&mut self) {
+        #[cfg(test)]
+        let secret = *self;
+        *self = Self::ZERO;
+        hint::black_box(&*self);
+        #[cfg(test)]
+        WIPED.with(|wiped| wiped.borrow_mut().push((secret, *self)));
+    }
+}
+
+#[cfg(test)]
+thread_local! {
+    static WIPED: std::cell::RefCell<Vec<(Num, Num)>> = Default::default();
+}
+
+/// The numbers [wiped](Num::wipe) on this thread since the last call, each as
+/// the secret it held and what was left of it afterwards.
+#[cfg(test)]
+pub(crate) fn take_wiped() -> Vec<(Num, Num)> {
+    WIPED.with(|wiped| wiped.take())
+}
+
+impl MillerRabin for Num {
+    fn from_u64(n: u64) -> Self {
+        Self::from_le_words([n, 0, 0, 0])
+    }
+
+    fn dec(self) -> Self {
+        Self(sub(self.0, Self::ONE.0).0)
+    }
+
+    fn trailing_zeros(self) -> usize {
+        (0..Self::BITS).find(|&i| self.get_bit(i)).unwrap()
+    }
+
+    fn shr(self, s: usize) -> Self {
+        let mut n = self.0;
+        for _ in 0..s {
+            n = shr(n);
+        }
+        Self(n)
+    }
+
+    fn mul_mod(self, rhs: Self, n: Self) -> Self {
+        self.mul(rhs, n)
+    }
+
+    fn pow_mod(self, e: Self, n: Self) -> Self {
+        self.pow(e, n)
+    }
+}
+
+/// Numbers are selected and compared word by word.
+impl CtSelect for Num {
+    fn ct_select(a: &Self, b: &Self, choice: Choice) -> Self {
+        Self(array::from_fn(|i| u64::ct_select(&a.0[i], &b.0[i], choice)))
+    }
+
+    fn ct_eq(&self, other: &Self) -> Choice {
+        let diff = self
+            .0
+            .iter()
+            .zip(other.0)
+            .fold(0, |acc, (a, b)| acc | (a ^ b));
+        diff.ct_eq(&0)
+    }
 }
 
 impl cmp::PartialOrd for Num {
@@ -262,7 +566,7 @@ struct Borrow(bool);
 
 /// Flag to indicate if an addition resulted in a carry.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
-struct Carry(bool);
+pub(crate) struct Carry(pub(crate) bool);
 
 /// The remainder left after a division.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -313,9 +617,10 @@ fn sub<const N: usize>(a: [u64; N], b: [u64; N]) -> ([u64; N], Borrow) {
     (result, Borrow(borrow))
 }
 
-/// Add two numbers.
+/// Add two numbers. Also used by [CTR mode](crate::Ctr) for counters wider
+/// than a word.
 #[must_use]
-fn add<const N: usize>(a: [u64; N], b: [u64; N]) -> ([u64; N], Carry) {
+pub(crate) fn add<const N: usize>(a: [u64; N], b: [u64; N]) -> ([u64; N], Carry) {
     // Same as addition on paper.
     let mut carry = false;
     let mut result = [0; N];
@@ -355,9 +660,20 @@ fn div<const N: usize>(n: [u64; N], d: [u64; N]) -> ([u64; N], Rem<N>) {
     // the count to the result as a single digit. Note that the count may be zero.
     // The algorithm finishes when there are no more digits in the dividend,
     // resulting in a quotient and a remainder.
+    //
+    // Nothing can be subtracted until the running remainder has as many bits
+    // as the divisor, so the leading digits of the quotient are all zero. The
+    // loop starts right before the first digit which can be nonzero, with the
+    // running remainder already holding the dividend's bits above it. This
+    // makes the division take as many steps as the quotient has bits, which
+    // matters for the extended Euclidean algorithm, where most quotients are
+    // tiny.
     let mut q = [0; N];
-    let mut r = [0; N];
-    for i in (0..N * u64::BITS as usize).rev() {
+    let steps = (bits(n) + 1)
+        .saturating_sub(bits(d))
+        .min(N * u64::BITS as usize);
+    let mut r = shr_by(n, steps);
+    for i in (0..steps).rev() {
         r = shl(r);
         if get_bit(n, i) {
             r = set_bit(r, 0);
@@ -383,9 +699,68 @@ fn div<const N: usize>(n: [u64; N], d: [u64; N]) -> ([u64; N], Rem<N>) {
 /// Reduce a number modulo another number.
 #[must_use]
 fn reduce<const N: usize, const P: usize>(n: [u64; N], p: [u64; P]) -> [u64; P] {
-    assert!(N >= P);
-    let (_div, rem) = div(n, util::resize(p));
-    util::resize(rem.0)
+    let (_div, rem) = div(n, util::widen(p));
+    // The remainder is below p, so the words past the width of p are zero.
+    util::truncate(rem.0)
+}
+
+/// Multiply two numbers, giving the full product of twice the width.
+#[must_use]
+fn mul_wide<const N: usize, const R: usize>(a: [u64; N], b: [u64; N]) -> [u64; R] {
+    debug_assert_eq!(R, 2 * N, "the product is twice as wide");
+    // Same as multiplication on paper.
+    let mut prod = [0; R];
+    for (i, a) in a.into_iter().enumerate() {
+        let mut carry = 0u128;
+        for (j, b) in b.into_iter().enumerate() {
+            let m = prod[i + j] as u128 + a as u128 * b as u128 + carry;
+            // The upper u64::BITS are the carry part.
+            carry = m >> u64::BITS;
+            // The lower u64::BITS are the digit to store at i + j.
+            prod[i + j] = u64::try_from(m & u64::MAX as u128).unwrap();
+        }
+        // The final carry becomes the next digit over.
+        prod[i + N] = u64::try_from(carry).unwrap();
+    }
+    prod
+}
+
+/// Reduce a product modulo $p = 2^{256} - c$, where $c \le 2^{224}$ is small
+/// compared to $p$.
+///
+/// Split the product into its low and high 256 bits, $n = n_{lo} + 2^{256}
+/// n_{hi}$. Since $2^{256} \equiv c \pmod p$, this is congruent to
+///
+/// $$
+/// n \equiv n_{lo} + c \cdot n_{hi} \pmod p
+/// $$
+///
+/// The new high part $c \cdot n_{hi} / 2^{256}$ is smaller than the old one
+/// by a factor of $2^{256} / c \ge 2^{32}$, so repeating the fold clears it
+/// within a few rounds, using only multiplications and additions. For the
+/// secp256k1 field prime, $c = 2^{32} + 977$ is tiny and two rounds suffice,
+/// while the P-256 prime has $c < 2^{224}$ and takes up to nine. What is left
+/// is below $2^{256}$, and since $p \ge 2^{256} - 2^{224}$, at most one
+/// subtraction of $p$ brings it into $[0, p)$.
+///
+/// Compared to the [long division](div), which runs through all 512 bits of
+/// the product one at a time, this is orders of magnitude faster.
+#[docext]
+#[must_use]
+fn reduce_pseudo_mersenne(mut n: [u64; 8], p: [u64; 4]) -> [u64; 4] {
+    debug_assert_eq!(p[3] >> 32, u64::from(u32::MAX), "c must be at most 2^224");
+    // c = 2^256 - p, computed as the two's complement of p.
+    let (c, _) = sub([0; 4], p);
+    loop {
+        let lo: [u64; 4] = util::truncate(n);
+        let hi: [u64; 4] = [n[4], n[5], n[6], n[7]];
+        if hi == [0; 4] {
+            let (r, borrow) = sub(lo, p);
+            return if borrow.0 { lo } else { r };
+        }
+        // n_lo + c * n_hi < 2^256 + 2^480, which doesn't overflow.
+        (n, _) = add(util::widen(lo), mul_wide(hi, c));
+    }
 }
 
 /// Shift all of the bits left by one.
@@ -405,6 +780,47 @@ fn shl<const N: usize>(n: [u64; N]) -> [u64; N] {
     res
 }
 
+/// Shift all of the bits right by one.
+#[must_use]
+fn shr<const N: usize>(n: [u64; N]) -> [u64; N] {
+    let mut res = [0; N];
+    let mut lsb = false;
+    for (i, digit) in n.into_iter().enumerate().rev() {
+        res[i] = digit.wrapping_shr(1);
+        // If the least significant bit was shifted out of the previous (more
+        // significant) digit, it becomes the most significant bit of this digit.
+        if lsb {
+            res[i] |= 1 << (u64::BITS - 1);
+        }
+        lsb = digit & 1 != 0;
+    }
+    res
+}
+
+/// Shift all of the bits right by `k`. Shifting by the full width or more
+/// gives zero.
+#[must_use]
+fn shr_by<const N: usize>(n: [u64; N], k: usize) -> [u64; N] {
+    let (words, bits) = (k / u64::BITS as usize, k % u64::BITS as usize);
+    array::from_fn(|i| {
+        let lo = n.get(i + words).copied().unwrap_or(0);
+        let hi = n.get(i + words + 1).copied().unwrap_or(0);
+        if bits == 0 {
+            lo
+        } else {
+            lo >> bits | hi << (u64::BITS as usize - bits)
+        }
+    })
+}
+
+/// The number of bits needed to write the number down, see [`Num::bits`].
+#[must_use]
+fn bits<const N: usize>(n: [u64; N]) -> usize {
+    n.iter().rposition(|&w| w != 0).map_or(0, |i| {
+        (i + 1) * u64::BITS as usize - n[i].leading_zeros() as usize
+    })
+}
+
 /// Get the bit at the given index. The rightmost (least significant) bit is at
 /// index 0.
 #[must_use]
@@ -443,6 +859,6 @@ impl<C: Curve> ops::Mul<Point<C>> for Num {
     type Output = Point<C>;
 
     fn mul(self, rhs: Point<C>) -> Self::Output {
-        rhs.scale(self)
+        Group::scale(rhs, self)
     }
 }
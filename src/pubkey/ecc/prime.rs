@@ -0,0 +1,131 @@
+//! Primality testing.
+//!
+//! [`Num::is_probable_prime`](super::Num::is_probable_prime) and
+//! [`is_prime_u64`] run the same [Miller-Rabin](miller_rabin) test, only
+//! over integers of different widths.
+
+use docext::docext;
+
+/// The Miller-Rabin bases, the first twelve primes. They make the test
+/// deterministic for $n < 3.3 \cdot 10^{24}$, which includes every `u64`.
+#[docext]
+pub(crate) const BASES: [u64; 12] = [2, 3, 5, 7, 11, 13, 17, 19, 23, 29, 31, 37];
+
+/// The smallest prime which isn't one of the [bases](BASES). A number below
+/// its square that isn't divisible by any of the bases is prime.
+const NEXT_PRIME: u64 = 41;
+
+/// The integer arithmetic which the [Miller-Rabin test](miller_rabin) needs.
+pub(crate) trait MillerRabin: Copy + Eq {
+    fn from_u64(n: u64) -> Self;
+
+    /// Subtract one from a nonzero number.
+    fn dec(self) -> Self;
+
+    /// The number of trailing zero bits of a nonzero number.
+    fn trailing_zeros(self) -> usize;
+
+    /// Shift the number right by `s` bits.
+    fn shr(self, s: usize) -> Self;
+
+    /// Modular multiplication with modulus `n`.
+    fn mul_mod(self, rhs: Self, n: Self) -> Self;
+
+    /// Modular exponentiation with modulus `n`.
+    fn pow_mod(self, e: Self, n: Self) -> Self;
+}
+
+/// Check that none of the bases is a witness that `n` is composite, see
+/// [`Num::is_probable_prime`](super::Num::is_probable_prime).
+///
+/// The caller must rule out the bases themselves and their multiples: `n` has
+/// to be odd and coprime to every base.
+pub(crate) fn miller_rabin<T: MillerRabin>(n: T, bases: &[u64]) -> bool {
+    // Split n - 1 into 2^s * d.
+    let n1 = n.dec();
+    let s = n1.trailing_zeros();
+    let d = n1.shr(s);
+    let one = T::from_u64(1);
+
+    'bases: for &b in bases {
+        let mut x = T::from_u64(b).pow_mod(d, n);
+        if x == one || x == n1 {
+            continue;
+        }
+        for _ in 1..s {
+            x = x.mul_mod(x, n);
+            if x == n1 {
+                continue 'bases;
+            }
+        }
+        return false;
+    }
+    true
+}
+
+impl MillerRabin for u64 {
+    fn from_u64(n: u64) -> Self {
+        n
+    }
+
+    fn dec(self) -> Self {
+        self - 1
+    }
+
+    fn trailing_zeros(self) -> usize {
+        u64::trailing_zeros(self) as usize
+    }
+
+    fn shr(self, s: usize) -> Self {
+        self >> s
+    }
+
+    fn mul_mod(self, rhs: Self, n: Self) -> Self {
+        // The product of two u64s always fits into a u128, and the remainder is
+        // below n.
+        (u128::from(self) * u128::from(rhs) % u128::from(n)) as u64
+    }
+
+    fn pow_mod(self, mut e: Self, n: Self) -> Self {
+        let mut result = 1 % n;
+        let mut base = self % n;
+        while e != 0 {
+            if e & 1 == 1 {
+                result = result.mul_mod(base, n);
+            }
+            base = base.mul_mod(base, n);
+            e >>= 1;
+        }
+        result
+    }
+}
+
+/// Check if a 64-bit number is prime.
+///
+/// Unlike [`Num::is_probable_prime`](super::Num::is_probable_prime), the
+/// answer is always correct: the number is first divided by the twelve
+/// Miller-Rabin bases, which settles every number below $41^2$, and the
+/// remaining ones are below $3.3 \cdot 10^{24}$, where these bases are known
+/// to have no strong pseudoprimes.
+#[docext]
+pub fn is_prime_u64(n: u64) -> bool {
+    if n < 2 {
+        return false;
+    }
+    for b in BASES {
+        if n == b {
+            return true;
+        }
+        if n % b == 0 {
+            return false;
+        }
+    }
+    n < NEXT_PRIME * NEXT_PRIME || miller_rabin(n, &BASES)
+}
+
+/// The smallest prime greater than or equal to `n`, or `None` if `n` is above
+/// the largest 64-bit prime, $2^{64} - 59$.
+#[docext]
+pub fn next_prime_u64(n: u64) -> Option<u64> {
+    (n..=u64::MAX).find(|&n| is_prime_u64(n))
+}
@@ -0,0 +1,120 @@
+use {
+    super::{Curve, Num},
+    docext::docext,
+    std::{fmt, marker::PhantomData},
+};
+
+/// A number modulo the [order](Curve::N) of a curve, derived from a hash
+/// digest.
+///
+/// Signature schemes hash the message and use the digest as a number modulo
+/// $N$. When the digest and $N$ have a different number of bits, there is a
+/// choice to make, and making it implicitly is a source of interoperability
+/// bugs and weaknesses. [`from_digest`](Scalar::from_digest) makes it
+/// explicitly, following Section 6.4.1 of [FIPS
+/// 186-5](https://doi.org/10.6028/NIST.FIPS.186-5):
+///
+/// - The digest is read as a big-endian number.
+/// - If the digest has more bits than $N$, only its _leftmost_ bits are used,
+///   as many as $N$ has. E.g. a 512-bit digest on a curve with a 256-bit order
+///   keeps its first 32 bytes.
+/// - The result is reduced modulo $N$. Since it has no more bits than $N$, it
+///   is less than $2N$, and at most one subtraction is needed.
+///
+/// A digest with _fewer_ bits than $N$ can only reach a small part of the
+/// scalars, e.g. SHA3-224 on a 256-bit curve misses all but one in $2^{32}$ of
+/// them, which weakens the scheme. `from_digest` rejects such digests with
+/// [`DigestSizeMismatch`]. FIPS 186-5 allows them, reading the whole digest as
+/// a number, which [`from_short_digest`](Scalar::from_short_digest) does when
+/// it is really intended.
+#[docext]
+pub struct Scalar<C>(Num, PhantomData<C>);
+
+impl<C: Curve> Scalar<C> {
+    /// Convert a digest with at least as many bits as $N$ into a scalar.
+    #[docext]
+    pub fn from_digest(digest: &[u8]) -> Result<Self, DigestSizeMismatch> {
+        let required = C::N.bits().div_ceil(8);
+        if digest.len() < required {
+            return Err(DigestSizeMismatch {
+                len: digest.len(),
+                required,
+            });
+        }
+        Ok(Self::from_short_digest(digest))
+    }
+
+    /// Convert a digest of any length into a scalar, zero-extending digests
+    /// shorter than $N$ on the left.
+    ///
+    /// For digests at least as long as $N$ this is the same as
+    /// [`from_digest`](Scalar::from_digest).
+    #[docext]
+    pub fn from_short_digest(digest: &[u8]) -> Self {
+        Self(Num::from_digest_slice(digest, C::N), PhantomData)
+    }
+}
+
+impl<C> Scalar<C> {
+    /// The scalar as a number in $[0, N)$.
+    #[docext]
+    pub fn num(&self) -> Num {
+        self.0
+    }
+}
+
+impl<C> fmt::Debug for Scalar<C> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("Scalar").field(&self.0).finish()
+    }
+}
+
+impl<C> Clone for Scalar<C> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<C> Copy for Scalar<C> {}
+
+impl<C> PartialEq for Scalar<C> {
+    fn eq(&self, other: &Self) -> bool {
+        self.0 == other.0
+    }
+}
+
+impl<C> Eq for Scalar<C> {}
+
+/// Error returned by [`Scalar::from_digest`] when the digest has fewer bytes
+/// than the order of the curve.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DigestSizeMismatch {
+    /// The length of the digest in bytes.
+    pub len: usize,
+    /// The number of bytes needed to cover the order of the curve.
+    pub required: usize,
+}
+
+impl fmt::Display for DigestSizeMismatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "digest of {} bytes is too short for a curve order of {} bytes",
+            self.len, self.required
+        )
+    }
+}
+
+impl std::error::Error for DigestSizeMismatch {}
+
+/// Convert a digest computed by a signature scheme into a scalar with
+/// [`Scalar::from_digest`].
+///
+/// A digest which is too short means that the scheme was set up with a hash
+/// function which doesn't fit the curve, which is a programming error rather
+/// than a bad input, so it panics.
+pub(crate) fn digest_scalar<C: Curve>(digest: &[u8]) -> Num {
+    Scalar::<C>::from_digest(digest)
+        .unwrap_or_else(|err| panic!("{err}"))
+        .num()
+}
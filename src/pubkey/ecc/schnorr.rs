@@ -1,22 +1,50 @@
 use {
     crate::{
-        ecc::{num, Coordinates, Curve, PrivateKey, PublicKey},
-        util::{self, CollectVec},
+        ecc::{self, num, Coordinates, Curve, PrivateKey, PublicKey, Transcript},
+        pubkey,
+        util,
         Csprng,
         Hash,
+        InvalidBatch,
         InvalidSignature,
         SignatureScheme,
     },
     docext::docext,
-    std::{array, fmt, marker::PhantomData},
+    std::{fmt, iter, marker::PhantomData},
 };
 
+mod adaptor;
+mod bip340;
+mod blind;
+mod lsag;
 mod multisig;
+mod musig2;
+mod registry;
 mod sag;
+mod session;
 
 pub use {
-    multisig::{MultiSchnorr, SchnorrRandomness},
-    sag::{SchnorrSag, SchnorrSagSignature},
+    adaptor::AdaptorSignature,
+    bip340::{Bip340Signature, SchnorrBip340, XOnlyPublicKey},
+    blind::{BlindSchnorr, BlindSession, Blinding},
+    lsag::{SchnorrLsag, SchnorrLsagSignature},
+    multisig::{
+        InvalidKeyAgg,
+        InvalidRound,
+        InvalidSchnorrRandomness,
+        KeyAggContext,
+        MultiSchnorr,
+        NonceCommitment,
+        PartialAggregate,
+        RegisteredNonce,
+        SchnorrNonce,
+        SchnorrRandomness,
+        SignatureShare,
+    },
+    musig2::{MuSig2, MuSig2Nonce, MuSig2PublicNonce},
+    registry::{SessionGuard, SessionId, SessionRegistry, SessionRejected, TooManySessions},
+    sag::{SchnorrSag, SchnorrSagError, SchnorrSagSignature},
+    session::{IncompleteSession, PartialSignature, SessionError, SigningSession},
 };
 
 // TODO I need a separate place to document the ecdlp assumption, maybe in the
@@ -45,82 +73,170 @@ pub use {
 /// $$
 ///
 /// which is the original definition of $R$ from the signing procedure.
+///
+/// In practice the public key is hashed as well. The challenge $e$ is derived
+/// from a [transcript](crate::Transcript) of the public key, the x-coordinate
+/// of $R$ and the message, so that bytes can't be moved from one field into
+/// its neighbour without changing $e$, and $e$ is uniformly distributed below
+/// $N$.
+///
+/// Since the signature is $(s, e)$, the verifier only learns $R$ by computing
+/// it, one signature at a time, so [`verify_batch`](Schnorr::verify_batch)
+/// checks every signature on its own. Signatures of the form $(R, s)$, such
+/// as [BIP-340](crate::SchnorrBip340), can instead be [verified in
+/// batches](crate::SchnorrBip340::verify_batch), by checking a random
+/// combination of the equations $sG = R + eP$ all at once.
+///
+/// # Nonces
+///
+/// If $r$ repeats for two different messages, the private key can be computed
+/// from the two signatures. Through [`SignatureScheme`], $r$ is derived
+/// deterministically by hashing the private key, the message, and the
+/// [identifier of the curve](crate::ecc::Curve::id), so it stays
+/// unpredictable as long as the private key is secret, and it only repeats
+/// for the same message.
+///
+/// [`sign_randomized`](Schnorr::sign_randomized) additionally mixes bytes
+/// from a random number generator into the hash. The generator is borrowed
+/// for the call only, so one generator can serve any number of schemes. A
+/// broken generator can't make $r$ repeat, since $r$ still depends on the
+/// private key and the message, while fresh randomness protects against
+/// attacks which rely on the same message always producing the same $r$, such
+/// as fault injection.
 #[docext]
-pub struct Schnorr<C, H, R: Csprng> {
+pub struct Schnorr<C, H> {
     _curve: C,
     hash: H,
-    rng: R::IntoIter,
 }
 
-impl<C, H, R: Csprng> Schnorr<C, H, R> {
-    pub fn new(curve: C, hash: H, rng: R) -> Self {
+impl<C: Curve, H> Schnorr<C, H> {
+    pub fn new(curve: C, hash: H) -> Self {
+        debug_assert_eq!(C::validate(), Ok(()), "invalid curve parameters");
         Self {
             _curve: curve,
             hash,
-            rng: rng.into_iter(),
         }
     }
 }
 
-impl<C, H, R, const DIGEST_SIZE: usize> SignatureScheme for Schnorr<C, H, R>
+impl<C, H, const DIGEST_SIZE: usize> Schnorr<C, H>
 where
     C: Curve,
     H: Hash<Digest = [u8; DIGEST_SIZE]>,
-    R: Csprng,
 {
-    type PublicKey = PublicKey<C>;
-    type PrivateKey = PrivateKey<C>;
-    type Signature = SchnorrSignature<C, H>;
+    /// Sign the given message under the given
+    /// [context](SignatureScheme#contexts), mixing bytes from the given source
+    /// of randomness into the [nonce](Schnorr#nonces).
+    pub fn sign_randomized(
+        &mut self,
+        key: PrivateKey<C>,
+        context: &[u8],
+        msg: &[u8],
+        rng: &mut impl Csprng,
+    ) -> SchnorrSignature<C, H> {
+        self.sign_with_seeds(
+            key,
+            context,
+            msg,
+            iter::repeat_with(|| {
+                let mut seed = [0; num::Num::BYTES];
+                rng.fill_bytes(&mut seed);
+                seed
+            }),
+        )
+    }
+
+    /// Verify a batch of signatures, each under its own key, and report every
+    /// signature which is invalid. An empty batch is valid. The messages are
+    /// verified without a [context](SignatureScheme#contexts).
+    ///
+    /// Since the signature is $(s, e)$ rather than $(R, s)$, there is no
+    /// equation to combine across the batch: $R$ only exists once it's
+    /// computed, and $e$ is a hash of it. So each signature is checked on its
+    /// own, which finds all of the invalid ones. The [BIP-340
+    /// batch](crate::SchnorrBip340::verify_batch) can do better.
+    #[docext]
+    pub fn verify_batch(&mut self, items: &[BatchItem<'_, C, H>]) -> Result<(), InvalidBatch> {
+        let invalid: Vec<_> = items
+            .iter()
+            .enumerate()
+            .filter(|(_, (key, msg, sig))| self.verify(*key, msg, sig).is_err())
+            .map(|(i, _)| i)
+            .collect();
+        if invalid.is_empty() {
+            Ok(())
+        } else {
+            Err(InvalidBatch(invalid))
+        }
+    }
 
-    fn sign(&mut self, key: Self::PrivateKey, msg: &[u8]) -> Self::Signature {
-        assert!(DIGEST_SIZE >= C::SIZE);
+    /// Sign with the nonce derived from the first seed which gives a valid
+    /// signature.
+    fn sign_with_seeds(
+        &mut self,
+        key: PrivateKey<C>,
+        context: &[u8],
+        msg: &[u8],
+        seeds: impl Iterator<Item = [u8; num::Num::BYTES]>,
+    ) -> SchnorrSignature<C, H> {
+        let msg = pubkey::with_context(context, msg);
         let pubkey = key.derive();
-        'retry: loop {
-            let k = num::Num::from_le_bytes(array::from_fn(|_| self.rng.next().unwrap()));
-            let r = match (k * C::g()).coordinates() {
-                Coordinates::Infinity => continue 'retry,
+        for seed in seeds {
+            // The nonce must lie in the scalar field.
+            let k = ecc::digest_scalar::<C>(&ecc::nonce_seed(&self.hash, &key, &[&seed, &msg]));
+            let r = match C::mul_g(k).coordinates() {
+                Coordinates::Infinity => continue,
                 Coordinates::Finite(x, _) => x,
             };
-            let e = self.hash.hash(
-                &pubkey
-                    .x()
-                    .to_le_bytes()
-                    .into_iter()
-                    .chain(r.to_le_bytes())
-                    .chain(msg.iter().copied())
-                    .collect_vec(),
-            );
-            let e = num::Num::from_le_bytes(util::resize(e));
-            let e = e.reduce(C::N);
+            let e = challenge(&self.hash, pubkey, r, &msg);
             let s = k.sub(key.0.mul(e, C::N), C::N);
-            return SchnorrSignature {
-                s,
-                e,
-                _curve: Default::default(),
-                _hash: Default::default(),
-            };
+            // A zero component is astronomically unlikely, but such a
+            // signature would be rejected by the verifier, so try again.
+            if let Ok(sig) = SchnorrSignature::new(s, e) {
+                return sig;
+            }
         }
+        unreachable!("the seeds never run out")
+    }
+}
+
+impl<C, H, const DIGEST_SIZE: usize> SignatureScheme for Schnorr<C, H>
+where
+    C: Curve,
+    H: Hash<Digest = [u8; DIGEST_SIZE]>,
+{
+    type PublicKey = PublicKey<C>;
+    type PrivateKey = PrivateKey<C>;
+    type Signature = SchnorrSignature<C, H>;
+
+    /// Sign with a deterministic [nonce](Schnorr#nonces). In the unlikely
+    /// case that the nonce gives an invalid signature, the seed is
+    /// incremented and the nonce derived again.
+    fn sign_with_context(
+        &mut self,
+        key: Self::PrivateKey,
+        context: &[u8],
+        msg: &[u8],
+    ) -> Self::Signature {
+        let seeds = (0u64..).map(|i| util::widen(i.to_le_bytes()));
+        self.sign_with_seeds(key, context, msg, seeds)
     }
 
-    fn verify(
+    fn verify_with_context(
         &mut self,
         key: Self::PublicKey,
+        context: &[u8],
         msg: &[u8],
         sig: &Self::Signature,
     ) -> Result<(), InvalidSignature> {
-        match (sig.s * C::g() + sig.e * key.point()).coordinates() {
+        // The components of a signature are always valid, unless the signature
+        // was forged without going through the constructor.
+        valid_components::<C>(sig.s, sig.e)?;
+        let msg = pubkey::with_context(context, msg);
+        match ecc::mul_double(sig.s, C::g(), sig.e, key.point()).coordinates() {
             Coordinates::Infinity => Err(InvalidSignature),
             Coordinates::Finite(r, _) => {
-                let e = self.hash.hash(
-                    &key.x()
-                        .to_le_bytes()
-                        .into_iter()
-                        .chain(r.to_le_bytes())
-                        .chain(msg.iter().copied())
-                        .collect_vec(),
-                );
-                let e = num::Num::from_le_bytes(util::resize(e));
-                if e.eq(sig.e, C::N) {
+                if challenge(&self.hash, key, r, &msg) == sig.e {
                     Ok(())
                 } else {
                     Err(InvalidSignature)
@@ -130,6 +246,42 @@ where
     }
 }
 
+/// A public key, a message and a signature to [verify in a
+/// batch](Schnorr::verify_batch).
+type BatchItem<'a, C, H> = (PublicKey<C>, &'a [u8], SchnorrSignature<C, H>);
+
+/// The challenge $e = H(P, R, m)$ of a [Schnorr](Schnorr) signature, where $r$
+/// is the x-coordinate of $R$.
+#[docext]
+fn challenge<C, H, const DIGEST_SIZE: usize>(
+    hash: &H,
+    key: PublicKey<C>,
+    r: num::Num,
+    msg: &[u8],
+) -> num::Num
+where
+    C: Curve,
+    H: Hash<Digest = [u8; DIGEST_SIZE]>,
+{
+    let mut transcript = Transcript::new(hash, b"literate-crypto schnorr");
+    transcript.append(b"pubkey", &key.x().to_le_bytes());
+    transcript.append(b"nonce", &r.to_le_bytes());
+    transcript.append(b"msg", msg);
+    transcript.challenge_scalar::<C>(b"challenge")
+}
+
+/// A [Schnorr](Schnorr) signature $(s, e)$.
+///
+/// # Components
+///
+/// Both $s$ and $e$ must lie in $[1, N - 1]$. Values of $N$ or more are
+/// rejected so that every signature has exactly one encoding. Zero is
+/// rejected because it makes the signature degenerate: with $s = 0$ the
+/// verifier computes $R = eP$, which doesn't involve the private key at all,
+/// and with $e = 0$ it computes $R = sG$, which doesn't involve the public key
+/// or the message. Every path which creates a signature from outside values
+/// goes through the same [check](SchnorrSignature::new).
+#[docext]
 #[derive(Debug)]
 pub struct SchnorrSignature<C, H> {
     s: num::Num,
@@ -138,17 +290,6 @@ pub struct SchnorrSignature<C, H> {
     _hash: PhantomData<H>,
 }
 
-impl<C, H> Default for SchnorrSignature<C, H> {
-    fn default() -> Self {
-        Self {
-            s: Default::default(),
-            e: Default::default(),
-            _curve: Default::default(),
-            _hash: Default::default(),
-        }
-    }
-}
-
 impl<C, H> Clone for SchnorrSignature<C, H> {
     fn clone(&self) -> Self {
         *self
@@ -158,18 +299,16 @@ impl<C, H> Clone for SchnorrSignature<C, H> {
 impl<C, H> Copy for SchnorrSignature<C, H> {}
 
 impl<C: Curve, H> SchnorrSignature<C, H> {
+    /// Create a signature from its components, which must be [valid
+    /// components](SchnorrSignature#components).
     pub fn new(s: num::Num, e: num::Num) -> Result<Self, InvalidSignature> {
-        // Verify that r and s are reduced modulo N.
-        if s < C::N && e < C::N {
-            Ok(Self {
-                s,
-                e,
-                _curve: Default::default(),
-                _hash: Default::default(),
-            })
-        } else {
-            Err(InvalidSignature)
-        }
+        valid_components::<C>(s, e)?;
+        Ok(Self {
+            s,
+            e,
+            _curve: Default::default(),
+            _hash: Default::default(),
+        })
     }
 
     pub fn s(&self) -> num::Num {
@@ -179,9 +318,48 @@ impl<C: Curve, H> SchnorrSignature<C, H> {
     pub fn e(&self) -> num::Num {
         self.e
     }
+
+    /// Encode the signature as the 64 bytes $s \parallel e$, both big-endian.
+    #[docext]
+    pub fn to_bytes(&self) -> [u8; 64] {
+        let mut bytes = [0; 64];
+        bytes[..32].copy_from_slice(&self.s.to_be_bytes());
+        bytes[32..].copy_from_slice(&self.e.to_be_bytes());
+        bytes
+    }
+
+    /// Decode a signature [encoded](SchnorrSignature::to_bytes) as $s
+    /// \parallel e$. Both must be [valid
+    /// components](SchnorrSignature#components).
+    #[docext]
+    pub fn from_bytes(bytes: &[u8; 64]) -> Result<Self, InvalidSignature> {
+        let (s, e) = bytes.split_at(32);
+        // The halves are 32 bytes each.
+        Self::new(
+            num::Num::from_be_bytes(s.try_into().unwrap()),
+            num::Num::from_be_bytes(e.try_into().unwrap()),
+        )
+    }
+}
+
+/// Check that $s$ and $e$ are [valid components](SchnorrSignature#components)
+/// of a signature.
+#[docext]
+fn valid_components<C: Curve>(s: num::Num, e: num::Num) -> Result<(), InvalidSignature> {
+    if valid_component::<C>(s) && valid_component::<C>(e) {
+        Ok(())
+    } else {
+        Err(InvalidSignature)
+    }
+}
+
+/// Check that a signature component lies in $[1, N - 1]$.
+#[docext]
+fn valid_component<C: Curve>(n: num::Num) -> bool {
+    n != num::Num::ZERO && n < C::N
 }
 
-impl<C, H, R: Csprng> fmt::Debug for Schnorr<C, H, R> {
+impl<C, H> fmt::Debug for Schnorr<C, H> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         f.debug_tuple("Schnorr").finish()
     }
@@ -0,0 +1,206 @@
+use {
+    super::{challenge, valid_component},
+    crate::{
+        ecc::{
+            self,
+            Coordinates,
+            Curve,
+            Group,
+            InvalidPublicKey,
+            Num,
+            Point,
+            PrivateKey,
+            PublicKey,
+        },
+        util,
+        Hash,
+        InvalidSignature,
+        Schnorr,
+        SchnorrSignature,
+    },
+    docext::docext,
+    std::marker::PhantomData,
+};
+
+/// A [Schnorr](Schnorr) pre-signature, which only becomes a valid signature
+/// once a secret number $t$ is added to it.
+///
+/// The signer picks his secret random number $k$ as usual, but computes the
+/// challenge over $R + T$ instead of $R = kG$, where $T = tG$ is the _adaptor
+/// point_:
+///
+/// $$
+/// e = H(P \parallel R + T \parallel m) \\
+/// s' = k - ep
+/// $$
+///
+/// The pre-signature is $(s', e, R)$. Anyone can check it against the adaptor
+/// point with [`verify_pre`](Schnorr::verify_pre), by checking that $s'G + eP
+/// = R$ and that $e$ was computed over $R + T$. The signer doesn't need to
+/// know $t$ to make the pre-signature, and the pre-signature alone is not a
+/// valid signature, since the verifier computes $sG + eP$ and expects to get
+/// $R + T$.
+///
+/// Whoever knows $t$ can [adapt](Schnorr::adapt) the pre-signature into a
+/// regular signature $(s, e)$ with $s = s' + t$:
+///
+/// $$
+/// sG + eP = s'G + tG + eP = R + T
+/// $$
+///
+/// Conversely, once the signature is published, anyone holding the
+/// pre-signature can [extract](Schnorr::extract_secret) the secret $t = s -
+/// s'$.
+///
+/// # Atomic swaps
+///
+/// Adaptor signatures tie two signatures together. Say Alice wants to trade
+/// something with Bob, where each of them must sign a transaction. Alice
+/// picks a secret $t$ and sends Bob $T = tG$ along with her pre-signature for
+/// her transaction. Bob checks it and replies with his own pre-signature, made
+/// with the same adaptor point $T$. Alice adapts Bob's pre-signature with $t$
+/// and publishes it to receive Bob's side of the trade. But by publishing it,
+/// she reveals $t$ to Bob, who extracts it and adapts Alice's pre-signature to
+/// receive her side. Either both transactions go through or neither does.
+#[docext]
+#[derive(Debug)]
+pub struct AdaptorSignature<C, H> {
+    s: Num,
+    e: Num,
+    r: Point<C>,
+    t: Point<C>,
+    _hash: PhantomData<H>,
+}
+
+impl<C, H> Clone for AdaptorSignature<C, H> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<C, H> Copy for AdaptorSignature<C, H> {}
+
+impl<C, H> AdaptorSignature<C, H> {
+    pub fn s(&self) -> Num {
+        self.s
+    }
+
+    pub fn e(&self) -> Num {
+        self.e
+    }
+
+    pub fn r(&self) -> Point<C> {
+        self.r
+    }
+
+    /// The adaptor point $T$ which the pre-signature was made for.
+    #[docext]
+    pub fn adaptor_point(&self) -> Point<C> {
+        self.t
+    }
+}
+
+impl<C, H, const DIGEST_SIZE: usize> Schnorr<C, H>
+where
+    C: Curve,
+    H: Hash<Digest = [u8; DIGEST_SIZE]>,
+{
+    /// Make a [pre-signature](AdaptorSignature) of the given message for the
+    /// given adaptor point $T$, which must be a [valid public
+    /// key](PublicKey::new). The nonce is derived deterministically, like
+    /// [the nonce](Schnorr#nonces) of a regular signature, with the adaptor
+    /// point hashed in as well.
+    #[docext]
+    pub fn pre_sign(
+        &mut self,
+        key: PrivateKey<C>,
+        msg: &[u8],
+        adaptor_point: Point<C>,
+    ) -> Result<AdaptorSignature<C, H>, InvalidPublicKey> {
+        let t = PublicKey::new(adaptor_point)?;
+        let pubkey = key.derive();
+        for i in 0u64.. {
+            let seed: [u8; Num::BYTES] = util::widen(i.to_le_bytes());
+            let seed = ecc::nonce_seed(&self.hash, &key, &[&seed, &t.to_uncompressed(), msg]);
+            let k = ecc::digest_scalar::<C>(&seed);
+            let r = k * C::g();
+            // Skip the astronomically unlikely nonces which give an invalid
+            // pre-signature.
+            let Coordinates::Finite(x, _) = (r + adaptor_point).coordinates() else {
+                continue;
+            };
+            if r == Point::infinity() {
+                continue;
+            }
+            let e = challenge(&self.hash, pubkey, x, msg);
+            let s = k.sub(key.0.mul(e, C::N), C::N);
+            if valid_component::<C>(s) && valid_component::<C>(e) {
+                return Ok(AdaptorSignature {
+                    s,
+                    e,
+                    r,
+                    t: adaptor_point,
+                    _hash: PhantomData,
+                });
+            }
+        }
+        unreachable!("the seeds never run out")
+    }
+
+    /// Verify a [pre-signature](AdaptorSignature) of the given message for the
+    /// given adaptor point.
+    pub fn verify_pre(
+        &self,
+        key: PublicKey<C>,
+        msg: &[u8],
+        adaptor_point: Point<C>,
+        sig: &AdaptorSignature<C, H>,
+    ) -> Result<(), InvalidSignature> {
+        if sig.t != adaptor_point
+            || PublicKey::new(adaptor_point).is_err()
+            || !valid_component::<C>(sig.s)
+            || !valid_component::<C>(sig.e)
+        {
+            return Err(InvalidSignature);
+        }
+        if Point::multi_scale(&[(sig.s, C::g()), (sig.e, key.point())]) != sig.r {
+            return Err(InvalidSignature);
+        }
+        match (sig.r + adaptor_point).coordinates() {
+            Coordinates::Finite(x, _) if challenge(&self.hash, key, x, msg) == sig.e => Ok(()),
+            _ => Err(InvalidSignature),
+        }
+    }
+
+    /// Turn the [pre-signature](AdaptorSignature) into a regular signature
+    /// with the secret $t$. Fails unless $tG = T$, or in the astronomically
+    /// unlikely case that $s' + t = 0$.
+    #[docext]
+    pub fn adapt(
+        &self,
+        sig: AdaptorSignature<C, H>,
+        t: Num,
+    ) -> Result<SchnorrSignature<C, H>, InvalidSignature> {
+        if t >= C::N || t * C::g() != sig.t {
+            return Err(InvalidSignature);
+        }
+        SchnorrSignature::new(sig.s.add(t, C::N), sig.e)
+    }
+
+    /// Extract the secret $t = s - s'$ from the
+    /// [pre-signature](AdaptorSignature) and the signature it was adapted
+    /// into. Fails if the signature was not adapted from the pre-signature.
+    #[docext]
+    pub fn extract_secret(
+        &self,
+        pre: &AdaptorSignature<C, H>,
+        sig: &SchnorrSignature<C, H>,
+    ) -> Result<Num, InvalidSignature> {
+        let t = sig.s().sub(pre.s, C::N);
+        if sig.e() == pre.e && t * C::g() == pre.t {
+            Ok(t)
+        } else {
+            Err(InvalidSignature)
+        }
+    }
+}
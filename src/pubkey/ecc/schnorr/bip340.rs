@@ -0,0 +1,368 @@
+use {
+    crate::{
+        ecc::{Coordinates, Curve, Group, InvalidPublicKey, Num, Point, PrivateKey, PublicKey},
+        pubkey,
+        Csprng,
+        Hash,
+        InvalidBatch,
+        InvalidSignature,
+        Secp256k1,
+        Sha256,
+        SignatureScheme,
+    },
+    docext::docext,
+};
+
+/// Schnorr signatures as specified by Bitcoin in
+/// [BIP-340](https://github.com/bitcoin/bips/blob/master/bip-0340.mediawiki),
+/// over [secp256k1](Secp256k1) with [SHA-256](Sha256).
+///
+/// The algorithm is the same as [`Schnorr`](crate::Schnorr), but the details
+/// are fixed so that signatures interoperate with other implementations:
+///
+/// - Public keys are [x-only](XOnlyPublicKey), 32 bytes. For every $x$ there
+///   are two points $(x, y)$ and $(x, P - y)$, and the key always means the one
+///   with even $y$. A private key $d$ whose public key $dG$ has odd $y$ is
+///   replaced by $N - d$ when signing.
+/// - The signature is $(r, s)$ instead of $(s, e)$, where $r$ is the
+///   x-coordinate of $R$. In the same way as the public key, $R$ must have an
+///   even $y$, so the signer negates the nonce if it doesn't.
+/// - Hashes are _tagged_: $H_{tag}(x) = SHA256(SHA256(tag) \parallel
+///   SHA256(tag) \parallel x)$, so that a hash computed for one purpose can
+///   never be mistaken for a hash computed for another.
+///
+/// To sign a message $m$ with the private key $d$ and the public key $P = dG$,
+/// derive the nonce $k = H_{BIP0340/nonce}(t \parallel P_x \parallel m)$
+/// where $t = d \oplus H_{BIP0340/aux}(a)$ mixes in the 32 bytes of auxiliary
+/// randomness $a$, compute $R = kG$, and
+///
+/// $$
+/// e = H_{BIP0340/challenge}(R_x \parallel P_x \parallel m) \\
+/// s = k + ed
+/// $$
+///
+/// The signature is the 64 bytes $R_x \parallel s$. To verify it, compute $R =
+/// sG - eP$ and check that it's finite, that its $y$ is even, and that its $x$
+/// equals $r$.
+///
+/// The message is signed as it is rather than hashed first, so that a
+/// [context](SignatureScheme#contexts) is the only framing added on top of
+/// BIP-340. Signing through [`SignatureScheme`] uses zeros as the auxiliary
+/// randomness, which BIP-340 allows, and gives deterministic signatures.
+/// [`sign_randomized`](SchnorrBip340::sign_randomized) reads the auxiliary
+/// randomness from a random number generator instead, for the same reasons as
+/// [`Schnorr::sign_randomized`](crate::Schnorr::sign_randomized).
+#[docext]
+#[derive(Debug, Default)]
+pub struct SchnorrBip340 {
+    hash: Sha256,
+}
+
+impl SchnorrBip340 {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sign the given message under the given
+    /// [context](SignatureScheme#contexts), with auxiliary randomness from the
+    /// given source.
+    pub fn sign_randomized(
+        &mut self,
+        key: PrivateKey<Secp256k1>,
+        context: &[u8],
+        msg: &[u8],
+        rng: &mut impl Csprng,
+    ) -> Bip340Signature {
+        let mut aux = [0; 32];
+        rng.fill_bytes(&mut aux);
+        self.sign_with_aux(key, &pubkey::with_context(context, msg), aux)
+    }
+
+    /// Sign the given message with the given auxiliary randomness, exactly as
+    /// the signing algorithm of BIP-340.
+    pub fn sign_with_aux(
+        &mut self,
+        key: PrivateKey<Secp256k1>,
+        msg: &[u8],
+        aux: [u8; 32],
+    ) -> Bip340Signature {
+        let pubkey = key.derive();
+        let d = even_y(pubkey.y(), key.0);
+        let px = pubkey.x().to_be_bytes();
+
+        let mut t = d.to_be_bytes();
+        for (t, a) in t.iter_mut().zip(self.tagged(b"BIP0340/aux", &[&aux])) {
+            *t ^= a;
+        }
+        let rand = self.tagged(b"BIP0340/nonce", &[&t, &px, msg]);
+        // A zero nonce would take a SHA-256 output which is a multiple of N,
+        // which is as hard to find as a preimage.
+        let k = PrivateKey::<Secp256k1>::new(Num::from_be_bytes(rand).reduce(Secp256k1::N))
+            .expect("the nonce is not zero");
+        let r = k.derive();
+        let k = even_y(r.y(), k.0);
+
+        let e = self.challenge(r.x(), &px, msg);
+        let s = k.add(e.mul(d, Secp256k1::N), Secp256k1::N);
+        Bip340Signature { r: r.x(), s }
+    }
+
+    /// Verify a batch of signatures, each under its own key, at once, and
+    /// report every signature which is invalid.
+    ///
+    /// Verifying a signature checks that $sG = R + eP$, where $R$ is the point
+    /// with x-coordinate $r$ and even $y$. For a batch, the equations are
+    /// multiplied by weights $a_i$ and added together:
+    ///
+    /// $$
+    /// \left(\sum_i a_i s_i\right) G = \sum_i \left(a_i R_i + a_i e_i
+    /// P_i\right)
+    /// $$
+    ///
+    /// For $k$ signatures, moving everything to one side gives a sum of
+    /// $2k + 1$ multiples which should be $\infty$. It's computed with a single
+    /// [multi-scalar multiplication](Group::multi_scale), whose multiples
+    /// share one chain of doublings, instead of the $k$ chains of verifying
+    /// every signature on its own. If every signature is valid, the sum is
+    /// $\infty$. If some signature is invalid, its equation is off by some
+    /// point $D \ne \infty$, and the sum only holds if the other terms happen
+    /// to cancel $a_i D$. The weights are $1$ plus a 128-bit number drawn from
+    /// a hash of the whole batch, as BIP-340 suggests, so they are only fixed
+    /// once all signatures are, and a forger can't arrange that, except with
+    /// probability $2^{-128}$.
+    ///
+    /// A batch which fails is split in half, and each half is checked again,
+    /// down to single signatures, so that the invalid signatures are found
+    /// with few extra checks as long as there are few of them. An empty batch
+    /// is valid. The messages are verified without a
+    /// [context](SignatureScheme#contexts).
+    #[docext]
+    pub fn verify_batch(
+        &mut self,
+        items: &[(XOnlyPublicKey, &[u8], Bip340Signature)],
+    ) -> Result<(), InvalidBatch> {
+        let n = Secp256k1::N;
+        let mut batch = Vec::new();
+        for (key, msg, sig) in items {
+            batch.extend_from_slice(&key.to_bytes());
+            batch.extend_from_slice(&sig.to_bytes());
+            batch.extend_from_slice(&u64::try_from(msg.len()).unwrap().to_le_bytes());
+            batch.extend_from_slice(msg);
+        }
+        let seed = self.tagged(b"BIP0340/batch", &[&batch]);
+
+        let mut invalid = Vec::new();
+        let mut terms = Vec::with_capacity(items.len());
+        for (i, (key, msg, sig)) in items.iter().enumerate() {
+            let Ok(r) = XOnlyPublicKey::from_bytes(&sig.r.to_be_bytes()) else {
+                invalid.push(i);
+                continue;
+            };
+            let e = self.challenge(sig.r, &key.to_bytes(), msg);
+            let weight = self.tagged(
+                b"BIP0340/batch",
+                &[&seed, &u64::try_from(i).unwrap().to_le_bytes()],
+            );
+            let mut bytes = [0; Num::BYTES];
+            bytes[..16].copy_from_slice(&weight[..16]);
+            let a = Num::from_le_bytes(bytes).add(Num::ONE, n);
+            terms.push(BatchTerm {
+                index: i,
+                a,
+                r: r.0.point(),
+                ae: a.mul(e, n),
+                p: key.0.point(),
+                as_: a.mul(sig.s, n),
+            });
+        }
+        find_invalid(&terms, &mut invalid);
+        if invalid.is_empty() {
+            Ok(())
+        } else {
+            invalid.sort_unstable();
+            Err(InvalidBatch(invalid))
+        }
+    }
+
+    /// The challenge $e = H_{BIP0340/challenge}(r \parallel P_x \parallel m)$.
+    #[docext]
+    fn challenge(&self, r: Num, px: &[u8; 32], msg: &[u8]) -> Num {
+        let digest = self.tagged(b"BIP0340/challenge", &[&r.to_be_bytes(), px, msg]);
+        Num::from_be_bytes(digest).reduce(Secp256k1::N)
+    }
+
+    /// The tagged hash $H_{tag}(x) = SHA256(SHA256(tag) \parallel SHA256(tag)
+    /// \parallel x)$, where $x$ is the concatenation of the parts.
+    #[docext]
+    fn tagged(&self, tag: &[u8], parts: &[&[u8]]) -> [u8; 32] {
+        let tag = self.hash.hash(tag);
+        let mut preimage = [tag, tag].concat();
+        for part in parts {
+            preimage.extend_from_slice(part);
+        }
+        self.hash.hash(&preimage)
+    }
+}
+
+/// The weighted verification equation of one signature in a
+/// [batch](SchnorrBip340::verify_batch).
+struct BatchTerm {
+    index: usize,
+    a: Num,
+    r: Point<Secp256k1>,
+    ae: Num,
+    p: Point<Secp256k1>,
+    as_: Num,
+}
+
+/// Check the sum of the equations with a single [multi-scalar
+/// multiplication](Group::multi_scale), and if it doesn't hold, split the
+/// terms in half and look for the invalid signatures in each half.
+fn find_invalid(terms: &[BatchTerm], invalid: &mut Vec<usize>) {
+    let n = Secp256k1::N;
+    let s = terms.iter().fold(Num::ZERO, |s, t| s.add(t.as_, n));
+    let products: Vec<_> = terms
+        .iter()
+        .flat_map(|t| [(t.a, t.r), (t.ae, t.p)])
+        .chain([(s.neg_mod(n), Secp256k1::g())])
+        .collect();
+    if Point::multi_scale(&products) == Point::infinity() {
+        return;
+    }
+    match terms {
+        [] => {}
+        [term] => invalid.push(term.index),
+        _ => {
+            let (left, right) = terms.split_at(terms.len() / 2);
+            find_invalid(left, invalid);
+            find_invalid(right, invalid);
+        }
+    }
+}
+
+/// Negate $n$ if $y$ is odd, so that $n$ corresponds to the point with even
+/// $y$.
+#[docext]
+fn even_y(y: Num, n: Num) -> Num {
+    if y.get_bit(0) {
+        n.neg_mod(Secp256k1::N)
+    } else {
+        n
+    }
+}
+
+impl SignatureScheme for SchnorrBip340 {
+    type PublicKey = XOnlyPublicKey;
+    type PrivateKey = PrivateKey<Secp256k1>;
+    type Signature = Bip340Signature;
+
+    fn sign_with_context(
+        &mut self,
+        key: Self::PrivateKey,
+        context: &[u8],
+        msg: &[u8],
+    ) -> Self::Signature {
+        self.sign_with_aux(key, &pubkey::with_context(context, msg), [0; 32])
+    }
+
+    fn verify_with_context(
+        &mut self,
+        key: Self::PublicKey,
+        context: &[u8],
+        msg: &[u8],
+        sig: &Self::Signature,
+    ) -> Result<(), InvalidSignature> {
+        let msg = pubkey::with_context(context, msg);
+        let e = self.challenge(sig.r, &key.to_bytes(), &msg);
+        let r = Point::multi_scale(&[
+            (sig.s, Secp256k1::g()),
+            (e.neg_mod(Secp256k1::N), key.0.point()),
+        ]);
+        match r.coordinates() {
+            Coordinates::Finite(x, y) if !y.get_bit(0) && x == sig.r => Ok(()),
+            _ => Err(InvalidSignature),
+        }
+    }
+}
+
+/// A [BIP-340](SchnorrBip340) public key, the x-coordinate of a point with
+/// even $y$.
+#[docext]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct XOnlyPublicKey(PublicKey<Secp256k1>);
+
+impl XOnlyPublicKey {
+    /// Decode the 32-byte big-endian x-coordinate, failing if there is no
+    /// point with that x-coordinate.
+    pub fn from_bytes(bytes: &[u8; 32]) -> Result<Self, InvalidPublicKey> {
+        // This is the compressed SEC1 encoding of the point with even y.
+        let mut sec1 = [0x02; 33];
+        sec1[1..].copy_from_slice(bytes);
+        PublicKey::from_sec1_bytes(&sec1).map(Self)
+    }
+
+    pub fn to_bytes(&self) -> [u8; 32] {
+        self.0.x().to_be_bytes()
+    }
+
+    /// The point with even $y$ which the key stands for.
+    #[docext]
+    pub fn public_key(&self) -> PublicKey<Secp256k1> {
+        self.0
+    }
+}
+
+/// Drop the y-coordinate. If it's odd, the resulting key stands for the
+/// negated point, whose private key is the negated private key.
+impl From<PublicKey<Secp256k1>> for XOnlyPublicKey {
+    fn from(key: PublicKey<Secp256k1>) -> Self {
+        if key.y().get_bit(0) {
+            // The negation of a valid point is a valid point.
+            Self(PublicKey::new(key.point().inverse()).unwrap())
+        } else {
+            Self(key)
+        }
+    }
+}
+
+/// A [BIP-340](SchnorrBip340) signature $(r, s)$, where $r$ is the
+/// x-coordinate of the nonce point $R$.
+///
+/// Every signature has exactly one encoding, so values of $r$ which aren't
+/// below $P$ and values of $s$ which aren't below $N$ are rejected.
+#[docext]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Bip340Signature {
+    r: Num,
+    s: Num,
+}
+
+impl Bip340Signature {
+    /// Decode the 64 bytes $r \parallel s$, both big-endian.
+    #[docext]
+    pub fn from_bytes(bytes: &[u8; 64]) -> Result<Self, InvalidSignature> {
+        let (r, s) = bytes.split_at(32);
+        // The halves are 32 bytes each.
+        let r = Num::from_be_bytes(r.try_into().unwrap());
+        let s = Num::from_be_bytes(s.try_into().unwrap());
+        if r >= Secp256k1::P || s >= Secp256k1::N {
+            return Err(InvalidSignature);
+        }
+        Ok(Self { r, s })
+    }
+
+    pub fn to_bytes(&self) -> [u8; 64] {
+        let mut bytes = [0; 64];
+        bytes[..32].copy_from_slice(&self.r.to_be_bytes());
+        bytes[32..].copy_from_slice(&self.s.to_be_bytes());
+        bytes
+    }
+
+    pub fn r(&self) -> Num {
+        self.r
+    }
+
+    pub fn s(&self) -> Num {
+        self.s
+    }
+}
@@ -0,0 +1,272 @@
+use {
+    super::{challenge, SessionGuard, SessionRegistry, TooManySessions},
+    crate::{
+        ecc::{Coordinates, Curve, Group, Num, Point, PrivateKey, PublicKey},
+        util,
+        Csprng,
+        Hash,
+        InvalidSignature,
+        Schnorr,
+        SchnorrSignature,
+    },
+    docext::docext,
+    std::{fmt, marker::PhantomData},
+};
+
+/// Blind [Schnorr](Schnorr) signatures, where the signer signs a message
+/// without seeing it.
+///
+/// Blind signatures are used for anonymous credentials and electronic cash:
+/// a bank signs a coin for a customer, and when the coin is later spent, the
+/// bank can check its own signature but can't tell which customer it signed
+/// the coin for.
+///
+/// The protocol runs between the signer with private key $p$ and public key
+/// $P$, and a user with the message $m$:
+///
+/// 1. The signer [starts a session](BlindSchnorr::start) by picking a secret
+///    random number $k$ and sending $R = kG$ to the user, where $G$ is the
+///    [generator point](crate::ecc::Curve::g) of the underlying [elliptic
+///    curve](crate::ecc::Curve).
+/// 2. The user [blinds](BlindSchnorr::blind) $R$ with random numbers $\alpha,
+///    \beta$, computing
+///
+///    $$
+///    R' = R + \alpha G - \beta P \\
+///    e' = H(P \parallel R' \parallel m) \\
+///    e = e' + \beta
+///    $$
+///
+///    and sends the blinded challenge $e$ to the signer.
+/// 3. The signer [responds](BlindSession::respond) with $s = k - ep$.
+/// 4. The user [unblinds](Blinding::unblind) the response, checking that $sG
+///    + eP = R$ and computing $s' = s + \alpha$.
+///
+/// The result $(s', e')$ is a regular Schnorr signature of $m$:
+///
+/// $$
+/// s'G + e'P = (k - ep + \alpha)G + e'P \\
+/// s'G + e'P = R - (e' + \beta)P + \alpha G + e'P \\
+/// s'G + e'P = R + \alpha G - \beta P \\
+/// s'G + e'P = R'
+/// $$
+///
+/// The signer saw $R$, $e$ and $s$, which are unrelated to $R'$, $e'$ and
+/// $s'$: for _any_ signature $(s', e')$ and any session $(R, e, s)$ of the
+/// signer, $\alpha = s' - s$ and $\beta = e - e'$ are blinding factors which
+/// turn one into the other. So the signer can't link a signature to the
+/// session it came from, no matter how much computing power he has.
+///
+/// # Sign convention
+///
+/// The literature usually writes Schnorr signatures as $s = k + ep$, verified
+/// by $sG = R + eP$. This crate uses $s = k - ep$, verified by $sG + eP = R$,
+/// so the signs of $\beta$ and of the response are flipped compared to most
+/// descriptions of the protocol. Keeping the convention of
+/// [`Schnorr`](Schnorr) means that the unblinded signature verifies with
+/// plain [`Schnorr::verify`](crate::SignatureScheme::verify).
+///
+/// # Concurrent sessions
+///
+/// Blind Schnorr signatures are only secure if the signer runs one session at
+/// a time. A user who opens $\ell$ sessions concurrently gets to pick the
+/// $\ell$ challenges after seeing all $\ell$ values of $R$. By solving the
+/// so-called ROS problem, e.g. with Wagner's generalized birthday algorithm, he
+/// can pick them so that the $\ell$ responses combine into $\ell + 1$
+/// signatures, one more than the signer agreed to. For $\ell$ in the hundreds
+/// the attack is practical, and for large enough $\ell$ it even runs in
+/// polynomial time.
+///
+/// As a mitigation, the number of concurrently open sessions per key is
+/// capped, by default at
+/// [`DEFAULT_MAX_SESSIONS`](BlindSchnorr::DEFAULT_MAX_SESSIONS). A session is
+/// open from [`start`](BlindSchnorr::start) until it is
+/// [responded to](BlindSession::respond) or dropped. The cap can be raised
+/// with [`with_max_sessions`](BlindSchnorr::with_max_sessions), at the
+/// signer's own risk.
+#[docext]
+pub struct BlindSchnorr<C, H> {
+    schnorr: Schnorr<C, H>,
+    registry: SessionRegistry<C>,
+}
+
+impl<C: Curve, H> BlindSchnorr<C, H> {
+    /// The default cap on concurrent sessions per key, which only allows
+    /// sequential sessions.
+    pub const DEFAULT_MAX_SESSIONS: usize = 1;
+
+    pub fn new(curve: C, hash: H) -> Self {
+        Self {
+            schnorr: Schnorr::new(curve, hash),
+            registry: SessionRegistry::new(Self::DEFAULT_MAX_SESSIONS),
+        }
+    }
+
+    /// Allow up to the given number of concurrently open sessions per key.
+    /// See [concurrent sessions](BlindSchnorr#concurrent-sessions) for why
+    /// this is dangerous.
+    pub fn with_max_sessions(self, max_sessions: usize) -> Self {
+        Self {
+            registry: self.registry.with_max_sessions(max_sessions),
+            ..self
+        }
+    }
+
+    /// The number of open sessions of the given key.
+    pub fn open_sessions(&self, key: PublicKey<C>) -> usize {
+        self.registry.open_sessions(key)
+    }
+
+    /// Start a signing session for the given private key, picking the secret
+    /// random number $k$ using the given source of randomness. The
+    /// [commitment](BlindSession::commitment) $R = kG$ of the session is sent
+    /// to the user.
+    #[docext]
+    pub fn start(
+        &mut self,
+        key: PrivateKey<C>,
+        rng: &mut impl Csprng,
+    ) -> Result<BlindSession<C>, TooManySessions> {
+        let guard = self.registry.open(key.derive())?;
+        let k = PrivateKey::<C>::random(rng).0;
+        Ok(BlindSession {
+            key,
+            k,
+            r: k * C::g(),
+            _guard: guard,
+        })
+    }
+}
+
+impl<C, H, const DIGEST_SIZE: usize> BlindSchnorr<C, H>
+where
+    C: Curve,
+    H: Hash<Digest = [u8; DIGEST_SIZE]>,
+{
+    /// Blind the commitment $R$ received from the signer with the given
+    /// pubkey, for signing the given message. Returns the blinded challenge
+    /// $e$ to send to the signer, along with the [blinding
+    /// factors](Blinding) to unblind his response with. Fails if $R$ is not a
+    /// [valid point](PublicKey::new).
+    #[docext]
+    pub fn blind(
+        &self,
+        key: PublicKey<C>,
+        r: Point<C>,
+        msg: &[u8],
+        rng: &mut impl Csprng,
+    ) -> Result<(Blinding<C, H>, Num), InvalidSignature> {
+        PublicKey::new(r).map_err(|_| InvalidSignature)?;
+        loop {
+            let alpha = PrivateKey::<C>::random(rng).0;
+            let beta = PrivateKey::<C>::random(rng).0;
+            let blinded = r + alpha * C::g() + C::N.sub(beta, C::N) * key.point();
+            let Coordinates::Finite(x, _) = blinded.coordinates() else {
+                continue;
+            };
+            let e_prime = challenge(&self.schnorr.hash, key, x, msg);
+            let e = e_prime.add(beta, C::N);
+            if e_prime == Num::ZERO || e == Num::ZERO {
+                continue;
+            }
+            let blinding = Blinding {
+                key,
+                r,
+                alpha,
+                e,
+                e_prime,
+                _hash: PhantomData,
+            };
+            return Ok((blinding, e));
+        }
+    }
+}
+
+/// An open session on the signer's side of a [blind
+/// signature](BlindSchnorr), holding the secret random number $k$.
+///
+/// The session can't be cloned, since responding to two challenges with the
+/// same $k$ reveals the private key. It counts towards the [cap on concurrent
+/// sessions](BlindSchnorr#concurrent-sessions) until it is responded to or
+/// dropped, and $k$ is wiped when it is dropped.
+#[docext]
+pub struct BlindSession<C> {
+    key: PrivateKey<C>,
+    k: Num,
+    r: Point<C>,
+    _guard: SessionGuard,
+}
+
+impl<C: Curve> BlindSession<C> {
+    /// The commitment $R = kG$, which is sent to the user.
+    #[docext]
+    pub fn commitment(&self) -> Point<C> {
+        self.r
+    }
+
+    /// Respond to the blinded challenge $e$ of the user with $s = k - ep$,
+    /// closing the session.
+    #[docext]
+    pub fn respond(self, e: Num) -> Result<Num, InvalidSignature> {
+        if e == Num::ZERO || e >= C::N {
+            return Err(InvalidSignature);
+        }
+        Ok(self.k.sub(self.key.0.mul(e, C::N), C::N))
+    }
+}
+
+impl<C> Drop for BlindSession<C> {
+    fn drop(&mut self) {
+        self.k.wipe();
+    }
+}
+
+impl<C> fmt::Debug for BlindSession<C> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("BlindSession")
+            .field("k", &util::Redacted(&self.k.to_be_bytes()))
+            .finish_non_exhaustive()
+    }
+}
+
+/// The user's side of a [blind signature](BlindSchnorr), holding the blinding
+/// factor $\alpha$ and the challenges until the signer's response arrives.
+#[docext]
+pub struct Blinding<C, H> {
+    key: PublicKey<C>,
+    r: Point<C>,
+    alpha: Num,
+    e: Num,
+    e_prime: Num,
+    _hash: PhantomData<H>,
+}
+
+impl<C: Curve, H> Blinding<C, H> {
+    /// Unblind the signer's response $s$ into a signature $(s + \alpha, e')$
+    /// of the message. Fails unless $sG + eP = R$, i.e. unless the signer
+    /// responded correctly.
+    #[docext]
+    pub fn unblind(self, s: Num) -> Result<SchnorrSignature<C, H>, InvalidSignature> {
+        if s >= C::N || Point::multi_scale(&[(s, C::g()), (self.e, self.key.point())]) != self.r {
+            return Err(InvalidSignature);
+        }
+        SchnorrSignature::new(s.add(self.alpha, C::N), self.e_prime)
+    }
+}
+
+impl<C: fmt::Debug, H> fmt::Debug for Blinding<C, H> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Blinding")
+            .field("key", &self.key)
+            .field("alpha", &util::Redacted(&self.alpha.to_be_bytes()))
+            .finish_non_exhaustive()
+    }
+}
+
+impl<C, H> fmt::Debug for BlindSchnorr<C, H> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("BlindSchnorr")
+            .field("max_sessions", &self.registry.max_sessions())
+            .finish_non_exhaustive()
+    }
+}
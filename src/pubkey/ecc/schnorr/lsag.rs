@@ -0,0 +1,286 @@
+use {
+    crate::{
+        ecc::{self, Curve, Group, Num, Point, PrivateKey, PublicKey, Transcript},
+        pubkey,
+        uniform_random,
+        util::CollectVec,
+        Csprng,
+        Hash,
+        InvalidSignature,
+        LinkableRingScheme,
+        RingScheme,
+    },
+    docext::docext,
+};
+
+/// Linkable spontaneous anonymous group signatures.
+///
+/// This is a [linkable ring signature scheme](crate::LinkableRingScheme)
+/// extending [SAG](crate::SchnorrSag). A SAG signature proves knowledge of one
+/// of the private keys behind $P_1, P_2, \dots, P_n$, but says nothing about
+/// which one, so two signatures by the same key look like two signatures by
+/// different keys. LSAG additionally proves that the same private key $p_n$ was
+/// used to compute the _key image_
+///
+/// $$
+/// I = p_n H_p(P_n)
+/// $$
+///
+/// where $H_p$ [hashes to a point](crate::ecc::hash_to_point) whose discrete
+/// logarithm nobody knows. The key image only depends on the private key, so
+/// two signatures by the same key have the same image, while computing the
+/// image of a pubkey without its private key is as hard as breaking the
+/// [ECDLP](crate::ecc::Curve).
+///
+/// Signing runs the same ring as SAG, but every step of the ring proves the
+/// relation for $G$ and $H_p(P_i)$ side by side. Scalars $r_1, r_2, \dots,
+/// r_{n-1}$ and $\alpha$ are selected randomly, and the challenges are
+///
+/// $$
+/// c_1 = H(\langle L \rangle, I, m, \alpha G, \alpha H_p(P_n)) \\
+/// c_{i+1} = H(\langle L \rangle, I, m, r_iG + c_iP_i, r_iH_p(P_i) + c_iI)
+/// $$
+///
+/// and the ring is closed with $r_n = \alpha - c_np_n$, since then
+///
+/// $$
+/// r_nG + c_nP_n = \alpha G \\
+/// r_nH_p(P_n) + c_nI = \alpha H_p(P_n)
+/// $$
+///
+/// The ring is rotated by a random amount, as in SAG, and the signature is
+/// $(c_1, r_1, \dots, r_n, P_1, \dots, P_n, I)$. The verifier recomputes the
+/// ring and checks that it closes.
+///
+/// For a decoy $P_i = p_iG$, the second point $r_iH_p(P_i) + c_iI$ only comes
+/// out as $\alpha_iH_p(P_i)$ for the same $\alpha_i$ as the first one if $I =
+/// p_iH_p(P_i)$, which the signer can't compute without $p_i$. So the image
+/// must belong to the key which actually closed the ring, while the ring still
+/// hides which key that was.
+#[docext]
+pub struct SchnorrLsag<C, H> {
+    _curve: C,
+    hash: H,
+}
+
+impl<C: Curve, H> SchnorrLsag<C, H> {
+    pub fn new(curve: C, hash: H) -> Self {
+        debug_assert_eq!(C::validate(), Ok(()), "invalid curve parameters");
+        Self {
+            _curve: curve,
+            hash,
+        }
+    }
+}
+
+impl<C, H, const DIGEST_SIZE: usize> SchnorrLsag<C, H>
+where
+    C: Curve,
+    H: Hash<Digest = [u8; DIGEST_SIZE]>,
+{
+    /// The point $H_p(P)$ which the key image of $P$ is a multiple of.
+    #[docext]
+    fn base(&self, key: PublicKey<C>) -> Point<C> {
+        ecc::hash_to_point(&self.hash, &key.to_sec1_bytes(true))
+    }
+}
+
+impl<C, H, const DIGEST_SIZE: usize> RingScheme for SchnorrLsag<C, H>
+where
+    C: Curve,
+    H: Hash<Digest = [u8; DIGEST_SIZE]>,
+{
+    type RingSignature = SchnorrLsagSignature<C>;
+    type PublicKey = PublicKey<C>;
+    type PrivateKey = PrivateKey<C>;
+
+    /// Sign the given message, hiding the private key among the decoys. The
+    /// key image and $\alpha H_p(P_n)$ are computed with the [constant-time
+    /// ladder](Group::scale_ct), since both multiply by a secret.
+    ///
+    /// # Panics
+    ///
+    /// If the pubkey of the private key is also listed as a decoy. It would
+    /// appear twice in the ring, which singles it out as the signer.
+    #[docext]
+    fn sign_with_context(
+        &mut self,
+        key: Self::PrivateKey,
+        decoys: &[Self::PublicKey],
+        context: &[u8],
+        msg: &[u8],
+        rng: &mut impl Csprng,
+    ) -> Self::RingSignature {
+        let msg = pubkey::with_context(context, msg);
+
+        let pubkey = key.derive();
+        assert!(
+            !decoys.contains(&pubkey),
+            "the signer's pubkey is listed as a decoy"
+        );
+        let mut pubkeys = decoys.to_vec();
+        pubkeys.push(pubkey);
+
+        let g = C::g();
+        let base = self.base(pubkey);
+        let image = base.scale_ct(key.0);
+        let transcript = ring_transcript(&self.hash, &pubkeys, image, &msg);
+
+        // Start the ring with a random alpha, the same for both points.
+        let alpha = PrivateKey::<C>::random(rng).0;
+        let mut c = vec![challenge::<C, _, DIGEST_SIZE>(
+            &transcript,
+            C::mul_g(alpha),
+            base.scale_ct(alpha),
+        )];
+        let mut r = Vec::new();
+
+        for &decoy in decoys {
+            let ci = *c.last().unwrap();
+            let ri = PrivateKey::<C>::random(rng).0;
+            let left = ecc::mul_double(ri, g, ci, decoy.point());
+            let right = ecc::mul_double(ri, self.base(decoy), ci, image);
+            r.push(ri);
+            c.push(challenge::<C, _, DIGEST_SIZE>(&transcript, left, right));
+        }
+
+        // Close the ring with the real private key.
+        let cn = *c.last().unwrap();
+        r.push(alpha.sub(cn.mul(key.0, C::N), C::N));
+
+        assert_eq!(r.len(), c.len());
+        assert_eq!(c.len(), pubkeys.len());
+
+        // Rotate the ring randomly so that the real signer isn't always last.
+        let shift =
+            usize::try_from(uniform_random(rng, 0..u32::try_from(r.len()).unwrap())).unwrap();
+        c.rotate_left(shift);
+        r.rotate_left(shift);
+        pubkeys.rotate_left(shift);
+
+        SchnorrLsagSignature {
+            c: c[0],
+            r,
+            keys: pubkeys,
+            image,
+        }
+    }
+
+    fn verify_with_context(
+        &mut self,
+        context: &[u8],
+        msg: &[u8],
+        sig: &Self::RingSignature,
+    ) -> Result<(), InvalidSignature> {
+        // An image outside of the subgroup could be shifted by a point of small
+        // order without changing the ring, giving the same key several images.
+        if sig.image == Point::infinity() || !sig.image.in_subgroup() {
+            return Err(InvalidSignature);
+        }
+        if sig.r.len() != sig.keys.len() || sig.keys.is_empty() {
+            return Err(InvalidSignature);
+        }
+        let msg = pubkey::with_context(context, msg);
+        let transcript = ring_transcript(&self.hash, &sig.keys, sig.image, &msg);
+        let g = C::g();
+        let c = sig.r.iter().zip(&sig.keys).fold(sig.c, |c, (&r, &key)| {
+            let left = ecc::mul_double(r, g, c, key.point());
+            let right = ecc::mul_double(r, self.base(key), c, sig.image);
+            challenge::<C, _, DIGEST_SIZE>(&transcript, left, right)
+        });
+        if c == sig.c {
+            Ok(())
+        } else {
+            Err(InvalidSignature)
+        }
+    }
+}
+
+impl<C, H, const DIGEST_SIZE: usize> LinkableRingScheme for SchnorrLsag<C, H>
+where
+    C: Curve,
+    H: Hash<Digest = [u8; DIGEST_SIZE]>,
+{
+    type KeyImage = Point<C>;
+
+    fn key_image(&self, sig: &Self::RingSignature) -> Self::KeyImage {
+        sig.image
+    }
+}
+
+/// An [LSAG](SchnorrLsag) ring signature $(c_1, r_1, \dots, r_n, P_1, \dots,
+/// P_n, I)$.
+#[docext]
+#[derive(Debug)]
+pub struct SchnorrLsagSignature<C> {
+    c: Num,
+    r: Vec<Num>,
+    keys: Vec<PublicKey<C>>,
+    image: Point<C>,
+}
+
+impl<C> SchnorrLsagSignature<C> {
+    pub fn c(&self) -> Num {
+        self.c
+    }
+
+    pub fn r(&self) -> &[Num] {
+        &self.r
+    }
+
+    pub fn keys(&self) -> &[PublicKey<C>] {
+        &self.keys
+    }
+
+    /// The key image $I$, the same for every signature by the same private
+    /// key.
+    #[docext]
+    pub fn key_image(&self) -> Point<C> {
+        self.image
+    }
+}
+
+/// The [transcript](Transcript) of the ring, the key image and the message,
+/// shared by every challenge in the ring.
+fn ring_transcript<C, H, const DIGEST_SIZE: usize>(
+    hash: H,
+    keys: &[PublicKey<C>],
+    image: Point<C>,
+    msg: &[u8],
+) -> Transcript<H>
+where
+    C: Curve,
+    H: Hash<Digest = [u8; DIGEST_SIZE]>,
+{
+    // The ring is rotated after signing, so the keys are encoded in their
+    // canonical order rather than the order they are listed in.
+    let mut keys = keys.to_vec();
+    keys.sort();
+    let ring = keys
+        .iter()
+        .flat_map(|key| key.to_sec1_bytes(true))
+        .collect_vec();
+    let mut transcript = Transcript::new(hash, b"literate-crypto schnorr lsag");
+    transcript.append(b"ring", &ring);
+    transcript.append(b"image", &ecc::compressed(image.coordinates()));
+    transcript.append(b"msg", msg);
+    transcript
+}
+
+/// The challenge $c_{i+1} = H(\langle L \rangle, I, m, r_iG + c_iP_i,
+/// r_iH_p(P_i) + c_iI)$, forked from the [ring transcript](ring_transcript).
+#[docext]
+fn challenge<C, H, const DIGEST_SIZE: usize>(
+    transcript: &Transcript<H>,
+    left: Point<C>,
+    right: Point<C>,
+) -> Num
+where
+    C: Curve,
+    H: Hash<Digest = [u8; DIGEST_SIZE]> + Clone,
+{
+    let mut transcript = transcript.clone();
+    transcript.append(b"left", &ecc::compressed(left.coordinates()));
+    transcript.append(b"right", &ecc::compressed(right.coordinates()));
+    transcript.challenge_scalar::<C>(b"challenge")
+}
@@ -1,15 +1,22 @@
 use {
     crate::{
         ecc,
-        ecc::{Curve, Num, Point, PrivateKey, PublicKey},
-        util::{self, CollectVec},
+        ecc::{Curve, Group, Num, Point, PrivateKey, PublicKey, Transcript},
+        pubkey,
+        util::{self, canonical, CollectVec},
         Csprng,
         Hash,
         InvalidSignature,
         MultisigScheme,
+        PartialSignature,
         Schnorr,
         SchnorrSignature,
+        SessionGuard,
+        SessionId,
+        SessionRegistry,
+        SessionRejected,
         SignatureScheme,
+        SigningSession,
     },
     core::fmt,
     docext::docext,
@@ -17,7 +24,7 @@ use {
 };
 
 // TODO link to simple approach
-/// A [multisig scheme](crate::Multisig) based on [Schnorr
+/// A [multisig scheme](crate::MultisigScheme) based on [Schnorr
 /// signatures](crate::Schnorr).
 ///
 /// Using Schnorr signatures has multiple advantages over the [simple approach
@@ -42,11 +49,11 @@ use {
 /// s \gets s + s_i
 /// $$
 ///
-/// Where $H_{agg}$ and $H_{sig}$ are hash functions returning numbers in the
-/// range $[1, N-1]$ (which don't have to be the same, but in this
-/// implementation they are), $\langle L \rangle$ is a unique encoding of the
-/// pubkeys $P_1, P_2, \dotsc, P_n$, and $c_i$ is called the "challenge" for
-/// $p_i$. $\tilde P$ is the combined pubkey:
+/// Where $H_{agg}$ and $H_{sig}$ are hash functions returning numbers below
+/// $N$, derived from [transcripts](crate::Transcript) of different protocols
+/// with the same underlying hash function, $\langle L \rangle$ is a unique
+/// encoding of the pubkeys $P_1, P_2, \dotsc, P_n$, and $c_i$ is called the
+/// "challenge" for $p_i$. $\tilde P$ is the combined pubkey:
 ///
 /// $$
 /// \tilde P = \sum_{i = 1}^{n} P_i \cdot H_{agg}(\langle L \rangle \parallel
@@ -55,6 +62,13 @@ use {
 /// The resulting signature is $(s, e)$ where $e = H_{sig}(\tilde P \parallel R
 /// \parallel m)$.
 ///
+/// Neither $s$ nor $e$ depends on the order of the actors: $s$ is a sum,
+/// $\langle L \rangle$ encodes the pubkeys independently of their order, and
+/// so does $R$. So the actors can just as well compute their $s_i$
+/// simultaneously, in the second of the [two rounds](MultisigScheme#rounds),
+/// and leave the summation to whoever [aggregates](MultisigScheme::aggregate)
+/// them.
+///
 /// A [regular Schnorr signature](crate::Schnorr) $(s, e)$ is verified using the
 /// following formula, where $e$ is the hash $H(P \parallel R \parallel m)$ and
 /// $P$ is the public key:
@@ -151,81 +165,765 @@ use {
 ///    prevents this problem, since the malicious actor would end up multiplying
 ///    $p_1'$ with the hash of $P_1$ resulting in an invalid signature.
 #[docext]
-pub struct MultiSchnorr<C, H, R: Csprng>(Schnorr<C, H, R>);
+pub struct MultiSchnorr<C, H>(Schnorr<C, H>);
 
-impl<C, H, R: Csprng> MultiSchnorr<C, H, R> {
-    pub fn new(curve: C, hash: H, rng: R) -> Self {
-        Self(Schnorr::new(curve, hash, rng))
+impl<C: Curve, H> MultiSchnorr<C, H> {
+    pub fn new(curve: C, hash: H) -> Self {
+        Self(Schnorr::new(curve, hash))
     }
 }
 
-impl<C, H, R, const DIGEST_SIZE: usize> MultisigScheme for MultiSchnorr<C, H, R>
+impl<C, H, const DIGEST_SIZE: usize> MultisigScheme for MultiSchnorr<C, H>
 where
     C: Curve,
     H: Hash<Digest = [u8; DIGEST_SIZE]>,
-    R: Csprng,
 {
     type PublicKey = PublicKey<C>;
-    type PrivateKey = (PrivateKey<C>, Vec<PublicKey<C>>, SchnorrRandomness<C>);
+    type PrivateKey = PrivateKey<C>;
     type Multisig = SchnorrSignature<C, H>;
+    type Nonce = SchnorrNonce<C>;
+    type Round1Msg = (Point<C>, [u8; DIGEST_SIZE]);
+    type Round2Msg = SignatureShare<C, H>;
+    type RoundError = InvalidRound;
 
-    fn sign(&mut self, key: Self::PrivateKey, msg: &[u8], sig: Self::Multisig) -> Self::Multisig {
-        assert!(DIGEST_SIZE >= C::SIZE);
-        let (key, pubkeys, randomness) = key;
-        let pubkey = key.derive();
-        let a = h_agg(&self.0.hash, &pubkeys, pubkey);
-        let e = h_sig(&self.0.hash, &pubkeys, randomness, msg);
-        let c = a.mul(e, C::N);
-        let s = randomness.local.sub(key.0.mul(c, C::N), C::N);
-        SchnorrSignature::new(sig.s().add(s, C::N), e).unwrap()
+    /// Pick the secret random number $r_i$ and broadcast $R_i$ along with the
+    /// commitment $t_i$, see [`SchnorrRandomness::commit`]. To prevent an
+    /// actor from choosing $R_i$ based on the $R_i$ values of the others, the
+    /// commitments $t_i$ can be exchanged before the points themselves.
+    #[docext]
+    fn round1(
+        &mut self,
+        _key: &Self::PrivateKey,
+        rng: &mut impl Csprng,
+    ) -> (Self::Nonce, Self::Round1Msg) {
+        let local = PrivateKey::<C>::random(rng).0;
+        let nonce = SchnorrNonce {
+            local,
+            _curve: PhantomData,
+        };
+        (nonce, SchnorrRandomness::commit(&self.0.hash, local))
+    }
+
+    fn round2_with_context(
+        &mut self,
+        key: &Self::PrivateKey,
+        nonce: Self::Nonce,
+        keys: &[Self::PublicKey],
+        round1: &[Self::Round1Msg],
+        context: &[u8],
+        msg: &[u8],
+    ) -> Result<Self::Round2Msg, Self::RoundError> {
+        let signer = key.derive();
+        if !keys.contains(&signer) {
+            return Err(InvalidRound::UnknownSigner);
+        }
+        let agg = KeyAggContext::sorted(&self.0.hash, keys).map_err(InvalidRound::KeyAgg)?;
+        let own = SchnorrRandomness::<C>::commit(&self.0.hash, nonce.local);
+        if round1.len() != keys.len() || round1.iter().filter(|&&m| m == own).count() != 1 {
+            return Err(InvalidRound::MissingNonce);
+        }
+        let others = round1.iter().filter(|&&m| m != own).copied().collect_vec();
+        let randomness = SchnorrRandomness::new(&self.0.hash, nonce.local, &others)
+            .map_err(InvalidRound::Randomness)?;
+        let (s, e) = self
+            .partial(
+                key,
+                signer,
+                &agg,
+                randomness,
+                &pubkey::with_context(context, msg),
+            )
+            .ok_or(InvalidRound::UnknownSigner)?;
+        Ok(SignatureShare {
+            signer,
+            s,
+            e,
+            _hash: PhantomData,
+        })
     }
 
-    fn verify(
+    /// Sum the individual signatures $s_i$. Addition is commutative, so the
+    /// result doesn't depend on the order of the shares.
+    #[docext]
+    fn aggregate(
+        &self,
+        keys: &[Self::PublicKey],
+        round2: &[Self::Round2Msg],
+    ) -> Result<Self::Multisig, InvalidSignature> {
+        aggregate_shares(keys, round2)
+    }
+
+    fn verify_with_context(
         &mut self,
         keys: &[Self::PublicKey],
+        context: &[u8],
         msg: &[u8],
         sig: &Self::Multisig,
     ) -> Result<(), InvalidSignature> {
-        assert!(DIGEST_SIZE >= C::SIZE);
-        let key = combine(&self.0.hash, keys);
-        self.0.verify(key, msg, sig)
+        let agg = KeyAggContext::sorted(&self.0.hash, keys).map_err(|_| InvalidSignature)?;
+        self.0
+            .verify_with_context(agg.aggregate_key(), context, msg, sig)
+    }
+}
+
+/// The secret random number $r_i$ of an actor, picked in the [first
+/// round](MultisigScheme::round1) of a [Schnorr multisig](MultiSchnorr).
+///
+/// The nonce can't be cloned, and the [second round](MultisigScheme::round2)
+/// consumes it, since using the same $r_i$ for two different signatures
+/// reveals the private key. The number is wiped when the nonce is dropped. Its
+/// [`Debug`](fmt::Debug) output only shows a
+/// [fingerprint](crate::key_fingerprint).
+#[docext]
+pub struct SchnorrNonce<C> {
+    local: Num,
+    _curve: PhantomData<C>,
+}
+
+impl<C> Drop for SchnorrNonce<C> {
+    fn drop(&mut self) {
+        self.local.wipe();
+    }
+}
+
+impl<C: Curve> SchnorrNonce<C> {
+    /// The public counterpart $R_i = r_iG$ of the secret number, which is
+    /// revealed to the other actors once everyone's
+    /// [commitment](NonceCommitment) has been received.
+    #[docext]
+    pub fn reveal(&self) -> Point<C> {
+        C::mul_g(self.local)
+    }
+}
+
+impl<C> fmt::Debug for SchnorrNonce<C> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SchnorrNonce")
+            .field("local", &util::Redacted(&self.local.to_be_bytes()))
+            .finish()
+    }
+}
+
+/// A [nonce](SchnorrNonce) of a session [opened](MultiSchnorr::open_session)
+/// in a [registry](SessionRegistry).
+///
+/// The session stays open until the nonce is
+/// [finished](MultiSchnorr::finish_session) or dropped. Either way the secret
+/// $r_i$ is wiped, and the registry won't open the same session id again.
+/// Finishing takes the nonce by value, so a session can't be finished twice:
+///
+/// ```compile_fail,E0382
+/// # use literate_crypto::{
+/// #     ecc::{Num, PrivateKey},
+/// #     Fortuna,
+/// #     MultiSchnorr,
+/// #     Secp256k1,
+/// #     SessionId,
+/// #     SessionRegistry,
+/// #     Sha256,
+/// # };
+/// let mut multisig = MultiSchnorr::new(Secp256k1::default(), Sha256::default());
+/// let mut registry = SessionRegistry::new(1);
+/// let key = PrivateKey::new(Num::ONE).unwrap();
+/// let mut rng = Fortuna::from_os();
+/// let id = SessionId::random(&mut rng);
+/// let (nonce, msg) = multisig
+///     .open_session(&mut registry, &key, id, &mut rng)
+///     .unwrap();
+/// let keys = [key.derive()];
+/// let _ = multisig.finish_session(&key, nonce, &keys, &[msg], &[], b"msg");
+/// let _ = multisig.finish_session(&key, nonce, &keys, &[msg], &[], b"msg");
+/// ```
+#[docext]
+pub struct RegisteredNonce<C> {
+    id: SessionId,
+    nonce: SchnorrNonce<C>,
+    guard: SessionGuard,
+}
+
+impl<C> RegisteredNonce<C> {
+    pub fn id(&self) -> SessionId {
+        self.id
+    }
+}
+
+impl<C> fmt::Debug for RegisteredNonce<C> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RegisteredNonce")
+            .field("id", &self.id)
+            .field("nonce", &self.nonce)
+            .finish_non_exhaustive()
+    }
+}
+
+/// The individual signature $s_i$ of one actor, broadcast in the [second
+/// round](MultisigScheme::round2) of a [Schnorr multisig](MultiSchnorr) or of
+/// [MuSig2](crate::MuSig2), along with the challenge $e$ which it was made
+/// for.
+#[docext]
+#[derive(Debug)]
+pub struct SignatureShare<C, H> {
+    pub(super) signer: PublicKey<C>,
+    pub(super) s: Num,
+    pub(super) e: Num,
+    pub(super) _hash: PhantomData<H>,
+}
+
+impl<C, H> Clone for SignatureShare<C, H> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<C, H> Copy for SignatureShare<C, H> {}
+
+impl<C, H> SignatureShare<C, H> {
+    /// Construct the second-round message of the actor with the given pubkey,
+    /// e.g. after receiving it from the network. It is checked when
+    /// [aggregated](MultisigScheme::aggregate).
+    pub fn new(signer: PublicKey<C>, s: Num, e: Num) -> Self {
+        Self {
+            signer,
+            s,
+            e,
+            _hash: PhantomData,
+        }
+    }
+
+    pub fn signer(&self) -> PublicKey<C> {
+        self.signer
+    }
+
+    pub fn s(&self) -> Num {
+        self.s
+    }
+
+    pub fn e(&self) -> Num {
+        self.e
+    }
+}
+
+/// Sum the shares of a multisig, provided that every one of the given pubkeys
+/// contributed exactly one share.
+pub(super) fn aggregate_shares<C: Curve, H>(
+    keys: &[PublicKey<C>],
+    shares: &[SignatureShare<C, H>],
+) -> Result<SchnorrSignature<C, H>, InvalidSignature> {
+    let complete = shares.len() == keys.len()
+        && keys
+            .iter()
+            .all(|&key| shares.iter().filter(|share| share.signer == key).count() == 1);
+    if !complete {
+        return Err(InvalidSignature);
+    }
+    shares
+        .iter()
+        .fold(PartialAggregate::default(), |sig, share| {
+            sig.add(share.s, share.e)
+        })
+        .finalize()
+}
+
+/// Error returned by the [second round](MultisigScheme::round2) of a [Schnorr
+/// multisig](MultiSchnorr) or of [MuSig2](crate::MuSig2).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InvalidRound {
+    /// The private key doesn't belong to any of the signers.
+    UnknownSigner,
+    /// The first-round messages don't contain the actor's own message exactly
+    /// once, or there isn't one message per signer.
+    MissingNonce,
+    /// The first-round messages of the other actors are invalid.
+    Randomness(InvalidSchnorrRandomness),
+    /// The pubkeys can't be aggregated, see [`KeyAggContext::new`].
+    KeyAgg(InvalidKeyAgg),
+}
+
+impl fmt::Display for InvalidRound {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::UnknownSigner => write!(f, "signer is not part of the multisig"),
+            Self::MissingNonce => write!(f, "first-round messages don't match the signers"),
+            Self::Randomness(e) => write!(f, "{e}"),
+            Self::KeyAgg(e) => write!(f, "{e}"),
+        }
+    }
+}
+
+impl std::error::Error for InvalidRound {}
+
+/// A [Schnorr multisig](MultiSchnorr) which only some of the actors have
+/// signed so far.
+///
+/// The aggregate keeps the sum of the individual signatures $s_i$ and the
+/// challenge $e$, which must be the same for every actor. The
+/// [empty](Default) aggregate has no challenge yet, so it is not a signature
+/// at all, not even an invalid one. It only becomes a
+/// [signature](SchnorrSignature) when it is
+/// [finalized](PartialAggregate::finalize), which fails if nobody has signed,
+/// if the actors signed with different challenges, if somebody who isn't one
+/// of the signers tried to sign, or if the result has a zero component.
+#[docext]
+#[derive(Debug)]
+pub struct PartialAggregate<C, H> {
+    s: Num,
+    /// The challenge, or `None` if nobody has signed yet.
+    e: Option<Num>,
+    /// Whether all actors so far signed with the same challenge, and were all
+    /// signers.
+    consistent: bool,
+    signers: usize,
+    _curve: PhantomData<C>,
+    _hash: PhantomData<H>,
+}
+
+impl<C, H> Default for PartialAggregate<C, H> {
+    fn default() -> Self {
+        Self {
+            s: Num::ZERO,
+            e: None,
+            consistent: true,
+            signers: 0,
+            _curve: PhantomData,
+            _hash: PhantomData,
+        }
+    }
+}
+
+impl<C, H> Clone for PartialAggregate<C, H> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<C, H> Copy for PartialAggregate<C, H> {}
+
+impl<C: Curve, H> PartialAggregate<C, H> {
+    /// The number of individual signatures in the aggregate.
+    pub fn signers(&self) -> usize {
+        self.signers
+    }
+
+    /// Turn the aggregate into a multisig.
+    pub fn finalize(self) -> Result<SchnorrSignature<C, H>, InvalidSignature> {
+        match self.e {
+            Some(e) if self.consistent => SchnorrSignature::new(self.s, e),
+            _ => Err(InvalidSignature),
+        }
+    }
+
+    /// Add the individual signature $s_i$ made with the challenge $e$.
+    #[docext]
+    fn add(self, s: Num, e: Num) -> Self {
+        Self {
+            s: self.s.add(s, C::N),
+            e: Some(e),
+            consistent: self.consistent && self.e.map_or(true, |prev| prev == e),
+            signers: self.signers + 1,
+            ..self
+        }
+    }
+
+    /// Mark the aggregate as unusable, after somebody who isn't one of the
+    /// signers tried to sign.
+    fn poison(self) -> Self {
+        Self {
+            consistent: false,
+            ..self
+        }
+    }
+}
+
+impl<C, H, const DIGEST_SIZE: usize> MultiSchnorr<C, H>
+where
+    C: Curve,
+    H: Hash<Digest = [u8; DIGEST_SIZE]>,
+{
+    /// Sign the given message under the given
+    /// [context](SignatureScheme#contexts) and add the individual signature to
+    /// the given partial aggregate, which starts out [empty](Default).
+    ///
+    /// This is the accumulating counterpart of the [two
+    /// rounds](MultisigScheme#rounds), for actors which have already exchanged
+    /// their [randomness](SchnorrRandomness) and sign one after another. Once
+    /// every actor has signed, the aggregate is
+    /// [finalized](PartialAggregate::finalize) into the multisig.
+    ///
+    /// If the private key doesn't belong to any of the signers, the aggregate
+    /// can no longer be finalized.
+    pub fn accumulate_with_context(
+        &mut self,
+        key: (PrivateKey<C>, &KeyAggContext<C, H>, SchnorrRandomness<C>),
+        context: &[u8],
+        msg: &[u8],
+        sig: PartialAggregate<C, H>,
+    ) -> PartialAggregate<C, H> {
+        let (key, agg, randomness) = key;
+        match self.partial(
+            &key,
+            key.derive(),
+            agg,
+            randomness,
+            &pubkey::with_context(context, msg),
+        ) {
+            Some((s, e)) => sig.add(s, e),
+            None => sig.poison(),
+        }
+    }
+
+    /// Sign the given message and add the individual signature to the given
+    /// partial aggregate, see
+    /// [`accumulate_with_context`](MultiSchnorr::accumulate_with_context).
+    pub fn accumulate(
+        &mut self,
+        key: (PrivateKey<C>, &KeyAggContext<C, H>, SchnorrRandomness<C>),
+        msg: &[u8],
+        sig: PartialAggregate<C, H>,
+    ) -> PartialAggregate<C, H> {
+        self.accumulate_with_context(key, &[], msg, sig)
+    }
+
+    /// The individual signature $s_i = r_i - p_ic_i$ of the given private key
+    /// with the given pubkey, along with the challenge $e$. Returns `None` if
+    /// the pubkey isn't one of the signers.
+    #[docext]
+    fn partial(
+        &self,
+        key: &PrivateKey<C>,
+        pubkey: PublicKey<C>,
+        agg: &KeyAggContext<C, H>,
+        randomness: SchnorrRandomness<C>,
+        msg: &[u8],
+    ) -> Option<(Num, Num)> {
+        let a = agg.coefficient(pubkey)?;
+        let e = h_sig(&self.0.hash, agg, &randomness, msg);
+        let c = a.mul(e, C::N);
+        Some((randomness.local.sub(key.0.mul(c, C::N), C::N), e))
+    }
+
+    /// Start a [signing session](SigningSession) for the given message, signed
+    /// by the aggregated pubkeys with the given aggregate randomness.
+    pub fn start_session(
+        &mut self,
+        agg: &KeyAggContext<C, H>,
+        randomness: SchnorrRandomness<C>,
+        msg: &[u8],
+    ) -> SigningSession<C, H> {
+        let e = h_sig(&self.0.hash, agg, &randomness, msg);
+        SigningSession::new(e, agg.pubkeys().to_vec(), randomness.total)
+    }
+
+    /// Compute the individual signature $s_i$ of the given private key for a
+    /// [signing session](SigningSession), to be
+    /// [contributed](SigningSession::contribute) to it.
+    ///
+    /// Returns `None` if the private key doesn't belong to any of the signers,
+    /// if the session is for different signers than the key aggregation, or if
+    /// the randomness doesn't match the aggregate randomness of the session.
+    #[docext]
+    pub fn sign_partial(
+        &mut self,
+        key: PrivateKey<C>,
+        agg: &KeyAggContext<C, H>,
+        session: &SigningSession<C, H>,
+        randomness: SchnorrRandomness<C>,
+    ) -> Option<PartialSignature<C>> {
+        if agg.pubkeys() != session.pubkeys() || randomness.total != session.total() {
+            return None;
+        }
+        let pubkey = key.derive();
+        let a = agg.coefficient(pubkey)?;
+        let c = a.mul(session.e(), C::N);
+        let s = randomness.local.sub(key.0.mul(c, C::N), C::N);
+        Some(PartialSignature { signer: pubkey, s })
+    }
+
+    /// Check the [partial signature](PartialSignature) of one actor before it
+    /// is [contributed](SigningSession::contribute) to a session.
+    ///
+    /// A wrong $s_i$, whether from a faulty device or a malicious actor, makes
+    /// the final multisig invalid, and the sum doesn't tell whose $s_i$ was
+    /// wrong. Since $s_i = r_i - p_ic_i$, multiplying by $G$ gives an equation
+    /// in public values only, which whoever collects the partial signatures
+    /// can check for each actor:
+    ///
+    /// $$
+    /// s_iG = R_i - c_iP_i
+    /// $$
+    ///
+    /// The points $R_i$ are the ones exchanged in the first round, listed in
+    /// the same order as the pubkeys, and the challenge $c_i$ is computed from
+    /// their sum $R$ and the message as in
+    /// [`sign_partial`](Self::sign_partial).
+    #[docext]
+    pub fn verify_partial(
+        &mut self,
+        signer: PublicKey<C>,
+        all_pubkeys: &[PublicKey<C>],
+        randomness_points: &[Point<C>],
+        msg: &[u8],
+        partial: &PartialSignature<C>,
+    ) -> Result<(), InvalidSignature> {
+        if partial.signer != signer || randomness_points.len() != all_pubkeys.len() {
+            return Err(InvalidSignature);
+        }
+        let agg = KeyAggContext::sorted(&self.0.hash, all_pubkeys).map_err(|_| InvalidSignature)?;
+        let a = agg.coefficient(signer).ok_or(InvalidSignature)?;
+        let i = all_pubkeys
+            .iter()
+            .position(|&key| key == signer)
+            .ok_or(InvalidSignature)?;
+        let total = randomness_points
+            .iter()
+            .fold(Point::infinity(), |a, &b| a.op(b));
+        let ecc::Coordinates::Finite(x, _) = total.coordinates() else {
+            return Err(InvalidSignature);
+        };
+        let e = super::challenge(&self.0.hash, agg.key, x, msg);
+        let c = a.mul(e, C::N);
+        if ecc::mul_double(partial.s, C::g(), c, signer.point()) == randomness_points[i] {
+            Ok(())
+        } else {
+            Err(InvalidSignature)
+        }
+    }
+
+    /// Run the [first round](MultisigScheme::round1) for the session with the
+    /// given id, after [opening](SessionRegistry::open_with_id) it in the
+    /// signer's registry.
+    ///
+    /// Fails if the key already has too many sessions open, or if it opened a
+    /// session with the same id before, e.g. because a recorded request to
+    /// sign is being replayed.
+    pub fn open_session(
+        &mut self,
+        registry: &mut SessionRegistry<C>,
+        key: &PrivateKey<C>,
+        id: SessionId,
+        rng: &mut impl Csprng,
+    ) -> Result<(RegisteredNonce<C>, <Self as MultisigScheme>::Round1Msg), SessionRejected> {
+        let guard = registry.open_with_id(key.derive(), id)?;
+        let (nonce, msg) = self.round1(key, rng);
+        Ok((RegisteredNonce { id, nonce, guard }, msg))
+    }
+
+    /// Run the [second round](MultisigScheme::round2_with_context) of a
+    /// session opened with [`open_session`](MultiSchnorr::open_session),
+    /// closing the session.
+    pub fn finish_session(
+        &mut self,
+        key: &PrivateKey<C>,
+        nonce: RegisteredNonce<C>,
+        keys: &[PublicKey<C>],
+        round1: &[<Self as MultisigScheme>::Round1Msg],
+        context: &[u8],
+        msg: &[u8],
+    ) -> Result<SignatureShare<C, H>, InvalidRound> {
+        let RegisteredNonce { nonce, guard, .. } = nonce;
+        let share = self.round2_with_context(key, nonce, keys, round1, context, msg);
+        drop(guard);
+        share
+    }
+}
+
+/// The key aggregation of a [Schnorr multisig](MultiSchnorr) or of
+/// [MuSig2](crate::MuSig2), computed once for a list of signers.
+///
+/// Signing and verifying need the coefficients $a_i = H_{agg}(\langle L
+/// \rangle \parallel P_i)$ of the signers and the combined pubkey $\tilde P =
+/// \sum_{i=1}^{n} a_iP_i$. Every coefficient hashes over the encoding $\langle
+/// L \rangle$ of all $n$ pubkeys, and $\tilde P$ needs all $n$ coefficients,
+/// so recomputing them for each of the $n$ actors takes $O(n^2)$ hashing. The
+/// context encodes $\langle L \rangle$ once, derives each coefficient once,
+/// and keeps them along with $\tilde P$, so that any number of signatures by
+/// the same signers costs $n$ coefficient hashes in total.
+///
+/// The pubkeys must be listed in their [canonical
+/// order](PublicKey#impl-Ord-for-PublicKey%3CC%3E), so that every actor builds
+/// the same context. No pubkey may be listed twice: its coefficient would
+/// count twice towards $\tilde P$, while its owner only signs once. A list in
+/// any other order can be [sorted](KeyAggContext::sorted) first.
+///
+/// The context is tied to the hash function $H$, since the coefficients are
+/// derived with it, so it can only be used with a scheme using the same hash.
+#[docext]
+pub struct KeyAggContext<C, H> {
+    /// The pubkeys, in canonical order.
+    pubkeys: Vec<PublicKey<C>>,
+    encoding: [u8; 32],
+    /// The coefficient of each pubkey, in the same order.
+    coefficients: Vec<Num>,
+    key: PublicKey<C>,
+    _hash: PhantomData<H>,
+}
+
+impl<C, H> Clone for KeyAggContext<C, H> {
+    fn clone(&self) -> Self {
+        Self {
+            pubkeys: self.pubkeys.clone(),
+            encoding: self.encoding,
+            coefficients: self.coefficients.clone(),
+            key: self.key,
+            _hash: PhantomData,
+        }
+    }
+}
+
+impl<C: fmt::Debug, H> fmt::Debug for KeyAggContext<C, H> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("KeyAggContext")
+            .field("pubkeys", &self.pubkeys)
+            .field("key", &self.key)
+            .finish()
+    }
+}
+
+impl<C, H, const DIGEST_SIZE: usize> KeyAggContext<C, H>
+where
+    C: Curve,
+    H: Hash<Digest = [u8; DIGEST_SIZE]>,
+{
+    /// Aggregate the given pubkeys, which must be listed in their canonical
+    /// order without duplicates.
+    pub fn new(hash: &H, pubkeys: &[PublicKey<C>]) -> Result<Self, InvalidKeyAgg> {
+        if pubkeys.is_empty() {
+            return Err(InvalidKeyAgg::Empty);
+        }
+        if let Some(i) = pubkeys.windows(2).position(|pair| pair[0] >= pair[1]) {
+            return Err(if pubkeys[i] == pubkeys[i + 1] {
+                InvalidKeyAgg::Duplicate(i + 1)
+            } else {
+                InvalidKeyAgg::Unordered(i + 1)
+            });
+        }
+        let encoding = encode(pubkeys);
+        let transcript = agg_transcript(hash, &encoding);
+        let coefficients = pubkeys
+            .iter()
+            .map(|&key| agg_coefficient(&transcript, key))
+            .collect_vec();
+        let terms = coefficients
+            .iter()
+            .zip(pubkeys)
+            .map(|(&a, key)| (a, key.point()))
+            .collect_vec();
+        let key =
+            PublicKey::new(Point::multi_scale(&terms)).map_err(|_| InvalidKeyAgg::Infinity)?;
+        Ok(Self {
+            pubkeys: pubkeys.to_vec(),
+            encoding,
+            coefficients,
+            key,
+            _hash: PhantomData,
+        })
+    }
+
+    /// Sort the given pubkeys into their canonical order and aggregate them.
+    /// Duplicates are still rejected.
+    pub fn sorted(hash: &H, pubkeys: &[PublicKey<C>]) -> Result<Self, InvalidKeyAgg> {
+        let mut pubkeys = pubkeys.to_vec();
+        pubkeys.sort();
+        Self::new(hash, &pubkeys)
     }
 }
 
-fn h_agg<C: Curve, const DIGEST_SIZE: usize>(
-    hash: &impl Hash<Digest = [u8; DIGEST_SIZE]>,
-    pubkeys: &[PublicKey<C>],
+impl<C: Curve, H> KeyAggContext<C, H> {
+    /// The pubkeys of the signers, in canonical order.
+    pub fn pubkeys(&self) -> &[PublicKey<C>] {
+        &self.pubkeys
+    }
+
+    /// The unique encoding $\langle L \rangle$ of the pubkeys, which every
+    /// coefficient is hashed over.
+    #[docext]
+    pub fn encoding(&self) -> &[u8] {
+        &self.encoding
+    }
+
+    /// The coefficient $a_i = H_{agg}(\langle L \rangle \parallel P_i)$ of the
+    /// given pubkey, or `None` if it isn't one of the signers.
+    #[docext]
+    pub fn coefficient(&self, pubkey: PublicKey<C>) -> Option<Num> {
+        let i = self.pubkeys.binary_search(&pubkey).ok()?;
+        Some(self.coefficients[i])
+    }
+
+    /// The combined pubkey $\tilde P$, which the multisig verifies under as a
+    /// regular [Schnorr signature](Schnorr).
+    #[docext]
+    pub fn aggregate_key(&self) -> PublicKey<C> {
+        self.key
+    }
+}
+
+/// Error returned by [`KeyAggContext::new`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InvalidKeyAgg {
+    /// There are no pubkeys to aggregate.
+    Empty,
+    /// The pubkey at the given index is the same as the one before it.
+    Duplicate(usize),
+    /// The pubkey at the given index comes before the one before it in the
+    /// canonical order.
+    Unordered(usize),
+    /// The combined pubkey is the point at infinity.
+    Infinity,
+}
+
+impl fmt::Display for InvalidKeyAgg {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Empty => write!(f, "no pubkeys to aggregate"),
+            Self::Duplicate(i) => write!(f, "pubkey {i} is listed twice"),
+            Self::Unordered(i) => write!(f, "pubkey {i} is out of canonical order"),
+            Self::Infinity => write!(f, "combined pubkey is infinity"),
+        }
+    }
+}
+
+impl std::error::Error for InvalidKeyAgg {}
+
+/// The transcript of the key aggregation for the given encoding of the
+/// pubkeys, which the coefficients of all signers are forked from.
+fn agg_transcript<H, const DIGEST_SIZE: usize>(hash: H, encoding: &[u8]) -> Transcript<H>
+where
+    H: Hash<Digest = [u8; DIGEST_SIZE]>,
+{
+    let mut transcript = Transcript::new(hash, b"literate-crypto multischnorr aggregation");
+    transcript.append(b"pubkeys", encoding);
+    transcript
+}
+
+/// The coefficient $H_{agg}(\langle L \rangle \parallel P_i)$ of a signer,
+/// forked from the [aggregation transcript](agg_transcript).
+#[docext]
+fn agg_coefficient<C, H, const DIGEST_SIZE: usize>(
+    transcript: &Transcript<H>,
     pubkey: PublicKey<C>,
-) -> Num {
-    Num::from_le_bytes(util::resize(
-        hash.hash(
-            &encode(pubkeys)
-                .into_iter()
-                .chain(pubkey.x().to_le_bytes())
-                .collect_vec(),
-        ),
-    ))
-    .reduce(C::N)
-}
-
-fn h_sig<C: Curve, const DIGEST_SIZE: usize>(
-    hash: &impl Hash<Digest = [u8; DIGEST_SIZE]>,
-    pubkeys: &[PublicKey<C>],
-    randomness: SchnorrRandomness<C>,
+) -> Num
+where
+    C: Curve,
+    H: Hash<Digest = [u8; DIGEST_SIZE]> + Clone,
+{
+    let mut transcript = transcript.clone();
+    transcript.append(b"pubkey", &pubkey.x().to_le_bytes());
+    transcript.challenge_scalar::<C>(b"coefficient")
+}
+
+/// The challenge $H_{sig}(\tilde P \parallel R \parallel m)$, which is the
+/// same as the [challenge](super::challenge) of a regular Schnorr signature by
+/// the combined pubkey, so that the multisig verifies as one.
+#[docext]
+fn h_sig<C: Curve, H, const DIGEST_SIZE: usize>(
+    hash: &H,
+    agg: &KeyAggContext<C, H>,
+    randomness: &SchnorrRandomness<C>,
     msg: &[u8],
-) -> Num {
-    Num::from_le_bytes(util::resize(
-        hash.hash(
-            &combine(hash, pubkeys)
-                .x()
-                .to_le_bytes()
-                .into_iter()
-                .chain(randomness.total.to_le_bytes())
-                .chain(msg.iter().copied())
-                .collect_vec(),
-        ),
-    ))
-    .reduce(C::N)
+) -> Num
+where
+    H: Hash<Digest = [u8; DIGEST_SIZE]>,
+{
+    super::challenge(hash, agg.key, randomness.x(), msg)
 }
 
 /// Before creating a [Schnorr multisig](MultiSchnorr), the actors must each
@@ -235,55 +933,257 @@ fn h_sig<C: Curve, const DIGEST_SIZE: usize>(
 ///    counterpart $R_i = r_iG$, where $G$ is the [generator
 ///    point](crate::ecc::Curve::g) of the underlying [elliptic
 ///    curve](crate::ecc::Curve). He reveals his commitment $t_i = H(R_i)$ to
-///    the other actors, where $H$ is a hash function.
+///    the other actors, where $H$ is a hash function. Both values are computed
+///    by [`commit`](SchnorrRandomness::commit).
 /// 2. After all commitments $t_i$ have been revealed, each actor shares his
 ///    value of $R_i$ and verifies the $R_i$ values of other actors against
-///    their corresponding commitments $t_i$.
+///    their corresponding commitments $t_i$. This is done by
+///    [`new`](SchnorrRandomness::new).
+///
+/// The same two rounds can be run with [`NonceCommitment`], which keeps the
+/// commitment apart from the point: an actor sends his commitment, waits for
+/// everyone else's, then [reveals](SchnorrNonce::reveal) his $R_i$ and
+/// combines the reveals of the others with
+/// [`from_reveals`](SchnorrRandomness::from_reveals).
 ///
 /// This two-round protocol serves to prevent any actor from maliciously
 /// changing his secret number $r_i$ based on the $R_i$ values of other actors.
+///
+/// The aggregate $R = \sum_{i=1}^{n} R_i$ is kept as a full point rather than
+/// just its x-coordinate, since $R$ and $-R$ share the same x-coordinate and
+/// can't be told apart without the y-coordinate.
+///
+/// The [`Debug`](fmt::Debug) output only shows a
+/// [fingerprint](crate::key_fingerprint) of the secret number $r_i$, since
+/// anyone who learns it can recover the private key from the signature. For
+/// the same reason $r_i$ is wiped when the randomness is dropped.
 #[docext]
-#[derive(Debug)]
 pub struct SchnorrRandomness<C> {
     local: Num,
-    total: Num,
-    _curve: PhantomData<C>,
+    total: Point<C>,
+}
+
+impl<C: fmt::Debug> fmt::Debug for SchnorrRandomness<C> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SchnorrRandomness")
+            .field("local", &util::Redacted(&self.local.to_be_bytes()))
+            .field("total", &self.total)
+            .finish()
+    }
 }
 
 impl<C> Clone for SchnorrRandomness<C> {
     fn clone(&self) -> Self {
-        *self
+        Self {
+            local: self.local,
+            total: self.total,
+        }
     }
 }
 
-impl<C> Copy for SchnorrRandomness<C> {}
+impl<C> Drop for SchnorrRandomness<C> {
+    fn drop(&mut self) {
+        self.local.wipe();
+    }
+}
 
 impl<C: Curve> SchnorrRandomness<C> {
-    pub fn new(local: Num, others: &[Point<C>]) -> Result<Self, InvalidSchnorrRandomness> {
-        let total = others.iter().fold(local * C::g(), |a, b| a + *b);
-        match total.coordinates() {
-            ecc::Coordinates::Infinity => Err(InvalidSchnorrRandomness),
-            ecc::Coordinates::Finite(x, _) => Ok(Self {
-                local,
-                total: x,
-                _curve: Default::default(),
-            }),
+    /// Compute the public counterpart $R_i = r_iG$ of the local secret number
+    /// $r_i$, along with the commitment $t_i = H(R_i)$ which is revealed to
+    /// the other actors in the first round.
+    #[docext]
+    pub fn commit<H: Hash>(hash: &H, local: Num) -> (Point<C>, H::Digest) {
+        let point = local * C::g();
+        (point, commitment(hash, point))
+    }
+
+    /// Combine the local secret number with the $R_i$ values of the other
+    /// actors, verifying each of them against its commitment $t_i$. Each $R_i$
+    /// must also be a finite point in the
+    /// [subgroup](crate::ecc::Point::in_subgroup) generated by $G$.
+    #[docext]
+    pub fn new<H: Hash>(
+        hash: &H,
+        local: Num,
+        others: &[(Point<C>, H::Digest)],
+    ) -> Result<Self, InvalidSchnorrRandomness>
+    where
+        H::Digest: PartialEq,
+    {
+        if local == Num::ZERO || local >= C::N {
+            return Err(InvalidSchnorrRandomness::InvalidLocal);
+        }
+        if let Some(i) = others
+            .iter()
+            .position(|(point, _)| *point == Point::infinity() || !point.in_subgroup())
+        {
+            return Err(InvalidSchnorrRandomness::InvalidPoint(i));
+        }
+        if let Some(i) = others
+            .iter()
+            .position(|(point, t)| commitment(hash, *point) != *t)
+        {
+            return Err(InvalidSchnorrRandomness::CommitmentMismatch(i));
+        }
+        let total = others.iter().fold(local * C::g(), |a, &(b, _)| a.op(b));
+        if total == Point::infinity() {
+            return Err(InvalidSchnorrRandomness::Infinity);
+        }
+        Ok(Self { local, total })
+    }
+
+    /// Combine the local nonce with the $R_i$ values revealed by the other
+    /// actors, verifying each of them against the
+    /// [commitment](NonceCommitment) which the same actor sent before anything
+    /// was revealed. The commitments and the reveals are listed in the same
+    /// order, and there must be exactly one reveal for every commitment.
+    /// Otherwise the checks are the same as in [`new`](SchnorrRandomness::new),
+    /// which does the verification.
+    #[docext]
+    pub fn from_reveals<H, const DIGEST_SIZE: usize>(
+        hash: &H,
+        local: SchnorrNonce<C>,
+        commitments: &[NonceCommitment<C, H>],
+        reveals: &[Point<C>],
+    ) -> Result<Self, InvalidSchnorrRandomness>
+    where
+        H: Hash<Digest = [u8; DIGEST_SIZE]>,
+    {
+        if commitments.len() != reveals.len() {
+            return Err(InvalidSchnorrRandomness::RevealCount {
+                commitments: commitments.len(),
+                reveals: reveals.len(),
+            });
+        }
+        let others = reveals
+            .iter()
+            .zip(commitments)
+            .map(|(&point, t)| (point, t.digest))
+            .collect_vec();
+        Self::new(hash, local.local, &others)
+    }
+
+    /// The local secret number $r_i$, for debugging only.
+    #[cfg(feature = "insecure-reveal-secrets")]
+    #[docext]
+    pub fn reveal_secret_for_debug(&self) -> Num {
+        self.local
+    }
+
+    /// The aggregate $R = \sum_{i=1}^{n} R_i$.
+    #[docext]
+    pub fn total(&self) -> Point<C> {
+        self.total
+    }
+
+    /// The x-coordinate of the [aggregate](SchnorrRandomness::total), which is
+    /// what gets hashed into the signature.
+    pub fn x(&self) -> Num {
+        match self.total.coordinates() {
+            ecc::Coordinates::Finite(x, _) => x,
+            ecc::Coordinates::Infinity => unreachable!("aggregate is never infinity"),
         }
     }
 }
 
-/// Combine multiple pubkeys into a single multisig pubkey.
-fn combine<C: Curve, const DIGEST_SIZE: usize>(
-    hash: &impl Hash<Digest = [u8; DIGEST_SIZE]>,
-    keys: &[PublicKey<C>],
-) -> PublicKey<C> {
-    PublicKey::new(
-        keys.iter()
-            .map(|&key| h_agg(hash, keys, key) * key.point())
-            .reduce(|a, b| a + b)
-            .unwrap(),
-    )
-    .unwrap()
+/// The commitment $t_i = H(R_i)$ of an actor to his
+/// [nonce](SchnorrNonce), exchanged in the first step of the two-round
+/// protocol described in [`SchnorrRandomness`].
+///
+/// An actor [commits](NonceCommitment::commit) and sends the commitment to
+/// everyone else, but keeps $R_i$ to himself until he has received the
+/// commitments of all other actors. Only then does he
+/// [reveal](SchnorrNonce::reveal) $R_i$, and the revealed points are checked
+/// against the commitments received earlier by
+/// [`from_reveals`](SchnorrRandomness::from_reveals). By the time anyone sees
+/// the $R_i$ of the others, his own is already fixed.
+#[docext]
+pub struct NonceCommitment<C, H: Hash> {
+    digest: H::Digest,
+    _curve: PhantomData<C>,
+}
+
+impl<C, H, const DIGEST_SIZE: usize> Clone for NonceCommitment<C, H>
+where
+    H: Hash<Digest = [u8; DIGEST_SIZE]>,
+{
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<C, H, const DIGEST_SIZE: usize> Copy for NonceCommitment<C, H> where
+    H: Hash<Digest = [u8; DIGEST_SIZE]>
+{
+}
+
+impl<C, H, const DIGEST_SIZE: usize> PartialEq for NonceCommitment<C, H>
+where
+    H: Hash<Digest = [u8; DIGEST_SIZE]>,
+{
+    fn eq(&self, other: &Self) -> bool {
+        self.digest == other.digest
+    }
+}
+
+impl<C, H, const DIGEST_SIZE: usize> Eq for NonceCommitment<C, H> where
+    H: Hash<Digest = [u8; DIGEST_SIZE]>
+{
+}
+
+impl<C, H, const DIGEST_SIZE: usize> fmt::Debug for NonceCommitment<C, H>
+where
+    H: Hash<Digest = [u8; DIGEST_SIZE]>,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("NonceCommitment")
+            .field(&self.digest)
+            .finish()
+    }
+}
+
+impl<C, H, const DIGEST_SIZE: usize> NonceCommitment<C, H>
+where
+    C: Curve,
+    H: Hash<Digest = [u8; DIGEST_SIZE]>,
+{
+    /// Pick a secret random number $r_i$ and commit to its public counterpart
+    /// $R_i$. The commitment can be sent right away, while the nonce is kept
+    /// until it is [revealed](SchnorrNonce::reveal).
+    #[docext]
+    pub fn commit(hash: &H, rng: &mut impl Csprng) -> (SchnorrNonce<C>, Self) {
+        let local = PrivateKey::<C>::random(rng).0;
+        let (_, digest) = SchnorrRandomness::<C>::commit(hash, local);
+        let nonce = SchnorrNonce {
+            local,
+            _curve: PhantomData,
+        };
+        (nonce, Self::new(digest))
+    }
+
+    /// Construct the commitment of another actor, e.g. after receiving it
+    /// from the network.
+    pub fn new(digest: [u8; DIGEST_SIZE]) -> Self {
+        Self {
+            digest,
+            _curve: PhantomData,
+        }
+    }
+
+    pub fn digest(&self) -> [u8; DIGEST_SIZE] {
+        self.digest
+    }
+}
+
+/// Compute the commitment $H(R_i)$, hashing over both coordinates of $R_i$.
+#[docext]
+fn commitment<C: Curve, H: Hash>(hash: &H, point: Point<C>) -> H::Digest {
+    match point.coordinates() {
+        ecc::Coordinates::Infinity => hash.hash(&[]),
+        ecc::Coordinates::Finite(x, y) => {
+            canonical::hash_fields(&hash, &[&x.to_le_bytes(), &y.to_le_bytes()])
+        }
+    }
 }
 
 /// Encode multiple pubkeys into a unique binary representation.
@@ -294,19 +1194,51 @@ fn encode<C: Curve>(keys: &[PublicKey<C>]) -> [u8; 32] {
         .to_le_bytes()
 }
 
-#[derive(Debug)]
-pub struct InvalidSchnorrRandomness;
+/// Error returned by [`SchnorrRandomness::new`] and
+/// [`SchnorrRandomness::from_reveals`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InvalidSchnorrRandomness {
+    /// The local secret number is zero or not reduced modulo
+    /// [`N`](crate::ecc::Curve::N).
+    InvalidLocal,
+    /// The public randomness of the other actor at the given index is the
+    /// point at infinity or lies outside of the
+    /// [subgroup](crate::ecc::Point::in_subgroup) generated by the generator
+    /// point.
+    InvalidPoint(usize),
+    /// The public randomness of the other actor at the given index doesn't
+    /// match his commitment.
+    CommitmentMismatch(usize),
+    /// The number of revealed points differs from the number of
+    /// [commitments](NonceCommitment) received earlier.
+    RevealCount { commitments: usize, reveals: usize },
+    /// The aggregate public randomness is the point at infinity.
+    Infinity,
+}
 
 impl fmt::Display for InvalidSchnorrRandomness {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(
-            f,
-            "invalid randomness (bad luck, regenerate local randomness)"
-        )
+        match self {
+            Self::InvalidLocal => write!(f, "invalid local randomness"),
+            Self::InvalidPoint(i) => write!(f, "randomness of actor {i} is not a valid point"),
+            Self::CommitmentMismatch(i) => {
+                write!(f, "randomness of actor {i} doesn't match commitment")
+            }
+            Self::RevealCount {
+                commitments,
+                reveals,
+            } => write!(f, "{reveals} reveals for {commitments} commitments"),
+            Self::Infinity => write!(
+                f,
+                "aggregate randomness is infinity (bad luck, regenerate local randomness)"
+            ),
+        }
     }
 }
 
-impl<C, H, R: Csprng> fmt::Debug for MultiSchnorr<C, H, R> {
+impl std::error::Error for InvalidSchnorrRandomness {}
+
+impl<C, H> fmt::Debug for MultiSchnorr<C, H> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         f.debug_tuple("MultiSchnorr").finish()
     }
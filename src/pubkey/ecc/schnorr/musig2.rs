@@ -0,0 +1,358 @@
+use {
+    super::multisig::{aggregate_shares, KeyAggContext},
+    crate::{
+        ecc::{Coordinates, Curve, Num, Point, PrivateKey, PublicKey, Transcript},
+        pubkey,
+        util,
+        Csprng,
+        Hash,
+        InvalidRound,
+        InvalidSchnorrRandomness,
+        InvalidSignature,
+        MultisigScheme,
+        Schnorr,
+        SchnorrSignature,
+        SignatureScheme,
+        SignatureShare,
+    },
+    docext::docext,
+    std::{fmt, marker::PhantomData},
+};
+
+/// A two-round [multisig scheme](crate::MultisigScheme) based on [Schnorr
+/// signatures](crate::Schnorr), known as MuSig2.
+///
+/// [`MultiSchnorr`](crate::MultiSchnorr) needs an extra round before signing:
+/// every actor commits to his $R_i$ before anyone reveals theirs. Without the
+/// commitments, a malicious actor who sees the $R_i$ values of the others
+/// first can pick his own $R_j$ based on them. By running many signing
+/// sessions concurrently and choosing his $R_j$ in each one cleverly, he can
+/// combine the resulting signatures into a signature for a message nobody
+/// agreed to sign.
+///
+/// MuSig2 removes the commitment round by having every actor pick _two_
+/// secret random numbers $k_{1,i}, k_{2,i}$. In the first round, each actor
+/// broadcasts $R_{1,i} = k_{1,i}G$ and $R_{2,i} = k_{2,i}G$, where $G$ is the
+/// [generator point](crate::ecc::Curve::g) of the underlying [elliptic
+/// curve](crate::ecc::Curve). Once all of them have arrived, every actor
+/// computes
+///
+/// $$
+/// R_1 = \sum_{i=1}^{n} R_{1,i} \\
+/// R_2 = \sum_{i=1}^{n} R_{2,i} \\
+/// b = H_{non}(\tilde P \parallel R_1 \parallel R_2 \parallel m) \\
+/// R = R_1 + bR_2
+/// $$
+///
+/// where $\tilde P$ is the combined pubkey, computed exactly as for
+/// [`MultiSchnorr`](crate::MultiSchnorr) with the coefficients $a_i =
+/// H_{agg}(\langle L \rangle \parallel P_i)$. $R$ is the effective nonce of the
+/// multisig. The binding value $b$ depends on the nonces of all actors, so if
+/// the malicious actor changes his $R_{1,j}$ or $R_{2,j}$, $b$ changes as
+/// well, and $R$ changes unpredictably. He can no longer steer $R$ to a
+/// value of his choosing, which is what the attack above relies on.
+///
+/// In the second round, each actor computes the challenge $e = H_{sig}(\tilde
+/// P \parallel R \parallel m)$ and his individual signature
+///
+/// $$
+/// s_i = k_{1,i} + bk_{2,i} - ea_ip_i
+/// $$
+///
+/// The multisig is $(s, e)$ where $s = \sum_{i=1}^{n} s_i$. It verifies as a
+/// regular Schnorr signature by $\tilde P$:
+///
+/// $$
+/// sG + e\tilde P = \sum_{i=1}^{n} (k_{1,i} + bk_{2,i})G - e\sum_{i=1}^{n}
+/// a_ip_iG + e\tilde P \\
+/// sG + e\tilde P = R_1 + bR_2 - e\tilde P + e\tilde P \\
+/// sG + e\tilde P = R
+/// $$
+///
+/// Each individual signature can also be checked on its own, with
+/// [`verify_partial`](MuSig2::verify_partial), since $s_iG + ea_iP_i =
+/// R_{1,i} + bR_{2,i}$. This identifies an actor who sends a bad $s_i$,
+/// whereas a bad multisig alone only shows that _somebody_ misbehaved.
+///
+/// Like $R$ in [`MultiSchnorr`](crate::MultiSchnorr), none of these values
+/// depend on the order of the actors, so the messages of either round can be
+/// passed in any order.
+#[docext]
+pub struct MuSig2<C, H>(Schnorr<C, H>);
+
+impl<C: Curve, H> MuSig2<C, H> {
+    pub fn new(curve: C, hash: H) -> Self {
+        Self(Schnorr::new(curve, hash))
+    }
+}
+
+impl<C, H, const DIGEST_SIZE: usize> MuSig2<C, H>
+where
+    C: Curve,
+    H: Hash<Digest = [u8; DIGEST_SIZE]>,
+{
+    /// The combined pubkey $\tilde P$, which the multisig verifies under as a
+    /// regular [Schnorr signature](Schnorr).
+    ///
+    /// # Panics
+    ///
+    /// If the list of pubkeys is empty or lists a pubkey twice, see
+    /// [`KeyAggContext::new`].
+    #[docext]
+    pub fn aggregate_key(&self, keys: &[PublicKey<C>]) -> PublicKey<C> {
+        KeyAggContext::sorted(&self.0.hash, keys)
+            .expect("a multisig needs distinct signers")
+            .aggregate_key()
+    }
+
+    /// Check the individual signature $s_i$ of a single actor under the given
+    /// [context](SignatureScheme#contexts), against the first-round messages
+    /// of all actors.
+    #[docext]
+    pub fn verify_partial(
+        &self,
+        agg: &KeyAggContext<C, H>,
+        round1: &[MuSig2PublicNonce<C>],
+        context: &[u8],
+        msg: &[u8],
+        share: &SignatureShare<C, H>,
+    ) -> Result<(), InvalidSignature> {
+        let (b, e) = self
+            .binding(agg, round1, &pubkey::with_context(context, msg))
+            .map_err(|_| InvalidSignature)?;
+        let nonce = round1
+            .iter()
+            .find(|n| n.signer == share.signer)
+            .ok_or(InvalidSignature)?;
+        if share.e != e || share.s >= C::N {
+            return Err(InvalidSignature);
+        }
+        let a = agg.coefficient(share.signer).ok_or(InvalidSignature)?;
+        let c = a.mul(e, C::N);
+        if share.s * C::g() + c * share.signer.point() == nonce.r1 + b * nonce.r2 {
+            Ok(())
+        } else {
+            Err(InvalidSignature)
+        }
+    }
+
+    /// Validate the first-round messages and compute the binding value $b$
+    /// along with the challenge $e$.
+    #[docext]
+    fn binding(
+        &self,
+        agg: &KeyAggContext<C, H>,
+        round1: &[MuSig2PublicNonce<C>],
+        msg: &[u8],
+    ) -> Result<(Num, Num), InvalidRound> {
+        let keys = agg.pubkeys();
+        let complete = round1.len() == keys.len()
+            && keys
+                .iter()
+                .all(|&key| round1.iter().filter(|n| n.signer == key).count() == 1);
+        if !complete {
+            return Err(InvalidRound::MissingNonce);
+        }
+        if let Some(i) = round1.iter().position(|n| {
+            [n.r1, n.r2]
+                .iter()
+                .any(|p| *p == Point::infinity() || !p.in_subgroup())
+        }) {
+            return Err(InvalidRound::Randomness(
+                InvalidSchnorrRandomness::InvalidPoint(i),
+            ));
+        }
+
+        let r1 = round1.iter().fold(Point::infinity(), |a, n| a + n.r1);
+        let r2 = round1.iter().fold(Point::infinity(), |a, n| a + n.r2);
+        let key = agg.aggregate_key();
+        let mut transcript = Transcript::new(&self.0.hash, b"literate-crypto musig2 nonce");
+        transcript.append(b"pubkey", &key.x().to_le_bytes());
+        transcript.append(b"r1", &encode(r1));
+        transcript.append(b"r2", &encode(r2));
+        transcript.append(b"message", msg);
+        let b = transcript.challenge_scalar::<C>(b"binding");
+
+        match (r1 + b * r2).coordinates() {
+            Coordinates::Infinity => {
+                Err(InvalidRound::Randomness(InvalidSchnorrRandomness::Infinity))
+            }
+            Coordinates::Finite(x, _) => Ok((b, super::challenge(&self.0.hash, key, x, msg))),
+        }
+    }
+}
+
+impl<C, H, const DIGEST_SIZE: usize> MultisigScheme for MuSig2<C, H>
+where
+    C: Curve,
+    H: Hash<Digest = [u8; DIGEST_SIZE]>,
+{
+    type PublicKey = PublicKey<C>;
+    type PrivateKey = PrivateKey<C>;
+    type Multisig = SchnorrSignature<C, H>;
+    type Nonce = MuSig2Nonce<C>;
+    type Round1Msg = MuSig2PublicNonce<C>;
+    type Round2Msg = SignatureShare<C, H>;
+    type RoundError = InvalidRound;
+
+    fn round1(
+        &mut self,
+        key: &Self::PrivateKey,
+        rng: &mut impl Csprng,
+    ) -> (Self::Nonce, Self::Round1Msg) {
+        let k1 = PrivateKey::<C>::random(rng).0;
+        let k2 = PrivateKey::<C>::random(rng).0;
+        let public = MuSig2PublicNonce {
+            signer: key.derive(),
+            r1: k1 * C::g(),
+            r2: k2 * C::g(),
+        };
+        (MuSig2Nonce { k1, k2, public }, public)
+    }
+
+    fn round2_with_context(
+        &mut self,
+        key: &Self::PrivateKey,
+        nonce: Self::Nonce,
+        keys: &[Self::PublicKey],
+        round1: &[Self::Round1Msg],
+        context: &[u8],
+        msg: &[u8],
+    ) -> Result<Self::Round2Msg, Self::RoundError> {
+        let signer = key.derive();
+        if !keys.contains(&signer) {
+            return Err(InvalidRound::UnknownSigner);
+        }
+        if nonce.public.signer != signer || !round1.contains(&nonce.public) {
+            return Err(InvalidRound::MissingNonce);
+        }
+        let agg = KeyAggContext::sorted(&self.0.hash, keys).map_err(InvalidRound::KeyAgg)?;
+        let (b, e) = self.binding(&agg, round1, &pubkey::with_context(context, msg))?;
+        let a = agg.coefficient(signer).ok_or(InvalidRound::UnknownSigner)?;
+        let c = a.mul(e, C::N);
+        let s = nonce
+            .k1
+            .add(b.mul(nonce.k2, C::N), C::N)
+            .sub(key.0.mul(c, C::N), C::N);
+        Ok(SignatureShare {
+            signer,
+            s,
+            e,
+            _hash: PhantomData,
+        })
+    }
+
+    fn aggregate(
+        &self,
+        keys: &[Self::PublicKey],
+        round2: &[Self::Round2Msg],
+    ) -> Result<Self::Multisig, InvalidSignature> {
+        aggregate_shares(keys, round2)
+    }
+
+    fn verify_with_context(
+        &mut self,
+        keys: &[Self::PublicKey],
+        context: &[u8],
+        msg: &[u8],
+        sig: &Self::Multisig,
+    ) -> Result<(), InvalidSignature> {
+        let agg = KeyAggContext::sorted(&self.0.hash, keys).map_err(|_| InvalidSignature)?;
+        self.0
+            .verify_with_context(agg.aggregate_key(), context, msg, sig)
+    }
+}
+
+/// The secret random numbers $k_{1,i}, k_{2,i}$ of an actor, picked in the
+/// [first round](MultisigScheme::round1) of [MuSig2](MuSig2).
+///
+/// Like [`SchnorrNonce`](crate::SchnorrNonce), the nonce can't be cloned and
+/// is consumed by the [second round](MultisigScheme::round2). The numbers
+/// are wiped when the nonce is dropped. Its
+/// [`Debug`](fmt::Debug) output only shows
+/// [fingerprints](crate::key_fingerprint).
+#[docext]
+pub struct MuSig2Nonce<C> {
+    k1: Num,
+    k2: Num,
+    public: MuSig2PublicNonce<C>,
+}
+
+impl<C> Drop for MuSig2Nonce<C> {
+    fn drop(&mut self) {
+        self.k1.wipe();
+        self.k2.wipe();
+    }
+}
+
+impl<C: fmt::Debug> fmt::Debug for MuSig2Nonce<C> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("MuSig2Nonce")
+            .field("k1", &util::Redacted(&self.k1.to_be_bytes()))
+            .field("k2", &util::Redacted(&self.k2.to_be_bytes()))
+            .field("public", &self.public)
+            .finish()
+    }
+}
+
+/// The public nonces $R_{1,i}, R_{2,i}$ which an actor broadcasts in the
+/// [first round](MultisigScheme::round1) of [MuSig2](MuSig2), along with his
+/// pubkey.
+#[docext]
+#[derive(Debug)]
+pub struct MuSig2PublicNonce<C> {
+    signer: PublicKey<C>,
+    r1: Point<C>,
+    r2: Point<C>,
+}
+
+impl<C> Clone for MuSig2PublicNonce<C> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<C> Copy for MuSig2PublicNonce<C> {}
+
+impl<C> PartialEq for MuSig2PublicNonce<C> {
+    fn eq(&self, other: &Self) -> bool {
+        self.signer == other.signer && self.r1 == other.r1 && self.r2 == other.r2
+    }
+}
+
+impl<C> Eq for MuSig2PublicNonce<C> {}
+
+impl<C> MuSig2PublicNonce<C> {
+    /// Construct the first-round message of the actor with the given pubkey.
+    /// The points are validated by the [second round](MultisigScheme::round2).
+    pub fn new(signer: PublicKey<C>, r1: Point<C>, r2: Point<C>) -> Self {
+        Self { signer, r1, r2 }
+    }
+
+    pub fn signer(&self) -> PublicKey<C> {
+        self.signer
+    }
+
+    pub fn r1(&self) -> Point<C> {
+        self.r1
+    }
+
+    pub fn r2(&self) -> Point<C> {
+        self.r2
+    }
+}
+
+/// Encode a point for hashing. The aggregate nonces can be infinity, which is
+/// encoded as the empty string.
+fn encode<C: Curve>(point: Point<C>) -> Vec<u8> {
+    match point.coordinates() {
+        Coordinates::Infinity => Vec::new(),
+        Coordinates::Finite(x, y) => [x.to_le_bytes(), y.to_le_bytes()].concat(),
+    }
+}
+
+impl<C, H> fmt::Debug for MuSig2<C, H> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("MuSig2").finish()
+    }
+}
@@ -0,0 +1,231 @@
+use {
+    crate::{
+        ecc::{Curve, PublicKey},
+        Csprng,
+    },
+    std::{
+        collections::HashSet,
+        fmt,
+        sync::{
+            atomic::{AtomicUsize, Ordering},
+            Arc,
+        },
+    },
+};
+
+/// The identifier of a signing session, picked at random by whoever starts
+/// the session and sent along with its round messages.
+///
+/// A signer [opens](SessionRegistry::open_with_id) every session under its id,
+/// and the [registry](SessionRegistry) refuses to open the same id twice for
+/// the same key. So a round message which an attacker recorded and replays
+/// later can't make the signer sign again with a fresh nonce for the same
+/// session.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct SessionId([u8; SessionId::SIZE]);
+
+impl SessionId {
+    pub const SIZE: usize = 16;
+
+    /// Pick a random id using the given source of randomness.
+    pub fn random(rng: &mut impl Csprng) -> Self {
+        let mut id = [0; Self::SIZE];
+        rng.fill_bytes(&mut id);
+        Self(id)
+    }
+
+    pub fn from_bytes(bytes: [u8; Self::SIZE]) -> Self {
+        Self(bytes)
+    }
+
+    pub fn to_bytes(self) -> [u8; Self::SIZE] {
+        self.0
+    }
+}
+
+/// The signer's record of its signing sessions, shared by all of the signing
+/// protocols which keep a secret nonce between rounds.
+///
+/// The registry enforces two rules for each key:
+///
+/// - At most [`max_sessions`](SessionRegistry::max_sessions) sessions are open
+///   at the same time. Running many sessions concurrently lets an attacker pick
+///   his messages after seeing all of the signer's nonces, see [concurrent
+///   sessions](crate::BlindSchnorr#concurrent-sessions).
+/// - Every [session id](SessionId) is opened at most once, even after the
+///   session is closed. A session which was aborted halfway may already have
+///   leaked its nonce commitment, so it can't be resumed either.
+///
+/// A session is open for as long as the [guard](SessionGuard) returned when
+/// opening it is alive.
+pub struct SessionRegistry<C> {
+    max_sessions: usize,
+    keys: Vec<KeySessions<C>>,
+}
+
+/// The sessions of a single key.
+struct KeySessions<C> {
+    key: PublicKey<C>,
+    open: Arc<AtomicUsize>,
+    used: HashSet<SessionId>,
+}
+
+impl<C> SessionRegistry<C> {
+    /// Allow up to the given number of concurrently open sessions per key.
+    pub fn new(max_sessions: usize) -> Self {
+        Self {
+            max_sessions,
+            keys: Vec::new(),
+        }
+    }
+
+    pub fn with_max_sessions(self, max_sessions: usize) -> Self {
+        Self {
+            max_sessions,
+            ..self
+        }
+    }
+
+    pub fn max_sessions(&self) -> usize {
+        self.max_sessions
+    }
+}
+
+impl<C: Curve> SessionRegistry<C> {
+    /// The number of open sessions of the given key.
+    pub fn open_sessions(&self, key: PublicKey<C>) -> usize {
+        self.find(key)
+            .map_or(0, |sessions| sessions.open.load(Ordering::SeqCst))
+    }
+
+    /// Whether a session with the given id was ever opened for the given key.
+    pub fn is_used(&self, key: PublicKey<C>, id: SessionId) -> bool {
+        self.find(key)
+            .is_some_and(|sessions| sessions.used.contains(&id))
+    }
+
+    /// Open a session without an id, which only counts towards the cap on
+    /// concurrent sessions.
+    pub fn open(&mut self, key: PublicKey<C>) -> Result<SessionGuard, TooManySessions> {
+        let sessions = self.find_or_insert(key);
+        let open = sessions.open.clone();
+        Self::reserve(&open, self.max_sessions)?;
+        Ok(SessionGuard { open, id: None })
+    }
+
+    /// Open a session with the given id. Fails if the key already has the
+    /// maximum number of sessions open, or if the id was used by the key
+    /// before.
+    pub fn open_with_id(
+        &mut self,
+        key: PublicKey<C>,
+        id: SessionId,
+    ) -> Result<SessionGuard, SessionRejected> {
+        let max_sessions = self.max_sessions;
+        let sessions = self.find_or_insert(key);
+        if sessions.used.contains(&id) {
+            return Err(SessionRejected::Replayed(id));
+        }
+        Self::reserve(&sessions.open, max_sessions)?;
+        sessions.used.insert(id);
+        Ok(SessionGuard {
+            open: sessions.open.clone(),
+            id: Some(id),
+        })
+    }
+
+    fn find(&self, key: PublicKey<C>) -> Option<&KeySessions<C>> {
+        self.keys.iter().find(|sessions| sessions.key == key)
+    }
+
+    fn find_or_insert(&mut self, key: PublicKey<C>) -> &mut KeySessions<C> {
+        match self.keys.iter().position(|sessions| sessions.key == key) {
+            Some(i) => &mut self.keys[i],
+            None => {
+                self.keys.push(KeySessions {
+                    key,
+                    open: Arc::new(AtomicUsize::new(0)),
+                    used: HashSet::new(),
+                });
+                self.keys.last_mut().unwrap()
+            }
+        }
+    }
+
+    fn reserve(open: &AtomicUsize, max_sessions: usize) -> Result<(), TooManySessions> {
+        if open.load(Ordering::SeqCst) >= max_sessions {
+            return Err(TooManySessions);
+        }
+        open.fetch_add(1, Ordering::SeqCst);
+        Ok(())
+    }
+}
+
+impl<C> fmt::Debug for SessionRegistry<C> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SessionRegistry")
+            .field("max_sessions", &self.max_sessions)
+            .finish_non_exhaustive()
+    }
+}
+
+/// An open session in a [registry](SessionRegistry), which closes when the
+/// guard is dropped. Dropping a session without finishing it aborts it: its
+/// id stays used.
+#[derive(Debug)]
+pub struct SessionGuard {
+    open: Arc<AtomicUsize>,
+    id: Option<SessionId>,
+}
+
+impl SessionGuard {
+    /// The id the session was opened with, if any.
+    pub fn id(&self) -> Option<SessionId> {
+        self.id
+    }
+}
+
+impl Drop for SessionGuard {
+    fn drop(&mut self) {
+        self.open.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+/// Error returned when the key already has the maximum number of [concurrent
+/// sessions](SessionRegistry) open.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TooManySessions;
+
+impl fmt::Display for TooManySessions {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "too many concurrent signing sessions")
+    }
+}
+
+impl std::error::Error for TooManySessions {}
+
+/// Error returned by [`SessionRegistry::open_with_id`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SessionRejected {
+    /// The key already has the maximum number of sessions open.
+    TooManySessions,
+    /// The key already opened a session with this id.
+    Replayed(SessionId),
+}
+
+impl From<TooManySessions> for SessionRejected {
+    fn from(_: TooManySessions) -> Self {
+        Self::TooManySessions
+    }
+}
+
+impl fmt::Display for SessionRejected {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::TooManySessions => write!(f, "{TooManySessions}"),
+            Self::Replayed(_) => write!(f, "session id was already used"),
+        }
+    }
+}
+
+impl std::error::Error for SessionRejected {}
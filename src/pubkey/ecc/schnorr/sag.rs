@@ -1,15 +1,16 @@
 use {
     crate::{
-        ecc::{Coordinates, Curve, Num, PrivateKey, PublicKey},
+        codec::{CodecError, Reader, Writer},
+        ecc::{self, Coordinates, Curve, Num, PrivateKey, PublicKey, Transcript},
+        pubkey,
         uniform_random,
-        util::{self, CollectVec},
         Csprng,
         Hash,
         InvalidSignature,
         RingScheme,
     },
     docext::docext,
-    std::array,
+    std::fmt,
 };
 
 /// Spontaneous anonymous group signatures.
@@ -31,7 +32,9 @@ use {
 /// where $H$ is a [hash function](crate::Hash), $\langle L \rangle$ is a unique
 /// encoding  of the pubkeys $(P_1, P_2, \dots, P_n)$, $m$ is the message to be
 /// signed, and $G$ is the [elliptic curve generator
-/// point](crate::ecc::Curve::g).
+/// point](crate::ecc::Curve::g). In this implementation $\langle L \rangle$
+/// and $m$ are appended to a [transcript](crate::Transcript) once, and every
+/// challenge is derived from a fork of it.
 ///
 /// The remaining values $c_2, c_3, \dots, c_n$ are calculated as
 ///
@@ -78,51 +81,52 @@ use {
 /// The scheme works because it's impossible to form a ring without knowing one
 /// private key, while it is unnecessary to know any of the other private keys.
 #[docext]
-pub struct SchnorrSag<C, H, R: Csprng> {
+pub struct SchnorrSag<C, H> {
     _curve: C,
     hash: H,
-    rng: R::IntoIter,
 }
 
-impl<C, H, R: Csprng> SchnorrSag<C, H, R> {
-    pub fn new(curve: C, hash: H, rng: R) -> Self {
+impl<C: Curve, H> SchnorrSag<C, H> {
+    pub fn new(curve: C, hash: H) -> Self {
+        debug_assert_eq!(C::validate(), Ok(()), "invalid curve parameters");
         Self {
             _curve: curve,
             hash,
-            rng: rng.into_iter(),
         }
     }
 }
 
-impl<C, H, R, const DIGEST_SIZE: usize> RingScheme for SchnorrSag<C, H, R>
+impl<C, H, const DIGEST_SIZE: usize> RingScheme for SchnorrSag<C, H>
 where
     C: Curve,
     H: Hash<Digest = [u8; DIGEST_SIZE]>,
-    R: Csprng,
 {
     type RingSignature = SchnorrSagSignature<C>;
     type PublicKey = PublicKey<C>;
     type PrivateKey = PrivateKey<C>;
 
-    fn sign(
+    fn sign_with_context(
         &mut self,
         key: Self::PrivateKey,
         decoys: &[Self::PublicKey],
+        context: &[u8],
         msg: &[u8],
+        rng: &mut impl Csprng,
     ) -> Self::RingSignature {
-        assert!(DIGEST_SIZE >= C::SIZE);
+        let msg = pubkey::with_context(context, msg);
 
         let mut pubkeys = decoys.to_vec();
         pubkeys.push(key.derive());
 
+        let g = C::g();
         let l = encode(&pubkeys);
 
         // Generate a random number alpha and multiply the generator point by it.
         let mut alpha;
         let x0;
         'retry: loop {
-            alpha = Num::from_le_bytes(array::from_fn(|_| self.rng.next().unwrap()));
-            x0 = match (alpha * C::g()).coordinates() {
+            alpha = Num::random_mod(rng, C::N);
+            x0 = match C::mul_g(alpha).coordinates() {
                 Coordinates::Finite(x, _) => x,
                 Coordinates::Infinity => continue 'retry,
             };
@@ -130,16 +134,8 @@ where
         }
 
         // Generate the initial c value to start the ring.
-        let mut c = vec![Num::from_le_bytes(util::resize(
-            self.hash.hash(
-                &l.iter()
-                    .copied()
-                    .chain(msg.iter().copied())
-                    .chain(x0.to_le_bytes())
-                    .collect_vec(),
-            ),
-        ))
-        .reduce(C::N)];
+        let transcript = ring_transcript(&self.hash, &l, &msg);
+        let mut c = vec![challenge::<C, _, DIGEST_SIZE>(&transcript, x0)];
         let mut r = Vec::new();
 
         for decoy in decoys {
@@ -147,31 +143,20 @@ where
             // ring.
             'retry: loop {
                 let ci = c.last().unwrap().to_owned();
-                let ri =
-                    Num::from_le_bytes(array::from_fn(|_| self.rng.next().unwrap())).reduce(C::N);
-                let cx = match (ri * C::g() + ci * decoy.point()).coordinates() {
+                let ri = Num::random_mod(rng, C::N);
+                let cx = match ecc::mul_double(ri, g, ci, decoy.point()).coordinates() {
                     Coordinates::Finite(x, _) => x,
                     Coordinates::Infinity => continue 'retry,
                 };
                 r.push(ri);
-                c.push(
-                    Num::from_le_bytes(util::resize(
-                        self.hash.hash(
-                            &l.iter()
-                                .copied()
-                                .chain(msg.iter().copied())
-                                .chain(cx.to_le_bytes())
-                                .collect_vec(),
-                        ),
-                    ))
-                    .reduce(C::N),
-                );
+                c.push(challenge::<C, _, DIGEST_SIZE>(&transcript, cx));
                 break;
             }
         }
 
         // Calculate the final r value in the ring based on the initial random number
         // alpha.
+        // The ring always starts with the initial c value, so it is never empty.
         let cn = c.last().unwrap().to_owned();
         let rn = alpha.sub(cn.mul(key.0, C::N), C::N);
         r.push(rn);
@@ -184,7 +169,7 @@ where
         // Rotate the ring randomly so the start of the ring can't be predicted. If this
         // didn't happen, the real signer of the ring signature would always
         // correspond to the first pubkey, and his identity would not be hidden at all.
-        let shift = uniform_random(&mut self.rng, 0..u32::try_from(r.len()).unwrap());
+        let shift = uniform_random(rng, 0..u32::try_from(r.len()).unwrap());
         c.rotate_left(usize::try_from(shift).unwrap());
         r.rotate_left(usize::try_from(shift).unwrap());
         pubkeys.rotate_left(usize::try_from(shift).unwrap());
@@ -196,28 +181,26 @@ where
         }
     }
 
-    fn verify(&mut self, msg: &[u8], sig: &Self::RingSignature) -> Result<(), InvalidSignature> {
-        assert!(DIGEST_SIZE >= C::SIZE);
+    fn verify_with_context(
+        &mut self,
+        context: &[u8],
+        msg: &[u8],
+        sig: &Self::RingSignature,
+    ) -> Result<(), InvalidSignature> {
+        let msg = pubkey::with_context(context, msg);
 
         // Start with the first c value and use the sequence of r values and pubkeys to
         // produce the next c value in the sequence.
         let mut c = sig.c;
         let l = encode(&sig.keys);
+        let transcript = ring_transcript(&self.hash, &l, &msg);
+        let g = C::g();
         for (&r, k) in sig.r.iter().zip(sig.keys.iter()) {
-            let x = match (r * C::g() + c * k.point()).coordinates() {
+            let x = match ecc::mul_double(r, g, c, k.point()).coordinates() {
                 Coordinates::Finite(x, _) => x,
                 Coordinates::Infinity => return Err(InvalidSignature),
             };
-            c = Num::from_le_bytes(util::resize(
-                self.hash.hash(
-                    &l.iter()
-                        .copied()
-                        .chain(msg.iter().copied())
-                        .chain(x.to_le_bytes())
-                        .collect_vec(),
-                ),
-            ))
-            .reduce(C::N);
+            c = challenge::<C, _, DIGEST_SIZE>(&transcript, x);
         }
 
         // At the end of the process, the ring should be closed.
@@ -229,6 +212,21 @@ where
     }
 }
 
+/// A [SAG](SchnorrSag) ring signature $(c_1, r_1, \dots, r_n, P_1, \dots,
+/// P_n)$.
+///
+/// The signature can be sent to a verifier with
+/// [`to_bytes`](SchnorrSagSignature::to_bytes). Its encoding is
+///
+/// ```text
+/// version (1 byte) || c_1 (32 bytes) || n (4 bytes)
+///     || r_1 (32 bytes) || ... || r_n (32 bytes)
+///     || P_1 (33 bytes) || ... || P_n (33 bytes)
+/// ```
+///
+/// where numbers are big-endian and points are compressed SEC1 points. The
+/// version is [`SchnorrSagSignature::VERSION`], see [`codec`](crate::codec).
+#[docext]
 #[derive(Debug)]
 pub struct SchnorrSagSignature<C> {
     c: Num,
@@ -250,6 +248,86 @@ impl<C> SchnorrSagSignature<C> {
     }
 }
 
+impl<C: Curve> SchnorrSagSignature<C> {
+    /// The current version of the signature encoding.
+    pub const VERSION: u8 = 1;
+
+    /// Encode the signature as bytes.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut writer = Writer::new(Self::VERSION);
+        writer.write_bytes(&self.c.to_be_bytes());
+        writer.write_u32(u32::try_from(self.r.len()).expect("too many keys to encode"));
+        for r in &self.r {
+            writer.write_bytes(&r.to_be_bytes());
+        }
+        for key in &self.keys {
+            writer.write_bytes(&key.to_sec1_bytes(true));
+        }
+        writer.into_bytes()
+    }
+
+    /// Decode a signature from bytes, validating every component: the ring
+    /// must not be empty, the numbers must be reduced modulo [`N`](Curve::N),
+    /// and the points must be [valid public keys](PublicKey::new).
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, SchnorrSagError> {
+        let mut reader = Reader::new(bytes);
+        reader.read_version(Self::VERSION)?;
+        let c = Num::from_be_bytes(reader.read_array()?);
+        let n = usize::try_from(reader.read_u32()?).map_err(|_| SchnorrSagError::Malformed)?;
+        if n == 0 || c >= C::N {
+            return Err(SchnorrSagError::Malformed);
+        }
+
+        // Don't trust n for the allocation before the ring has been read.
+        let capacity = n.min(reader.remaining() / (Num::BYTES + ecc::COMPRESSED_SIZE));
+        let mut r = Vec::with_capacity(capacity);
+        for _ in 0..n {
+            let ri = Num::from_be_bytes(reader.read_array()?);
+            if ri >= C::N {
+                return Err(SchnorrSagError::Malformed);
+            }
+            r.push(ri);
+        }
+        let mut keys = Vec::with_capacity(capacity);
+        for _ in 0..n {
+            let key = PublicKey::from_sec1_bytes(reader.read_bytes(ecc::COMPRESSED_SIZE)?)
+                .map_err(|_| SchnorrSagError::Malformed)?;
+            keys.push(key);
+        }
+        reader.finish()?;
+        Ok(Self { c, r, keys })
+    }
+}
+
+/// Error returned when decoding a [SAG signature](SchnorrSagSignature) fails.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SchnorrSagError {
+    /// The encoded signature is invalid.
+    Malformed,
+    /// The encoded signature has a version which is not supported.
+    UnknownVersion(u8),
+}
+
+impl fmt::Display for SchnorrSagError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Malformed => write!(f, "malformed SAG signature"),
+            Self::UnknownVersion(v) => write!(f, "unknown SAG signature version {v}"),
+        }
+    }
+}
+
+impl std::error::Error for SchnorrSagError {}
+
+impl From<CodecError> for SchnorrSagError {
+    fn from(err: CodecError) -> Self {
+        match err {
+            CodecError::Truncated | CodecError::TrailingBytes => Self::Malformed,
+            CodecError::UnknownVersion(v) => Self::UnknownVersion(v),
+        }
+    }
+}
+
 /// Encode multiple pubkeys into a unique binary representation.
 fn encode<C: Curve>(keys: &[PublicKey<C>]) -> [u8; 32] {
     keys.iter()
@@ -257,3 +335,29 @@ fn encode<C: Curve>(keys: &[PublicKey<C>]) -> [u8; 32] {
         .fold(Num::ZERO, |a, b| a.add(b, C::N))
         .to_le_bytes()
 }
+
+/// The [transcript](Transcript) of the ring and the message, shared by every
+/// challenge in the ring.
+fn ring_transcript<H, const DIGEST_SIZE: usize>(hash: H, l: &[u8], msg: &[u8]) -> Transcript<H>
+where
+    H: Hash<Digest = [u8; DIGEST_SIZE]>,
+{
+    let mut transcript = Transcript::new(hash, b"literate-crypto schnorr sag");
+    transcript.append(b"ring", l);
+    transcript.append(b"msg", msg);
+    transcript
+}
+
+/// The challenge $c_{i+1} = H(\langle L \rangle, m, x)$, forked from the
+/// [ring transcript](ring_transcript), where $x$ is the x-coordinate of $r_iG
+/// + c_iP_i$.
+#[docext]
+fn challenge<C, H, const DIGEST_SIZE: usize>(transcript: &Transcript<H>, x: Num) -> Num
+where
+    C: Curve,
+    H: Hash<Digest = [u8; DIGEST_SIZE]> + Clone,
+{
+    let mut transcript = transcript.clone();
+    transcript.append(b"commitment", &x.to_le_bytes());
+    transcript.challenge_scalar::<C>(b"challenge")
+}
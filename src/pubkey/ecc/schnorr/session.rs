@@ -0,0 +1,290 @@
+use {
+    super::valid_component,
+    crate::{
+        codec::{CodecError, Reader, Writer},
+        ecc::{Curve, Num, Point, PublicKey, UNCOMPRESSED_SIZE},
+        SchnorrSignature,
+    },
+    docext::docext,
+    std::{fmt, marker::PhantomData},
+};
+
+/// A [Schnorr multisig](crate::MultiSchnorr) in the process of being signed.
+///
+/// In practice, the actors of a multisig sign on different devices and at
+/// different times, so the signing has to be suspended and resumed. A session
+/// captures everything that is needed to continue signing:
+///
+/// - the hash $e = H_{sig}(\tilde P \parallel R \parallel m)$, which binds the
+///   message $m$, the combined pubkey $\tilde P$ and the aggregate randomness
+///   $R$,
+/// - the pubkeys $P_i$, in their canonical order,
+/// - the aggregate randomness $R$,
+/// - the running sum $s$ of the individual signatures $s_i$, and
+/// - which of the actors have already contributed their $s_i$.
+///
+/// A session is started with
+/// [`MultiSchnorr::start_session`](crate::MultiSchnorr::start_session). Each
+/// actor computes his [partial signature](PartialSignature) with
+/// [`MultiSchnorr::sign_partial`](crate::MultiSchnorr::sign_partial) and
+/// [contributes](SigningSession::contribute) it. Once every actor has
+/// contributed, the session is [finalized](SigningSession::finalize) into a
+/// regular [Schnorr signature](SchnorrSignature).
+///
+/// The session can be stored or sent to another device at any point with
+/// [`to_bytes`](SigningSession::to_bytes). Its encoding is
+///
+/// ```text
+/// version (1 byte) || e (32 bytes) || R (65 bytes) || s (32 bytes) || n (4 bytes)
+///     || P_1 (65 bytes) || contributed_1 (1 byte)
+///     || ...
+///     || P_n (65 bytes) || contributed_n (1 byte)
+/// ```
+///
+/// where numbers are big-endian, points are uncompressed SEC1 points, and each
+/// contributed flag is either zero or one. The version is
+/// [`SigningSession::VERSION`], see [`codec`](crate::codec).
+#[docext]
+pub struct SigningSession<C, H> {
+    e: Num,
+    pubkeys: Vec<PublicKey<C>>,
+    total: Point<C>,
+    s: Num,
+    contributed: Vec<bool>,
+    _hash: PhantomData<H>,
+}
+
+/// The individual signature $s_i$ of one of the actors in a [signing
+/// session](SigningSession).
+#[docext]
+#[derive(Debug)]
+pub struct PartialSignature<C> {
+    pub(super) signer: PublicKey<C>,
+    pub(super) s: Num,
+}
+
+impl<C> Clone for PartialSignature<C> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<C> Copy for PartialSignature<C> {}
+
+impl<C> PartialSignature<C> {
+    /// Construct the partial signature of the actor with the given pubkey,
+    /// e.g. after receiving it from the network. It can be checked with
+    /// [`MultiSchnorr::verify_partial`](crate::MultiSchnorr::verify_partial).
+    pub fn new(signer: PublicKey<C>, s: Num) -> Self {
+        Self { signer, s }
+    }
+
+    pub fn signer(&self) -> PublicKey<C> {
+        self.signer
+    }
+
+    pub fn s(&self) -> Num {
+        self.s
+    }
+}
+
+/// The size of a single signer in the session encoding.
+const SIGNER_SIZE: usize = UNCOMPRESSED_SIZE + 1;
+
+impl<C: Curve, H> SigningSession<C, H> {
+    /// The current version of the session encoding.
+    pub const VERSION: u8 = 1;
+
+    pub(super) fn new(e: Num, pubkeys: Vec<PublicKey<C>>, total: Point<C>) -> Self {
+        Self {
+            e,
+            contributed: vec![false; pubkeys.len()],
+            pubkeys,
+            total,
+            s: Num::ZERO,
+            _hash: PhantomData,
+        }
+    }
+
+    /// Add the individual signature of one of the actors to the session.
+    pub fn contribute(&mut self, partial: PartialSignature<C>) -> Result<(), SessionError> {
+        let i = self
+            .pubkeys
+            .iter()
+            .position(|&p| p == partial.signer)
+            .ok_or(SessionError::UnknownSigner)?;
+        if self.contributed[i] {
+            return Err(SessionError::DuplicateContribution);
+        }
+        self.contributed[i] = true;
+        self.s = self.s.add(partial.s, C::N);
+        Ok(())
+    }
+
+    /// Finish the session once every actor has contributed, producing the
+    /// multisig.
+    pub fn finalize(self) -> Result<SchnorrSignature<C, H>, IncompleteSession> {
+        if self.contributed.iter().all(|&c| c) {
+            SchnorrSignature::new(self.s, self.e).map_err(|_| IncompleteSession)
+        } else {
+            Err(IncompleteSession)
+        }
+    }
+
+    /// The pubkeys which haven't contributed yet.
+    pub fn missing(&self) -> impl Iterator<Item = PublicKey<C>> + '_ {
+        self.pubkeys
+            .iter()
+            .zip(&self.contributed)
+            .filter(|(_, &c)| !c)
+            .map(|(&p, _)| p)
+    }
+
+    pub fn e(&self) -> Num {
+        self.e
+    }
+
+    pub fn pubkeys(&self) -> &[PublicKey<C>] {
+        &self.pubkeys
+    }
+
+    pub fn total(&self) -> Point<C> {
+        self.total
+    }
+
+    /// Encode the session as bytes.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        // The aggregate randomness is never infinity, so it is a valid pubkey.
+        let total = PublicKey::new(self.total).unwrap();
+        let mut writer = Writer::new(Self::VERSION);
+        writer.write_bytes(&self.e.to_be_bytes());
+        writer.write_bytes(&total.to_uncompressed());
+        writer.write_bytes(&self.s.to_be_bytes());
+        writer.write_u32(u32::try_from(self.pubkeys.len()).expect("too many signers to encode"));
+        for (p, &c) in self.pubkeys.iter().zip(&self.contributed) {
+            writer.write_bytes(&p.to_uncompressed());
+            writer.write_u8(u8::from(c));
+        }
+        writer.into_bytes()
+    }
+
+    /// Decode a session from bytes, validating every component: the numbers
+    /// must be reduced modulo [`N`](Curve::N), the points must be [valid
+    /// public keys](PublicKey::new), the pubkeys must be unique, and a session
+    /// without any contributions must have $s = 0$. The challenge $e$ must be a
+    /// [valid signature component](SchnorrSignature#components), and so must
+    /// $s$ once every actor has contributed.
+    #[docext]
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, SessionError> {
+        let mut reader = Reader::new(bytes);
+        reader.read_version(Self::VERSION)?;
+        let e = Num::from_be_bytes(reader.read_array()?);
+        let total = PublicKey::<C>::from_uncompressed(reader.read_bytes(UNCOMPRESSED_SIZE)?)
+            .map_err(|_| SessionError::Malformed)?
+            .point();
+        let s = Num::from_be_bytes(reader.read_array()?);
+        let n = usize::try_from(reader.read_u32()?).map_err(|_| SessionError::Malformed)?;
+        if n == 0 {
+            return Err(SessionError::Malformed);
+        }
+        if !valid_component::<C>(e) || s >= C::N {
+            return Err(SessionError::Malformed);
+        }
+
+        // Don't trust n for the allocation before the signers have been read.
+        let capacity = n.min(reader.remaining() / SIGNER_SIZE);
+        let mut pubkeys = Vec::with_capacity(capacity);
+        let mut contributed = Vec::with_capacity(capacity);
+        for _ in 0..n {
+            let p = PublicKey::from_uncompressed(reader.read_bytes(UNCOMPRESSED_SIZE)?)
+                .map_err(|_| SessionError::Malformed)?;
+            if pubkeys.contains(&p) {
+                return Err(SessionError::Malformed);
+            }
+            pubkeys.push(p);
+            contributed.push(match reader.read_u8()? {
+                0 => false,
+                1 => true,
+                _ => return Err(SessionError::Malformed),
+            });
+        }
+        reader.finish()?;
+        if s != Num::ZERO && !contributed.contains(&true) {
+            return Err(SessionError::Malformed);
+        }
+        if !contributed.contains(&false) && !valid_component::<C>(s) {
+            return Err(SessionError::Malformed);
+        }
+
+        Ok(Self {
+            e,
+            pubkeys,
+            total,
+            s,
+            contributed,
+            _hash: PhantomData,
+        })
+    }
+}
+
+/// Error returned when updating or decoding a [signing
+/// session](SigningSession) fails.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SessionError {
+    /// The partial signature was made by a key which is not part of the
+    /// session.
+    UnknownSigner,
+    /// The signer has already contributed to the session.
+    DuplicateContribution,
+    /// The encoded session is invalid.
+    Malformed,
+    /// The encoded session has a version which is not supported.
+    UnknownVersion(u8),
+}
+
+impl fmt::Display for SessionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::UnknownSigner => write!(f, "signer is not part of the session"),
+            Self::DuplicateContribution => write!(f, "signer has already contributed"),
+            Self::Malformed => write!(f, "malformed signing session"),
+            Self::UnknownVersion(v) => write!(f, "unknown signing session version {v}"),
+        }
+    }
+}
+
+impl std::error::Error for SessionError {}
+
+impl From<CodecError> for SessionError {
+    fn from(err: CodecError) -> Self {
+        match err {
+            CodecError::Truncated | CodecError::TrailingBytes => Self::Malformed,
+            CodecError::UnknownVersion(v) => Self::UnknownVersion(v),
+        }
+    }
+}
+
+/// Error returned when [finalizing](SigningSession::finalize) a session before
+/// every actor has contributed. It is also returned in the astronomically
+/// unlikely case that the contributions add up to $s = 0$, which is not a
+/// [valid signature](SchnorrSignature#components).
+#[docext]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct IncompleteSession;
+
+impl fmt::Display for IncompleteSession {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "not every signer has contributed to the session")
+    }
+}
+
+impl std::error::Error for IncompleteSession {}
+
+impl<C, H> fmt::Debug for SigningSession<C, H> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SigningSession")
+            .field("e", &self.e)
+            .field("contributed", &self.contributed)
+            .finish_non_exhaustive()
+    }
+}
@@ -24,20 +24,22 @@ impl Curve for Secp256k1 {
     const B: Num = Num::SEVEN;
 
     fn g() -> Point<Self> {
-        Point::new(
-            Num::from_le_words([
-                0x59F2815B16F81798,
-                0x029BFCDB2DCE28D9,
-                0x55A06295CE870B07,
-                0x79BE667EF9DCBBAC,
-            ]),
-            Num::from_le_words([
-                0x9C47D08FFB10D4B8,
-                0xFD17B448A6855419,
-                0x5DA4FBFC0E1108A8,
-                0x483ADA7726A3C465,
-            ]),
-        )
-        .unwrap()
+        G
     }
 }
+
+/// The generator point, checked to lie on the curve by [`Curve::validate`].
+const G: Point<Secp256k1> = Point::new_unchecked(
+    Num::from_le_words([
+        0x59F2815B16F81798,
+        0x029BFCDB2DCE28D9,
+        0x55A06295CE870B07,
+        0x79BE667EF9DCBBAC,
+    ]),
+    Num::from_le_words([
+        0x9C47D08FFB10D4B8,
+        0xFD17B448A6855419,
+        0x5DA4FBFC0E1108A8,
+        0x483ADA7726A3C465,
+    ]),
+);
@@ -0,0 +1,67 @@
+use {
+    crate::ecc::{Curve, Num, Point},
+    docext::docext,
+};
+
+/// The NIST curve P-256, also known as secp256r1 and prime256v1, defined in
+/// [FIPS 186-4](https://nvlpubs.nist.gov/nistpubs/FIPS/NIST.FIPS.186-4.pdf),
+/// appendix D.1.2.3.
+///
+/// Unlike [secp256k1](crate::ecc::Secp256k1), the $a$ parameter is not zero
+/// but $-3$, i.e. $P - 3$. This is what most TLS certificates and WebCrypto
+/// keys use.
+#[docext]
+#[derive(Debug, Default)]
+pub struct Secp256r1(());
+
+impl Curve for Secp256r1 {
+    const SIZE: usize = 32;
+
+    const P: Num = Num::from_le_words([
+        0xFFFFFFFFFFFFFFFF,
+        0x00000000FFFFFFFF,
+        0x0000000000000000,
+        0xFFFFFFFF00000001,
+    ]);
+
+    const N: Num = Num::from_le_words([
+        0xF3B9CAC2FC632551,
+        0xBCE6FAADA7179E84,
+        0xFFFFFFFFFFFFFFFF,
+        0xFFFFFFFF00000000,
+    ]);
+
+    const A: Num = Num::from_le_words([
+        0xFFFFFFFFFFFFFFFC,
+        0x00000000FFFFFFFF,
+        0x0000000000000000,
+        0xFFFFFFFF00000001,
+    ]);
+
+    const B: Num = Num::from_le_words([
+        0x3BCE3C3E27D2604B,
+        0x651D06B0CC53B0F6,
+        0xB3EBBD55769886BC,
+        0x5AC635D8AA3A93E7,
+    ]);
+
+    fn g() -> Point<Self> {
+        G
+    }
+}
+
+/// The generator point, checked to lie on the curve by [`Curve::validate`].
+const G: Point<Secp256r1> = Point::new_unchecked(
+    Num::from_le_words([
+        0xF4A13945D898C296,
+        0x77037D812DEB33A0,
+        0xF8BCE6E563A440F2,
+        0x6B17D1F2E12C4247,
+    ]),
+    Num::from_le_words([
+        0xCBB6406837BF51F5,
+        0x2BCE33576B315ECE,
+        0x8EE7EB4A7C0F9E16,
+        0x4FE342E2FE1A7F9B,
+    ]),
+);
@@ -0,0 +1,97 @@
+use {
+    super::{
+        curve::{Coordinates, Curve, Point},
+        group::Group,
+        jacobian::Jacobian,
+        num::Num,
+    },
+    crate::util::CtSelect,
+    docext::docext,
+    std::{
+        any::TypeId,
+        collections::BTreeMap,
+        sync::{Mutex, PoisonError},
+    },
+};
+
+/// Bits of the scalar consumed by one window.
+const WINDOW_BITS: usize = 4;
+
+/// Multiples of the window base stored per window, including zero.
+const WINDOW_SIZE: usize = 1 << WINDOW_BITS;
+
+/// Number of windows covering all bits of a [`Num`].
+const WINDOWS: usize = Num::BITS / WINDOW_BITS;
+
+/// Tables built so far, one per curve, leaked so that they live as long as the
+/// program.
+static TABLES: Mutex<BTreeMap<TypeId, &'static GeneratorTable>> = Mutex::new(BTreeMap::new());
+
+/// Precomputed multiples of the [generator point](Curve::g) for [fixed-base
+/// multiplication](Curve::mul_g).
+///
+/// The scalar $n$ is split into 4-bit windows, $n = \sum_i n_i 16^i$ with $0
+/// \le n_i < 16$. Window $i$ of the table holds the sixteen points $j \cdot
+/// 16^i G$ for $j \in [0, 16)$, so that
+///
+/// $$
+/// nG = \sum_i n_i 16^i G
+/// $$
+///
+/// is a sum of 64 table entries, one from each window. [Double-and-add
+/// ](Group::scale) needs 256 doublings and up to 256 additions for the same
+/// product. The table takes 1024 points, and is computed once per curve, the
+/// first time it is needed.
+///
+/// The scalar is usually secret, and looking up the entry for $n_i$ directly
+/// would load a memory location depending on it, which leaks through the
+/// cache. Instead, every entry of the window is [selected](CtSelect) over, and
+/// only the one matching $n_i$ is kept.
+#[docext]
+struct GeneratorTable([[Coordinates; WINDOW_SIZE]; WINDOWS]);
+
+impl GeneratorTable {
+    fn new<C: Curve>() -> Self {
+        let mut base = Jacobian::from(C::g());
+        let mut windows = [[Coordinates::Infinity; WINDOW_SIZE]; WINDOWS];
+        for window in windows.iter_mut() {
+            let mut multiple = Jacobian::identity();
+            for entry in window.iter_mut() {
+                *entry = Point::from(multiple).coordinates();
+                multiple = multiple.op(base);
+            }
+            // After adding the base sixteen times, the multiple is the base of
+            // the next window.
+            base = multiple;
+        }
+        Self(windows)
+    }
+
+    fn mul<C: Curve>(&self, n: Num) -> Point<C> {
+        let bytes = n.to_le_bytes();
+        let mut result = Jacobian::identity();
+        for (i, window) in self.0.iter().enumerate() {
+            let digit = u64::from(bytes[i / 2] >> (i % 2 * WINDOW_BITS) & 0xf);
+            let entry = window
+                .iter()
+                .zip(0..)
+                .fold(Coordinates::Infinity, |acc, (entry, j)| {
+                    Coordinates::ct_select(&acc, entry, digit.ct_eq(&j))
+                });
+            result = result.op(Jacobian::from_coordinates(entry));
+        }
+        result.into()
+    }
+}
+
+/// Compute $nG$ through the [table](GeneratorTable) of the curve, building it
+/// on first use.
+#[docext]
+pub(crate) fn mul_g<C: Curve>(n: Num) -> Point<C> {
+    let table = *TABLES
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+        .entry(TypeId::of::<C>())
+        .or_insert_with(|| Box::leak(Box::new(GeneratorTable::new::<C>())));
+    table.mul(n)
+}
@@ -0,0 +1,134 @@
+use {
+    super::{Curve, Num},
+    crate::{util::canonical, Hash},
+    docext::docext,
+    std::fmt,
+};
+
+/// Operation tags, so that a challenge can never be confused with an appended
+/// message, whatever the labels.
+const OP_PROTOCOL: &[u8] = b"protocol";
+const OP_APPEND: &[u8] = b"append";
+const OP_CHALLENGE: &[u8] = b"challenge";
+const OP_SCALAR: &[u8] = b"scalar";
+
+/// A transcript of an interactive protocol, made non-interactive with the
+/// Fiat-Shamir transform.
+///
+/// In an interactive proof, the verifier sends random _challenges_ which the
+/// prover can't predict. The Fiat-Shamir transform replaces the verifier with
+/// a [hash function](Hash): each challenge is the hash of everything that was
+/// said before it. The prover still can't predict a challenge before
+/// committing to the messages it depends on, and anybody can recompute the
+/// challenges to check the proof. The [Schnorr](crate::Schnorr) signature is
+/// the classic example, with the challenge $e = H(P, R, m)$.
+///
+/// Computing such hashes ad hoc is error-prone. If a field is forgotten, the
+/// fields are hashed in a different order by the two parties, or two protocols
+/// hash the same fields, the challenge no longer binds what it should. The
+/// transcript makes the structure explicit:
+///
+/// - [`new`](Transcript::new) starts the transcript with the name of the
+///   protocol, so that transcripts of different protocols never agree.
+/// - [`append`](Transcript::append) adds a labeled message.
+/// - [`challenge_bytes`](Transcript::challenge_bytes) and
+///   [`challenge_scalar`](Transcript::challenge_scalar) derive a labeled
+///   challenge from everything appended so far. The request for the challenge
+///   is itself recorded, so two challenges in a row are unrelated.
+///
+/// Every operation is recorded as its tag, its label and its data, each
+/// [length-prefixed](crate::canonical), so the sequence of operations can be
+/// read back from the transcript in exactly one way. A challenge is the hash
+/// of the whole record, expanded to the requested length by hashing it again
+/// with a block counter:
+///
+/// $$
+/// seed = H(record), \quad block_i = H(seed \parallel i)
+/// $$
+///
+/// A transcript can be cloned to fork it, e.g. when a sub-protocol derives
+/// several challenges from the same shared prefix. The forks evolve
+/// independently. Forking requires the hash function to be cloneable, which a
+/// shared reference to a [hash function](Hash) always is, so a transcript
+/// started with `Transcript::new(&hash, ...)` can always be forked.
+#[docext]
+#[derive(Clone)]
+pub struct Transcript<H> {
+    hash: H,
+    record: Vec<u8>,
+}
+
+impl<H, const DIGEST_SIZE: usize> Transcript<H>
+where
+    H: Hash<Digest = [u8; DIGEST_SIZE]>,
+{
+    /// Start a transcript for the named protocol.
+    pub fn new(hash: H, protocol: &'static [u8]) -> Self {
+        let mut transcript = Self {
+            hash,
+            record: Vec::new(),
+        };
+        transcript.record(OP_PROTOCOL, protocol, &[]);
+        transcript
+    }
+
+    /// Append a labeled message.
+    pub fn append(&mut self, label: &'static [u8], data: &[u8]) {
+        self.record(OP_APPEND, label, data);
+    }
+
+    /// Fill `out` with a labeled challenge.
+    ///
+    /// The length of the output is part of the request, so a shorter
+    /// challenge is not a prefix of a longer one.
+    pub fn challenge_bytes(&mut self, label: &'static [u8], out: &mut [u8]) {
+        let len = u64::try_from(out.len()).unwrap().to_le_bytes();
+        self.record(OP_CHALLENGE, label, &len);
+        let seed = self.hash.hash(&self.record);
+        for (i, chunk) in out.chunks_mut(DIGEST_SIZE).enumerate() {
+            let i = u64::try_from(i).unwrap().to_le_bytes();
+            let block = canonical::hash_fields(&self.hash, &[&seed, &i]);
+            chunk.copy_from_slice(&block[..chunk.len()]);
+        }
+    }
+
+    /// Derive a labeled challenge uniformly distributed in $[0, N)$, where $N$
+    /// is the [order](Curve::N) of the curve.
+    ///
+    /// Reducing a random number modulo $N$ would make the small remainders
+    /// more likely than the others. Instead, a challenge with as many bits as
+    /// $N$ is drawn, and drawn again as long as it is not below $N$. Every
+    /// attempt is at least as likely to succeed as not, and all parties repeat
+    /// the same attempts, so they still agree on the challenge. The [curve
+    /// identifier](Curve::id) is recorded first, so the same transcript gives
+    /// unrelated challenges on different curves.
+    #[docext]
+    pub fn challenge_scalar<C: Curve>(&mut self, label: &'static [u8]) -> Num {
+        self.record(OP_SCALAR, label, &C::id());
+        let bits = C::N.bits();
+        let mut bytes = [0; Num::BYTES];
+        loop {
+            self.challenge_bytes(label, &mut bytes[..(bits + 7) / 8]);
+            if bits % 8 != 0 {
+                bytes[bits / 8] &= (1 << (bits % 8)) - 1;
+            }
+            let n = Num::from_le_bytes(bytes);
+            if n < C::N {
+                return n;
+            }
+        }
+    }
+
+    fn record(&mut self, op: &[u8], label: &[u8], data: &[u8]) {
+        self.record
+            .extend(canonical::encode_fields(&[op, label, data]));
+    }
+}
+
+impl<H> fmt::Debug for Transcript<H> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Transcript")
+            .field("len", &self.record.len())
+            .finish_non_exhaustive()
+    }
+}
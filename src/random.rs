@@ -1,8 +1,22 @@
 mod fortuna;
+mod health;
+mod jitter;
+#[cfg(unix)]
+mod os;
+mod pool;
 
-use std::ops::Range;
-
-pub use fortuna::Fortuna;
+#[cfg(unix)]
+pub use os::OsEntropy;
+use {
+    docext::docext,
+    std::{iter, ops::Range},
+};
+pub use {
+    fortuna::Fortuna,
+    health::{HealthTestConfig, HealthTested},
+    jitter::TimingJitter,
+    pool::EntropyPool,
+};
 
 /// Cryptographically secure pseudorandom number generator.
 ///
@@ -13,8 +27,33 @@ pub use fortuna::Fortuna;
 /// [entropy](Entropy), and typically the seed is generated using a source of
 /// entropy.
 ///
-/// The random numbers are yielded by [`into_iter`](IntoIterator::into_iter).
-pub trait Csprng: IntoIterator<Item = u8> {}
+/// Random bytes are written by [`fill_bytes`](Csprng::fill_bytes), which only
+/// needs a `&mut` reference, so one generator can be lent to any number of
+/// functions in turn. A generator can also be consumed by
+/// [`into_iter`](IntoIterator::into_iter), or borrowed as an iterator by
+/// [`bytes`](Csprng::bytes). The bytes never end.
+pub trait Csprng: IntoIterator<Item = u8> {
+    /// Fill the buffer with random bytes.
+    fn fill_bytes(&mut self, buf: &mut [u8]);
+
+    /// Mix the given seed into the state of the generator. Reseeding never
+    /// makes the output less random: the seed is added to the existing state,
+    /// rather than replacing it. Two generators in the same state which are
+    /// reseeded with the same seed produce the same bytes afterwards.
+    fn reseed(&mut self, seed: &[u8]);
+
+    /// Borrow the generator as an iterator of random bytes.
+    fn bytes(&mut self) -> impl Iterator<Item = u8> + '_
+    where
+        Self: Sized,
+    {
+        iter::repeat_with(|| {
+            let mut byte = [0];
+            self.fill_bytes(&mut byte);
+            byte[0]
+        })
+    }
+}
 
 /// A source of entropy.
 ///
@@ -30,32 +69,96 @@ pub trait Entropy {
     fn get(&mut self, buf: &mut [u8]);
 }
 
+/// [`Entropy::get`] takes `&mut self`, so an entropy source can only be
+/// forwarded through exclusive references and boxes.
+impl<T: Entropy + ?Sized> Entropy for &mut T {
+    fn get(&mut self, buf: &mut [u8]) {
+        T::get(self, buf)
+    }
+}
+
+impl<T: Entropy + ?Sized> Entropy for Box<T> {
+    fn get(&mut self, buf: &mut [u8]) {
+        T::get(self, buf)
+    }
+}
+
 /// Draw a uniformly random number from a range.
 ///
 /// Being uniformly random means that every number in the range has equal chance
-/// of being drawn, except negligible difference.
-pub fn uniform_random(rand: &mut impl Iterator<Item = u8>, range: Range<u32>) -> u32 {
-    let draw = u32::from_le_bytes([
-        rand.next().unwrap(),
-        rand.next().unwrap(),
-        rand.next().unwrap(),
-        rand.next().unwrap(),
-    ]);
-    let result = u64::from(range.start)
-        + u64::from(range.end - range.start) * u64::from(draw) / u64::from(u32::MAX);
-    result.try_into().unwrap()
+/// of being drawn. Mapping a random 32-bit draw into a range whose length
+/// doesn't divide $2^{32}$ would favor some of the numbers, so the draws in the
+/// uneven tail of the 32-bit range are rejected, and the number is drawn again.
+/// At most half of the draws are rejected, so this ends quickly.
+///
+/// An empty range, whose start equals its end, gives its start without
+/// drawing any randomness.
+///
+/// # Panics
+///
+/// If the range is reversed, i.e. its start is greater than its end.
+#[docext]
+pub fn uniform_random(rand: &mut impl Csprng, range: Range<u32>) -> u32 {
+    let len = range.end.checked_sub(range.start).expect("reversed range");
+    let draw = uniform_below::<4>(rand, u64::from(len));
+    // The draw is below the length of the range, which came from a u32.
+    range.start + u32::try_from(draw).unwrap()
+}
+
+/// Draw a uniformly random number from a range of 64-bit numbers, like
+/// [`uniform_random`]. An empty range gives its start.
+///
+/// # Panics
+///
+/// If the range is reversed, i.e. its start is greater than its end.
+pub fn uniform_random_u64(rand: &mut impl Csprng, range: Range<u64>) -> u64 {
+    let len = range.end.checked_sub(range.start).expect("reversed range");
+    range.start + uniform_below::<8>(rand, len)
+}
+
+/// Draw a number below `len` from random draws of `BYTES` bytes, rejecting the
+/// draws which fall into the tail beyond the last multiple of `len`. Gives zero
+/// if `len` is zero.
+fn uniform_below<const BYTES: usize>(rand: &mut impl Csprng, len: u64) -> u64 {
+    if len == 0 {
+        return 0;
+    }
+    let draws = 1u128 << (8 * BYTES);
+    let accepted = draws - draws % u128::from(len);
+    loop {
+        let mut draw = [0; 8];
+        rand.fill_bytes(&mut draw[..BYTES]);
+        let draw = u64::from_le_bytes(draw);
+        if u128::from(draw) < accepted {
+            return draw % len;
+        }
+    }
 }
 
 /// Randomly shuffle the elements of a slice.
 ///
 /// This works by walking the slice and swapping the current element with a
-/// random element picked from the remainder of the slice to the right. This is
-/// equivalent to randomly removing elements from the slice and pushing them
-/// into an empty container, but more efficient since it operates in-place.
-pub fn shuffle<T>(rand: &mut impl Iterator<Item = u8>, elems: &mut [T]) {
-    let len = u32::try_from(elems.len()).unwrap();
-    for i in 0..len - 1 {
-        let j = uniform_random(rand, i + 1..len);
+/// random element picked from the remainder of the slice, including the current
+/// element itself. This is equivalent to randomly removing elements from the
+/// slice and pushing them into an empty container, but more efficient since it
+/// operates in-place. Every order is equally likely.
+///
+/// # Panics
+///
+/// If the slice has more than `u32::MAX` elements.
+pub fn shuffle<T>(rand: &mut impl Csprng, elems: &mut [T]) {
+    let len = u32::try_from(elems.len()).expect("too many elements to shuffle");
+    for i in 0..len.saturating_sub(1) {
+        let j = uniform_random(rand, i..len);
+        // Both indices are below the length, which came from a usize.
         elems.swap(i.try_into().unwrap(), j.try_into().unwrap());
     }
 }
+
+/// Generate a random value, such as a key or an IV, by filling its bytes from
+/// a source of randomness.
+pub(crate) fn random_bytes<T: Default + AsMut<[u8]>>(rand: &mut impl Csprng) -> T {
+    let mut result = T::default();
+    rand.fill_bytes(result.as_mut());
+    result
+}
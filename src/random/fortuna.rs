@@ -1,24 +1,46 @@
 use {
     crate::{
+        canonical,
+        util::{Zeroize, Zeroizing},
         Aes256,
         BlockEncrypt,
-        BlockSizeTooSmall,
-        CipherEncrypt,
         Csprng,
         Ctr,
+        CtrConfigError,
         Entropy,
         Hash,
+        Hmac,
+        Mac,
         Sha256,
     },
-    std::iter,
+    docext::docext,
+    std::{
+        array,
+        fmt,
+        iter,
+        time::{Duration, Instant},
+    },
 };
 
+#[cfg(unix)]
+use crate::OsEntropy;
+
 /// Size of the seed in bytes.
 const SEED_SIZE: usize = 32;
 
 /// Number of bytes generated by Fortuna before it gets reseeded.
 const RESEED_SIZE: usize = 2048;
 
+/// Number of pools in the [accumulator](Fortuna#accumulator).
+const POOLS: usize = 32;
+
+/// Number of bytes of events which the first pool must collect before the
+/// pools are used for a reseed.
+const MIN_POOL_SIZE: usize = 64;
+
+/// The default minimum time between two reseeds from the pools.
+const MIN_RESEED_INTERVAL: Duration = Duration::from_millis(100);
+
 /// Fortuna is a [CSPRNG](crate::Csprng) built from a [block
 /// cipher](crate::BlockEncrypt) and a [hash function](crate::Hash).
 ///
@@ -27,43 +49,407 @@ const RESEED_SIZE: usize = 2048;
 /// bytes are hashed to generate a key. The block cipher runs in [CTR
 /// mode](crate::Ctr), encrypting a monotonically incrementing counter. The
 /// output of the block cipher represents a stream of random numbers. The key is
-/// periodically reseeded, in which case the random bytes of entropy are
-/// appended to the current key and the resulting sequence of bytes is hashed
-/// to generate the new key. This helps avoid attackers from predicting future
+/// periodically reseeded, in which case the current key and the random bytes
+/// of entropy are [framed](crate::canonical) together and hashed to generate
+/// the new key. This helps avoid attackers from predicting future
 /// output, especially in the case that the internal state is compromised.
 ///
 /// One variation is to also update the key more frequently by using the output
 /// of the block cipher. This helps prevent attackers from knowing future
 /// outputs in the case where only the key has been compromised (but not the
 /// internal counter), so the usefulness of this method is somewhat limited.
-#[derive(Debug, Clone)]
-pub struct Fortuna<Ent, Enc = Aes256, H = Sha256> {
+///
+/// # Accumulator
+///
+/// A single source of entropy which is polled for every reseed is only as good
+/// as that source. Real systems also see a trickle of unpredictable _events_,
+/// such as the timings of interrupts, key presses and network packets, each of
+/// which carries little entropy on its own. Fortuna collects them with
+/// [`add_event`](Fortuna::add_event) into 32 _pools_ $P_0, P_1, \dots,
+/// P_{31}$. The events of every source are spread over the pools round-robin,
+/// and each pool keeps a hash of its events.
+///
+/// Once $P_0$ has collected enough bytes of events, and enough time has passed
+/// since the previous reseed from the pools, the next output reseeds the key
+/// from the pools as well. Reseed number $r$ uses pool $P_i$ whenever $2^i$
+/// divides $r$, and empties it:
+///
+/// - $P_0$ is used for every reseed,
+/// - $P_1$ for every second reseed,
+/// - $P_2$ for every fourth, and so on.
+///
+/// An attacker who can observe or inject some of the events might be able to
+/// guess the contents of the pools which are used often, and so the key after
+/// a reseed. But the pools which are used rarely gather events for longer, so
+/// eventually one of them has gathered enough entropy which the attacker
+/// couldn't guess, and the generator recovers. This works without any
+/// estimate of how much entropy an event contains, which is the main idea of
+/// Fortuna.
+///
+/// # Substreams
+///
+/// When one generator supplies several consumers, e.g. an AES key, an IV and
+/// a signature nonce, all of them are cut from the same stream, so a consumer
+/// which leaks its bytes exposes a segment right next to the others.
+/// [`substream`](Fortuna::substream) instead derives an independent generator
+/// for each consumer, see its documentation for details.
+///
+/// The [`Debug`](fmt::Debug) output leaves out the source of entropy, whose
+/// state could reveal the seeds of the generator, the key and the pools. The
+/// keys, the pools and the output which wasn't handed out yet are wiped when
+/// the generator is dropped.
+#[docext]
+#[derive(Clone)]
+pub struct Fortuna<Ent, Enc: BlockEncrypt = Aes256, H = Sha256>
+where
+    Enc::EncryptionKey: AsMut<[u8]>,
+{
     entropy: Ent,
     ctr: Ctr<Enc>,
     hash: H,
+    /// The key for the first output of the generator, if it was already
+    /// seeded by a [substream](Fortuna::substream).
+    key: Option<Enc::EncryptionKey>,
+    /// The key of the current batch of output, or `None` before the first
+    /// output.
+    current: Option<Enc::EncryptionKey>,
+    /// The current batch of output, and how many of its bytes were handed out.
+    batch: Vec<u8>,
+    used: usize,
+    accumulator: Accumulator<Enc::EncryptionKey>,
+    /// The number of substreams derived so far.
+    substreams: u64,
+}
+
+impl<Ent, Enc, H> fmt::Debug for Fortuna<Ent, Enc, H>
+where
+    Enc: BlockEncrypt + fmt::Debug,
+    Enc::EncryptionKey: AsMut<[u8]>,
+    H: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Fortuna")
+            .field("ctr", &self.ctr)
+            .field("hash", &self.hash)
+            .field("reseeds", &self.accumulator.reseeds)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<Ent, Enc: BlockEncrypt, H> Drop for Fortuna<Ent, Enc, H>
+where
+    Enc::EncryptionKey: AsMut<[u8]>,
+{
+    fn drop(&mut self) {
+        let pools = self
+            .accumulator
+            .pools
+            .iter_mut()
+            .map(|pool| &mut pool.digest);
+        for key in [&mut self.key, &mut self.current]
+            .into_iter()
+            .chain(pools)
+            .flatten()
+        {
+            key.zeroize();
+        }
+        self.batch.zeroize();
+    }
 }
 
 impl<Ent, Enc, H, const BLOCK_SIZE: usize> Fortuna<Ent, Enc, H>
 where
     Enc: BlockEncrypt<EncryptionBlock = [u8; BLOCK_SIZE]>,
+    Enc::EncryptionKey: AsMut<[u8]>,
 {
-    pub fn new(entropy: Ent, enc: Enc, hash: H) -> Result<Self, BlockSizeTooSmall> {
+    pub fn new(entropy: Ent, enc: Enc, hash: H) -> Result<Self, CtrConfigError> {
         Ok(Self {
             entropy,
             ctr: Ctr::new(enc, 0)?,
             hash,
+            key: None,
+            current: None,
+            batch: Vec::new(),
+            used: 0,
+            accumulator: Accumulator::new(),
+            substreams: 0,
         })
     }
 }
 
+impl<Ent, Enc: BlockEncrypt, H> Fortuna<Ent, Enc, H>
+where
+    Enc::EncryptionKey: AsMut<[u8]>,
+{
+    /// Set the minimum time between two reseeds from the
+    /// [pools](Fortuna#accumulator), 100 milliseconds by default.
+    ///
+    /// Without a minimum, an attacker who can inject events could trigger
+    /// reseeds in quick succession, and use up the rarely used pools before
+    /// they have gathered enough entropy.
+    pub fn with_reseed_interval(mut self, interval: Duration) -> Self {
+        self.accumulator.interval = interval;
+        self
+    }
+
+    /// The number of reseeds from the [pools](Fortuna#accumulator) so far.
+    pub fn reseeds(&self) -> u64 {
+        self.accumulator.reseeds
+    }
+
+    /// The number of bytes of events collected by pool $i$ since it was last
+    /// used for a reseed.
+    ///
+    /// # Panics
+    ///
+    /// If $i$ is not below 32.
+    #[docext]
+    pub fn pool_size(&self, i: usize) -> usize {
+        self.accumulator.pools[i].size
+    }
+}
+
+impl<Ent, Enc, H> Fortuna<Ent, Enc, H>
+where
+    Enc: BlockEncrypt,
+    H: Hash<Digest = Enc::EncryptionKey>,
+    Enc::EncryptionKey: AsRef<[u8]> + AsMut<[u8]>,
+{
+    /// Add an event from the source with the given number to the
+    /// [pools](Fortuna#accumulator).
+    ///
+    /// The events of each source go to the pools in turn, so that every pool
+    /// gets its share of every source. The data is hashed into the pool along
+    /// with the number of the source.
+    pub fn add_event(&mut self, source: u8, data: &[u8]) {
+        let next = &mut self.accumulator.next[usize::from(source)];
+        let pool = &mut self.accumulator.pools[usize::from(*next)];
+        *next = (*next + 1) % u8::try_from(POOLS).unwrap();
+        let mut digest = pool.digest.take();
+        let prev = digest.as_ref().map_or(&[][..], AsRef::as_ref);
+        pool.digest = Some(canonical::hash_fields(&self.hash, &[prev, &[source], data]));
+        if let Some(digest) = &mut digest {
+            digest.zeroize();
+        }
+        pool.size += data.len();
+    }
+}
+
+#[cfg(unix)]
+impl Fortuna<OsEntropy> {
+    /// Fortuna with [AES-256](Aes256) and [SHA-256](Sha256), seeded from the
+    /// [operating system](OsEntropy).
+    pub fn from_os() -> Self {
+        Self::new(OsEntropy::default(), Aes256::default(), Sha256::default())
+            .expect("AES-256 is a valid cipher for CTR mode")
+    }
+}
+
+impl<Ent, Enc, H, const BLOCK_SIZE: usize, const KEY_SIZE: usize, const HASH_BLOCK_SIZE: usize>
+    Fortuna<Ent, Enc, H>
+where
+    Ent: Entropy,
+    Enc: BlockEncrypt<EncryptionBlock = [u8; BLOCK_SIZE], EncryptionKey = [u8; KEY_SIZE]> + Default,
+    H: Hash<Digest = [u8; KEY_SIZE], Block = [u8; HASH_BLOCK_SIZE]> + Default,
+    [u8; BLOCK_SIZE]: Default,
+    [u8; KEY_SIZE]: Default,
+{
+    /// Derive an independent generator for the consumer named by the label,
+    /// without disturbing the stream of this generator.
+    ///
+    /// The key of the substream is the [HMAC](Hmac) of the label and the number
+    /// of substreams derived so far, keyed with the key which this generator
+    /// will use for its first output:
+    ///
+    /// $$
+    /// K_{sub} = HMAC(K, \text{"fortuna substream"} \parallel label
+    /// \parallel n)
+    /// $$
+    ///
+    /// The fields are [framed](crate::canonical). The substream then runs like
+    /// this generator, except that it has no entropy of its own, so each reseed
+    /// just hashes its key forward.
+    ///
+    /// The substreams are independent of each other and of this generator, as
+    /// long as HMAC is a pseudorandom function:
+    ///
+    /// - This generator never uses $K_{sub}$, and its own output is the
+    ///   encryption of a counter under $K$, which no substream ever sees. Its
+    ///   stream is the same whether or not any substreams were derived.
+    /// - Every substream gets a distinct input to HMAC, so the keys of the
+    ///   substreams look unrelated to each other and to $K$. A consumer which
+    ///   leaks its key or output learns nothing about the other streams.
+    /// - Because $n$ counts every substream, asking for the same label again
+    ///   gives a fresh stream, rather than repeating the bytes which an earlier
+    ///   consumer already used.
+    ///
+    /// If this generator hasn't produced any output yet, it's seeded from the
+    /// source of entropy now, exactly as it would have been for its first
+    /// output.
+    #[docext]
+    pub fn substream(&mut self, label: &[u8]) -> impl Csprng {
+        let key = match self.key.take() {
+            Some(key) => key,
+            None => {
+                let current = Zeroizing::new(self.current.unwrap_or_default());
+                self.next_key(&current)
+            }
+        };
+        let n = self.substreams.to_le_bytes();
+        let msg = canonical::encode_fields(&[b"fortuna substream", label, &n]);
+        let sub_key = Hmac::new(&self.hash).mac(&msg, &key);
+        self.key = Some(key);
+        self.substreams += 1;
+
+        let mut sub = Fortuna::new(NoFurtherEntropy, Enc::default(), H::default())
+            .expect("the cipher was already accepted for this generator");
+        sub.key = Some(sub_key);
+        sub
+    }
+}
+
+impl<Ent, Enc, H> Fortuna<Ent, Enc, H>
+where
+    Ent: Entropy,
+    Enc: BlockEncrypt,
+    H: Hash<Digest = Enc::EncryptionKey>,
+    Enc::EncryptionKey: AsRef<[u8]> + AsMut<[u8]>,
+{
+    /// Fetch random bytes from the source of entropy, and hash them together
+    /// with the old key to get the new key. If the [pools](Fortuna#accumulator)
+    /// are ready, the scheduled ones are hashed in as well.
+    fn next_key(&mut self, key: &Enc::EncryptionKey) -> Enc::EncryptionKey {
+        let mut seed = [0; SEED_SIZE];
+        self.entropy.get(&mut seed);
+        let mut pools = if self.accumulator.ready() {
+            self.accumulator.drain()
+        } else {
+            Vec::new()
+        };
+        let fields = [key.as_ref(), &seed]
+            .into_iter()
+            .chain(pools.iter().map(AsRef::as_ref))
+            .collect::<Vec<_>>();
+        let key = canonical::hash_fields(&self.hash, &fields);
+        seed.zeroize();
+        pools.iter_mut().for_each(Zeroize::zeroize);
+        key
+    }
+}
+
+/// The [pools](Fortuna#accumulator) of a [Fortuna](Fortuna) generator.
+#[derive(Clone)]
+struct Accumulator<D> {
+    pools: [Pool<D>; POOLS],
+    /// The pool which the next event of each source goes to.
+    next: [u8; 256],
+    reseeds: u64,
+    last_reseed: Option<Instant>,
+    interval: Duration,
+}
+
+/// A pool of events, kept as the hash of the previous hash and the latest
+/// event, or `None` if it's empty.
+#[derive(Clone)]
+struct Pool<D> {
+    digest: Option<D>,
+    size: usize,
+}
+
+impl<D> Accumulator<D> {
+    fn new() -> Self {
+        Self {
+            pools: array::from_fn(|_| Pool {
+                digest: None,
+                size: 0,
+            }),
+            next: [0; 256],
+            reseeds: 0,
+            last_reseed: None,
+            interval: MIN_RESEED_INTERVAL,
+        }
+    }
+
+    /// Whether the first pool is large enough, and enough time has passed
+    /// since the last reseed from the pools.
+    fn ready(&self) -> bool {
+        self.pools[0].size >= MIN_POOL_SIZE
+            && self
+                .last_reseed
+                .map_or(true, |last| last.elapsed() >= self.interval)
+    }
+
+    /// Count a reseed, and empty the pools scheduled for it, returning their
+    /// hashes.
+    fn drain(&mut self) -> Vec<D> {
+        self.reseeds += 1;
+        self.last_reseed = Some(Instant::now());
+        let reseeds = self.reseeds;
+        self.pools
+            .iter_mut()
+            .enumerate()
+            .take_while(|&(i, _)| reseeds % (1 << i) == 0)
+            .filter_map(|(_, pool)| {
+                pool.size = 0;
+                pool.digest.take()
+            })
+            .collect()
+    }
+}
+
+/// The source of entropy of a [substream](Fortuna::substream), which has no
+/// entropy of its own. Reseeding with a constant seed only hashes the key
+/// forward, so that earlier output can't be recovered from a later key.
+struct NoFurtherEntropy;
+
+impl Entropy for NoFurtherEntropy {
+    fn get(&mut self, buf: &mut [u8]) {
+        buf.fill(0);
+    }
+}
+
 impl<Ent, Enc, H> Csprng for Fortuna<Ent, Enc, H>
 where
     Ent: Entropy,
     Enc: BlockEncrypt,
     H: Hash<Digest = Enc::EncryptionKey>,
-    Enc::EncryptionBlock: IntoIterator<Item = u8> + AsMut<[u8]> + Default,
-    Enc::EncryptionKey: 'static + AsRef<[u8]> + Clone + Default,
+    Enc::EncryptionBlock: IntoIterator<Item = u8> + AsMut<[u8]> + Default + Clone,
+    Enc::EncryptionKey: 'static + AsRef<[u8]> + AsMut<[u8]> + Clone + Default,
 {
+    /// The bytes continue the same stream as the [iterator](IntoIterator),
+    /// except that if the [pools](Fortuna#accumulator) are ready, the rest of
+    /// the current batch is thrown away and the key reseeded right away, so
+    /// that the events added since the last request take effect.
+    fn fill_bytes(&mut self, out: &mut [u8]) {
+        if self.accumulator.ready() {
+            self.discard_batch();
+        }
+        let mut filled = 0;
+        while filled < out.len() {
+            if self.used == self.batch.len() {
+                self.refill();
+            }
+            let len = (out.len() - filled).min(self.batch.len() - self.used);
+            out[filled..filled + len].copy_from_slice(&self.batch[self.used..self.used + len]);
+            self.batch[self.used..self.used + len].zeroize();
+            filled += len;
+            self.used += len;
+        }
+    }
+
+    /// The seed is hashed together with the current key into the key for the
+    /// next output, and the rest of the current batch is thrown away.
+    fn reseed(&mut self, seed: &[u8]) {
+        let key = Zeroizing::new(match self.key.take() {
+            Some(key) => key,
+            None => self.current.clone().unwrap_or_default(),
+        });
+        self.key = Some(canonical::hash_fields(
+            &self.hash,
+            &[b"fortuna reseed", key.as_ref(), seed],
+        ));
+        self.discard_batch();
+    }
 }
 
 impl<Ent, Enc, H> IntoIterator for Fortuna<Ent, Enc, H>
@@ -71,29 +457,57 @@ where
     Ent: Entropy,
     Enc: BlockEncrypt,
     H: Hash<Digest = Enc::EncryptionKey>,
-    Enc::EncryptionBlock: IntoIterator<Item = u8> + AsMut<[u8]> + Default,
-    Enc::EncryptionKey: 'static + AsRef<[u8]> + Clone + Default,
+    Enc::EncryptionBlock: IntoIterator<Item = u8> + AsMut<[u8]> + Default + Clone,
+    Enc::EncryptionKey: 'static + AsRef<[u8]> + AsMut<[u8]> + Clone + Default,
 {
     type Item = u8;
 
     type IntoIter = impl Iterator<Item = u8>;
 
     fn into_iter(mut self) -> Self::IntoIter {
-        let mut key = Enc::EncryptionKey::default();
         iter::repeat_with(move || {
-            // Fetch random bytes from the source of entropy to use for reseeding.
-            let mut seed = [0; SEED_SIZE];
-            self.entropy.get(&mut seed);
-            // As part of the reseed, the cipher key is updated by hashing the old key and
-            // the entropy bytes.
-            let mut key_and_seed = Vec::new();
-            key_and_seed.extend(key.as_ref());
-            key_and_seed.extend(seed);
-            key = self.hash.hash(&key_and_seed);
-
-            // Generate RESEED_SIZE pseudorandom bytes via the block cipher.
-            self.ctr.encrypt(vec![0; RESEED_SIZE], key.clone()).unwrap()
+            let mut byte = [0];
+            self.fill_bytes(&mut byte);
+            byte[0]
         })
-        .flatten()
+    }
+}
+
+impl<Ent, Enc, H> Fortuna<Ent, Enc, H>
+where
+    Ent: Entropy,
+    Enc: BlockEncrypt,
+    H: Hash<Digest = Enc::EncryptionKey>,
+    Enc::EncryptionBlock: IntoIterator<Item = u8> + AsMut<[u8]> + Default + Clone,
+    Enc::EncryptionKey: 'static + AsRef<[u8]> + AsMut<[u8]> + Clone + Default,
+{
+    /// Wipe the rest of the current batch, so that the next output starts a
+    /// new one.
+    fn discard_batch(&mut self) {
+        self.batch[self.used..].zeroize();
+        self.used = self.batch.len();
+    }
+
+    /// Reseed, unless the first key was already derived for a substream, and
+    /// generate the next batch of RESEED_SIZE bytes via the block cipher. The
+    /// previous key is wiped.
+    fn refill(&mut self) {
+        let next = match self.key.take() {
+            Some(seeded) => seeded,
+            None => {
+                let current = Zeroizing::new(self.current.take().unwrap_or_default());
+                self.next_key(&current)
+            }
+        };
+        if let Some(current) = &mut self.current {
+            current.zeroize();
+        }
+        self.current = Some(next.clone());
+        // Encrypting zeros gives the keystream itself, so take it directly.
+        self.batch = self
+            .ctr
+            .keystream_bytes(next, 0..u64::try_from(RESEED_SIZE).unwrap())
+            .unwrap();
+        self.used = 0;
     }
 }
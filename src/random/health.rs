@@ -0,0 +1,164 @@
+use {crate::Entropy, docext::docext, std::fmt};
+
+/// Thresholds for the continuous health tests run by [`HealthTested`].
+///
+/// The thresholds depend on the _claimed min-entropy_ $H$ of each sample, i.e.
+/// how many bits of entropy every byte from the source is assumed to contain,
+/// and the acceptable false positive probability $\alpha$. NIST SP 800-90B
+/// recommends $\alpha = 2^{-20}$, and the cutoffs are computed as
+///
+/// $$
+/// C_{RCT} = 1 + \left\lceil \frac{-\log_2 \alpha}{H} \right\rceil \\
+/// C_{APT} = 1 + \mathrm{CRITBINOM}(W, 2^{-H}, 1 - \alpha)
+/// $$
+///
+/// where $W$ is the window size of the adaptive proportion test, and
+/// $\mathrm{CRITBINOM}(n, p, q)$ is the smallest $k$ such that a binomial
+/// random variable with $n$ trials and success probability $p$ is at most $k$
+/// with probability at least $q$.
+///
+/// The [default](Default) thresholds are computed for 8-bit samples with a
+/// conservative claim of $H = 4$ bits of entropy per sample and $\alpha =
+/// 2^{-20}$, with the window $W = 512$ recommended for non-binary samples.
+/// They are meant to catch sources which break down catastrophically, not to
+/// estimate the entropy of a working source.
+#[docext]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HealthTestConfig {
+    /// The repetition count test fails when this many identical samples are
+    /// seen in a row.
+    pub repetition_cutoff: usize,
+    /// The number of samples in each window of the adaptive proportion test.
+    pub proportion_window: usize,
+    /// The adaptive proportion test fails when the first sample of a window
+    /// occurs this many times within the window.
+    pub proportion_cutoff: usize,
+}
+
+impl Default for HealthTestConfig {
+    fn default() -> Self {
+        Self {
+            repetition_cutoff: 6,
+            proportion_window: 512,
+            proportion_cutoff: 62,
+        }
+    }
+}
+
+/// A source of [entropy](crate::Entropy) which continuously runs the health
+/// tests required by [NIST SP
+/// 800-90B](https://nvlpubs.nist.gov/nistpubs/SpecialPublications/NIST.SP.800-90B.pdf)
+/// over the bytes produced by another source.
+///
+/// Entropy sources fail in practice: hardware breaks and gets stuck producing
+/// the same value, or starts producing heavily biased output. Since the output
+/// of an entropy source is usually hashed before use, such failures are
+/// invisible downstream, and every key generated afterwards is weak. The
+/// health tests are designed to detect these failures quickly, with a small
+/// probability of false positives. Each byte is one sample, and two tests are
+/// run (Section 4.4 of the specification):
+///
+/// - The _repetition count test_ detects a source which gets stuck on a single
+///   value. It counts how many times in a row the same sample has been seen,
+///   and fails if the count reaches the [cutoff](HealthTestConfig).
+/// - The _adaptive proportion test_ detects a source which starts producing one
+///   value much more often than it should. The samples are split into windows.
+///   The first sample of each window is noted, and the test fails if it occurs
+///   too many times within the window.
+///
+/// The [`Entropy`] trait has no way of reporting errors, so the bytes are
+/// always passed through unchanged. Once a test fails, the source is marked
+/// as unhealthy for good, and the caller must check
+/// [`is_healthy`](HealthTested::is_healthy) before using the output.
+///
+/// The [`Debug`](fmt::Debug) output leaves out the most recent samples, since
+/// they are raw entropy which may end up in a key.
+#[docext]
+pub struct HealthTested<E> {
+    source: E,
+    config: HealthTestConfig,
+    failures: usize,
+    /// The last sample, and how many times in a row it has been seen.
+    repetition: Option<(u8, usize)>,
+    /// The first sample of the current window, how many times it has occurred
+    /// within the window, and how many samples the window contains so far.
+    proportion: Option<(u8, usize, usize)>,
+}
+
+impl<E: fmt::Debug> fmt::Debug for HealthTested<E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("HealthTested")
+            .field("source", &self.source)
+            .field("config", &self.config)
+            .field("failures", &self.failures)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<E> HealthTested<E> {
+    /// Run the health tests with the [default thresholds](HealthTestConfig).
+    pub fn new(source: E) -> Self {
+        Self::with_config(source, HealthTestConfig::default())
+    }
+
+    pub fn with_config(source: E, config: HealthTestConfig) -> Self {
+        Self {
+            source,
+            config,
+            failures: 0,
+            repetition: None,
+            proportion: None,
+        }
+    }
+
+    /// Returns `false` if any of the health tests has ever failed.
+    pub fn is_healthy(&self) -> bool {
+        self.failures == 0
+    }
+
+    /// The number of times the health tests have failed. Both tests reset
+    /// after failing, so a source which stays broken keeps failing.
+    pub fn failures(&self) -> usize {
+        self.failures
+    }
+
+    /// Run both tests on a single sample.
+    fn test(&mut self, sample: u8) {
+        // Repetition count test.
+        let count = match self.repetition {
+            Some((last, count)) if last == sample => count + 1,
+            _ => 1,
+        };
+        if count >= self.config.repetition_cutoff {
+            self.failures += 1;
+            self.repetition = None;
+        } else {
+            self.repetition = Some((sample, count));
+        }
+
+        // Adaptive proportion test.
+        self.proportion = match self.proportion {
+            None => Some((sample, 1, 1)),
+            Some((first, count, len)) => {
+                let count = count + usize::from(sample == first);
+                if count >= self.config.proportion_cutoff {
+                    self.failures += 1;
+                    None
+                } else if len + 1 == self.config.proportion_window {
+                    None
+                } else {
+                    Some((first, count, len + 1))
+                }
+            }
+        };
+    }
+}
+
+impl<E: Entropy> Entropy for HealthTested<E> {
+    fn get(&mut self, buf: &mut [u8]) {
+        self.source.get(buf);
+        for &sample in buf.iter() {
+            self.test(sample);
+        }
+    }
+}
@@ -0,0 +1,41 @@
+use {
+    crate::Entropy,
+    std::{hint, time::Instant},
+};
+
+/// Number of iterations of the busy loop which is timed for every bit.
+const SPIN_ITERATIONS: u32 = 64;
+
+/// A source of [entropy](crate::Entropy) which measures the timing jitter of
+/// the CPU.
+///
+/// The time it takes to run the same busy loop varies slightly from run to run,
+/// due to effects such as cache misses, interrupts, frequency scaling, and
+/// scheduling. This source repeatedly times a short busy loop using
+/// [`Instant::now`] and collects the lowest bit of each measurement.
+///
+/// # Security
+///
+/// Timing jitter on its own is a _weak_ source of entropy. The amount of
+/// randomness in the measurements depends heavily on the hardware and the
+/// timer resolution, can be very low on idle or virtualized machines, and may
+/// be influenced by an attacker who shares the machine. It should only ever be
+/// used as one of several sources in an [entropy pool](crate::EntropyPool),
+/// with a high weight, and never as the only source of entropy.
+#[derive(Debug, Default)]
+pub struct TimingJitter(());
+
+impl Entropy for TimingJitter {
+    fn get(&mut self, buf: &mut [u8]) {
+        for b in buf.iter_mut() {
+            *b = (0..u8::BITS).fold(0, |byte, _| {
+                let start = Instant::now();
+                for i in 0..SPIN_ITERATIONS {
+                    hint::black_box(i);
+                }
+                let elapsed = start.elapsed().as_nanos();
+                (byte << 1) | u8::from(elapsed & 1 == 1)
+            });
+        }
+    }
+}
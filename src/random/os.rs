@@ -0,0 +1,36 @@
+use {
+    crate::Entropy,
+    std::{fs::File, io::Read},
+};
+
+/// The device from which the kernel serves random bytes.
+const DEVICE: &str = "/dev/urandom";
+
+/// A source of [entropy](crate::Entropy) backed by the random number generator
+/// of the operating system.
+///
+/// The kernel collects entropy from interrupts, device timings and hardware
+/// random number generators, and serves it through `/dev/urandom` once it has
+/// been seeded. This is the source which seeds the generators of most other
+/// libraries, and the one to use unless there is a specific reason not to.
+///
+/// Only unix-like systems have `/dev/urandom`, so the source is only available
+/// there. Elsewhere, whatever sources the platform offers can be combined in an
+/// [entropy pool](crate::EntropyPool), but [timing
+/// jitter](crate::TimingJitter) alone is not enough.
+///
+/// # Panics
+///
+/// If the device can't be opened or read. A source of entropy has no way to
+/// report an error, and carrying on without entropy would silently make every
+/// key generated from it predictable.
+#[derive(Debug, Default)]
+pub struct OsEntropy(());
+
+impl Entropy for OsEntropy {
+    fn get(&mut self, buf: &mut [u8]) {
+        File::open(DEVICE)
+            .and_then(|mut device| device.read_exact(buf))
+            .expect("the random number generator of the operating system is unavailable");
+    }
+}
@@ -0,0 +1,108 @@
+use {
+    crate::{
+        canonical,
+        util::{CollectVec, Zeroize},
+        Entropy,
+        Sha256,
+    },
+    docext::docext,
+    std::{fmt, iter},
+};
+
+/// A source of [entropy](crate::Entropy) which combines several other sources.
+///
+/// Real systems usually have access to multiple sources of entropy of differing
+/// quality, such as the operating system, hardware random number generators,
+/// and timing measurements. Relying on a single source is risky, since a broken
+/// or compromised source silently breaks everything built on top of it. The
+/// pool instead polls every registered source and hashes all of their
+/// contributions together, so the output is unpredictable as long as _any_ of
+/// the sources is.
+///
+/// Each source is registered with a weight, which is the number of bytes
+/// requested from the source for every byte of output. Sources of low quality,
+/// such as [timing jitter](crate::TimingJitter), should be given a higher
+/// weight to compensate for the little entropy each byte contains.
+///
+/// The pool keeps a state $s$, which starts out as zeros. Every request hashes
+/// the contributions into the state with [SHA-256](Sha256), and the output is
+/// produced from the new state, prefixed by a counter to produce as many bytes
+/// as requested:
+///
+/// $$
+/// s_n = H(s_{n-1} \parallel c) \\
+/// \text{output} = H(0 \parallel s_n) \parallel H(1 \parallel s_n) \parallel
+/// \dots
+/// $$
+///
+/// where $c$ lists the contributions from all sources, and the state, the
+/// counter and each contribution are [length-prefixed](crate::canonical).
+/// Since the state carries over, every output depends on all contributions
+/// gathered so far, and not only on those of the current request: sources which
+/// fail or repeat themselves for a while still don't make the pool repeat
+/// itself.
+#[docext]
+#[derive(Default)]
+pub struct EntropyPool {
+    sources: Vec<(Box<dyn Entropy>, usize)>,
+    hash: Sha256,
+    state: [u8; 32],
+}
+
+impl EntropyPool {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Register a source of entropy. For every byte of output, `weight` bytes
+    /// are requested from the source.
+    pub fn add_source(mut self, source: Box<dyn Entropy>, weight: usize) -> Self {
+        self.sources.push((source, weight));
+        self
+    }
+}
+
+impl Entropy for EntropyPool {
+    fn get(&mut self, buf: &mut [u8]) {
+        let mut contributions = Vec::new();
+        for (source, weight) in self.sources.iter_mut() {
+            // The buffer is zeroed before every request, so a failing source which
+            // doesn't write anything simply contributes zeros.
+            let mut contribution = vec![0; buf.len() * *weight];
+            source.get(&mut contribution);
+            contributions.push(contribution);
+        }
+
+        let fields = iter::once(&self.state[..])
+            .chain(contributions.iter().map(Vec::as_slice))
+            .collect_vec();
+        self.state = canonical::hash_fields(&self.hash, &fields);
+        contributions.iter_mut().for_each(Zeroize::zeroize);
+
+        // The counter is 8 bytes long and the state 32, so the output blocks
+        // can't collide with the states.
+        for (i, chunk) in buf.chunks_mut(32).enumerate() {
+            let counter = u64::try_from(i).unwrap().to_le_bytes();
+            let mut digest = canonical::hash_fields(&self.hash, &[&counter, &self.state]);
+            chunk.copy_from_slice(&digest[..chunk.len()]);
+            digest.zeroize();
+        }
+    }
+}
+
+impl Drop for EntropyPool {
+    fn drop(&mut self) {
+        self.state.zeroize();
+    }
+}
+
+impl fmt::Debug for EntropyPool {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("EntropyPool")
+            .field(
+                "weights",
+                &self.sources.iter().map(|(_, w)| w).collect::<Vec<_>>(),
+            )
+            .finish()
+    }
+}
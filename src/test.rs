@@ -1,10 +1,39 @@
 mod aes;
+mod analysis;
+mod base64;
+mod canonical;
 mod cbc;
 mod cipher;
+mod cmac;
+mod codec;
+mod ct;
 mod ctr;
+mod derived;
+mod empty;
+mod entropy;
+mod etm;
 mod fortuna;
+mod forward;
+mod fuzz;
+mod gcm;
+mod group;
 mod hash;
+mod hex;
+mod hkdf;
 mod hmac;
+mod jose;
+mod laws;
+mod mct;
+mod no_panic;
 mod padding;
+mod prime;
 mod random;
+mod redact;
+mod resize;
 mod secp256k1;
+mod secp256r1;
+mod small_buf;
+mod testing;
+mod trace;
+mod vectors;
+mod zeroize;
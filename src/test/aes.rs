@@ -1,6 +1,16 @@
 //! AES test vectors, from the specification.
 
-use crate::{Aes128, Aes192, Aes256, BlockDecrypt, BlockEncrypt};
+use crate::{
+    aes::{self, Gf256, RoundTrace, State, Transformation, INV_S_BOX, RCON, S_BOX},
+    Aes128,
+    Aes192,
+    Aes256,
+    BlockDecrypt,
+    BlockEncrypt,
+    CipherDecrypt,
+    CipherEncrypt,
+    Ctr,
+};
 
 #[test]
 pub fn encrypt_128() {
@@ -183,3 +193,301 @@ pub fn decrypt_256() {
         ]
     )
 }
+
+/// The cipher example from Appendix B of the AES specification. Every
+/// intermediate state must match the state printed in the appendix.
+#[test]
+pub fn appendix_b() {
+    // Start of round, after SubBytes, after ShiftRows, after MixColumns, and the
+    // round key value, for rounds 1 through 10.
+    let rounds = [
+        (
+            "193de3bea0f4e22b9ac68d2ae9f84808",
+            "d42711aee0bf98f1b8b45de51e415230",
+            "d4bf5d30e0b452aeb84111f11e2798e5",
+            Some("046681e5e0cb199a48f8d37a2806264c"),
+            "a0fafe1788542cb123a339392a6c7605",
+        ),
+        (
+            "a49c7ff2689f352b6b5bea43026a5049",
+            "49ded28945db96f17f39871a7702533b",
+            "49db873b453953897f02d2f177de961a",
+            Some("584dcaf11b4b5aacdbe7caa81b6bb0e5"),
+            "f2c295f27a96b9435935807a7359f67f",
+        ),
+        (
+            "aa8f5f0361dde3ef82d24ad26832469a",
+            "ac73cf7befc111df13b5d6b545235ab8",
+            "acc1d6b8efb55a7b1323cfdf457311b5",
+            Some("75ec0993200b633353c0cf7cbb25d0dc"),
+            "3d80477d4716fe3e1e237e446d7a883b",
+        ),
+        (
+            "486c4eee671d9d0d4de3b138d65f58e7",
+            "52502f2885a45ed7e311c807f6cf6a94",
+            "52a4c89485116a28e3cf2fd7f6505e07",
+            Some("0fd6daa9603138bf6fc0106b5eb31301"),
+            "ef44a541a8525b7fb671253bdb0bad00",
+        ),
+        (
+            "e0927fe8c86363c0d9b1355085b8be01",
+            "e14fd29be8fbfbba35c89653976cae7c",
+            "e1fb967ce8c8ae9b356cd2ba974ffb53",
+            Some("25d1a9adbd11d168b63a338e4c4cc0b0"),
+            "d4d1c6f87c839d87caf2b8bc11f915bc",
+        ),
+        (
+            "f1006f55c1924cef7cc88b325db5d50c",
+            "a163a8fc784f29df10e83d234cd503fe",
+            "a14f3dfe78e803fc10d5a8df4c632923",
+            Some("4b868d6d2c4a8980339df4e837d218d8"),
+            "6d88a37a110b3efddbf98641ca0093fd",
+        ),
+        (
+            "260e2e173d41b77de86472a9fdd28b25",
+            "f7ab31f02783a9ff9b4340d354b53d3f",
+            "f783403f27433df09bb531ff54aba9d3",
+            Some("1415b5bf461615ec274656d7342ad843"),
+            "4e54f70e5f5fc9f384a64fb24ea6dc4f",
+        ),
+        (
+            "5a4142b11949dc1fa3e019657a8c040c",
+            "be832cc8d43b86c00ae1d44dda64f2fe",
+            "be3bd4fed4e1f2c80a642cc0da83864d",
+            Some("00512fd1b1c889ff54766dcdfa1b99ea"),
+            "ead27321b58dbad2312bf5607f8d292f",
+        ),
+        (
+            "ea835cf00445332d655d98ad8596b0c5",
+            "87ec4a8cf26ec3d84d4c46959790e7a6",
+            "876e46a6f24ce78c4d904ad897ecc395",
+            Some("473794ed40d4e4a5a3703aa64c9f42bc"),
+            "ac7766f319fadc2128d12941575c006e",
+        ),
+        (
+            "eb40f21e592e38848ba113e71bc342d2",
+            "e9098972cb31075f3d327d94af2e2cb5",
+            "e9317db5cb322c723d2e895faf090794",
+            None,
+            "d014f9a8c9ee2589e13f0cc8b6630ca6",
+        ),
+    ];
+    let (ciphertext, trace) = Aes128::default().encrypt_traced(
+        [
+            0x32, 0x43, 0xf6, 0xa8, 0x88, 0x5a, 0x30, 0x8d, 0x31, 0x31, 0x98, 0xa2, 0xe0, 0x37,
+            0x07, 0x34,
+        ],
+        [
+            0x2b, 0x7e, 0x15, 0x16, 0x28, 0xae, 0xd2, 0xa6, 0xab, 0xf7, 0x15, 0x88, 0x09, 0xcf,
+            0x4f, 0x3c,
+        ],
+    );
+
+    let mut trace = trace.into_iter();
+    let mut expect = |round, transformation, state: &str| {
+        assert_eq!(
+            trace.next(),
+            Some(RoundTrace {
+                round,
+                transformation,
+                state: block(state).into(),
+            })
+        );
+    };
+    expect(0, Transformation::AddRoundKey, rounds[0].0);
+    for (i, (start, sub_bytes, shift_rows, mix_columns, round_key)) in rounds.iter().enumerate() {
+        let round = i + 1;
+        // The start of the round is the result of the previous AddRoundKey.
+        if round > 1 {
+            expect(round - 1, Transformation::AddRoundKey, start);
+        }
+        expect(round, Transformation::SubBytes, sub_bytes);
+        expect(round, Transformation::ShiftRows, shift_rows);
+        let before_round_key = match mix_columns {
+            Some(mix_columns) => {
+                expect(round, Transformation::MixColumns, mix_columns);
+                mix_columns
+            }
+            None => shift_rows,
+        };
+        // The round key is XORed into the state by AddRoundKey.
+        let mut next = block(before_round_key);
+        next.iter_mut()
+            .zip(block(round_key))
+            .for_each(|(s, k)| *s ^= k);
+        if let Some((next_start, ..)) = rounds.get(round) {
+            assert_eq!(next, block(next_start));
+        } else {
+            assert_eq!(next, ciphertext);
+        }
+    }
+    expect(
+        10,
+        Transformation::AddRoundKey,
+        "3925841d02dc09fbdc118597196a0b32",
+    );
+    assert_eq!(trace.next(), None);
+}
+
+/// The state is printed column-major, in the same layout as the specification.
+#[test]
+pub fn state_display() {
+    let state = State::from(block("193de3bea0f4e22b9ac68d2ae9f84808"));
+    let expected = "19 a0 9a e9\n3d f4 c6 f8\ne3 e2 8d 48\nbe 2b 2a 08";
+    assert_eq!(state.to_string(), expected);
+    assert_eq!(format!("{state:?}"), expected);
+    assert_eq!(state[(1, 2)], 0xc6);
+    assert_eq!(
+        <[u8; 16]>::from(state),
+        block("193de3bea0f4e22b9ac68d2ae9f84808")
+    );
+}
+
+/// Assert that MixColumns mixes every column of a state of any number of
+/// columns, and that ShiftRows only shifts the first 16 bytes of a longer
+/// state, as the transformations on [`State`] do for a single block.
+#[test]
+pub fn transformations_on_slices() {
+    let block = block("d4bf5d30e0b452aeb84111f11e2798e5");
+    let mut state = State::from(block);
+    state.mix_columns();
+    let mut columns = [0; 24];
+    columns[..16].copy_from_slice(&block);
+    columns[16..].copy_from_slice(&block[..8]);
+    aes::mix_columns(&mut columns);
+    assert_eq!(columns[..16], *state.as_ref());
+    assert_eq!(columns[16..], state.as_ref()[..8]);
+    aes::inv_mix_columns(&mut columns);
+    assert_eq!(columns[..16], block);
+
+    let mut state = State::from(block);
+    state.shift_rows();
+    let mut bytes = [0xab; 20];
+    bytes[..16].copy_from_slice(&block);
+    aes::shift_rows(&mut bytes);
+    assert_eq!(bytes[..16], *state.as_ref());
+    assert_eq!(bytes[16..], [0xab; 4]);
+    aes::inv_shift_rows(&mut bytes);
+    assert_eq!(bytes[..16], block);
+}
+
+/// The affine transformation of the S-box, defined in Equation 5.1 of the AES
+/// specification: bit $i$ of the result is the XOR of bits $i$, $i + 4$, $i +
+/// 5$, $i + 6$ and $i + 7$ (modulo 8) of the input, and bit $i$ of `0x63`.
+fn affine(b: u8) -> u8 {
+    b ^ b.rotate_left(1) ^ b.rotate_left(2) ^ b.rotate_left(3) ^ b.rotate_left(4) ^ 0x63
+}
+
+/// Regenerate the S-box from its algebraic definition, the inverse in
+/// $GF(2^8)$ followed by the affine transformation.
+#[test]
+pub fn s_box_from_field() {
+    for b in 0..=255 {
+        assert_eq!(S_BOX[usize::from(b)], affine(Gf256(b).inv().0), "{b:#04x}");
+        assert_eq!(INV_S_BOX[usize::from(S_BOX[usize::from(b)])], b);
+    }
+}
+
+/// The round constants are the powers of $x$.
+#[test]
+pub fn rcon_from_field() {
+    for (i, &rcon) in RCON.iter().enumerate().skip(1) {
+        assert_eq!(Gf256(0x02).pow(u32::try_from(i - 1).unwrap()), Gf256(rcon));
+    }
+}
+
+#[test]
+pub fn gf256_mul() {
+    // Example from Section 4.2 of the specification.
+    assert_eq!(Gf256(0x57) * Gf256(0x83), Gf256(0xc1));
+    assert_eq!(Gf256(0x57) * Gf256(0x13), Gf256(0xfe));
+    for b in 0..=255 {
+        let g = Gf256(b);
+        assert_eq!(aes::times_02(b), (g * Gf256(0x02)).0);
+        assert_eq!(aes::times_03(b), aes::times_02(b) ^ b);
+        assert_eq!(aes::times_04(b), aes::times_02(aes::times_02(b)));
+        assert_eq!(aes::times_08(b), aes::times_02(aes::times_04(b)));
+        assert_eq!(aes::times_09(b), aes::times_08(b) ^ b);
+        assert_eq!(aes::times_0b(b), aes::times_08(b) ^ aes::times_03(b));
+        assert_eq!(aes::times_0d(b), aes::times_08(b) ^ aes::times_04(b) ^ b);
+        assert_eq!(
+            aes::times_0e(b),
+            aes::times_08(b) ^ aes::times_04(b) ^ aes::times_02(b)
+        );
+        assert_eq!(g * Gf256::ONE, g);
+        assert_eq!(g * Gf256::ZERO, Gf256::ZERO);
+        assert_eq!(g + g, Gf256::ZERO);
+    }
+}
+
+#[test]
+pub fn gf256_inv() {
+    assert_eq!(Gf256::ZERO.inv(), Gf256::ZERO);
+    assert_eq!(Gf256(0x53).inv(), Gf256(0xca));
+    for b in 1..=255 {
+        let g = Gf256(b);
+        assert_eq!(g * g.inv(), Gf256::ONE, "{b:#04x}");
+        assert_eq!(g.inv().inv(), g);
+        assert_eq!(g.pow(255), Gf256::ONE);
+        assert_eq!(g.pow(0), Gf256::ONE);
+        assert_eq!(g.pow(3), g * g * g);
+    }
+}
+
+/// Assert that a [key schedule](aes::AesKeySchedule) gives the same blocks as
+/// expanding the key for every block, for every key size.
+#[test]
+pub fn key_schedule() {
+    let blocks = (0..8u8)
+        .map(|i| [i.wrapping_mul(37); 16])
+        .collect::<Vec<_>>();
+
+    let key = [0x2b; 16];
+    let schedule = Aes128::default().schedule(key);
+    for &b in &blocks {
+        let ciphertext = Aes128::default().encrypt(b, key);
+        assert_eq!(schedule.encrypt_block(b), ciphertext);
+        assert_eq!(schedule.decrypt_block(ciphertext), b);
+    }
+
+    let key = [0x8e; 24];
+    let schedule = Aes192::default().schedule(key);
+    for &b in &blocks {
+        let ciphertext = Aes192::default().encrypt(b, key);
+        assert_eq!(schedule.encrypt_block(b), ciphertext);
+        assert_eq!(schedule.decrypt_block(ciphertext), b);
+    }
+
+    let key = [0x60; 32];
+    let schedule = Aes256::default().schedule(key);
+    for &b in &blocks {
+        let ciphertext = Aes256::default().encrypt(b, key);
+        assert_eq!(schedule.encrypt(b, ()), ciphertext);
+        assert_eq!(schedule.decrypt(ciphertext, ()), b);
+    }
+    assert_eq!(format!("{schedule:?}"), "AesKeySchedule { rounds: 14, .. }");
+}
+
+/// Assert that a block mode over a [key schedule](aes::AesKeySchedule) gives
+/// the same ciphertext as over the cipher itself.
+#[test]
+pub fn key_schedule_ctr() {
+    let key = [0x2b; 16];
+    let data = (0..1000).map(|i| i as u8).collect::<Vec<_>>();
+    let expected = Ctr::new(Aes128::default(), 7)
+        .unwrap()
+        .encrypt_from(&data, key)
+        .unwrap();
+    let ctr = Ctr::new(Aes128::default().schedule(key), 7).unwrap();
+    let ciphertext = ctr.encrypt_from(&data, ()).unwrap();
+    assert_eq!(ciphertext, expected);
+    assert_eq!(ctr.decrypt(ciphertext, ()).unwrap(), data);
+}
+
+fn block(hex: &str) -> [u8; 16] {
+    let mut block = [0; 16];
+    block.iter_mut().enumerate().for_each(|(i, b)| {
+        *b = u8::from_str_radix(&hex[2 * i..2 * i + 2], 16).unwrap();
+    });
+    block
+}
@@ -0,0 +1,91 @@
+use crate::{
+    aes,
+    analysis::{avalanche_matrix, ddt, hamming_distance, lat},
+    testing::rng,
+    Aes128,
+    BlockEncrypt,
+};
+
+#[test]
+fn hamming() {
+    assert_eq!(hamming_distance(&[], &[]), 0);
+    assert_eq!(hamming_distance(&[0xff, 0x00], &[0xff, 0x00]), 0);
+    assert_eq!(hamming_distance(&[0xff, 0x00], &[0x00, 0x01]), 9);
+    assert_eq!(hamming_distance(b"karolin", b"kathrin"), 9);
+}
+
+#[test]
+#[should_panic]
+fn hamming_unequal_lengths() {
+    hamming_distance(&[0], &[0, 0]);
+}
+
+/// The best differential through the AES S-box holds for 4 out of 256 inputs.
+#[test]
+fn aes_ddt() {
+    let table = ddt(&aes::S_BOX);
+    assert_eq!(table[0][0], 256);
+    assert!(table.iter().all(|row| row.iter().sum::<u16>() == 256));
+    assert_eq!(table[1..].iter().flatten().max(), Some(&4));
+}
+
+/// The AES S-box has no linear approximation with a bias above 16/256.
+#[test]
+fn aes_lat() {
+    let table = lat(&aes::S_BOX);
+    assert_eq!(table[0][0], 128);
+    let max = table
+        .iter()
+        .flatten()
+        .skip(1)
+        .map(|entry| entry.abs())
+        .max();
+    assert_eq!(max, Some(16));
+}
+
+/// A linear S-box is the worst case for both tables: every differential and
+/// every matching linear approximation holds with certainty.
+#[test]
+fn identity_sbox() {
+    let sbox = std::array::from_fn(|i| u8::try_from(i).unwrap());
+    let table = ddt(&sbox);
+    assert!((0..256).all(|d| table[d][d] == 256));
+    let table = lat(&sbox);
+    assert!((0..256).all(|m| table[m][m] == 128));
+}
+
+/// A cipher which XORs the block with the key has no avalanche at all.
+#[test]
+fn xor_avalanche() {
+    struct Xor;
+
+    impl BlockEncrypt for Xor {
+        type EncryptionBlock = [u8; 2];
+        type EncryptionKey = [u8; 2];
+
+        fn encrypt(&self, data: [u8; 2], key: [u8; 2]) -> [u8; 2] {
+            [data[0] ^ key[0], data[1] ^ key[1]]
+        }
+    }
+
+    let mut rng = rng();
+    let matrix = avalanche_matrix(&Xor, 10, &mut rng);
+    assert_eq!(matrix.len(), 16);
+    for (i, row) in matrix.iter().enumerate() {
+        for (j, &p) in row.iter().enumerate() {
+            assert_eq!(p, if i == j { 1.0 } else { 0.0 });
+        }
+    }
+}
+
+/// In AES, flipping any input bit flips each output bit about half the time.
+#[test]
+fn aes_avalanche() {
+    let mut rng = rng();
+    let matrix = avalanche_matrix(&Aes128::default(), 64, &mut rng);
+    assert_eq!(matrix.len(), 128);
+    let entries: Vec<f64> = matrix.into_iter().flatten().collect();
+    let mean = entries.iter().sum::<f64>() / entries.len() as f64;
+    assert!((mean - 0.5).abs() < 0.01);
+    assert!(entries.iter().all(|&p| (0.1..0.9).contains(&p)));
+}
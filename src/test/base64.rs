@@ -0,0 +1,124 @@
+use {
+    crate::base64::{self, Alphabet, Base64Error},
+    rand::Rng,
+};
+
+/// The base64 test vectors from RFC 4648.
+#[test]
+fn base64_rfc4648() {
+    for (input, output) in [
+        ("", ""),
+        ("f", "Zg=="),
+        ("fo", "Zm8="),
+        ("foo", "Zm9v"),
+        ("foob", "Zm9vYg=="),
+        ("fooba", "Zm9vYmE="),
+        ("foobar", "Zm9vYmFy"),
+    ] {
+        for alphabet in [Alphabet::Standard, Alphabet::UrlSafe] {
+            assert_eq!(base64::encode(input.as_bytes(), alphabet, true), output);
+            assert_eq!(
+                base64::encode(input.as_bytes(), alphabet, false),
+                output.trim_end_matches('=')
+            );
+            assert_eq!(base64::decode(output, alphabet).unwrap(), input.as_bytes());
+            assert_eq!(
+                base64::decode(output.trim_end_matches('='), alphabet).unwrap(),
+                input.as_bytes()
+            );
+        }
+    }
+}
+
+/// The two alphabets only differ in the characters for 62 and 63.
+#[test]
+fn base64_alphabets() {
+    // 0xfb 0xff 0xbf encodes the values 62, 63, 62, 63.
+    let data = [0xfb, 0xff, 0xbf];
+    assert_eq!(base64::encode(&data, Alphabet::Standard, true), "+/+/");
+    assert_eq!(base64::encode(&data, Alphabet::UrlSafe, true), "-_-_");
+    assert_eq!(base64::decode("+/+/", Alphabet::Standard).unwrap(), data);
+    assert_eq!(base64::decode("-_-_", Alphabet::UrlSafe).unwrap(), data);
+    assert_eq!(
+        base64::decode("-_-_", Alphabet::Standard),
+        Err(Base64Error::InvalidCharacter { index: 0 })
+    );
+    assert_eq!(
+        base64::decode("+/+/", Alphabet::UrlSafe),
+        Err(Base64Error::InvalidCharacter { index: 0 })
+    );
+
+    // Every value encodes to the expected character.
+    let values: Vec<u8> = (0..64).collect();
+    let standard = "ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let data = pack(&values);
+    assert_eq!(base64::encode(&data, Alphabet::Standard, true), standard);
+    assert_eq!(
+        base64::encode(&data, Alphabet::UrlSafe, true),
+        standard.replace('+', "-").replace('/', "_")
+    );
+}
+
+/// Random data of every length modulo 3 survives a round trip, with and
+/// without padding.
+#[test]
+fn base64_round_trip() {
+    let mut rng = rand::thread_rng();
+    for len in 0..96usize {
+        let data: Vec<u8> = (0..len).map(|_| rng.gen()).collect();
+        for alphabet in [Alphabet::Standard, Alphabet::UrlSafe] {
+            let padded = base64::encode(&data, alphabet, true);
+            assert_eq!(padded.len(), len.div_ceil(3) * 4);
+            assert_eq!(base64::decode(&padded, alphabet).unwrap(), data);
+            let unpadded = base64::encode(&data, alphabet, false);
+            assert_eq!(unpadded, padded.trim_end_matches('='));
+            assert_eq!(base64::decode(&unpadded, alphabet).unwrap(), data);
+        }
+    }
+}
+
+/// Invalid input is rejected with a precise error.
+#[test]
+fn base64_invalid() {
+    let decode = |s| base64::decode(s, Alphabet::Standard);
+    // A single character in the last group can't hold a byte.
+    assert_eq!(decode("Z"), Err(Base64Error::InvalidLength { len: 1 }));
+    assert_eq!(decode("Zm9vY"), Err(Base64Error::InvalidLength { len: 5 }));
+    assert_eq!(decode("Z==="), Err(Base64Error::InvalidLength { len: 4 }));
+    // Padded input must be a multiple of 4 characters.
+    assert_eq!(decode("Zg="), Err(Base64Error::InvalidLength { len: 3 }));
+    // The amount of padding must match the data.
+    assert_eq!(decode("Zm8=="), Err(Base64Error::InvalidLength { len: 5 }));
+    assert_eq!(
+        decode("Zm9v===="),
+        Err(Base64Error::InvalidPadding { index: 4 })
+    );
+    // Padding in the middle is an invalid character.
+    assert_eq!(
+        decode("Zg==Zg=="),
+        Err(Base64Error::InvalidCharacter { index: 2 })
+    );
+    assert_eq!(
+        decode("Zm9v.mFy"),
+        Err(Base64Error::InvalidCharacter { index: 4 })
+    );
+    assert_eq!(
+        decode("Zm9vYmF\n"),
+        Err(Base64Error::InvalidCharacter { index: 7 })
+    );
+    // The unused bits of the last character must be zero.
+    assert_eq!(decode("Zh=="), Err(Base64Error::NonCanonical { index: 1 }));
+    assert_eq!(decode("Zm9="), Err(Base64Error::NonCanonical { index: 2 }));
+    assert_eq!(decode("Zh"), Err(Base64Error::NonCanonical { index: 1 }));
+}
+
+/// Pack 6-bit values into bytes.
+fn pack(values: &[u8]) -> Vec<u8> {
+    values
+        .chunks(4)
+        .flat_map(|c| {
+            let n = c.iter().fold(0u32, |acc, &v| (acc << 6) | u32::from(v));
+            n.to_be_bytes()[1..].to_vec()
+        })
+        .collect()
+}
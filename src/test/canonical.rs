@@ -0,0 +1,89 @@
+use crate::{
+    canonical::{encode_fields, update_field},
+    ecc::{Coordinates, Curve, Group, Num, Point, PrivateKey},
+    pubkey,
+    Hash,
+    IncrementalHash,
+    Schnorr,
+    Secp256k1,
+    Sha256,
+    SignatureScheme,
+    Transcript,
+};
+
+#[test]
+fn encoding() {
+    assert_eq!(encode_fields(&[]), b"");
+    assert_eq!(encode_fields(&[b""]), [0; 8]);
+    assert_eq!(
+        encode_fields(&[b"ab", b"c"]),
+        [
+            2, 0, 0, 0, 0, 0, 0, 0, b'a', b'b', //
+            1, 0, 0, 0, 0, 0, 0, 0, b'c',
+        ]
+    );
+}
+
+/// Moving a byte from one field into its neighbour doesn't change the plain
+/// concatenation of the fields, and so doesn't change its hash. The canonical
+/// encoding tells the two lists apart.
+#[test]
+fn shifted_boundary() {
+    let sha = Sha256::default();
+    let (a, b): (&[&[u8]], &[&[u8]]) = (&[b"ab", b"c"], &[b"a", b"bc"]);
+    assert_eq!(sha.hash(&a.concat()), sha.hash(&b.concat()));
+    assert_ne!(encode_fields(a), encode_fields(b));
+    assert_ne!(sha.hash(&encode_fields(a)), sha.hash(&encode_fields(b)));
+
+    // Empty fields are not lost either.
+    assert_ne!(encode_fields(&[b"a"]), encode_fields(&[b"a", b""]));
+    assert_ne!(encode_fields(&[b"", b"a"]), encode_fields(&[b"a", b""]));
+}
+
+/// Feeding the fields one by one gives the digest of their encoding.
+#[test]
+fn incremental() {
+    let sha = Sha256::default();
+    let fields: [&[u8]; 4] = [b"", b"literate", &[0; 100], b"crypto"];
+    let mut state = sha.init();
+    fields
+        .iter()
+        .for_each(|field| update_field(&sha, &mut state, field));
+    assert_eq!(sha.finalize(state), sha.hash(&encode_fields(&fields)));
+}
+
+/// The Schnorr challenge covers the framed public key, nonce point and
+/// message. A message carrying the bytes of the nonce point in front of it
+/// therefore gets an unrelated challenge, even though the plain
+/// concatenation of the two inputs would be the same.
+#[test]
+fn schnorr_challenge() {
+    let key = PrivateKey::<Secp256k1>::new(Num::from_le_words([0x1234567890, 0, 0, 0])).unwrap();
+    let pubkey = key.derive();
+    let mut schnorr = Schnorr::new(Secp256k1::default(), Sha256::default());
+    let (context, msg) = (b"framing", b"message");
+    let sig = schnorr.sign_with_context(key, context, msg);
+
+    let r = match Point::multi_scale(&[(sig.s(), Secp256k1::g()), (sig.e(), pubkey.point())])
+        .coordinates()
+    {
+        Coordinates::Finite(r, _) => r,
+        Coordinates::Infinity => unreachable!(),
+    };
+    let msg = pubkey::with_context(context, msg);
+    let x = pubkey.x().to_le_bytes();
+    let r = r.to_le_bytes();
+    let challenge = |x: &[u8], r: &[u8], msg: &[u8]| {
+        let mut transcript = Transcript::new(Sha256::default(), b"literate-crypto schnorr");
+        transcript.append(b"pubkey", x);
+        transcript.append(b"nonce", r);
+        transcript.append(b"msg", msg);
+        transcript.challenge_scalar::<Secp256k1>(b"challenge")
+    };
+    assert_eq!(challenge(&x, &r, &msg), sig.e());
+
+    // Fold the nonce point into the message instead.
+    let shifted = [&r[..], &msg].concat();
+    assert_eq!([&x[..], &r, &msg].concat(), [&x[..], &shifted].concat());
+    assert_ne!(challenge(&x, &[], &shifted), sig.e());
+}
@@ -1,5 +1,19 @@
 use {
-    crate::{BlockCipher, BlockDecrypt, BlockEncrypt, Cbc, CipherDecrypt, CipherEncrypt, Padding},
+    crate::{
+        hex,
+        Aes128,
+        BlockCipher,
+        BlockDecrypt,
+        BlockEncrypt,
+        Cbc,
+        CbcCts,
+        CipherDecrypt,
+        CipherEncrypt,
+        CtsTooShort,
+        Padding,
+        Pkcs7,
+    },
+    rand::Rng,
     std::convert::Infallible,
 };
 
@@ -32,6 +46,221 @@ fn cbc() {
     );
 }
 
+/// Two messages which share their first block, and differ in the second.
+const SHARED_PREFIX: [&[u8]; 2] = [
+    b"attack at dawn!! from the north",
+    b"attack at dawn!! from the south",
+];
+
+/// Document the [leakage of reusing the IV](Cbc#iv-reuse): an instance
+/// encrypts every message with the IV it was created with, so the first
+/// blocks of two messages with a common first block encrypt identically, as
+/// in ECB mode.
+///
+/// This pins down the current behavior of [`encrypt`](CipherEncrypt::encrypt).
+/// If it starts drawing a fresh IV per message, flip the first assertion.
+#[test]
+fn cbc_iv_reuse_leaks() {
+    let cbc = Cbc::new(Aes128::default(), Pkcs7::default(), [0x42; 16]);
+    let key = [7; 16];
+    let [a, b] = SHARED_PREFIX.map(|msg| cbc.encrypt_from(msg, key).unwrap());
+    assert_eq!(a[..16], b[..16], "the shared first block leaks");
+    assert_ne!(a[16..], b[16..]);
+}
+
+/// Assert that a fresh IV per message hides the common prefix, and that the
+/// messages decrypt with their IVs.
+#[test]
+fn cbc_encrypt_with_iv() {
+    let cbc = Cbc::new(Aes128::default(), Pkcs7::default(), [0x42; 16]);
+    let key = [7; 16];
+    let ivs = [[1; 16], [2; 16]];
+    let [a, b] = [0, 1].map(|i| cbc.encrypt_with_iv(SHARED_PREFIX[i].to_vec(), key, ivs[i]));
+    assert_ne!(a[..16], b[..16]);
+    assert_eq!(
+        cbc.decrypt_with_iv(a, key, ivs[0]).unwrap(),
+        SHARED_PREFIX[0]
+    );
+    assert_eq!(
+        cbc.decrypt_with_iv(b, key, ivs[1]).unwrap(),
+        SHARED_PREFIX[1]
+    );
+
+    // The configured IV is the default.
+    let c = cbc.encrypt_with_iv(SHARED_PREFIX[0].to_vec(), key, [0x42; 16]);
+    assert_eq!(c, cbc.encrypt_from(SHARED_PREFIX[0], key).unwrap());
+}
+
+/// Assert that an instance restricted to one message still decrypts it, and
+/// still encrypts with explicit IVs.
+#[test]
+fn cbc_encrypt_once() {
+    let cbc = Cbc::new(Aes128::default(), Pkcs7::default(), [0x42; 16]).encrypt_once();
+    let key = [7; 16];
+    let ciphertext = cbc.encrypt_from(SHARED_PREFIX[0], key).unwrap();
+    assert_eq!(
+        cbc.decrypt(ciphertext.clone(), key).unwrap(),
+        SHARED_PREFIX[0]
+    );
+    assert_eq!(cbc.decrypt(ciphertext, key).unwrap(), SHARED_PREFIX[0]);
+    cbc.encrypt_with_iv(SHARED_PREFIX[1].to_vec(), key, [1; 16]);
+}
+
+/// Assert that an instance restricted to one message refuses to reuse its IV.
+#[test]
+#[should_panic(expected = "CBC IV reused")]
+fn cbc_encrypt_once_reused() {
+    let cbc = Cbc::new(Aes128::default(), Pkcs7::default(), [0x42; 16]).encrypt_once();
+    let key = [7; 16];
+    cbc.encrypt_from(SHARED_PREFIX[0], key).unwrap();
+    cbc.encrypt_from(SHARED_PREFIX[1], key).unwrap();
+}
+
+/// Assert that lazy decryption yields the same plaintext as decrypting at
+/// once, for every length around the padding edge cases.
+#[test]
+fn cbc_decrypt_iter() {
+    let mut rng = rand::thread_rng();
+    let cbc = Cbc::new(Aes128::default(), Pkcs7::default(), rng.gen());
+    let key = rng.gen();
+    for len in 0..=64usize {
+        let data: Vec<u8> = (0..len).map(|_| rng.gen()).collect();
+        let ciphertext = cbc.encrypt_from(&data, key).unwrap();
+        let blocks: Vec<_> = cbc
+            .decrypt_iter(&ciphertext, key)
+            .collect::<Result<_, _>>()
+            .unwrap();
+        // All blocks but the last are whole, and a block of padding yields
+        // nothing.
+        assert_eq!(blocks.len(), len.div_ceil(16), "{len}");
+        assert!(blocks.iter().all(|block| !block.is_empty()));
+        assert_eq!(blocks.concat(), data, "{len}");
+        assert_eq!(blocks.concat(), cbc.decrypt(ciphertext, key).unwrap());
+    }
+}
+
+/// Assert that invalid padding and partial blocks are reported by the last
+/// item of lazy decryption, like by the one-shot decryption.
+#[test]
+fn cbc_decrypt_iter_invalid() {
+    let cbc = Cbc::new(Aes128::default(), Pkcs7::default(), [0x42; 16]);
+    let key = [7; 16];
+    let ciphertext = cbc.encrypt_from(&[1; 40], key).unwrap();
+
+    // Flipping a bit of the second to last block flips the same bit of the
+    // last plaintext block, which breaks its padding.
+    let mut tampered = ciphertext.clone();
+    tampered[31] ^= 0x80;
+    let items: Vec<_> = cbc.decrypt_iter(&tampered, key).collect();
+    assert_eq!(items.len(), 3);
+    assert!(items[..2].iter().all(Result::is_ok));
+    assert!(items[2].is_err());
+    assert!(cbc.decrypt(tampered, key).is_err());
+
+    for bad in [&ciphertext[..0], &ciphertext[..47], &ciphertext[..1]] {
+        let items: Vec<_> = cbc.decrypt_iter(bad, key).collect();
+        assert_eq!(items.len(), 1, "{}", bad.len());
+        assert!(items[0].is_err());
+        assert!(cbc.decrypt(bad.to_vec(), key).is_err());
+    }
+}
+
+/// Assert that a lazy decryption can be abandoned halfway, and that it is fused
+/// once it ends.
+#[test]
+fn cbc_decrypt_iter_early_end() {
+    let cbc = Cbc::new(Aes128::default(), Pkcs7::default(), [0x42; 16]);
+    let key = [7; 16];
+    let data: Vec<u8> = (0..100).collect();
+    let ciphertext = cbc.encrypt_from(&data, key).unwrap();
+
+    let prefix: Vec<_> = cbc
+        .decrypt_iter(&ciphertext, key)
+        .take(2)
+        .collect::<Result<_, _>>()
+        .unwrap();
+    assert_eq!(prefix.concat(), data[..32]);
+
+    let mut iter = cbc.decrypt_iter(&ciphertext, key);
+    assert_eq!(iter.by_ref().count(), 7);
+    assert!(iter.next().is_none());
+}
+
+/// Assert that AES-128 in CBC-CS3 mode with a zero IV matches the known-answer
+/// vectors from Appendix B of
+/// [RFC 3962](https://www.rfc-editor.org/rfc/rfc3962#appendix-B). The
+/// plaintexts are prefixes of one message.
+#[test]
+fn cbc_cts_vectors() {
+    const KEY: &[u8; 16] = b"chicken teriyaki";
+    const MSG: &[u8] = b"I would like the General Gau's Chicken, please, and wonton soup.";
+    let vectors = [
+        (17, "c6353568f2bf8cb4d8a580362da7ff7f97"),
+        (
+            31,
+            "fc00783e0efdb2c1d445d4c8eff7ed2297687268d6ecccc0c07b25e25ecfe5",
+        ),
+        (
+            32,
+            "39312523a78662d5be7fcbcc98ebf5a897687268d6ecccc0c07b25e25ecfe584",
+        ),
+        (
+            47,
+            "97687268d6ecccc0c07b25e25ecfe584b3fffd940c16a18c1b5549d2f838029e39312523a78662d5be7fc\
+             bcc98ebf5",
+        ),
+        (
+            48,
+            "97687268d6ecccc0c07b25e25ecfe5849dad8bbb96c4cdc03bc103e1a194bbd839312523a78662d5be7fc\
+             bcc98ebf5a8",
+        ),
+        (
+            64,
+            "97687268d6ecccc0c07b25e25ecfe58439312523a78662d5be7fcbcc98ebf5a84807efe836ee89a526730\
+             dbc2f7bc8409dad8bbb96c4cdc03bc103e1a194bbd8",
+        ),
+    ];
+    let cts = CbcCts::new(Aes128::default(), [0; 16]);
+    for (len, expected) in vectors {
+        let ciphertext = cts.encrypt_from(&MSG[..len], *KEY).unwrap();
+        assert_eq!(ciphertext, hex::decode(expected).unwrap(), "{len} bytes");
+        assert_eq!(cts.decrypt(ciphertext, *KEY).unwrap(), &MSG[..len]);
+    }
+}
+
+/// Assert that messages of at least one block round-trip without growing, and
+/// that shorter messages are rejected.
+#[test]
+fn cbc_cts_round_trip() {
+    let cts = CbcCts::new(Aes128::default(), [0x42; 16]);
+    let key = rand::thread_rng().gen();
+    for len in [16, 17, 31, 32, 33, 100] {
+        let data = (0..len).map(|_| rand::random()).collect::<Vec<u8>>();
+        let ciphertext = cts.encrypt_from(&data, key).unwrap();
+        assert_eq!(ciphertext.len(), len);
+        assert_eq!(cts.decrypt(ciphertext, key).unwrap(), data, "{len} bytes");
+    }
+
+    // A single block is plain CBC.
+    let cbc = Cbc::new(Aes128::default(), NoPadding, [0x42; 16]);
+    assert_eq!(
+        cts.encrypt(vec![7; 16], key).unwrap(),
+        cbc.encrypt(vec![7; 16], key).unwrap()
+    );
+
+    let err = CtsTooShort {
+        len: 15,
+        block_size: 16,
+    };
+    assert_eq!(cts.encrypt(vec![0; 15], key), Err(err));
+    assert_eq!(cts.decrypt(vec![0; 15], key), Err(err));
+    assert_eq!(cts.encrypt(Vec::new(), key).unwrap_err().len, 0);
+    assert_eq!(
+        err.to_string(),
+        "ciphertext stealing needs at least one block of 16 bytes, got 15 bytes"
+    );
+}
+
 /// Test block cipher which XORs the data with the key.
 struct TestCipher;
 
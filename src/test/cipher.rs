@@ -5,9 +5,29 @@
 //! for a random plaintext and key.
 
 use {
-    crate::{util::CollectVec, Aes128, Aes192, Aes256, Cbc, Cipher, Ctr, Ecb, Pkcs7},
+    crate::{
+        testing::{self, NoEntropy, StreamRng},
+        util::CollectVec,
+        Aes128,
+        Aes192,
+        Aes256,
+        BlockCipher,
+        BlockDecrypt,
+        BlockEncrypt,
+        Cbc,
+        CbcCts,
+        Cipher,
+        CipherDecrypt,
+        CipherEncrypt,
+        Csprng,
+        Ctr,
+        Ecb,
+        Fortuna,
+        OneTimePad,
+        Pkcs7,
+    },
     rand::Rng,
-    std::fmt,
+    std::{array, fmt, sync::Arc},
 };
 
 #[test]
@@ -36,7 +56,7 @@ fn aes_256_ecb_pkcs7() {
 
 #[test]
 fn aes_128_cbc_pkcs7() {
-    let iv = [1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15, 16];
+    let iv = Cbc::<Aes128, Pkcs7, _>::generate_iv(&mut rng());
     test(Cbc::new(Aes128::default(), Pkcs7::default(), iv), 10);
     test(Cbc::new(Aes128::default(), Pkcs7::default(), iv), 20);
     test(Cbc::new(Aes128::default(), Pkcs7::default(), iv), 30);
@@ -45,7 +65,7 @@ fn aes_128_cbc_pkcs7() {
 
 #[test]
 fn aes_192_cbc_pkcs7() {
-    let iv = [1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15, 16];
+    let iv = Cbc::<Aes192, Pkcs7, _>::generate_iv(&mut rng());
     test(Cbc::new(Aes192::default(), Pkcs7::default(), iv), 10);
     test(Cbc::new(Aes192::default(), Pkcs7::default(), iv), 20);
     test(Cbc::new(Aes192::default(), Pkcs7::default(), iv), 30);
@@ -54,7 +74,7 @@ fn aes_192_cbc_pkcs7() {
 
 #[test]
 fn aes_256_cbc_pkcs7() {
-    let iv = [1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15, 16];
+    let iv = Cbc::<Aes256, Pkcs7, _>::generate_iv(&mut rng());
     test(Cbc::new(Aes256::default(), Pkcs7::default(), iv), 10);
     test(Cbc::new(Aes256::default(), Pkcs7::default(), iv), 20);
     test(Cbc::new(Aes256::default(), Pkcs7::default(), iv), 30);
@@ -81,31 +101,164 @@ fn aes_256_ctr() {
     );
 }
 
+#[test]
+fn aes_128_cbc_cts() {
+    let iv = Cbc::<Aes128, Pkcs7, _>::generate_iv(&mut rng());
+    test(CbcCts::new(Aes128::default(), iv), 16);
+    test(CbcCts::new(Aes128::default(), iv), 20);
+    test(CbcCts::new(Aes128::default(), iv), 30);
+    test(CbcCts::new(Aes128::default(), iv), 32);
+}
+
+/// The one-time pad can encrypt borrowed data, with the same result as
+/// encrypting owned data.
+#[test]
+fn one_time_pad_borrowed() {
+    let data = rng().into_iter().take(50).collect_vec();
+    let key = rng().into_iter().take(50).collect_vec();
+    let otp = OneTimePad::default();
+    let ciphertext = otp.encrypt_from(&data, key.clone().into_iter()).unwrap();
+    assert_eq!(
+        ciphertext,
+        otp.encrypt(data.clone(), key.clone().into_iter()).unwrap()
+    );
+    assert_eq!(
+        otp.decrypt_from(&ciphertext, key.into_iter()).unwrap(),
+        data
+    );
+}
+
+/// Block ciphers fill their keys straight from the source of randomness, and
+/// the modes and the CBC IV don't draw any more bytes than they need.
+#[test]
+fn generated_key_bytes() {
+    let script = (0..32).collect_vec();
+    let key = Aes128::generate_key(&mut StreamRng(script.clone()));
+    assert_eq!(key[..], script[..16]);
+    let key = Aes256::generate_key(&mut StreamRng(script.clone()));
+    assert_eq!(key[..], script[..]);
+    let iv = Cbc::<Aes128, Pkcs7, _>::generate_iv(&mut StreamRng(script[..16].to_vec()));
+    assert_eq!(iv[..], script[..16]);
+}
+
+/// The modes generate keys with the block cipher, so that ciphers with key
+/// constraints keep them when wrapped in a mode.
+#[test]
+fn generated_key_delegation() {
+    let rng = || StreamRng(vec![0; 16]);
+    assert_eq!(ParityCipher::generate_key(&mut rng()), [1; 16]);
+    assert_eq!(
+        Ecb::<ParityCipher, Pkcs7>::generate_key(&mut rng()),
+        [1; 16]
+    );
+    assert_eq!(
+        Cbc::<ParityCipher, Pkcs7, _>::generate_key(&mut rng()),
+        [1; 16]
+    );
+    assert_eq!(CbcCts::<ParityCipher, _>::generate_key(&mut rng()), [1; 16]);
+    assert_eq!(Ctr::<ParityCipher>::generate_key(&mut rng()), [1; 16]);
+
+    // Borrowed and boxed ciphers forward to the cipher they wrap.
+    assert_eq!(<&ParityCipher>::generate_key(&mut rng()), [1; 16]);
+    assert_eq!(Box::<ParityCipher>::generate_key(&mut rng()), [1; 16]);
+    assert_eq!(Arc::<ParityCipher>::generate_key(&mut rng()), [1; 16]);
+    assert_eq!(Ctr::<&ParityCipher>::generate_key(&mut rng()), [1; 16]);
+    assert_eq!(Ctr::<Box<ParityCipher>>::generate_key(&mut rng()), [1; 16]);
+}
+
+/// Two keys generated from the same source of randomness must differ.
+#[test]
+fn generated_keys_differ() {
+    let mut rng = rng();
+    let a = Aes256::generate_key(&mut rng);
+    let b = Aes256::generate_key(&mut rng);
+    assert_ne!(a, b);
+    let a = Cbc::<Aes128, Pkcs7, _>::generate_iv(&mut rng);
+    let b = Cbc::<Aes128, Pkcs7, _>::generate_iv(&mut rng);
+    assert_ne!(a, b);
+}
+
 /// Test that a cipher is valid by making sure that
 /// ```
 /// decrypt(encrypt(plaintext, key)) == plaintext
 /// ```
 fn test<Cip: Cipher>(cip: Cip, data_size: usize)
 where
-    Cip::Key: for<'a> TryFrom<&'a [u8]> + fmt::Debug + Clone,
+    Cip::Key: Default + AsMut<[u8]> + fmt::Debug + Clone,
     Cip::EncryptionErr: fmt::Debug,
     Cip::DecryptionErr: fmt::Debug,
 {
     let data = (0..data_size)
         .map(|_| rand::thread_rng().gen())
         .collect_vec();
-    let key_size = std::mem::size_of::<Cip::Key>();
-    let key = (0..key_size)
-        .map(|_| rand::thread_rng().gen())
-        .collect_vec();
-    let key = Cip::Key::try_from(key.as_slice()).unwrap_or_else(|_| unreachable!());
+    let key = Cip::generate_key(&mut rng());
 
-    let ciphertext = cip.encrypt(data.clone(), key.clone()).unwrap();
-    let plaintext = cip.decrypt(ciphertext.clone(), key.clone()).unwrap();
+    let ciphertext = cip.encrypt_from(&data, key.clone()).unwrap();
+    let plaintext = cip.decrypt_from(&ciphertext, key.clone()).unwrap();
 
     assert_eq!(
         data, plaintext,
         "decrypted plaintext did not match for cipher\ndata: {data:?}\nkey: {key:?}\nciphertext: \
          {ciphertext:?}\nplaintext: {plaintext:?}"
     );
+
+    // The borrowing methods must behave exactly like the owning ones.
+    assert_eq!(
+        ciphertext,
+        cip.encrypt(data.clone(), key.clone()).unwrap(),
+        "encrypt_from did not match encrypt"
+    );
+    assert_eq!(
+        plaintext,
+        cip.decrypt(ciphertext.clone(), key).unwrap(),
+        "decrypt_from did not match decrypt"
+    );
+}
+
+/// A generator reseeded at random, so that every run tests different keys.
+fn rng() -> Fortuna<NoEntropy> {
+    let mut rng = testing::rng();
+    rng.reseed(&rand::random::<[u8; 32]>());
+    rng
+}
+
+/// Test block cipher which XORs the data with the key, and whose keys must have
+/// odd parity in every byte, as with DES.
+struct ParityCipher;
+
+impl BlockEncrypt for ParityCipher {
+    type EncryptionBlock = [u8; 16];
+    type EncryptionKey = [u8; 16];
+
+    fn encrypt(
+        &self,
+        data: Self::EncryptionBlock,
+        key: Self::EncryptionKey,
+    ) -> Self::EncryptionBlock {
+        array::from_fn(|i| data[i] ^ key[i])
+    }
+
+    fn generate_key(rng: &mut impl Csprng) -> Self::EncryptionKey {
+        let mut key = [0; 16];
+        rng.fill_bytes(&mut key);
+        key.map(|b| b ^ u8::from(b.count_ones() % 2 == 0))
+    }
+}
+
+impl BlockDecrypt for ParityCipher {
+    type DecryptionBlock = [u8; 16];
+    type DecryptionKey = [u8; 16];
+
+    fn decrypt(
+        &self,
+        data: Self::DecryptionBlock,
+        key: Self::DecryptionKey,
+    ) -> Self::DecryptionBlock {
+        array::from_fn(|i| data[i] ^ key[i])
+    }
+}
+
+impl BlockCipher for ParityCipher {
+    type Block = [u8; 16];
+    type Key = [u8; 16];
 }
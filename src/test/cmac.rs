@@ -0,0 +1,71 @@
+use crate::{
+    hex,
+    test::vectors::{load_vectors, Record, Vector, VectorError},
+    Aes128,
+    Cmac,
+    Mac,
+};
+
+/// An AES-128 case from `tests/vectors/aes128_cmac.txt`.
+struct CmacCase {
+    key: [u8; 16],
+    message: Vec<u8>,
+    tag: [u8; 16],
+}
+
+impl Vector for CmacCase {
+    fn parse(record: &mut Record) -> Result<Self, VectorError> {
+        Ok(Self {
+            key: record.array("key")?,
+            message: record.hex("message")?,
+            tag: record.array("tag")?,
+        })
+    }
+}
+
+/// Assert that [CMAC](Cmac) with AES-128 matches the examples of RFC 4493.
+#[test]
+fn cmac_rfc_4493() {
+    let mut cmac = Cmac::new(Aes128::default());
+    for case in load_vectors::<CmacCase>("aes128_cmac.txt") {
+        assert_eq!(
+            cmac.mac(&case.message, &case.key),
+            case.tag,
+            "{} byte message",
+            case.message.len()
+        );
+    }
+}
+
+/// Assert that the [CMAC subkeys](Cmac#subkeys) match the subkey generation
+/// example of RFC 4493.
+#[test]
+fn cmac_subkeys() {
+    let key = hex::decode("2b7e151628aed2a6abf7158809cf4f3c").unwrap();
+    let (k1, k2) = Cmac::new(Aes128::default()).subkeys(key.try_into().unwrap());
+    assert_eq!(hex::encode(&k1), "fbeed618357133667c85e08f7236a8de");
+    assert_eq!(hex::encode(&k2), "f7ddac306ae266ccf90bc11ee46d513b");
+}
+
+/// Assert that [CMAC](Cmac) gives a message ending in a partial block a
+/// different tag than the same message padded by hand, or extended with a zero
+/// byte.
+#[test]
+fn cmac_padding_distinct() {
+    let mut cmac = Cmac::new(Aes128::default());
+    let key = [0x2b; 16];
+    let short = cmac.mac(b"fifteen bytes!!", &key);
+    let padded = cmac.mac(b"fifteen bytes!!\x80", &key);
+    let zeros = cmac.mac(b"fifteen bytes!!\0", &key);
+    assert_ne!(short, padded);
+    assert_ne!(short, zeros);
+    assert_ne!(padded, zeros);
+    assert_ne!(cmac.mac(b"", &key), cmac.mac(b"\x80", &key));
+}
+
+/// Assert that [CMAC](Cmac) refuses a key which doesn't fit the block cipher.
+#[test]
+#[should_panic = "CMAC key of 15 bytes is invalid"]
+fn cmac_wrong_key_size() {
+    Cmac::new(Aes128::default()).mac(b"msg", &[0; 15]);
+}
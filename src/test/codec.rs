@@ -0,0 +1,129 @@
+use {
+    super::fuzz::{ecies_corpus, session_corpus},
+    crate::{
+        codec::{CodecError, Reader, Writer},
+        EciesCiphertext,
+        EciesError,
+        Secp256k1,
+        SessionError,
+        Sha256,
+        SigningSession,
+    },
+    std::fmt,
+};
+
+type Ciphertext = EciesCiphertext<Secp256k1, 16, 32>;
+type Session = SigningSession<Secp256k1, Sha256>;
+
+/// Assert that the reader returns errors instead of panicking when the input
+/// runs out, and that it consumes exactly what was read.
+#[test]
+fn codec_reader() {
+    let bytes = [1, 0xde, 0xad, 0xbe, 0xef, 0, 0, 0, 2, 7, 8, 9];
+    let mut reader = Reader::new(&bytes);
+    assert_eq!(reader.read_version(1), Ok(()));
+    assert_eq!(reader.read_u32(), Ok(0xdeadbeef));
+    assert_eq!(reader.read_len_prefixed(), Ok(&[7, 8][..]));
+    assert_eq!(reader.remaining(), 1);
+    assert_eq!(reader.clone().finish(), Err(CodecError::TrailingBytes));
+    assert_eq!(reader.read_array::<2>(), Err(CodecError::Truncated));
+    assert_eq!(reader.read_u8(), Ok(9));
+    assert_eq!(reader.read_u8(), Err(CodecError::Truncated));
+    assert_eq!(reader.read_bytes(0), Ok(&[][..]));
+    assert_eq!(reader.finish(), Ok(()));
+
+    assert_eq!(
+        Reader::new(&[2]).read_version(1),
+        Err(CodecError::UnknownVersion(2))
+    );
+    assert_eq!(Reader::new(&[]).read_version(1), Err(CodecError::Truncated));
+    assert_eq!(
+        Reader::new(&[0, 0, 0, 3, 1, 2]).read_len_prefixed(),
+        Err(CodecError::Truncated)
+    );
+    assert_eq!(
+        Reader::new(&[0xff; 4]).read_len_prefixed(),
+        Err(CodecError::Truncated)
+    );
+}
+
+/// Assert that whatever the writer writes, the reader reads back.
+#[test]
+fn codec_round_trip() {
+    let mut writer = Writer::new(3);
+    writer.write_u8(4);
+    writer.write_u32(0x01020304);
+    writer.write_len_prefixed(b"abc");
+    writer.write_bytes(b"de");
+    let bytes = writer.into_bytes();
+    assert_eq!(
+        bytes,
+        [3, 4, 1, 2, 3, 4, 0, 0, 0, 3, b'a', b'b', b'c', b'd', b'e']
+    );
+
+    let mut reader = Reader::new(&bytes);
+    assert_eq!(reader.read_version(3), Ok(()));
+    assert_eq!(reader.read_u8(), Ok(4));
+    assert_eq!(reader.read_u32(), Ok(0x01020304));
+    assert_eq!(reader.read_len_prefixed(), Ok(&b"abc"[..]));
+    assert_eq!(reader.read_array(), Ok(*b"de"));
+    assert_eq!(reader.finish(), Ok(()));
+}
+
+/// Assert that ECIES ciphertexts round-trip, and that truncated, extended and
+/// future-version ciphertexts are rejected.
+#[test]
+fn codec_ecies() {
+    for bytes in ecies_corpus() {
+        assert_eq!(bytes[0], Ciphertext::VERSION);
+        assert_eq!(Ciphertext::from_bytes(&bytes).unwrap().to_bytes(), bytes);
+        assert_truncations_rejected(&bytes, |bytes| Ciphertext::from_bytes(bytes).map(|_| ()));
+        assert_eq!(
+            Ciphertext::from_bytes(&[&bytes[..], &[0]].concat()).unwrap_err(),
+            EciesError::Malformed
+        );
+        assert_eq!(
+            Ciphertext::from_bytes(&bumped(&bytes)).unwrap_err(),
+            EciesError::UnknownVersion(Ciphertext::VERSION + 1)
+        );
+    }
+}
+
+/// Assert that signing sessions round-trip, and that truncated, extended and
+/// future-version sessions are rejected.
+#[test]
+fn codec_session() {
+    for bytes in session_corpus() {
+        assert_eq!(bytes[0], Session::VERSION);
+        assert_eq!(Session::from_bytes(&bytes).unwrap().to_bytes(), bytes);
+        assert_truncations_rejected(&bytes, |bytes| Session::from_bytes(bytes).map(|_| ()));
+        assert_eq!(
+            Session::from_bytes(&[&bytes[..], &[0]].concat()).unwrap_err(),
+            SessionError::Malformed
+        );
+        assert_eq!(
+            Session::from_bytes(&bumped(&bytes)).unwrap_err(),
+            SessionError::UnknownVersion(Session::VERSION + 1)
+        );
+    }
+}
+
+/// Assert that every strict prefix of the encoding fails to decode.
+fn assert_truncations_rejected<E: fmt::Debug>(
+    bytes: &[u8],
+    decode: impl Fn(&[u8]) -> Result<(), E>,
+) {
+    for len in 0..bytes.len() {
+        assert!(
+            decode(&bytes[..len]).is_err(),
+            "truncated to {len} of {} bytes",
+            bytes.len()
+        );
+    }
+}
+
+fn bumped(bytes: &[u8]) -> Vec<u8> {
+    let mut bytes = bytes.to_vec();
+    bytes[0] += 1;
+    bytes
+}
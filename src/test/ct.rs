@@ -0,0 +1,127 @@
+//! Tests for the [constant-time selection](CtSelect) primitives. The truth
+//! tables are exhaustive, and the selections are checked against their
+//! branching counterparts on random inputs.
+
+use crate::{
+    ecc::{Coordinates, Curve, Group, Num, Point},
+    testing::rng,
+    Choice,
+    Csprng,
+    CtSelect,
+    Secp256k1,
+};
+
+fn random_num(rng: &mut impl Csprng) -> Num {
+    let mut bytes = [0; Num::BYTES];
+    rng.fill_bytes(&mut bytes);
+    Num::from_le_bytes(bytes)
+}
+
+const CHOICES: [Choice; 2] = [Choice::FALSE, Choice::TRUE];
+
+/// The operators on choices follow the truth tables of their boolean
+/// counterparts.
+#[test]
+fn choice_truth_tables() {
+    for a in CHOICES {
+        let x = bool::from(a);
+        assert_eq!(bool::from(!a), !x);
+        assert_eq!(a.mask(), if x { u64::MAX } else { 0 });
+        for b in CHOICES {
+            let y = bool::from(b);
+            assert_eq!(bool::from(a & b), x & y);
+            assert_eq!(bool::from(a | b), x | y);
+            assert_eq!(bool::from(a ^ b), x ^ y);
+        }
+    }
+    assert_eq!(Choice::from_bit(0), Choice::FALSE);
+    assert_eq!(Choice::from_bit(1), Choice::TRUE);
+}
+
+/// Selecting, swapping and comparing words, for every choice and for words
+/// which differ in a single bit, in all bits or not at all.
+#[test]
+fn word_truth_tables() {
+    let words = [0, 1, 1 << 63, u64::MAX, 0x0123_4567_89ab_cdef];
+    for a in words {
+        for b in words {
+            assert_eq!(bool::from(a.ct_eq(&b)), a == b, "{a:x} == {b:x}");
+            assert_eq!(u64::ct_select(&a, &b, Choice::FALSE), a);
+            assert_eq!(u64::ct_select(&a, &b, Choice::TRUE), b);
+            for choice in CHOICES {
+                let (mut x, mut y) = (a, b);
+                u64::ct_swap(&mut x, &mut y, choice);
+                let expected = if bool::from(choice) { (b, a) } else { (a, b) };
+                assert_eq!((x, y), expected);
+            }
+        }
+    }
+}
+
+/// Selecting a number agrees with branching on the choice, and comparing
+/// agrees with `==`, including for numbers which differ in a single word.
+#[test]
+fn num_select_equivalence() {
+    let mut rng = rng();
+    for _ in 0..100 {
+        let a = random_num(&mut rng);
+        let b = random_num(&mut rng);
+        let choice = Choice::from_bit(rng.bytes().next().unwrap() & 1);
+        let expected = if bool::from(choice) { b } else { a };
+        assert_eq!(Num::ct_select(&a, &b, choice), expected);
+
+        let (mut x, mut y) = (a, b);
+        Num::ct_swap(&mut x, &mut y, choice);
+        assert_eq!(x, expected);
+        assert_eq!(y, if bool::from(choice) { a } else { b });
+
+        assert_eq!(bool::from(a.ct_eq(&b)), a == b);
+        assert!(bool::from(a.ct_eq(&a)));
+        for i in 0..Num::BITS {
+            assert_eq!(bool::from(a.ct_bit(i)), a.get_bit(i));
+        }
+    }
+
+    for word in 0..Num::WIDTH {
+        let mut words = [u64::MAX; Num::WIDTH];
+        words[word] ^= 1 << 17;
+        let a = Num::from_le_words(words);
+        let b = Num::from_le_words([u64::MAX; Num::WIDTH]);
+        assert!(!bool::from(a.ct_eq(&b)));
+    }
+}
+
+/// Selecting points, including the point at infinity, agrees with branching
+/// on the choice.
+#[test]
+fn point_select_equivalence() {
+    let g = Secp256k1::g();
+    let points = [Point::infinity(), g, g + g, g.inverse()];
+    for a in points {
+        for b in points {
+            assert_eq!(bool::from(a.ct_eq(&b)), a == b);
+            for choice in CHOICES {
+                let expected = if bool::from(choice) { b } else { a };
+                assert_eq!(Point::ct_select(&a, &b, choice), expected);
+
+                let (mut x, mut y) = (a, b);
+                Point::ct_swap(&mut x, &mut y, choice);
+                let other = if bool::from(choice) { a } else { b };
+                assert_eq!((x, y), (expected, other));
+            }
+        }
+    }
+
+    // The point at infinity isn't confused with a finite point whose
+    // coordinates are zero.
+    let zero = Coordinates::Finite(Num::ZERO, Num::ZERO);
+    assert!(!bool::from(Coordinates::Infinity.ct_eq(&zero)));
+    assert_eq!(
+        Coordinates::ct_select(&zero, &Coordinates::Infinity, Choice::TRUE),
+        Coordinates::Infinity
+    );
+    assert_eq!(
+        Coordinates::ct_select(&Coordinates::Infinity, &zero, Choice::TRUE),
+        zero
+    );
+}
@@ -1,44 +1,456 @@
-use crate::{Aes128, CipherEncrypt, Ctr};
-
-/// Test the [CTR block mode](Ctr) with hand-checked test vectors. The nonce is
-/// set to 1 and never incremented.
-#[test]
-fn ctr_no_increments() {
-    let ctr = Ctr::new(Aes128::default(), 1).unwrap();
-    let ciphertext = ctr
-        .encrypt(
-            vec![0x01, 0x10, 0x20],
-            [
-                0x2b, 0x7e, 0x15, 0x16, 0x28, 0xae, 0xd2, 0xa6, 0xab, 0xf7, 0x15, 0x88, 0x09, 0xcf,
-                0x4f, 0x3c,
-            ],
-        )
+use {
+    crate::{
+        hex,
+        test::vectors::{load_vectors, Record, Vector, VectorError},
+        Aes128,
+        BlockEncrypt,
+        CipherDecrypt,
+        CipherEncrypt,
+        CounterPosition,
+        CounterWidth,
+        Ctr,
+        CtrConfigError,
+        CtrError,
+    },
+    rand::Rng,
+};
+
+/// An AES-128 case from `tests/vectors/aes128_ctr.txt`.
+struct CtrCase {
+    key: [u8; 16],
+    nonce: u64,
+    plaintext: Vec<u8>,
+    ciphertext: Vec<u8>,
+}
+
+impl Vector for CtrCase {
+    fn parse(record: &mut Record) -> Result<Self, VectorError> {
+        Ok(Self {
+            key: record.array("key")?,
+            nonce: record.u64("nonce")?,
+            plaintext: record.hex("plaintext")?,
+            ciphertext: record.hex("ciphertext")?,
+        })
+    }
+}
+
+/// Test the [CTR block mode](Ctr) with hand-checked test vectors, both with a
+/// counter that is never incremented and one that is incremented once.
+#[test]
+fn ctr_vectors() {
+    for case in load_vectors::<CtrCase>("aes128_ctr.txt") {
+        let ctr = Ctr::new(Aes128::default(), case.nonce).unwrap();
+        let ciphertext = ctr.encrypt(case.plaintext.clone(), case.key).unwrap();
+        assert_eq!(ciphertext, case.ciphertext, "nonce {}", case.nonce);
+        assert_eq!(ctr.decrypt(ciphertext, case.key).unwrap(), case.plaintext);
+    }
+}
+
+/// An AES-128 case from `tests/vectors/aes128_ctr_sp800_38a.txt`.
+struct CtrIvCase {
+    key: [u8; 16],
+    iv: [u8; 16],
+    plaintext: Vec<u8>,
+    ciphertext: Vec<u8>,
+}
+
+impl Vector for CtrIvCase {
+    fn parse(record: &mut Record) -> Result<Self, VectorError> {
+        Ok(Self {
+            key: record.array("key")?,
+            iv: record.array("iv")?,
+            plaintext: record.hex("plaintext")?,
+            ciphertext: record.hex("ciphertext")?,
+        })
+    }
+}
+
+/// Test [`Ctr::with_iv`] with the vectors of NIST SP 800-38A.
+#[test]
+fn ctr_iv_vectors() {
+    for case in load_vectors::<CtrIvCase>("aes128_ctr_sp800_38a.txt") {
+        let ctr = Ctr::with_iv(Aes128::default(), case.iv).unwrap();
+        let ciphertext = ctr.encrypt(case.plaintext.clone(), case.key).unwrap();
+        assert_eq!(ciphertext, case.ciphertext);
+        assert_eq!(ctr.decrypt(ciphertext, case.key).unwrap(), case.plaintext);
+    }
+}
+
+/// Assert that the counter of [`Ctr::with_iv`] carries across the whole block,
+/// and that smaller blocks use the whole block as the counter as well.
+#[test]
+fn ctr_iv_carry() {
+    let key = [0x2b; 16];
+    let mut iv = [0; 16];
+    iv[8..].fill(0xff);
+    let mut next = [0; 16];
+    next[7] = 1;
+    let keystream = Ctr::with_iv(Aes128::default(), iv)
+        .unwrap()
+        .keystream_bytes(key, 0..32)
+        .unwrap();
+    assert_eq!(keystream[..16], Aes128::default().encrypt(iv, key));
+    assert_eq!(keystream[16..], Aes128::default().encrypt(next, key));
+
+    let keystream = Ctr::with_iv(Tiny, [0, 0, 0xff, 0xff])
+        .unwrap()
+        .keystream_bytes([0; 4], 4..8)
+        .unwrap();
+    assert_eq!(keystream, [0, 1, 0, 0]);
+
+    // Blocks wider than 128 bits only count in their last 128 bits.
+    let mut block = [0xff; 32];
+    block[16] = 0;
+    let keystream = Ctr::with_iv(Wide, block)
+        .unwrap()
+        .keystream_bytes([0; 32], 32..64)
+        .unwrap();
+    assert_eq!(keystream[..16], block[..16]);
+    assert_eq!(keystream[16], 1);
+    assert!(keystream[17..].iter().all(|&b| b == 0));
+    assert!(Ctr::with_iv(Wide, [0xff; 32])
+        .unwrap()
+        .keystream_bytes([0; 32], 0..64)
+        .is_err());
+}
+
+/// Computing the [CTR](Ctr) keystream in two consecutive ranges gives the same
+/// bytes as computing it in one go, whether or not the second range starts on a
+/// block boundary.
+#[test]
+fn ctr_keystream_continuation() {
+    let key = [0x2b; 16];
+    let whole = Ctr::new(Aes128::default(), 7)
+        .unwrap()
+        .keystream_bytes(key, 0..100)
+        .unwrap();
+    assert_eq!(whole.len(), 100);
+
+    for split in [0, 1, 15, 16, 17, 20, 32, 99, 100] {
+        let ctr = Ctr::new(Aes128::default(), 7).unwrap();
+        let mut parts = ctr.keystream_bytes(key, 0..split).unwrap();
+        parts.extend(ctr.keystream_bytes(key, split..100).unwrap());
+        assert_eq!(parts, whole, "split at {split}");
+    }
+
+    // The keystream is also what encryption XORs with the plaintext.
+    let ctr = Ctr::new(Aes128::default(), 7).unwrap();
+    assert_eq!(ctr.encrypt(vec![0; 100], key).unwrap(), whole);
+}
+
+/// The same [CTR](Ctr) instance gives every key its own keystream, in whatever
+/// order the keys are used.
+#[test]
+fn ctr_keystream_key_change() {
+    let (a, b) = ([0x2b; 16], [0x3c; 16]);
+    let ctr = Ctr::new(Aes128::default(), 7).unwrap();
+    let fresh = |key| {
+        Ctr::new(Aes128::default(), 7)
+            .unwrap()
+            .keystream_bytes(key, 20..40)
+            .unwrap()
+    };
+
+    // Compute the second block under key a, then the same block under key b.
+    let first = ctr.keystream_bytes(a, 0..20).unwrap();
+    assert_eq!(
+        first,
+        Ctr::new(Aes128::default(), 7)
+            .unwrap()
+            .keystream_bytes(a, 0..20)
+            .unwrap()
+    );
+    let second = ctr.keystream_bytes(b, 20..40).unwrap();
+    assert_eq!(second, fresh(b));
+    assert_ne!(second, fresh(a));
+
+    // Switching back to key a gives the keystream of key a again.
+    assert_eq!(ctr.keystream_bytes(a, 20..40).unwrap(), fresh(a));
+}
+
+/// Encrypting and decrypting borrowed data with [CTR](Ctr) fails past the
+/// [limit](Ctr::with_limit) just like with owned data.
+#[test]
+fn ctr_borrowed_limit() {
+    let key = [0x2b; 16];
+    let ctr = Ctr::new(Aes128::default(), 7)
+        .unwrap()
+        .with_limit(20)
+        .unwrap();
+    let data = [0x5a; 21];
+    let err = ctr.encrypt(data.to_vec(), key).unwrap_err();
+    assert_eq!(ctr.encrypt_from(&data, key), Err(err));
+    assert_eq!(ctr.decrypt_from(&data, key), Err(err));
+    let ciphertext = ctr.encrypt_from(&data[..20], key).unwrap();
+    assert_eq!(ciphertext, ctr.encrypt(data[..20].to_vec(), key).unwrap());
+    assert_eq!(ctr.decrypt_from(&ciphertext, key).unwrap(), data[..20]);
+}
+
+/// [CTR](Ctr) refuses to let the counter wrap around instead of silently
+/// reusing the keystream of counter zero.
+#[test]
+fn ctr_counter_wrap() {
+    let key = [0x2b; 16];
+    let ctr = Ctr::new(Aes128::default(), u64::MAX - 1).unwrap();
+    // Two blocks fit before the counter would wrap.
+    assert_eq!(ctr.encrypt(vec![0; 32], key).unwrap().len(), 32);
+    assert_eq!(
+        ctr.encrypt(vec![0; 33], key),
+        Err(CtrError::CounterWrap {
+            nonce: u128::from(u64::MAX - 1),
+            blocks: 3,
+        })
+    );
+    assert_eq!(
+        ctr.decrypt(vec![0; 33], key),
+        Err(CtrError::CounterWrap {
+            nonce: u128::from(u64::MAX - 1),
+            blocks: 3,
+        })
+    );
+    assert!(ctr.keystream_bytes(key, 16..33).is_err());
+}
+
+/// The per-nonce byte limit of [CTR](Ctr) is enforced, and doesn't change the
+/// ciphertext of messages within the limit.
+#[test]
+fn ctr_limit() {
+    let key = [0x2b; 16];
+    let limited = Ctr::new(Aes128::default(), 7)
+        .unwrap()
+        .with_limit(20)
+        .unwrap();
+    let unlimited = Ctr::new(Aes128::default(), 7).unwrap();
+    assert_eq!(
+        limited.encrypt(vec![1; 20], key),
+        unlimited.encrypt(vec![1; 20], key)
+    );
+    assert_eq!(
+        limited.encrypt(vec![1; 21], key),
+        Err(CtrError::LimitExceeded { len: 21, limit: 20 })
+    );
+    assert_eq!(
+        limited.keystream_bytes(key, 10..21),
+        Err(CtrError::LimitExceeded { len: 21, limit: 20 })
+    );
+    assert_eq!(limited.keystream_bytes(key, 10..20).unwrap().len(), 10);
+}
+
+/// Assert that lazy decryption yields the same plaintext as decrypting at
+/// once, for whole and partial last blocks.
+#[test]
+fn ctr_decrypt_iter() {
+    let mut rng = rand::thread_rng();
+    let ctr = Ctr::new(Aes128::default(), rng.gen()).unwrap();
+    let key = rng.gen();
+    for len in 0..=64usize {
+        let data: Vec<u8> = (0..len).map(|_| rng.gen()).collect();
+        let Ok(ciphertext) = ctr.encrypt(data.clone(), key) else {
+            // The random nonce is too close to wrapping around.
+            continue;
+        };
+        let blocks: Vec<_> = ctr
+            .decrypt_iter(&ciphertext, key)
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(blocks.len(), len.div_ceil(16), "{len}");
+        assert_eq!(blocks.concat(), data, "{len}");
+        assert_eq!(blocks.concat(), ctr.decrypt(ciphertext, key).unwrap());
+    }
+}
+
+/// Assert that running out of keystream is the last item of a lazy
+/// decryption.
+#[test]
+fn ctr_decrypt_iter_limit() {
+    let key = [0x2b; 16];
+    let ctr = Ctr::new(Aes128::default(), 7)
+        .unwrap()
+        .with_limit(20)
         .unwrap();
-    assert_eq!(ciphertext, vec![0x7f, 0x49, 0x17]);
+    let mut iter = ctr.decrypt_iter(&[1; 40], key);
+    assert_eq!(iter.next().unwrap().unwrap().len(), 16);
+    assert_eq!(
+        iter.next(),
+        Some(Err(CtrError::LimitExceeded { len: 32, limit: 20 }))
+    );
+    assert_eq!(iter.next(), None);
+
+    let ctr = Ctr::new(Aes128::default(), u64::MAX - 1).unwrap();
+    let items: Vec<_> = ctr.decrypt_iter(&[0; 40], key).collect();
+    assert_eq!(items.len(), 3);
+    assert_eq!(
+        items[2],
+        Err(CtrError::CounterWrap {
+            nonce: u128::from(u64::MAX - 1),
+            blocks: 3,
+        })
+    );
 }
 
-/// Test the [CTR block mode](Ctr) with hand-checked test vectors. The nonce is
-/// set to 256 and incremented once.
+/// Lock down the bytes of the AES-CTR keystream with the default [counter
+/// block](Ctr#counter-block), so that changes to the counter handling can't
+/// silently change it.
 #[test]
-fn ctr_with_increment() {
-    let ctr = Ctr::new(Aes128::default(), 256).unwrap();
-    let ciphertext = ctr
-        .encrypt(
-            vec![
-                0x01, 0x10, 0x20, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
-                0x00, 0x00, 0x30, 0x40, 0x50,
-            ],
-            [
-                0x2b, 0x7e, 0x15, 0x16, 0x28, 0xae, 0xd2, 0xa6, 0xab, 0xf7, 0x15, 0x88, 0x09, 0xcf,
-                0x4f, 0x3c,
-            ],
+fn ctr_default_counter_block() {
+    let key = *b"literate-crypto!";
+    let cases = [
+        (
+            0x0123_4567_89ab_cdef,
+            "791eafedb49ba314dbed4a78ffea1a89cbdbbbb6c74c599d3b455251e97abc23ba86237159d1e5a3",
+        ),
+        (
+            u64::MAX - 1,
+            "8075ed0dcdefc3e0afd7784ceb353e177a9687cae1f3d8ac6d10205b9e5a4b5a",
+        ),
+    ];
+    for (nonce, expected) in cases {
+        let expected = hex::decode(expected).unwrap();
+        let len = u64::try_from(expected.len()).unwrap();
+        let ctr = Ctr::new(Aes128::default(), nonce).unwrap();
+        assert_eq!(ctr.keystream_bytes(key, 0..len).unwrap(), expected);
+
+        let mut block = [0; 16];
+        block[..8].copy_from_slice(&nonce.to_le_bytes());
+        let explicit = Ctr::with_counter_block(
+            Aes128::default(),
+            block,
+            CounterWidth::Bits64,
+            CounterPosition::Prefix,
         )
         .unwrap();
+        assert_eq!(explicit.keystream_bytes(key, 0..len).unwrap(), expected);
+    }
+}
+
+/// Assert that the counter is incremented in place, with the rest of the
+/// counter block untouched, for every width and position.
+#[test]
+fn ctr_counter_layout() {
+    let mut block = [0; 32];
+    block.iter_mut().zip(0..).for_each(|(b, i)| *b = i);
+    for width in [
+        CounterWidth::Bits32,
+        CounterWidth::Bits64,
+        CounterWidth::Bits128,
+    ] {
+        let w = width.bytes();
+        for position in [CounterPosition::Prefix, CounterPosition::Suffix] {
+            let (field, rest, lsb) = match position {
+                CounterPosition::Prefix => (0..w, w..32, 0),
+                CounterPosition::Suffix => (32 - w..32, 0..32 - w, w - 1),
+            };
+            let ctr = Ctr::with_counter_block(Wide, block, width, position).unwrap();
+            let keystream = ctr.keystream_bytes([0; 32], 0..96).unwrap();
+            for (i, counter_block) in (0..).zip(keystream.chunks(32)) {
+                assert_eq!(counter_block[rest.clone()], block[rest.clone()]);
+                // The least significant byte is small enough not to carry.
+                let mut expected = block[field.clone()].to_vec();
+                expected[lsb] += i;
+                assert_eq!(
+                    counter_block[field.clone()],
+                    expected,
+                    "{width:?} {position:?}"
+                );
+            }
+        }
+    }
+}
+
+/// Assert that the counter wraps around at its width rather than at 64 bits,
+/// and carries into the more significant bytes of the counter without touching
+/// the rest of the block.
+#[test]
+fn ctr_counter_width_wrap() {
+    for width in [
+        CounterWidth::Bits32,
+        CounterWidth::Bits64,
+        CounterWidth::Bits128,
+    ] {
+        let w = width.bytes();
+        // The big-endian counter is two below its maximum.
+        let mut block = [0x5a; 32];
+        block[32 - w..].fill(0xff);
+        block[31] = 0xfd;
+        let ctr = Ctr::with_counter_block(Wide, block, width, CounterPosition::Suffix).unwrap();
+        let keystream = ctr.keystream_bytes([0; 32], 64..96).unwrap();
+        assert_eq!(keystream[..32 - w], block[..32 - w]);
+        assert!(keystream[32 - w..].iter().all(|&b| b == 0xff));
+        assert!(matches!(
+            ctr.keystream_bytes([0; 32], 0..97),
+            Err(CtrError::CounterWrap { blocks: 4, .. })
+        ));
+
+        // A little-endian counter carries into the next byte.
+        let mut block = [0x5a; 32];
+        block[..w].fill(0);
+        block[0] = 0xff;
+        let ctr = Ctr::with_counter_block(Wide, block, width, CounterPosition::Prefix).unwrap();
+        let keystream = ctr.keystream_bytes([0; 32], 32..64).unwrap();
+        assert_eq!(keystream[..2], [0x00, 0x01]);
+        assert_eq!(keystream[w..], block[w..]);
+    }
+}
+
+/// Assert that a counter wider than the block is rejected.
+#[test]
+fn ctr_counter_too_wide() {
     assert_eq!(
-        ciphertext,
-        vec![
-            0xfc, 0xe3, 0xf9, 0xdf, 0xcd, 0x52, 0x35, 0x0d, 0x21, 0xd5, 0xdc, 0xda, 0x57, 0x8f,
-            0x32, 0x28, 0x0b, 0x87, 0x73
-        ]
+        Ctr::with_counter_block(Tiny, [0; 4], CounterWidth::Bits64, CounterPosition::Suffix)
+            .unwrap_err(),
+        CtrConfigError::BlockTooSmall {
+            block: 4,
+            required: 8,
+        }
+    );
+    assert!(
+        Ctr::with_counter_block(Tiny, [0; 4], CounterWidth::Bits32, CounterPosition::Suffix)
+            .is_ok()
     );
 }
+
+/// Invalid [CTR](Ctr) parameters are reported with the offending values.
+#[test]
+fn ctr_config_errors() {
+    assert_eq!(
+        Ctr::new(Aes128::default(), 0)
+            .unwrap()
+            .with_limit(0)
+            .unwrap_err(),
+        CtrConfigError::ZeroLimit
+    );
+    assert_eq!(
+        Ctr::new(Tiny, 0).unwrap_err(),
+        CtrConfigError::BlockTooSmall {
+            block: 4,
+            required: 8,
+        }
+    );
+}
+
+/// A block cipher with 32-byte blocks which leaves the data as is, so that the
+/// keystream is made of the counter blocks.
+#[derive(Debug)]
+struct Wide;
+
+impl BlockEncrypt for Wide {
+    type EncryptionBlock = [u8; 32];
+    type EncryptionKey = [u8; 32];
+
+    fn encrypt(&self, data: [u8; 32], _: [u8; 32]) -> [u8; 32] {
+        data
+    }
+}
+
+/// A block cipher with blocks too small for a 64-bit counter.
+#[derive(Debug)]
+struct Tiny;
+
+impl BlockEncrypt for Tiny {
+    type EncryptionBlock = [u8; 4];
+    type EncryptionKey = [u8; 4];
+
+    fn encrypt(&self, data: [u8; 4], _: [u8; 4]) -> [u8; 4] {
+        data
+    }
+}
@@ -0,0 +1,105 @@
+use {
+    crate::{
+        testing::rng,
+        Aes128,
+        Cbc,
+        Cipher,
+        CipherDecrypt,
+        CipherEncrypt,
+        Csprng,
+        Ctr,
+        DerivedNonce,
+        DerivedNonceError,
+        Pkcs7,
+    },
+    std::collections::HashSet,
+};
+
+#[test]
+fn ctr_round_trip() {
+    let mut rng = rng();
+    let key = Ctr::<Aes128>::generate_key(&mut rng);
+    for len in [0, 1, 15, 16, 17, 100] {
+        let nonce = DerivedNonce::<Ctr<Aes128>>::generate_nonce(&mut rng);
+        let cip = DerivedNonce::new(Ctr::new(Aes128::default(), 0).unwrap(), nonce);
+        let data: Vec<u8> = (0..len).collect();
+        let ciphertext = cip.encrypt_from(&data, key).unwrap();
+        assert_eq!(ciphertext.len(), 24 + data.len());
+        assert_eq!(ciphertext[..24], nonce);
+        assert_eq!(cip.decrypt(ciphertext, key).unwrap(), data);
+    }
+}
+
+#[test]
+fn cbc_round_trip() {
+    let mut rng = rng();
+    let key = Cbc::<Aes128, Pkcs7, [u8; 16]>::generate_key(&mut rng);
+    let nonce = rng.bytes().take(16).collect::<Vec<_>>().try_into().unwrap();
+    let cbc = Cbc::new(Aes128::default(), Pkcs7::default(), [0; 16]);
+    let cip = DerivedNonce::<_, 16>::new(cbc, nonce);
+    for len in [0, 15, 16, 33] {
+        let data: Vec<u8> = (0..len).collect();
+        let ciphertext = cip.encrypt_from(&data, key).unwrap();
+        assert_eq!(ciphertext[..16], nonce);
+        assert_eq!(cip.decrypt(ciphertext, key).unwrap(), data);
+    }
+}
+
+/// The recipient reads the long nonce from the ciphertext, so it doesn't need
+/// to know it in advance.
+#[test]
+fn nonce_travels_with_ciphertext() {
+    let key = [7; 16];
+    let sender = DerivedNonce::new(Ctr::new(Aes128::default(), 0).unwrap(), [1; 24]);
+    let recipient = DerivedNonce::new(Ctr::new(Aes128::default(), 0).unwrap(), [2; 24]);
+    let ciphertext = sender.encrypt_from(b"attack at dawn", key).unwrap();
+    assert_eq!(
+        recipient.decrypt(ciphertext.clone(), key).unwrap(),
+        b"attack at dawn"
+    );
+    assert_ne!(
+        recipient.encrypt_from(b"attack at dawn", key).unwrap(),
+        ciphertext
+    );
+}
+
+#[test]
+fn missing_nonce() {
+    let cip = DerivedNonce::new(Ctr::new(Aes128::default(), 0).unwrap(), [0; 24]);
+    assert_eq!(
+        cip.decrypt(vec![0; 23], [0; 16]),
+        Err(DerivedNonceError::MissingNonce)
+    );
+    assert_eq!(cip.decrypt(vec![0; 24], [0; 16]), Ok(Vec::new()));
+}
+
+/// Messages with distinct long nonces are encrypted under distinct derived
+/// keys, and none of their keystream blocks repeat, even though the
+/// underlying mode is configured with the same counter for all of them.
+#[test]
+fn distinct_nonces_never_share_keystream() {
+    let key = [0x2b; 16];
+    let zeros = vec![0; 4 * 16];
+    let mut keys = HashSet::new();
+    let mut blocks = HashSet::new();
+    for i in 0..64u8 {
+        let mut nonce = [0; 24];
+        nonce[23] = i;
+        let (derived_key, counter) = DerivedNonce::<Ctr<Aes128>>::derive(&key, &nonce);
+        assert!(keys.insert(derived_key));
+
+        // The ciphertext of zeros is the keystream of the derived key and
+        // counter.
+        let counter = u64::from_le_bytes(counter.try_into().unwrap());
+        let expected = Ctr::new(Aes128::default(), counter)
+            .unwrap()
+            .encrypt(zeros.clone(), derived_key)
+            .unwrap();
+        let cip = DerivedNonce::new(Ctr::new(Aes128::default(), 0).unwrap(), nonce);
+        let keystream = cip.encrypt(zeros.clone(), key).unwrap().split_off(24);
+        assert_eq!(keystream, expected);
+        for block in keystream.chunks(16) {
+            assert!(blocks.insert(block.to_vec()));
+        }
+    }
+}
@@ -0,0 +1,203 @@
+//! Tests for empty input. The empty message is a valid input to every hash
+//! function, MAC, cipher and signature scheme, and it exercises edge cases
+//! like padding a message which has no full blocks at all.
+
+use crate::{
+    ecc::{Num, PrivateKey, PublicKey},
+    hex,
+    test::hash::test,
+    testing::rng,
+    Aes128,
+    Cbc,
+    CipherDecrypt,
+    CipherEncrypt,
+    Ctr,
+    Ecb,
+    Ecdsa,
+    EncryptThenMac,
+    Hmac,
+    Mac,
+    MultiSchnorr,
+    MultisigScheme,
+    OneTimePad,
+    Padding,
+    Pkcs7,
+    RingScheme,
+    Schnorr,
+    SchnorrSag,
+    Secp256k1,
+    Sha1,
+    Sha224,
+    Sha256,
+    Sha384,
+    Sha3_224,
+    Sha3_256,
+    Sha3_384,
+    Sha3_512,
+    Sha512,
+    Sha512_224,
+    Sha512_256,
+    SignatureScheme,
+    Sm3,
+};
+
+/// The digests of the empty message, as given by the respective standards.
+#[test]
+fn empty_hash() {
+    let digest = |s| hex::decode(s).unwrap();
+    test(
+        &Sha1::default(),
+        b"",
+        &digest("da39a3ee5e6b4b0d3255bfef95601890afd80709"),
+    );
+    test(
+        &Sha224::default(),
+        b"",
+        &digest("d14a028c2a3a2bc9476102bb288234c415a2b01f828ea62ac5b3e42f"),
+    );
+    test(
+        &Sha256::default(),
+        b"",
+        &digest("e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855"),
+    );
+    test(
+        &Sha384::default(),
+        b"",
+        &digest(
+            "38b060a751ac96384cd9327eb1b1e36a21fdb71114be07434c0cc7bf63f6e1da274edebfe76f65fbd51ad2f14898b95b",
+        ),
+    );
+    test(
+        &Sha512::default(),
+        b"",
+        &digest(
+            "cf83e1357eefb8bdf1542850d66d8007d620e4050b5715dc83f4a921d36ce9ce47d0d13c5d85f2b0ff8318d2877eec2f63b931bd47417a81a538327af927da3e",
+        ),
+    );
+    test(
+        &Sha512_224::default(),
+        b"",
+        &digest("6ed0dd02806fa89e25de060c19d3ac86cabb87d6a0ddd05c333b84f4"),
+    );
+    test(
+        &Sha512_256::default(),
+        b"",
+        &digest("c672b8d1ef56ed28ab87c3622c5114069bdd3ad7b8f9737498d0c01ecef0967a"),
+    );
+    test(
+        &Sha3_224::default(),
+        b"",
+        &digest("6b4e03423667dbb73b6e15454f0eb1abd4597f9a1b078e3f5b5a6bc7"),
+    );
+    test(
+        &Sha3_256::default(),
+        b"",
+        &digest("a7ffc6f8bf1ed76651c14756a061d662f580ff4de43b49fa82d80a4b80f8434a"),
+    );
+    test(
+        &Sha3_384::default(),
+        b"",
+        &digest(
+            "0c63a75b845e4f7d01107d852e4c2485c51a50aaaa94fc61995e71bbee983a2ac3713831264adb47fb6bd1e058d5f004",
+        ),
+    );
+    test(
+        &Sha3_512::default(),
+        b"",
+        &digest(
+            "a69f73cca23a9ac5c8b567dc185a756e97c982164fe25859e0d1dcc1475c80a615b2123af1f5f94c11e3e9402c3ac558f500199d95b6d3e301758586281dcd26",
+        ),
+    );
+    test(
+        &Sm3::default(),
+        b"",
+        &digest("1ab21d8355cfa17f8e61194831e81a8f22bec8c728fefb747ed035eb5082aa2b"),
+    );
+}
+
+/// HMAC of the empty message, with an empty and a non-empty key.
+#[test]
+fn empty_hmac() {
+    let mut hmac = Hmac::new(Sha256::default());
+    assert_eq!(
+        hex::encode(&hmac.mac(b"", b"")),
+        "b613679a0814d9ec772f95d778c35fc5ff1697c493715653c6c712144292c5ad"
+    );
+    assert_eq!(
+        hex::encode(&hmac.mac(b"", b"key")),
+        "5d5d139563c95b5967b9bd9a8c9b233a9dedb45072794cd232dc1b74832607d0"
+    );
+}
+
+/// Padding the empty message with PKCS#7 gives a full block of padding, so
+/// block ciphers encrypt it to exactly one block. Stream ciphers encrypt it to
+/// nothing.
+#[test]
+fn empty_cipher() {
+    assert_eq!(Pkcs7::default().pad(vec![], 16), vec![16; 16]);
+    assert_eq!(Pkcs7::default().unpad(vec![16; 16], 16).unwrap(), vec![]);
+
+    let key: [u8; 16] = std::array::from_fn(|i| i as u8);
+    let cbc = Cbc::new(Aes128::default(), Pkcs7::default(), [0x42; 16]);
+    let ciphertext = cbc.encrypt(vec![], key).unwrap();
+    assert_eq!(hex::encode(&ciphertext), "4e81903f65984705490aa73d94e4cbee");
+    assert_eq!(cbc.decrypt(ciphertext, key).unwrap(), vec![]);
+
+    let ecb = Ecb::new(Aes128::default(), Pkcs7::default());
+    let ciphertext = ecb.encrypt(vec![], key).unwrap();
+    assert_eq!(hex::encode(&ciphertext), "954f64f2e4e86e9eee82d20216684899");
+    assert_eq!(ecb.decrypt(ciphertext, key).unwrap(), vec![]);
+
+    let ctr = Ctr::new(Aes128::default(), 0).unwrap();
+    assert_eq!(ctr.encrypt(vec![], key).unwrap(), vec![]);
+    assert_eq!(ctr.decrypt(vec![], key).unwrap(), vec![]);
+
+    let otp = OneTimePad::default();
+    assert_eq!(otp.encrypt(vec![], std::iter::empty()).unwrap(), vec![]);
+
+    let mut etm = EncryptThenMac::new(
+        Cbc::new(Aes128::default(), Pkcs7::default(), [0x42; 16]),
+        Hmac::new(Sha256::default()),
+    );
+    let envelope = etm.seal(b"", b"key").unwrap();
+    assert_eq!(etm.open(&envelope, b"key").unwrap(), b"");
+}
+
+/// Every signature scheme can sign the empty message, and the signature
+/// doesn't verify for a message consisting of a single zero byte.
+#[test]
+fn empty_signature() {
+    let key = PrivateKey::new(Num::from_le_words([7, 0, 0, 0])).unwrap();
+    let pubkey = key.derive();
+
+    let mut ecdsa = Ecdsa::new(Secp256k1::default(), Sha256::default());
+    let sig = ecdsa.sign(key.clone(), b"");
+    assert!(ecdsa.verify(pubkey, b"", &sig).is_ok());
+    assert!(ecdsa.verify(pubkey, &[0], &sig).is_err());
+
+    let mut schnorr = Schnorr::new(Secp256k1::default(), Sha256::default());
+    let sig = schnorr.sign(key.clone(), b"");
+    assert!(schnorr.verify(pubkey, b"", &sig).is_ok());
+    assert!(schnorr.verify(pubkey, &[0], &sig).is_err());
+
+    let mut multi = MultiSchnorr::new(Secp256k1::default(), Sha256::default());
+    let (nonce, round1) = multi.round1(&key, &mut rng());
+    let share = multi
+        .round2(&key, nonce, &[pubkey], &[round1], b"")
+        .unwrap();
+    let sig = multi.aggregate(&[pubkey], &[share]).unwrap();
+    assert!(multi.verify(&[pubkey], b"", &sig).is_ok());
+    assert!(multi.verify(&[pubkey], &[0], &sig).is_err());
+
+    let decoys: Vec<PublicKey<Secp256k1>> = [2, 3]
+        .map(|n| {
+            PrivateKey::new(Num::from_le_words([n, 0, 0, 0]))
+                .unwrap()
+                .derive()
+        })
+        .to_vec();
+    let mut sag = SchnorrSag::new(Secp256k1::default(), Sha256::default());
+    let sig = sag.sign(key, &decoys, b"", &mut rng());
+    assert!(sag.verify(b"", &sig).is_ok());
+    assert!(sag.verify(&[0], &sig).is_err());
+}
@@ -0,0 +1,233 @@
+use {
+    crate::{
+        testing::NoEntropy,
+        util::CollectVec,
+        Aes256,
+        Entropy,
+        EntropyPool,
+        Fortuna,
+        HealthTestConfig,
+        HealthTested,
+        Sha256,
+        TimingJitter,
+    },
+    rand::{rngs::StdRng, RngCore, SeedableRng},
+};
+
+#[cfg(unix)]
+use crate::OsEntropy;
+
+/// The pool output depends on every registered source: changing the output of
+/// any one of them changes the output of the pool.
+#[test]
+fn pool_depends_on_every_source() {
+    let output = |bytes: [u8; 3]| {
+        let mut pool = EntropyPool::new()
+            .add_source(Box::new(ConstEntropy(bytes[0])), 1)
+            .add_source(Box::new(ConstEntropy(bytes[1])), 2)
+            .add_source(Box::new(ConstEntropy(bytes[2])), 3);
+        let mut buf = [0; 100];
+        pool.get(&mut buf);
+        buf
+    };
+
+    let base = output([1, 2, 3]);
+    assert_eq!(base, output([1, 2, 3]));
+    assert_ne!(base, output([0, 2, 3]));
+    assert_ne!(base, output([1, 0, 3]));
+    assert_ne!(base, output([1, 2, 0]));
+}
+
+/// A source which fails to produce any bytes doesn't stall the pool, and the
+/// pool still produces output from the remaining sources.
+#[test]
+fn pool_failing_source() {
+    let mut pool = EntropyPool::new()
+        .add_source(Box::new(FailingEntropy), 1)
+        .add_source(Box::new(ConstEntropy(1)), 1);
+    let mut buf = [0; 64];
+    pool.get(&mut buf);
+    assert_ne!(buf, [0; 64]);
+
+    let mut pool = EntropyPool::new()
+        .add_source(Box::new(FailingEntropy), 1)
+        .add_source(Box::new(ConstEntropy(2)), 1);
+    let mut other = [0; 64];
+    pool.get(&mut other);
+    assert_ne!(buf, other);
+}
+
+/// The pool works with one, two, and three sources, and can seed Fortuna. The
+/// state carries over between requests, so the pool doesn't repeat itself even
+/// when its sources do.
+#[test]
+fn pool_sources() {
+    let pools = || {
+        [
+            EntropyPool::new().add_source(Box::new(ConstEntropy(7)), 8),
+            EntropyPool::new()
+                .add_source(Box::new(CounterEntropy(0)), 8)
+                .add_source(Box::new(NoEntropy), 1),
+            EntropyPool::new()
+                .add_source(Box::new(PrngEntropy(StdRng::seed_from_u64(1))), 8)
+                .add_source(Box::new(NoEntropy), 1)
+                .add_source(Box::new(ConstEntropy(7)), 1),
+        ]
+    };
+    for (mut pool, mut again) in pools().into_iter().zip(pools()) {
+        let mut a = [0; 40];
+        let mut b = [0; 40];
+        pool.get(&mut a);
+        pool.get(&mut b);
+        assert_ne!(a, [0; 40]);
+        assert_ne!(a, b);
+
+        // The same sources give the same output.
+        let mut c = [0; 40];
+        again.get(&mut c);
+        assert_eq!(a, c);
+        again.get(&mut c);
+        assert_eq!(b, c);
+
+        let bytes = Fortuna::new(pool, Aes256::default(), Sha256::default())
+            .unwrap()
+            .into_iter()
+            .take(4096)
+            .collect_vec();
+        assert!((0..=u8::MAX).all(|x| bytes.contains(&x)));
+    }
+}
+
+/// Timing jitter has at least one bit of min-entropy per byte, by the
+/// most-common-value estimate of Section 6.3.1 of [NIST SP
+/// 800-90B](https://nvlpubs.nist.gov/nistpubs/SpecialPublications/NIST.SP.800-90B.pdf):
+/// the upper bound of the 99% confidence interval for the probability of the
+/// most common byte is below one half.
+///
+/// The quality of the jitter depends on the machine and its load, so the bound
+/// is deliberately loose. It still fails for a source which is stuck, or which
+/// only alternates between a few values.
+#[test]
+fn timing_jitter() {
+    const SAMPLES: usize = 4096;
+    let mut buf = vec![0; SAMPLES];
+    TimingJitter::default().get(&mut buf);
+    let mut counts = [0; 256];
+    buf.iter().for_each(|&b| counts[usize::from(b)] += 1);
+    let most_common = counts.into_iter().max().unwrap();
+    let p = most_common as f64 / SAMPLES as f64;
+    let upper = p + 2.576 * (p * (1.0 - p) / (SAMPLES - 1) as f64).sqrt();
+    assert!(upper < 0.5, "most common byte seen {most_common} times");
+}
+
+/// The operating system fills the whole buffer, with different bytes every
+/// time, and seeds Fortuna.
+#[cfg(unix)]
+#[test]
+fn os_entropy() {
+    let mut source = OsEntropy::default();
+    let mut a = [0; 1000];
+    let mut b = [0; 1000];
+    source.get(&mut a);
+    source.get(&mut b);
+    assert_ne!(a, b);
+    // The chance of 32 zeros in a row anywhere is negligible.
+    assert!(a.chunks(32).all(|chunk| chunk != [0; 32]));
+    assert!(b.chunks(32).all(|chunk| chunk != [0; 32]));
+
+    let a = Fortuna::from_os().into_iter().take(64).collect_vec();
+    let b = Fortuna::from_os().into_iter().take(64).collect_vec();
+    assert_ne!(a, b);
+}
+
+/// A source stuck on a single value fails the repetition count test as soon as
+/// the cutoff is reached.
+#[test]
+fn health_stuck_source() {
+    let mut source = HealthTested::new(ConstEntropy(0x41));
+    let mut buf = [0; 5];
+    source.get(&mut buf);
+    assert!(source.is_healthy());
+    source.get(&mut buf[..1]);
+    assert!(!source.is_healthy());
+    assert_eq!(source.failures(), 1);
+
+    // The test keeps failing every six samples while the source stays broken.
+    // After 62 samples, the adaptive proportion test fails as well.
+    let mut buf = [0; 60];
+    source.get(&mut buf);
+    assert_eq!(source.failures(), 11 + 1);
+
+    // The cutoff is configurable.
+    let mut source = HealthTested::with_config(
+        ConstEntropy(0x41),
+        HealthTestConfig {
+            repetition_cutoff: 3,
+            ..Default::default()
+        },
+    );
+    source.get(&mut buf[..3]);
+    assert!(!source.is_healthy());
+}
+
+/// A source which never repeats a value twice in a row, but cycles through
+/// only four values, fails the adaptive proportion test.
+#[test]
+fn health_counter_source() {
+    let mut source = HealthTested::new(CounterEntropy(0));
+    let mut buf = [0; 244];
+    source.get(&mut buf);
+    assert!(source.is_healthy());
+    // The first sample of the window occurs for the 62nd time.
+    source.get(&mut buf[..1]);
+    assert!(!source.is_healthy());
+    assert_eq!(source.failures(), 1);
+}
+
+/// A source backed by a good PRNG passes the health tests over a long run.
+#[test]
+fn health_prng_source() {
+    let mut source = HealthTested::new(PrngEntropy(StdRng::seed_from_u64(0x5eed)));
+    let mut buf = vec![0; 1 << 16];
+    for _ in 0..32 {
+        source.get(&mut buf);
+    }
+    assert!(source.is_healthy());
+}
+
+/// A source of entropy which always outputs the same byte.
+struct ConstEntropy(u8);
+
+impl Entropy for ConstEntropy {
+    fn get(&mut self, buf: &mut [u8]) {
+        buf.iter_mut().for_each(|x| *x = self.0);
+    }
+}
+
+/// A broken source of entropy which never writes any bytes.
+struct FailingEntropy;
+
+impl Entropy for FailingEntropy {
+    fn get(&mut self, _: &mut [u8]) {}
+}
+
+/// A source of entropy which cycles through the values 0, 1, 2 and 3.
+struct CounterEntropy(u8);
+
+impl Entropy for CounterEntropy {
+    fn get(&mut self, buf: &mut [u8]) {
+        buf.iter_mut().for_each(|x| {
+            *x = self.0;
+            self.0 = (self.0 + 1) % 4;
+        });
+    }
+}
+
+/// A source of entropy backed by a seeded PRNG.
+struct PrngEntropy(StdRng);
+
+impl Entropy for PrngEntropy {
+    fn get(&mut self, buf: &mut [u8]) {
+        self.0.fill_bytes(buf);
+    }
+}
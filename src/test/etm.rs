@@ -0,0 +1,74 @@
+use crate::{
+    kcv,
+    key_fingerprint,
+    Aes128,
+    Aes192,
+    Aes256,
+    Cbc,
+    EncryptThenMac,
+    EnvelopeError,
+    Hmac,
+    Pkcs7,
+    Sha256,
+};
+
+/// Check [KCVs](kcv) against the well-known encryptions of the zero block.
+#[test]
+fn kcv_aes() {
+    assert_eq!(kcv(&Aes128::default(), [0; 16]), [0x66, 0xe9, 0x4b]);
+    assert_eq!(
+        kcv(
+            &Aes128::default(),
+            [
+                0x2b, 0x7e, 0x15, 0x16, 0x28, 0xae, 0xd2, 0xa6, 0xab, 0xf7, 0x15, 0x88, 0x09, 0xcf,
+                0x4f, 0x3c,
+            ]
+        ),
+        [0x7d, 0xf7, 0x6b]
+    );
+    assert_eq!(kcv(&Aes192::default(), [0; 24]), [0xaa, 0xe0, 0x69]);
+    assert_eq!(kcv(&Aes256::default(), [0; 32]), [0xdc, 0x95, 0xc0]);
+}
+
+/// Different keys should have different [fingerprints](key_fingerprint).
+#[test]
+fn fingerprints_differ() {
+    assert_eq!(key_fingerprint(b"key"), key_fingerprint(b"key"));
+    assert_ne!(key_fingerprint(b"key"), key_fingerprint(b"kex"));
+    assert_ne!(key_fingerprint(b""), key_fingerprint(&[0]));
+}
+
+/// Seal and open an [envelope](EncryptThenMac), and check that using the wrong
+/// key is reported differently from tampering with the data.
+#[test]
+fn envelope_wrong_key_and_tampered() {
+    let mut etm = EncryptThenMac::new(
+        Cbc::new(Aes128::default(), Pkcs7::default(), [0x42; 16]),
+        Hmac::new(Sha256::default()),
+    );
+    let msg = b"attack at dawn, bring snacks";
+    let envelope = etm.seal(msg, b"correct horse").unwrap();
+    assert_eq!(etm.open(&envelope, b"correct horse").unwrap(), msg);
+
+    assert_eq!(
+        etm.open(&envelope, b"battery staple"),
+        Err(EnvelopeError::WrongKey)
+    );
+
+    for i in 8..envelope.len() {
+        let mut tampered = envelope.clone();
+        tampered[i] ^= 1;
+        assert_eq!(
+            etm.open(&tampered, b"correct horse"),
+            Err(EnvelopeError::Tampered)
+        );
+    }
+    assert_eq!(
+        etm.open(&envelope[..envelope.len() - 1], b"correct horse"),
+        Err(EnvelopeError::Tampered)
+    );
+    assert_eq!(
+        etm.open(&envelope[..10], b"correct horse"),
+        Err(EnvelopeError::Tampered)
+    );
+}
@@ -1,4 +1,7 @@
-use crate::{util::CollectVec, Aes256, Entropy, Fortuna, Sha256};
+use {
+    crate::{testing::NoEntropy, util::CollectVec, Aes256, Csprng, Ctr, Entropy, Fortuna, Sha256},
+    std::time::Duration,
+};
 
 /// Test that fortuna generates bytes. Don't test the values of those bytes, as
 /// they are pseudo-random.
@@ -9,10 +12,168 @@ fn fortuna_generates_bytes() {
     assert!((0..=u8::MAX).all(|x| bytes.contains(&x)));
 }
 
-pub struct NoEntropy;
+/// Substreams with different labels, and substreams with the same label
+/// derived at different times, give unrelated bytes.
+#[test]
+fn substreams_differ() {
+    let mut rng = fortuna();
+    let key = rng.substream(b"aes key").into_iter().take(64).collect_vec();
+    let iv = rng.substream(b"iv").into_iter().take(64).collect_vec();
+    let again = rng.substream(b"aes key").into_iter().take(64).collect_vec();
+    let parent = rng.into_iter().take(64).collect_vec();
+    assert_ne!(key, iv);
+    assert_ne!(key, again);
+    assert_ne!(key, parent);
+    assert_ne!(iv, parent);
+
+    // The substreams are deterministic given the seeds of the parent.
+    let mut other = fortuna();
+    assert_eq!(
+        other
+            .substream(b"aes key")
+            .into_iter()
+            .take(64)
+            .collect_vec(),
+        key
+    );
+}
+
+/// The parent stream is the same whether or not substreams were derived, and
+/// the substreams keep going past a reseed.
+#[test]
+fn substreams_leave_parent_alone() {
+    let expected = fortuna().into_iter().take(5000).collect_vec();
+
+    let mut rng = fortuna();
+    let sub = rng.substream(b"nonce").into_iter().take(5000).collect_vec();
+    rng.substream(b"nonce");
+    assert_eq!(rng.into_iter().take(5000).collect_vec(), expected);
+    assert_ne!(sub, expected);
+    assert_ne!(sub[..2048], sub[2048..4096]);
+}
+
+/// Events only change the output once the first pool has collected enough of
+/// them, and then change it right away.
+#[test]
+fn events_reseed() {
+    let mut expected = [0; 100];
+    let mut rng = fortuna().with_reseed_interval(Duration::ZERO);
+    rng.fill_bytes(&mut expected[..50]);
+    rng.fill_bytes(&mut expected[50..]);
+
+    let mut rng = fortuna().with_reseed_interval(Duration::ZERO);
+    let mut bytes = [0; 100];
+    rng.fill_bytes(&mut bytes[..50]);
+    // Each source starts at the first pool, so 32 events of 2 bytes from
+    // different sources fill the first pool up to 64 bytes.
+    for source in 0..31 {
+        rng.add_event(source, &[source, 1]);
+    }
+    rng.fill_bytes(&mut bytes[50..75]);
+    assert_eq!(rng.reseeds(), 0);
+    assert_eq!(bytes[..75], expected[..75]);
+
+    rng.add_event(31, &[31, 1]);
+    assert_eq!(rng.pool_size(0), 64);
+    rng.fill_bytes(&mut bytes[75..]);
+    assert_eq!(rng.reseeds(), 1);
+    assert_eq!(rng.pool_size(0), 0);
+    assert_ne!(bytes[75..], expected[75..]);
+}
+
+/// Pool i is used for every reseed whose number is divisible by 2^i, and no
+/// other.
+#[test]
+fn pool_schedule() {
+    let mut rng = fortuna().with_reseed_interval(Duration::ZERO);
+    let mut byte = [0];
+    for reseed in 1..=16u64 {
+        // The events of a source go to the pools in turn, so this is one event
+        // for every pool.
+        for _ in 0..32 {
+            rng.add_event(0, &[0; 64]);
+        }
+        rng.fill_bytes(&mut byte);
+        assert_eq!(rng.reseeds(), reseed);
+        for i in 0..32 {
+            let size = 64 * (reseed % (1 << i));
+            assert_eq!(rng.pool_size(i), usize::try_from(size).unwrap(), "pool {i}");
+        }
+    }
+}
+
+/// Without a minimum interval reseeds from the pools happen as often as they
+/// are ready, but the default interval holds them back.
+#[test]
+fn reseed_interval() {
+    let mut rng = fortuna();
+    let mut byte = [0];
+    for _ in 0..3 {
+        rng.add_event(0, &[0; 64]);
+        rng.fill_bytes(&mut byte);
+    }
+    assert_eq!(rng.reseeds(), 1);
+}
+
+/// Assert that [`fill_bytes`](Csprng::fill_bytes) gives the same bytes as
+/// the iterator, however the requests are split, including across a reseed
+/// from the source of entropy.
+#[test]
+fn fill_bytes_matches_iterator() {
+    let expected = fortuna().into_iter().take(5000).collect_vec();
+
+    let mut rng = fortuna();
+    let mut bytes = vec![0; 5000];
+    let (head, tail) = bytes.split_at_mut(1);
+    rng.fill_bytes(head);
+    let (mid, tail) = tail.split_at_mut(3000);
+    rng.fill_bytes(mid);
+    rng.fill_bytes(tail);
+    assert_eq!(bytes, expected);
+
+    let mut rng = fortuna();
+    assert_eq!(rng.bytes().take(5000).collect_vec(), expected);
+}
+
+/// Assert that generators in the same state which are reseeded with the same
+/// seed give the same bytes, and that the seed changes the output right away.
+#[test]
+fn reseed_reproduces_stream() {
+    let stream = |seed: &[u8]| {
+        let mut rng = fortuna();
+        let mut bytes = [0; 100];
+        rng.fill_bytes(&mut bytes[..50]);
+        rng.reseed(seed);
+        rng.fill_bytes(&mut bytes[50..]);
+        bytes
+    };
+    let plain = fortuna().into_iter().take(100).collect_vec();
+    let a = stream(b"seed");
+    assert_eq!(a, stream(b"seed"));
+    assert_eq!(a[..50], plain[..50]);
+    assert_ne!(a[50..], plain[50..]);
+    assert_ne!(a[50..], stream(b"other seed")[50..]);
+}
+
+/// Assert that a generator can be shared between threads, as long as its
+/// source of entropy can.
+#[test]
+fn fortuna_sync() {
+    fn assert_sync<T: Sync>() {}
+    assert_sync::<Fortuna<NoEntropy>>();
+    assert_sync::<Ctr<Aes256>>();
+}
+
+fn fortuna() -> Fortuna<CountingEntropy> {
+    Fortuna::new(CountingEntropy(0), Aes256::default(), Sha256::default()).unwrap()
+}
+
+/// Entropy which changes with every call, so that every reseed matters.
+struct CountingEntropy(u8);
 
-impl Entropy for NoEntropy {
+impl Entropy for CountingEntropy {
     fn get(&mut self, buf: &mut [u8]) {
-        buf.iter_mut().for_each(|x| *x = 0);
+        buf.fill(self.0);
+        self.0 = self.0.wrapping_add(1);
     }
 }
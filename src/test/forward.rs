@@ -0,0 +1,117 @@
+//! The forwarding implementations for references and smart pointers must give
+//! the same results as the owned values they point to.
+
+use {
+    crate::{
+        ecc::{Num, PrivateKey},
+        testing::NoEntropy,
+        Aes128,
+        Aes256,
+        BlockEncrypt,
+        Cbc,
+        CipherEncrypt,
+        Ecdsa,
+        Fortuna,
+        Hash,
+        Hmac,
+        Mac,
+        Padding,
+        Pkcs7,
+        Secp256k1,
+        Sha256,
+        Sha3_256,
+        SignatureScheme,
+    },
+    std::sync::Arc,
+};
+
+/// One [`Sha256`] can be shared between an [`Hmac`] and plain hashing.
+#[test]
+fn shared_hash() {
+    let sha = Sha256::default();
+    let mut borrowed = Hmac::new(&sha);
+    let mut owned = Hmac::new(Sha256::default());
+    assert_eq!(borrowed.mac(b"msg", b"key"), owned.mac(b"msg", b"key"));
+    assert_eq!(digest(&sha), sha.hash(b"msg"));
+    assert_eq!(digest(Box::<Sha256>::default()), sha.hash(b"msg"));
+}
+
+/// A boxed or borrowed MAC is still a MAC.
+#[test]
+fn forwarded_mac() {
+    let mut owned = Hmac::new(Sha256::default());
+    let expected = owned.mac(b"msg", b"key");
+    assert_eq!(tag(&mut owned), expected);
+    let mut boxed: Box<dyn Mac<Tag = [u8; 32]>> = Box::new(Hmac::new(Sha256::default()));
+    assert_eq!(boxed.mac(b"msg", b"key"), expected);
+}
+
+/// ECDSA over an [`Arc`] of a hash signs exactly like ECDSA over the hash.
+#[test]
+fn arc_ecdsa() {
+    let key = PrivateKey::<Secp256k1>::new(Num::from_le_words([7, 0, 0, 0])).unwrap();
+    let hash = Arc::new(Sha3_256::default());
+    let mut shared = Ecdsa::new(Secp256k1::default(), Arc::clone(&hash));
+    let mut owned = Ecdsa::new(Secp256k1::default(), Sha3_256::default());
+    let a = shared.sign(key.clone(), b"msg");
+    let b = owned.sign(key.clone(), b"msg");
+    assert_eq!((a.r(), a.s()), (b.r(), b.s()));
+    shared.verify(key.derive(), b"msg", &a).unwrap();
+
+    // A boxed signature scheme can be used behind a trait object.
+    let mut boxed: Box<dyn SignatureScheme<PublicKey = _, PrivateKey = _, Signature = _>> =
+        Box::new(shared);
+    boxed.verify(key.derive(), b"msg", &a).unwrap();
+}
+
+/// Block ciphers and padding schemes can be borrowed by a mode of operation.
+#[test]
+fn borrowed_cipher() {
+    let aes = Aes128::default();
+    let pad = Pkcs7::default();
+    let key = [1; 16];
+    assert_eq!(
+        encrypted(Arc::new(Aes128::default()), key),
+        aes.encrypt([2; 16], key)
+    );
+    assert_eq!(padded(Box::<Pkcs7>::default()), pad.pad(vec![3; 5], 16));
+    let borrowed = Cbc::new(&aes, &pad, [4; 16]).encrypt(b"attack at dawn".to_vec(), key);
+    let owned = Cbc::new(aes, pad, [4; 16]).encrypt(b"attack at dawn".to_vec(), key);
+    assert_eq!(borrowed, owned);
+}
+
+/// A borrowed entropy source can seed a [`Fortuna`] without giving it up.
+#[test]
+fn borrowed_entropy() {
+    let mut entropy = NoEntropy;
+    let a: Vec<_> = Fortuna::new(&mut entropy, Aes256::default(), Sha256::default())
+        .unwrap()
+        .into_iter()
+        .take(32)
+        .collect();
+    let b: Vec<_> = Fortuna::new(NoEntropy, Aes256::default(), Sha256::default())
+        .unwrap()
+        .into_iter()
+        .take(32)
+        .collect();
+    assert_eq!(a, b);
+}
+
+fn digest(hash: impl Hash<Digest = [u8; 32]>) -> [u8; 32] {
+    hash.hash(b"msg")
+}
+
+fn tag(mut mac: impl Mac<Tag = [u8; 32]>) -> [u8; 32] {
+    mac.mac(b"msg", b"key")
+}
+
+fn encrypted(
+    cip: impl BlockEncrypt<EncryptionBlock = [u8; 16], EncryptionKey = [u8; 16]>,
+    key: [u8; 16],
+) -> [u8; 16] {
+    cip.encrypt([2; 16], key)
+}
+
+fn padded(pad: impl Padding) -> Vec<u8> {
+    pad.pad(vec![3; 5], 16)
+}
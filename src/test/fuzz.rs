@@ -0,0 +1,316 @@
+//! A small fuzzer for the parsers, which are the attack surface of the crate:
+//! every byte they see may come from an attacker.
+//!
+//! Each target takes arbitrary bytes and must never panic. If the input is
+//! accepted, encoding the result again must give back the input, so that every
+//! value has exactly one encoding. The inputs are mutations of a corpus of
+//! valid encodings, with [Fortuna](crate::Fortuna) as the source of randomness
+//! so that any failure can be reproduced.
+//!
+//! The smoke tests run a few thousand inputs per target. The long runs are
+//! ignored by default; run them with `cargo test fuzz -- --ignored`.
+
+use {
+    crate::{
+        base64::{self, Alphabet},
+        ecc::{Num, PublicKey},
+        hex,
+        testing::{key, rng},
+        Aes128,
+        Ecies,
+        EciesCiphertext,
+        KeyAggContext,
+        MultiSchnorr,
+        Padding,
+        Pkcs7,
+        RingScheme,
+        SchnorrRandomness,
+        SchnorrSag,
+        SchnorrSagSignature,
+        Secp256k1,
+        Sha256,
+        SigningSession,
+    },
+    std::panic::{self, AssertUnwindSafe},
+};
+
+const SMOKE: usize = 5_000;
+const LONG: usize = 2_000_000;
+
+#[test]
+fn fuzz_pkcs7_smoke() {
+    fuzz(&pkcs7_corpus(), SMOKE, pkcs7_target);
+}
+
+#[test]
+#[ignore]
+fn fuzz_pkcs7() {
+    fuzz(&pkcs7_corpus(), LONG, pkcs7_target);
+}
+
+#[test]
+fn fuzz_hex_smoke() {
+    fuzz(&hex_corpus(), SMOKE, hex_target);
+}
+
+#[test]
+#[ignore]
+fn fuzz_hex() {
+    fuzz(&hex_corpus(), LONG, hex_target);
+}
+
+#[test]
+fn fuzz_base64_smoke() {
+    fuzz(&base64_corpus(), SMOKE, base64_target);
+}
+
+#[test]
+#[ignore]
+fn fuzz_base64() {
+    fuzz(&base64_corpus(), LONG, base64_target);
+}
+
+#[test]
+fn fuzz_sec1_smoke() {
+    fuzz(&sec1_corpus(), SMOKE, sec1_target);
+}
+
+#[test]
+#[ignore]
+fn fuzz_sec1() {
+    fuzz(&sec1_corpus(), LONG, sec1_target);
+}
+
+#[test]
+fn fuzz_ecies_smoke() {
+    fuzz(&ecies_corpus(), SMOKE, ecies_target);
+}
+
+#[test]
+#[ignore]
+fn fuzz_ecies() {
+    fuzz(&ecies_corpus(), LONG, ecies_target);
+}
+
+#[test]
+fn fuzz_session_smoke() {
+    fuzz(&session_corpus(), SMOKE, session_target);
+}
+
+#[test]
+#[ignore]
+fn fuzz_session() {
+    fuzz(&session_corpus(), LONG, session_target);
+}
+
+#[test]
+fn fuzz_sag_smoke() {
+    fuzz(&sag_corpus(), SMOKE, sag_target);
+}
+
+#[test]
+#[ignore]
+fn fuzz_sag() {
+    fuzz(&sag_corpus(), LONG, sag_target);
+}
+
+/// The first byte picks the block size, the rest is the padded data.
+fn pkcs7_target(input: &[u8]) {
+    let Some((&n, data)) = input.split_first() else {
+        return;
+    };
+    let n = usize::from(n.max(1));
+    if let Ok(unpadded) = Pkcs7::default().unpad(data.to_vec(), n) {
+        assert_eq!(Pkcs7::default().pad(unpadded, n), data);
+    }
+}
+
+fn hex_target(input: &[u8]) {
+    let Ok(input) = std::str::from_utf8(input) else {
+        return;
+    };
+    if let Ok(bytes) = hex::decode(input) {
+        assert_eq!(hex::encode(&bytes), input.to_lowercase());
+    }
+}
+
+fn base64_target(input: &[u8]) {
+    let Ok(input) = std::str::from_utf8(input) else {
+        return;
+    };
+    for alphabet in [Alphabet::Standard, Alphabet::UrlSafe] {
+        if let Ok(bytes) = base64::decode(input, alphabet) {
+            let padded = input.len() % 4 == 0;
+            assert_eq!(base64::encode(&bytes, alphabet, padded), input);
+        }
+    }
+}
+
+fn sec1_target(input: &[u8]) {
+    if let Ok(key) = PublicKey::<Secp256k1>::from_sec1_bytes(input) {
+        assert_eq!(key.to_sec1_bytes(input[0] != 0x04), input);
+    }
+}
+
+fn ecies_target(input: &[u8]) {
+    if let Ok(ciphertext) = EciesCiphertext::<Secp256k1, 16, 32>::from_bytes(input) {
+        assert_eq!(ciphertext.to_bytes(), input);
+    }
+}
+
+fn session_target(input: &[u8]) {
+    if let Ok(session) = SigningSession::<Secp256k1, Sha256>::from_bytes(input) {
+        assert_eq!(session.to_bytes(), input);
+    }
+}
+
+fn sag_target(input: &[u8]) {
+    if let Ok(sig) = SchnorrSagSignature::<Secp256k1>::from_bytes(input) {
+        assert_eq!(sig.to_bytes(), input);
+    }
+}
+
+fn pkcs7_corpus() -> Vec<Vec<u8>> {
+    [0, 1, 15, 16, 17, 31]
+        .into_iter()
+        .flat_map(|len| {
+            [8u8, 16].map(|n| {
+                let mut seed = vec![n];
+                seed.extend(Pkcs7::default().pad(vec![0xaa; len], n.into()));
+                seed
+            })
+        })
+        .collect()
+}
+
+fn hex_corpus() -> Vec<Vec<u8>> {
+    ["", "66", "666F6F626172", "00ff", "DEADbeef"]
+        .map(|s| s.as_bytes().to_vec())
+        .to_vec()
+}
+
+fn base64_corpus() -> Vec<Vec<u8>> {
+    ["", "Zg==", "Zm8=", "Zm9v", "Zm9vYmFy", "Zg", "+/+/", "-_-_"]
+        .map(|s| s.as_bytes().to_vec())
+        .to_vec()
+}
+
+fn sec1_corpus() -> Vec<Vec<u8>> {
+    [1, 2, 7]
+        .into_iter()
+        .flat_map(|n| [true, false].map(|compressed| key(n).derive().to_sec1_bytes(compressed)))
+        .collect()
+}
+
+pub(super) fn ecies_corpus() -> Vec<Vec<u8>> {
+    let mut ecies = Ecies::<_, _, Aes128>::new(Secp256k1::default(), Sha256::default());
+    let mut rng = rng();
+    [&b""[..], b"attack at dawn", &[0; 40]]
+        .map(|msg| ecies.encrypt(key(7).derive(), msg, &mut rng).to_bytes())
+        .to_vec()
+}
+
+pub(super) fn session_corpus() -> Vec<Vec<u8>> {
+    let hash = Sha256::default();
+    let randomness =
+        SchnorrRandomness::<Secp256k1>::new(&hash, Num::from_le_words([11, 0, 0, 0]), &[]).unwrap();
+    let mut schnorr = MultiSchnorr::new(Secp256k1::default(), Sha256::default());
+    let agg = KeyAggContext::sorted(&hash, &[key(2).derive(), key(3).derive()]).unwrap();
+    let session = schnorr.start_session(&agg, randomness.clone(), b"msg");
+    let mut contributed = schnorr.start_session(&agg, randomness.clone(), b"msg");
+    let partial = schnorr
+        .sign_partial(key(2), &agg, &session, randomness)
+        .unwrap();
+    contributed.contribute(partial).unwrap();
+    vec![session.to_bytes(), contributed.to_bytes()]
+}
+
+fn sag_corpus() -> Vec<Vec<u8>> {
+    let mut sag = SchnorrSag::new(Secp256k1::default(), Sha256::default());
+    let mut rng = rng();
+    [
+        &[][..],
+        &[key(2).derive()],
+        &[key(3).derive(), key(5).derive()],
+    ]
+    .map(|decoys| sag.sign(key(7), decoys, b"msg", &mut rng).to_bytes())
+    .to_vec()
+}
+
+/// Run the target on `iterations` inputs, each of which is either a mutated
+/// seed from the corpus or completely random. A panic is reported together with
+/// the input that caused it.
+fn fuzz(corpus: &[Vec<u8>], iterations: usize, target: fn(&[u8])) {
+    let mut rng = rng().into_iter();
+    // Bytes which appear in the corpus, to keep mutations within the alphabet
+    // of text formats.
+    let dictionary: Vec<u8> = corpus.iter().flatten().copied().collect();
+
+    for seed in corpus {
+        run(target, seed);
+    }
+    for _ in 0..iterations {
+        let mut input = corpus[random(&mut rng, corpus.len())].clone();
+        for _ in 0..=random(&mut rng, 4) {
+            mutate(&mut input, &dictionary, &mut rng);
+        }
+        run(target, &input);
+    }
+}
+
+fn run(target: fn(&[u8]), input: &[u8]) {
+    if panic::catch_unwind(AssertUnwindSafe(|| target(input))).is_err() {
+        panic!("fuzz target panicked on input {}", hex::encode(input));
+    }
+}
+
+/// Apply one random mutation to the input.
+fn mutate(input: &mut Vec<u8>, dictionary: &[u8], rng: &mut impl Iterator<Item = u8>) {
+    let byte = |rng: &mut dyn Iterator<Item = u8>| {
+        if rng.next().unwrap() % 2 == 0 && !dictionary.is_empty() {
+            dictionary[random(rng, dictionary.len())]
+        } else {
+            rng.next().unwrap()
+        }
+    };
+    let len = input.len();
+    match rng.next().unwrap() % 6 {
+        // Replace a byte.
+        0 if len > 0 => {
+            let i = random(rng, len);
+            input[i] = byte(rng);
+        }
+        // Flip a single bit.
+        1 if len > 0 => {
+            let i = random(rng, len);
+            input[i] ^= 1 << (rng.next().unwrap() % 8);
+        }
+        // Insert a byte.
+        2 => {
+            let i = random(rng, len + 1);
+            let b = byte(rng);
+            input.insert(i, b);
+        }
+        // Remove a byte.
+        3 if len > 0 => {
+            input.remove(random(rng, len));
+        }
+        // Truncate.
+        4 => input.truncate(random(rng, len + 1)),
+        // Replace everything with random bytes.
+        _ => {
+            let len = random(rng, 2 * len + 8);
+            *input = (0..len).map(|_| rng.next().unwrap()).collect();
+        }
+    }
+}
+
+/// A random number in `0..n`. The modulo bias doesn't matter here.
+fn random(rng: &mut (impl Iterator<Item = u8> + ?Sized), n: usize) -> usize {
+    let bytes = [
+        rng.next().unwrap(),
+        rng.next().unwrap(),
+        rng.next().unwrap(),
+    ];
+    (usize::from(bytes[0]) << 16 | usize::from(bytes[1]) << 8 | usize::from(bytes[2])) % n
+}
@@ -0,0 +1,167 @@
+use {
+    crate::{
+        gf128_mul,
+        ghash,
+        hex,
+        test::vectors::{load_vectors, Record, Vector, VectorError},
+        Aes128,
+        AuthenticationFailed,
+        Gcm,
+    },
+    rand::Rng,
+};
+
+/// The element $1$ of $GF(2^{128})$ in the reflected bit order of GCM.
+const ONE: u128 = 1 << 127;
+
+/// An AES-128 case from `tests/vectors/aes128_gcm.txt`.
+struct GcmCase {
+    key: [u8; 16],
+    nonce: [u8; 12],
+    aad: Vec<u8>,
+    plaintext: Vec<u8>,
+    ciphertext: Vec<u8>,
+    tag: [u8; 16],
+}
+
+impl Vector for GcmCase {
+    fn parse(record: &mut Record) -> Result<Self, VectorError> {
+        Ok(Self {
+            key: record.array("key")?,
+            nonce: record.array("nonce")?,
+            aad: record.hex("aad")?,
+            plaintext: record.hex("plaintext")?,
+            ciphertext: record.hex("ciphertext")?,
+            tag: record.array("tag")?,
+        })
+    }
+}
+
+/// Assert that [GCM](Gcm) with AES-128 matches the NIST vectors, and that
+/// decryption recovers the plaintext.
+#[test]
+fn gcm_vectors() {
+    for case in load_vectors::<GcmCase>("aes128_gcm.txt") {
+        let gcm = Gcm::new(Aes128::default(), case.nonce);
+        let (ciphertext, tag) = gcm.encrypt(&case.plaintext, &case.aad, case.key).unwrap();
+        assert_eq!(ciphertext, case.ciphertext);
+        assert_eq!(tag, case.tag);
+        assert_eq!(
+            gcm.decrypt(&ciphertext, &case.aad, &tag, case.key).unwrap(),
+            case.plaintext
+        );
+    }
+}
+
+/// Assert that [GCM](Gcm) rejects a message if any bit of the ciphertext, the
+/// AAD or the tag is flipped, or if the ciphertext is truncated.
+#[test]
+fn gcm_tampering_rejected() {
+    let key = [0x2b; 16];
+    let gcm = Gcm::new(Aes128::default(), [7; 12]);
+    let (ciphertext, tag) = gcm.encrypt(b"attack at dawn!!!", b"header", key).unwrap();
+
+    for i in 0..ciphertext.len() * 8 {
+        let mut tampered = ciphertext.clone();
+        tampered[i / 8] ^= 1 << (i % 8);
+        assert_eq!(
+            gcm.decrypt(&tampered, b"header", &tag, key),
+            Err(AuthenticationFailed),
+            "ciphertext bit {i}"
+        );
+    }
+    for i in 0..48 {
+        let mut aad = *b"header";
+        aad[i / 8] ^= 1 << (i % 8);
+        assert_eq!(
+            gcm.decrypt(&ciphertext, &aad, &tag, key),
+            Err(AuthenticationFailed),
+            "AAD bit {i}"
+        );
+    }
+    for i in 0..128 {
+        let mut tampered = tag;
+        tampered[i / 8] ^= 1 << (i % 8);
+        assert_eq!(
+            gcm.decrypt(&ciphertext, b"header", &tampered, key),
+            Err(AuthenticationFailed),
+            "tag bit {i}"
+        );
+    }
+    assert_eq!(
+        gcm.decrypt(&ciphertext[..16], b"header", &tag, key),
+        Err(AuthenticationFailed)
+    );
+    // Moving bytes from the ciphertext to the AAD changes the length block.
+    assert_eq!(
+        gcm.decrypt(
+            &ciphertext[1..],
+            &[b"header".as_slice(), &ciphertext[..1]].concat(),
+            &tag,
+            key
+        ),
+        Err(AuthenticationFailed)
+    );
+    // A different key or nonce doesn't authenticate the message either.
+    assert_eq!(
+        gcm.decrypt(&ciphertext, b"header", &tag, [0x3c; 16]),
+        Err(AuthenticationFailed)
+    );
+    assert_eq!(
+        Gcm::new(Aes128::default(), [8; 12]).decrypt(&ciphertext, b"header", &tag, key),
+        Err(AuthenticationFailed)
+    );
+    assert_eq!(
+        gcm.decrypt(&ciphertext, b"header", &tag, key).unwrap(),
+        b"attack at dawn!!!"
+    );
+}
+
+/// Assert that [GF(2^128) multiplication](gf128_mul) reduces $x \cdot
+/// x^{127} = x^{128}$ to $x^7 + x^2 + x + 1$, and matches a product from the
+/// GCM specification.
+#[test]
+fn gf128_mul_known() {
+    // In the reflected order, x is the second most significant bit and x^127
+    // the least significant one.
+    assert_eq!(gf128_mul(ONE >> 1, 1), 0xe1 << 120);
+    assert_eq!(gf128_mul(1, ONE >> 1), 0xe1 << 120);
+    assert_eq!(gf128_mul(ONE, ONE), ONE);
+    assert_eq!(gf128_mul(0, u128::MAX), 0);
+
+    // X_1 * H from test case 2 of the GCM specification, where H = E_K(0) for
+    // the zero key and X_1 is the first ciphertext block.
+    let h = block("66e94bd4ef8a2c3b884cfa59ca342b2e");
+    let c = block("0388dace60b6a392f328c2b971b2fe78");
+    assert_eq!(gf128_mul(c, h), block("5e2ec746917062882c85b0685353deb7"));
+    // GHASH adds the length block, 128 bits of ciphertext, and multiplies by H
+    // again.
+    assert_eq!(
+        gf128_mul(block("5e2ec746917062882c85b0685353deb7") ^ 128, h),
+        block("f38cbb1ad69223dcc3457ae5b6b0f885")
+    );
+    assert_eq!(
+        ghash(h, &[], &c.to_be_bytes()),
+        block("f38cbb1ad69223dcc3457ae5b6b0f885")
+    );
+}
+
+/// Assert that [GF(2^128) multiplication](gf128_mul) is commutative,
+/// associative, distributes over XOR, and has $1$ as its identity.
+#[test]
+fn gf128_mul_laws() {
+    let mut rng = rand::thread_rng();
+    for _ in 0..200 {
+        let (a, b, c): (u128, u128, u128) = (rng.gen(), rng.gen(), rng.gen());
+        assert_eq!(gf128_mul(a, ONE), a);
+        assert_eq!(gf128_mul(ONE, a), a);
+        assert_eq!(gf128_mul(a, b), gf128_mul(b, a));
+        assert_eq!(gf128_mul(gf128_mul(a, b), c), gf128_mul(a, gf128_mul(b, c)));
+        assert_eq!(gf128_mul(a, b ^ c), gf128_mul(a, b) ^ gf128_mul(a, c));
+    }
+}
+
+/// Read a hex-encoded block as a field element.
+fn block(s: &str) -> u128 {
+    u128::from_be_bytes(hex::decode(s).unwrap().try_into().unwrap())
+}
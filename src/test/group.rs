@@ -0,0 +1,242 @@
+//! The [`Group`] algorithms don't depend on elliptic curves, so they are also
+//! checked in a toy multiplicative group, where every result can be computed
+//! by hand.
+
+use {
+    super::laws::{laws, Sample},
+    crate::{
+        ecc::{Curve, Group, Num, Point, Secp256k1},
+        Choice,
+        CtSelect,
+    },
+    std::cell::RefCell,
+};
+
+/// The nonzero integers modulo 101 under multiplication.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Mod101(u64);
+
+const P: u64 = 101;
+
+impl Group for Mod101 {
+    fn identity() -> Self {
+        Self(1)
+    }
+
+    fn op(self, rhs: Self) -> Self {
+        Self(self.0 * rhs.0 % P)
+    }
+
+    fn inverse(self) -> Self {
+        // By Fermat's little theorem, a^(p-2) * a = a^(p-1) = 1.
+        self.scale(Num::from_le_words([P - 2, 0, 0, 0]))
+    }
+}
+
+impl CtSelect for Mod101 {
+    fn ct_select(a: &Self, b: &Self, choice: Choice) -> Self {
+        Self(u64::ct_select(&a.0, &b.0, choice))
+    }
+
+    fn ct_eq(&self, other: &Self) -> Choice {
+        self.0.ct_eq(&other.0)
+    }
+}
+
+/// The group has order 100, and 2 is a generator.
+impl Sample for Mod101 {
+    fn order() -> Num {
+        num(P - 1)
+    }
+
+    fn generator() -> Self {
+        Self(2)
+    }
+}
+
+laws!(mod101_laws: Mod101);
+
+/// Compute a^n by repeated multiplication.
+fn naive_pow(a: u64, n: u64) -> u64 {
+    (0..n).fold(1, |acc, _| acc * a % P)
+}
+
+fn num(n: u64) -> Num {
+    Num::from_le_words([n, 0, 0, 0])
+}
+
+/// Scaling in the toy group is exponentiation.
+#[test]
+fn toy_scale() {
+    for a in [1, 2, 3, 50, 100] {
+        for n in [0, 1, 2, 7, 100, 101, 255] {
+            assert_eq!(Mod101(a).scale(num(n)), Mod101(naive_pow(a, n)));
+        }
+    }
+    // 2 generates the whole group, so its order is 100.
+    assert_eq!(Mod101(2).scale(num(100)), Mod101::identity());
+    assert_ne!(Mod101(2).scale(num(50)), Mod101::identity());
+}
+
+#[test]
+fn toy_inverse() {
+    for a in 1..P {
+        assert_eq!(Mod101(a).op(Mod101(a).inverse()), Mod101::identity());
+    }
+}
+
+/// The multi-scalar multiplication $a^n b^m$.
+#[docext::docext]
+#[test]
+fn toy_multi_scale() {
+    assert_eq!(
+        Mod101::multi_scale(&[(num(5), Mod101(3)), (num(9), Mod101(7))]),
+        Mod101(naive_pow(3, 5) * naive_pow(7, 9) % P)
+    );
+    assert_eq!(Mod101::multi_scale(&[]), Mod101::identity());
+}
+
+/// Strauss' algorithm in [`multi_scale`](Group::multi_scale) agrees with
+/// scaling every term on its own and combining the results, for any number of
+/// terms and for scalars of different lengths.
+#[test]
+fn multi_scale_matches_fold() {
+    fn fold<G: Group>(terms: &[(Num, G)]) -> G {
+        terms
+            .iter()
+            .fold(G::identity(), |acc, &(n, a)| acc.op(a.scale(n)))
+    }
+
+    let scalars = [
+        Num::ZERO,
+        num(1),
+        num(37),
+        Num::from_le_words([u64::MAX, 7, 0, 0]),
+        Num::from_le_words([0xdead_beef, 0, 0, 1 << 63]),
+        Secp256k1::N.sub(Num::ONE, Secp256k1::N),
+    ];
+    for k in 0..=scalars.len() {
+        let toy: Vec<_> = scalars[..k]
+            .iter()
+            .zip([2, 3, 50, 100, 7, 11])
+            .map(|(&n, a)| (n, Mod101(a)))
+            .collect();
+        assert_eq!(Mod101::multi_scale(&toy), fold(&toy));
+
+        let points: Vec<_> = scalars[..k]
+            .iter()
+            .zip(1..)
+            .map(|(&n, i)| (n, num(i * 1000 + 3) * Secp256k1::g()))
+            .collect();
+        assert_eq!(Point::multi_scale(&points), fold(&points));
+    }
+}
+
+/// The group laws hold for curve points, and the generic scalar multiplication
+/// agrees with the [`Num`] times [`Point`] operator.
+#[test]
+fn point_group() {
+    let g = Secp256k1::g();
+    let p = num(7) * g;
+    assert_eq!(g.op(Point::identity()), g);
+    assert_eq!(Point::<Secp256k1>::identity().inverse(), Point::infinity());
+    assert_eq!(p.op(p.inverse()), Point::infinity());
+    assert_eq!(g.scale(num(3)), num(3) * g);
+    assert_eq!(Point::multi_scale(&[(num(2), g), (num(5), p)]), num(37) * g);
+    assert_eq!(Secp256k1::N.sub(Num::ONE, Secp256k1::N) * g, g.inverse());
+}
+
+/// The Montgomery ladder agrees with double-and-add, in the toy group for
+/// every small exponent and in the curve group for a few scalars.
+#[test]
+fn scale_ct() {
+    for a in [1, 2, 3, 50, 100] {
+        for n in 0..300 {
+            assert_eq!(Mod101(a).scale_ct(num(n)), Mod101(a).scale(num(n)));
+        }
+        let n = Num::from_le_words([u64::MAX, 7, 0, 1 << 63]);
+        assert_eq!(Mod101(a).scale_ct(n), Mod101(a).scale(n));
+    }
+
+    let g = Secp256k1::g();
+    assert_eq!(g.scale_ct(Num::ZERO), Point::infinity());
+    assert_eq!(g.scale_ct(num(37)), num(37) * g);
+    let n = Secp256k1::N.sub(Num::ONE, Secp256k1::N);
+    assert_eq!(g.scale_ct(n), g.inverse());
+}
+
+thread_local! {
+    /// The operations of [`Traced`], in the order they were called.
+    static TRACE: RefCell<Vec<&'static str>> = RefCell::new(Vec::new());
+}
+
+/// The toy group, recording every group operation and every swap.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Traced(Mod101);
+
+impl Group for Traced {
+    fn identity() -> Self {
+        Self(Mod101::identity())
+    }
+
+    fn op(self, rhs: Self) -> Self {
+        TRACE.with(|trace| trace.borrow_mut().push("op"));
+        Self(self.0.op(rhs.0))
+    }
+
+    fn inverse(self) -> Self {
+        Self(self.0.inverse())
+    }
+}
+
+impl CtSelect for Traced {
+    fn ct_select(a: &Self, b: &Self, choice: Choice) -> Self {
+        Self(Mod101::ct_select(&a.0, &b.0, choice))
+    }
+
+    fn ct_eq(&self, other: &Self) -> Choice {
+        self.0.ct_eq(&other.0)
+    }
+
+    fn ct_swap(a: &mut Self, b: &mut Self, choice: Choice) {
+        TRACE.with(|trace| trace.borrow_mut().push("swap"));
+        let (x, y) = (*a, *b);
+        *a = Self::ct_select(&x, &y, choice);
+        *b = Self::ct_select(&y, &x, choice);
+    }
+}
+
+/// The ladder runs the same sequence of operations for every scalar: a swap
+/// and two group operations per bit, and a final swap. Double-and-add doesn't.
+#[test]
+fn scale_ct_trace() {
+    let trace = |scale: fn(Traced, Num) -> Traced, n: Num| {
+        TRACE.with(|trace| trace.borrow_mut().clear());
+        scale(Traced(Mod101(3)), n);
+        TRACE.with(|trace| trace.take())
+    };
+
+    let scalars = [
+        Num::ZERO,
+        Num::ONE,
+        num(0b1010_1010),
+        Num::from_le_words([u64::MAX; 4]),
+        Secp256k1::N,
+    ];
+    let expected = trace(Traced::scale_ct, Num::ZERO);
+    assert_eq!(expected.len(), 3 * Num::BITS + 1);
+    assert_eq!(
+        expected.iter().filter(|&&e| e == "swap").count(),
+        Num::BITS + 1
+    );
+    assert_eq!(expected[..4], ["swap", "op", "op", "swap"]);
+    assert_eq!(expected.last(), Some(&"swap"));
+    for n in scalars {
+        assert_eq!(trace(Traced::scale_ct, n), expected);
+    }
+
+    assert_ne!(
+        trace(Traced::scale, Num::ZERO),
+        trace(Traced::scale, Num::from_le_words([u64::MAX; 4]))
+    );
+}
@@ -1,17 +1,38 @@
-use {crate::Hash, std::fmt};
+use {crate::IncrementalHash, std::fmt};
 
+mod adapters;
+mod merkledamgard;
+mod merkletree;
 mod sha1;
+mod sha1cd;
 mod sha2;
 mod sha3;
+mod sm3;
 
-fn test<H: Hash>(hash: &H, preimage: &[u8], output: &[u8])
+/// Check the one-shot digest of the preimage, as well as the
+/// [incremental](IncrementalHash) digest with the preimage split into pieces
+/// of various sizes.
+pub(super) fn test<H: IncrementalHash>(hash: &H, preimage: &[u8], output: &[u8])
 where
     H::Digest: AsRef<[u8]> + fmt::Debug,
 {
-    let hash = hash.hash(preimage);
+    let digest = hash.hash(preimage);
     assert_eq!(
-        hash.as_ref(),
+        digest.as_ref(),
         output,
-        "invalid hash for:\n{preimage:#?}\n\nexpected:\n{output:#?}\n\ngot:\n{hash:#?}"
+        "invalid hash for:\n{preimage:#?}\n\nexpected:\n{output:#?}\n\ngot:\n{digest:#?}"
     );
+
+    for size in [1, 3, 64, 100] {
+        let mut state = hash.init();
+        preimage
+            .chunks(size)
+            .for_each(|chunk| hash.update(&mut state, chunk));
+        let digest = hash.finalize(state);
+        assert_eq!(
+            digest.as_ref(),
+            output,
+            "invalid incremental hash with {size} byte pieces for:\n{preimage:#?}"
+        );
+    }
 }
@@ -0,0 +1,51 @@
+use {
+    crate::{Hash, Sha256, Sha3_256, StdHasher, WriteHasher},
+    std::{
+        collections::HashMap,
+        fs,
+        hash::{BuildHasher, BuildHasherDefault, Hasher},
+        io,
+    },
+};
+
+/// Streaming a file into a [`WriteHasher`] with [`io::copy`] should give the
+/// same digest as hashing the file contents in one shot.
+#[test]
+fn write_hasher_file() {
+    let data: Vec<u8> = (0..100_000u32).map(|i| (i * 7 + i / 256) as u8).collect();
+    let path = std::env::temp_dir().join(format!("literate-crypto-{}", std::process::id()));
+    fs::write(&path, &data).unwrap();
+
+    let mut file = fs::File::open(&path).unwrap();
+    let mut hasher = WriteHasher::new(Sha256::default());
+    io::copy(&mut file, &mut hasher).unwrap();
+    fs::remove_file(&path).unwrap();
+    assert_eq!(hasher.finalize(), Sha256::default().hash(&data));
+
+    let mut hasher = WriteHasher::new(Sha3_256::default());
+    io::copy(&mut data.as_slice(), &mut hasher).unwrap();
+    assert_eq!(hasher.finalize(), Sha3_256::default().hash(&data));
+}
+
+/// A [`StdHasher`] should produce stable values, and work as the hasher of a
+/// [`HashMap`].
+#[test]
+fn std_hasher() {
+    let mut hasher = StdHasher::new(Sha256::default());
+    hasher.write(b"abc");
+    // The first eight bytes of SHA-256("abc"), in little-endian order.
+    assert_eq!(hasher.finish(), 0xeacf018fbf1678ba);
+    // Finishing doesn't reset the hasher.
+    assert_eq!(hasher.finish(), 0xeacf018fbf1678ba);
+
+    let build = BuildHasherDefault::<StdHasher<Sha256>>::default();
+    // Hashing a str writes its bytes followed by 0xff.
+    assert_eq!(build.hash_one("abc"), 0x0b883612dc083b8e);
+
+    let mut map = HashMap::with_hasher(build);
+    map.insert("abc", 1);
+    map.insert("def", 2);
+    assert_eq!(map.get("abc"), Some(&1));
+    assert_eq!(map.get("def"), Some(&2));
+    assert_eq!(map.get("ghi"), None);
+}
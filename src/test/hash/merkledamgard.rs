@@ -0,0 +1,63 @@
+use {
+    crate::{CompressionFn, Hash, MerkleDamgard, MerkleDamgardPad},
+    std::{cell::RefCell, rc::Rc},
+};
+
+const BLOCK_SIZE: usize = 4;
+
+/// Compression function which records the address of every block it is given.
+#[derive(Debug)]
+struct RecordingCompression(Rc<RefCell<Vec<usize>>>);
+
+impl CompressionFn for RecordingCompression {
+    type Block = [u8; BLOCK_SIZE];
+    type State = u8;
+
+    fn compress(&self, state: Self::State, block: &Self::Block) -> Self::State {
+        self.0.borrow_mut().push(block.as_ptr() as usize);
+        block.iter().fold(state, |state, b| state ^ b)
+    }
+}
+
+/// Padding which appends a single one byte followed by zeros.
+#[derive(Debug)]
+struct OnePadding;
+
+impl MerkleDamgardPad for OnePadding {
+    type Block = [u8; BLOCK_SIZE];
+
+    fn split<'a>(&self, preimage: &'a [u8]) -> (impl Iterator<Item = &'a Self::Block>, &'a [u8])
+    where
+        Self::Block: 'a,
+    {
+        let (blocks, tail) = preimage.split_at(preimage.len() - preimage.len() % BLOCK_SIZE);
+        (blocks.array_chunks(), tail)
+    }
+
+    fn pad_tail(&self, tail: &[u8], _len: u64) -> impl Iterator<Item = Self::Block> {
+        let mut block = [0; BLOCK_SIZE];
+        block[..tail.len()].copy_from_slice(tail);
+        block[tail.len()] = 1;
+        [block].into_iter()
+    }
+}
+
+/// Full blocks are compressed straight from the preimage, only the padded tail
+/// is copied.
+#[test]
+fn merkle_damgard_borrows_blocks() {
+    let preimage: Vec<u8> = (0..4099u32).map(|i| i as u8).collect();
+    let addresses = Rc::default();
+    let hash = MerkleDamgard::new(RecordingCompression(Rc::clone(&addresses)), OnePadding, 0);
+    let digest = hash.hash(&preimage);
+
+    assert_eq!(digest, preimage.iter().fold(1, |state, b| state ^ b));
+    let addresses = addresses.borrow();
+    let (last, blocks) = addresses.split_last().unwrap();
+    assert_eq!(blocks.len(), preimage.len() / BLOCK_SIZE);
+    for (i, &address) in blocks.iter().enumerate() {
+        assert_eq!(address, preimage.as_ptr() as usize + i * BLOCK_SIZE);
+    }
+    let range = preimage.as_ptr_range();
+    assert!(!(range.start as usize..range.end as usize).contains(last));
+}
@@ -0,0 +1,80 @@
+use crate::{hash_chunked_parallel, hex, Hash, MerkleTree, Sha256};
+
+/// The roots of the first 1 to 8 leaves of the RFC 6962 test tree.
+#[test]
+fn merkle_tree_rfc6962() {
+    let leaves = [
+        "",
+        "00",
+        "10",
+        "2021",
+        "3031",
+        "40414243",
+        "5051525354555657",
+        "606162636465666768696a6b6c6d6e6f",
+    ]
+    .map(|leaf| hex::decode(leaf).unwrap());
+    let roots = [
+        "6e340b9cffb37a989ca544e6bb780a2c78901d3fb33738768511a30617afa01d",
+        "fac54203e7cc696cf0dfcb42c92a1d9dbaf70ad9e621f4bd8d98662f00e3c125",
+        "aeb6bcfe274b70a14fb067a5e5578264db0fa9b51af5e0ba159158f329e06e77",
+        "d37ee418976dd95753c1c73862b9398fa2a2cf9b4ff0fdfe8b30cd95209614b7",
+        "4e3bbb1f7b478dcfe71fb631631519a3bca12c9aefca1612bfce4c13a86264d4",
+        "76e67dadbcdf1e10e1b74ddc608abd2f98dfb16fbce75277b5232a127f2087ef",
+        "ddb89be403809e325750d3d263cd78929c2942b7942a34b77e122c9594a74c8c",
+        "5dc9da79a70659a9ad559cb701ded9a2ab9d823aad2f4960cfe370eff4604328",
+    ];
+    let hash = Sha256::default();
+    for (n, root) in roots.iter().enumerate() {
+        let tree = MerkleTree::build(&hash, &leaves[..=n]);
+        assert_eq!(tree.len(), n + 1);
+        assert_eq!(hex::encode(tree.root()), *root, "{} leaves", n + 1);
+    }
+
+    let empty = MerkleTree::build::<_, &[u8]>(&hash, &[]);
+    assert!(empty.is_empty());
+    assert_eq!(*empty.root(), hash.hash(&[]));
+}
+
+/// The parallel build gives the same root as the sequential build, for any
+/// number of leaves and threads.
+#[test]
+fn merkle_tree_parallel() {
+    let hash = Sha256::default();
+    let leaves: Vec<Vec<u8>> = (0..1000u32).map(|i| i.to_le_bytes().to_vec()).collect();
+    let counts = (0..=20)
+        .chain((21..=1000).step_by(47))
+        .chain([511, 512, 513, 1000]);
+    for n in counts {
+        let sequential = MerkleTree::build(&hash, &leaves[..n]);
+        for threads in 1..=8 {
+            assert_eq!(
+                MerkleTree::build_parallel(&hash, &leaves[..n], threads),
+                sequential,
+                "{n} leaves, {threads} threads"
+            );
+        }
+    }
+}
+
+/// The chunked tree hash is the root of the tree over the chunks, which is
+/// different from the flat hash of the data.
+#[test]
+fn merkle_tree_chunked() {
+    let hash = Sha256::default();
+    let data: Vec<u8> = (0..10000).map(|i| (i % 251) as u8).collect();
+    for threads in [1, 3, 8] {
+        let digest = hash_chunked_parallel(&hash, &data, 1000, threads);
+        assert_eq!(
+            hex::encode(&digest),
+            "a8f8a7e941409e0b5c50c6fb0aa7d9860c073d607010de3982ceb06b2e274147"
+        );
+        assert_ne!(digest, hash.hash(&data));
+    }
+}
+
+#[test]
+#[should_panic = "chunk size must not be zero"]
+fn merkle_tree_chunked_zero_size() {
+    hash_chunked_parallel(&Sha256::default(), b"data", 0, 1);
+}
@@ -0,0 +1,90 @@
+use crate::{hex, testing::rng, Hash, Sha1, Sha1Cd};
+
+/// The first 192 bytes shared by the two SHAttered PDFs, ending in the middle
+/// of a JPEG comment.
+const SHATTERED_PREFIX: &str = "\
+    255044462d312e330a25e2e3cfd30a0a0a312030206f626a0a3c3c2f5769647468203220302052\
+    2f4865696768742033203020522f547970652034203020522f537562747970652035203020522f\
+    46696c7465722036203020522f436f6c6f7253706163652037203020522f4c656e677468203820\
+    3020522f42697473506572436f6d706f6e656e7420383e3e0a73747265616d0affd8fffe002453\
+    48412d3120697320646561642121212121852fec092339759c39b1a1c63c4c97e1fffe01";
+
+/// The two near-collision blocks of each SHAttered PDF.
+const SHATTERED_BLOCKS: [&str; 2] = [
+    "7346dc9166b67e118f029ab621b2560ff9ca67cca8c7f85ba84c79030c2b3de218f86db3a90901d5\
+     df45c14f26fedfb3dc38e96ac22fe7bd728f0e45bce046d23c570feb141398bb552ef5a0a82be331\
+     fea48037b8b5d71f0e332edf93ac3500eb4ddc0decc1a864790c782c76215660dd309791d06bd0af\
+     3f98cda4bc4629b1",
+    "7f46dc93a6b67e013b029aaa1db2560b45ca67d688c7f84b8c4c791fe02b3df614f86db1690901c5\
+     6b45c1530afedfb76038e972722fe7ad728f0e4904e046c230570fe9d41398abe12ef5bc942be335\
+     42a4802d98b5d70f2a332ec37fac3514e74ddc0f2cc1a874cd0c78305a21566461309789606bd0bf\
+     3f98cda8044629a1",
+];
+
+/// The first 320 bytes of each SHAttered PDF, which collide.
+fn shattered() -> [Vec<u8>; 2] {
+    SHATTERED_BLOCKS.map(|blocks| hex::decode(&format!("{SHATTERED_PREFIX}{blocks}")).unwrap())
+}
+
+/// The digests are those of plain SHA-1.
+#[test]
+fn sha1_digests() {
+    let data: Vec<u8> = (0..=255).cycle().take(300).collect();
+    for len in [0, 3, 55, 56, 64, 65, 128, 300] {
+        assert_eq!(
+            Sha1Cd::default().hash(&data[..len]),
+            Sha1::default().hash(&data[..len])
+        );
+    }
+}
+
+#[test]
+fn shattered_detected() {
+    let [first, second] = shattered();
+    assert_ne!(first, second);
+    assert_eq!(first.len(), 320);
+
+    let sha1cd = Sha1Cd::default();
+    let (digest, detected) = sha1cd.hash_with_detection(&first);
+    assert!(detected);
+    assert_eq!(digest, Sha1::default().hash(&first));
+    assert_eq!(sha1cd.hash_with_detection(&second), (digest, true));
+    assert_eq!(
+        hex::encode(&digest),
+        "f92d74e3874587aaf443d1db961d4e26dde13e9c"
+    );
+
+    // The detection carries over to any message extending the collision.
+    let mut extended = first.clone();
+    extended.extend(b"%%EOF");
+    assert!(sha1cd.hash_with_detection(&extended).1);
+}
+
+/// Only the second block of an attack, which cancels the state difference
+/// out, completes a collision. The near collision alone is not detected, and
+/// neither is a damaged second block.
+#[test]
+fn shattered_incomplete() {
+    let sha1cd = Sha1Cd::default();
+    for data in shattered() {
+        assert!(!sha1cd.hash_with_detection(&data[..256]).1);
+        for i in [256, 300, 319] {
+            let mut damaged = data.clone();
+            damaged[i] ^= 0x40;
+            assert!(!sha1cd.hash_with_detection(&damaged).1);
+        }
+    }
+}
+
+/// No detection fires for random messages.
+#[test]
+fn no_false_positives() {
+    let sha1cd = Sha1Cd::default();
+    let mut rng = rng().into_iter();
+    for len in (0..4096).map(|i| i % 400) {
+        let data: Vec<u8> = rng.by_ref().take(len).collect();
+        let (digest, detected) = sha1cd.hash_with_detection(&data);
+        assert!(!detected, "false positive for {}", hex::encode(&data));
+        assert_eq!(digest, Sha1::default().hash(&data));
+    }
+}
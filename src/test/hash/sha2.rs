@@ -1,73 +1,326 @@
 use {
     super::test,
-    crate::{Sha224, Sha256},
+    crate::{
+        hex,
+        sha2::{self, consts, LengthPadding, LengthPadding128, Sha1State, Sha2State},
+        test::vectors::{load_vectors, Record, Vector, VectorError},
+        util::CollectVec,
+        IncrementalHash,
+        MerkleDamgardPad,
+        Sha224,
+        Sha256,
+        Sha384,
+        Sha512,
+        Sha512_224,
+        Sha512_256,
+    },
+    std::fmt,
 };
 
+/// A SHA-2 digest from `tests/vectors/sha2.txt`.
+struct Sha2Case {
+    alg: String,
+    msg: String,
+    digest: Vec<u8>,
+}
+
+impl Vector for Sha2Case {
+    fn parse(record: &mut Record) -> Result<Self, VectorError> {
+        Ok(Self {
+            alg: record.text("alg")?,
+            msg: record.text("msg")?,
+            digest: record.hex("digest")?,
+        })
+    }
+}
+
+/// Check the digests of the algorithm `alg` against `hash`.
+fn test_vectors<H: IncrementalHash>(hash: &H, alg: &str)
+where
+    H::Digest: AsRef<[u8]> + fmt::Debug,
+{
+    let cases = load_vectors::<Sha2Case>("sha2.txt")
+        .into_iter()
+        .filter(|case| case.alg == alg)
+        .collect_vec();
+    assert!(!cases.is_empty(), "no vectors for {alg}");
+    for case in cases {
+        test(hash, case.msg.as_bytes(), &case.digest);
+    }
+}
+
 /// SHA-256 test vectors.
 #[test]
 fn sha256() {
-    let hash = Sha256::default();
+    test_vectors(&Sha256::default(), "SHA-256");
+}
 
-    test(
-        &hash,
-        b"abc",
-        &[
-            0xba, 0x78, 0x16, 0xbf, 0x8f, 0x01, 0xcf, 0xea, 0x41, 0x41, 0x40, 0xde, 0x5d, 0xae,
-            0x22, 0x23, 0xb0, 0x03, 0x61, 0xa3, 0x96, 0x17, 0x7a, 0x9c, 0xb4, 0x10, 0xff, 0x61,
-            0xf2, 0x00, 0x15, 0xad,
-        ],
-    );
+/// SHA-224 test vectors.
+#[test]
+fn sha224() {
+    test_vectors(&Sha224::default(), "SHA-224");
+}
 
-    test(
-        &hash,
-        b"abcdbcdecdefdefgefghfghighijhijkijkljklmklmnlmnomnopnopq",
-        &[
-            0x24, 0x8d, 0x6a, 0x61, 0xd2, 0x06, 0x38, 0xb8, 0xe5, 0xc0, 0x26, 0x93, 0x0c, 0x3e,
-            0x60, 0x39, 0xa3, 0x3c, 0xe4, 0x59, 0x64, 0xff, 0x21, 0x67, 0xf6, 0xec, 0xed, 0xd4,
-            0x19, 0xdb, 0x06, 0xc1,
-        ],
-    );
+/// SHA-512 test vectors.
+#[test]
+fn sha512() {
+    test_vectors(&Sha512::default(), "SHA-512");
+}
 
-    test(
-        &hash,
-        b"abcdbcdecdefdefgefghfghighijhijkijkljklmklmnlmnomnopnop",
-        &[
-            0xaa, 0x35, 0x3e, 0x00, 0x9e, 0xdb, 0xae, 0xbf, 0xc6, 0xe4, 0x94, 0xc8, 0xd8, 0x47,
-            0x69, 0x68, 0x96, 0xcb, 0x8b, 0x39, 0x8e, 0x01, 0x73, 0xa4, 0xb5, 0xc1, 0xb6, 0x36,
-            0x29, 0x2d, 0x87, 0xc7,
-        ],
-    );
+/// SHA-384 test vectors.
+#[test]
+fn sha384() {
+    test_vectors(&Sha384::default(), "SHA-384");
 }
 
-/// SHA-224 test vectors.
+/// SHA-512/224 test vectors.
 #[test]
-fn sha224() {
-    let hash = Sha224::default();
-
-    test(
-        &hash,
-        b"abc",
-        &[
-            0x23, 0x09, 0x7d, 0x22, 0x34, 0x05, 0xd8, 0x22, 0x86, 0x42, 0xa4, 0x77, 0xbd, 0xa2,
-            0x55, 0xb3, 0x2a, 0xad, 0xbc, 0xe4, 0xbd, 0xa0, 0xb3, 0xf7, 0xe3, 0x6c, 0x9d, 0xa7,
-        ],
+fn sha512_224() {
+    test_vectors(&Sha512_224::default(), "SHA-512/224");
+}
+
+/// SHA-512/256 test vectors.
+#[test]
+fn sha512_256() {
+    test_vectors(&Sha512_256::default(), "SHA-512/256");
+}
+
+/// The initial states of SHA-512/224 and SHA-512/256 are the output of the
+/// IV generation function.
+#[test]
+fn sha512_t_iv() {
+    assert_eq!(sha2::sha512_t_iv(224), consts::H0_512_224);
+    assert_eq!(sha2::sha512_t_iv(256), consts::H0_512_256);
+}
+
+/// SHA-512/384 would clash with SHA-384.
+#[test]
+#[should_panic = "no SHA-512/384"]
+fn sha512_t_iv_384() {
+    sha2::sha512_t_iv(384);
+}
+
+/// Recompute the SHA-1 and SHA-2 constants from the roots of primes and check
+/// them against the tables.
+#[test]
+fn constants() {
+    let primes = primes(80);
+
+    for (i, &p) in primes[..8].iter().enumerate() {
+        assert_eq!(consts::H0_256[i], low32(root(p, 2, 32)), "H0_256[{i}]");
+    }
+    for (i, &p) in primes[8..16].iter().enumerate() {
+        assert_eq!(consts::H0_224[i], low32(root(p, 2, 64)), "H0_224[{i}]");
+    }
+    for (i, &p) in primes[..64].iter().enumerate() {
+        assert_eq!(consts::KT_256[i], low32(root(p, 3, 32)), "KT_256[{i}]");
+    }
+    for (i, x) in [2, 3, 5, 10].into_iter().enumerate() {
+        assert_eq!(u128::from(consts::KT_1[i]), root(x, 2, 30), "KT_1[{i}]");
+    }
+
+    for (i, &p) in primes[..8].iter().enumerate() {
+        assert_eq!(consts::H0_512[i], low64(root(p, 2, 64)), "H0_512[{i}]");
+    }
+    for (i, &p) in primes[8..16].iter().enumerate() {
+        assert_eq!(consts::H0_384[i], low64(root(p, 2, 64)), "H0_384[{i}]");
+    }
+    // Scaling a cube root by 2^64 overflows the remainder in `root`, so only
+    // the upper 32 bits are recomputed. The first 64 of them must also agree
+    // with SHA-256.
+    for (i, &p) in primes.iter().enumerate() {
+        let high = (consts::KT_512[i] >> 32) as u32;
+        assert_eq!(high, low32(root(p, 3, 32)), "KT_512[{i}]");
+    }
+    for (i, &k) in consts::KT_256.iter().enumerate() {
+        assert_eq!((consts::KT_512[i] >> 32) as u32, k, "KT_512[{i}]");
+    }
+}
+
+/// The first `n` primes, by trial division.
+fn primes(n: usize) -> Vec<u128> {
+    (2..)
+        .filter(|&p| (2..p).take_while(|d| d * d <= p).all(|d| p % d != 0))
+        .take(n)
+        .collect()
+}
+
+/// Calculate $\lfloor 2^{bits} \sqrt[k]{x} \rfloor$ for $k = 2, 3$ using only
+/// integer math.
+///
+/// This is the schoolbook digit-by-digit method in base 2. Starting from the
+/// integer root $r$ of $x$, each step appends $k$ zero bits to $x$ and one bit
+/// to $r$: the new bit is 1 if $(2r + 1)^k$ still doesn't exceed the extended
+/// $x$. Only the remainder $x - r^k$ is tracked, which keeps the numbers small.
+#[docext::docext]
+fn root(x: u128, k: u32, bits: u32) -> u128 {
+    let mut r = (0..).take_while(|r: &u128| r.pow(k) <= x).last().unwrap();
+    let mut rem = x - r.pow(k);
+    for _ in 0..bits {
+        rem <<= k;
+        // (2r + 1)^k - (2r)^k.
+        let step = match k {
+            2 => 4 * r + 1,
+            3 => 12 * r * r + 6 * r + 1,
+            _ => unimplemented!(),
+        };
+        r <<= 1;
+        if rem >= step {
+            rem -= step;
+            r |= 1;
+        }
+    }
+    r
+}
+
+/// The low 32 bits, i.e. the part of the root after discarding the integer
+/// and any preceding fractional bits.
+fn low32(x: u128) -> u32 {
+    x as u32
+}
+
+/// The low 64 bits, like [`low32`].
+fn low64(x: u128) -> u64 {
+    x as u64
+}
+
+/// Words are encoded big-endian, in order. Each check uses a pattern where
+/// swapping the bytes of a word, or swapping two words, changes the result.
+#[test]
+fn state_bytes() {
+    // The bytes of a single word.
+    let mut word = [0; 8];
+    word[0] = 0x01020304;
+    let bytes: [u8; 32] = sha2::to_be_bytes(word);
+    assert_eq!(bytes[..5], [0x01, 0x02, 0x03, 0x04, 0x00]);
+    assert!(bytes[4..].iter().all(|&b| b == 0));
+
+    // The order of the words.
+    let mut last = [0; 8];
+    last[7] = 0xff;
+    let bytes: [u8; 32] = sha2::to_be_bytes(last);
+    assert_eq!(bytes[31], 0xff);
+    assert!(bytes[..31].iter().all(|&b| b == 0));
+
+    // Both at once, for a full state.
+    let counting: Sha2State = [
+        0x00010203, 0x04050607, 0x08090a0b, 0x0c0d0e0f, 0x10111213, 0x14151617, 0x18191a1b,
+        0x1c1d1e1f,
+    ];
+    let bytes: [u8; 32] = sha2::to_be_bytes(counting);
+    assert_eq!(bytes, std::array::from_fn(|i| i as u8));
+    assert_eq!(sha2::from_be_bytes::<32, 8>(&bytes), counting);
+
+    let sha1: Sha1State = [0x67452301, 0xefcdab89, 0x98badcfe, 0x10325476, 0xc3d2e1f0];
+    let bytes: [u8; 20] = sha2::to_be_bytes(sha1);
+    assert_eq!(bytes[..8], [0x67, 0x45, 0x23, 0x01, 0xef, 0xcd, 0xab, 0x89]);
+    assert_eq!(bytes[16..], [0xc3, 0xd2, 0xe1, 0xf0]);
+    assert_eq!(sha2::from_be_bytes::<20, 5>(&bytes), sha1);
+}
+
+/// A preimage block is read as 16 big-endian words, in order.
+#[test]
+fn block_words() {
+    let block: [u8; 64] = std::array::from_fn(|i| i as u8);
+    let words: [u32; 16] = sha2::from_be_bytes(&block);
+    assert_eq!(words[0], 0x00010203);
+    assert_eq!(words[1], 0x04050607);
+    assert_eq!(words[15], 0x3c3d3e3f);
+    assert_eq!(sha2::to_be_bytes::<16, 64>(words), block);
+
+    let mut block = [0; 64];
+    block[63] = 0x80;
+    let words: [u32; 16] = sha2::from_be_bytes(&block);
+    assert_eq!(words[15], 0x80);
+    assert!(words[..15].iter().all(|&w| w == 0));
+}
+
+#[test]
+#[should_panic = "wrong number of bytes"]
+fn state_bytes_wrong_size() {
+    let _: [u8; 28] = sha2::to_be_bytes([0; 8]);
+}
+
+/// Length of the long preimage, past $2^{32}$ bits.
+const LONG_LEN: u64 = 600 << 20;
+
+/// The bit length of a preimage of $2^{32}$ bits or more has a nonzero upper
+/// word.
+#[test]
+fn length_padding_high_word() {
+    let blocks = LengthPadding::default()
+        .pad_tail(&[0xab; 3], LONG_LEN)
+        .collect_vec();
+    assert_eq!(blocks.len(), 1);
+    assert_eq!(blocks[0][..4], [0xab, 0xab, 0xab, 0x80]);
+    assert!(blocks[0][4..56].iter().all(|&b| b == 0));
+    assert_eq!(blocks[0][56..], [0, 0, 0, 1, 0x2c, 0, 0, 0]);
+
+    let blocks = LengthPadding::default()
+        .pad_tail(&[0; 60], u64::MAX >> 3)
+        .collect_vec();
+    assert_eq!(blocks.len(), 2);
+    assert_eq!(
+        blocks[1][56..],
+        [0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xf8]
     );
+}
 
-    test(
-        &hash,
-        b"abcdbcdecdefdefgefghfghighijhijkijkljklmklmnlmnomnopnopq",
-        &[
-            0x75, 0x38, 0x8b, 0x16, 0x51, 0x27, 0x76, 0xcc, 0x5d, 0xba, 0x5d, 0xa1, 0xfd, 0x89,
-            0x01, 0x50, 0xb0, 0xc6, 0x45, 0x5c, 0xb4, 0xf5, 0x8b, 0x19, 0x52, 0x52, 0x25, 0x25,
-        ],
+/// SHA-512 padding splits into 128-byte blocks and ends with a 128-bit
+/// length, which needs a second block once the tail is longer than 111 bytes.
+/// The largest preimage length still fits.
+#[test]
+fn length_padding_128() {
+    let blocks = LengthPadding128::default()
+        .pad_tail(&[0xab; 3], LONG_LEN)
+        .collect_vec();
+    assert_eq!(blocks.len(), 1);
+    assert_eq!(blocks[0][..4], [0xab, 0xab, 0xab, 0x80]);
+    assert!(blocks[0][4..120].iter().all(|&b| b == 0));
+    assert_eq!(blocks[0][120..], [0, 0, 0, 1, 0x2c, 0, 0, 0]);
+
+    let blocks = LengthPadding128::default()
+        .pad_tail(&[0; 111], 111)
+        .collect_vec();
+    assert_eq!(blocks.len(), 1);
+    assert_eq!(blocks[0][111], 0x80);
+    assert_eq!(blocks[0][126..], [0x03, 0x78]);
+
+    let blocks = LengthPadding128::default()
+        .pad_tail(&[0; 112], u64::MAX)
+        .collect_vec();
+    assert_eq!(blocks.len(), 2);
+    assert_eq!(blocks[0][112], 0x80);
+    assert!(blocks[0][113..].iter().all(|&b| b == 0));
+    assert!(blocks[1][..119].iter().all(|&b| b == 0));
+    assert_eq!(
+        blocks[1][119..],
+        [0x07, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xf8]
     );
+}
+
+/// The bit length of a preimage of $2^{61}$ bytes doesn't fit into 64 bits.
+#[test]
+#[should_panic = "too long for a 64-bit length"]
+fn length_padding_too_long() {
+    LengthPadding::default()
+        .pad_tail(&[], 1 << 61)
+        .for_each(drop);
+}
 
-    test(
-        &hash,
-        b"abcdbcdecdefdefgefghfghighijhijkijkljklmklmnlmnomnopnop",
-        &[
-            0x7a, 0x02, 0x7d, 0x88, 0xe3, 0x94, 0xd2, 0x89, 0xed, 0x7a, 0x10, 0xa9, 0x18, 0xb9,
-            0x3d, 0x1f, 0x21, 0x0b, 0x47, 0x41, 0xd4, 0x45, 0x34, 0xce, 0x64, 0x27, 0x5a, 0xb9,
-        ],
+/// Hash 600 MiB of zeros incrementally, so that memory stays bounded, to pin
+/// the encoding of a bit length above $2^{32}$. The digest was computed with
+/// both `sha256sum` and Python's `hashlib`.
+#[test]
+fn sha256_long_message() {
+    let hash = Sha256::default();
+    let zeros = vec![0; 1 << 20];
+    let mut state = hash.init();
+    for _ in 0..LONG_LEN >> 20 {
+        hash.update(&mut state, &zeros);
+    }
+    assert_eq!(
+        hex::encode(&hash.finalize(state)),
+        "987523e7780392e283b404990c4e84e580bc75c451138b0c86c4f81c296eeebe"
     );
 }
@@ -1,6 +1,17 @@
 use {
     super::test,
-    crate::{Sha3_224, Sha3_256, Sha3_384, Sha3_512},
+    crate::{
+        hex,
+        sha3::{self, KeccakSponge, KeccakState, Lane, State},
+        BitHash,
+        Hash,
+        Keccak256,
+        Keccak512,
+        Sha3_224,
+        Sha3_256,
+        Sha3_384,
+        Sha3_512,
+    },
 };
 
 /// SHA3-224 test vectors.
@@ -240,3 +251,320 @@ fn sha3_512() {
         ],
     );
 }
+
+/// Keccak-256 and Keccak-512 test vectors, as used by Ethereum. They differ
+/// from SHA-3 only in the domain separator.
+#[test]
+fn keccak() {
+    let keccak256 = Keccak256::default();
+    let keccak512 = Keccak512::default();
+    let cases: [(&[u8], &str, &str); 2] = [
+        (
+            b"",
+            "c5d2460186f7233c927e7db2dcc703c0e500b653ca82273b7bfad8045d85a470",
+            "0eab42de4c3ceb9235fc91acffe746b29c29a8c366b7c60e4e67c466f36a4304\
+             c00fa9caf9d87976ba469bcbe06713b435f091ef2769fb160cdab33d3670680e",
+        ),
+        (
+            b"abc",
+            "4e03657aea45a94fc7d47ba826c8d667c0d1e6e33a64a036ec44f58fa12d6c45",
+            "18587dc2ea106b9a1563e32b3312421ca164c7f1f07bc922a9c83d77cea3a1e5\
+             d0c69910739025372dc14ac9642629379540c17e2a65b19d77aa511a9d00bb96",
+        ),
+    ];
+    for (preimage, digest256, digest512) in cases {
+        test(&keccak256, preimage, &hex::decode(digest256).unwrap());
+        test(&keccak512, preimage, &hex::decode(digest512).unwrap());
+        assert_ne!(keccak256.hash(preimage), Sha3_256::default().hash(preimage));
+        assert_ne!(keccak512.hash(preimage), Sha3_512::default().hash(preimage));
+    }
+}
+
+/// The domain separator is written right after the input, and the final bit
+/// of the padding only needs a new block if the separator fills the last one.
+#[test]
+fn pad10star1_domains() {
+    let pad = |len: usize, domain| {
+        sha3::pad10star1_with_domain::<8>(&[0; 16], 8 * len, domain).collect::<Vec<_>>()
+    };
+    assert_eq!(
+        pad(0, sha3::KECCAK_DOMAIN),
+        [[0x01, 0, 0, 0, 0, 0, 0, 0x80]]
+    );
+    assert_eq!(pad(0, sha3::SHA3_DOMAIN), [[0x06, 0, 0, 0, 0, 0, 0, 0x80]]);
+    assert_eq!(pad(7, sha3::KECCAK_DOMAIN), [[0, 0, 0, 0, 0, 0, 0, 0x81]]);
+    assert_eq!(pad(7, sha3::SHA3_DOMAIN), [[0, 0, 0, 0, 0, 0, 0, 0x86]]);
+    // SHAKE's separator "1111" spills into another block after 63 bits.
+    assert_eq!(
+        sha3::pad10star1_with_domain::<8>(&[0; 8], 63, 0x1f).collect::<Vec<_>>(),
+        [[0, 0, 0, 0, 0, 0, 0, 0x80], [0x0f, 0, 0, 0, 0, 0, 0, 0x80]]
+    );
+}
+
+/// SHA3-256 test vectors with messages which are not a whole number of bytes,
+/// from the NIST examples for FIPS 202.
+#[test]
+fn sha3_256_bits() {
+    let hash = Sha3_256::default();
+
+    // The 5-bit message 11001.
+    assert_eq!(
+        hash.hash_bits(&[0x13], 5),
+        [
+            0x7b, 0x00, 0x47, 0xcf, 0x5a, 0x45, 0x68, 0x82, 0x36, 0x3c, 0xbf, 0x0f, 0xb0, 0x53,
+            0x22, 0xcf, 0x65, 0xf4, 0xb7, 0x05, 0x9a, 0x46, 0x36, 0x5e, 0x83, 0x01, 0x32, 0xe3,
+            0xb5, 0xd9, 0x57, 0xaf,
+        ]
+    );
+
+    // The 30-bit message 110010100001101011011110100110.
+    assert_eq!(
+        hash.hash_bits(&[0x53, 0x58, 0x7b, 0x19], 30),
+        [
+            0xc8, 0x24, 0x2f, 0xef, 0x40, 0x9e, 0x5a, 0xe9, 0xd1, 0xf1, 0xc8, 0x57, 0xae, 0x4d,
+            0xc6, 0x24, 0xb9, 0x2b, 0x19, 0x80, 0x9f, 0x62, 0xaa, 0x8c, 0x07, 0x41, 0x1c, 0x54,
+            0xa0, 0x78, 0xb1, 0xd0,
+        ]
+    );
+
+    // Bits past the bit length are ignored.
+    assert_eq!(hash.hash_bits(&[0xf3], 5), hash.hash_bits(&[0x13], 5));
+}
+
+/// Hashing whole bytes with [`BitHash`] should give the same digest as
+/// [`Hash`], including when the padding spills into another block.
+#[test]
+fn sha3_bits_byte_aligned() {
+    let preimage: Vec<u8> = (0..300).map(|i| (i * 31 % 251) as u8).collect();
+    for len in [0, 1, 71, 72, 73, 103, 135, 136, 137, 143, 144, 145, 300] {
+        let preimage = &preimage[..len];
+        assert_eq!(
+            Sha3_224::default().hash_bits(preimage, 8 * len),
+            Sha3_224::default().hash(preimage)
+        );
+        assert_eq!(
+            Sha3_256::default().hash_bits(preimage, 8 * len),
+            Sha3_256::default().hash(preimage)
+        );
+        assert_eq!(
+            Sha3_384::default().hash_bits(preimage, 8 * len),
+            Sha3_384::default().hash(preimage)
+        );
+        assert_eq!(
+            Sha3_512::default().hash_bits(preimage, 8 * len),
+            Sha3_512::default().hash(preimage)
+        );
+    }
+}
+
+/// The bytes of a block are XORed into the words of the state in row-major
+/// order, little-endian, stopping at the rate.
+#[test]
+fn absorb_block() {
+    let block: [u8; 144] = std::array::from_fn(|i| i as u8);
+    let mut state = State::default();
+    sha3::absorb_block(&mut state, &block);
+    // Bytes within a word.
+    assert_eq!(state[0][0], 0x0706050403020100);
+    // The next word in the same row.
+    assert_eq!(state[0][1], 0x0f0e0d0c0b0a0908);
+    // The first word of the next row.
+    assert_eq!(state[1][0], 0x2f2e2d2c2b2a2928);
+    // The last word of the rate, and the first word of the capacity.
+    assert_eq!(state[3][2], 0x8f8e8d8c8b8a8988);
+    assert_eq!(state[3][3], 0);
+    assert!(state[4].iter().all(|&w| w == 0));
+
+    // The block is XORed into the state.
+    sha3::absorb_block(&mut state, &block);
+    assert_eq!(state, State::default());
+
+    // A rate which isn't a whole number of words.
+    let mut state = State::default();
+    sha3::absorb_block(&mut state, &[0x01, 0x02, 0x03]);
+    assert_eq!(state[0][0], 0x030201);
+    assert_eq!(state[0][1], 0);
+}
+
+/// Squeezing uses the byte order of absorbing, and never outputs the capacity.
+#[test]
+fn squeeze_bytes() {
+    let mut state = State::default();
+    state[0][0] = 0x0706050403020100;
+    state[0][1] = 0x8000000000000001;
+    state[1][0] = 0xff;
+    state[4][4] = u64::MAX;
+
+    let mut output = [0; 48];
+    sha3::squeeze_bytes::<72>(&state, &mut output);
+    assert_eq!(output[..8], [0, 1, 2, 3, 4, 5, 6, 7]);
+    assert_eq!(output[8..16], [1, 0, 0, 0, 0, 0, 0, 0x80]);
+    assert_eq!(output[40..], [0xff, 0, 0, 0, 0, 0, 0, 0]);
+
+    let mut output = [0; 72];
+    sha3::squeeze_bytes::<72>(&state, &mut output);
+    assert!(output[48..].iter().all(|&b| b == 0));
+
+    // Squeezing is the inverse of absorbing into an empty state.
+    let block: [u8; 136] = std::array::from_fn(|i| (i * 7) as u8);
+    let mut state = State::default();
+    sha3::absorb_block(&mut state, &block);
+    let mut output = [0; 136];
+    sha3::squeeze_bytes::<136>(&state, &mut output);
+    assert_eq!(output, block);
+}
+
+#[test]
+#[should_panic = "output longer than the rate"]
+fn squeeze_past_rate() {
+    sha3::squeeze_bytes::<136>(&State::default(), &mut [0; 137]);
+}
+
+/// The round constants and rotation offsets generated from their formulas
+/// match the tables in the specification.
+#[test]
+fn keccak_tables() {
+    assert_eq!(sha3::RC, PUBLISHED_RC);
+    assert_eq!(sha3::rctable(), PUBLISHED_RC);
+    assert_eq!(sha3::RHO_OFFSETS, PUBLISHED_RHO_OFFSETS);
+    assert_eq!(<u16 as Lane>::ROUNDS, 20);
+    assert_eq!(<u32 as Lane>::ROUNDS, 22);
+    assert_eq!(<u64 as Lane>::ROUNDS, sha3::NUM_ROUNDS);
+}
+
+/// Keccak-f[400] applied to the zero state, once and twice. The vectors were
+/// computed with an independent bit-level implementation of the
+/// specification, which gives the published Keccak-f[1600] output on the zero
+/// state.
+#[test]
+fn keccak_p_400() {
+    let mut state = KeccakState::<u16>::default();
+    sha3::keccak_p_400(&mut state);
+    assert_eq!(
+        state,
+        [
+            [0x09f5, 0x40ac, 0x0fa9, 0x14f5, 0xe89f],
+            [0xeca0, 0x5bd1, 0x7870, 0xeff0, 0xbf8f],
+            [0x0337, 0x6052, 0xdc75, 0x0ec9, 0xe776],
+            [0x5246, 0x59a1, 0x5d81, 0x6d95, 0x6e14],
+            [0x633e, 0x58ee, 0x71ff, 0x714c, 0xb38e],
+        ]
+    );
+    sha3::keccak_p_400(&mut state);
+    assert_eq!(
+        state,
+        [
+            [0xe537, 0xd5d6, 0xdbe7, 0xaaf3, 0x9bc7],
+            [0xca7d, 0x86b2, 0xfdec, 0x692c, 0x4e5b],
+            [0x67b1, 0x15ad, 0xa7f7, 0xa66f, 0x67ff],
+            [0x3f8a, 0x2f99, 0xe2c2, 0x656b, 0x5f31],
+            [0x5ba6, 0xca29, 0xc224, 0xb85c, 0x097c],
+        ]
+    );
+}
+
+/// Keccak-f[800] applied to the zero state, once and twice, computed like the
+/// vectors of [`keccak_p_400`].
+#[test]
+fn keccak_p_800() {
+    let mut state = KeccakState::<u32>::default();
+    sha3::keccak_p_800(&mut state);
+    assert_eq!(
+        state,
+        [
+            [0xe531d45d, 0xf404c6fb, 0x23a0bf99, 0xf1f8452f, 0x51ffd042],
+            [0xe539f578, 0xf00b80a7, 0xaf973664, 0xbf5af34c, 0x227a2424],
+            [0x88172715, 0x9f685884, 0xb15cd054, 0x1bf4fc0e, 0x6166fa91],
+            [0x1a9e599a, 0xa3970a1f, 0xab659687, 0xafab8d68, 0xe74b1015],
+            [0x34001a98, 0x4119eff3, 0x930a0e76, 0x87b28070, 0x11efe996],
+        ]
+    );
+    sha3::keccak_p_800(&mut state);
+    assert_eq!(
+        state,
+        [
+            [0x75bf2d0d, 0x9b610e89, 0xc826af40, 0x64cd84ab, 0xf905bdd6],
+            [0xbc832835, 0x5f8001b9, 0x15662cce, 0x8e38c95e, 0x701fe543],
+            [0x1b544380, 0x89acdeff, 0x51edb5de, 0x0e9702d9, 0x6c19aa16],
+            [0xa2913eee, 0x60754e9a, 0x9819063c, 0xf4709254, 0xd09f9084],
+            [0x772da259, 0x1db35df7, 0x5aa60162, 0x358825d5, 0xb3783bab],
+        ]
+    );
+}
+
+/// The generic sponge is SHA-3 with 64-bit lanes, and squeezes outputs longer
+/// than the rate on the smaller permutations.
+#[test]
+fn keccak_sponge() {
+    let sponge = KeccakSponge::<u64, 136, 32>::default();
+    for len in [0, 1, 135, 136, 137, 300] {
+        let preimage = vec![0xa5; len];
+        assert_eq!(sponge.hash(&preimage), Sha3_256::default().hash(&preimage));
+    }
+
+    // The first block of output is the rate of the absorbed state.
+    let long = KeccakSponge::<u16, 18, 40>::default().hash(b"abc");
+    let short = KeccakSponge::<u16, 18, 18>::default().hash(b"abc");
+    assert_eq!(long[..18], short);
+    assert_ne!(long[18..36], short);
+    assert_ne!(
+        KeccakSponge::<u32, 40, 16>::default().hash(b"abc"),
+        KeccakSponge::<u32, 40, 16>::default().hash(b"abd")
+    );
+
+    // An empty preimage is padded into a single block, which is then permuted.
+    let mut state = KeccakState::<u16>::default();
+    state[0][0] = 0x0006;
+    state[1][3] = 0x8000;
+    sha3::keccak_p_400(&mut state);
+    let expected: Vec<u8> = state
+        .iter()
+        .flatten()
+        .flat_map(|l| l.to_le_bytes())
+        .collect();
+    assert_eq!(
+        KeccakSponge::<u16, 18, 18>::default().hash(b""),
+        expected[..18]
+    );
+}
+
+#[test]
+#[should_panic = "rate leaves no capacity"]
+fn keccak_sponge_no_capacity() {
+    KeccakSponge::<u16, 50, 16>::default().hash(b"");
+}
+
+const PUBLISHED_RC: [u64; sha3::NUM_ROUNDS] = [
+    0x0000000000000001,
+    0x0000000000008082,
+    0x800000000000808a,
+    0x8000000080008000,
+    0x000000000000808b,
+    0x0000000080000001,
+    0x8000000080008081,
+    0x8000000000008009,
+    0x000000000000008a,
+    0x0000000000000088,
+    0x0000000080008009,
+    0x000000008000000a,
+    0x000000008000808b,
+    0x800000000000008b,
+    0x8000000000008089,
+    0x8000000000008003,
+    0x8000000000008002,
+    0x8000000000000080,
+    0x000000000000800a,
+    0x800000008000000a,
+    0x8000000080008081,
+    0x8000000000008080,
+    0x0000000080000001,
+    0x8000000080008008,
+];
+
+const PUBLISHED_RHO_OFFSETS: [[u32; 5]; 5] = [
+    [0, 1, 190, 28, 91],
+    [36, 300, 6, 55, 276],
+    [3, 10, 171, 153, 231],
+    [105, 45, 15, 21, 136],
+    [210, 66, 253, 120, 78],
+];
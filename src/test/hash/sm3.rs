@@ -0,0 +1,57 @@
+use {
+    super::test,
+    crate::{Hmac, Mac, Sm3},
+};
+
+/// SM3 test vectors from the specification.
+#[test]
+fn sm3() {
+    let hash = Sm3::default();
+
+    test(
+        &hash,
+        b"abc",
+        &[
+            0x66, 0xc7, 0xf0, 0xf4, 0x62, 0xee, 0xed, 0xd9, 0xd1, 0xf2, 0xd4, 0x6b, 0xdc, 0x10,
+            0xe4, 0xe2, 0x41, 0x67, 0xc4, 0x87, 0x5c, 0xf2, 0xf7, 0xa2, 0x29, 0x7d, 0xa0, 0x2b,
+            0x8f, 0x4b, 0xa8, 0xe0,
+        ],
+    );
+
+    test(
+        &hash,
+        b"abcdabcdabcdabcdabcdabcdabcdabcdabcdabcdabcdabcdabcdabcdabcdabcd",
+        &[
+            0xde, 0xbe, 0x9f, 0xf9, 0x22, 0x75, 0xb8, 0xa1, 0x38, 0x60, 0x48, 0x89, 0xc1, 0x8e,
+            0x5a, 0x4d, 0x6f, 0xdb, 0x70, 0xe5, 0x38, 0x7e, 0x57, 0x65, 0x29, 0x3d, 0xcb, 0xa3,
+            0x9c, 0x0c, 0x57, 0x32,
+        ],
+    );
+}
+
+/// HMAC-SM3 test vectors, for a short key and for a key longer than the block
+/// size.
+#[test]
+fn hmac_sm3() {
+    let mut hmac = Hmac::new(Sm3::default());
+    let tag = hmac.mac(b"The quick brown fox jumps over the lazy dog", b"key");
+    assert_eq!(
+        tag,
+        [
+            0xbd, 0x4a, 0x34, 0x07, 0x78, 0x88, 0x16, 0x2b, 0x21, 0x06, 0x45, 0xb8, 0xeb, 0xf7,
+            0x4b, 0x9a, 0xf3, 0x57, 0x30, 0x37, 0x89, 0x35, 0x7a, 0x27, 0xc7, 0xfc, 0x45, 0x72,
+            0x44, 0xeb, 0xd3, 0x98,
+        ],
+    );
+
+    let key = (0..100).collect::<Vec<u8>>();
+    let tag = hmac.mac(b"Hi There", &key);
+    assert_eq!(
+        tag,
+        [
+            0x61, 0xa9, 0x9a, 0x34, 0x73, 0xf7, 0x0e, 0xa6, 0x6e, 0x70, 0x34, 0xf9, 0xa3, 0x38,
+            0x81, 0x17, 0xbd, 0xc9, 0x1f, 0x0c, 0xe2, 0x39, 0xcc, 0x6e, 0x77, 0x6c, 0xd0, 0xe6,
+            0x0b, 0xe7, 0x4c, 0x7e,
+        ],
+    );
+}
@@ -0,0 +1,73 @@
+use {
+    crate::hex::{self, HexError},
+    rand::Rng,
+};
+
+/// The base 16 test vectors from RFC 4648.
+#[test]
+fn hex_rfc4648() {
+    for (input, output) in [
+        ("", ""),
+        ("f", "66"),
+        ("fo", "666F"),
+        ("foo", "666F6F"),
+        ("foob", "666F6F62"),
+        ("fooba", "666F6F6261"),
+        ("foobar", "666F6F626172"),
+    ] {
+        assert_eq!(hex::encode(input.as_bytes()), output.to_lowercase());
+        assert_eq!(hex::decode(output).unwrap(), input.as_bytes());
+        assert_eq!(
+            hex::decode(&output.to_lowercase()).unwrap(),
+            input.as_bytes()
+        );
+    }
+}
+
+/// Every byte value survives a round trip, and uppercase, lowercase and mixed
+/// case digits decode to the same bytes.
+#[test]
+fn hex_round_trip() {
+    let all: Vec<u8> = (0..=255).collect();
+    let encoded = hex::encode(&all);
+    assert_eq!(&encoded[..8], "00010203");
+    assert_eq!(&encoded[encoded.len() - 4..], "feff");
+    assert_eq!(hex::decode(&encoded).unwrap(), all);
+    assert_eq!(hex::decode(&encoded.to_uppercase()).unwrap(), all);
+    assert_eq!(hex::decode("aBcD").unwrap(), [0xab, 0xcd]);
+
+    let mut rng = rand::thread_rng();
+    for len in 0..64 {
+        let data: Vec<u8> = (0..len).map(|_| rng.gen()).collect();
+        let encoded = hex::encode(&data);
+        assert_eq!(encoded.len(), 2 * len);
+        assert_eq!(hex::decode(&encoded).unwrap(), data);
+    }
+}
+
+/// Invalid input is rejected with the offset of the first invalid character.
+#[test]
+fn hex_invalid() {
+    assert_eq!(hex::decode("abc"), Err(HexError::OddLength { len: 3 }));
+    assert_eq!(
+        hex::decode("0g"),
+        Err(HexError::InvalidCharacter { index: 1 })
+    );
+    assert_eq!(
+        hex::decode("00112x3y"),
+        Err(HexError::InvalidCharacter { index: 5 })
+    );
+    // The characters just outside of each valid range.
+    for c in ['/', ':', '@', 'G', '`', 'g', ' '] {
+        assert_eq!(
+            hex::decode(&format!("00{c}0")),
+            Err(HexError::InvalidCharacter { index: 2 }),
+            "{c:?}"
+        );
+    }
+    // Offsets are in bytes, and bytes of multibyte characters are invalid.
+    assert_eq!(
+        hex::decode("0é0"),
+        Err(HexError::InvalidCharacter { index: 1 })
+    );
+}
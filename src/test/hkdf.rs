@@ -0,0 +1,77 @@
+use crate::{
+    test::vectors::{load_vectors, Record, Vector, VectorError},
+    Hkdf,
+    HkdfError,
+    Prk,
+    Sha256,
+};
+
+/// A case from `tests/vectors/hkdf_sha256.txt`.
+struct HkdfCase {
+    ikm: Vec<u8>,
+    salt: Vec<u8>,
+    info: Vec<u8>,
+    prk: [u8; 32],
+    okm: Vec<u8>,
+}
+
+impl Vector for HkdfCase {
+    fn parse(record: &mut Record) -> Result<Self, VectorError> {
+        Ok(Self {
+            ikm: record.hex("ikm")?,
+            salt: record.hex("salt")?,
+            info: record.hex("info")?,
+            prk: record.array("prk")?,
+            okm: record.hex("okm")?,
+        })
+    }
+}
+
+/// Assert that [HKDF](Hkdf) with SHA-256 matches test cases 1 to 3 of RFC
+/// 5869, both step by step and through [`derive`](Hkdf::derive).
+#[test]
+fn hkdf_rfc_5869() {
+    let mut hkdf = Hkdf::new(Sha256::default());
+    for case in load_vectors::<HkdfCase>("hkdf_sha256.txt") {
+        let prk = hkdf.extract(&case.salt, &case.ikm);
+        assert_eq!(prk.as_bytes(), &case.prk);
+        assert_eq!(
+            hkdf.expand(&prk, &case.info, case.okm.len()).unwrap(),
+            case.okm
+        );
+        assert_eq!(
+            hkdf.expand(&Prk::from_bytes(case.prk), &case.info, case.okm.len())
+                .unwrap(),
+            case.okm
+        );
+        assert_eq!(
+            hkdf.derive(&case.salt, &case.ikm, &case.info, case.okm.len())
+                .unwrap(),
+            case.okm
+        );
+    }
+}
+
+/// Assert that [HKDF](Hkdf) produces up to 255 digests of output, and fails
+/// beyond that.
+#[test]
+fn hkdf_output_limit() {
+    let mut hkdf = Hkdf::new(Sha256::default());
+    let prk = hkdf.extract(b"salt", b"ikm");
+    assert_eq!(Hkdf::<Sha256>::MAX_OUTPUT, 255 * 32);
+
+    let longest = hkdf.expand(&prk, b"info", 255 * 32).unwrap();
+    assert_eq!(longest.len(), 255 * 32);
+    // Shorter outputs are prefixes of longer ones.
+    assert_eq!(hkdf.expand(&prk, b"info", 33).unwrap(), longest[..33]);
+    assert_eq!(hkdf.expand(&prk, b"info", 0).unwrap(), []);
+
+    assert_eq!(
+        hkdf.expand(&prk, b"info", 255 * 32 + 1),
+        Err(HkdfError::OutputTooLong {
+            len: 255 * 32 + 1,
+            max: 255 * 32
+        })
+    );
+    assert!(hkdf.derive(b"salt", b"ikm", b"info", usize::MAX).is_err());
+}
@@ -1,4 +1,4 @@
-use crate::{Hmac, Mac, Sha1, Sha256};
+use crate::{hex, Hmac, HmacKey, IncrementalHash, Mac, Sha1, Sha256, Sha3_256, Sha512, Sm3};
 
 #[test]
 fn hmac_sha1() {
@@ -26,3 +26,130 @@ fn hmac_sha256() {
         ],
     );
 }
+
+/// The HMAC-SHA256 test cases of RFC 4231, except the truncated one.
+const RFC_4231: [(&[u8], &[u8], &str); 6] = [
+    (
+        &[0x0b; 20],
+        b"Hi There",
+        "b0344c61d8db38535ca8afceaf0bf12b881dc200c9833da726e9376c2e32cff7",
+    ),
+    (
+        b"Jefe",
+        b"what do ya want for nothing?",
+        "5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843",
+    ),
+    (
+        &[0xaa; 20],
+        &[0xdd; 50],
+        "773ea91e36800e46854db8ebd09181a72959098b3ef8c122d9635514ced565fe",
+    ),
+    (
+        &[
+            0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07, 0x08, 0x09, 0x0a, 0x0b, 0x0c, 0x0d, 0x0e,
+            0x0f, 0x10, 0x11, 0x12, 0x13, 0x14, 0x15, 0x16, 0x17, 0x18, 0x19,
+        ],
+        &[0xcd; 50],
+        "82558a389a443c0ea4cc819899f2083a85f0faa3e578f8077a2e3ff46729665b",
+    ),
+    (
+        &[0xaa; 131],
+        b"Test Using Larger Than Block-Size Key - Hash Key First",
+        "60e431591ee0b67f0d8a26aacbf5b77f8e0bc6213728c5140546040f0ee37f54",
+    ),
+    (
+        &[0xaa; 131],
+        b"This is a test using a larger than block-size key and a larger than block-size \
+          data. The key needs to be hashed before being used by the HMAC algorithm.",
+        "9b09ffa71b942fcb27635fbcd5b0e944bfdc63644f0713938a7f51535c3a35e2",
+    ),
+];
+
+#[test]
+fn hmac_rfc_4231() {
+    for (key, msg, tag) in RFC_4231 {
+        let tag = hex::decode(tag).unwrap();
+        assert_eq!(Hmac::new(Sha256::default()).mac(msg, key), tag.as_slice());
+        assert_eq!(
+            HmacKey::new(Sha256::default(), key).mac(msg),
+            tag.as_slice()
+        );
+    }
+}
+
+/// HMAC-SHA512 test cases of RFC 4231. The last key is longer than the
+/// 128-byte block of SHA-512, so it is hashed first.
+#[test]
+fn hmac_sha512_rfc_4231() {
+    let cases: [(&[u8], &[u8], &str); 3] = [
+        (
+            &[0x0b; 20],
+            b"Hi There",
+            "87aa7cdea5ef619d4ff0b4241a1d6cb02379f4e2ce4ec2787ad0b30545e17cde\
+             daa833b7d6b8a702038b274eaea3f4e4be9d914eeb61f1702e696c203a126854",
+        ),
+        (
+            b"Jefe",
+            b"what do ya want for nothing?",
+            "164b7a7bfcf819e2e395fbe73b56e0a387bd64222e831fd610270cd7ea250554\
+             9758bf75c05a994a6d034f65f8f0e6fdcaeab1a34d4a6b4b636e070a38bce737",
+        ),
+        (
+            &[0xaa; 131],
+            b"Test Using Larger Than Block-Size Key - Hash Key First",
+            "80b24263c7c1a3ebb71493c1dd7be8b49b46d1f41b4aeec1121b013783f8f352\
+             6b56d037e05f2598bd0fd2215d6a1e5295e64f73f63f0aec8b915a985d786598",
+        ),
+    ];
+    for (key, msg, tag) in cases {
+        let tag = hex::decode(tag).unwrap();
+        assert_eq!(Hmac::new(Sha512::default()).mac(msg, key), tag.as_slice());
+        assert_eq!(
+            HmacKey::new(Sha512::default(), key).mac(msg),
+            tag.as_slice()
+        );
+    }
+}
+
+/// A precomputed key gives the same tags as [`Hmac`] for keys shorter than,
+/// equal to and longer than the block size.
+#[test]
+fn hmac_key() {
+    hmac_key_matches(Sha1::default);
+    hmac_key_matches(Sha256::default);
+    hmac_key_matches(Sha3_256::default);
+    hmac_key_matches(Sm3::default);
+}
+
+fn hmac_key_matches<H, const BLOCK_SIZE: usize, const DIGEST_SIZE: usize>(hash: impl Fn() -> H)
+where
+    H: IncrementalHash<Block = [u8; BLOCK_SIZE], Digest = [u8; DIGEST_SIZE]>,
+{
+    let data: Vec<u8> = (0..=u8::MAX).collect();
+    for key_len in [
+        0,
+        1,
+        20,
+        BLOCK_SIZE - 1,
+        BLOCK_SIZE,
+        BLOCK_SIZE + 1,
+        2 * BLOCK_SIZE + 3,
+    ] {
+        let key: Vec<u8> = data.iter().cycle().take(key_len).map(|b| !b).collect();
+        let precomputed = HmacKey::new(hash(), &key);
+        let mut hmac = Hmac::new(hash());
+        // The same key computes many tags.
+        for msg_len in [0, 1, 55, 56, 64, BLOCK_SIZE, 200] {
+            let msg = &data[..msg_len];
+            let tag = hmac.mac(msg, &key);
+            assert_eq!(
+                precomputed.mac(msg),
+                tag,
+                "key {key_len}, message {msg_len}"
+            );
+
+            let (a, b) = msg.split_at(msg_len / 3);
+            assert_eq!(precomputed.mac_parts(&[a, &[], b]), tag);
+        }
+    }
+}
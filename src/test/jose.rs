@@ -0,0 +1,265 @@
+use crate::{
+    base64::{self, Alphabet},
+    ecc::{
+        jose::{jws_es256k_sign, jws_es256k_verify, JoseError},
+        Num,
+        PrivateKey,
+        PublicKey,
+        Secp256k1,
+    },
+};
+
+/// A private key in JWK format, exported by the `joserfc` Python library.
+const FIXTURE_JWK: &str = r#"{"crv": "secp256k1", "x": "eW2Ypfvp_PJTPsCOY0dLXkHf9BnCFxzp6KiGxkRYBQ4", "y": "AaQ3aaKw0KdzVuagqu71HuzLS0PtsNgUHOeCBHcFxvw", "d": "Hy49TFtqeYgBI0VniavN7_7cuph2VDIQDx4tPEtaaXg", "kty": "EC"}"#;
+
+/// A JWS signed by `joserfc` with the key above.
+const FIXTURE_JWS: &str =
+    "eyJhbGciOiJFUzI1NksiLCJ0eXAiOiJKV1QifQ.eyJzdWIiOiJsaXRlcmF0ZS1jcnlwdG8iLCJuIjo0Mn0.\
+     pJnfutspFGRElA3HlV5xvZc0gjutHWecTGo5AgzQ-FHK7Q8miGpyvjRlNFKiQ4xeonbHBcs8uP0VBYznbwBRxQ";
+
+const HEADER: &str = r#"{"alg":"ES256K","typ":"JWT"}"#;
+const PAYLOAD: &[u8] = br#"{"sub":"literate-crypto","n":42}"#;
+
+fn fixture_key() -> PrivateKey<Secp256k1> {
+    PrivateKey::from_jwk(FIXTURE_JWK).unwrap()
+}
+
+/// Replace one part of a compact JWS.
+fn replace_part(token: &str, index: usize, part: &str) -> String {
+    let mut parts: Vec<_> = token.split('.').collect();
+    parts[index] = part;
+    parts.join(".")
+}
+
+fn encode(bytes: &[u8]) -> String {
+    base64::encode(bytes, Alphabet::UrlSafe, false)
+}
+
+#[test]
+fn jwk_round_trip() {
+    let key = fixture_key();
+    assert_eq!(
+        key.to_jwk(),
+        r#"{"kty":"EC","crv":"secp256k1","x":"eW2Ypfvp_PJTPsCOY0dLXkHf9BnCFxzp6KiGxkRYBQ4","y":"AaQ3aaKw0KdzVuagqu71HuzLS0PtsNgUHOeCBHcFxvw","d":"Hy49TFtqeYgBI0VniavN7_7cuph2VDIQDx4tPEtaaXg"}"#
+    );
+    assert_eq!(
+        PrivateKey::from_jwk(&key.to_jwk()).unwrap().to_jwk(),
+        key.to_jwk()
+    );
+
+    let public = key.derive();
+    assert_eq!(PublicKey::from_jwk(&public.to_jwk()).unwrap(), public);
+    // The public key can be read from the private JWK as well.
+    assert_eq!(PublicKey::from_jwk(FIXTURE_JWK).unwrap(), public);
+    assert_eq!(
+        PrivateKey::<Secp256k1>::from_jwk(&public.to_jwk()).unwrap_err(),
+        JoseError::MissingMember("d")
+    );
+}
+
+/// Coordinates with leading zero bytes keep their full 32-byte length.
+#[test]
+fn jwk_fixed_width() {
+    let key = PrivateKey::<Secp256k1>::new(Num::ONE).unwrap();
+    let jwk = key.to_jwk();
+    assert!(jwk.contains(r#""d":"AAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAE""#));
+
+    let short = jwk.replace("AAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAE", "AQ");
+    assert_eq!(
+        PrivateKey::<Secp256k1>::from_jwk(&short).unwrap_err(),
+        JoseError::InvalidMember("d")
+    );
+}
+
+#[test]
+fn jwk_invalid() {
+    let public = fixture_key().derive().to_jwk();
+    let invalid = |jwk: &str| PublicKey::<Secp256k1>::from_jwk(jwk).unwrap_err();
+
+    assert_eq!(
+        invalid(&public.replace("secp256k1", "P-256")),
+        JoseError::InvalidMember("crv")
+    );
+    assert_eq!(
+        invalid(&public.replace(r#""EC""#, r#""OKP""#)),
+        JoseError::InvalidMember("kty")
+    );
+    assert_eq!(
+        invalid(&public.replace(r#""kty":"EC""#, r#""kty":1"#)),
+        JoseError::InvalidMember("kty")
+    );
+    assert_eq!(
+        invalid(
+            r#"{"kty":"EC","crv":"secp256k1","x":"eW2Ypfvp_PJTPsCOY0dLXkHf9BnCFxzp6KiGxkRYBQ4"}"#
+        ),
+        JoseError::MissingMember("y")
+    );
+    // A point off the curve.
+    assert_eq!(
+        invalid(&public.replace("AaQ3", "AaQ4")),
+        JoseError::InvalidKey
+    );
+    // Padding is not allowed in JOSE.
+    assert_eq!(
+        invalid(&public.replace("xkRYBQ4", "xkRYBQ4=")),
+        JoseError::InvalidMember("x")
+    );
+
+    // A private key which doesn't match the public key.
+    let other = PrivateKey::<Secp256k1>::new(Num::TWO).unwrap().to_jwk();
+    let d = other.split('"').nth(19).unwrap();
+    let mismatched = FIXTURE_JWK.replace("Hy49TFtqeYgBI0VniavN7_7cuph2VDIQDx4tPEtaaXg", d);
+    assert_ne!(mismatched, FIXTURE_JWK);
+    assert_eq!(
+        PrivateKey::<Secp256k1>::from_jwk(&mismatched).unwrap_err(),
+        JoseError::InvalidKey
+    );
+}
+
+/// Only flat JSON objects without duplicate members are accepted.
+#[test]
+fn json() {
+    let public = fixture_key().derive().to_jwk();
+    let parse = |jwk: &str| PublicKey::<Secp256k1>::from_jwk(jwk).map(|_| ());
+
+    // Whitespace, escapes and other scalar members are fine.
+    let spaced = public
+        .replace(',', " ,\n\t")
+        .replace(r#""EC""#, r#""\u0045C""#)
+        .replace(
+            '{',
+            r#"{ "use": "sig", "ext": true, "n": -1.5e3, "x5u": null, "#,
+        );
+    assert_eq!(parse(&spaced), Ok(()));
+    assert_eq!(
+        parse(&public.replace('{', r#"{"kid":"🔑 \"key\"\\","#)),
+        Ok(())
+    );
+
+    for invalid in [
+        "",
+        "[]",
+        "{",
+        r#"{"kty":"EC"} {}"#,
+        &public.replace('{', r#"{"key_ops":["sign"],"#),
+        &public.replace('{', r#"{"nested":{},"#),
+        &public.replace('{', r#"{"crv":"secp256k1","#),
+        &public.replace('{', r#"{"kid":"\ud83d","#),
+        &public.replace('{', r#"{"kid":"\q","#),
+        &public.replace('{', "{\"kid\":\"\n\","),
+        &public.replace('{', r#"{"n":tru,"#),
+        &public.replace('{', r#"{"n":-,"#),
+        &public.replace('}', ",}"),
+    ] {
+        assert_eq!(parse(invalid), Err(JoseError::Json), "{invalid}");
+    }
+}
+
+/// Verify a token signed by another implementation.
+#[test]
+fn jws_fixture() {
+    let public = fixture_key().derive();
+    assert_eq!(jws_es256k_verify(public, FIXTURE_JWS).unwrap(), PAYLOAD);
+}
+
+#[test]
+fn jws_round_trip() {
+    let key = fixture_key();
+    let token = jws_es256k_sign(key.clone(), HEADER, PAYLOAD).unwrap();
+    let parts: Vec<_> = token.split('.').collect();
+    assert_eq!(parts[0], encode(HEADER.as_bytes()));
+    assert_eq!(parts[1], encode(PAYLOAD));
+    assert_eq!(parts[2].len(), 86);
+    assert_eq!(jws_es256k_verify(key.derive(), &token).unwrap(), PAYLOAD);
+
+    // The header must select ES256K.
+    assert_eq!(
+        jws_es256k_sign(key.clone(), r#"{"typ":"JWT"}"#, PAYLOAD).unwrap_err(),
+        JoseError::MissingMember("alg")
+    );
+    assert_eq!(
+        jws_es256k_sign(key, r#"{"alg":"ES256"}"#, PAYLOAD).unwrap_err(),
+        JoseError::InvalidMember("alg")
+    );
+}
+
+#[test]
+fn jws_tampered() {
+    let public = fixture_key().derive();
+    let verify = |token: &str| jws_es256k_verify(public, token).unwrap_err();
+
+    let payload = encode(br#"{"sub":"literate-crypto","n":43}"#);
+    assert_eq!(
+        verify(&replace_part(FIXTURE_JWS, 1, &payload)),
+        JoseError::InvalidSignature
+    );
+    let header = encode(br#"{"alg":"ES256K","typ":"JOSE"}"#);
+    assert_eq!(
+        verify(&replace_part(FIXTURE_JWS, 0, &header)),
+        JoseError::InvalidSignature
+    );
+    let mut sig =
+        base64::decode(FIXTURE_JWS.split('.').nth(2).unwrap(), Alphabet::UrlSafe).unwrap();
+    sig[10] ^= 1;
+    assert_eq!(
+        verify(&replace_part(FIXTURE_JWS, 2, &encode(&sig))),
+        JoseError::InvalidSignature
+    );
+
+    // The wrong key.
+    let other = PrivateKey::<Secp256k1>::new(Num::TWO).unwrap().derive();
+    assert_eq!(
+        jws_es256k_verify(other, FIXTURE_JWS).unwrap_err(),
+        JoseError::InvalidSignature
+    );
+}
+
+/// Tokens which can be rejected without doing any elliptic curve arithmetic.
+#[test]
+fn jws_malformed() {
+    let public = fixture_key().derive();
+    let verify = |token: &str| jws_es256k_verify(public, token).unwrap_err();
+
+    for alg in ["none", "ES256", "HS256"] {
+        let header = encode(format!(r#"{{"alg":"{alg}"}}"#).as_bytes());
+        assert_eq!(
+            verify(&replace_part(FIXTURE_JWS, 0, &header)),
+            JoseError::InvalidMember("alg")
+        );
+    }
+    let header = encode(br#"{"alg":"ES256K","crit":"exp"}"#);
+    assert_eq!(
+        verify(&replace_part(FIXTURE_JWS, 0, &header)),
+        JoseError::InvalidMember("crit")
+    );
+    assert_eq!(
+        verify(&replace_part(FIXTURE_JWS, 0, &encode(b"ES256K"))),
+        JoseError::Json
+    );
+
+    let sig = FIXTURE_JWS.split('.').nth(2).unwrap();
+    assert_eq!(
+        verify(&FIXTURE_JWS.replace(sig, "")),
+        JoseError::InvalidSignature
+    );
+    assert_eq!(
+        verify(&replace_part(FIXTURE_JWS, 2, &encode(&[0; 64]))),
+        JoseError::InvalidSignature
+    );
+    assert_eq!(
+        verify(&replace_part(FIXTURE_JWS, 2, &encode(&[0xff; 64]))),
+        JoseError::InvalidSignature
+    );
+
+    assert_eq!(verify(""), JoseError::Malformed);
+    assert_eq!(
+        verify(&FIXTURE_JWS[..FIXTURE_JWS.rfind('.').unwrap()]),
+        JoseError::Malformed
+    );
+    assert_eq!(verify(&format!("{FIXTURE_JWS}.")), JoseError::Malformed);
+    assert_eq!(verify(&format!("{FIXTURE_JWS}=")), JoseError::Malformed);
+    assert_eq!(
+        verify(&replace_part(FIXTURE_JWS, 1, "a+b/")),
+        JoseError::Malformed
+    );
+}
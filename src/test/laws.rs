@@ -0,0 +1,432 @@
+//! Property tests for the algebraic laws of [groups](Group).
+//!
+//! Fixed test vectors only cover the inputs somebody thought of. The laws
+//! below must hold for _all_ inputs, so they are checked on randomized
+//! instances instead:
+//!
+//! - associativity, $(a + b) + c = a + (b + c)$,
+//! - commutativity, $a + b = b + a$,
+//! - identity, $a + e = e + a = a$,
+//! - inverse, $a + (-a) = e$,
+//! - distributivity, $(m + n \bmod N) G = mG + nG$, and
+//! - compatibility, $(mn \bmod N) G = m(nG)$,
+//!
+//! where $G$ is the generator of order $N$. Any [`Sample`] type gets the whole
+//! suite with a single invocation of the [`laws!`](laws) macro, e.g. every
+//! [curve](Curve) through its points:
+//!
+//! ```text
+//! laws!(secp256k1_laws: Point<Secp256k1>);
+//! ```
+//!
+//! The first cases of every law are edge cases: identities, repeated and
+//! inverse elements, and scalars $0$, $1$ and $N - 1$. The rest are drawn from
+//! [Fortuna](crate::Fortuna), so runs are reproducible. When a case fails, it
+//! is shrunk to a simpler failing case, which is printed in hex.
+//!
+//! The smoke tests run a few dozen cases per law, fewer for the slow scalar
+//! multiplications. The long runs are ignored by default; run them with
+//! `cargo test laws -- --ignored`.
+
+use {
+    crate::{
+        ecc::{Curve, Group, Num, Point},
+        hex,
+        testing::rng,
+        Secp256k1,
+    },
+    std::{any, fmt},
+};
+
+/// Cases per law in the smoke tests.
+pub(super) const SMOKE: usize = 32;
+
+/// Cases per law in the smoke tests of laws built on scalar multiplication,
+/// which is slow on real curves.
+const SMOKE_SCALE: usize = 4;
+
+/// Cases per law in the long runs.
+pub(super) const LONG: usize = 500;
+
+/// The number of attempts to simplify a failing case.
+const SHRINK_BUDGET: usize = 2_000;
+
+/// A group whose elements can be generated for the property tests.
+pub(super) trait Sample: Group + fmt::Debug {
+    /// The order $N$ of the [generator](Sample::generator).
+    fn order() -> Num;
+
+    fn generator() -> Self;
+
+    /// Map a number to an element. Zero must map to the identity, and smaller
+    /// numbers should give simpler elements, so that shrinking the number
+    /// simplifies the element.
+    ///
+    /// By default this is a multiple of the generator.
+    fn element(k: Num) -> Self {
+        Self::generator().scale(k)
+    }
+}
+
+/// Curve points are sampled by hashing to the curve: starting at $x = k \bmod
+/// P$, the first $x$ for which $x^3 + ax + b$ is a square gives the point $(x,
+/// \sqrt{x^3 + ax + b})$. This is much cheaper than multiplying the generator,
+/// and it also reaches points outside of the subgroup of $G$ on curves with a
+/// [cofactor](Curve::COFACTOR).
+impl<C: Curve + fmt::Debug> Sample for Point<C> {
+    fn order() -> Num {
+        C::N
+    }
+
+    fn generator() -> Self {
+        C::g()
+    }
+
+    fn element(k: Num) -> Self {
+        if k == Num::ZERO {
+            return Point::infinity();
+        }
+        let mut x = k.reduce(C::P);
+        loop {
+            let rhs = x
+                .mul(x, C::P)
+                .mul(x, C::P)
+                .add(C::A.mul(x, C::P), C::P)
+                .add(C::B, C::P);
+            if let Some(y) = rhs.sqrt(C::P) {
+                return Point::new(x, y).unwrap();
+            }
+            x = x.add(Num::ONE, C::P);
+        }
+    }
+}
+
+/// A law which must hold for all scalars and elements of the group.
+pub(super) struct Law<G> {
+    name: &'static str,
+    /// The number of scalars below the [order](Sample::order) the law takes.
+    scalars: usize,
+    /// The number of elements the law takes.
+    elements: usize,
+    /// The number of cases in the smoke test.
+    smoke: usize,
+    holds: fn(&[Num], &[G]) -> bool,
+}
+
+pub(super) fn associativity<G: Sample>() -> Law<G> {
+    Law {
+        name: "associativity",
+        scalars: 0,
+        elements: 3,
+        smoke: SMOKE,
+        holds: |_, e| e[0].op(e[1]).op(e[2]) == e[0].op(e[1].op(e[2])),
+    }
+}
+
+pub(super) fn commutativity<G: Sample>() -> Law<G> {
+    Law {
+        name: "commutativity",
+        scalars: 0,
+        elements: 2,
+        smoke: SMOKE,
+        holds: |_, e| e[0].op(e[1]) == e[1].op(e[0]),
+    }
+}
+
+pub(super) fn identity<G: Sample>() -> Law<G> {
+    Law {
+        name: "identity",
+        scalars: 0,
+        elements: 1,
+        smoke: SMOKE,
+        holds: |_, e| e[0].op(G::identity()) == e[0] && G::identity().op(e[0]) == e[0],
+    }
+}
+
+pub(super) fn inverse<G: Sample>() -> Law<G> {
+    Law {
+        name: "inverse",
+        scalars: 0,
+        elements: 1,
+        smoke: SMOKE,
+        holds: |_, e| {
+            e[0].op(e[0].inverse()) == G::identity()
+                && e[0].inverse().op(e[0]) == G::identity()
+                && e[0].inverse().inverse() == e[0]
+        },
+    }
+}
+
+pub(super) fn distributivity<G: Sample>() -> Law<G> {
+    Law {
+        name: "distributivity",
+        scalars: 2,
+        elements: 0,
+        smoke: SMOKE_SCALE,
+        holds: |s, _| {
+            let g = G::generator();
+            g.scale(s[0].add(s[1], G::order())) == g.scale(s[0]).op(g.scale(s[1]))
+        },
+    }
+}
+
+pub(super) fn compatibility<G: Sample>() -> Law<G> {
+    Law {
+        name: "compatibility",
+        scalars: 2,
+        elements: 0,
+        smoke: SMOKE_SCALE,
+        holds: |s, _| {
+            let g = G::generator();
+            g.scale(s[0].mul(s[1], G::order())) == g.scale(s[1]).scale(s[0])
+        },
+    }
+}
+
+/// Generate a module with a test for each law, checked for the given
+/// [`Sample`] type, and an ignored test running all laws for longer.
+macro_rules! laws {
+    ($name:ident: $group:ty) => {
+        mod $name {
+            #[allow(unused_imports)]
+            use super::*;
+
+            $crate::test::laws::laws!(
+                @tests $group;
+                associativity,
+                commutativity,
+                identity,
+                inverse,
+                distributivity,
+                compatibility
+            );
+        }
+    };
+    (@tests $group:ty; $($law:ident),*) => {
+        $(
+            #[test]
+            fn $law() {
+                let law = $crate::test::laws::$law::<$group>();
+                $crate::test::laws::check(&law, law.smoke_cases());
+            }
+        )*
+
+        #[test]
+        #[ignore]
+        fn long() {
+            $(
+                $crate::test::laws::check(
+                    &$crate::test::laws::$law::<$group>(),
+                    $crate::test::laws::LONG,
+                );
+            )*
+        }
+    };
+}
+
+pub(super) use laws;
+
+laws!(secp256k1: Point<Secp256k1>);
+
+impl<G: Sample> Law<G> {
+    pub(super) fn smoke_cases(&self) -> usize {
+        self.smoke
+    }
+
+    /// Check the law for the scalars followed by the seeds of the elements.
+    /// The lowest bit of a seed selects the inverse of the
+    /// [element](Sample::element) given by the remaining bits.
+    fn check_inputs(&self, inputs: &[Num]) -> bool {
+        let (scalars, seeds) = inputs.split_at(self.scalars);
+        let elements: Vec<G> = seeds.iter().map(|&seed| element(seed)).collect();
+        (self.holds)(scalars, &elements)
+    }
+
+    /// The inputs of the `i`-th case. The first three cases are edge cases.
+    fn inputs(&self, i: usize, rng: &mut impl Iterator<Item = u8>) -> Vec<Num> {
+        let n = G::order();
+        let last = n.sub(Num::ONE, n);
+        let mut random = || Num::from_le_bytes(std::array::from_fn(|_| rng.next().unwrap()));
+        let seed = random();
+        let scalars = (0..self.scalars).map(|j| match (i, j % 2) {
+            (0, _) => Num::ZERO,
+            (1, _) => last,
+            (2, 0) => Num::ONE,
+            (2, _) => last,
+            _ => random().reduce(n),
+        });
+        let scalars: Vec<Num> = scalars.collect();
+        let seeds = (0..self.elements).map(|j| match (i, j % 2) {
+            // The identity.
+            (0, _) => Num::ZERO,
+            // Equal elements.
+            (1, _) => seed,
+            // Inverse elements.
+            (2, 0) => seed,
+            (2, _) => flip_lowest_bit(seed),
+            _ => random(),
+        });
+        scalars.into_iter().chain(seeds).collect()
+    }
+}
+
+/// Check the law on the given number of cases, and panic with a shrunk
+/// reproduction if it fails.
+pub(super) fn check<G: Sample>(law: &Law<G>, cases: usize) {
+    if let Some(inputs) = find_failure(law, cases) {
+        panic!("{}", report(law, &inputs));
+    }
+}
+
+/// Find a failing case of the law, shrunk to a simpler one.
+fn find_failure<G: Sample>(law: &Law<G>, cases: usize) -> Option<Vec<Num>> {
+    let mut rng = rng().into_iter();
+    (0..cases)
+        .map(|i| law.inputs(i, &mut rng))
+        .find(|inputs| !law.check_inputs(inputs))
+        .map(|inputs| shrink(law, inputs))
+}
+
+/// Greedily replace inputs with simpler ones for as long as the law still
+/// fails, trying zero and one, then halving, then subtracting powers of two
+/// from the largest down. The result is a local minimum: no single input can
+/// be simplified further this way, and if the law fails exactly for the inputs
+/// above some threshold, the threshold is found.
+fn shrink<G: Sample>(law: &Law<G>, mut inputs: Vec<Num>) -> Vec<Num> {
+    let mut budget = SHRINK_BUDGET;
+    'simplify: while budget > 0 {
+        for i in 0..inputs.len() {
+            for candidate in simpler(inputs[i]) {
+                if budget == 0 {
+                    break 'simplify;
+                }
+                budget -= 1;
+                let mut next = inputs.clone();
+                next[i] = candidate;
+                if !law.check_inputs(&next) {
+                    inputs = next;
+                    continue 'simplify;
+                }
+            }
+        }
+        break;
+    }
+    inputs
+}
+
+/// Numbers smaller than `n`, simplest first.
+fn simpler(n: Num) -> impl Iterator<Item = Num> {
+    let top = (0..Num::BITS).rev().find(|&i| n.get_bit(i));
+    let powers = top
+        .into_iter()
+        .flat_map(|top| (0..=top).rev())
+        .map(move |i| n.sub(set_bit(Num::ZERO, i, true), n));
+    [Num::ZERO, Num::ONE, shift_right(n)]
+        .into_iter()
+        .chain(powers)
+        .filter(move |&c| c < n)
+}
+
+fn report<G: Sample>(law: &Law<G>, inputs: &[Num]) -> String {
+    let (scalars, seeds) = inputs.split_at(law.scalars);
+    let elements: Vec<G> = seeds.iter().map(|&seed| element(seed)).collect();
+    let hex = |nums: &[Num]| nums.iter().map(|&n| to_hex(n)).collect::<Vec<_>>();
+    format!(
+        "law `{}` fails for {}\n  scalars: {:?}\n  seeds: {:?}\n  elements: {:?}",
+        law.name,
+        any::type_name::<G>(),
+        hex(scalars),
+        hex(seeds),
+        elements,
+    )
+}
+
+/// Print a number in hex without leading zeros.
+fn to_hex(n: Num) -> String {
+    let hex = hex::encode(&n.to_be_bytes());
+    match hex.trim_start_matches('0') {
+        "" => "0x0".to_owned(),
+        digits => format!("0x{digits}"),
+    }
+}
+
+fn element<G: Sample>(seed: Num) -> G {
+    let e = G::element(shift_right(seed));
+    if seed.get_bit(0) {
+        e.inverse()
+    } else {
+        e
+    }
+}
+
+fn shift_right(n: Num) -> Num {
+    let mut bytes = n.to_le_bytes();
+    for i in 0..Num::BYTES {
+        bytes[i] = bytes[i] >> 1 | bytes.get(i + 1).map_or(0, |b| b << 7);
+    }
+    Num::from_le_bytes(bytes)
+}
+
+fn set_bit(n: Num, i: usize, value: bool) -> Num {
+    let mut bytes = n.to_le_bytes();
+    bytes[i / 8] &= !(1 << (i % 8));
+    bytes[i / 8] |= u8::from(value) << (i % 8);
+    Num::from_le_bytes(bytes)
+}
+
+fn flip_lowest_bit(n: Num) -> Num {
+    set_bit(n, 0, !n.get_bit(0))
+}
+
+fn num(n: u64) -> Num {
+    Num::from_le_words([n, 0, 0, 0])
+}
+
+/// A broken law fails, and is shrunk to the smallest failing input.
+#[test]
+fn shrinking() {
+    let law = Law::<Point<Secp256k1>> {
+        name: "small scalars",
+        scalars: 1,
+        elements: 1,
+        smoke: SMOKE,
+        holds: |s, _| s[0] < num(1000),
+    };
+    let inputs = find_failure(&law, SMOKE).unwrap();
+    assert_eq!(inputs, [num(1000), Num::ZERO]);
+    assert!(report(&law, &inputs).contains(r#"scalars: ["0x3e8"]"#));
+
+    let law = Law::<Point<Secp256k1>> {
+        name: "identity only",
+        scalars: 0,
+        elements: 2,
+        smoke: SMOKE,
+        holds: |_, e| e[1] == Point::infinity(),
+    };
+    // Seed 1 is the inverse of the identity, which is still the identity.
+    assert_eq!(find_failure(&law, SMOKE).unwrap(), [Num::ZERO, num(2)]);
+}
+
+#[test]
+fn sqrt_toy() {
+    let p = num(1009);
+    let squares: Vec<u64> = (1..1009).map(|x| x * x % 1009).collect();
+    for a in 0..1009 {
+        match num(a).sqrt(p) {
+            Some(r) => assert_eq!(r.mul(r, p), num(a)),
+            None => assert!(!squares.contains(&a)),
+        }
+    }
+}
+
+#[test]
+fn sampling() {
+    type P = Point<Secp256k1>;
+    assert_eq!(element::<P>(Num::ZERO), P::infinity());
+    for seed in [2, 3, 1000, 1001] {
+        assert_ne!(element::<P>(num(seed)), P::infinity());
+    }
+    assert_eq!(element::<P>(num(1001)), element::<P>(num(1000)).inverse());
+    assert_eq!(to_hex(Num::ZERO), "0x0");
+    assert_eq!(to_hex(num(0x1234)), "0x1234");
+}
@@ -0,0 +1,277 @@
+//! Monte Carlo tests (MCT) from the NIST Cryptographic Algorithm Validation
+//! Program (CAVP).
+//!
+//! Each test chains thousands of operations, feeding every output back into the
+//! next input, and only checks a value after every 1000 operations. A bug which
+//! corrupts some state carried between operations, like the message schedule
+//! of a hash or the key schedule of a cipher, is very likely to show up in the
+//! final value even if it is missed by single-shot vectors.
+//!
+//! The full tests run 100 checkpoints and are ignored by default; run them with
+//! `cargo test mct -- --ignored`. The smoke tests run the first 10 checkpoints
+//! of the same chains.
+//!
+//! The SHA-256 values and the first AES-128 encryption checkpoint are from the
+//! CAVP response files (`SHA256Monte.rsp`, `ECBMCT128.rsp`). The other AES
+//! checkpoints were computed by running the same procedure against OpenSSL.
+
+use crate::{hex, Aes128, Aes192, Aes256, BlockDecrypt, BlockEncrypt, Hash, Sha256};
+
+/// The SHA-256 MCT seed from `SHA256Monte.rsp`.
+const SHA256_SEED: &str = "6d1e72ad03ddeb5de891e572e2396f8da015d899ef0e79503152d6010a3fe691";
+
+/// Run the SHA-256 MCT with 10 checkpoints.
+#[test]
+fn sha256_mct_smoke() {
+    let checkpoints = sha_mct(&Sha256::default(), block(SHA256_SEED), 10);
+    assert_eq!(
+        hex::encode(&checkpoints[0]),
+        "e93c330ae5447738c8aa85d71a6c80f2a58381d05872d26bdd39f1fcd4f2b788"
+    );
+    assert_eq!(
+        hex::encode(&checkpoints[9]),
+        "72361401c670d07f1151a95e2ee914665c2bdb1228581833c7dc53b89c01c927"
+    );
+}
+
+/// Run the full SHA-256 MCT.
+#[test]
+#[ignore]
+fn sha256_mct() {
+    let checkpoints = sha_mct(&Sha256::default(), block(SHA256_SEED), 100);
+    assert_eq!(
+        hex::encode(&checkpoints[99]),
+        "6a912ba4188391a78e6f13d88ed2d14e13afce9db6f7dcbf4a48c24f3db02778"
+    );
+}
+
+/// Run the AES ECB MCT with 10 checkpoints, for every key size and in both
+/// directions.
+#[test]
+fn aes_ecb_mct_smoke() {
+    for case in AES_CASES {
+        let checkpoints = case.run(10);
+        if case.name == "AES-128 encrypt" {
+            // COUNT = 0 from ECBMCT128.rsp.
+            assert_eq!(
+                hex::encode(&checkpoints[0]),
+                "d7c3ffac9031238650901e157364c386"
+            );
+        }
+        assert_eq!(
+            hex::encode(&checkpoints[9]),
+            case.expected[0],
+            "{}",
+            case.name
+        );
+    }
+}
+
+/// Run the full AES ECB MCT, for every key size and in both directions.
+#[test]
+#[ignore]
+fn aes_ecb_mct() {
+    for case in AES_CASES {
+        let checkpoints = case.run(100);
+        assert_eq!(
+            hex::encode(&checkpoints[99]),
+            case.expected[1],
+            "{}",
+            case.name
+        );
+    }
+}
+
+/// The SHAVS Monte Carlo test for a hash with an N-byte digest.
+///
+/// Each checkpoint starts from a seed $S$ and sets $MD_0 = MD_1 = MD_2 = S$.
+/// Then, for $i = 3$ to $1002$:
+///
+/// $$
+/// MD_i = Hash(MD_{i-3} \parallel MD_{i-2} \parallel MD_{i-1})
+/// $$
+///
+/// The checkpoint value is $MD_{1002}$, which is also the seed of the next
+/// checkpoint.
+#[docext::docext]
+fn sha_mct<H, const N: usize>(hash: &H, seed: [u8; N], checkpoints: usize) -> Vec<[u8; N]>
+where
+    H: Hash<Digest = [u8; N]>,
+{
+    let mut seed = seed;
+    let mut result = Vec::new();
+    for _ in 0..checkpoints {
+        // The three most recent digests, oldest first.
+        let mut md = [seed; 3];
+        for _ in 0..1000 {
+            let msg = md.concat();
+            md.rotate_left(1);
+            md[2] = hash.hash(&msg);
+        }
+        seed = md[2];
+        result.push(seed);
+    }
+    result
+}
+
+/// The direction of an [AES MCT](aes_mct).
+#[derive(Debug, Clone, Copy)]
+enum Direction {
+    Encrypt,
+    Decrypt,
+}
+
+/// The AESAVS Monte Carlo test for ECB mode.
+///
+/// Each checkpoint runs 1000 operations with the same key $K$, feeding each
+/// output block back as the next input, so that $T_{j+1} = AES_K(T_j)$.
+/// The checkpoint value is the last output $T_{1000}$, which is also the first
+/// input of the next checkpoint. Before the next checkpoint, the key is XORed
+/// with the last $|K|$ bytes of the last two outputs:
+///
+/// $$
+/// K \leftarrow K \oplus \text{last}_{|K|}(T_{999} \parallel T_{1000})
+/// $$
+///
+/// For AES-128 this is just $T_{1000}$, for AES-192 the last 8 bytes of
+/// $T_{999}$ followed by $T_{1000}$, and for AES-256 both blocks in full.
+#[docext::docext]
+fn aes_mct<Cip, const KEY: usize>(
+    cip: &Cip,
+    key: [u8; KEY],
+    text: [u8; 16],
+    direction: Direction,
+    checkpoints: usize,
+) -> Vec<[u8; 16]>
+where
+    Cip: BlockEncrypt<EncryptionBlock = [u8; 16], EncryptionKey = [u8; KEY]>
+        + BlockDecrypt<DecryptionBlock = [u8; 16], DecryptionKey = [u8; KEY]>,
+{
+    let (mut key, mut text) = (key, text);
+    let mut result = Vec::new();
+    for _ in 0..checkpoints {
+        let mut prev = [0; 16];
+        for _ in 0..1000 {
+            prev = text;
+            text = match direction {
+                Direction::Encrypt => cip.encrypt(text, key),
+                Direction::Decrypt => cip.decrypt(text, key),
+            };
+        }
+        result.push(text);
+
+        let last_two = [prev, text].concat();
+        key.iter_mut()
+            .zip(&last_two[last_two.len() - KEY..])
+            .for_each(|(k, t)| *k ^= t);
+    }
+    result
+}
+
+/// A chain of the [AES MCT](aes_mct).
+struct AesCase {
+    name: &'static str,
+    direction: Direction,
+    key: &'static str,
+    text: &'static str,
+    /// Checkpoints 9 and 99.
+    expected: [&'static str; 2],
+}
+
+impl AesCase {
+    fn run(&self, checkpoints: usize) -> Vec<[u8; 16]> {
+        let (text, direction) = (block(self.text), self.direction);
+        // The key size follows from the length of the hex key.
+        match self.key.len() / 2 {
+            16 => aes_mct(
+                &Aes128::default(),
+                block(self.key),
+                text,
+                direction,
+                checkpoints,
+            ),
+            24 => aes_mct(
+                &Aes192::default(),
+                block(self.key),
+                text,
+                direction,
+                checkpoints,
+            ),
+            _ => aes_mct(
+                &Aes256::default(),
+                block(self.key),
+                text,
+                direction,
+                checkpoints,
+            ),
+        }
+    }
+}
+
+const AES_CASES: [AesCase; 6] = [
+    AesCase {
+        name: "AES-128 encrypt",
+        direction: Direction::Encrypt,
+        key: "139a35422f1d61de3c91787fe0507afd",
+        text: "b9145a768b7dc489a096b546f43b231f",
+        expected: [
+            "b0eaede3f3eebfef88822a6ede1950b1",
+            "fb2649694783b551eacd9d5db6126d47",
+        ],
+    },
+    AesCase {
+        name: "AES-128 decrypt",
+        direction: Direction::Decrypt,
+        key: "139a35422f1d61de3c91787fe0507afd",
+        text: "d7c3ffac9031238650901e157364c386",
+        expected: [
+            "78e51def0e7ca1565c70dc2bc2057fc0",
+            "1cc530d6b2b9adbba5d444dd05c2f922",
+        ],
+    },
+    AesCase {
+        name: "AES-192 encrypt",
+        direction: Direction::Encrypt,
+        key: "000102030405060708090a0b0c0d0e0f1011121314151617",
+        text: "f0f1f2f3f4f5f6f7f8f9fafbfcfdfeff",
+        expected: [
+            "8c7bd501bb1b4687180b2524221919ca",
+            "8b178e443536207037ffe7dd96721182",
+        ],
+    },
+    AesCase {
+        name: "AES-192 decrypt",
+        direction: Direction::Decrypt,
+        key: "000102030405060708090a0b0c0d0e0f1011121314151617",
+        text: "f0f1f2f3f4f5f6f7f8f9fafbfcfdfeff",
+        expected: [
+            "00aa97d984320c6a89b9e651f494a2d9",
+            "91f33b276ed4f13d9a6b6a66f12277ea",
+        ],
+    },
+    AesCase {
+        name: "AES-256 encrypt",
+        direction: Direction::Encrypt,
+        key: "000102030405060708090a0b0c0d0e0f101112131415161718191a1b1c1d1e1f",
+        text: "f0f1f2f3f4f5f6f7f8f9fafbfcfdfeff",
+        expected: [
+            "ec77aa10f99030a2a887c5d35073d4ba",
+            "806a2335fc20e49564b8882093e332a4",
+        ],
+    },
+    AesCase {
+        name: "AES-256 decrypt",
+        direction: Direction::Decrypt,
+        key: "000102030405060708090a0b0c0d0e0f101112131415161718191a1b1c1d1e1f",
+        text: "f0f1f2f3f4f5f6f7f8f9fafbfcfdfeff",
+        expected: [
+            "aa36cff5c7aa200da6b8daf3d4e81c4f",
+            "33b58790cd4f8fb131330ed216213ba1",
+        ],
+    },
+];
+
+/// Decode a hex string into an array.
+fn block<const N: usize>(hex: &str) -> [u8; N] {
+    hex::decode(hex).unwrap().try_into().unwrap()
+}
@@ -0,0 +1,464 @@
+//! Adversarial inputs for every public entry point which takes data that may
+//! come from an attacker. None of them may panic: malformed input must be
+//! rejected with an error.
+//!
+//! Unlike the [fuzzer](super::fuzz), which mutates valid encodings, the inputs
+//! here are structured: every field is drawn either from a list of edge cases
+//! (zero, one, the group order and its neighbours, the field prime, the
+//! largest number, lengths around block boundaries) or at random, with
+//! [Fortuna](crate::Fortuna) as the generator so that any failure can be
+//! reproduced. The iteration counts are bounded so that the test runs with the
+//! rest of the suite; targets which do elliptic curve arithmetic get fewer
+//! iterations, since that is slow in debug builds.
+//!
+//! The classification of every `unwrap` in the crate is in `no_panic.md`, next
+//! to this file.
+
+use {
+    crate::{
+        base64::{self, Alphabet},
+        codec::Reader,
+        ecc::{jose::jws_es256k_verify, Curve, Num, Point, PrivateKey, PublicKey},
+        hex,
+        hpke_open,
+        random::{shuffle, uniform_random},
+        testing::{key, rng},
+        Aes128,
+        Cbc,
+        CipherDecrypt,
+        Ctr,
+        Ecb,
+        Ecdsa,
+        EcdsaSignature,
+        Ecies,
+        EciesCiphertext,
+        EciesKem,
+        EncryptThenMac,
+        Hmac,
+        Kem,
+        MuSig2,
+        MultiSchnorr,
+        MultisigScheme,
+        NonceMode,
+        Padding,
+        Pkcs7,
+        Schnorr,
+        SchnorrSignature,
+        Secp256k1,
+        Sha256,
+        SignatureScheme,
+        SigningSession,
+    },
+    std::{
+        fmt,
+        panic::{self, AssertUnwindSafe},
+        sync::OnceLock,
+    },
+};
+
+/// Iterations for the targets which only parse or do symmetric cryptography.
+const ITERATIONS: usize = 300;
+
+/// Iterations for the targets which may do elliptic curve arithmetic.
+const EC_ITERATIONS: usize = 6;
+
+#[test]
+fn no_panic_num() {
+    let mut gen = Gen::new();
+    for _ in 0..ITERATIONS {
+        let input = (gen.num(), gen.num(), gen.num());
+        check(input, |&(a, b, p)| {
+            let _ = a.add(b, p);
+            let _ = a.sub(b, p);
+            let _ = a.mul(b, p);
+            let _ = a.eq(b, p);
+            let _ = a.reduce(p);
+            let _ = a.inv(p);
+        });
+    }
+}
+
+#[test]
+fn no_panic_keys() {
+    let mut gen = Gen::new();
+    for _ in 0..ITERATIONS {
+        let input = (gen.num(), gen.num());
+        check(input, |&(x, y)| {
+            let _ = PrivateKey::<Secp256k1>::new(x);
+            let _ = Point::<Secp256k1>::new(x, y);
+            let mut sec1 = vec![4];
+            sec1.extend(x.to_be_bytes());
+            sec1.extend(y.to_be_bytes());
+            let _ = PublicKey::<Secp256k1>::from_uncompressed(&sec1);
+        });
+    }
+    for _ in 0..ITERATIONS {
+        let input = gen.bytes(65);
+        check(input, |input| {
+            let _ = PublicKey::<Secp256k1>::from_uncompressed(input);
+        });
+    }
+}
+
+#[test]
+fn no_panic_signatures() {
+    let mut gen = Gen::new();
+    let key = key(3).derive();
+    for _ in 0..ITERATIONS {
+        let input = (gen.num(), gen.num());
+        check(input, |&(a, b)| {
+            let _ = EcdsaSignature::<Secp256k1, Sha256>::new(a, b);
+            let _ = SchnorrSignature::<Secp256k1, Sha256>::new(a, b);
+        });
+    }
+    for _ in 0..ITERATIONS {
+        let input = gen.array::<64>();
+        check(input, |input| {
+            let _ = SchnorrSignature::<Secp256k1, Sha256>::from_bytes(input);
+        });
+    }
+    // Zero components pass the signature constructors, but must be rejected
+    // by verification.
+    for input in [(Num::ZERO, Num::ONE), (Num::ONE, Num::ZERO)] {
+        check(input, |&(r, s)| {
+            let sig = EcdsaSignature::new(r, s).unwrap();
+            assert!(Ecdsa::new(Secp256k1::default(), Sha256::default())
+                .verify(key, b"msg", &sig)
+                .is_err());
+        });
+    }
+    for _ in 0..EC_ITERATIONS {
+        let input = (gen.num(), gen.num(), gen.bytes(8));
+        check(input, |(a, b, msg)| {
+            if let Ok(sig) = EcdsaSignature::new(*a, *b) {
+                let _ = Ecdsa::new(Secp256k1::default(), Sha256::default()).verify(key, msg, &sig);
+            }
+            if let Ok(sig) = SchnorrSignature::new(*a, *b) {
+                let _ =
+                    Schnorr::new(Secp256k1::default(), Sha256::default()).verify(key, msg, &sig);
+            }
+        });
+    }
+}
+
+/// A multisig by nobody has no combined pubkey, and must not verify.
+#[test]
+fn no_panic_multisig() {
+    let mut gen = Gen::new();
+    for _ in 0..ITERATIONS {
+        let input = (gen.num(), gen.num());
+        check(input, |&(s, e)| {
+            let Ok(sig) = SchnorrSignature::new(s, e) else {
+                return;
+            };
+            assert!(MultiSchnorr::new(Secp256k1::default(), Sha256::default())
+                .verify(&[], b"msg", &sig)
+                .is_err());
+            assert!(MuSig2::new(Secp256k1::default(), Sha256::default())
+                .verify(&[], b"msg", &sig)
+                .is_err());
+        });
+    }
+}
+
+#[test]
+fn no_panic_block_modes() {
+    let mut gen = Gen::new();
+    for _ in 0..ITERATIONS {
+        let input = (gen.bytes(16), gen.array::<16>(), gen.array::<16>());
+        check(input, |(data, key, iv)| {
+            let cbc = Cbc::new(Aes128::default(), Pkcs7::default(), *iv);
+            let _ = cbc.decrypt(data.clone(), *key);
+            let _ = cbc.decrypt_with_nonce(data.clone(), *key, iv);
+            let _ = Ecb::new(Aes128::default(), Pkcs7::default()).decrypt(data.clone(), *key);
+            let ctr = Ctr::new(Aes128::default(), 0).unwrap();
+            let _ = ctr.decrypt(data.clone(), *key);
+            let _ = ctr.decrypt_with_nonce(data.clone(), *key, &iv[..8]);
+        });
+    }
+}
+
+#[test]
+fn no_panic_padding() {
+    let mut gen = Gen::new();
+    for _ in 0..ITERATIONS {
+        let n = 1 + gen.below(255);
+        let input = (gen.bytes(n), n);
+        check(input, |(data, n)| {
+            let _ = Pkcs7::default().unpad(data.clone(), *n);
+        });
+    }
+}
+
+#[test]
+fn no_panic_text() {
+    let mut gen = Gen::new();
+    let alphabet = b"0123456789abcdefABCDEF+/-_=. \x00\xff";
+    for _ in 0..ITERATIONS {
+        let input = gen.text(alphabet);
+        check(input, |input| {
+            let _ = hex::decode(input);
+            let _ = base64::decode(input, Alphabet::Standard);
+            let _ = base64::decode(input, Alphabet::UrlSafe);
+        });
+    }
+}
+
+/// Read fields of random kinds until the input runs out or a read fails.
+#[test]
+fn no_panic_codec() {
+    let mut gen = Gen::new();
+    for _ in 0..ITERATIONS {
+        let input = gen.bytes(4);
+        check(input, |input| {
+            let mut reader = Reader::new(input);
+            for &kind in input.iter().cycle().take(8) {
+                let read = match kind % 5 {
+                    0 => reader.read_version(1),
+                    1 => reader.read_u8().map(drop),
+                    2 => reader.read_u32().map(drop),
+                    3 => reader.read_bytes(usize::from(kind)).map(drop),
+                    _ => reader.read_len_prefixed().map(drop),
+                };
+                if read.is_err() {
+                    return;
+                }
+            }
+            let _ = reader.remaining();
+            let _ = reader.finish();
+        });
+    }
+}
+
+#[test]
+fn no_panic_envelope() {
+    let mut gen = Gen::new();
+    let mut etm = EncryptThenMac::new(
+        Cbc::new(Aes128::default(), Pkcs7::default(), [0; 16]),
+        Hmac::new(Sha256::default()),
+    );
+    for _ in 0..ITERATIONS {
+        let input = (gen.bytes(16), gen.bytes(16));
+        check(input, |(envelope, key)| {
+            let _ = etm.open(envelope, key);
+        });
+    }
+}
+
+#[test]
+fn no_panic_session() {
+    let mut gen = Gen::new();
+    for _ in 0..ITERATIONS {
+        let input = gen.session();
+        check(input, |input| {
+            let _ = SigningSession::<Secp256k1, Sha256>::from_bytes(input);
+        });
+    }
+}
+
+#[test]
+fn no_panic_ecies() {
+    let mut gen = Gen::new();
+    for _ in 0..ITERATIONS {
+        let input = gen.ecies();
+        check(input, |input| {
+            let _ = EciesCiphertext::<Secp256k1, 16, 32>::from_bytes(input);
+        });
+    }
+    let mut ecies = Ecies::<_, _, Aes128>::new(Secp256k1::default(), Sha256::default());
+    for _ in 0..EC_ITERATIONS {
+        let input = gen.ecies();
+        check(input, |input| {
+            if let Ok(ciphertext) = EciesCiphertext::from_bytes(input) {
+                let _ = ecies.decrypt(key(7), &ciphertext);
+            }
+        });
+    }
+}
+
+#[test]
+fn no_panic_hpke() {
+    let mut gen = Gen::new();
+    for _ in 0..EC_ITERATIONS {
+        let input = (gen.point(), gen.bytes(16), gen.bytes(16));
+        check(input, |(enc, aad, ciphertext)| {
+            let _ = EciesKem::secp256k1().decapsulate(&key(7), enc);
+            let _ = hpke_open(&key(7), enc, b"info", aad, ciphertext);
+        });
+    }
+}
+
+#[test]
+fn no_panic_jose() {
+    let mut gen = Gen::new();
+    let key = key(3).derive();
+    let alphabet = b"eyJhbGciOiJFUzI1NksifQ.-_=+/";
+    for _ in 0..ITERATIONS {
+        let input = gen.text(alphabet);
+        check(input, |input| {
+            let _ = jws_es256k_verify(key, input);
+        });
+    }
+    // A well-formed header and payload, with signature components drawn from
+    // the edge cases.
+    let header = base64::encode(br#"{"alg":"ES256K","typ":"JWT"}"#, Alphabet::UrlSafe, false);
+    for _ in 0..EC_ITERATIONS {
+        let mut sig = gen.num().to_be_bytes().to_vec();
+        sig.extend(gen.num().to_be_bytes());
+        let input = format!(
+            "{header}.e30.{}",
+            base64::encode(&sig, Alphabet::UrlSafe, false)
+        );
+        check(input, |input| {
+            let _ = jws_es256k_verify(key, input);
+        });
+    }
+}
+
+#[test]
+fn no_panic_random() {
+    let mut gen = Gen::new();
+    let mut rng = rng();
+    for _ in 0..ITERATIONS {
+        let len = gen.below(4);
+        let start = gen.u32();
+        let input = (len, start, start.saturating_add(gen.u32() % 4));
+        check(input, |&(len, start, end)| {
+            shuffle(&mut rng, &mut vec![0u8; len]);
+            let _ = uniform_random(&mut rng, start..end);
+        });
+    }
+}
+
+/// Run the target on the input, and report the input if the target panics.
+fn check<T: fmt::Debug>(input: T, target: impl FnOnce(&T)) {
+    if panic::catch_unwind(AssertUnwindSafe(|| target(&input))).is_err() {
+        panic!("panicked on input {input:?}");
+    }
+}
+
+/// A generator of structured adversarial inputs.
+struct Gen(Box<dyn Iterator<Item = u8>>);
+
+impl Gen {
+    fn new() -> Self {
+        Self(Box::new(rng().into_iter()))
+    }
+
+    fn byte(&mut self) -> u8 {
+        self.0.next().unwrap()
+    }
+
+    fn u32(&mut self) -> u32 {
+        u32::from_le_bytes(self.array())
+    }
+
+    /// A random number in `0..n`. The modulo bias doesn't matter here.
+    fn below(&mut self, n: usize) -> usize {
+        usize::try_from(self.u32()).unwrap() % n
+    }
+
+    fn array<const N: usize>(&mut self) -> [u8; N] {
+        std::array::from_fn(|_| self.byte())
+    }
+
+    /// A number which is usually one of the edge cases, and otherwise random.
+    fn num(&mut self) -> Num {
+        let n = Secp256k1::N;
+        let p = Secp256k1::P;
+        let edges = [
+            Num::ZERO,
+            Num::ONE,
+            n.sub(Num::ONE, p),
+            n,
+            n.add(Num::ONE, p),
+            p.sub(Num::ONE, p),
+            p,
+            Num::from_le_words([u64::MAX; 4]),
+        ];
+        match self.below(edges.len() + 2) {
+            i if i < edges.len() => edges[i],
+            _ => Num::from_le_bytes(self.array()),
+        }
+    }
+
+    /// A length which is usually at or next to a multiple of the block size.
+    fn len(&mut self, block: usize) -> usize {
+        if self.byte() % 4 == 0 {
+            self.below(4 * block + 1)
+        } else {
+            (self.below(5) * block).saturating_add_signed(self.below(3) as isize - 1)
+        }
+    }
+
+    /// Random bytes, with a length drawn by [`len`](Gen::len).
+    fn bytes(&mut self, block: usize) -> Vec<u8> {
+        let len = self.len(block);
+        (0..len).map(|_| self.byte()).collect()
+    }
+
+    /// Random text over the given alphabet.
+    fn text(&mut self, alphabet: &[u8]) -> String {
+        let len = self.len(4);
+        (0..len)
+            .map(|_| char::from(alphabet[self.below(alphabet.len())]))
+            .collect()
+    }
+
+    /// An uncompressed SEC1 point with edge-case coordinates, which is
+    /// sometimes a valid point.
+    fn point(&mut self) -> [u8; 65] {
+        let mut point = [0; 65];
+        if self.byte() % 2 == 0 {
+            // Deriving a key is slow, so the valid points are computed once.
+            static VALID: OnceLock<Vec<[u8; 65]>> = OnceLock::new();
+            let valid =
+                VALID.get_or_init(|| (2..5).map(|n| key(n).derive().to_uncompressed()).collect());
+            point = valid[self.below(valid.len())];
+        } else {
+            point[0] = [4, 0, 2, 3][self.below(4)];
+            point[1..33].copy_from_slice(&self.num().to_be_bytes());
+            point[33..].copy_from_slice(&self.num().to_be_bytes());
+        }
+        point
+    }
+
+    /// An ECIES ciphertext with the right layout but adversarial fields.
+    fn ecies(&mut self) -> Vec<u8> {
+        let body = self.bytes(16);
+        let mut input = vec![[1, 1, 0, 2][self.below(4)]];
+        input.extend(self.point());
+        input.extend(self.array::<16>());
+        let len = match self.below(3) {
+            0 => u32::MAX,
+            1 => self.u32() % 64,
+            _ => body.len().try_into().unwrap(),
+        };
+        input.extend(len.to_be_bytes());
+        input.extend(body);
+        input.extend(self.bytes(32));
+        input
+    }
+
+    /// A signing session with the right layout but adversarial fields.
+    fn session(&mut self) -> Vec<u8> {
+        let mut input = vec![[1, 1, 0, 2][self.below(4)]];
+        input.extend(self.num().to_be_bytes());
+        input.extend(self.point());
+        input.extend(self.num().to_be_bytes());
+        let signers = self.below(3);
+        let count = match self.below(3) {
+            0 => u32::MAX,
+            _ => u32::try_from(signers + self.below(2)).unwrap(),
+        };
+        input.extend(count.to_be_bytes());
+        for _ in 0..signers {
+            input.extend(self.point());
+            input.push(self.byte() % 3);
+        }
+        if self.byte() % 4 == 0 {
+            input.truncate(self.below(input.len() + 1));
+        }
+        input
+    }
+}
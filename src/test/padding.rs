@@ -43,3 +43,17 @@ where
          {unpadded:?}"
     );
 }
+
+/// Unpadding rejects data which is too short for its padding byte, or which is
+/// not made of whole blocks, instead of panicking.
+#[test]
+fn pkcs7_invalid() {
+    let pad = Pkcs7::default();
+    assert!(pad.unpad(vec![], 16).is_err());
+    assert!(pad.unpad(vec![5], 16).is_err());
+    assert!(pad.unpad(vec![16; 15], 16).is_err());
+    assert!(pad.unpad(vec![1; 17], 16).is_err());
+    assert!(pad.unpad(vec![0; 16], 16).is_err());
+    assert!(pad.unpad(vec![17; 16], 16).is_err());
+    assert_eq!(pad.unpad(vec![16; 16], 16).unwrap(), vec![]);
+}
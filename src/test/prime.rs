@@ -0,0 +1,95 @@
+use crate::ecc::{self, is_prime_u64, next_prime_u64, Num};
+
+/// Bound of the exhaustive comparison with the sieve.
+const LIMIT: usize = 1_000_000;
+
+/// The largest 64-bit prime, $2^{64} - 59$.
+const LARGEST: u64 = u64::MAX - 58;
+
+/// Sieve of Eratosthenes: whether each number below `n` is prime.
+fn sieve(n: usize) -> Vec<bool> {
+    let mut prime = vec![true; n];
+    prime[0] = false;
+    prime[1] = false;
+    for i in (2..).take_while(|i| i * i < n) {
+        if prime[i] {
+            (i * i..n).step_by(i).for_each(|j| prime[j] = false);
+        }
+    }
+    prime
+}
+
+#[test]
+fn is_prime_u64_sieve() {
+    for (n, prime) in sieve(LIMIT).into_iter().enumerate() {
+        assert_eq!(is_prime_u64(n as u64), prime, "{n}");
+    }
+}
+
+#[test]
+fn is_prime_u64_boundaries() {
+    assert!(!is_prime_u64(0));
+    assert!(!is_prime_u64(1));
+    assert!(is_prime_u64(2));
+    assert!(!is_prime_u64(u64::MAX));
+    assert!(is_prime_u64(LARGEST));
+    assert!(is_prime_u64(u64::from(u32::MAX) - 4));
+    // 2^32 + 1 = 641 * 6700417 is the first composite Fermat number.
+    assert!(!is_prime_u64(u64::from(u32::MAX) + 2));
+    // A product of two primes just below 2^32.
+    assert!(!is_prime_u64(4_294_967_291 * 4_294_967_279));
+}
+
+/// Assert that strong pseudoprimes to the first few bases are caught by the
+/// rest of them.
+#[test]
+fn is_prime_u64_strong_pseudoprimes() {
+    let cases: [(u64, usize); 2] = [
+        // The smallest strong pseudoprime to bases 2, 3, 5 and 7.
+        (3_215_031_751, 4),
+        // The smallest strong pseudoprime to the first nine prime bases, 2 to 23.
+        (3_825_123_056_546_413_051, 9),
+    ];
+    let bases = [2, 3, 5, 7, 11, 13, 17, 19, 23, 29, 31, 37];
+    for (n, fooled) in cases {
+        assert!(ecc::miller_rabin(n, &bases[..fooled]), "{n}");
+        assert!(!is_prime_u64(n), "{n}");
+        assert!(!Num::from_le_words([n, 0, 0, 0]).is_probable_prime(), "{n}");
+    }
+}
+
+/// Assert that the two primality tests agree on 64-bit numbers.
+#[test]
+fn is_prime_u64_num() {
+    for n in (0..10_000).chain(LARGEST - 10_000..=u64::MAX) {
+        assert_eq!(
+            is_prime_u64(n),
+            Num::from_le_words([n, 0, 0, 0]).is_probable_prime(),
+            "{n}"
+        );
+    }
+}
+
+#[test]
+fn next_prime_u64_values() {
+    assert_eq!(next_prime_u64(0), Some(2));
+    assert_eq!(next_prime_u64(2), Some(2));
+    assert_eq!(next_prime_u64(3), Some(3));
+    assert_eq!(next_prime_u64(24), Some(29));
+    assert_eq!(next_prime_u64(1_000_000), Some(1_000_003));
+    assert_eq!(next_prime_u64(LARGEST - 1), Some(LARGEST));
+    assert_eq!(next_prime_u64(LARGEST), Some(LARGEST));
+    assert_eq!(next_prime_u64(LARGEST + 1), None);
+    assert_eq!(next_prime_u64(u64::MAX), None);
+
+    let primes = sieve(LIMIT);
+    let mut next = None;
+    for n in (0..LIMIT).rev() {
+        if primes[n] {
+            next = Some(n as u64);
+        }
+        if next.is_some() {
+            assert_eq!(next_prime_u64(n as u64), next, "{n}");
+        }
+    }
+}
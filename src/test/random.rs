@@ -1,6 +1,12 @@
 use {
-    super::fortuna::NoEntropy,
-    crate::{shuffle, uniform_random, util::CollectVec, Aes256, Fortuna, Sha256},
+    crate::{
+        shuffle,
+        testing::{rng, StreamRng},
+        uniform_random,
+        uniform_random_u64,
+        util::CollectVec,
+        Csprng,
+    },
     std::{collections::HashSet, ops::Range},
 };
 
@@ -8,34 +14,53 @@ use {
 /// range.
 #[test]
 fn random_within_range() {
-    let rng = Fortuna::new(NoEntropy, Aes256::default(), Sha256::default()).unwrap();
-    let mut iter = rng.into_iter();
-    test_range(&mut iter, 0..1);
-    test_range(&mut iter, 0..2);
-    test_range(&mut iter, 5..50);
+    let mut rng = rng();
+    test_range(&mut rng, 0..1);
+    test_range(&mut rng, 0..2);
+    test_range(&mut rng, 5..50);
 }
 
-/// Test that outputs generated by [`uniform_random`] are within the requested
-/// range.
+/// Assert that an empty range gives its start, without drawing any randomness.
 #[test]
-fn random_empty_range_returns_zero() {
-    let rng = Fortuna::new(NoEntropy, Aes256::default(), Sha256::default()).unwrap();
-    let mut iter = rng.into_iter();
-    let draw = uniform_random(&mut iter, 0..0);
+fn random_empty_range_returns_start() {
+    let mut rng = rng();
+    let draw = uniform_random(&mut rng, 0..0);
     assert_eq!(draw, 0);
+
+    let mut rng = StreamRng(Vec::new());
+    assert_eq!(uniform_random(&mut rng, 7..7), 7);
+    assert_eq!(uniform_random(&mut rng, u32::MAX..u32::MAX), u32::MAX);
+    assert_eq!(uniform_random_u64(&mut rng, 7..7), 7);
+    assert_eq!(uniform_random_u64(&mut rng, u64::MAX..u64::MAX), u64::MAX);
+}
+
+/// Assert that a reversed range panics instead of wrapping around to a huge
+/// range, also in release builds, where arithmetic overflow isn't checked.
+#[test]
+#[should_panic(expected = "reversed range")]
+#[allow(clippy::reversed_empty_ranges)]
+fn random_reversed_range() {
+    uniform_random(&mut rng(), 5..4);
+}
+
+/// Same as [`random_reversed_range`], for [`uniform_random_u64`].
+#[test]
+#[should_panic(expected = "reversed range")]
+#[allow(clippy::reversed_empty_ranges)]
+fn random_reversed_range_u64() {
+    uniform_random_u64(&mut rng(), 1..0);
 }
 
 /// Assert that shuffling a slice contains the exact same elements, but in a
 /// different order.
 #[test]
 fn random_shuffle() {
-    let rng = Fortuna::new(NoEntropy, Aes256::default(), Sha256::default()).unwrap();
-    let mut iter = rng.into_iter();
+    let mut rng = rng();
 
     let original = (0..100).collect_vec();
     let mut shuffled = original.clone();
 
-    shuffle(&mut iter, &mut shuffled);
+    shuffle(&mut rng, &mut shuffled);
 
     // The chance that the order is not changed after shuffling is negligible.
     assert_ne!(original, shuffled);
@@ -45,10 +70,74 @@ fn random_shuffle() {
     assert!(shuffled.iter().all(|x| original.contains(x)));
 }
 
-fn test_range(iter: &mut impl Iterator<Item = u8>, range: Range<u32>) {
+/// Assert that draws from a tiny range stay below its end, and are spread
+/// evenly over it.
+#[test]
+fn random_tiny_range_uniform() {
+    let mut rng = rng();
+    let mut counts = [0; 3];
+    for _ in 0..30000 {
+        let draw = uniform_random(&mut rng, 0..3);
+        assert!(draw < 3);
+        counts[usize::try_from(draw).unwrap()] += 1;
+    }
+    // Each count is 10000 on average, with a standard deviation of about 82.
+    assert!(
+        counts.iter().all(|&n| (9500..10500).contains(&n)),
+        "{counts:?}"
+    );
+
+    let mut counts = [0; 3];
+    for _ in 0..30000 {
+        let draw = uniform_random_u64(&mut rng, 10..13);
+        assert!((10..13).contains(&draw));
+        counts[usize::try_from(draw - 10).unwrap()] += 1;
+    }
+    assert!(
+        counts.iter().all(|&n| (9500..10500).contains(&n)),
+        "{counts:?}"
+    );
+}
+
+/// Regression test: a draw of `0xFFFFFFFF` used to be mapped to `range.end`,
+/// outside of the range. Now it's rejected whenever it falls into the uneven
+/// tail, and another number is drawn.
+#[test]
+fn random_max_draw() {
+    let mut rng = StreamRng(vec![0xff, 0xff, 0xff, 0xff, 2, 0, 0, 0]);
+    assert_eq!(uniform_random(&mut rng, 5..8), 7);
+    assert!(rng.0.is_empty());
+
+    // 2^32 is a multiple of 2, so no draws are rejected.
+    let mut rng = StreamRng(vec![0xff; 4]);
+    assert_eq!(uniform_random(&mut rng, 0..2), 1);
+
+    let mut rng = StreamRng([vec![0xff; 8], vec![1; 8]].concat());
+    assert_eq!(
+        uniform_random_u64(&mut rng, 0..3),
+        0x0101_0101_0101_0101 % 3
+    );
+}
+
+/// Assert that every position can keep its element when shuffling.
+#[test]
+fn random_shuffle_identity() {
+    let mut rng = rng();
+    let mut kept = [false; 3];
+    for _ in 0..100 {
+        let mut elems = [0, 1, 2];
+        shuffle(&mut rng, &mut elems);
+        for (i, &elem) in elems.iter().enumerate() {
+            kept[i] |= elem == i;
+        }
+    }
+    assert_eq!(kept, [true; 3]);
+}
+
+fn test_range(rng: &mut impl Csprng, range: Range<u32>) {
     let mut draws = HashSet::new();
     for _ in 0..100 {
-        let draw = uniform_random(iter, range.clone());
+        let draw = uniform_random(rng, range.clone());
         draws.insert(draw);
         assert!(range.contains(&draw));
     }
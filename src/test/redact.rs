@@ -0,0 +1,116 @@
+use crate::{
+    ecc::{KeyPair, Num, PrivateKey},
+    hex,
+    key_fingerprint,
+    Aes256,
+    Ecdsa,
+    Entropy,
+    Fortuna,
+    HealthTested,
+    SchnorrRandomness,
+    Secp256k1,
+    Sha256,
+    SignatureScheme,
+};
+
+const SECRET: Num = Num::from_le_words([
+    0x0123456789abcdef,
+    0xfedcba9876543210,
+    0x0f1e2d3c4b5a6978,
+    0x1122334455667788,
+]);
+
+/// Assert that the debug output hides the secret but shows its fingerprint.
+fn assert_redacted(debug: &str, secret: Num) {
+    let bytes = secret.to_be_bytes();
+    assert!(!debug.contains(&hex::encode(&bytes)), "{debug}");
+    assert!(!debug.contains(&format!("{secret:?}")), "{debug}");
+    assert!(
+        !debug.contains(&format!("{}", 0x0123456789abcdefu64)),
+        "{debug}"
+    );
+    assert!(
+        debug.contains(&hex::encode(&key_fingerprint(&bytes))),
+        "{debug}"
+    );
+}
+
+#[test]
+fn private_key() {
+    let key = PrivateKey::<Secp256k1>::new(SECRET).unwrap();
+    assert_redacted(&format!("{key:?}"), SECRET);
+    assert_redacted(&format!("{key:#?}"), SECRET);
+}
+
+/// A key pair prints its private key through the redacted impl.
+#[test]
+fn key_pair() {
+    let pair = KeyPair::new(PrivateKey::<Secp256k1>::new(SECRET).unwrap());
+    assert_redacted(&format!("{pair:?}"), SECRET);
+}
+
+#[test]
+fn schnorr_randomness() {
+    let randomness = SchnorrRandomness::<Secp256k1>::new(&Sha256::default(), SECRET, &[]).unwrap();
+    assert_redacted(&format!("{randomness:?}"), SECRET);
+}
+
+/// An ECDSA instance remembers its recent nonces, which are derived from the
+/// private key, and only shows how many there are.
+#[test]
+fn ecdsa_hides_recent_nonces() {
+    let mut ecdsa = Ecdsa::new(Secp256k1::default(), Sha256::default());
+    let key = PrivateKey::<Secp256k1>::new(SECRET).unwrap();
+    let sig = ecdsa.sign(key, b"msg");
+    let debug = format!("{ecdsa:?}");
+    assert!(debug.contains("recent: 1"), "{debug}");
+    assert!(!debug.contains(&format!("{:?}", sig.r())), "{debug}");
+    assert!(!debug.contains("RecentNonce"), "{debug}");
+}
+
+/// Equal secrets have equal fingerprints, so logs can still be correlated.
+#[test]
+fn fingerprint_is_stable() {
+    let a = PrivateKey::<Secp256k1>::new(SECRET).unwrap();
+    let b = PrivateKey::<Secp256k1>::new(SECRET).unwrap();
+    let c = PrivateKey::<Secp256k1>::new(Num::ONE).unwrap();
+    assert_eq!(format!("{a:?}"), format!("{b:?}"));
+    assert_ne!(format!("{a:?}"), format!("{c:?}"));
+}
+
+/// An entropy source whose debug output stands in for its internal state.
+#[derive(Debug)]
+struct Leaky([u8; 4]);
+
+impl Entropy for Leaky {
+    fn get(&mut self, buf: &mut [u8]) {
+        buf.fill(self.0[0]);
+    }
+}
+
+#[test]
+fn fortuna_hides_entropy() {
+    let fortuna = Fortuna::new(Leaky(*b"seed"), Aes256::default(), Sha256::default()).unwrap();
+    let debug = format!("{fortuna:?}");
+    assert!(debug.starts_with("Fortuna"), "{debug}");
+    assert!(!debug.contains("Leaky"), "{debug}");
+}
+
+#[test]
+fn health_tested_hides_samples() {
+    let mut source = HealthTested::new(Leaky([0xab; 4]));
+    source.get(&mut [0; 16]);
+    let debug = format!("{source:?}");
+    assert!(debug.contains("failures"), "{debug}");
+    assert!(!debug.contains("repetition: "), "{debug}");
+    assert!(!debug.contains("proportion: "), "{debug}");
+}
+
+#[cfg(feature = "insecure-reveal-secrets")]
+#[test]
+fn reveal_secret() {
+    let key = PrivateKey::<Secp256k1>::new(SECRET).unwrap();
+    assert_eq!(key.reveal_secret_for_debug(), SECRET);
+    let randomness = SchnorrRandomness::<Secp256k1>::new(&Sha256::default(), SECRET, &[]).unwrap();
+    assert_eq!(randomness.reveal_secret_for_debug(), SECRET);
+}
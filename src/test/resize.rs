@@ -0,0 +1,29 @@
+use crate::util::{truncate, widen};
+
+#[test]
+fn widen_appends_defaults() {
+    assert_eq!(widen::<_, 2, 4>([1u8, 2]), [1, 2, 0, 0]);
+    assert_eq!(widen::<_, 0, 2>([0u64; 0]), [0, 0]);
+    assert_eq!(widen::<_, 1, 4>([u64::MAX]), [u64::MAX, 0, 0, 0]);
+}
+
+#[test]
+fn truncate_drops_last() {
+    assert_eq!(truncate::<_, 4, 2>([1u8, 2, 3, 4]), [1, 2]);
+    assert_eq!(truncate::<_, 2, 0>([1u64, 2]), []);
+}
+
+#[test]
+fn resize_identity() {
+    let a = [5u32, 6, 7];
+    assert_eq!(widen::<_, 3, 3>(a), a);
+    assert_eq!(truncate::<_, 3, 3>(a), a);
+}
+
+/// Assert that widening then truncating gives back the original array.
+#[test]
+fn widen_truncate_roundtrip() {
+    let a = 0x0123_4567_89ab_cdefu64.to_le_bytes();
+    let wide: [u8; 32] = widen(a);
+    assert_eq!(truncate::<_, 32, 8>(wide), a);
+}
@@ -1,3 +1,17 @@
+mod adaptor;
+mod bip340;
+mod blind;
 mod curve;
+mod ecies;
+mod hpke;
+mod invalid_curve;
+mod kem;
+mod key_agg;
+mod keys;
+mod musig2;
 mod num;
+mod reference;
+mod registry;
+mod scalar;
 mod sign;
+mod transcript;
@@ -0,0 +1,97 @@
+use crate::{
+    ecc::{Curve, Num, Point},
+    testing::key,
+    Schnorr,
+    SchnorrSignature,
+    Secp256k1,
+    Sha256,
+    SignatureScheme,
+};
+
+/// Assert that publishing one adapted signature of an atomic swap lets the
+/// counterparty extract the secret and complete his own signature.
+#[test]
+fn adaptor_atomic_swap() {
+    let mut schnorr = schnorr();
+    let alice = key(2);
+    let bob = key(3);
+    let t = Num::from_le_words([12345, 0, 0, 0]);
+    let adaptor = t * Secp256k1::g();
+
+    // Alice knows t and pre-signs her transaction for T. Bob checks it and
+    // pre-signs his transaction for the same T.
+    let alice_pre = schnorr
+        .pre_sign(alice.clone(), b"alice pays bob", adaptor)
+        .unwrap();
+    assert!(schnorr
+        .verify_pre(alice.derive(), b"alice pays bob", adaptor, &alice_pre)
+        .is_ok());
+    let bob_pre = schnorr
+        .pre_sign(bob.clone(), b"bob pays alice", adaptor)
+        .unwrap();
+    assert!(schnorr
+        .verify_pre(bob.derive(), b"bob pays alice", adaptor, &bob_pre)
+        .is_ok());
+
+    // A pre-signature is not a signature.
+    let fake = SchnorrSignature::new(bob_pre.s(), bob_pre.e()).unwrap();
+    assert!(schnorr
+        .verify(bob.derive(), b"bob pays alice", &fake)
+        .is_err());
+
+    // Alice completes Bob's signature and publishes it.
+    let bob_sig = schnorr.adapt(bob_pre, t).unwrap();
+    assert!(schnorr
+        .verify(bob.derive(), b"bob pays alice", &bob_sig)
+        .is_ok());
+
+    // Bob learns t from the published signature and completes Alice's.
+    let extracted = schnorr.extract_secret(&bob_pre, &bob_sig).unwrap();
+    assert_eq!(extracted, t);
+    let alice_sig = schnorr.adapt(alice_pre, extracted).unwrap();
+    assert!(schnorr
+        .verify(alice.derive(), b"alice pays bob", &alice_sig)
+        .is_ok());
+}
+
+/// Assert that pre-signatures don't verify, adapt or give up their secret
+/// with a different adaptor point.
+#[test]
+fn adaptor_mismatched_point() {
+    let mut schnorr = schnorr();
+    let alice = key(2);
+    let t = Num::from_le_words([12345, 0, 0, 0]);
+    let other = Num::from_le_words([54321, 0, 0, 0]);
+    let adaptor = t * Secp256k1::g();
+    let other_adaptor = other * Secp256k1::g();
+
+    let pre = schnorr.pre_sign(alice.clone(), b"msg", adaptor).unwrap();
+    assert!(schnorr
+        .verify_pre(alice.derive(), b"msg", other_adaptor, &pre)
+        .is_err());
+    assert!(schnorr
+        .verify_pre(alice.derive(), b"other", adaptor, &pre)
+        .is_err());
+    assert!(schnorr
+        .verify_pre(key(3).derive(), b"msg", adaptor, &pre)
+        .is_err());
+    assert!(schnorr.adapt(pre, other).is_err());
+
+    // The signature adapted from another pre-signature doesn't reveal t.
+    let other_pre = schnorr
+        .pre_sign(alice.clone(), b"msg", other_adaptor)
+        .unwrap();
+    let other_sig = schnorr.adapt(other_pre, other).unwrap();
+    assert!(schnorr.extract_secret(&pre, &other_sig).is_err());
+    assert_eq!(
+        schnorr.extract_secret(&other_pre, &other_sig).unwrap(),
+        other
+    );
+
+    // The point at infinity hides nothing, so it is not an adaptor point.
+    assert!(schnorr.pre_sign(alice, b"msg", Point::infinity()).is_err());
+}
+
+fn schnorr() -> Schnorr<Secp256k1, Sha256> {
+    Schnorr::new(Secp256k1::default(), Sha256::default())
+}
@@ -0,0 +1,223 @@
+use {
+    crate::{
+        ecc::{Num, PrivateKey},
+        test::vectors::{load_vectors, Record, Vector, VectorError},
+        testing::rng,
+        Bip340Signature,
+        InvalidBatch,
+        SchnorrBip340,
+        SignatureScheme,
+        XOnlyPublicKey,
+    },
+    docext::docext,
+};
+
+/// A case from `tests/vectors/bip340.txt`. Verification-only cases have no
+/// secret key and no auxiliary randomness.
+struct Bip340Case {
+    index: u64,
+    secret_key: Option<[u8; 32]>,
+    public_key: [u8; 32],
+    aux_rand: Option<[u8; 32]>,
+    message: Vec<u8>,
+    signature: [u8; 64],
+    valid: bool,
+    comment: String,
+}
+
+impl Vector for Bip340Case {
+    fn parse(record: &mut Record) -> Result<Self, VectorError> {
+        let index = record.u64("index")?;
+        let secret_key = optional(record, "secret_key")?;
+        let public_key = record.array("public_key")?;
+        let aux_rand = optional(record, "aux_rand")?;
+        let message = record.hex("message")?;
+        let signature = record.array("signature")?;
+        let valid = match record.text("valid")?.as_str() {
+            "true" => true,
+            "false" => false,
+            _ => return Err(record.invalid("valid")),
+        };
+        let comment = record.text("comment")?;
+        Ok(Self {
+            index,
+            secret_key,
+            public_key,
+            aux_rand,
+            message,
+            signature,
+            valid,
+            comment,
+        })
+    }
+}
+
+/// Read a field which is either empty or 32 bytes of hex.
+fn optional(record: &mut Record, name: &str) -> Result<Option<[u8; 32]>, VectorError> {
+    let bytes = record.hex(name)?;
+    if bytes.is_empty() {
+        return Ok(None);
+    }
+    bytes.try_into().map(Some).map_err(|_| record.invalid(name))
+}
+
+/// Assert that [BIP-340 signatures](SchnorrBip340) match the official test
+/// vectors: signing gives the expected signature, and verification accepts
+/// exactly the valid ones. An invalid public key or signature encoding counts
+/// as a failed verification.
+#[test]
+fn bip340_vectors() {
+    let mut schnorr = SchnorrBip340::new();
+    for case in load_vectors::<Bip340Case>("bip340.txt") {
+        let name = format!("vector {} {}", case.index, case.comment);
+        if let (Some(secret_key), Some(aux)) = (case.secret_key, case.aux_rand) {
+            let key = PrivateKey::new(Num::from_be_bytes(secret_key)).unwrap();
+            assert_eq!(
+                XOnlyPublicKey::from(key.derive()).to_bytes(),
+                case.public_key,
+                "{name}"
+            );
+            let sig = schnorr.sign_with_aux(key, &case.message, aux);
+            assert_eq!(sig.to_bytes(), case.signature, "{name}");
+        }
+        let verified = XOnlyPublicKey::from_bytes(&case.public_key)
+            .map_err(|_| ())
+            .and_then(|key| {
+                let sig = Bip340Signature::from_bytes(&case.signature).map_err(|_| ())?;
+                schnorr.verify(key, &case.message, &sig).map_err(|_| ())
+            });
+        assert_eq!(verified.is_ok(), case.valid, "{name}");
+    }
+}
+
+/// Assert that keys with an odd y-coordinate sign correctly, and that a
+/// signature only verifies under the context and message it was made for.
+#[test]
+fn bip340_sign_verify() {
+    let mut schnorr = SchnorrBip340::new();
+    let mut odd = false;
+    for n in 1..=8 {
+        let key = PrivateKey::new(Num::from_le_words([n, 0, 0, 0])).unwrap();
+        odd |= key.derive().y().get_bit(0);
+        let pubkey = XOnlyPublicKey::from(key.derive());
+        assert_eq!(pubkey.public_key().x(), key.derive().x());
+        assert!(!pubkey.public_key().y().get_bit(0));
+
+        let sig = schnorr.sign_with_context(key.clone(), b"ctx", b"msg");
+        assert!(schnorr
+            .verify_with_context(pubkey, b"ctx", b"msg", &sig)
+            .is_ok());
+        assert!(schnorr.verify(pubkey, b"msg", &sig).is_err());
+        assert!(schnorr
+            .verify_with_context(pubkey, b"ctx", b"msh", &sig)
+            .is_err());
+
+        let randomized = schnorr.sign_randomized(key, b"ctx", b"msg", &mut rng());
+        assert_ne!(randomized, sig);
+        assert!(schnorr
+            .verify_with_context(pubkey, b"ctx", b"msg", &randomized)
+            .is_ok());
+    }
+    assert!(odd, "no key with odd y was tested");
+}
+
+/// Assert that [BIP-340 signatures](Bip340Signature) and
+/// [keys](XOnlyPublicKey) round-trip through their byte encodings.
+#[test]
+fn bip340_encoding() {
+    let mut schnorr = SchnorrBip340::new();
+    let key = PrivateKey::new(Num::from_le_words([7, 0, 0, 0])).unwrap();
+    let sig = schnorr.sign(key.clone(), b"msg");
+    assert_eq!(Bip340Signature::from_bytes(&sig.to_bytes()).unwrap(), sig);
+    let pubkey = XOnlyPublicKey::from(key.derive());
+    assert_eq!(
+        XOnlyPublicKey::from_bytes(&pubkey.to_bytes()).unwrap(),
+        pubkey
+    );
+}
+
+/// Assert that a batch of 20 valid signatures passes [batch
+/// verification](SchnorrBip340::verify_batch), together with the valid
+/// signatures from the BIP, and that corrupting the signature, the message or
+/// the key of any single item makes the batch fail and reports that item.
+#[test]
+fn bip340_batch() {
+    let mut schnorr = SchnorrBip340::new();
+    assert_eq!(schnorr.verify_batch(&[]), Ok(()));
+
+    let messages: Vec<_> = (0..20u64).map(u64::to_le_bytes).collect();
+    let mut items: Vec<_> = messages
+        .iter()
+        .zip(1..)
+        .map(|(msg, n)| {
+            let key = PrivateKey::new(Num::from_le_words([n * 0x9e37_79b9, n, 0, 0])).unwrap();
+            let sig = schnorr.sign(key.clone(), msg);
+            (XOnlyPublicKey::from(key.derive()), &msg[..], sig)
+        })
+        .collect();
+    assert!(schnorr.verify_batch(&items).is_ok());
+
+    let vectors = load_vectors::<Bip340Case>("bip340.txt");
+    let valid: Vec<_> = vectors
+        .iter()
+        .filter(|case| case.valid)
+        .map(|case| {
+            (
+                XOnlyPublicKey::from_bytes(&case.public_key).unwrap(),
+                &case.message[..],
+                Bip340Signature::from_bytes(&case.signature).unwrap(),
+            )
+        })
+        .collect();
+    assert!(valid.len() > 1);
+    assert!(schnorr.verify_batch(&valid).is_ok());
+    items.extend(valid);
+    assert!(schnorr.verify_batch(&items).is_ok());
+
+    let other = XOnlyPublicKey::from(PrivateKey::new(Num::SEVEN).unwrap().derive());
+    for i in 0..20 {
+        let (key, msg, sig) = items[i];
+        let mut bytes = sig.to_bytes();
+        bytes[63] ^= 1;
+        let corrupted = [
+            (key, msg, Bip340Signature::from_bytes(&bytes).unwrap()),
+            (key, &b"other"[..], sig),
+            (other, msg, sig),
+        ];
+        for item in corrupted {
+            items[i] = item;
+            assert_eq!(schnorr.verify_batch(&items), Err(InvalidBatch(vec![i])));
+        }
+        items[i] = (key, msg, sig);
+    }
+}
+
+/// Assert that [batch verification](SchnorrBip340::verify_batch) reports
+/// every invalid signature when there are several, including a signature whose
+/// $r$ isn't the x-coordinate of any point.
+#[docext]
+#[test]
+fn bip340_batch_several_invalid() {
+    let mut schnorr = SchnorrBip340::new();
+    let messages: Vec<_> = (0..9u64).map(u64::to_le_bytes).collect();
+    let mut items: Vec<_> = messages
+        .iter()
+        .zip(1..)
+        .map(|(msg, n)| {
+            let key = PrivateKey::new(Num::from_le_words([n, 0, 0, 0])).unwrap();
+            let sig = schnorr.sign(key.clone(), msg);
+            (XOnlyPublicKey::from(key.derive()), &msg[..], sig)
+        })
+        .collect();
+    items[2].1 = &b"other"[..];
+    items[3].1 = &b"other"[..];
+    items[7].1 = &b"other"[..];
+    // There is no point with x = 5 on secp256k1.
+    let mut bytes = items[5].2.to_bytes();
+    bytes[..32].copy_from_slice(&Num::from_le_words([5, 0, 0, 0]).to_be_bytes());
+    items[5].2 = Bip340Signature::from_bytes(&bytes).unwrap();
+    assert_eq!(
+        schnorr.verify_batch(&items),
+        Err(InvalidBatch(vec![2, 3, 5, 7]))
+    );
+}
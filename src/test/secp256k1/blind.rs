@@ -0,0 +1,145 @@
+use crate::{
+    ecc::{Curve, Group, Num, Point},
+    testing::{key, rng},
+    BlindSchnorr,
+    Schnorr,
+    Secp256k1,
+    Sha256,
+    SignatureScheme,
+    TooManySessions,
+};
+
+/// Assert that blind signatures verify as regular Schnorr signatures of the
+/// user's message, and of nothing else.
+#[test]
+fn blind_schnorr_valid() {
+    let mut blind = blind_schnorr();
+    let mut rng = rng();
+    let (key, other) = (key(5), key(6));
+
+    let session = blind.start(key.clone(), &mut rng).unwrap();
+    let (blinding, e) = blind
+        .blind(key.derive(), session.commitment(), b"coin", &mut rng)
+        .unwrap();
+    let s = session.respond(e).unwrap();
+    let sig = blinding.unblind(s).unwrap();
+
+    let mut schnorr = Schnorr::new(Secp256k1::default(), Sha256::default());
+    assert!(schnorr.verify(key.derive(), b"coin", &sig).is_ok());
+    assert!(schnorr.verify(key.derive(), b"other", &sig).is_err());
+    assert!(schnorr.verify(other.derive(), b"coin", &sig).is_err());
+}
+
+/// Assert that a wrong response from the signer is caught when unblinding.
+#[test]
+fn blind_schnorr_bad_response() {
+    let mut blind = blind_schnorr();
+    let mut rng = rng();
+    let key = key(5);
+
+    let session = blind.start(key.clone(), &mut rng).unwrap();
+    let (blinding, e) = blind
+        .blind(key.derive(), session.commitment(), b"coin", &mut rng)
+        .unwrap();
+    let s = session.respond(e).unwrap();
+    assert!(blinding.unblind(s.add(Num::ONE, Secp256k1::N)).is_err());
+
+    assert!(blind
+        .start(key.clone(), &mut rng)
+        .unwrap()
+        .respond(Num::ZERO)
+        .is_err());
+    assert!(blind
+        .blind(key.derive(), Point::infinity(), b"coin", &mut rng)
+        .is_err());
+}
+
+/// Assert that the signer's view of a session is unlinkable to the signature:
+/// every session is consistent with every signature, and the challenge which
+/// the signer sees shares no more bits with the final challenge than chance.
+#[test]
+fn blind_schnorr_unlinkable() {
+    let mut blind = blind_schnorr();
+    let mut rng = rng();
+    let key = key(5);
+    let pubkey = key.derive().point();
+
+    // The signer records (R, e, s) of each session, the user keeps (s', e').
+    let mut transcripts = Vec::new();
+    let mut sigs = Vec::new();
+    for _ in 0..8 {
+        let session = blind.start(key.clone(), &mut rng).unwrap();
+        let r = session.commitment();
+        let (blinding, e) = blind.blind(key.derive(), r, b"coin", &mut rng).unwrap();
+        let s = session.respond(e).unwrap();
+        transcripts.push((r, e, s));
+        sigs.push(blinding.unblind(s).unwrap());
+    }
+
+    for &(r, e, s) in &transcripts[..3] {
+        for sig in &sigs[..3] {
+            // The blinding factors which would turn this session into this
+            // signature.
+            let alpha = sig.s().sub(s, Secp256k1::N);
+            let beta = e.sub(sig.e(), Secp256k1::N);
+            let blinded = r
+                .op(alpha * Secp256k1::g())
+                .op(Secp256k1::N.sub(beta, Secp256k1::N) * pubkey);
+            let verified = sig.s() * Secp256k1::g() + sig.e() * pubkey;
+            assert_eq!(blinded, verified);
+        }
+    }
+
+    let same: u32 = transcripts
+        .iter()
+        .zip(&sigs)
+        .map(|(&(_, e, _), sig)| {
+            e.to_le_bytes()
+                .iter()
+                .zip(sig.e().to_le_bytes())
+                .map(|(a, b)| (!(a ^ b)).count_ones())
+                .sum::<u32>()
+        })
+        .sum();
+    // 8 * 256 bits, of which about half should match by chance.
+    assert!((896..=1152).contains(&same), "{same} matching bits");
+}
+
+/// Assert that the cap on concurrent sessions is enforced per key, and that
+/// responding to or dropping a session closes it.
+#[test]
+fn blind_schnorr_session_cap() {
+    let mut blind = blind_schnorr();
+    let mut rng = rng();
+    let (key1, key2) = (key(5), key(6));
+
+    let session = blind.start(key1.clone(), &mut rng).unwrap();
+    assert_eq!(blind.open_sessions(key1.derive()), 1);
+    assert_eq!(
+        blind.start(key1.clone(), &mut rng).unwrap_err(),
+        TooManySessions
+    );
+    let other = blind.start(key2.clone(), &mut rng).unwrap();
+    session.respond(Num::ONE).unwrap();
+    assert_eq!(blind.open_sessions(key1.derive()), 0);
+    let session = blind.start(key1.clone(), &mut rng).unwrap();
+    drop(session);
+    drop(other);
+    assert_eq!(blind.open_sessions(key2.derive()), 0);
+
+    let mut blind = blind_schnorr().with_max_sessions(3);
+    let sessions: Vec<_> = (0..3)
+        .map(|_| blind.start(key1.clone(), &mut rng).unwrap())
+        .collect();
+    assert_eq!(blind.open_sessions(key1.derive()), 3);
+    assert_eq!(
+        blind.start(key1.clone(), &mut rng).unwrap_err(),
+        TooManySessions
+    );
+    drop(sessions);
+    assert!(blind.start(key1, &mut rng).is_ok());
+}
+
+fn blind_schnorr() -> BlindSchnorr<Secp256k1, Sha256> {
+    BlindSchnorr::new(Secp256k1::default(), Sha256::default())
+}
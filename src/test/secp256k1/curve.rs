@@ -1,4 +1,26 @@
-use crate::ecc::{Curve, Num, Point, Secp256k1};
+use {
+    crate::{
+        ecc::{
+            hash_to_point,
+            mul_double,
+            Coordinates,
+            Curve,
+            CurveError,
+            Group,
+            Num,
+            Point,
+            Secp256k1,
+            Secp256r1,
+        },
+        testing::rng,
+        Ecdsa,
+        Sha256,
+    },
+    std::{
+        array,
+        collections::{BTreeSet, HashSet},
+    },
+};
 
 #[test]
 fn doubling() {
@@ -80,3 +102,277 @@ fn multiplication() {
         .unwrap()
     );
 }
+
+/// Assert that scalar multiplication, which runs in Jacobian coordinates,
+/// gives the same points as double-and-add with affine additions, including
+/// for the scalars which reach infinity along the way.
+#[test]
+fn jacobian() {
+    let g = Secp256k1::g();
+    let p = Num::SEVEN * g;
+    let scalars = [
+        Num::ZERO,
+        Num::ONE,
+        Num::TWO,
+        Num::from_le_words([0xdead_beef, 0, 0, 1 << 63]),
+        Secp256k1::N.sub(Num::ONE, Secp256k1::P),
+        Secp256k1::N,
+    ];
+    for n in scalars {
+        for a in [g, p, Point::infinity()] {
+            assert_eq!(a.scale(n), affine_scale(a, n));
+            assert_eq!(a.scale_ct(n), affine_scale(a, n));
+        }
+    }
+    assert_eq!(Secp256k1::N * g, Point::infinity());
+    assert_eq!(
+        Point::multi_scale(&[(Num::ONE, g), (Secp256k1::N.sub(Num::ONE, Secp256k1::P), g)]),
+        Point::infinity()
+    );
+    assert_eq!(
+        Point::multi_scale(&[(Num::SEVEN, g), (Num::TWO, p)]),
+        affine_scale(g, Num::from_le_words([21, 0, 0, 0]))
+    );
+}
+
+/// Assert that [fixed-base multiplication](Curve::mul_g) through the table
+/// gives the same points as multiplying the generator, for random scalars as
+/// well as for scalars whose windows are all zero or all fifteen.
+#[test]
+fn mul_g() {
+    let mut rng = rng().into_iter();
+    let scalars = (0..16)
+        .map(|_| Num::from_le_bytes(array::from_fn(|_| rng.next().unwrap())))
+        .chain([
+            Num::ZERO,
+            Num::ONE,
+            Num::from_le_words([u64::MAX; 4]),
+            Secp256k1::N,
+            Secp256k1::N.sub(Num::ONE, Secp256k1::P),
+        ]);
+    for n in scalars {
+        assert_eq!(Secp256k1::mul_g(n), n * Secp256k1::g());
+    }
+    assert_eq!(Anomalous::mul_g(Num::SEVEN), Num::SEVEN * Anomalous::g());
+}
+
+/// Assert that [Shamir's trick](mul_double) gives the same point as the two
+/// products added together, for random scalars and points, and when either
+/// side or the sum is the point at infinity.
+#[test]
+fn shamir() {
+    let mut rng = rng().into_iter();
+    let mut random = || Num::from_le_bytes(array::from_fn(|_| rng.next().unwrap()));
+    let g = Secp256k1::g();
+    for _ in 0..8 {
+        let (a, b) = (random(), random());
+        let (p, q) = (random() * g, random() * g);
+        assert_eq!(mul_double(a, p, b, q), a * p + b * q);
+    }
+    let (a, p) = (random(), random() * g);
+    let inf = Point::infinity();
+    assert_eq!(mul_double(a, p, Num::ZERO, g), a * p);
+    assert_eq!(mul_double(Num::ZERO, g, a, p), a * p);
+    assert_eq!(mul_double(a, inf, a, p), a * p);
+    assert_eq!(mul_double(a, p, a, p), Num::TWO * (a * p));
+    assert_eq!(mul_double(a, g, Secp256k1::N.sub(a, Secp256k1::N), g), inf);
+}
+
+/// Points are ordered by their compressed encoding: infinity first, then by
+/// the parity of $y$, then by $x$. The order is total, so sorting any
+/// permutation of the same points gives the same list.
+#[test]
+fn point_order() {
+    let g = Secp256k1::g();
+    let Coordinates::Finite(x, y) = g.coordinates() else {
+        unreachable!()
+    };
+    // The y-coordinate of G is even, so -G has an odd one.
+    let neg_g = Point::<Secp256k1>::new(x, Num::ZERO.sub(y, Secp256k1::P)).unwrap();
+    let g2 = g + g;
+    let g3 = g2 + g;
+    let inf = Point::infinity();
+
+    let points = [g3, neg_g, inf, g2, g];
+    let mut sorted = points;
+    sorted.sort();
+    assert_eq!(sorted[0], inf);
+    assert!(g < neg_g);
+    for (a, b) in sorted.iter().zip(&sorted[1..]) {
+        assert!(a < b);
+        let (Coordinates::Finite(xa, ya), Coordinates::Finite(xb, yb)) =
+            (a.coordinates(), b.coordinates())
+        else {
+            continue;
+        };
+        assert!((ya.get_bit(0), xa) < (yb.get_bit(0), xb));
+    }
+    for a in &points {
+        for b in &points {
+            assert_eq!(a.cmp(b), b.cmp(a).reverse());
+            assert_eq!(a.cmp(b).is_eq(), a == b);
+        }
+    }
+    for i in 0..points.len() {
+        let mut rotated = points;
+        rotated.rotate_left(i);
+        rotated.reverse();
+        rotated.sort();
+        assert_eq!(rotated, sorted);
+    }
+
+    // Equal points, however they were computed, collapse in sets.
+    let duplicates = [g, g2, g2 + inf, g3 + neg_g, inf, g + neg_g];
+    assert_eq!(duplicates.iter().collect::<HashSet<_>>().len(), 3);
+    assert_eq!(duplicates.iter().collect::<BTreeSet<_>>().len(), 3);
+}
+
+/// The secp256k1 parameters should pass [validation](Curve::validate).
+#[test]
+fn validate() {
+    assert_eq!(Secp256k1::validate(), Ok(()));
+}
+
+/// The constant generator should equal the point built through the checked
+/// constructor.
+#[test]
+fn constant_generator() {
+    let Coordinates::Finite(x, y) = Secp256k1::g().coordinates() else {
+        panic!("generator at infinity");
+    };
+    assert_eq!(Point::<Secp256k1>::new(x, y).unwrap(), Secp256k1::g());
+}
+
+/// Curves with mistyped parameters should fail [validation](Curve::validate).
+#[test]
+fn validate_broken() {
+    assert_eq!(TypoInN::validate(), Err(CurveError::CompositeGroupOrder));
+    assert_eq!(WrongN::validate(), Err(CurveError::WrongGeneratorOrder));
+    assert_eq!(Singular::validate(), Err(CurveError::Singular));
+    assert_eq!(Anomalous::validate(), Err(CurveError::Anomalous));
+}
+
+/// Debug builds should refuse to construct a signature scheme over a broken
+/// curve.
+#[test]
+#[should_panic(expected = "invalid curve parameters")]
+fn ecdsa_broken_curve() {
+    Ecdsa::new(WrongN, Sha256::default());
+}
+
+/// secp256k1 with a single mistyped digit in N.
+#[derive(Debug)]
+struct TypoInN;
+
+impl Curve for TypoInN {
+    const SIZE: usize = Secp256k1::SIZE;
+    const P: Num = Secp256k1::P;
+    const N: Num = Num::from_le_words([
+        0xBFD25E8CD0364141,
+        0xBAAEDCF6AF48A03B,
+        0xFFFFFFFFFFFFFFFE,
+        0xFFFFFFFFFFFFFFFF,
+    ]);
+    const A: Num = Secp256k1::A;
+    const B: Num = Secp256k1::B;
+
+    fn g() -> Point<Self> {
+        generator()
+    }
+}
+
+/// secp256k1 with N replaced by the next prime after it.
+#[derive(Debug)]
+struct WrongN;
+
+impl Curve for WrongN {
+    const SIZE: usize = Secp256k1::SIZE;
+    const P: Num = Secp256k1::P;
+    const N: Num = Num::from_le_words([
+        0xBFD25E8CD0364159,
+        0xBAAEDCE6AF48A03B,
+        0xFFFFFFFFFFFFFFFE,
+        0xFFFFFFFFFFFFFFFF,
+    ]);
+    const A: Num = Secp256k1::A;
+    const B: Num = Secp256k1::B;
+
+    fn g() -> Point<Self> {
+        generator()
+    }
+}
+
+/// The singular curve $y^2 = x^3$ over the secp256k1 field.
+#[derive(Debug)]
+struct Singular;
+
+impl Curve for Singular {
+    const SIZE: usize = Secp256k1::SIZE;
+    const P: Num = Secp256k1::P;
+    const N: Num = Secp256k1::N;
+    const A: Num = Num::ZERO;
+    const B: Num = Num::ZERO;
+
+    fn g() -> Point<Self> {
+        Point::new(Num::ONE, Num::ONE).unwrap()
+    }
+}
+
+/// secp256k1 with N mistakenly set to P.
+#[derive(Debug)]
+struct Anomalous;
+
+impl Curve for Anomalous {
+    const SIZE: usize = Secp256k1::SIZE;
+    const P: Num = Secp256k1::P;
+    const N: Num = Secp256k1::P;
+    const A: Num = Secp256k1::A;
+    const B: Num = Secp256k1::B;
+
+    fn g() -> Point<Self> {
+        generator()
+    }
+}
+
+/// The secp256k1 generator point on a curve with the same equation.
+fn generator<C: Curve>() -> Point<C> {
+    match Secp256k1::g().coordinates() {
+        Coordinates::Finite(x, y) => Point::new(x, y).unwrap(),
+        Coordinates::Infinity => unreachable!(),
+    }
+}
+
+/// Double-and-add with affine point additions, one inversion per addition.
+fn affine_scale(a: Point<Secp256k1>, n: Num) -> Point<Secp256k1> {
+    let (mut s, mut result) = (a, Point::infinity());
+    for i in 0..Num::BITS {
+        if n.get_bit(i) {
+            result += s;
+        }
+        s += s;
+    }
+    result
+}
+
+/// Assert that hashing to a point gives a finite point on the curve, the same
+/// one for the same data and different ones for different data or curves.
+#[test]
+fn hash_to_curve() {
+    let hash = Sha256::default();
+    let points = [&b""[..], b"a", b"b", b"abc"].map(|data| {
+        let point = hash_to_point::<Secp256k1, _, 32>(&hash, data);
+        let Coordinates::Finite(x, y) = point.coordinates() else {
+            panic!("hashed to infinity");
+        };
+        assert_eq!(Point::new(x, y).unwrap(), point);
+        assert!(!y.get_bit(0));
+        assert_eq!(hash_to_point(&hash, data), point);
+        point
+    });
+    assert_eq!(points.iter().collect::<BTreeSet<_>>().len(), points.len());
+    assert!(!points.contains(&Secp256k1::g()));
+
+    let other = hash_to_point::<Secp256r1, _, 32>(&hash, b"a");
+    assert!(matches!(other.coordinates(), Coordinates::Finite(..)));
+    assert_ne!(other.coordinates(), points[1].coordinates());
+}
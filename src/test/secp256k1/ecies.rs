@@ -0,0 +1,123 @@
+use crate::{
+    ecc::{Num, PrivateKey},
+    testing::{rng, StreamRng},
+    Aes128,
+    Ecies,
+    EciesCiphertext,
+    EciesError,
+    Secp256k1,
+    Sha256,
+};
+
+type Ciphertext = EciesCiphertext<Secp256k1, 16, 32>;
+
+/// Assert that encrypted messages of various lengths decrypt to the original
+/// plaintext.
+#[test]
+fn ecies_round_trip() {
+    let mut ecies = Ecies::<_, _, Aes128>::new(Secp256k1::default(), Sha256::default());
+    let mut rng = rng();
+    let key = recipient();
+
+    for len in [0, 1, 15, 16, 17, 100] {
+        let plaintext = (0..len).map(|i| i as u8).collect::<Vec<_>>();
+        let ciphertext = ecies.encrypt(key.derive(), &plaintext, &mut rng);
+        assert_eq!(
+            ecies.decrypt(key.clone(), &ciphertext),
+            Ok(plaintext.clone())
+        );
+
+        let ciphertext = Ciphertext::from_bytes(&ciphertext.to_bytes()).unwrap();
+        assert_eq!(ecies.decrypt(key.clone(), &ciphertext), Ok(plaintext));
+    }
+}
+
+/// Assert that modifying any component of the ciphertext, or decrypting with
+/// the wrong private key, is detected.
+#[test]
+fn ecies_tampered() {
+    let mut ecies = Ecies::<_, _, Aes128>::new(Secp256k1::default(), Sha256::default());
+    let mut rng = rng();
+    let key = recipient();
+    let bytes = ecies
+        .encrypt(key.derive(), b"attack at dawn", &mut rng)
+        .to_bytes();
+
+    let other = PrivateKey::new(Num::from_be_bytes([0x22; 32])).unwrap();
+    let ciphertext = Ciphertext::from_bytes(&bytes).unwrap();
+    assert_eq!(ecies.decrypt(other, &ciphertext), Err(EciesError::Tampered));
+
+    // A different, valid ephemeral public key.
+    let other_bytes = ecies.encrypt(key.derive(), b"", &mut rng).to_bytes();
+    let mut tampered = bytes.clone();
+    tampered[1..66].copy_from_slice(&other_bytes[1..66]);
+    let ciphertext = Ciphertext::from_bytes(&tampered).unwrap();
+    assert_eq!(
+        ecies.decrypt(key.clone(), &ciphertext),
+        Err(EciesError::Tampered)
+    );
+
+    // An ephemeral public key which is not on the curve.
+    let mut tampered = bytes.clone();
+    tampered[65] ^= 1;
+    assert_eq!(
+        Ciphertext::from_bytes(&tampered).unwrap_err(),
+        EciesError::Malformed
+    );
+
+    // The IV, the ciphertext and the tag.
+    for i in [66, 81, 86, 101, bytes.len() - 1] {
+        let mut tampered = bytes.clone();
+        tampered[i] ^= 1;
+        let ciphertext = Ciphertext::from_bytes(&tampered).unwrap();
+        assert_eq!(
+            ecies.decrypt(key.clone(), &ciphertext),
+            Err(EciesError::Tampered),
+            "byte {i}"
+        );
+    }
+
+    // Truncated ciphertexts, and a ciphertext length which doesn't match.
+    assert_eq!(
+        Ciphertext::from_bytes(&bytes[..bytes.len() - 1]).unwrap_err(),
+        EciesError::Malformed
+    );
+    let mut tampered = bytes.clone();
+    tampered[85] ^= 1;
+    assert_eq!(
+        Ciphertext::from_bytes(&tampered).unwrap_err(),
+        EciesError::Malformed
+    );
+}
+
+/// Pin the exact output for a fixed ephemeral key and IV, so that the key
+/// derivation and the layout can't silently change. The vector was generated
+/// independently with the Python `cryptography` package.
+#[test]
+fn ecies_vector() {
+    let mut ecies = Ecies::<_, _, Aes128>::new(Secp256k1::default(), Sha256::default());
+    // The ephemeral key is read from the first 32 bytes as a little-endian
+    // number, and the IV from the next 16 bytes.
+    let mut rng = StreamRng((1..=48).collect());
+    let ciphertext = ecies.encrypt(recipient().derive(), b"attack at dawn", &mut rng);
+    let expected = [
+        0x01, 0x04, 0x84, 0xbb, 0x07, 0x71, 0x42, 0xc3, 0x01, 0xd4, 0x71, 0xa3, 0x3a, 0x99, 0x5b,
+        0x22, 0x09, 0xdb, 0xe3, 0x78, 0x89, 0xd0, 0x1b, 0xe0, 0x31, 0xe6, 0xb0, 0x9d, 0xdc, 0x65,
+        0x73, 0x1b, 0x19, 0x62, 0xf2, 0x4d, 0x6f, 0x7d, 0xaa, 0x43, 0x01, 0x7d, 0x27, 0x57, 0x5f,
+        0x25, 0x4c, 0x4f, 0xcc, 0x74, 0x96, 0x44, 0x75, 0x6c, 0x3f, 0x91, 0xa1, 0x50, 0x58, 0xff,
+        0xdb, 0x34, 0xf4, 0x66, 0x15, 0xe6, 0x21, 0x22, 0x23, 0x24, 0x25, 0x26, 0x27, 0x28, 0x29,
+        0x2a, 0x2b, 0x2c, 0x2d, 0x2e, 0x2f, 0x30, 0x00, 0x00, 0x00, 0x10, 0xa3, 0xc2, 0x6c, 0xf2,
+        0xe4, 0xd3, 0x4b, 0xe6, 0xfa, 0xad, 0x27, 0x47, 0x8d, 0x93, 0x69, 0x2f, 0x7d, 0xe8, 0x2d,
+        0x16, 0x3c, 0xa2, 0x7f, 0x01, 0xec, 0x07, 0xc5, 0x6f, 0x5f, 0x96, 0x9c, 0x1c, 0x0e, 0x1e,
+        0xdf, 0x0d, 0x10, 0x91, 0x99, 0x92, 0x02, 0xb5, 0x67, 0x4a, 0x41, 0x1d, 0x80, 0x54,
+    ];
+    assert_eq!(ciphertext.to_bytes(), expected);
+    assert_eq!(
+        ecies.decrypt(recipient(), &Ciphertext::from_bytes(&expected).unwrap()),
+        Ok(b"attack at dawn".to_vec())
+    );
+}
+
+fn recipient() -> PrivateKey<Secp256k1> {
+    PrivateKey::new(Num::from_be_bytes([0x11; 32])).unwrap()
+}
@@ -0,0 +1,177 @@
+use {
+    super::kem::recipient,
+    crate::{
+        ecc::{Num, PrivateKey},
+        hex,
+        hpke_open,
+        hpke_seal,
+        testing::{rng, StreamRng},
+        HpkeContext,
+        HpkeError,
+        Secp256k1,
+    },
+};
+
+const INFO: &[u8] = b"literate-crypto test";
+
+/// Two messages and an exported secret for the KEM fixture, computed with an
+/// independent implementation of the key schedule and the AEAD in Python.
+#[test]
+fn hpke_fixture() {
+    let key = recipient();
+    let (enc, mut sender) =
+        HpkeContext::sender(&key.derive(), INFO, &mut StreamRng((1..=32).collect()));
+    let plaintext = b"attack at dawn, bring the whole army";
+    let first = sender.seal(b"header", plaintext).unwrap();
+    let second = sender.seal(b"", b"second").unwrap();
+    assert_eq!(
+        hex::encode(&first),
+        "53e9af27b7eb1b31b00aa6d3292f0f3bed04978d5d576dbd460052ec06eac0a1f900a33417c949daab949238\
+         396b46aabea26406b304ac53799fda88ed400c53ff28fc0b"
+    );
+    assert_eq!(
+        hex::encode(&second),
+        "6b92289336c5ff6d1d144d2cce2f1f103d0596acb00b73b3d7d9e9ea7b374047f74aa2486d6d"
+    );
+    assert_eq!(
+        hex::encode(&sender.export(b"channel binding", 32)),
+        "ad900fc15f63a6c4dc45b2c7a9b212f2cd8078a947f61dc9d967804f9d0802d2"
+    );
+
+    let mut receiver = HpkeContext::receiver(&key, &enc, INFO).unwrap();
+    assert_eq!(receiver.open(b"header", &first).unwrap(), plaintext);
+    assert_eq!(receiver.open(b"", &second).unwrap(), b"second");
+    assert_eq!(receiver.seq(), 2);
+}
+
+#[test]
+fn hpke_round_trip() {
+    let key = recipient();
+    let mut rng = rng();
+    for len in [0, 1, 15, 16, 17, 100] {
+        let plaintext: Vec<u8> = (0..len).collect();
+        let (enc, ciphertext) =
+            hpke_seal(&key.derive(), INFO, b"aad", &plaintext, &mut rng).unwrap();
+        assert_eq!(ciphertext.len(), len as usize + 32);
+        assert_eq!(
+            hpke_open(&key, &enc, INFO, b"aad", &ciphertext),
+            Ok(plaintext)
+        );
+    }
+}
+
+#[test]
+fn hpke_mismatch() {
+    let key = recipient();
+    let (enc, ciphertext) =
+        hpke_seal(&key.derive(), INFO, b"aad", b"plaintext", &mut rng()).unwrap();
+
+    assert_eq!(
+        hpke_open(&key, &enc, INFO, b"other", &ciphertext),
+        Err(HpkeError::Open)
+    );
+    assert_eq!(
+        hpke_open(&key, &enc, b"other", b"aad", &ciphertext),
+        Err(HpkeError::Open)
+    );
+    let wrong = PrivateKey::<Secp256k1>::new(Num::TWO).unwrap();
+    assert_eq!(
+        hpke_open(&wrong, &enc, INFO, b"aad", &ciphertext),
+        Err(HpkeError::Open)
+    );
+
+    // Flipping a bit of the ciphertext or the tag.
+    for i in [0, 8, ciphertext.len() - 32, ciphertext.len() - 1] {
+        let mut corrupted = ciphertext.clone();
+        corrupted[i] ^= 0x80;
+        assert_eq!(
+            hpke_open(&key, &enc, INFO, b"aad", &corrupted),
+            Err(HpkeError::Open)
+        );
+    }
+    for len in [31, ciphertext.len() - 1] {
+        assert_eq!(
+            hpke_open(&key, &enc, INFO, b"aad", &ciphertext[..len]),
+            Err(HpkeError::Open)
+        );
+    }
+
+    let mut corrupted = enc;
+    corrupted[1] ^= 1;
+    assert_eq!(
+        hpke_open(&key, &corrupted, INFO, b"aad", &ciphertext),
+        Err(HpkeError::Decaps)
+    );
+}
+
+/// The additional data and the ciphertext can't trade bytes.
+#[test]
+fn hpke_aad_boundary() {
+    let key = recipient();
+    let (enc, mut sender) = HpkeContext::sender(&key.derive(), INFO, &mut rng());
+    let ciphertext = sender.seal(b"ab", b"cd").unwrap();
+    let mut shifted = vec![b'b'];
+    shifted.extend(&ciphertext);
+    let mut receiver = HpkeContext::receiver(&key, &enc, INFO).unwrap();
+    assert_eq!(receiver.open(b"a", &shifted), Err(HpkeError::Open));
+    assert_eq!(receiver.open(b"ab", &ciphertext).unwrap(), b"cd");
+}
+
+/// Messages open in order, and a failed open doesn't advance the sequence.
+#[test]
+fn hpke_sequence() {
+    let key = recipient();
+    let (enc, mut sender) = HpkeContext::sender(&key.derive(), INFO, &mut rng());
+    let messages: Vec<_> = (0..3u8)
+        .map(|i| sender.seal(b"", &[i; 20]).unwrap())
+        .collect();
+    assert_eq!(sender.seq(), 3);
+
+    // The same plaintext seals differently under each nonce.
+    assert_ne!(messages[0][1..], messages[1][1..]);
+
+    let mut receiver = HpkeContext::receiver(&key, &enc, INFO).unwrap();
+    assert_eq!(receiver.open(b"", &messages[1]), Err(HpkeError::Open));
+    assert_eq!(receiver.seq(), 0);
+    for (i, ciphertext) in messages.iter().enumerate() {
+        assert_eq!(receiver.open(b"", ciphertext).unwrap(), [i as u8; 20]);
+    }
+    assert_eq!(receiver.open(b"", &messages[2]), Err(HpkeError::Open));
+}
+
+#[test]
+fn hpke_export() {
+    let key = recipient();
+    let mut rng = rng();
+    let (enc, mut sender) = HpkeContext::sender(&key.derive(), INFO, &mut rng);
+    let receiver = HpkeContext::receiver(&key, &enc, INFO).unwrap();
+
+    let secret = sender.export(b"context", 64);
+    assert_eq!(secret.len(), 64);
+    assert_eq!(receiver.export(b"context", 64), secret);
+    // Sealing messages doesn't change the exported secrets.
+    sender.seal(b"", b"message").unwrap();
+    assert_eq!(sender.export(b"context", 64), secret);
+    // The length is part of the derivation, so a shorter export is not a
+    // prefix of a longer one.
+    assert_ne!(sender.export(b"context", 10), secret[..10]);
+    assert_ne!(sender.export(b"other", 64), secret);
+    assert!(sender.export(b"context", 0).is_empty());
+
+    // Another encapsulation to the same key exports something else.
+    let (_, other) = HpkeContext::sender(&key.derive(), INFO, &mut rng);
+    assert_ne!(other.export(b"context", 64), secret);
+}
+
+#[test]
+#[should_panic(expected = "exported secret too long")]
+fn hpke_export_too_long() {
+    let (_, sender) = HpkeContext::sender(&recipient().derive(), INFO, &mut rng());
+    sender.export(b"context", 255 * 32 + 1);
+}
+
+#[test]
+fn hpke_context_debug() {
+    let (_, sender) = HpkeContext::sender(&recipient().derive(), INFO, &mut rng());
+    assert_eq!(format!("{sender:?}"), "HpkeContext { seq: 0, .. }");
+}
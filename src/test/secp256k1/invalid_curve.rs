@@ -0,0 +1,189 @@
+//! Every entry point accepting external point data must reject points which
+//! are not on the curve, the point at infinity, and points outside of the
+//! subgroup generated by the generator point.
+
+use {
+    crate::{
+        ecc::{Coordinates, Curve, Num, Point, PrivateKey, PublicKey},
+        testing::StreamRng,
+        Aes128,
+        Ecies,
+        EciesCiphertext,
+        EciesError,
+        Hash,
+        InvalidSchnorrRandomness,
+        SchnorrRandomness,
+        Secp256k1,
+        Sha256,
+    },
+    std::iter,
+};
+
+/// The point $(4, y)$ on the curve $y^2 = x^3 + 6$, which differs from
+/// secp256k1 only in $b$.
+fn wrong_b() -> (Num, Num) {
+    (
+        Num::from_le_words([4, 0, 0, 0]),
+        Num::from_le_words([
+            0x894c0737ec98ca52,
+            0xb6eac9b23f286237,
+            0x23a3bd889d6300f9,
+            0x45da53745bd84570,
+        ]),
+    )
+}
+
+/// Points on a curve with a different $b$ must be rejected.
+#[test]
+fn invalid_curve_point() {
+    let (x, y) = wrong_b();
+    assert!(Point::<Secp256k1>::new(x, y).is_err());
+
+    // The point (1, y) is on secp256k1, but must not be accepted as (1 + P, y).
+    let y = Num::from_le_words([
+        0xbc750d587e76a7ee,
+        0x264ca8d2587fdd6f,
+        0x63db68605822fb14,
+        0x4218f20ae6c646b3,
+    ]);
+    assert!(Point::<Secp256k1>::new(Num::ONE, y).is_ok());
+    let unreduced = Num::from_le_words([
+        0xfffffffefffffc30,
+        0xffffffffffffffff,
+        0xffffffffffffffff,
+        0xffffffffffffffff,
+    ]);
+    assert!(Point::<Secp256k1>::new(unreduced, y).is_err());
+
+    let (gx, gy) = coordinates(Secp256k1::g());
+
+    assert_eq!(
+        EciesCiphertext::<Secp256k1, 16, 32>::from_bytes(&encode(x, y)).unwrap_err(),
+        EciesError::Malformed
+    );
+    assert_eq!(
+        EciesCiphertext::<Secp256k1, 16, 32>::from_bytes(&encode(gx, gy)).map(|_| ()),
+        Ok(())
+    );
+}
+
+/// The point at infinity must be rejected.
+#[test]
+fn infinity_point() {
+    assert!(PublicKey::<Secp256k1>::new(Point::infinity()).is_err());
+
+    // SEC1 encodes infinity as a single zero byte.
+    let mut bytes = vec![EciesCiphertext::<Secp256k1, 16, 32>::VERSION, 0];
+    bytes.resize(2 + 64 + 16 + 4 + 32, 0);
+    assert_eq!(
+        EciesCiphertext::<Secp256k1, 16, 32>::from_bytes(&bytes).unwrap_err(),
+        EciesError::Malformed
+    );
+
+    let hash = Sha256::default();
+    let infinity = Point::<Secp256k1>::infinity();
+    assert_eq!(
+        SchnorrRandomness::new(&hash, Num::ONE, &[(infinity, hash.hash(&[]))]).unwrap_err(),
+        InvalidSchnorrRandomness::InvalidPoint(0)
+    );
+}
+
+/// Points of small order, and points with a small-order component, must be
+/// rejected on a curve with a cofactor.
+#[test]
+fn small_subgroup_point() {
+    assert_eq!(Cofactor::validate(), Ok(()));
+    let g = Cofactor::g();
+    let t = small_order();
+    assert_eq!(Num::TWO * t, Point::infinity());
+
+    assert!(g.in_subgroup());
+    assert!(!t.in_subgroup());
+    assert!(!(g + t).in_subgroup());
+    assert!(PublicKey::new(g).is_ok());
+    assert!(PublicKey::new(t).is_err());
+    assert!(PublicKey::new(g + t).is_err());
+
+    let (x, y) = coordinates(g + t);
+    assert_eq!(
+        EciesCiphertext::<Cofactor, 16, 32>::from_bytes(&encode(x, y)).unwrap_err(),
+        EciesError::Malformed
+    );
+    let (x, y) = coordinates(g);
+    assert!(EciesCiphertext::<Cofactor, 16, 32>::from_bytes(&encode(x, y)).is_ok());
+
+    let hash = Sha256::default();
+    let (local, other) = (Num::TWO, Num::THREE);
+    let (point, commitment) = SchnorrRandomness::<Cofactor>::commit(&hash, other);
+    assert!(SchnorrRandomness::new(&hash, local, &[(point, commitment)]).is_ok());
+    let (x, y) = coordinates(point + t);
+    let commitment = hash.hash(&[x.to_le_bytes(), y.to_le_bytes()].concat());
+    assert_eq!(
+        SchnorrRandomness::new(&hash, local, &[(point + t, commitment)]).unwrap_err(),
+        InvalidSchnorrRandomness::InvalidPoint(0)
+    );
+}
+
+/// Decrypting with ECIES never multiplies the private key by an invalid point,
+/// since the ephemeral key is rejected when the ciphertext is decoded.
+#[test]
+fn ecies_rejects_invalid_ephemeral() {
+    let mut ecies = Ecies::<_, _, Aes128>::new(Cofactor, Sha256::default());
+    let mut rng = StreamRng(iter::once(5).chain([0; 47]).collect());
+    let key = PrivateKey::<Cofactor>::new(Num::SEVEN).unwrap();
+    let bytes = ecies.encrypt(key.derive(), b"message", &mut rng).to_bytes();
+    let ciphertext = EciesCiphertext::from_bytes(&bytes).unwrap();
+    assert_eq!(ecies.decrypt(key, &ciphertext), Ok(b"message".to_vec()));
+
+    let (x, y) = coordinates(ciphertext.ephemeral().point() + small_order());
+    let mut tampered = encode(x, y)[..66].to_vec();
+    tampered.extend(&bytes[66..]);
+    assert_eq!(
+        EciesCiphertext::<Cofactor, 16, 32>::from_bytes(&tampered).unwrap_err(),
+        EciesError::Malformed
+    );
+}
+
+/// A toy curve $y^2 = x^3 + x + 8$ over $\mathbb{F}_{1009}$ with $1004 = 4
+/// \cdot 251$ points.
+#[derive(Debug)]
+pub(super) struct Cofactor;
+
+impl Curve for Cofactor {
+    const SIZE: usize = 2;
+    const P: Num = Num::from_le_words([1009, 0, 0, 0]);
+    const N: Num = Num::from_le_words([251, 0, 0, 0]);
+    const COFACTOR: Num = Num::from_le_words([4, 0, 0, 0]);
+    const A: Num = Num::ONE;
+    const B: Num = Num::from_le_words([8, 0, 0, 0]);
+
+    fn g() -> Point<Self> {
+        Point::new(
+            Num::from_le_words([506, 0, 0, 0]),
+            Num::from_le_words([72, 0, 0, 0]),
+        )
+        .unwrap()
+    }
+}
+
+/// A point of order two on the toy curve.
+fn small_order() -> Point<Cofactor> {
+    Point::new(Num::from_le_words([513, 0, 0, 0]), Num::ZERO).unwrap()
+}
+
+fn coordinates<C: Curve>(p: Point<C>) -> (Num, Num) {
+    match p.coordinates() {
+        Coordinates::Finite(x, y) => (x, y),
+        Coordinates::Infinity => panic!("point at infinity"),
+    }
+}
+
+/// Encode an ECIES ciphertext with the given ephemeral key and an empty
+/// message.
+fn encode(x: Num, y: Num) -> Vec<u8> {
+    let mut bytes = vec![EciesCiphertext::<Secp256k1, 16, 32>::VERSION, 0x04];
+    bytes.extend(x.to_be_bytes());
+    bytes.extend(y.to_be_bytes());
+    bytes.resize(bytes.len() + 16 + 4 + 32, 0);
+    bytes
+}
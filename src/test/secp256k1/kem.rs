@@ -0,0 +1,114 @@
+use crate::{
+    ecc::{Group, Num, PrivateKey, PublicKey},
+    hex,
+    testing::{rng, StreamRng},
+    DecapsError,
+    EciesKem,
+    Kem,
+    Secp256k1,
+    Sha256,
+};
+
+/// The recipient key, SHA-256("recipient") read as a big-endian number.
+pub(super) fn recipient() -> PrivateKey<Secp256k1> {
+    PrivateKey::new(Num::from_be_bytes(
+        hex::decode("665d0698dbc8fb95afc25c3a4d9cf280d87a585b7999243ca6008fd03258975f")
+            .unwrap()
+            .try_into()
+            .unwrap(),
+    ))
+    .unwrap()
+}
+
+/// The ciphertext and the shared secret for the ephemeral key read
+/// little-endian from the bytes 1 to 32, computed with an independent
+/// implementation of DHKEM on top of the `cryptography` Python library.
+#[test]
+fn kem_fixture() {
+    let mut kem = EciesKem::secp256k1();
+    let key = recipient();
+    let (enc, secret) = kem.encapsulate(&key.derive(), &mut StreamRng((1..=32).collect()));
+    assert_eq!(
+        hex::encode(&enc),
+        "0484bb077142c301d471a33a995b2209dbe37889d01be031e6b09ddc65731b1962f24d6f7daa43017d27575f\
+         254c4fcc749644756c3f91a15058ffdb34f46615e6"
+    );
+    assert_eq!(
+        hex::encode(secret.as_bytes()),
+        "c38fecbe0bba600c74f2525cc4e6d6d05a9f37255e3eb0b96b030a1612314e15"
+    );
+    assert_eq!(kem.decapsulate(&key, &enc), Ok(secret));
+}
+
+#[test]
+fn kem_round_trip() {
+    let mut kem = EciesKem::secp256k1();
+    let mut rng = rng();
+    let key = recipient();
+
+    let (enc1, secret1) = kem.encapsulate(&key.derive(), &mut rng);
+    let (enc2, secret2) = kem.encapsulate(&key.derive(), &mut rng);
+    assert_eq!(kem.decapsulate(&key, &enc1), Ok(secret1.clone()));
+    assert_eq!(kem.decapsulate(&key, &enc2), Ok(secret2.clone()));
+
+    // Every encapsulation to the same key gives a fresh secret.
+    assert_ne!(enc1, enc2);
+    assert_ne!(secret1, secret2);
+    assert_eq!(secret1.as_bytes().len(), 32);
+}
+
+/// A corrupted ciphertext is either rejected, or decapsulates to a different
+/// secret.
+#[test]
+fn kem_corrupted() {
+    let mut kem = EciesKem::secp256k1();
+    let key = recipient();
+    let (enc, secret) = kem.encapsulate(&key.derive(), &mut rng());
+
+    // The prefix of the point encoding.
+    let mut corrupted = enc;
+    corrupted[0] = 0x02;
+    assert_eq!(kem.decapsulate(&key, &corrupted), Err(DecapsError));
+
+    // Changing a coordinate moves the point off the curve.
+    for i in [1, 32, 33, 64] {
+        let mut corrupted = enc;
+        corrupted[i] ^= 1;
+        assert_eq!(kem.decapsulate(&key, &corrupted), Err(DecapsError));
+    }
+
+    // The negated ephemeral key is a valid point, and the shared point has the
+    // same x-coordinate. The secret is still different, because the ciphertext
+    // is bound into the derivation.
+    let ephemeral = PublicKey::<Secp256k1>::from_uncompressed(&enc).unwrap();
+    let negated = PublicKey::new(ephemeral.point().inverse()).unwrap();
+    let negated = negated.to_uncompressed();
+    assert_ne!(negated, enc);
+    assert_ne!(kem.decapsulate(&key, &negated), Ok(secret.clone()));
+
+    // The wrong private key.
+    let wrong = PrivateKey::<Secp256k1>::new(Num::TWO).unwrap();
+    assert_ne!(kem.decapsulate(&wrong, &enc), Ok(secret));
+}
+
+/// Different KEM identifiers derive unrelated secrets from the same exchange.
+#[test]
+fn kem_id() {
+    let key = recipient();
+    let mut kem = EciesKem::secp256k1();
+    let mut other = EciesKem::new(Secp256k1::default(), Sha256::default(), 0x0010);
+    assert_eq!(kem.id(), 0x0016);
+
+    let (enc, secret) = kem.encapsulate(&key.derive(), &mut rng());
+    let (other_enc, other_secret) = other.encapsulate(&key.derive(), &mut rng());
+    assert_eq!(enc, other_enc);
+    assert_ne!(secret, other_secret);
+}
+
+#[test]
+fn shared_secret_debug() {
+    let (_, secret) = EciesKem::secp256k1().encapsulate(&recipient().derive(), &mut rng());
+    let debug = format!("{secret:?}");
+    assert!(debug.starts_with("SharedSecret("));
+    assert!(!debug.contains(&hex::encode(&secret.as_bytes()[..4])));
+}
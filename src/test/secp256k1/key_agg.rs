@@ -0,0 +1,193 @@
+//! Tests for the [key aggregation](KeyAggContext) of Schnorr multisigs.
+
+use {
+    crate::{
+        ecc::{Num, PrivateKey, PublicKey},
+        hex,
+        testing::rng,
+        util::CollectVec,
+        Hash,
+        InvalidKeyAgg,
+        InvalidRound,
+        KeyAggContext,
+        MuSig2,
+        MultiSchnorr,
+        MultisigScheme,
+        SchnorrRandomness,
+        SchnorrSignature,
+        Secp256k1,
+        Sha256,
+    },
+    std::{cell::Cell, rc::Rc},
+};
+
+/// The combined pubkey and the multisig $(s, e)$ of `msg` by 2 and 5 signers,
+/// as computed before the key aggregation was cached.
+#[docext::docext]
+const REFERENCE: [(u64, &str, &str, &str); 2] = [
+    (
+        2,
+        "9de4b35cb6a7ef6fb24c96bef2462b210bf3e777d43089cecf4fc55689e393be",
+        "a1da009baa8eeee8811bcf1ff47463a66acc2869e1bbdac366aca73d22524024",
+        "36d7114c4b443e52d81e37bcccfed6212c004bc315e706c4009b79ffd984c30b",
+    ),
+    (
+        5,
+        "5004c040591553f555a7cba6c8c452ab88133c7594d1f43b5ed3fc070ecfb23f",
+        "5a050db208b9c07c0a904b154504d1bce417c46b2d2b6b94ba86afc7fa7d7e19",
+        "c994d7503378f653e8d02b66b2081f85d4faba0930a5e3117eb4ef32f94d4bb1",
+    ),
+];
+
+/// Assert that the cached key aggregation gives the same combined pubkey and
+/// the same multisigs as before.
+#[test]
+fn key_agg_reference() {
+    let hash = Sha256::default();
+    for (n, key, s, e) in REFERENCE {
+        let (privkeys, pubkeys) = keys(n);
+        let agg = KeyAggContext::sorted(&hash, &pubkeys).unwrap();
+        assert_eq!(hex::encode(&agg.aggregate_key().x().to_be_bytes()), key);
+        assert_eq!(
+            MuSig2::new(Secp256k1::default(), Sha256::default()).aggregate_key(&pubkeys),
+            agg.aggregate_key()
+        );
+
+        let mut schnorr = MultiSchnorr::new(Secp256k1::default(), Sha256::default());
+        let sig = sign(&mut schnorr, &agg, &privkeys);
+        assert_eq!(hex::encode(&sig.s().to_be_bytes()), s);
+        assert_eq!(hex::encode(&sig.e().to_be_bytes()), e);
+        assert!(schnorr.verify(&pubkeys, b"msg", &sig).is_ok());
+    }
+}
+
+/// Assert that the pubkeys must be in canonical order, and that a pubkey
+/// listed twice is rejected rather than counted twice.
+#[test]
+fn key_agg_invalid() {
+    let hash = Sha256::default();
+    let (privkeys, pubkeys) = keys(3);
+    let mut sorted = pubkeys.clone();
+    sorted.sort();
+    let new = |keys: &[PublicKey<Secp256k1>]| {
+        KeyAggContext::new(&hash, keys).map(|agg| agg.aggregate_key())
+    };
+    let agg = KeyAggContext::new(&hash, &sorted).unwrap();
+    assert_eq!(agg.pubkeys(), sorted);
+    assert!(sorted.iter().all(|&key| agg.coefficient(key).is_some()));
+
+    assert_eq!(new(&[]), Err(InvalidKeyAgg::Empty));
+    assert_eq!(
+        new(&[sorted[0], sorted[1], sorted[1], sorted[2]]),
+        Err(InvalidKeyAgg::Duplicate(2))
+    );
+    assert_eq!(
+        new(&[sorted[1], sorted[0], sorted[2]]),
+        Err(InvalidKeyAgg::Unordered(1))
+    );
+    assert_eq!(
+        KeyAggContext::sorted(&hash, &[sorted[2], sorted[0], sorted[2]]).unwrap_err(),
+        InvalidKeyAgg::Duplicate(2)
+    );
+    let reordered = KeyAggContext::sorted(&hash, &[sorted[1], sorted[2], sorted[0]]).unwrap();
+    assert_eq!(reordered.aggregate_key(), agg.aggregate_key());
+    assert_eq!(reordered.encoding(), agg.encoding());
+
+    // The second round rejects a duplicate signer as well.
+    let mut musig = MuSig2::new(Secp256k1::default(), Sha256::default());
+    let mut rng = rng();
+    let (nonce1, msg1) = musig.round1(&privkeys[0], &mut rng);
+    let (_, msg2) = musig.round1(&privkeys[1], &mut rng);
+    let duplicate = [pubkeys[0], pubkeys[1], pubkeys[1]];
+    assert!(matches!(
+        musig.round2(
+            &privkeys[0],
+            nonce1,
+            &duplicate,
+            &[msg1, msg2, msg2],
+            b"msg"
+        ),
+        Err(InvalidRound::KeyAgg(InvalidKeyAgg::Duplicate(_)))
+    ));
+}
+
+/// A hash which counts the preimages it hashes for the key aggregation, that
+/// is the coefficients it derives.
+#[derive(Clone, Default)]
+struct CountingHash(Rc<Cell<usize>>);
+
+const AGG_PROTOCOL: &[u8] = b"literate-crypto multischnorr aggregation";
+
+impl Hash for CountingHash {
+    type Digest = <Sha256 as Hash>::Digest;
+    type Block = <Sha256 as Hash>::Block;
+
+    fn hash(&self, preimage: &[u8]) -> Self::Digest {
+        if preimage
+            .windows(AGG_PROTOCOL.len())
+            .any(|w| w == AGG_PROTOCOL)
+        {
+            self.0.set(self.0.get() + 1);
+        }
+        Sha256::default().hash(preimage)
+    }
+}
+
+/// Assert that aggregating the keys once and then signing by every signer
+/// derives each coefficient once, instead of once per signer.
+#[test]
+fn key_agg_hash_count() {
+    let n = 6;
+    let (privkeys, pubkeys) = keys(n);
+    let hash = CountingHash::default();
+    let agg = KeyAggContext::sorted(&hash, &pubkeys).unwrap();
+    assert_eq!(hash.0.get(), pubkeys.len());
+
+    let mut schnorr = MultiSchnorr::new(Secp256k1::default(), hash.clone());
+    let sig = sign(&mut schnorr, &agg, &privkeys);
+    assert_eq!(hash.0.get(), pubkeys.len());
+
+    // Verifying with the list of pubkeys aggregates them once more.
+    assert!(schnorr.verify(&pubkeys, b"msg", &sig).is_ok());
+    assert_eq!(hash.0.get(), 2 * pubkeys.len());
+}
+
+/// Sign `msg` in a session by all of the given keys, with fixed randomness.
+fn sign<H: Hash<Digest = [u8; 32]>>(
+    schnorr: &mut MultiSchnorr<Secp256k1, H>,
+    agg: &KeyAggContext<Secp256k1, H>,
+    privkeys: &[PrivateKey<Secp256k1>],
+) -> SchnorrSignature<Secp256k1, H> {
+    let hash = Sha256::default();
+    let locals = (1..=privkeys.len() as u64)
+        .map(|i| Num::from_le_words([i * 13 + 5, 0, 0, 0]))
+        .collect_vec();
+    let commitments = locals
+        .iter()
+        .map(|&local| SchnorrRandomness::<Secp256k1>::commit(&hash, local))
+        .collect_vec();
+    let randomness = locals
+        .iter()
+        .enumerate()
+        .map(|(i, &local)| {
+            let mut others = commitments.clone();
+            others.remove(i);
+            SchnorrRandomness::new(&hash, local, &others).unwrap()
+        })
+        .collect_vec();
+
+    let mut session = schnorr.start_session(agg, randomness[0].clone(), b"msg");
+    for (key, r) in privkeys.iter().zip(randomness) {
+        let partial = schnorr.sign_partial(key.clone(), agg, &session, r).unwrap();
+        session.contribute(partial).unwrap();
+    }
+    session.finalize().unwrap()
+}
+
+fn keys(n: u64) -> (Vec<PrivateKey<Secp256k1>>, Vec<PublicKey<Secp256k1>>) {
+    let keys = (1..=n)
+        .map(|i| PrivateKey::new(Num::from_le_words([i * 7 + 3, 0, 0, 0])).unwrap())
+        .collect_vec();
+    let pubkeys = keys.iter().map(|k| k.derive()).collect();
+    (keys, pubkeys)
+}
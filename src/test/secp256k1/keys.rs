@@ -0,0 +1,259 @@
+use {
+    super::invalid_curve::Cofactor,
+    crate::{
+        ecc::{self, Curve, Group, KeyPair, Num, PrivateKey, PublicKey},
+        hex,
+        testing::{rng, StreamRng},
+        Ecdsa,
+        Secp256k1,
+        Sha256,
+        SignatureScheme,
+    },
+    std::collections::{BTreeSet, HashSet},
+};
+
+/// Subkeys are deterministic, and differ from each other and from the original
+/// key.
+#[test]
+fn subkeys_differ() {
+    let key = seven::<Secp256k1>();
+    let a = key.derive_subkey(b"a");
+    let b = key.derive_subkey(b"b");
+    assert_eq!(a.derive(), key.derive_subkey(b"a").derive());
+    assert_ne!(a.derive(), b.derive());
+    assert_ne!(a.derive(), key.derive());
+    assert_ne!(key.derive_subkey(b"").derive(), key.derive());
+
+    // Checked against HKDF-SHA256 from Python's hmac module.
+    assert_eq!(
+        key.derive_subkey(b"test").derive(),
+        PrivateKey::new(Num::from_be_bytes(block(
+            "f14eb2e26b96dac6974aae94f549cfc1ea304ce15520a422800ce1de670eb115"
+        )))
+        .unwrap()
+        .derive()
+    );
+
+    let pair = KeyPair::new(key.clone());
+    assert_eq!(pair.public(), key.derive());
+    assert_eq!(pair.origin(), Secp256k1::id());
+    assert_eq!(pair.derive_subkey(b"a").public(), a.derive());
+
+    // Subkeys also work on curves where most outputs of HKDF are rejected.
+    let toy = seven::<Cofactor>();
+    assert_ne!(toy.derive_subkey(b"a").derive(), toy.derive());
+}
+
+/// Public keys can be deduplicated in sets, and are ordered like their points.
+#[test]
+fn pubkey_set() {
+    let keys = [7, 3, 7, 5, 3].map(|n| {
+        PrivateKey::<Secp256k1>::new(Num::from_le_words([n, 0, 0, 0]))
+            .unwrap()
+            .derive()
+    });
+    let hashed: HashSet<_> = keys.iter().copied().collect();
+    let ordered: BTreeSet<_> = keys.iter().copied().collect();
+    assert_eq!(hashed.len(), 3);
+    assert_eq!(ordered.len(), 3);
+    assert!(ordered.iter().all(|key| hashed.contains(key)));
+
+    let by_point: BTreeSet<_> = keys.iter().map(|key| key.point()).collect();
+    assert!(ordered
+        .iter()
+        .map(|key| key.point())
+        .eq(by_point.iter().copied()));
+}
+
+/// The curve identifier is mixed into the nonces, pinned against an
+/// independent implementation in Python.
+#[test]
+fn nonce_curve_tag() {
+    assert_eq!(
+        hex::encode(&Secp256k1::id()),
+        "7e9af42f7984391a0aa54b43002bdcd453131e1267de2b1d79694983c84f9988"
+    );
+    assert_eq!(
+        hex::encode(&Cofactor::id()),
+        "b5f34b0228a873e771c1133cf25301e050205d019e7332d9b07c97393ef79e30"
+    );
+    assert_eq!(
+        hex::encode(&ecc::nonce_seed(
+            &Sha256::default(),
+            &seven::<Secp256k1>(),
+            &[b"abc"]
+        )),
+        "7fc1fc115bd0b7323dd5cbbc315c29c7398738810cd9d899b0b063f8737d2d18"
+    );
+
+    let sig = Ecdsa::new(Secp256k1::default(), Sha256::default()).sign(seven(), b"abc");
+    assert_eq!(
+        sig.r(),
+        Num::from_be_bytes(block(
+            "c67ec6b9cd4fb61b6a65151b95809b365b096d0f3865ae34bf48e906165bb3b2"
+        ))
+    );
+    assert_eq!(
+        sig.s(),
+        Num::from_be_bytes(block(
+            "6430b8096d66e2a0dfcf66c2a0058e5039b3c66cd88c8bc873ce5f0a498059ba"
+        ))
+    );
+}
+
+/// The same scalar signing the same message on two curves gets unrelated
+/// nonces.
+#[test]
+fn cross_curve_reuse() {
+    let hash = Sha256::default();
+    let secp = ecc::nonce_seed(&hash, &seven::<Secp256k1>(), &[b"abc"]);
+    let toy = ecc::nonce_seed(&hash, &seven::<Cofactor>(), &[b"abc"]);
+    assert_ne!(secp, toy);
+
+    // Without the curve identifier, both seeds would be the hash of the same
+    // preimage.
+    let mut preimage = Num::from_le_words([7, 0, 0, 0]).to_le_bytes().to_vec();
+    preimage.extend(b"abc");
+    let plain = crate::Hash::hash(&hash, &preimage);
+    assert_ne!(secp, plain);
+    assert_ne!(toy, plain);
+}
+
+/// Assert that the SEC1 encodings of the generator match the ones given in
+/// SEC 2, and that both forms decode to the same key.
+#[test]
+fn sec1_generator() {
+    let g = PublicKey::new(Secp256k1::g()).unwrap();
+    let x = "79be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798";
+    let y = "483ada7726a3c4655da4fbfc0e1108a8fd17b448a68554199c47d08ffb10d4b8";
+    assert_eq!(hex::encode(&g.to_sec1_bytes(true)), format!("02{x}"));
+    assert_eq!(hex::encode(&g.to_sec1_bytes(false)), format!("04{x}{y}"));
+    for compressed in [true, false] {
+        assert_eq!(
+            PublicKey::<Secp256k1>::from_sec1_bytes(&g.to_sec1_bytes(compressed)).unwrap(),
+            g
+        );
+    }
+}
+
+/// Assert that public keys with both parities of y survive a round trip
+/// through either SEC1 form.
+#[test]
+fn sec1_round_trip() {
+    let mut parities = BTreeSet::new();
+    for n in 1..20 {
+        let key = PrivateKey::<Secp256k1>::new(Num::from_le_words([n, 0, 0, 0]))
+            .unwrap()
+            .derive();
+        parities.insert(key.y().get_bit(0));
+        for compressed in [true, false] {
+            let bytes = key.to_sec1_bytes(compressed);
+            assert_eq!(bytes.len(), if compressed { 33 } else { 65 });
+            assert_eq!(PublicKey::from_sec1_bytes(&bytes).unwrap(), key);
+        }
+        // Flipping the parity gives the negated key.
+        let mut flipped = key.to_sec1_bytes(true);
+        flipped[0] ^= 1;
+        assert_eq!(
+            PublicKey::<Secp256k1>::from_sec1_bytes(&flipped)
+                .unwrap()
+                .point(),
+            key.point().inverse()
+        );
+    }
+    assert_eq!(parities.len(), 2);
+}
+
+/// Assert that malformed SEC1 encodings are rejected: unknown prefixes, wrong
+/// lengths, coordinates out of range, and x without a point on the curve.
+#[test]
+fn sec1_invalid() {
+    let g = PublicKey::new(Secp256k1::g()).unwrap();
+    let compressed = g.to_sec1_bytes(true);
+    let uncompressed = g.to_sec1_bytes(false);
+    let p = Secp256k1::P.to_be_bytes();
+    let mut not_on_curve = [0; 33];
+    not_on_curve[0] = 0x02;
+    not_on_curve[32] = 5;
+    let cases: [&[u8]; 9] = [
+        &[],
+        &[0x00],
+        &compressed[..32],
+        &[compressed.as_slice(), &[0]].concat(),
+        &uncompressed[..64],
+        &[&[0x05], &compressed[1..]].concat(),
+        &[&[0x04], &compressed[1..]].concat(),
+        &[&[0x02], p.as_slice()].concat(),
+        &not_on_curve,
+    ];
+    for bytes in cases {
+        assert!(
+            PublicKey::<Secp256k1>::from_sec1_bytes(bytes).is_err(),
+            "{}",
+            hex::encode(bytes)
+        );
+    }
+}
+
+/// Assert that random private keys are drawn below the curve order without
+/// skewing the top bits, and that zero and numbers past the order are drawn
+/// again.
+#[test]
+fn random_keys() {
+    let mut rng = rng();
+    let mut high = 0;
+    for _ in 0..1000 {
+        let n = Num::random_mod(&mut rng, Secp256k1::N);
+        assert!(n < Secp256k1::N);
+        // The order of secp256k1 is just below 2^256, so the top bit is set
+        // for about half of the draws.
+        high += usize::from(n.get_bit(255));
+    }
+    assert!((400..600).contains(&high), "{high}");
+
+    let draws = [
+        [0xff; 32],
+        [0; 32],
+        Num::from_le_words([7, 0, 0, 0]).to_le_bytes(),
+    ];
+    let mut stream = StreamRng(draws.concat());
+    let key = PrivateKey::<Secp256k1>::random(&mut stream);
+    assert_eq!(key.derive(), seven::<Secp256k1>().derive());
+    assert!(stream.0.is_empty());
+
+    // The order of the toy curve is far below a power of two, so most draws
+    // are rejected.
+    for _ in 0..100 {
+        assert!(Num::random_mod(&mut rng, Cofactor::N) < Cofactor::N);
+        PrivateKey::<Cofactor>::random(&mut rng);
+    }
+
+    let pair = KeyPair::<Secp256k1>::random(&mut rng);
+    assert_eq!(pair.public(), pair.private().derive());
+}
+
+/// Assert that [`Num::random_mod`] spreads its draws evenly below the modulus.
+#[test]
+fn random_mod_uniform() {
+    let mut rng = rng();
+    let p = Num::from_le_words([10, 0, 0, 0]);
+    let mut counts = [0; 10];
+    for _ in 0..10000 {
+        let n = Num::random_mod(&mut rng, p);
+        assert!(n < p);
+        counts[usize::from(n.to_le_bytes()[0])] += 1;
+    }
+    assert!(
+        counts.iter().all(|&n| (850..1150).contains(&n)),
+        "{counts:?}"
+    );
+    assert_eq!(Num::random_mod(&mut rng, Num::ONE), Num::ZERO);
+}
+
+fn seven<C: Curve>() -> PrivateKey<C> {
+    PrivateKey::new(Num::from_le_words([7, 0, 0, 0])).unwrap()
+}
+
+fn block(hex: &str) -> [u8; 32] {
+    hex::decode(hex).unwrap().try_into().unwrap()
+}
@@ -0,0 +1,183 @@
+use crate::{
+    ecc::{Curve, Num, Point, PrivateKey, PublicKey},
+    testing::rng,
+    util::CollectVec,
+    Csprng,
+    InvalidRound,
+    KeyAggContext,
+    MuSig2,
+    MuSig2PublicNonce,
+    MultisigScheme,
+    Schnorr,
+    Secp256k1,
+    Sha256,
+    SignatureScheme,
+    SignatureShare,
+};
+
+/// Assert that a 2-of-2 MuSig2 multisig verifies, both as a multisig and as a
+/// regular Schnorr signature by the combined pubkey.
+#[test]
+fn musig2_two_signers() {
+    let mut musig = musig2();
+    let (keys, pubkeys) = keys(2);
+    let (round1, shares) = sign(&mut musig, &keys, &pubkeys, b"msg", &mut rng());
+    let sig = musig.aggregate(&pubkeys, &shares).unwrap();
+
+    assert!(musig.verify(&pubkeys, b"msg", &sig).is_ok());
+    assert!(musig.verify(&pubkeys, b"other", &sig).is_err());
+    assert!(musig.verify(&pubkeys[..1], b"msg", &sig).is_err());
+    for share in &shares {
+        assert!(musig
+            .verify_partial(&key_agg(&pubkeys), &round1, &[], b"msg", share)
+            .is_ok());
+    }
+
+    let mut schnorr = Schnorr::new(Secp256k1::default(), Sha256::default());
+    let key = musig.aggregate_key(&pubkeys);
+    assert!(schnorr.verify(key, b"msg", &sig).is_ok());
+    assert!(schnorr.verify(key, b"other", &sig).is_err());
+}
+
+/// Assert that a 5-of-5 MuSig2 multisig verifies, and that it doesn't depend
+/// on the order of the messages in either round.
+#[test]
+fn musig2_five_signers() {
+    let mut musig = musig2();
+    let (keys, pubkeys) = keys(5);
+    let mut rng = rng();
+    let (nonces, round1): (Vec<_>, Vec<_>) = keys.iter().map(|k| musig.round1(k, &mut rng)).unzip();
+
+    // Every actor receives the first-round messages in a different order.
+    let shares = keys
+        .iter()
+        .zip(nonces)
+        .enumerate()
+        .map(|(i, (key, nonce))| {
+            let mut received = round1.clone();
+            received.rotate_left(i);
+            musig
+                .round2(key, nonce, &pubkeys, &received, b"msg")
+                .unwrap()
+        })
+        .collect_vec();
+    let sig = musig.aggregate(&pubkeys, &shares).unwrap();
+    assert!(musig.verify(&pubkeys, b"msg", &sig).is_ok());
+
+    let mut reversed = shares.clone();
+    reversed.reverse();
+    let other = musig.aggregate(&pubkeys, &reversed).unwrap();
+    assert_eq!((other.s(), other.e()), (sig.s(), sig.e()));
+
+    let mut schnorr = Schnorr::new(Secp256k1::default(), Sha256::default());
+    assert!(schnorr
+        .verify(musig.aggregate_key(&pubkeys), b"msg", &sig)
+        .is_ok());
+
+    // Without any one of the shares, the multisig can't be aggregated.
+    for i in 0..shares.len() {
+        let mut missing = shares.clone();
+        missing.remove(i);
+        assert!(musig.aggregate(&pubkeys, &missing).is_err());
+    }
+}
+
+/// Assert that a bad individual signature makes the multisig invalid, and that
+/// partial verification identifies the actor who sent it.
+#[test]
+fn musig2_bad_contributor() {
+    let mut musig = musig2();
+    let (keys, pubkeys) = keys(5);
+    let mut rng = rng();
+    let (round1, mut shares) = sign(&mut musig, &keys, &pubkeys, b"msg", &mut rng);
+    let bad = shares[3];
+    shares[3] = SignatureShare::new(bad.signer(), bad.s().add(Num::ONE, Secp256k1::N), bad.e());
+
+    let sig = musig.aggregate(&pubkeys, &shares).unwrap();
+    assert!(musig.verify(&pubkeys, b"msg", &sig).is_err());
+    let invalid = shares
+        .iter()
+        .map(|share| {
+            musig
+                .verify_partial(&key_agg(&pubkeys), &round1, &[], b"msg", share)
+                .is_err()
+        })
+        .collect_vec();
+    assert_eq!(invalid, [false, false, false, true, false]);
+
+    // A share made for another message is caught as well.
+    let (round1, other) = sign(&mut musig, &keys, &pubkeys, b"other", &mut rng);
+    assert!(musig
+        .verify_partial(&key_agg(&pubkeys), &round1, &[], b"msg", &other[0])
+        .is_err());
+}
+
+/// Assert that the second round rejects first-round messages which don't
+/// match the signers.
+#[test]
+fn musig2_invalid_round1() {
+    let mut musig = musig2();
+    let (keys, pubkeys) = keys(2);
+    let mut rng = rng();
+    let (_, msg2) = musig.round1(&keys[1], &mut rng);
+
+    let mut round2 = |msgs: &dyn Fn(MuSig2PublicNonce<Secp256k1>) -> Vec<_>| {
+        let (nonce, msg1) = musig.round1(&keys[0], &mut rng);
+        musig
+            .round2(&keys[0], nonce, &pubkeys, &msgs(msg1), b"msg")
+            .unwrap_err()
+    };
+    assert_eq!(round2(&|_| vec![msg2]), InvalidRound::MissingNonce);
+    assert_eq!(round2(&|m| vec![m]), InvalidRound::MissingNonce);
+    assert_eq!(round2(&|m| vec![m, m]), InvalidRound::MissingNonce);
+    assert_eq!(round2(&|m| vec![m, msg2, msg2]), InvalidRound::MissingNonce);
+    let infinity = MuSig2PublicNonce::new(msg2.signer(), msg2.r1(), Point::infinity());
+    assert!(matches!(
+        round2(&|m| vec![m, infinity]),
+        InvalidRound::Randomness(_)
+    ));
+
+    let (nonce, msg1) = musig.round1(&keys[0], &mut rng);
+    let stranger = PrivateKey::random(&mut rng);
+    assert_eq!(
+        musig
+            .round2(&stranger, nonce, &pubkeys, &[msg1, msg2], b"msg")
+            .unwrap_err(),
+        InvalidRound::UnknownSigner
+    );
+}
+
+type Round<C> = (Vec<MuSig2PublicNonce<C>>, Vec<SignatureShare<C, Sha256>>);
+
+/// Run both rounds for all actors.
+fn sign(
+    musig: &mut MuSig2<Secp256k1, Sha256>,
+    keys: &[PrivateKey<Secp256k1>],
+    pubkeys: &[PublicKey<Secp256k1>],
+    msg: &[u8],
+    rng: &mut impl Csprng,
+) -> Round<Secp256k1> {
+    let (nonces, round1): (Vec<_>, Vec<_>) = keys.iter().map(|k| musig.round1(k, rng)).unzip();
+    let shares = keys
+        .iter()
+        .zip(nonces)
+        .map(|(key, nonce)| musig.round2(key, nonce, pubkeys, &round1, msg).unwrap())
+        .collect_vec();
+    (round1, shares)
+}
+
+fn keys(n: u64) -> (Vec<PrivateKey<Secp256k1>>, Vec<PublicKey<Secp256k1>>) {
+    let keys = (1..=n)
+        .map(|i| PrivateKey::new(Num::from_le_words([1000 + i, 0, 0, 0])).unwrap())
+        .collect_vec();
+    let pubkeys = keys.iter().map(|k| k.derive()).collect();
+    (keys, pubkeys)
+}
+
+fn musig2() -> MuSig2<Secp256k1, Sha256> {
+    MuSig2::new(Secp256k1::default(), Sha256::default())
+}
+
+fn key_agg(pubkeys: &[PublicKey<Secp256k1>]) -> KeyAggContext<Secp256k1, Sha256> {
+    KeyAggContext::sorted(&Sha256::default(), pubkeys).unwrap()
+}
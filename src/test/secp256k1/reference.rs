@@ -0,0 +1,238 @@
+//! An independent reference implementation of modular arithmetic, used to
+//! generate and check the [`Num`] test vectors.
+//!
+//! Numbers are four little-endian 64-bit words, like in [`Num`], but the
+//! arithmetic shares nothing with it: products are computed with schoolbook
+//! multiplication over `u128` chunks, and every result is reduced by plain
+//! shift-and-subtract long division. This is slow, but simple enough to be
+//! checked by hand.
+//!
+//! The vectors are drawn from a seeded generator, so the same seed always
+//! gives the same vectors. To print them in the format of the vector files,
+//! run
+//!
+//! ```text
+//! cargo test print_vectors -- --ignored --nocapture
+//! ```
+
+use crate::{
+    ecc::{Curve, Num, Secp256k1},
+    hex,
+    util::CollectVec,
+};
+
+pub type Words = [u64; 4];
+
+/// A binary operation modulo the last argument.
+pub type Op = fn(Words, Words, Words) -> Words;
+
+/// Convert a [`Num`] into its little-endian words.
+pub fn words(n: Num) -> Words {
+    let bytes = n.to_le_bytes();
+    std::array::from_fn(|i| u64::from_le_bytes(bytes[8 * i..8 * i + 8].try_into().unwrap()))
+}
+
+/// Compute $(a + b) \bmod m$.
+pub fn add(a: Words, b: Words, m: Words) -> Words {
+    let mut sum = [0; 8];
+    let mut carry = 0;
+    for i in 0..4 {
+        let s = u128::from(a[i]) + u128::from(b[i]) + carry;
+        sum[i] = s as u64;
+        carry = s >> 64;
+    }
+    sum[4] = carry as u64;
+    rem(sum, m)
+}
+
+/// Compute $(a - b) \bmod m$.
+pub fn sub(a: Words, b: Words, m: Words) -> Words {
+    let (a, b) = (reduce(a, m), reduce(b, m));
+    if geq(&a, &b) {
+        sub_words(a, b)
+    } else {
+        // a + m - b < m fits into 256 bits, even though a + m might not.
+        sub_words(a, sub_words(b, m))
+    }
+}
+
+/// Compute $ab \bmod m$.
+pub fn mul(a: Words, b: Words, m: Words) -> Words {
+    let mut product = [0; 8];
+    for i in 0..4 {
+        let mut carry = 0;
+        for j in 0..4 {
+            let p = u128::from(a[i]) * u128::from(b[j]) + u128::from(product[i + j]) + carry;
+            product[i + j] = p as u64;
+            carry = p >> 64;
+        }
+        product[i + 4] = carry as u64;
+    }
+    rem(product, m)
+}
+
+/// Compute $a^{-1} \bmod m$ as $a^{m - 2}$, which requires $m$ to be prime.
+pub fn inv(a: Words, m: Words) -> Words {
+    let e = sub_words(m, [2, 0, 0, 0]);
+    let mut result = reduce([1, 0, 0, 0], m);
+    for bit in (0..256).rev() {
+        result = mul(result, result, m);
+        if e[bit / 64] >> (bit % 64) & 1 == 1 {
+            result = mul(result, a, m);
+        }
+    }
+    result
+}
+
+/// Reduce a 256-bit number modulo $m$.
+fn reduce(a: Words, m: Words) -> Words {
+    rem([a[0], a[1], a[2], a[3], 0, 0, 0, 0], m)
+}
+
+/// Compute the remainder of a 512-bit number, one bit at a time.
+fn rem(n: [u64; 8], m: Words) -> Words {
+    // The remainder stays below 2m, so it needs one bit more than m.
+    let m = [m[0], m[1], m[2], m[3], 0];
+    let mut r = [0u64; 5];
+    for bit in (0..512).rev() {
+        for i in (1..5).rev() {
+            r[i] = r[i] << 1 | r[i - 1] >> 63;
+        }
+        r[0] = r[0] << 1 | n[bit / 64] >> (bit % 64) & 1;
+        if geq(&r, &m) {
+            let mut borrow = 0;
+            for i in 0..5 {
+                let (d, b1) = r[i].overflowing_sub(m[i]);
+                let (d, b2) = d.overflowing_sub(borrow);
+                r[i] = d;
+                borrow = u64::from(b1 || b2);
+            }
+        }
+    }
+    [r[0], r[1], r[2], r[3]]
+}
+
+/// Compute $a - b$, wrapping around at $2^{256}$.
+fn sub_words(a: Words, b: Words) -> Words {
+    let mut result = [0; 4];
+    let mut borrow = 0;
+    for i in 0..4 {
+        let (d, b1) = a[i].overflowing_sub(b[i]);
+        let (d, b2) = d.overflowing_sub(borrow);
+        result[i] = d;
+        borrow = u64::from(b1 || b2);
+    }
+    result
+}
+
+/// Compare two little-endian numbers of equal length.
+fn geq(a: &[u64], b: &[u64]) -> bool {
+    a.iter().rev().cmp(b.iter().rev()).is_ge()
+}
+
+/// The SplitMix64 generator, seeded for reproducible vectors.
+pub struct SplitMix(u64);
+
+impl SplitMix {
+    pub fn new(seed: u64) -> Self {
+        Self(seed)
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e3779b97f4a7c15);
+        let mut z = self.0;
+        z = (z ^ z >> 30).wrapping_mul(0xbf58476d1ce4e5b9);
+        z = (z ^ z >> 27).wrapping_mul(0x94d049bb133111eb);
+        z ^ z >> 31
+    }
+
+    /// Draw a uniformly random 256-bit number.
+    pub fn words(&mut self) -> Words {
+        std::array::from_fn(|_| self.next_u64())
+    }
+}
+
+/// Generate `count` cases $(a, b, op(a, b))$ from the seed, where the operands
+/// are arbitrary 256-bit numbers.
+pub fn binary_cases(seed: u64, count: usize, m: Words, op: Op) -> Vec<[Words; 3]> {
+    let mut rng = SplitMix::new(seed);
+    (0..count)
+        .map(|_| {
+            let (a, b) = (rng.words(), rng.words());
+            [a, b, op(a, b, m)]
+        })
+        .collect()
+}
+
+/// Generate `count` cases $(a, a^{-1})$ from the seed, where $a$ is a nonzero
+/// residue modulo the prime $m$.
+pub fn inversion_cases(seed: u64, count: usize, m: Words) -> Vec<[Words; 2]> {
+    let mut rng = SplitMix::new(seed);
+    (0..count)
+        .map(|_| loop {
+            let a = reduce(rng.words(), m);
+            if a != [0; 4] {
+                break [a, inv(a, m)];
+            }
+        })
+        .collect()
+}
+
+/// Format a case as a record of a vector file, with the numbers as big-endian
+/// hex.
+pub fn record(op: &str, names: &[&str], case: &[Words]) -> String {
+    let mut record = format!("op = {op}\n");
+    for (name, w) in names.iter().zip(case) {
+        let bytes = w.iter().rev().flat_map(|w| w.to_be_bytes()).collect_vec();
+        record.push_str(&format!("{name} = {}\n", hex::encode(&bytes)));
+    }
+    record
+}
+
+const MAX: Words = [u64::MAX; 4];
+
+fn small(n: u64) -> Words {
+    [n, 0, 0, 0]
+}
+
+#[test]
+fn small_modulus() {
+    let m = small(97);
+    assert_eq!(add(small(50), small(60), m), small(13));
+    assert_eq!(add(small(96), small(1), m), small(0));
+    assert_eq!(sub(small(3), small(5), m), small(95));
+    assert_eq!(sub(small(200), small(5), m), small(1));
+    assert_eq!(mul(small(10), small(20), m), small(6));
+    assert_eq!(mul(small(0), small(20), m), small(0));
+    // 3 * 65 = 195 = 2 * 97 + 1
+    assert_eq!(inv(small(3), m), small(65));
+    assert_eq!(inv(small(96), m), small(96));
+}
+
+/// Results which only fit after wrapping past $2^{256}$, where
+/// $2^{256} \bmod P = 2^{32} + 977$.
+#[test]
+fn wide_operands() {
+    let p = words(Secp256k1::P);
+    let wrap = small((1 << 32) + 977);
+    assert_eq!(add(MAX, small(1), p), wrap);
+    assert_eq!(mul([0, 0, 1, 0], [0, 0, 1, 0], p), wrap);
+    assert_eq!(sub(small(0), small(1), p), sub_words(p, small(1)));
+    assert_eq!(sub(MAX, MAX, p), small(0));
+    assert_eq!(mul(MAX, small(1), p), sub_words(MAX, p));
+}
+
+#[test]
+fn seeded() {
+    let p = words(Secp256k1::P);
+    assert_eq!(binary_cases(7, 3, p, add), binary_cases(7, 3, p, add));
+    assert_ne!(binary_cases(7, 3, p, add), binary_cases(8, 3, p, add));
+    assert_eq!(
+        record("inv", &["a", "c"], &[small(1), [0, 0, 0, 2 << 60]]),
+        format!(
+            "op = inv\na = {}1\nc = 2{}\n",
+            "0".repeat(63),
+            "0".repeat(63)
+        )
+    );
+}
@@ -0,0 +1,170 @@
+use crate::{
+    ecc::{self, Curve, Num, Point},
+    testing::{key, rng},
+    util::CollectVec,
+    BlindSchnorr,
+    MultiSchnorr,
+    MultisigScheme,
+    Secp256k1,
+    SessionId,
+    SessionRegistry,
+    SessionRejected,
+    Sha256,
+    TooManySessions,
+};
+
+/// Assert that a session finished through the registry produces a valid
+/// multisig, and that the session is closed afterwards.
+#[test]
+fn registry_multischnorr_valid() {
+    let mut schnorr = multi_schnorr();
+    let mut registry = SessionRegistry::new(1);
+    let mut rng = rng();
+    let privkeys = [key(5), key(6)];
+    let pubkeys = privkeys.clone().map(|k| k.derive());
+    let id = SessionId::random(&mut rng);
+
+    let (nonce1, msg1) = schnorr
+        .open_session(&mut registry, &privkeys[0], id, &mut rng)
+        .unwrap();
+    let (nonce2, msg2) = schnorr
+        .open_session(&mut registry, &privkeys[1], id, &mut rng)
+        .unwrap();
+    assert_eq!(nonce1.id(), id);
+    assert_eq!(registry.open_sessions(pubkeys[0]), 1);
+    let round1 = [msg1, msg2];
+    let shares = [
+        schnorr
+            .finish_session(&privkeys[0], nonce1, &pubkeys, &round1, &[], b"msg")
+            .unwrap(),
+        schnorr
+            .finish_session(&privkeys[1], nonce2, &pubkeys, &round1, &[], b"msg")
+            .unwrap(),
+    ];
+    assert_eq!(registry.open_sessions(pubkeys[0]), 0);
+    assert_eq!(registry.open_sessions(pubkeys[1]), 0);
+
+    let sig = schnorr.aggregate(&pubkeys, &shares).unwrap();
+    assert!(schnorr.verify(&pubkeys, b"msg", &sig).is_ok());
+}
+
+/// Assert that replaying a recorded session id into a fresh session is
+/// rejected, whether the original session was finished or aborted, while the
+/// same id stays usable for other keys.
+#[test]
+fn registry_replay_rejected() {
+    let mut schnorr = multi_schnorr();
+    let mut registry = SessionRegistry::new(2);
+    let mut rng = rng();
+    let (privkey, other) = (key(5), key(6));
+    let pubkeys = [privkey.derive()];
+    let (finished, aborted) = (SessionId::random(&mut rng), SessionId::random(&mut rng));
+
+    let (nonce, msg) = schnorr
+        .open_session(&mut registry, &privkey, finished, &mut rng)
+        .unwrap();
+    schnorr
+        .finish_session(&privkey, nonce, &pubkeys, &[msg], &[], b"msg")
+        .unwrap();
+    let (nonce, _) = schnorr
+        .open_session(&mut registry, &privkey, aborted, &mut rng)
+        .unwrap();
+    drop(nonce);
+
+    for id in [finished, aborted] {
+        assert!(registry.is_used(privkey.derive(), id));
+        assert_eq!(
+            schnorr
+                .open_session(&mut registry, &privkey, id, &mut rng)
+                .unwrap_err(),
+            SessionRejected::Replayed(id)
+        );
+    }
+    assert_eq!(registry.open_sessions(privkey.derive()), 0);
+    assert!(!registry.is_used(other.derive(), finished));
+    assert!(schnorr
+        .open_session(&mut registry, &other, finished, &mut rng)
+        .is_ok());
+}
+
+/// Assert that the cap on concurrent sessions applies to sessions with ids as
+/// well, and that a rejected session doesn't use up its id.
+#[test]
+fn registry_session_cap() {
+    let mut registry = SessionRegistry::<Secp256k1>::new(1);
+    let mut rng = rng();
+    let pubkey = key(5).derive();
+    let (id1, id2) = (SessionId::random(&mut rng), SessionId::random(&mut rng));
+
+    let guard = registry.open_with_id(pubkey, id1).unwrap();
+    assert_eq!(guard.id(), Some(id1));
+    assert_eq!(registry.open(pubkey).unwrap_err(), TooManySessions);
+    assert_eq!(
+        registry.open_with_id(pubkey, id2).unwrap_err(),
+        SessionRejected::TooManySessions
+    );
+    assert!(!registry.is_used(pubkey, id2));
+    drop(guard);
+    assert!(registry.open_with_id(pubkey, id2).is_ok());
+}
+
+/// Assert that dropping a session, finished or not, wipes its secret nonce.
+#[test]
+fn registry_nonce_wiped() {
+    let mut schnorr = multi_schnorr();
+    let mut registry = SessionRegistry::new(1);
+    let mut rng = rng();
+    let privkey = key(5);
+    let pubkeys = [privkey.derive()];
+    ecc::take_wiped();
+
+    let (nonce, (r, _)) = schnorr
+        .open_session(
+            &mut registry,
+            &privkey,
+            SessionId::random(&mut rng),
+            &mut rng,
+        )
+        .unwrap();
+    drop(nonce);
+    assert_nonce_wiped(r);
+
+    let (nonce, (r, t)) = schnorr
+        .open_session(
+            &mut registry,
+            &privkey,
+            SessionId::random(&mut rng),
+            &mut rng,
+        )
+        .unwrap();
+    schnorr
+        .finish_session(&privkey, nonce, &pubkeys, &[(r, t)], &[], b"msg")
+        .unwrap();
+    assert_nonce_wiped(r);
+
+    let mut blind = BlindSchnorr::new(Secp256k1::default(), Sha256::default());
+    let session = blind.start(privkey, &mut rng).unwrap();
+    let r = session.commitment();
+    session.respond(Num::ONE).unwrap();
+    assert_nonce_wiped(r);
+}
+
+/// Assert that the numbers wiped since the last call, other than the private
+/// key used in [`registry_nonce_wiped`], are all the nonce behind `r`. The
+/// nonce may be wiped more than once, since every copy held by a value which
+/// gets dropped is wiped on its own.
+fn assert_nonce_wiped(r: Point<Secp256k1>) {
+    let wiped = ecc::take_wiped()
+        .into_iter()
+        .filter(|&(n, _)| n != Num::from_le_words([5, 0, 0, 0]))
+        .collect_vec();
+    assert!(!wiped.is_empty());
+    assert!(wiped
+        .iter()
+        .all(|&(n, left)| n == wiped[0].0 && left == Num::ZERO));
+    assert_eq!(wiped[0].0 * Secp256k1::g(), r);
+}
+
+fn multi_schnorr() -> MultiSchnorr<Secp256k1, Sha256> {
+    MultiSchnorr::new(Secp256k1::default(), Sha256::default())
+}
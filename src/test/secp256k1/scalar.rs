@@ -0,0 +1,173 @@
+use {
+    super::kem::recipient,
+    crate::{
+        ecc::{Curve, DigestSizeMismatch, Num, Point, Scalar},
+        hex,
+        Ecdsa,
+        EcdsaSignature,
+        Hash,
+        Secp256k1,
+        Sha3_224,
+        Sha3_384,
+        Sha3_512,
+        SignatureScheme,
+    },
+};
+
+fn num(hex: &str) -> Num {
+    Num::from_be_bytes(hex::decode(hex).unwrap().try_into().unwrap())
+}
+
+/// A 64-byte digest keeps its leftmost 32 bytes on secp256k1. The signature
+/// was made with ECDSA over SHA3-512 by the `cryptography` Python library, so
+/// it only verifies if both sides truncate the digest the same way.
+#[test]
+fn scalar_long_digest() {
+    let digest = Sha3_512::default().hash(b"abc");
+    let scalar = Scalar::<Secp256k1>::from_digest(&digest).unwrap();
+    assert_eq!(
+        scalar.num(),
+        num("b751850b1a57168a5693cd924b6b096e08f621827444f70d884f5d0240d2712e")
+    );
+    assert_eq!(Scalar::from_short_digest(&digest), scalar);
+
+    let sig = EcdsaSignature::new(
+        num("e07c3daa7580c8cc667fa44bcdbb3f23beb44430006d3ca8ee1ffe4c94152c16"),
+        num("b920b298f7d3e88b7a2db9496e6c8009506b5274d119b028a27a49e860b8103c"),
+    )
+    .unwrap();
+    let mut ecdsa = Ecdsa::new(Secp256k1::default(), Sha3_512::default());
+    assert!(ecdsa.verify(recipient().derive(), b"abc", &sig).is_ok());
+    assert!(ecdsa.verify(recipient().derive(), b"abd", &sig).is_err());
+}
+
+/// Assert that [`Num::from_digest`] reads digests big-endian and keeps their
+/// leftmost bits, for digests longer than the modulus.
+#[test]
+fn num_from_long_digest() {
+    let digest = Sha3_384::default().hash(b"abc");
+    let leftmost = Num::from_be_bytes(digest[..32].try_into().unwrap());
+    assert_eq!(
+        Num::from_digest(digest, Secp256k1::N),
+        leftmost.reduce(Secp256k1::N)
+    );
+
+    let digest = Sha3_512::default().hash(b"abc");
+    assert_eq!(
+        Num::from_digest(digest, Secp256k1::N),
+        Scalar::<Secp256k1>::from_digest(&digest).unwrap().num()
+    );
+
+    // Only the leftmost 32 bytes count, and the last of them is the least
+    // significant.
+    let mut digest = [0xff; 48];
+    digest[..32].fill(0);
+    assert_eq!(Num::from_digest(digest, Secp256k1::N), Num::ZERO);
+    digest[31] = 1;
+    assert_eq!(Num::from_digest(digest, Secp256k1::N), Num::ONE);
+
+    // A 17-bit modulus keeps the leftmost 17 bits, 0x1ffff, which reduces to
+    // 0xffff.
+    let n = Num::from_le_words([0x10000, 0, 0, 0]);
+    assert_eq!(
+        Num::from_digest([0xff; 48], n),
+        Num::from_le_words([0xffff, 0, 0, 0])
+    );
+}
+
+/// A digest at least as large as $N$ is reduced.
+#[test]
+fn scalar_reduced() {
+    assert_eq!(
+        Scalar::<Secp256k1>::from_digest(&[0xff; 32]).unwrap().num(),
+        Num::from_be_bytes([0xff; 32]).sub(Secp256k1::N, Secp256k1::N)
+    );
+    assert_eq!(
+        Scalar::<Secp256k1>::from_digest(&Secp256k1::N.to_be_bytes())
+            .unwrap()
+            .num(),
+        Num::ZERO
+    );
+}
+
+#[test]
+fn scalar_short_digest() {
+    let digest = Sha3_224::default().hash(b"abc");
+    assert_eq!(
+        Scalar::<Secp256k1>::from_digest(&digest),
+        Err(DigestSizeMismatch {
+            len: 28,
+            required: 32
+        })
+    );
+    assert_eq!(
+        DigestSizeMismatch {
+            len: 28,
+            required: 32
+        }
+        .to_string(),
+        "digest of 28 bytes is too short for a curve order of 32 bytes"
+    );
+    assert_eq!(
+        Scalar::<Secp256k1>::from_digest(&[]),
+        Err(DigestSizeMismatch {
+            len: 0,
+            required: 32
+        })
+    );
+
+    // Zero-extended on the left when explicitly allowed.
+    let mut extended = [0; 32];
+    extended[4..].copy_from_slice(&digest);
+    assert_eq!(
+        Scalar::<Secp256k1>::from_short_digest(&digest).num(),
+        Num::from_be_bytes(extended)
+    );
+}
+
+#[test]
+#[should_panic(expected = "digest of 28 bytes is too short for a curve order of 32 bytes")]
+fn scalar_short_hash_signing() {
+    Ecdsa::new(Secp256k1::default(), Sha3_224::default()).sign(recipient(), b"abc");
+}
+
+/// The order of this toy curve has 17 bits, so the leftmost bits end in the
+/// middle of the third byte.
+#[derive(Debug)]
+struct Toy;
+
+impl Curve for Toy {
+    const SIZE: usize = 3;
+    const P: Num = Num::from_le_words([65437, 0, 0, 0]);
+    const N: Num = Num::from_le_words([65929, 0, 0, 0]);
+    const A: Num = Num::ZERO;
+    const B: Num = Num::from_le_words([6, 0, 0, 0]);
+
+    fn g() -> Point<Self> {
+        Point::new(Num::ONE, Num::from_le_words([8488, 0, 0, 0])).unwrap()
+    }
+}
+
+#[test]
+fn scalar_leftmost_bits() {
+    let scalar = |digest: &[u8]| Scalar::<Toy>::from_digest(digest).map(|s| s.num());
+    // The leftmost 17 bits are 131071, which is reduced modulo N = 65929.
+    assert_eq!(scalar(&[0xff; 4]), Ok(Num::from_le_words([65142, 0, 0, 0])));
+    assert_eq!(
+        scalar(&[0x12, 0x34, 0x56]),
+        Ok(Num::from_le_words([9320, 0, 0, 0]))
+    );
+    // Only the leftmost bits matter.
+    assert_eq!(
+        scalar(&[0x12, 0x34, 0x56]),
+        scalar(&[0x12, 0x34, 0x57, 0xff])
+    );
+    assert_ne!(scalar(&[0x12, 0x34, 0x56]), scalar(&[0x12, 0x34, 0xd6]));
+    assert_eq!(
+        scalar(&[0x12, 0x34]),
+        Err(DigestSizeMismatch {
+            len: 2,
+            required: 3
+        })
+    );
+}
@@ -1,23 +1,38 @@
 use {
     crate::{
-        ecc::{self, Curve, Num, PublicKey},
-        test::fortuna::NoEntropy,
+        ecc::{self, Curve, Num, PublicKey, Scalar},
+        testing::{rng, CountingHash, FixedOutputHash, StreamRng},
         util::CollectVec,
-        Aes256,
+        Csprng,
         Ecdsa,
         EcdsaSignature,
-        Fortuna,
+        Hash,
+        IncompleteSession,
+        InvalidBatch,
+        InvalidRound,
+        InvalidSchnorrRandomness,
+        KeyAggContext,
+        LinkableRingScheme,
         MultiSchnorr,
         MultisigScheme,
+        NonceCommitment,
+        PartialAggregate,
+        PartialSignature,
         RingScheme,
         Schnorr,
+        SchnorrLsag,
+        SchnorrNonce,
         SchnorrRandomness,
         SchnorrSag,
+        SchnorrSagError,
+        SchnorrSagSignature,
         SchnorrSignature,
         Secp256k1,
+        SessionError,
         Sha256,
         Sha3_256,
         SignatureScheme,
+        SigningSession,
     },
     rand::Rng,
 };
@@ -68,6 +83,169 @@ fn ecdsa_invalid_pubkey() {
     assert!(ecdsa.verify(rand_pubkey(), &data, &sig).is_err());
 }
 
+/// Assert that ECDSA refuses to reuse a nonce for a different message under
+/// the same key, while re-signing the same message is still allowed.
+#[test]
+fn ecdsa_reused_nonce_rejected() {
+    let mut ecdsa = Ecdsa::new(Secp256k1::default(), Sha3_256::default());
+    let privkey = rand_privkey();
+    let k = rand_num();
+
+    let sig = ecdsa.sign_with_nonce(&privkey, b"first", k).unwrap();
+    assert!(ecdsa.sign_with_nonce(&privkey, b"second", k).is_none());
+    let again = ecdsa.sign_with_nonce(&privkey, b"first", k).unwrap();
+    assert_eq!((sig.r(), sig.s()), (again.r(), again.s()));
+
+    // A different key may still use the same nonce.
+    assert!(ecdsa
+        .sign_with_nonce(&rand_privkey(), b"second", k)
+        .is_some());
+}
+
+/// Assert that the private key is recovered from two signatures which reuse a
+/// nonce, and that nothing is recovered from signatures with distinct nonces.
+#[test]
+fn ecdsa_reused_nonce_recovery() {
+    let privkey = rand_privkey();
+    let k = rand_num();
+    // Separate instances don't share the record of recent nonces, so the reuse
+    // goes through.
+    let sig1 = Ecdsa::new(Secp256k1::default(), Sha3_256::default())
+        .sign_with_nonce(&privkey, b"first", k)
+        .unwrap();
+    let sig2 = Ecdsa::new(Secp256k1::default(), Sha3_256::default())
+        .sign_with_nonce(&privkey, b"second", k)
+        .unwrap();
+    assert_eq!(sig1.r(), sig2.r());
+
+    let mut ecdsa = Ecdsa::new(Secp256k1::default(), Sha3_256::default());
+    let sig3 = ecdsa.sign(privkey.clone(), b"third");
+    let recovered = ecdsa
+        .detect_reused_nonce(&[(b"third", sig3), (b"first", sig1), (b"second", sig2)])
+        .unwrap();
+    assert_eq!(recovered.derive().x(), privkey.derive().x());
+    assert_eq!(recovered.derive().y(), privkey.derive().y());
+
+    let sig4 = ecdsa.sign(privkey, b"fourth");
+    assert!(ecdsa
+        .detect_reused_nonce(&[(b"third", sig3), (b"first", sig1), (b"fourth", sig4)])
+        .is_none());
+}
+
+/// Assert that ECDSA signing moves on to the next nonce when the nonce is zero,
+/// so that $kG$ is the point at infinity. A real hash gives a zero nonce with
+/// negligible probability, so the digest of the first nonce is forced.
+#[docext::docext]
+#[test]
+fn ecdsa_retry_infinite_nonce() {
+    let privkey = rand_privkey();
+    // The nonce seed is hashed first. Then each nonce takes one hash to derive,
+    // and two to sign with: the message and the private key.
+    let hash = CountingHash::new(FixedOutputHash::new(
+        Sha256::default(),
+        [None, Some([0; 32])],
+    ));
+    let sig = Ecdsa::new(Secp256k1::default(), &hash).sign(privkey.clone(), b"msg");
+    assert_eq!(hash.calls(), 1 + 3 + 3);
+
+    let mut ecdsa = Ecdsa::new(Secp256k1::default(), Sha256::default());
+    let next = next_nonce(Num::ZERO);
+    let expected = ecdsa.sign_with_nonce(&privkey, b"msg", next).unwrap();
+    assert_eq!((sig.r(), sig.s()), (expected.r(), expected.s()));
+    // The script is used up, so the hash passes through to SHA-256.
+    assert!(Ecdsa::new(Secp256k1::default(), &hash)
+        .verify(privkey.derive(), b"msg", &sig)
+        .is_ok());
+}
+
+/// Assert that ECDSA signing moves on to the next nonce when $s = 0$, by
+/// forcing the nonce $k$ and the message digest $e = -rp$, so that $s =
+/// k^{-1}(e + rp) = 0$.
+#[docext::docext]
+#[test]
+fn ecdsa_retry_zero_s() {
+    let p = rand_scalar();
+    let privkey = ecc::PrivateKey::new(p).unwrap();
+    let k = rand_scalar();
+    let r = match (k * Secp256k1::g()).coordinates() {
+        ecc::Coordinates::Finite(x, _) => x,
+        ecc::Coordinates::Infinity => unreachable!("k is nonzero"),
+    };
+    let e = Num::ZERO.sub(r.mul(p, Secp256k1::N), Secp256k1::N);
+    let hash = CountingHash::new(FixedOutputHash::new(
+        Sha256::default(),
+        [None, Some(k.to_be_bytes()), Some(e.to_be_bytes())],
+    ));
+    let sig = Ecdsa::new(Secp256k1::default(), &hash).sign(privkey.clone(), b"msg");
+    assert_eq!(hash.calls(), 1 + 3 + 3);
+
+    let mut ecdsa = Ecdsa::new(Secp256k1::default(), Sha256::default());
+    let expected = ecdsa
+        .sign_with_nonce(&privkey, b"msg", next_nonce(k))
+        .unwrap();
+    assert_eq!((sig.r(), sig.s()), (expected.r(), expected.s()));
+    // The script is used up, so the hash passes through to SHA-256.
+    assert!(Ecdsa::new(Secp256k1::default(), &hash)
+        .verify(privkey.derive(), b"msg", &sig)
+        .is_ok());
+}
+
+/// The nonce ECDSA signing derives after rejecting the nonce `k`.
+fn next_nonce(k: Num) -> Num {
+    Scalar::<Secp256k1>::from_digest(&Sha256::default().hash(&k.to_le_bytes()))
+        .unwrap()
+        .num()
+}
+
+/// Assert that both an ECDSA signature and its high-s twin verify by default,
+/// but the strict mode only accepts the low one.
+#[test]
+fn ecdsa_high_s() {
+    let EcdsaSetup {
+        pubkey,
+        sig,
+        data,
+        mut ecdsa,
+    } = ecdsa_setup();
+    let twin = EcdsaSignature::new(sig.r(), sig.s().neg_mod(Secp256k1::N)).unwrap();
+    let (low, high) = if sig.has_low_s() {
+        (sig, twin)
+    } else {
+        (twin, sig)
+    };
+    assert!(low.has_low_s());
+    assert!(!high.has_low_s());
+
+    assert!(ecdsa.verify(pubkey, &data, &low).is_ok());
+    assert!(ecdsa.verify(pubkey, &data, &high).is_ok());
+
+    let mut strict = Ecdsa::new_low_s(Secp256k1::default(), Sha3_256::default());
+    assert!(strict.verify(pubkey, &data, &low).is_ok());
+    assert!(strict.verify(pubkey, &data, &high).is_err());
+}
+
+/// Assert that [`normalize_s`](EcdsaSignature::normalize_s) maps both twins to
+/// the low one, and that signatures created in strict mode are normalized.
+#[test]
+fn ecdsa_normalize_s() {
+    let EcdsaSetup { sig, .. } = ecdsa_setup();
+    let twin =
+        EcdsaSignature::<Secp256k1, Sha3_256>::new(sig.r(), sig.s().neg_mod(Secp256k1::N)).unwrap();
+    let normalized = sig.normalize_s();
+    assert!(normalized.has_low_s());
+    assert_eq!(normalized.s(), twin.normalize_s().s());
+    assert_eq!(normalized.s(), normalized.normalize_s().s());
+    assert!(normalized.s() == sig.s() || normalized.s() == twin.s());
+
+    let mut strict = Ecdsa::new_low_s(Secp256k1::default(), Sha3_256::default());
+    let privkey = rand_privkey();
+    for i in 0u8..16 {
+        let sig = strict.sign(privkey.clone(), &[i]);
+        assert!(sig.has_low_s());
+        assert!(strict.verify(privkey.derive(), &[i], &sig).is_ok());
+    }
+}
+
 /// Assert that valid Schnorr signatures verify successfully.
 #[test]
 fn schnorr_valid() {
@@ -90,6 +268,50 @@ fn schnorr_valid() {
     assert!(schnorr.verify(pubkey, &data, &sig).is_err());
 }
 
+/// Assert that Schnorr signatures made through [`SignatureScheme`] are
+/// deterministic, while randomized signatures differ and still verify.
+#[test]
+fn schnorr_randomized() {
+    let mut schnorr = Schnorr::new(Secp256k1::default(), Sha256::default());
+    let privkey = rand_privkey();
+    let pubkey = privkey.derive();
+    let data = (0u8..100).collect_vec();
+
+    let a = schnorr.sign(privkey.clone(), &data);
+    let b = schnorr.sign(privkey.clone(), &data);
+    assert_eq!((a.s(), a.e()), (b.s(), b.e()));
+
+    let mut rng = rng();
+    let c = schnorr.sign_randomized(privkey.clone(), &[], &data, &mut rng);
+    let d = schnorr.sign_randomized(privkey, &[], &data, &mut rng);
+    assert_ne!((c.s(), c.e()), (a.s(), a.e()));
+    assert_ne!((c.s(), c.e()), (d.s(), d.e()));
+    assert!(schnorr.verify(pubkey, &data, &c).is_ok());
+    assert!(schnorr.verify(pubkey, &data, &d).is_ok());
+}
+
+/// Assert that one random number generator can be shared by several schemes,
+/// since it is only borrowed for the duration of each call.
+#[test]
+fn shared_rng() {
+    let mut schnorr = Schnorr::new(Secp256k1::default(), Sha256::default());
+    let mut sag = SchnorrSag::new(Secp256k1::default(), Sha256::default());
+    let privkey = rand_privkey();
+    let decoys = [rand_pubkey(), rand_pubkey()];
+    let data = (0u8..100).collect_vec();
+    let mut rng = rng();
+
+    let sig = schnorr.sign_randomized(privkey.clone(), &[], &data, &mut rng);
+    assert!(schnorr.verify(privkey.derive(), &data, &sig).is_ok());
+    let ring = sag.sign(privkey.clone(), &decoys, &data, &mut rng);
+    assert!(sag.verify(&data, &ring).is_ok());
+    let sig = schnorr.sign_randomized(privkey.clone(), &[], &data, &mut rng);
+    assert!(schnorr.verify(privkey.derive(), &data, &sig).is_ok());
+
+    // The generator can still be used directly afterwards.
+    assert_eq!(rng.bytes().take(16).count(), 16);
+}
+
 /// Assert that valid Schnorr signatures fail to verify with an incorrect
 /// pubkey.
 #[test]
@@ -124,6 +346,36 @@ fn schnorr_invalid_signature() {
     assert!(schnorr.verify(pubkey, &data, &sig).is_err());
 }
 
+/// Assert that a batch of 20 valid Schnorr signatures passes [batch
+/// verification](Schnorr::verify_batch), and that corrupting any single one of
+/// them makes the batch fail and reports it.
+#[test]
+fn schnorr_batch() {
+    let mut schnorr = Schnorr::new(Secp256k1::default(), Sha256::default());
+    assert_eq!(schnorr.verify_batch(&[]), Ok(()));
+
+    let messages: Vec<_> = (0..20u64).map(u64::to_le_bytes).collect();
+    let mut items: Vec<_> = messages
+        .iter()
+        .map(|msg| {
+            let key = rand_privkey();
+            (key.derive(), &msg[..], schnorr.sign(key, msg))
+        })
+        .collect();
+    assert_eq!(schnorr.verify_batch(&items), Ok(()));
+
+    for i in 0..items.len() {
+        let (key, msg, sig) = items[i];
+        items[i].2 = SchnorrSignature::new(sig.s().add(Num::ONE, Secp256k1::N), sig.e()).unwrap();
+        assert_eq!(schnorr.verify_batch(&items), Err(InvalidBatch(vec![i])));
+        items[i] = (key, msg, sig);
+    }
+
+    items[3].1 = b"other";
+    items[11].1 = b"other";
+    assert_eq!(schnorr.verify_batch(&items), Err(InvalidBatch(vec![3, 11])));
+}
+
 /// Assert that valid Schnorr multisigs verify successfully.
 #[test]
 fn multi_schnorr_valid() {
@@ -179,21 +431,956 @@ fn multi_schnorr_invalid_sig() {
     assert!(schnorr.verify(&[pubkey1, pubkey2], &data, &sig).is_err());
 }
 
+/// Regression test: the aggregate randomness R = -2G has the same
+/// x-coordinate as 2G. The full aggregate point must be retained, and the
+/// resulting multisig must still verify.
+#[test]
+fn multi_schnorr_negated_randomness() {
+    let hash = Sha256::default();
+    let r1 = Num::ONE;
+    let r2 = Secp256k1::N.sub(Num::THREE, Secp256k1::N);
+    let randomness =
+        SchnorrRandomness::new(&hash, r1, &[SchnorrRandomness::commit(&hash, r2)]).unwrap();
+
+    let minus_two = Secp256k1::N.sub(Num::TWO, Secp256k1::N) * Secp256k1::g();
+    let two = Num::TWO * Secp256k1::g();
+    assert_eq!(randomness.total(), minus_two);
+    assert_ne!(randomness.total(), two);
+    assert_eq!(
+        Some(randomness.x()),
+        match two.coordinates() {
+            ecc::Coordinates::Finite(x, _) => Some(x),
+            ecc::Coordinates::Infinity => None,
+        }
+    );
+
+    let MultiSchnorrSetup {
+        pubkey1,
+        pubkey2,
+        sig,
+        data,
+        mut schnorr,
+    } = multi_schnorr_setup_with(r1, r2);
+    assert!(schnorr.verify(&[pubkey1, pubkey2], &data, &sig).is_ok());
+}
+
+/// Assert that invalid multisig randomness is rejected with the appropriate
+/// error.
+#[test]
+fn multi_schnorr_invalid_randomness() {
+    let hash = Sha256::default();
+    let r1 = rand_scalar();
+    let r2 = rand_scalar();
+    let (point2, commitment2) = SchnorrRandomness::<Secp256k1>::commit(&hash, r2);
+    let (point3, commitment3) = SchnorrRandomness::<Secp256k1>::commit(&hash, rand_scalar());
+
+    assert_eq!(
+        SchnorrRandomness::new(&hash, Num::ZERO, &[(point2, commitment2)]).unwrap_err(),
+        InvalidSchnorrRandomness::InvalidLocal
+    );
+    assert_eq!(
+        SchnorrRandomness::new(&hash, Secp256k1::N, &[(point2, commitment2)]).unwrap_err(),
+        InvalidSchnorrRandomness::InvalidLocal
+    );
+    assert_eq!(
+        SchnorrRandomness::new(
+            &hash,
+            r1,
+            &[
+                (point2, commitment2),
+                (point2, commitment3),
+                (point3, commitment3)
+            ]
+        )
+        .unwrap_err(),
+        InvalidSchnorrRandomness::CommitmentMismatch(1)
+    );
+
+    // The aggregate of r and -r is infinity.
+    let minus_r1 = Secp256k1::N.sub(r1, Secp256k1::N);
+    assert_eq!(
+        SchnorrRandomness::<Secp256k1>::new(
+            &hash,
+            r1,
+            &[SchnorrRandomness::commit(&hash, minus_r1)]
+        )
+        .unwrap_err(),
+        InvalidSchnorrRandomness::Infinity
+    );
+}
+
+/// Assert that nonces exchanged as [commitments](NonceCommitment) followed by
+/// reveals combine into the same randomness for every actor, and into a valid
+/// multisig, while a tampered reveal and a missing one are rejected with
+/// different errors.
+#[test]
+fn multi_schnorr_nonce_commitments() {
+    let hash = Sha256::default();
+    let mut rng = rng();
+    let mut commit = || NonceCommitment::<Secp256k1, Sha256>::commit(&hash, &mut rng);
+    let [(nonce1, t1), (nonce2, t2), (nonce3, t3)] = [(); 3].map(|()| commit());
+    let (probe1, _) = commit();
+    let (probe2, _) = commit();
+    let [r1, r2, r3] = [&nonce1, &nonce2, &nonce3].map(SchnorrNonce::reveal);
+
+    assert_eq!(
+        SchnorrRandomness::from_reveals(&hash, probe1, &[t2, t3], &[r2, r2]).unwrap_err(),
+        InvalidSchnorrRandomness::CommitmentMismatch(1)
+    );
+    let missing = SchnorrRandomness::from_reveals(&hash, probe2, &[t2, t3], &[r2]).unwrap_err();
+    assert_eq!(
+        missing,
+        InvalidSchnorrRandomness::RevealCount {
+            commitments: 2,
+            reveals: 1
+        }
+    );
+    assert_eq!(missing.to_string(), "1 reveals for 2 commitments");
+
+    let randomness = [
+        SchnorrRandomness::from_reveals(&hash, nonce1, &[t2, t3], &[r2, r3]).unwrap(),
+        SchnorrRandomness::from_reveals(&hash, nonce2, &[t1, t3], &[r1, r3]).unwrap(),
+        SchnorrRandomness::from_reveals(&hash, nonce3, &[t1, t2], &[r1, r2]).unwrap(),
+    ];
+    for r in &randomness {
+        assert_eq!(r.total(), r1 + r2 + r3);
+    }
+
+    let privkeys = [rand_privkey(), rand_privkey(), rand_privkey()];
+    let pubkeys = privkeys.iter().map(|k| k.derive()).collect_vec();
+    let agg = key_agg(&pubkeys);
+    let mut schnorr = multi_schnorr();
+    let mut session = schnorr.start_session(&agg, randomness[0].clone(), b"msg");
+    for (key, r) in privkeys.into_iter().zip(randomness) {
+        let partial = schnorr.sign_partial(key, &agg, &session, r).unwrap();
+        session.contribute(partial).unwrap();
+    }
+    let sig = session.finalize().unwrap();
+    assert!(schnorr.verify(&pubkeys, b"msg", &sig).is_ok());
+}
+
 #[test]
 fn sag_valid() {
     let privkey = rand_privkey();
     let decoy1 = rand_pubkey();
     let decoy2 = rand_pubkey();
     let msg = (0u8..100).collect_vec();
-    let mut sag = SchnorrSag::new(
-        Secp256k1::default(),
-        Sha256::default(),
-        Fortuna::new(NoEntropy, Aes256::default(), Sha256::default()).unwrap(),
-    );
-    let sig = sag.sign(privkey, &[decoy1, decoy2], &msg);
+    let mut sag = SchnorrSag::new(Secp256k1::default(), Sha256::default());
+    let sig = sag.sign(privkey, &[decoy1, decoy2], &msg, &mut rng());
     assert!(sag.verify(&msg, &sig).is_ok());
 }
 
+/// Assert that valid LSAG signatures verify successfully, and fail to verify
+/// for a different message.
+#[test]
+fn lsag_valid() {
+    let privkey = rand_privkey();
+    let decoys = [rand_pubkey(), rand_pubkey()];
+    let msg = (0u8..100).collect_vec();
+    let mut lsag = SchnorrLsag::new(Secp256k1::default(), Sha256::default());
+    let sig = lsag.sign(privkey, &decoys, &msg, &mut rng());
+    assert!(lsag.verify(&msg, &sig).is_ok());
+    assert!(lsag.verify(b"other", &sig).is_err());
+    assert!(lsag.verify_with_context(b"ctx", &msg, &sig).is_err());
+}
+
+/// Assert that LSAG signatures by the same private key have the same key image,
+/// whatever the message and the decoys, that signatures by different keys have
+/// different key images, and that the key image is $pH_p(P)$.
+#[docext::docext]
+#[test]
+fn lsag_linked() {
+    let scalar = rand_scalar();
+    let privkey = ecc::PrivateKey::<Secp256k1>::new(scalar).unwrap();
+    let other = rand_privkey();
+    let mut rng = rng();
+    let mut lsag = SchnorrLsag::new(Secp256k1::default(), Sha256::default());
+    let sig1 = lsag.sign(privkey.clone(), &[rand_pubkey()], b"msg 1", &mut rng);
+    let sig2 = lsag.sign(
+        privkey.clone(),
+        &[rand_pubkey(), rand_pubkey()],
+        b"msg 2",
+        &mut rng,
+    );
+    let sig3 = lsag.sign(other, &[privkey.derive()], b"msg 1", &mut rng);
+    assert!(lsag.verify(b"msg 1", &sig1).is_ok());
+    assert!(lsag.verify(b"msg 2", &sig2).is_ok());
+    assert!(lsag.verify(b"msg 1", &sig3).is_ok());
+
+    assert_eq!(lsag.key_image(&sig1), lsag.key_image(&sig2));
+    assert!(lsag.linked(&sig1, &sig2));
+    assert_ne!(lsag.key_image(&sig1), lsag.key_image(&sig3));
+    assert!(!lsag.linked(&sig1, &sig3));
+
+    let base = ecc::hash_to_point::<Secp256k1, _, 32>(
+        &Sha256::default(),
+        &privkey.derive().to_sec1_bytes(true),
+    );
+    assert_eq!(sig1.key_image(), scalar * base);
+}
+
+/// Assert that LSAG refuses to sign with a ring which lists the signer's pubkey
+/// as a decoy as well.
+#[test]
+#[should_panic = "the signer's pubkey is listed as a decoy"]
+fn lsag_signer_as_decoy() {
+    let privkey = rand_privkey();
+    let decoys = [rand_pubkey(), privkey.derive()];
+    let mut lsag = SchnorrLsag::new(Secp256k1::default(), Sha256::default());
+    lsag.sign(privkey, &decoys, b"msg", &mut rng());
+}
+
+/// Assert that the signer of an LSAG signature can't be told from the position
+/// of his pubkey in the ring, and that the key image isn't one of the pubkeys.
+#[test]
+fn lsag_anonymous() {
+    let privkey = rand_privkey();
+    let decoys = [rand_pubkey(), rand_pubkey(), rand_pubkey()];
+    let mut rng = rng();
+    let mut lsag = SchnorrLsag::new(Secp256k1::default(), Sha256::default());
+    let positions = (0..20)
+        .map(|_| {
+            let sig = lsag.sign(privkey.clone(), &decoys, b"msg", &mut rng);
+            assert!(lsag.verify(b"msg", &sig).is_ok());
+            assert!(sig.keys().iter().all(|key| key.point() != sig.key_image()));
+            sig.keys()
+                .iter()
+                .position(|&key| key == privkey.derive())
+                .unwrap()
+        })
+        .collect_vec();
+    assert!(positions.iter().any(|&i| i != positions[0]));
+}
+
+/// Assert that Schnorr signatures round-trip through their byte encoding, and
+/// that flipping a bit of the encoding makes it fail to decode or to verify.
+#[test]
+fn schnorr_bytes() {
+    let SchnorrSetup {
+        pubkey,
+        sig,
+        data,
+        mut schnorr,
+    } = schnorr_setup();
+    let bytes = sig.to_bytes();
+    let decoded = SchnorrSignature::<Secp256k1, Sha256>::from_bytes(&bytes).unwrap();
+    assert_eq!((decoded.s(), decoded.e()), (sig.s(), sig.e()));
+    assert!(schnorr.verify(pubkey, &data, &decoded).is_ok());
+
+    // A few bits of each component, since verification is slow.
+    for bit in [0, 7, 100, 255, 256, 300, 511] {
+        let mut flipped = bytes;
+        flipped[bit / 8] ^= 1 << (bit % 8);
+        if let Ok(sig) = SchnorrSignature::from_bytes(&flipped) {
+            assert!(schnorr.verify(pubkey, &data, &sig).is_err(), "bit {bit}");
+        }
+    }
+
+    let mut unreduced = bytes;
+    unreduced[32..].copy_from_slice(&Secp256k1::N.to_be_bytes());
+    assert!(SchnorrSignature::<Secp256k1, Sha256>::from_bytes(&unreduced).is_err());
+}
+
+/// Assert that SAG signatures round-trip through their byte encoding, and that
+/// flipping a bit of any field makes it fail to decode or to verify.
+#[test]
+fn sag_bytes() {
+    let privkey = rand_privkey();
+    let msg = (0u8..100).collect_vec();
+    let mut sag = SchnorrSag::new(Secp256k1::default(), Sha256::default());
+    let sig = sag.sign(privkey, &[rand_pubkey(), rand_pubkey()], &msg, &mut rng());
+    let bytes = sig.to_bytes();
+    assert_eq!(bytes.len(), 1 + 32 + 4 + 3 * (32 + 33));
+    let decoded = SchnorrSagSignature::<Secp256k1>::from_bytes(&bytes).unwrap();
+    assert_eq!(decoded.to_bytes(), bytes);
+    assert_eq!(decoded.keys(), sig.keys());
+    assert!(sag.verify(&msg, &decoded).is_ok());
+
+    // A bit of each field: the version, c, the length, the r values, and the
+    // prefix and x-coordinate of the keys.
+    for i in [0, 1, 32, 36, 37, 100, 133, 134, 231] {
+        let mut flipped = bytes.clone();
+        flipped[i] ^= 1 << (i % 8);
+        if let Ok(sig) = SchnorrSagSignature::from_bytes(&flipped) {
+            assert!(sag.verify(&msg, &sig).is_err(), "byte {i}");
+        }
+    }
+
+    assert_eq!(
+        SchnorrSagSignature::<Secp256k1>::from_bytes(&bytes[..bytes.len() - 1]).unwrap_err(),
+        SchnorrSagError::Malformed
+    );
+    let mut empty = bytes[..33].to_vec();
+    empty.extend(0u32.to_be_bytes());
+    assert_eq!(
+        SchnorrSagSignature::<Secp256k1>::from_bytes(&empty).unwrap_err(),
+        SchnorrSagError::Malformed
+    );
+    let mut unreduced = bytes.clone();
+    unreduced[1..33].copy_from_slice(&Secp256k1::N.to_be_bytes());
+    assert_eq!(
+        SchnorrSagSignature::<Secp256k1>::from_bytes(&unreduced).unwrap_err(),
+        SchnorrSagError::Malformed
+    );
+    let mut version = bytes;
+    version[0] = 2;
+    assert_eq!(
+        SchnorrSagSignature::<Secp256k1>::from_bytes(&version).unwrap_err(),
+        SchnorrSagError::UnknownVersion(2)
+    );
+}
+
+/// Assert that ECDSA signatures only verify under the context they were made
+/// with.
+#[test]
+fn ecdsa_context() {
+    let mut ecdsa = Ecdsa::new(Secp256k1::default(), Sha3_256::default());
+    let privkey = rand_privkey();
+    let pubkey = privkey.derive();
+    let data = (0u8..100).collect_vec();
+
+    let sig_a = ecdsa.sign_with_context(privkey.clone(), b"A", &data);
+    assert!(ecdsa
+        .verify_with_context(pubkey, b"A", &data, &sig_a)
+        .is_ok());
+    assert!(ecdsa
+        .verify_with_context(pubkey, b"B", &data, &sig_a)
+        .is_err());
+    assert!(ecdsa.verify(pubkey, &data, &sig_a).is_err());
+
+    let sig = ecdsa.sign(privkey, &data);
+    assert!(ecdsa.verify_with_context(pubkey, &[], &data, &sig).is_ok());
+    assert!(ecdsa
+        .verify_with_context(pubkey, b"A", &data, &sig)
+        .is_err());
+}
+
+/// Assert that Schnorr signatures only verify under the context they were
+/// made with, and that the context is not simply concatenated with the
+/// message.
+#[test]
+fn schnorr_context() {
+    let SchnorrSetup {
+        pubkey,
+        sig,
+        data,
+        mut schnorr,
+    } = schnorr_setup();
+    assert!(schnorr
+        .verify_with_context(pubkey, b"A", &data, &sig)
+        .is_err());
+
+    let privkey = rand_privkey();
+    let pubkey = privkey.derive();
+    let sig_a = schnorr.sign_with_context(privkey.clone(), b"A", &data);
+    assert!(schnorr
+        .verify_with_context(pubkey, b"A", &data, &sig_a)
+        .is_ok());
+    assert!(schnorr
+        .verify_with_context(pubkey, b"B", &data, &sig_a)
+        .is_err());
+    assert!(schnorr.verify(pubkey, &data, &sig_a).is_err());
+
+    let sig = schnorr.sign_with_context(privkey, b"ab", b"c");
+    assert!(schnorr
+        .verify_with_context(pubkey, b"ab", b"c", &sig)
+        .is_ok());
+    assert!(schnorr
+        .verify_with_context(pubkey, b"a", b"bc", &sig)
+        .is_err());
+}
+
+/// Assert that Schnorr multisig partials made under mismatched contexts don't
+/// aggregate to a valid multisig.
+#[test]
+fn multi_schnorr_context() {
+    let mut schnorr = MultiSchnorr::new(Secp256k1::default(), Sha256::default());
+    let hash = Sha256::default();
+    let (r1, r2) = (rand_scalar(), rand_scalar());
+    let commitment1 = SchnorrRandomness::<Secp256k1>::commit(&hash, r1);
+    let commitment2 = SchnorrRandomness::<Secp256k1>::commit(&hash, r2);
+    let randomness1 = SchnorrRandomness::new(&hash, r1, &[commitment2]).unwrap();
+    let randomness2 = SchnorrRandomness::new(&hash, r2, &[commitment1]).unwrap();
+    let privkey1 = rand_privkey();
+    let privkey2 = rand_privkey();
+    let pubkeys = vec![privkey1.derive(), privkey2.derive()];
+    let agg = key_agg(&pubkeys);
+    let data = (0..100u8).collect_vec();
+
+    let sign = |schnorr: &mut MultiSchnorr<_, _>, context1: &[u8], context2: &[u8]| {
+        let sig = schnorr.accumulate_with_context(
+            (privkey1.clone(), &agg, randomness1.clone()),
+            context1,
+            &data,
+            Default::default(),
+        );
+        schnorr.accumulate_with_context(
+            (privkey2.clone(), &agg, randomness2.clone()),
+            context2,
+            &data,
+            sig,
+        )
+    };
+
+    let sig = sign(&mut schnorr, b"A", b"A");
+    let sig = sig.finalize().unwrap();
+    assert!(schnorr
+        .verify_with_context(&pubkeys, b"A", &data, &sig)
+        .is_ok());
+    assert!(schnorr
+        .verify_with_context(&pubkeys, b"B", &data, &sig)
+        .is_err());
+    assert!(schnorr.verify(&pubkeys, &data, &sig).is_err());
+
+    // Partials made under different contexts have different challenges, so
+    // they can't even be aggregated.
+    let sig = sign(&mut schnorr, b"A", b"B");
+    assert!(sig.finalize().is_err());
+    let sig = sign(&mut schnorr, b"A", &[]);
+    assert!(sig.finalize().is_err());
+}
+
+/// Assert that Schnorr signatures with a zero component can't be constructed,
+/// since they would not depend on the private key or on the challenge.
+#[test]
+fn schnorr_zero_components() {
+    let e = rand_scalar();
+    assert!(SchnorrSignature::<Secp256k1, Sha256>::new(Num::ZERO, e).is_err());
+    assert!(SchnorrSignature::<Secp256k1, Sha256>::new(e, Num::ZERO).is_err());
+    assert!(SchnorrSignature::<Secp256k1, Sha256>::new(Num::ZERO, Num::ZERO).is_err());
+    assert!(SchnorrSignature::<Secp256k1, Sha256>::new(Secp256k1::N, e).is_err());
+    assert!(SchnorrSignature::<Secp256k1, Sha256>::new(e, e).is_ok());
+}
+
+/// Assert that Schnorr signing moves on to the next seed when the nonce is
+/// zero, so that $kG$ is the point at infinity.
+#[docext::docext]
+#[test]
+fn schnorr_retry_infinite_nonce() {
+    let privkey = rand_privkey();
+    let hash = FixedOutputHash::new(Sha256::default(), [Some([0; 32])]);
+    let sig = Schnorr::new(Secp256k1::default(), &hash).sign(privkey.clone(), b"msg");
+    assert_eq!(hash.remaining(), 0);
+
+    // The script is used up, so the hash passes through to SHA-256.
+    let mut schnorr = Schnorr::new(Secp256k1::default(), &hash);
+    assert!(schnorr.verify(privkey.derive(), b"msg", &sig).is_ok());
+    assert_ne!(schnorr.sign(privkey, b"msg").s(), sig.s());
+}
+
+/// Assert that Schnorr signing moves on to the next seed when the challenge
+/// $e$ is zero, which would make the signature independent of the public key.
+#[docext::docext]
+#[test]
+fn schnorr_retry_zero_challenge() {
+    let privkey = rand_privkey();
+    // The nonce takes one hash, and the challenge two: the seed of the
+    // transcript, and its output block.
+    let hash = FixedOutputHash::new(Sha256::default(), [None, None, Some([0; 32])]);
+    let sig = Schnorr::new(Secp256k1::default(), &hash).sign(privkey.clone(), b"msg");
+    assert_eq!(hash.remaining(), 0);
+
+    // The script is used up, so the hash passes through to SHA-256.
+    let mut schnorr = Schnorr::new(Secp256k1::default(), &hash);
+    assert!(schnorr.verify(privkey.derive(), b"msg", &sig).is_ok());
+    assert_ne!(schnorr.sign(privkey, b"msg").s(), sig.s());
+}
+
+/// Assert that randomized Schnorr signing draws a fresh seed of 32 bytes for
+/// every attempt, so a rejected nonce costs a second seed.
+#[test]
+fn schnorr_randomized_retry() {
+    let privkey = rand_privkey();
+    let hash = FixedOutputHash::new(Sha256::default(), [Some([0; 32])]);
+    let mut rng = StreamRng(vec![7; 64]);
+    let sig = Schnorr::new(Secp256k1::default(), &hash).sign_randomized(
+        privkey.clone(),
+        &[],
+        b"msg",
+        &mut rng,
+    );
+    assert!(Schnorr::new(Secp256k1::default(), &hash)
+        .verify(privkey.derive(), b"msg", &sig)
+        .is_ok());
+}
+
+/// With a single seed, the retry runs out of randomness.
+#[test]
+#[should_panic(expected = "StreamRng exhausted: 32 bytes requested, 0 left")]
+fn schnorr_randomized_retry_exhausted() {
+    let hash = FixedOutputHash::new(Sha256::default(), [Some([0; 32])]);
+    let mut rng = StreamRng(vec![7; 32]);
+    Schnorr::new(Secp256k1::default(), &hash).sign_randomized(
+        rand_privkey(),
+        &[],
+        b"msg",
+        &mut rng,
+    );
+}
+
+/// Assert that an empty Schnorr multisig aggregate is not a signature, and
+/// that the aggregate only becomes one when finalized.
+#[test]
+fn multi_schnorr_partial_aggregate() {
+    let MultiSchnorrSetup {
+        pubkey1,
+        pubkey2,
+        data,
+        schnorr,
+        ..
+    } = multi_schnorr_setup();
+    let empty = PartialAggregate::<Secp256k1, Sha256>::default();
+    assert_eq!(empty.signers(), 0);
+    assert!(empty.finalize().is_err());
+    assert!(schnorr.aggregate(&[pubkey1, pubkey2], &[]).is_err());
+
+    // A single signer out of two aggregates into a well-formed signature,
+    // which doesn't verify for the pair.
+    let mut schnorr = schnorr;
+    let hash = Sha256::default();
+    let (r1, r2) = (rand_scalar(), rand_scalar());
+    let randomness = SchnorrRandomness::new(
+        &hash,
+        r1,
+        &[SchnorrRandomness::<Secp256k1>::commit(&hash, r2)],
+    )
+    .unwrap();
+    let privkey = rand_privkey();
+    let pubkeys = vec![privkey.derive(), pubkey2];
+    let partial = schnorr.accumulate((privkey, &key_agg(&pubkeys), randomness), &data, empty);
+    assert_eq!(partial.signers(), 1);
+    let sig = partial.finalize().unwrap();
+    assert!(schnorr.verify(&pubkeys, &data, &sig).is_err());
+    assert!(schnorr.verify(&[pubkey1, pubkey2], &data, &sig).is_err());
+}
+
+/// Assert that Schnorr multisigs signed in two rounds verify, and that the
+/// result doesn't depend on the order of the messages in either round, nor on
+/// the order in which the actors sign.
+#[test]
+fn multi_schnorr_rounds_order() {
+    let mut schnorr = multi_schnorr();
+    let mut rng = rng();
+    let privkeys = [rand_privkey(), rand_privkey()];
+    let pubkeys = privkeys.clone().map(|k| k.derive());
+    let data = (0..100u8).collect_vec();
+
+    let (nonce1, msg1) = schnorr.round1(&privkeys[0], &mut rng);
+    let (nonce2, msg2) = schnorr.round1(&privkeys[1], &mut rng);
+    let share1 = schnorr
+        .round2(&privkeys[0], nonce1, &pubkeys, &[msg1, msg2], &data)
+        .unwrap();
+    let share2 = schnorr
+        .round2(
+            &privkeys[1],
+            nonce2,
+            &[pubkeys[1], pubkeys[0]],
+            &[msg2, msg1],
+            &data,
+        )
+        .unwrap();
+    assert_eq!(share1.e(), share2.e());
+
+    let sig = schnorr.aggregate(&pubkeys, &[share1, share2]).unwrap();
+    assert!(schnorr.verify(&pubkeys, &data, &sig).is_ok());
+    let swapped = schnorr.aggregate(&pubkeys, &[share2, share1]).unwrap();
+    assert_eq!((swapped.s(), swapped.e()), (sig.s(), sig.e()));
+    assert!(schnorr
+        .verify(&[pubkeys[1], pubkeys[0]], &data, &sig)
+        .is_ok());
+
+    // Accumulating the signatures in either order gives the same multisig.
+    let SessionSetup {
+        privkeys,
+        pubkeys,
+        randomness,
+        data,
+    } = session_setup();
+    let agg = key_agg(&pubkeys);
+    let first = (privkeys[0].clone(), &agg, randomness[0].clone());
+    let second = (privkeys[1].clone(), &agg, randomness[1].clone());
+    let forward = schnorr.accumulate(first.clone(), &data, Default::default());
+    let forward = schnorr.accumulate(second.clone(), &data, forward);
+    let backward = schnorr.accumulate(second, &data, Default::default());
+    let backward = schnorr.accumulate(first, &data, backward);
+    let (forward, backward) = (forward.finalize().unwrap(), backward.finalize().unwrap());
+    assert_eq!((forward.s(), forward.e()), (backward.s(), backward.e()));
+    assert!(schnorr.verify(&pubkeys, &data, &forward).is_ok());
+}
+
+/// The pubkeys are aggregated in their canonical order, so every actor can list
+/// them in any order, and the multisig verifies under every permutation.
+#[test]
+fn multi_schnorr_key_permutations() {
+    let mut schnorr = multi_schnorr();
+    let mut rng = rng();
+    let privkeys = [rand_privkey(), rand_privkey(), rand_privkey()];
+    let pubkeys = privkeys.clone().map(|k| k.derive());
+    let data = b"permuted";
+    let permutations = [[0, 1, 2], [2, 1, 0], [1, 2, 0], [2, 0, 1]];
+
+    let round1 = privkeys.clone().map(|k| schnorr.round1(&k, &mut rng));
+    let msgs = round1.iter().map(|(_, msg)| *msg).collect_vec();
+    let shares = round1
+        .into_iter()
+        .zip(privkeys)
+        .zip(permutations)
+        .map(|(((nonce, _), key), order)| {
+            let keys = order.map(|i| pubkeys[i]);
+            let msgs = order.map(|i| msgs[i]);
+            schnorr.round2(&key, nonce, &keys, &msgs, data).unwrap()
+        })
+        .collect_vec();
+    assert!(shares.iter().all(|share| share.e() == shares[0].e()));
+
+    let sig = schnorr.aggregate(&pubkeys, &shares).unwrap();
+    for order in permutations {
+        let keys = order.map(|i| pubkeys[i]);
+        assert!(schnorr.verify(&keys, data, &sig).is_ok());
+    }
+}
+
+/// Assert that a Schnorr multisig can't be aggregated unless every actor has
+/// contributed exactly once, and that a partial aggregate doesn't verify even
+/// when it is aggregated for fewer pubkeys.
+#[test]
+fn multi_schnorr_rounds_incomplete() {
+    let mut schnorr = multi_schnorr();
+    let mut rng = rng();
+    let privkeys = [rand_privkey(), rand_privkey()];
+    let pubkeys = privkeys.clone().map(|k| k.derive());
+    let data = (0..100u8).collect_vec();
+
+    let (nonce1, msg1) = schnorr.round1(&privkeys[0], &mut rng);
+    let (nonce2, msg2) = schnorr.round1(&privkeys[1], &mut rng);
+    let share1 = schnorr
+        .round2(&privkeys[0], nonce1, &pubkeys, &[msg1, msg2], &data)
+        .unwrap();
+    let share2 = schnorr
+        .round2(&privkeys[1], nonce2, &pubkeys, &[msg1, msg2], &data)
+        .unwrap();
+
+    assert!(schnorr.aggregate(&pubkeys, &[]).is_err());
+    assert!(schnorr.aggregate(&pubkeys, &[share1]).is_err());
+    assert!(schnorr.aggregate(&pubkeys, &[share1, share1]).is_err());
+    assert!(schnorr
+        .aggregate(&pubkeys, &[share1, share2, share2])
+        .is_err());
+
+    // An aggregator who leaves out the second actor gets a well-formed
+    // signature, which verifies for neither set of pubkeys.
+    let forced = schnorr.aggregate(&pubkeys[..1], &[share1]).unwrap();
+    assert!(schnorr.verify(&pubkeys, &data, &forced).is_err());
+    assert!(schnorr.verify(&pubkeys[..1], &data, &forced).is_err());
+}
+
+/// Assert that the second round rejects first-round messages which don't
+/// match the signers.
+#[test]
+fn multi_schnorr_rounds_invalid() {
+    let mut schnorr = multi_schnorr();
+    let mut rng = rng();
+    let privkeys = [rand_privkey(), rand_privkey()];
+    let pubkeys = privkeys.clone().map(|k| k.derive());
+    let data = (0..100u8).collect_vec();
+    let (_, msg2) = schnorr.round1(&privkeys[1], &mut rng);
+
+    let mut round2 = |msgs: &dyn Fn(_) -> Vec<_>, key: &ecc::PrivateKey<Secp256k1>| {
+        let (nonce, msg) = schnorr.round1(&privkeys[0], &mut rng);
+        schnorr
+            .round2(key, nonce, &pubkeys, &msgs(msg), &data)
+            .unwrap_err()
+    };
+    assert_eq!(
+        round2(&|msg| vec![msg, msg2], &rand_privkey()),
+        InvalidRound::UnknownSigner
+    );
+    assert_eq!(
+        round2(&|_| vec![msg2, msg2], &privkeys[0]),
+        InvalidRound::MissingNonce
+    );
+    assert_eq!(
+        round2(&|msg| vec![msg], &privkeys[0]),
+        InvalidRound::MissingNonce
+    );
+    assert_eq!(
+        round2(&|msg| vec![msg, msg], &privkeys[0]),
+        InvalidRound::MissingNonce
+    );
+    let (point, mut commitment) = msg2;
+    commitment[0] ^= 1;
+    assert_eq!(
+        round2(&|msg| vec![msg, (point, commitment)], &privkeys[0]),
+        InvalidRound::Randomness(InvalidSchnorrRandomness::CommitmentMismatch(0))
+    );
+}
+
+/// Assert that a signing session with a zero challenge, or a complete session
+/// with $s = 0$, is rejected when decoded.
+#[docext::docext]
+#[test]
+fn session_zero_components() {
+    let SessionSetup {
+        privkeys,
+        pubkeys,
+        randomness,
+        ..
+    } = session_setup();
+    let agg = key_agg(&pubkeys);
+    let mut schnorr = multi_schnorr();
+    let session = schnorr.start_session(&agg, randomness[0].clone(), b"msg");
+    let mut bytes = session.to_bytes();
+    bytes[1..33].fill(0);
+    assert_eq!(
+        SigningSession::<Secp256k1, Sha256>::from_bytes(&bytes).unwrap_err(),
+        SessionError::Malformed
+    );
+
+    let mut session = session;
+    for (key, r) in privkeys.iter().zip(randomness) {
+        let partial = schnorr
+            .sign_partial(key.clone(), &agg, &session, r)
+            .unwrap();
+        session.contribute(partial).unwrap();
+    }
+    let mut bytes = session.to_bytes();
+    bytes[1 + 32 + 65..1 + 32 + 65 + 32].fill(0);
+    assert_eq!(
+        SigningSession::<Secp256k1, Sha256>::from_bytes(&bytes).unwrap_err(),
+        SessionError::Malformed
+    );
+}
+
+/// Assert that SAG ring signatures only verify under the context they were
+/// made with.
+#[test]
+fn sag_context() {
+    let privkey = rand_privkey();
+    let decoys = [rand_pubkey(), rand_pubkey()];
+    let msg = (0u8..100).collect_vec();
+    let mut sag = SchnorrSag::new(Secp256k1::default(), Sha256::default());
+    let mut rng = rng();
+
+    let sig = sag.sign_with_context(privkey.clone(), &decoys, b"A", &msg, &mut rng);
+    assert!(sag.verify_with_context(b"A", &msg, &sig).is_ok());
+    assert!(sag.verify_with_context(b"B", &msg, &sig).is_err());
+    assert!(sag.verify(&msg, &sig).is_err());
+
+    let sig = sag.sign(privkey, &decoys, &msg, &mut rng);
+    assert!(sag.verify_with_context(b"A", &msg, &sig).is_err());
+}
+
+/// Assert that a multisig signing session can be suspended after the first
+/// signer, resumed from its encoding, and completed into a valid multisig.
+#[test]
+fn multi_schnorr_session() {
+    let SessionSetup {
+        privkeys,
+        pubkeys,
+        randomness,
+        data,
+    } = session_setup();
+    let agg = key_agg(&pubkeys);
+
+    // The first device starts the session and contributes.
+    let bytes = {
+        let mut schnorr = multi_schnorr();
+        let mut session = schnorr.start_session(&agg, randomness[0].clone(), &data);
+        let partial = schnorr
+            .sign_partial(privkeys[0].clone(), &agg, &session, randomness[0].clone())
+            .unwrap();
+        session.contribute(partial).unwrap();
+        session.to_bytes()
+    };
+
+    // The second device resumes the session from its encoding.
+    let mut schnorr = multi_schnorr();
+    let mut session = SigningSession::<Secp256k1, Sha256>::from_bytes(&bytes).unwrap();
+    assert_eq!(session.to_bytes(), bytes);
+    assert_eq!(session.missing().collect_vec(), [pubkeys[1]]);
+    let partial = schnorr
+        .sign_partial(privkeys[1].clone(), &agg, &session, randomness[1].clone())
+        .unwrap();
+    session.contribute(partial).unwrap();
+    let sig = session.finalize().unwrap();
+
+    assert!(multi_schnorr().verify(&pubkeys, &data, &sig).is_ok());
+    assert!(multi_schnorr().verify(&pubkeys, b"other", &sig).is_err());
+}
+
+/// Assert that sessions can't be finalized early, and that signers can't
+/// contribute twice or join a session they are not part of.
+#[test]
+fn multi_schnorr_session_contributions() {
+    let SessionSetup {
+        privkeys,
+        pubkeys,
+        randomness,
+        data,
+    } = session_setup();
+    let agg = key_agg(&pubkeys);
+    let mut schnorr = multi_schnorr();
+    let mut session = schnorr.start_session(&agg, randomness[0].clone(), &data);
+
+    let partial = schnorr
+        .sign_partial(privkeys[0].clone(), &agg, &session, randomness[0].clone())
+        .unwrap();
+    session.contribute(partial).unwrap();
+    assert_eq!(
+        session.contribute(partial),
+        Err(SessionError::DuplicateContribution)
+    );
+
+    let outsider = rand_privkey();
+    assert!(schnorr
+        .sign_partial(outsider.clone(), &agg, &session, randomness[1].clone())
+        .is_none());
+    let other_agg = key_agg(&[outsider.derive(), pubkeys[1]]);
+    let other = schnorr.start_session(&other_agg, randomness[0].clone(), &data);
+    let forged = schnorr
+        .sign_partial(outsider, &other_agg, &other, randomness[0].clone())
+        .unwrap();
+    assert_eq!(session.contribute(forged), Err(SessionError::UnknownSigner));
+
+    assert_eq!(session.finalize().unwrap_err(), IncompleteSession);
+}
+
+/// Assert that [partial signatures](PartialSignature) are checked against the
+/// points $R_i$ from the first round: good ones pass and aggregate into a
+/// valid multisig, while a tampered one, one attributed to the wrong signer,
+/// or one checked against the wrong $R_i$ or message is caught.
+#[docext::docext]
+#[test]
+fn multi_schnorr_verify_partial() {
+    let hash = Sha256::default();
+    let privkeys = [rand_privkey(), rand_privkey(), rand_privkey()];
+    let pubkeys = privkeys.iter().map(|k| k.derive()).collect_vec();
+    let locals = [rand_scalar(), rand_scalar(), rand_scalar()];
+    let commitments = locals.map(|r| SchnorrRandomness::<Secp256k1>::commit(&hash, r));
+    let points = commitments.map(|(point, _)| point);
+    let agg = key_agg(&pubkeys);
+    let mut schnorr = multi_schnorr();
+
+    let randomness = |i: usize| {
+        let others = (0..3)
+            .filter(|&j| j != i)
+            .map(|j| commitments[j])
+            .collect_vec();
+        SchnorrRandomness::new(&hash, locals[i], &others).unwrap()
+    };
+    let mut session = schnorr.start_session(&agg, randomness(0), b"msg");
+    let partials = (0..3)
+        .map(|i| {
+            schnorr
+                .sign_partial(privkeys[i].clone(), &agg, &session, randomness(i))
+                .unwrap()
+        })
+        .collect_vec();
+
+    for (i, partial) in partials.iter().enumerate() {
+        assert!(schnorr
+            .verify_partial(pubkeys[i], &pubkeys, &points, b"msg", partial)
+            .is_ok());
+        assert!(schnorr
+            .verify_partial(pubkeys[i], &pubkeys, &points, b"other", partial)
+            .is_err());
+        let j = (i + 1) % 3;
+        assert!(schnorr
+            .verify_partial(pubkeys[j], &pubkeys, &points, b"msg", partial)
+            .is_err());
+        let forged = PartialSignature::new(pubkeys[j], partial.s());
+        assert!(schnorr
+            .verify_partial(pubkeys[j], &pubkeys, &points, b"msg", &forged)
+            .is_err());
+        let mut swapped = points;
+        swapped.swap(i, j);
+        assert!(schnorr
+            .verify_partial(pubkeys[i], &pubkeys, &swapped, b"msg", partial)
+            .is_err());
+    }
+    assert!(schnorr
+        .verify_partial(pubkeys[0], &pubkeys, &points[1..], b"msg", &partials[0])
+        .is_err());
+
+    let bad = PartialSignature::new(pubkeys[1], partials[1].s().add(Num::ONE, Secp256k1::N));
+    assert!(schnorr
+        .verify_partial(pubkeys[1], &pubkeys, &points, b"msg", &bad)
+        .is_err());
+
+    for partial in partials {
+        session.contribute(partial).unwrap();
+    }
+    let sig = session.finalize().unwrap();
+    assert!(multi_schnorr().verify(&pubkeys, b"msg", &sig).is_ok());
+}
+
+/// Assert that corrupted session encodings are rejected.
+#[test]
+fn multi_schnorr_session_corrupted() {
+    let SessionSetup {
+        privkeys,
+        pubkeys,
+        randomness,
+        data,
+    } = session_setup();
+    let agg = key_agg(&pubkeys);
+    let mut schnorr = multi_schnorr();
+    let session = schnorr.start_session(&agg, randomness[0].clone(), &data);
+    let fresh = session.to_bytes();
+    let mut session = SigningSession::<Secp256k1, Sha256>::from_bytes(&fresh).unwrap();
+    let partial = schnorr
+        .sign_partial(privkeys[0].clone(), &agg, &session, randomness[0].clone())
+        .unwrap();
+    session.contribute(partial).unwrap();
+    let bytes = session.to_bytes();
+
+    let decode = |bytes: &[u8]| SigningSession::<Secp256k1, Sha256>::from_bytes(bytes).map(|_| ());
+    assert_eq!(decode(&bytes), Ok(()));
+    assert_eq!(
+        decode(&bytes[..bytes.len() - 1]),
+        Err(SessionError::Malformed)
+    );
+    assert_eq!(
+        decode(&[bytes.as_slice(), &[0]].concat()),
+        Err(SessionError::Malformed)
+    );
+
+    // e and s not reduced modulo N.
+    for offset in [1, 1 + 32 + 65] {
+        let mut corrupted = bytes.clone();
+        corrupted[offset..offset + 32].copy_from_slice(&[0xff; 32]);
+        assert_eq!(decode(&corrupted), Err(SessionError::Malformed));
+    }
+
+    // Points which are not on the curve: R, and the first and second pubkeys.
+    for offset in [
+        1 + 32 + 64,
+        1 + 32 + 65 + 32 + 4 + 64,
+        1 + 32 + 65 + 32 + 4 + 66 + 64,
+    ] {
+        let mut corrupted = bytes.clone();
+        corrupted[offset] ^= 1;
+        assert_eq!(decode(&corrupted), Err(SessionError::Malformed));
+    }
+
+    // An invalid contribution flag.
+    let mut corrupted = bytes.clone();
+    corrupted[1 + 32 + 65 + 32 + 4 + 65] = 2;
+    assert_eq!(decode(&corrupted), Err(SessionError::Malformed));
+
+    // A nonzero sum without any contributions.
+    let mut corrupted = bytes.clone();
+    corrupted[1 + 32 + 65 + 32 + 4 + 65] = 0;
+    corrupted[1 + 32 + 65 + 32 + 4 + 66 + 65] = 0;
+    assert_eq!(decode(&corrupted), Err(SessionError::Malformed));
+
+    // A duplicated pubkey.
+    let mut corrupted = bytes.clone();
+    let first = 1 + 32 + 65 + 32 + 4;
+    corrupted.copy_within(first..first + 65, first + 66);
+    assert_eq!(decode(&corrupted), Err(SessionError::Malformed));
+}
+
 fn ecdsa_setup() -> EcdsaSetup {
     let mut ecdsa = Ecdsa::new(Secp256k1::default(), Sha3_256::default());
     let data = (0u8..100).collect_vec();
@@ -218,11 +1405,7 @@ struct EcdsaSetup {
 
 /// Create a Schnorr signature.
 fn schnorr_setup() -> SchnorrSetup {
-    let mut schnorr = Schnorr::new(
-        Secp256k1::default(),
-        Sha256::default(),
-        Fortuna::new(NoEntropy, Aes256::default(), Sha256::default()).unwrap(),
-    );
+    let mut schnorr = Schnorr::new(Secp256k1::default(), Sha256::default());
     let data = (0u8..100).collect_vec();
     let privkey = rand_privkey();
     let pubkey = privkey.derive();
@@ -240,19 +1423,26 @@ struct SchnorrSetup {
     pubkey: PublicKey<Secp256k1>,
     sig: SchnorrSignature<Secp256k1, Sha256>,
     data: Vec<u8>,
-    schnorr: Schnorr<Secp256k1, Sha256, Fortuna<NoEntropy, Aes256, Sha256>>,
+    schnorr: Schnorr<Secp256k1, Sha256>,
 }
 
 /// Create a multisig of two keys.
 fn multi_schnorr_setup() -> MultiSchnorrSetup {
-    let mut schnorr = MultiSchnorr::new(
-        Secp256k1::default(),
-        Sha256::default(),
-        Fortuna::new(NoEntropy, Aes256::default(), Sha256::default()).unwrap(),
-    );
+    multi_schnorr_setup_with(rand_scalar(), rand_scalar())
+}
 
-    let r1 = rand_num();
-    let r2 = rand_num();
+/// Create a multisig of two keys, using the given secret random numbers.
+fn multi_schnorr_setup_with(r1: Num, r2: Num) -> MultiSchnorrSetup {
+    let mut schnorr = MultiSchnorr::new(Secp256k1::default(), Sha256::default());
+
+    // First round: exchange the commitments. Second round: exchange the public
+    // counterparts and verify them against the commitments.
+    let hash = Sha256::default();
+    let commitment1 = SchnorrRandomness::<Secp256k1>::commit(&hash, r1);
+    let commitment2 = SchnorrRandomness::<Secp256k1>::commit(&hash, r2);
+    let randomness1 = SchnorrRandomness::new(&hash, r1, &[commitment2]).unwrap();
+    let randomness2 = SchnorrRandomness::new(&hash, r2, &[commitment1]).unwrap();
+    assert_eq!(randomness1.total(), randomness2.total());
 
     let privkey1 = rand_privkey();
     let pubkey1 = privkey1.derive();
@@ -261,28 +1451,14 @@ fn multi_schnorr_setup() -> MultiSchnorrSetup {
     let pubkey2 = privkey2.derive();
 
     let data = (0..100u8).collect_vec();
+    let agg = key_agg(&[pubkey1, pubkey2]);
 
     // Sign by 1st signer.
-    let sig = schnorr.sign(
-        (
-            privkey1,
-            vec![pubkey1, pubkey2],
-            SchnorrRandomness::new(r1, &[r2 * Secp256k1::g()]).unwrap(),
-        ),
-        &data,
-        Default::default(),
-    );
+    let sig = schnorr.accumulate((privkey1, &agg, randomness1), &data, Default::default());
 
     // Sign by 2nd signer.
-    let sig = schnorr.sign(
-        (
-            privkey2,
-            vec![pubkey1, pubkey2],
-            SchnorrRandomness::new(r2, &[r1 * Secp256k1::g()]).unwrap(),
-        ),
-        &data,
-        sig,
-    );
+    let sig = schnorr.accumulate((privkey2, &agg, randomness2), &data, sig);
+    let sig = sig.finalize().unwrap();
 
     MultiSchnorrSetup {
         pubkey1,
@@ -299,18 +1475,49 @@ struct MultiSchnorrSetup {
     pubkey2: PublicKey<Secp256k1>,
     sig: SchnorrSignature<Secp256k1, Sha256>,
     data: Vec<u8>,
-    schnorr: MultiSchnorr<Secp256k1, Sha256, Fortuna<NoEntropy, Aes256, Sha256>>,
+    schnorr: MultiSchnorr<Secp256k1, Sha256>,
 }
 
-fn rand_privkey() -> ecc::PrivateKey<Secp256k1> {
-    'retry: loop {
-        match ecc::PrivateKey::new(rand_num()) {
-            Ok(key) => return key,
-            Err(_) => continue 'retry,
-        }
+/// Two signers with exchanged randomness, ready to start a signing session.
+fn session_setup() -> SessionSetup {
+    let hash = Sha256::default();
+    let (r1, r2) = (rand_scalar(), rand_scalar());
+    let commitment1 = SchnorrRandomness::<Secp256k1>::commit(&hash, r1);
+    let commitment2 = SchnorrRandomness::<Secp256k1>::commit(&hash, r2);
+    let privkeys = [rand_privkey(), rand_privkey()];
+    SessionSetup {
+        privkeys: privkeys.clone(),
+        pubkeys: privkeys.iter().map(|k| k.derive()).collect(),
+        randomness: [
+            SchnorrRandomness::new(&hash, r1, &[commitment2]).unwrap(),
+            SchnorrRandomness::new(&hash, r2, &[commitment1]).unwrap(),
+        ],
+        data: (0..100u8).collect_vec(),
     }
 }
 
+#[derive(Debug)]
+struct SessionSetup {
+    privkeys: [ecc::PrivateKey<Secp256k1>; 2],
+    pubkeys: Vec<PublicKey<Secp256k1>>,
+    randomness: [SchnorrRandomness<Secp256k1>; 2],
+    data: Vec<u8>,
+}
+
+fn multi_schnorr() -> MultiSchnorr<Secp256k1, Sha256> {
+    MultiSchnorr::new(Secp256k1::default(), Sha256::default())
+}
+
+fn key_agg(pubkeys: &[PublicKey<Secp256k1>]) -> KeyAggContext<Secp256k1, Sha256> {
+    KeyAggContext::sorted(&Sha256::default(), pubkeys).unwrap()
+}
+
+fn rand_privkey() -> ecc::PrivateKey<Secp256k1> {
+    let mut rng = rng();
+    rng.reseed(&rand::random::<[u8; 32]>());
+    ecc::PrivateKey::random(&mut rng)
+}
+
 /// Generate a random pubkey quickly.
 fn rand_pubkey() -> ecc::PublicKey<Secp256k1> {
     let n = rand::thread_rng().gen_range(1..100);
@@ -318,6 +1525,16 @@ fn rand_pubkey() -> ecc::PublicKey<Secp256k1> {
     ecc::PublicKey::new(n * Secp256k1::g()).unwrap()
 }
 
+/// Generate a random number in the range [1, N - 1].
+fn rand_scalar() -> Num {
+    loop {
+        let n = rand_num();
+        if n != Num::ZERO && n < Secp256k1::N {
+            return n;
+        }
+    }
+}
+
 fn rand_num() -> Num {
     Num::from_le_words([
         rand::random(),
@@ -0,0 +1,142 @@
+use {
+    super::invalid_curve::Cofactor,
+    crate::{
+        ecc::{Curve, Num},
+        hex,
+        Secp256k1,
+        Sha256,
+        Transcript,
+    },
+};
+
+fn transcript(hash: &Sha256) -> Transcript<&Sha256> {
+    let mut transcript = Transcript::new(hash, b"test protocol");
+    transcript.append(b"first", b"hello");
+    transcript.append(b"second", b"");
+    transcript
+}
+
+/// Challenges of a fixed transcript, computed with an independent Python
+/// implementation of the framing, so that it never changes silently.
+#[test]
+fn transcript_vectors() {
+    let sha = Sha256::default();
+    let mut transcript = transcript(&sha);
+    let mut short = [0; 16];
+    transcript.challenge_bytes(b"bytes", &mut short);
+    assert_eq!(hex::encode(&short), "a72fdcf3544055453db26d3f03305e31");
+    // Longer than one digest.
+    let mut long = [0; 40];
+    transcript.challenge_bytes(b"bytes", &mut long);
+    assert_eq!(
+        hex::encode(&long),
+        "b3b7ae412c677b778346031cc3882c5fdd816f738855aa2c88f2ca693b66818fe711df8c25d995c0"
+    );
+    assert_eq!(
+        transcript.challenge_scalar::<Secp256k1>(b"scalar"),
+        Num::from_be_bytes(
+            hex::decode("310cb8a86ab69470dd4971727791843e8cdf932a54de651ec5c2e28941bafeab")
+                .unwrap()
+                .try_into()
+                .unwrap()
+        )
+    );
+}
+
+fn challenge(transcript: &mut Transcript<&Sha256>) -> [u8; 32] {
+    let mut out = [0; 32];
+    transcript.challenge_bytes(b"challenge", &mut out);
+    out
+}
+
+#[test]
+fn transcript_labels() {
+    let sha = Sha256::default();
+    let expected = challenge(&mut transcript(&sha));
+
+    // The protocol name, the labels, the data and the order of the messages
+    // all matter.
+    let mut protocol = Transcript::new(&sha, b"other protocol");
+    protocol.append(b"first", b"hello");
+    protocol.append(b"second", b"");
+    let mut label = Transcript::new(&sha, b"test protocol");
+    label.append(b"first", b"hello");
+    label.append(b"other", b"");
+    let mut order = Transcript::new(&sha, b"test protocol");
+    order.append(b"second", b"");
+    order.append(b"first", b"hello");
+    let mut boundary = Transcript::new(&sha, b"test protocol");
+    boundary.append(b"first", b"hell");
+    boundary.append(b"second", b"o");
+    let mut moved = Transcript::new(&sha, b"test protocol");
+    moved.append(b"firs", b"thello");
+    moved.append(b"second", b"");
+    for mut transcript in [protocol, label, order, boundary, moved] {
+        assert_ne!(challenge(&mut transcript), expected);
+    }
+
+    // So does the challenge label.
+    let mut out = [0; 32];
+    transcript(&sha).challenge_bytes(b"other", &mut out);
+    assert_ne!(out, expected);
+    // And the challenge length, so a short challenge is not a prefix.
+    let mut short = [0; 16];
+    transcript(&sha).challenge_bytes(b"challenge", &mut short);
+    assert_ne!(short, expected[..16]);
+
+    // Challenges in a row are unrelated, and the transcript is deterministic.
+    let mut transcript = transcript(&sha);
+    assert_eq!(challenge(&mut transcript), expected);
+    assert_ne!(challenge(&mut transcript), expected);
+}
+
+/// Forks evolve independently of each other and of the original.
+#[test]
+fn transcript_fork() {
+    let sha = Sha256::default();
+    let base = transcript(&sha);
+    let mut left = base.clone();
+    let mut right = base.clone();
+    left.append(b"branch", b"left");
+    right.append(b"branch", b"right");
+    let left_challenge = challenge(&mut left);
+    assert_ne!(left_challenge, challenge(&mut right));
+
+    // The original is unaffected by its forks.
+    assert_eq!(
+        challenge(&mut base.clone()),
+        challenge(&mut transcript(&sha))
+    );
+    let mut again = base.clone();
+    again.append(b"branch", b"left");
+    assert_eq!(challenge(&mut again), left_challenge);
+}
+
+/// The scalar challenges on a toy curve are reduced without bias: every value
+/// below $N$ shows up, and nothing else.
+#[test]
+fn transcript_scalar() {
+    let sha = Sha256::default();
+    let mut transcript = transcript(&sha);
+    let mut counts = [0; 256];
+    for _ in 0..251 * 20 {
+        let n = transcript.challenge_scalar::<Cofactor>(b"scalar");
+        assert!(n < Cofactor::N);
+        counts[usize::from(n.to_le_bytes()[0])] += 1;
+    }
+    assert!(counts[..251].iter().all(|&count| count > 0));
+    assert!(counts[251..].iter().all(|&count| count == 0));
+
+    // The curve is part of the challenge.
+    let mut other = self::transcript(&sha);
+    let n = self::transcript(&sha).challenge_scalar::<Secp256k1>(b"scalar");
+    assert_ne!(other.challenge_scalar::<Cofactor>(b"scalar"), n);
+}
+
+#[test]
+fn transcript_debug() {
+    let sha = Sha256::default();
+    let debug = format!("{:?}", transcript(&sha));
+    assert!(debug.starts_with("Transcript { len: "));
+    assert!(!debug.contains("hello"));
+}
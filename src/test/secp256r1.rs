@@ -0,0 +1,113 @@
+use crate::{
+    ecc::{Coordinates, Curve, Group, Num, Point, PrivateKey},
+    hex,
+    Ecdsa,
+    EcdsaSignature,
+    Secp256r1,
+    Sha256,
+    SignatureScheme,
+};
+
+/// The P-256 parameters should pass [validation](Curve::validate).
+#[test]
+fn validate() {
+    assert_eq!(Secp256r1::validate(), Ok(()));
+}
+
+/// Assert that doubling and tripling the generator agree with an independent
+/// implementation, the `cryptography` Python library. Doubling is the only
+/// formula which involves $a$, which is $-3$ rather than zero on this curve.
+#[test]
+fn small_multiples() {
+    let g = Secp256r1::g();
+    let two = point(
+        "7CF27B188D034F7E8A52380304B51AC3C08969E277F21B35A60B48FC47669978",
+        "07775510DB8ED040293D9AC69F7430DBBA7DADE63CE982299E04B79D227873D1",
+    );
+    let three = point(
+        "5ECBE4D1A6330A44C8F7EF951D4BF165E6C6B721EFADA985FB41661BC6E7FD6C",
+        "8734640C4998FF7E374B06CE1A64A2ECD82AB036384FB83D9A79B127A27D5032",
+    );
+    assert_eq!(g + g, two);
+    assert_eq!(Num::TWO * g, two);
+    assert_eq!(g.scale_ct(Num::TWO), two);
+    assert_eq!(Secp256r1::mul_g(Num::TWO), two);
+    assert_eq!(g + g + g, three);
+    assert_eq!(Num::THREE * g, three);
+    assert_eq!(Secp256r1::N * g, Point::infinity());
+}
+
+/// Assert that points are checked against the P-256 equation, which has the
+/// $-3x$ term, and not the secp256k1 one.
+#[test]
+fn point_validation() {
+    let Coordinates::Finite(x, y) = Secp256r1::g().coordinates() else {
+        unreachable!()
+    };
+    assert!(Point::<Secp256r1>::new(x, y).is_ok());
+    assert!(Point::<Secp256r1>::new(x, y.add(Num::ONE, Secp256r1::P)).is_err());
+    // At x = 0 the -3x term vanishes, so this point lies on both curves.
+    let b_root = Secp256r1::B.sqrt(Secp256r1::P).unwrap();
+    assert!(Point::<Secp256r1>::new(Num::ZERO, b_root).is_ok());
+    // At x = 1, y^2 = 1 + b only lies on a curve without the -3x term.
+    let y = Num::ONE
+        .add(Secp256r1::B, Secp256r1::P)
+        .sqrt(Secp256r1::P)
+        .unwrap();
+    assert!(Point::<Secp256r1>::new(Num::ONE, y).is_err());
+}
+
+/// Assert that ECDSA over P-256 with SHA-256 reproduces the known-answer
+/// signatures from [RFC 6979](https://www.rfc-editor.org/rfc/rfc6979),
+/// appendix A.2.5, given the same nonces, and that they verify. The vectors
+/// were cross-checked against the `cryptography` Python library.
+#[test]
+fn ecdsa_known_answer() {
+    let key = PrivateKey::<Secp256r1>::new(num(
+        "C9AFA9D845BA75166B5C215767B1D6934E50C3DB36E89B127B8A622B120F6721",
+    ))
+    .unwrap();
+    let pubkey = key.derive();
+    assert_eq!(
+        pubkey.point(),
+        point(
+            "60FED4BA255A9D31C961EB74C6356D68C049B8923B61FA6CE669622E60F29FB6",
+            "7903FE1008B8BC99A41AE9E95628BC64F2F1B20C2D7E9F5177A3C294D4462299",
+        )
+    );
+    let cases = [
+        (
+            &b"sample"[..],
+            "A6E3C57DD01ABE90086538398355DD4C3B17AA873382B0F24D6129493D8AAD60",
+            "EFD48B2AACB6A8FD1140DD9CD45E81D69D2C877B56AAF991C34D0EA84EAF3716",
+            "F7CB1C942D657C41D436C7A1B6E29F65F3E900DBB9AFF4064DC4AB2F843ACDA8",
+        ),
+        (
+            &b"test"[..],
+            "D16B6AE827F17175E040871A1C7EC3500192C4C92677336EC2537ACAEE0008E0",
+            "F1ABB023518351CD71D881567B1EA663ED3EFCF6C5132B354F28D3B0B7D38367",
+            "019F4113742A2B14BD25926B49C649155F267E60D3814B4C0CC84250E46F0083",
+        ),
+    ];
+    let mut ecdsa = Ecdsa::new(Secp256r1::default(), Sha256::default());
+    for (msg, k, r, s) in cases {
+        let sig = ecdsa.sign_with_nonce(&key, msg, num(k)).unwrap();
+        assert_eq!((sig.r(), sig.s()), (num(r), num(s)));
+
+        let sig = EcdsaSignature::new(num(r), num(s)).unwrap();
+        ecdsa.verify(pubkey, msg, &sig).unwrap();
+        assert!(ecdsa.verify(pubkey, b"other", &sig).is_err());
+        let tampered = EcdsaSignature::new(num(r), num(s).add(Num::ONE, Secp256r1::N)).unwrap();
+        assert!(ecdsa.verify(pubkey, msg, &tampered).is_err());
+    }
+    let sig = ecdsa.sign(key.clone(), b"round trip");
+    ecdsa.verify(pubkey, b"round trip", &sig).unwrap();
+}
+
+fn num(hex: &str) -> Num {
+    Num::from_be_bytes(hex::decode(hex).unwrap().try_into().unwrap())
+}
+
+fn point(x: &str, y: &str) -> Point<Secp256r1> {
+    Point::new(num(x), num(y)).unwrap()
+}
@@ -0,0 +1,43 @@
+use crate::util::SmallBuf;
+
+#[test]
+fn empty() {
+    let buf = SmallBuf::<4>::new();
+    assert!(buf.is_empty());
+    assert_eq!(buf.as_ref(), b"");
+    assert!(SmallBuf::<0>::default().is_empty());
+}
+
+#[test]
+fn exact_fit() {
+    let mut buf = SmallBuf::<5>::new();
+    buf.extend_from_slice(b"ab");
+    buf.extend_from_slice(b"");
+    buf.extend_from_slice(b"cde");
+    assert_eq!(buf.as_ref(), b"abcde");
+    assert_eq!(buf.len(), 5);
+
+    let mut buf = SmallBuf::<0>::new();
+    buf.extend_from_slice(b"");
+    assert!(buf.is_empty());
+}
+
+/// A failed append reports the capacity and leaves the contents unchanged.
+#[test]
+fn overflow() {
+    let mut buf = SmallBuf::<4>::new();
+    buf.extend_from_slice(b"abc");
+    let err = buf.try_extend_from_slice(b"de").unwrap_err();
+    assert_eq!((err.capacity, err.required), (4, 5));
+    assert_eq!(err.to_string(), "buffer of 4 bytes cannot hold 5 bytes");
+    assert_eq!(buf.as_ref(), b"abc");
+    buf.try_extend_from_slice(b"d").unwrap();
+    assert_eq!(buf.as_ref(), b"abcd");
+}
+
+#[test]
+#[should_panic = "buffer of 4 bytes cannot hold 5 bytes"]
+fn overflow_panics() {
+    let mut buf = SmallBuf::<4>::new();
+    buf.extend_from_slice(b"abcde");
+}
@@ -0,0 +1,62 @@
+//! Tests for the [test doubles](crate::testing) themselves.
+
+use crate::{
+    testing::{CountingHash, FixedOutputHash, StreamRng},
+    Entropy,
+    Hash,
+    Sha256,
+};
+
+#[test]
+fn counting_hash() {
+    let hash = CountingHash::new(Sha256::default());
+    assert_eq!(hash.hash(b"abc"), Sha256::default().hash(b"abc"));
+    // A reference to the hash counts towards the same counters.
+    <&CountingHash<Sha256> as Hash>::hash(&&hash, b"hello");
+    assert_eq!((hash.calls(), hash.bytes()), (2, 8));
+    hash.reset();
+    assert_eq!((hash.calls(), hash.bytes()), (0, 0));
+}
+
+/// Scripted digests are returned in order regardless of the preimage, `None`
+/// passes through, and so does everything after the script.
+#[test]
+fn fixed_output_hash() {
+    let sha = Sha256::default();
+    let hash = FixedOutputHash::new(Sha256::default(), [Some([1; 32]), None, Some([2; 32])]);
+    assert_eq!(hash.remaining(), 3);
+    assert_eq!(hash.hash(b"abc"), [1; 32]);
+    assert_eq!(hash.hash(b"abc"), sha.hash(b"abc"));
+    assert_eq!(hash.hash(b"abc"), [2; 32]);
+    assert_eq!(hash.remaining(), 0);
+    assert_eq!(hash.hash(b"abc"), sha.hash(b"abc"));
+}
+
+#[test]
+fn stream_rng() {
+    let bytes = StreamRng(vec![1, 2, 3])
+        .into_iter()
+        .take(3)
+        .collect::<Vec<_>>();
+    assert_eq!(bytes, [1, 2, 3]);
+
+    let mut entropy = StreamRng(vec![1, 2, 3, 4, 5]);
+    let mut buf = [0; 2];
+    entropy.get(&mut buf);
+    assert_eq!(buf, [1, 2]);
+    entropy.get(&mut buf);
+    assert_eq!(buf, [3, 4]);
+    assert_eq!(entropy.0, [5]);
+}
+
+#[test]
+#[should_panic(expected = "StreamRng exhausted after 3 bytes")]
+fn stream_rng_exhausted() {
+    StreamRng(vec![1, 2, 3]).into_iter().nth(3);
+}
+
+#[test]
+#[should_panic(expected = "StreamRng exhausted: 2 bytes requested, 1 left")]
+fn stream_rng_entropy_exhausted() {
+    StreamRng(vec![1]).get(&mut [0; 2]);
+}
@@ -0,0 +1,166 @@
+//! Intermediate values reported to a tracer, checked against the examples
+//! printed in the specifications.
+
+use crate::{
+    hex,
+    sha3::{self, KECCAK_P},
+    trace::{CollectingTracer, TraceKind},
+    Aes128,
+    BlockDecrypt,
+    BlockEncrypt,
+    Hash,
+    IncrementalHash,
+    Sha1,
+    Sha256,
+    Sha3_256,
+};
+
+/// The state at the start of every round of the cipher example from Appendix
+/// B of the AES specification is the state at the end of the previous round.
+#[test]
+fn aes_appendix_b() {
+    let tracer = CollectingTracer::default();
+    let aes = Aes128::with_trace(&tracer);
+    let plaintext = block("3243f6a8885a308d313198a2e0370734");
+    let key = block("2b7e151628aed2a6abf7158809cf4f3c");
+    let ciphertext = aes.encrypt(plaintext, key);
+
+    assert_eq!(
+        states(&tracer, TraceKind::Round, "AES-128"),
+        [
+            "193de3bea0f4e22b9ac68d2ae9f84808",
+            "a49c7ff2689f352b6b5bea43026a5049",
+            "aa8f5f0361dde3ef82d24ad26832469a",
+            "486c4eee671d9d0d4de3b138d65f58e7",
+            "e0927fe8c86363c0d9b1355085b8be01",
+            "f1006f55c1924cef7cc88b325db5d50c",
+            "260e2e173d41b77de86472a9fdd28b25",
+            "5a4142b11949dc1fa3e019657a8c040c",
+            "ea835cf00445332d655d98ad8596b0c5",
+            "eb40f21e592e38848ba113e71bc342d2",
+            "3925841d02dc09fbdc118597196a0b32",
+        ]
+    );
+    let rounds: Vec<_> = tracer.events().iter().map(|e| e.index).collect();
+    assert_eq!(rounds, (0..=10).collect::<Vec<_>>());
+
+    // Only encryption is traced.
+    tracer.clear();
+    assert_eq!(aes.decrypt(ciphertext, key), plaintext);
+    assert!(tracer.events().is_empty());
+}
+
+/// The working variables a, b, ..., h of SHA-256 after rounds 0, 1, 2 and
+/// 63 of the one-block message example from FIPS 180-2.
+#[test]
+fn sha256_working_variables() {
+    let tracer = CollectingTracer::default();
+    let digest = Sha256::with_trace(&tracer).hash(b"abc");
+
+    let rounds = states(&tracer, TraceKind::Round, "SHACAL-2");
+    assert_eq!(rounds.len(), 64);
+    assert_eq!(
+        rounds[0],
+        "5d6aebcd6a09e667bb67ae853c6ef372fa2a4622510e527f9b05688c1f83d9ab"
+    );
+    assert_eq!(
+        rounds[1],
+        "5a6ad9ad5d6aebcd6a09e667bb67ae8578ce7989fa2a4622510e527f9b05688c"
+    );
+    assert_eq!(
+        rounds[2],
+        "c8c347a75a6ad9ad5d6aebcd6a09e667f92939eb78ce7989fa2a4622510e527f"
+    );
+    assert_eq!(
+        rounds[63],
+        "506e3058d39a216504d24d6cb85e2ce95ef50f24fb121210948d25b6961f4894"
+    );
+
+    // The state after the only block is the digest.
+    assert_eq!(
+        states(&tracer, TraceKind::Block, "SHA-256"),
+        [hex::encode(&digest)]
+    );
+}
+
+/// The working variables of SHA-1 after rounds 0 and 79 of the one-block
+/// message example from FIPS 180-2.
+#[test]
+fn sha1_working_variables() {
+    let tracer = CollectingTracer::default();
+    Sha1::with_trace(&tracer).hash(b"abc");
+
+    let rounds = states(&tracer, TraceKind::Round, "SHACAL-1");
+    assert_eq!(rounds.len(), 80);
+    assert_eq!(rounds[0], "0116fc33674523017bf36ae298badcfe10325476");
+    assert_eq!(rounds[79], "42541b355738d5e121834873681e6df6d8fdf6ad");
+}
+
+/// The intermediate hash value after the first block of the multi-block
+/// message example from FIPS 180-2, reported by the Merkle-Damgard block loop.
+/// Incremental hashing reports the same blocks, wherever the input is split.
+#[test]
+fn sha256_blocks() {
+    let preimage = b"abcdbcdecdefdefgefghfghighijhijkijkljklmklmnlmnomnopnopq";
+    let expected = [
+        "85e655d6417a17953363376a624cde5c76e09589cac5f811cc4b32c1f20e533a",
+        "248d6a61d20638b8e5c026930c3e6039a33ce45964ff2167f6ecedd419db06c1",
+    ];
+
+    let tracer = CollectingTracer::default();
+    let sha = Sha256::with_trace(&tracer);
+    sha.hash(preimage);
+    assert_eq!(states(&tracer, TraceKind::Block, "SHA-256"), expected);
+
+    for split in [0, 1, 55, 56] {
+        tracer.clear();
+        let mut state = sha.init();
+        sha.update(&mut state, &preimage[..split]);
+        sha.update(&mut state, &preimage[split..]);
+        sha.finalize(state);
+        assert_eq!(states(&tracer, TraceKind::Block, "SHA-256"), expected);
+        let blocks: Vec<_> = tracer
+            .events()
+            .iter()
+            .filter(|e| e.kind == TraceKind::Block)
+            .map(|e| e.index)
+            .collect();
+        assert_eq!(blocks, [0, 1]);
+    }
+}
+
+/// Every absorbed block is permuted by all rounds of Keccak-p, and the digest
+/// is squeezed from the state after the last round.
+#[test]
+fn sha3_rounds() {
+    let tracer = CollectingTracer::default();
+    let digest = Sha3_256::with_trace(&tracer).hash(&[0; 200]);
+
+    // 200 bytes with a rate of 136 bytes are padded into two blocks.
+    let rounds = tracer.events();
+    assert_eq!(rounds.len(), 2 * sha3::NUM_ROUNDS);
+    assert!(rounds
+        .iter()
+        .all(|e| e.kind == TraceKind::Round && e.algorithm == KECCAK_P));
+    let indices: Vec<_> = rounds.iter().map(|e| e.index).collect();
+    assert_eq!(
+        indices,
+        (0..sha3::NUM_ROUNDS)
+            .chain(0..sha3::NUM_ROUNDS)
+            .collect::<Vec<_>>()
+    );
+    assert_eq!(rounds.last().unwrap().state[..32], digest);
+    assert_eq!(digest, Sha3_256::default().hash(&[0; 200]));
+}
+
+fn states(tracer: &CollectingTracer, kind: TraceKind, algorithm: &str) -> Vec<String> {
+    tracer
+        .states(kind, algorithm)
+        .iter()
+        .map(|s| hex::encode(s))
+        .collect()
+}
+
+fn block(hex: &str) -> [u8; 16] {
+    hex::decode(hex).unwrap().try_into().unwrap()
+}
@@ -0,0 +1,323 @@
+//! Loader for the test vectors in `tests/vectors/`.
+//!
+//! The files are line-oriented, like the response files of the NIST
+//! validation programs. A vector is a record of `name = value` fields, one per
+//! line, and records are separated by blank lines. Lines starting with `#` are
+//! comments. Values are text, and the [`Vector`] implementation decides how to
+//! read them, usually [as hex](Record::hex).
+//!
+//! Every error names the file and the line it was found on, so that a broken
+//! vector can be found without counting records.
+
+use {
+    crate::hex,
+    std::{fmt, fs, path::Path},
+};
+
+/// A test vector which can be read from a [record](Record).
+pub(super) trait Vector: Sized {
+    fn parse(record: &mut Record) -> Result<Self, VectorError>;
+}
+
+/// Load all vectors from a file in `tests/vectors/`, panicking with the
+/// location of the first error.
+pub(super) fn load_vectors<V: Vector>(file: &str) -> Vec<V> {
+    let path = Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/vectors")
+        .join(file);
+    let contents =
+        fs::read_to_string(&path).unwrap_or_else(|e| panic!("can't read {}: {e}", path.display()));
+    let vectors = parse_vectors(&contents).unwrap_or_else(|e| panic!("{file}:{e}"));
+    assert!(!vectors.is_empty(), "{file}: no vectors");
+    vectors
+}
+
+/// Parse all vectors from the contents of a file.
+pub(super) fn parse_vectors<V: Vector>(contents: &str) -> Result<Vec<V>, VectorError> {
+    let mut vectors = Vec::new();
+    let mut record = Record::default();
+    // Append an empty line, so that the last record is terminated like the
+    // others.
+    for (i, line) in contents.lines().chain([""]).enumerate() {
+        let line = line.trim();
+        if line.starts_with('#') {
+            continue;
+        }
+        if line.is_empty() {
+            if !record.fields.is_empty() {
+                vectors.push(V::parse(&mut record)?);
+                record.finish()?;
+            }
+            record = Record::default();
+            continue;
+        }
+        let Some((name, value)) = line.split_once('=') else {
+            return Err(VectorError {
+                line: i + 1,
+                kind: VectorErrorKind::Malformed,
+            });
+        };
+        let name = name.trim();
+        if record.fields.iter().any(|f| f.name == name) {
+            return Err(VectorError {
+                line: i + 1,
+                kind: VectorErrorKind::DuplicateField(name.to_owned()),
+            });
+        }
+        if record.fields.is_empty() {
+            record.line = i + 1;
+        }
+        record.fields.push(Field {
+            name: name.to_owned(),
+            value: value.trim().to_owned(),
+            line: i + 1,
+            used: false,
+        });
+    }
+    Ok(vectors)
+}
+
+/// The fields of a single vector. Each field must be read exactly once, and
+/// fields which aren't read are reported as unexpected.
+#[derive(Debug, Default)]
+pub(super) struct Record {
+    fields: Vec<Field>,
+    line: usize,
+}
+
+#[derive(Debug)]
+struct Field {
+    name: String,
+    value: String,
+    line: usize,
+    used: bool,
+}
+
+impl Record {
+    /// Read a field as text.
+    pub fn text(&mut self, name: &str) -> Result<String, VectorError> {
+        let line = self.line;
+        let field = self
+            .fields
+            .iter_mut()
+            .find(|f| f.name == name && !f.used)
+            .ok_or_else(|| VectorError {
+                line,
+                kind: VectorErrorKind::MissingField(name.to_owned()),
+            })?;
+        field.used = true;
+        Ok(field.value.clone())
+    }
+
+    /// Read a field as hex-encoded bytes.
+    pub fn hex(&mut self, name: &str) -> Result<Vec<u8>, VectorError> {
+        let value = self.text(name)?;
+        hex::decode(&value).map_err(|_| self.invalid(name))
+    }
+
+    /// Read a field as a fixed number of hex-encoded bytes.
+    pub fn array<const N: usize>(&mut self, name: &str) -> Result<[u8; N], VectorError> {
+        let bytes = self.hex(name)?;
+        bytes.try_into().map_err(|bytes: Vec<u8>| VectorError {
+            line: self.field_line(name),
+            kind: VectorErrorKind::WrongLength {
+                name: name.to_owned(),
+                expected: N,
+                actual: bytes.len(),
+            },
+        })
+    }
+
+    /// Read a field as a decimal number.
+    pub fn u64(&mut self, name: &str) -> Result<u64, VectorError> {
+        let value = self.text(name)?;
+        value.parse().map_err(|_| self.invalid(name))
+    }
+
+    /// Report the field as invalid.
+    pub fn invalid(&self, name: &str) -> VectorError {
+        VectorError {
+            line: self.field_line(name),
+            kind: VectorErrorKind::InvalidField(name.to_owned()),
+        }
+    }
+
+    /// Fail if a field was never read.
+    fn finish(&self) -> Result<(), VectorError> {
+        match self.fields.iter().find(|f| !f.used) {
+            Some(field) => Err(VectorError {
+                line: field.line,
+                kind: VectorErrorKind::UnexpectedField(field.name.clone()),
+            }),
+            None => Ok(()),
+        }
+    }
+
+    fn field_line(&self, name: &str) -> usize {
+        self.fields
+            .iter()
+            .find(|f| f.name == name)
+            .map_or(self.line, |f| f.line)
+    }
+}
+
+/// Error returned when a vector file can't be parsed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(super) struct VectorError {
+    /// The line of the error, starting from 1. For a missing field, the first
+    /// line of the record.
+    pub line: usize,
+    pub kind: VectorErrorKind,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(super) enum VectorErrorKind {
+    /// The line is neither a comment nor a `name = value` field.
+    Malformed,
+    /// The record already has a field with this name.
+    DuplicateField(String),
+    /// The record lacks a field which the vector needs.
+    MissingField(String),
+    /// The record has a field which the vector doesn't use.
+    UnexpectedField(String),
+    /// The value of the field can't be parsed.
+    InvalidField(String),
+    /// The field has the wrong number of bytes.
+    WrongLength {
+        name: String,
+        expected: usize,
+        actual: usize,
+    },
+}
+
+impl fmt::Display for VectorError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: ", self.line)?;
+        match &self.kind {
+            VectorErrorKind::Malformed => write!(f, "expected `name = value`"),
+            VectorErrorKind::DuplicateField(name) => write!(f, "duplicate field `{name}`"),
+            VectorErrorKind::MissingField(name) => write!(f, "missing field `{name}`"),
+            VectorErrorKind::UnexpectedField(name) => write!(f, "unexpected field `{name}`"),
+            VectorErrorKind::InvalidField(name) => write!(f, "invalid value for `{name}`"),
+            VectorErrorKind::WrongLength {
+                name,
+                expected,
+                actual,
+            } => write!(f, "`{name}` has {actual} bytes, expected {expected}"),
+        }
+    }
+}
+
+/// A vector of the loader's own tests.
+#[derive(Debug, PartialEq, Eq)]
+struct Sample {
+    key: [u8; 2],
+    data: Vec<u8>,
+    n: u64,
+}
+
+impl Vector for Sample {
+    fn parse(record: &mut Record) -> Result<Self, VectorError> {
+        Ok(Self {
+            key: record.array("key")?,
+            data: record.hex("data")?,
+            n: record.u64("n")?,
+        })
+    }
+}
+
+#[test]
+fn vectors_parse() {
+    let contents =
+        "# comment\n\nkey = 0102\ndata =\nn = 7\n\n\n# another\nn=8\ndata=ff\nkey = A0B1  \n";
+    assert_eq!(
+        parse_vectors::<Sample>(contents),
+        Ok(vec![
+            Sample {
+                key: [1, 2],
+                data: vec![],
+                n: 7
+            },
+            Sample {
+                key: [0xa0, 0xb1],
+                data: vec![0xff],
+                n: 8
+            },
+        ])
+    );
+    assert_eq!(parse_vectors::<Sample>(""), Ok(vec![]));
+}
+
+#[test]
+fn vectors_malformed() {
+    let error = |contents| parse_vectors::<Sample>(contents).unwrap_err();
+    assert_eq!(
+        error("key = 0102\ndata\nn = 1\n"),
+        VectorError {
+            line: 2,
+            kind: VectorErrorKind::Malformed
+        }
+    );
+    assert_eq!(
+        error("key = 0102\ndata = 00\ndata = 01\nn = 1\n"),
+        VectorError {
+            line: 3,
+            kind: VectorErrorKind::DuplicateField("data".to_owned())
+        }
+    );
+    assert_eq!(
+        error("key = 0102\ndata = 0\nn = 1\n"),
+        VectorError {
+            line: 2,
+            kind: VectorErrorKind::InvalidField("data".to_owned())
+        }
+    );
+    assert_eq!(
+        error("key = 0102\ndata = 00\nn = -1\n"),
+        VectorError {
+            line: 3,
+            kind: VectorErrorKind::InvalidField("n".to_owned())
+        }
+    );
+    assert_eq!(
+        error("key = 010203\ndata = 00\nn = 1\n"),
+        VectorError {
+            line: 1,
+            kind: VectorErrorKind::WrongLength {
+                name: "key".to_owned(),
+                expected: 2,
+                actual: 3
+            }
+        }
+    );
+    assert_eq!(
+        error("key = 0102\ndata = 00\nn = 1\n\nkey = 0102\ndata = 00\nn = 1\nx").to_string(),
+        "8: expected `name = value`"
+    );
+}
+
+#[test]
+fn vectors_missing_field() {
+    assert_eq!(
+        parse_vectors::<Sample>("key = 0102\ndata = 00\nn = 1\n\n# next\nkey = 0102\nn = 1\n"),
+        Err(VectorError {
+            line: 6,
+            kind: VectorErrorKind::MissingField("data".to_owned())
+        })
+    );
+}
+
+/// Fields which the vector doesn't read are trailing data, and rejected
+/// rather than silently ignored.
+#[test]
+fn vectors_trailing_data() {
+    let error = parse_vectors::<Sample>("key = 0102\ndata = 00\nn = 1\nextra = 00\n").unwrap_err();
+    assert_eq!(
+        error,
+        VectorError {
+            line: 4,
+            kind: VectorErrorKind::UnexpectedField("extra".to_owned())
+        }
+    );
+    assert_eq!(error.to_string(), "4: unexpected field `extra`");
+}
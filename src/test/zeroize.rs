@@ -0,0 +1,56 @@
+use crate::{
+    ecc::{self, Num, PrivateKey, SchnorrRandomness},
+    util::{Zeroize, Zeroizing},
+    Secp256k1,
+    Sha256,
+};
+
+/// Assert that byte arrays, slices and vectors are overwritten with zeros.
+#[test]
+fn zeroize_bytes() {
+    let mut array = [0xab; 16];
+    array.zeroize();
+    assert_eq!(array, [0; 16]);
+
+    let mut vec = vec![1, 2, 3];
+    vec.zeroize();
+    assert_eq!(vec, [0; 3]);
+
+    let mut array = [7u8; 8];
+    array[2..5].zeroize();
+    assert_eq!(array, [7, 7, 0, 0, 0, 7, 7, 7]);
+}
+
+/// Assert that the wrapped bytes are wiped by the time the wrapper is dropped.
+#[test]
+fn zeroizing_drop() {
+    let mut key = [0x5a; 32];
+    let mut wrapped = Zeroizing::new(&mut key);
+    wrapped[0] = 1;
+    assert_eq!(wrapped[..2], [1, 0x5a]);
+    drop(wrapped);
+    assert_eq!(key, [0; 32]);
+}
+
+/// Assert that dropping a private key, or a clone of it, wipes its scalar.
+#[test]
+fn private_key_wiped() {
+    let n = Num::from_le_words([7, 0, 0, 0]);
+    let key = PrivateKey::<Secp256k1>::new(n).unwrap();
+    let copy = key.clone();
+    ecc::take_wiped();
+    drop(key);
+    assert_eq!(ecc::take_wiped(), [(n, Num::ZERO)]);
+    drop(copy);
+    assert_eq!(ecc::take_wiped(), [(n, Num::ZERO)]);
+}
+
+/// Assert that dropping the randomness of a multisig wipes the local secret.
+#[test]
+fn schnorr_randomness_wiped() {
+    let local = Num::from_le_words([11, 0, 0, 0]);
+    let randomness = SchnorrRandomness::<Secp256k1>::new(&Sha256::default(), local, &[]).unwrap();
+    ecc::take_wiped();
+    drop(randomness);
+    assert_eq!(ecc::take_wiped(), [(local, Num::ZERO)]);
+}
@@ -0,0 +1,251 @@
+//! Instrumented stand-ins for [hash functions](Hash) and [sources of
+//! randomness](Csprng), for testing code built on this crate.
+//!
+//! Some properties can't be seen from the output of an algorithm: how many
+//! times it hashes, or what it does when a hash happens to come out as zero.
+//! The test doubles in this module make them visible:
+//!
+//! - [`CountingHash`] counts the calls to a hash function and the bytes hashed,
+//!   e.g. to check that an expensive computation is cached.
+//! - [`FixedOutputHash`] returns scripted digests, to force values which a real
+//!   hash function only produces with negligible probability, such as a nonce
+//!   of zero. This reaches the retry branches of the signature schemes.
+//! - [`StreamRng`] plays back a fixed sequence of bytes as a [CSPRNG](Csprng)
+//!   or as [entropy](Entropy), and panics when the script runs out, so that a
+//!   test also pins down how much randomness is used.
+//! - [`rng`] is a [Fortuna] generator without [entropy](NoEntropy), which gives
+//!   the same bytes on every run, and [`key`] makes small private keys, so that
+//!   tests don't each define their own.
+//! - [`record_allocation`] and [`count_allocations`] count heap allocations.
+//!   The counting global allocator itself needs `unsafe` code, which this crate
+//!   forbids, so it lives in the test and calls `record_allocation`.
+//!
+//! The module is only compiled with the `testing` feature. Never enable it in
+//! production builds: a [`StreamRng`] is not random at all.
+
+use {
+    crate::{
+        ecc::{Num, PrivateKey},
+        Aes256,
+        Csprng,
+        Entropy,
+        Fortuna,
+        Hash,
+        Secp256k1,
+        Sha256,
+    },
+    docext::docext,
+    std::{
+        cell::{Cell, RefCell},
+        collections::VecDeque,
+    },
+};
+
+/// A [hash function](Hash) which counts how many times it was called, and how
+/// many bytes it hashed in total.
+///
+/// The counters live behind a shared reference, so the hash can be passed to
+/// a scheme by reference, since `&H` is a hash function as well, and read
+/// afterwards.
+#[derive(Debug, Default)]
+pub struct CountingHash<H> {
+    inner: H,
+    calls: Cell<usize>,
+    bytes: Cell<usize>,
+}
+
+impl<H> CountingHash<H> {
+    pub fn new(inner: H) -> Self {
+        Self {
+            inner,
+            calls: Cell::new(0),
+            bytes: Cell::new(0),
+        }
+    }
+
+    /// The number of preimages hashed so far.
+    pub fn calls(&self) -> usize {
+        self.calls.get()
+    }
+
+    /// The total length of the preimages hashed so far.
+    pub fn bytes(&self) -> usize {
+        self.bytes.get()
+    }
+
+    /// Reset both counters to zero.
+    pub fn reset(&self) {
+        self.calls.set(0);
+        self.bytes.set(0);
+    }
+}
+
+impl<H: Hash> Hash for CountingHash<H> {
+    type Digest = H::Digest;
+    type Block = H::Block;
+
+    fn hash(&self, preimage: &[u8]) -> Self::Digest {
+        self.calls.set(self.calls.get() + 1);
+        self.bytes.set(self.bytes.get() + preimage.len());
+        self.inner.hash(preimage)
+    }
+}
+
+/// A [hash function](Hash) which returns scripted digests in sequence.
+///
+/// Each call takes the next entry of the script: `Some(digest)` returns the
+/// digest regardless of the preimage, and `None` passes the preimage through
+/// to the real hash function. Once the script is used up, every call is
+/// passed through. A test forcing a value therefore scripts the calls up to
+/// the one it is interested in, and leaves the rest to the real hash, so that
+/// the result can still be verified normally.
+#[derive(Debug)]
+pub struct FixedOutputHash<H: Hash> {
+    inner: H,
+    script: RefCell<VecDeque<Option<H::Digest>>>,
+}
+
+impl<H: Hash> FixedOutputHash<H> {
+    pub fn new(inner: H, script: impl IntoIterator<Item = Option<H::Digest>>) -> Self {
+        Self {
+            inner,
+            script: RefCell::new(script.into_iter().collect()),
+        }
+    }
+
+    /// The number of script entries which haven't been used yet.
+    pub fn remaining(&self) -> usize {
+        self.script.borrow().len()
+    }
+}
+
+impl<H: Hash> Hash for FixedOutputHash<H> {
+    type Digest = H::Digest;
+    type Block = H::Block;
+
+    fn hash(&self, preimage: &[u8]) -> Self::Digest {
+        let next = self.script.borrow_mut().pop_front();
+        match next {
+            Some(Some(digest)) => digest,
+            Some(None) | None => self.inner.hash(preimage),
+        }
+    }
+}
+
+/// A [CSPRNG](Csprng) and [source of entropy](Entropy) which plays back the
+/// given bytes in order.
+///
+/// Reading past the end panics, naming how many bytes were read, instead of
+/// silently ending the stream: the code under test is entitled to assume that
+/// randomness never runs out.
+#[derive(Debug, Clone, Default)]
+pub struct StreamRng(pub Vec<u8>);
+
+/// Reseeding replaces the bytes which are left with the seed, so that a test
+/// can script the bytes which come after a reseed.
+impl Csprng for StreamRng {
+    fn fill_bytes(&mut self, buf: &mut [u8]) {
+        self.get(buf);
+    }
+
+    fn reseed(&mut self, seed: &[u8]) {
+        self.0 = seed.to_vec();
+    }
+}
+
+impl IntoIterator for StreamRng {
+    type Item = u8;
+
+    type IntoIter = impl Iterator<Item = u8>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        let len = self.0.len();
+        let mut bytes = self.0.into_iter();
+        std::iter::repeat_with(move || {
+            bytes
+                .next()
+                .unwrap_or_else(|| panic!("StreamRng exhausted after {len} bytes"))
+        })
+    }
+}
+
+impl Entropy for StreamRng {
+    fn get(&mut self, buf: &mut [u8]) {
+        assert!(
+            buf.len() <= self.0.len(),
+            "StreamRng exhausted: {} bytes requested, {} left",
+            buf.len(),
+            self.0.len()
+        );
+        buf.copy_from_slice(&self.0[..buf.len()]);
+        self.0.drain(..buf.len());
+    }
+}
+
+/// A [source of entropy](Entropy) which only ever gives zeros.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct NoEntropy;
+
+impl Entropy for NoEntropy {
+    fn get(&mut self, buf: &mut [u8]) {
+        buf.fill(0);
+    }
+}
+
+/// A [Fortuna] generator seeded with [`NoEntropy`], which gives the same bytes
+/// every time it's created.
+pub fn rng() -> Fortuna<NoEntropy> {
+    // The blocks of AES-256 have room for the counter of CTR mode.
+    Fortuna::new(NoEntropy, Aes256::default(), Sha256::default()).unwrap()
+}
+
+/// The [secp256k1](Secp256k1) private key $n$.
+///
+/// # Panics
+///
+/// If $n$ is zero.
+#[docext]
+pub fn key(n: u64) -> PrivateKey<Secp256k1> {
+    PrivateKey::new(Num::from_le_words([n, 0, 0, 0])).unwrap()
+}
+
+thread_local! {
+    static ALLOCATIONS: Cell<usize> = const { Cell::new(0) };
+}
+
+/// Count a heap allocation on the current thread. Meant to be called from the
+/// `alloc` method of a counting [global
+/// allocator](std::alloc::GlobalAlloc), e.g.
+///
+/// ```ignore
+/// struct CountingAlloc;
+///
+/// unsafe impl GlobalAlloc for CountingAlloc {
+///     unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+///         literate_crypto::testing::record_allocation();
+///         System.alloc(layout)
+///     }
+///
+///     unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+///         System.dealloc(ptr, layout)
+///     }
+/// }
+///
+/// #[global_allocator]
+/// static ALLOC: CountingAlloc = CountingAlloc;
+/// ```
+///
+/// The counter is per thread, so that tests running in parallel don't count
+/// each other's allocations.
+pub fn record_allocation() {
+    ALLOCATIONS.with(|n| n.set(n.get() + 1));
+}
+
+/// Run the closure and return its result along with the number of heap
+/// allocations it [recorded](record_allocation) on the current thread.
+pub fn count_allocations<R>(f: impl FnOnce() -> R) -> (R, usize) {
+    let before = ALLOCATIONS.with(Cell::get);
+    let result = f();
+    let after = ALLOCATIONS.with(Cell::get);
+    (result, after - before)
+}
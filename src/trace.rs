@@ -0,0 +1,170 @@
+//! Step-by-step output of the intermediate values of an algorithm.
+//!
+//! Cryptographic algorithms are hard to follow from their output alone: a
+//! single wrong bit in the first round turns the whole digest or ciphertext
+//! into garbage, with no hint of where things went wrong. The specifications
+//! therefore print the intermediate values of their examples, e.g. the state
+//! of [AES](crate::aes) after every round in Appendix B of FIPS 197, or the
+//! working variables of [SHA-256](crate::Sha256) in FIPS 180-2.
+//!
+//! A [tracer](Trace) is notified of the same values while an algorithm runs.
+//! Printing from library code would be wrong, so the tracer is passed in by
+//! the caller, e.g. with [`Aes128::with_trace`](crate::Aes128::with_trace) or
+//! [`Sha256::with_trace`](crate::Sha256::with_trace). Hash functions
+//! constructed with [`Default`] use [`NoTrace`], whose callbacks are empty and
+//! compiled away, and a plain [`Aes128`](crate::Aes128) has no tracer at all,
+//! so tracing costs nothing unless it's asked for.
+
+use {
+    crate::hex,
+    std::{
+        rc::Rc,
+        sync::{Arc, Mutex},
+    },
+};
+
+/// Callbacks for the intermediate values of an algorithm, see the [module
+/// documentation](self).
+///
+/// The `algorithm` names the primitive which reports the value, e.g. `AES-128`
+/// or `SHACAL-2`. A single [hash function](crate::Hash) reports values of
+/// several primitives: SHA-256 reports the rounds of SHACAL-2 and the blocks
+/// of SHA-256 itself. The `state` is encoded as bytes in the same way as in
+/// the specification of the algorithm.
+///
+/// All callbacks do nothing by default.
+pub trait Trace {
+    /// Called with the state at the end of every round of a block cipher or a
+    /// permutation.
+    fn on_round(&self, _algorithm: &str, _round: usize, _state: &[u8]) {}
+
+    /// Called with the state of a [Merkle-Damgard](crate::MerkleDamgard) hash
+    /// after every compressed block.
+    fn on_block(&self, _algorithm: &str, _block: usize, _state: &[u8]) {}
+}
+
+/// Forward to the shared tracer, so that a tracer can be lent to an algorithm
+/// and inspected afterwards.
+impl<T: Trace + ?Sized> Trace for &T {
+    fn on_round(&self, algorithm: &str, round: usize, state: &[u8]) {
+        T::on_round(self, algorithm, round, state)
+    }
+
+    fn on_block(&self, algorithm: &str, block: usize, state: &[u8]) {
+        T::on_block(self, algorithm, block, state)
+    }
+}
+
+impl<T: Trace + ?Sized> Trace for Rc<T> {
+    fn on_round(&self, algorithm: &str, round: usize, state: &[u8]) {
+        T::on_round(self, algorithm, round, state)
+    }
+
+    fn on_block(&self, algorithm: &str, block: usize, state: &[u8]) {
+        T::on_block(self, algorithm, block, state)
+    }
+}
+
+impl<T: Trace + ?Sized> Trace for Arc<T> {
+    fn on_round(&self, algorithm: &str, round: usize, state: &[u8]) {
+        T::on_round(self, algorithm, round, state)
+    }
+
+    fn on_block(&self, algorithm: &str, block: usize, state: &[u8]) {
+        T::on_block(self, algorithm, block, state)
+    }
+}
+
+/// The tracer which ignores everything, used by default.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct NoTrace;
+
+impl Trace for NoTrace {}
+
+/// The kind of a [traced event](TraceEvent).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TraceKind {
+    /// Reported by [`Trace::on_round`].
+    Round,
+    /// Reported by [`Trace::on_block`].
+    Block,
+}
+
+/// A single callback received by the [`CollectingTracer`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TraceEvent {
+    pub kind: TraceKind,
+    pub algorithm: String,
+    /// The round or the block number.
+    pub index: usize,
+    pub state: Vec<u8>,
+}
+
+/// A tracer which records every event, to be inspected after the algorithm
+/// has run.
+///
+/// The tracer is usually lent to the algorithm, e.g.
+/// `Sha256::with_trace(&tracer)`, and its [events](CollectingTracer::events)
+/// read afterwards.
+#[derive(Debug, Default)]
+pub struct CollectingTracer {
+    events: Mutex<Vec<TraceEvent>>,
+}
+
+impl CollectingTracer {
+    /// All events recorded so far, in order.
+    pub fn events(&self) -> Vec<TraceEvent> {
+        self.events.lock().unwrap().clone()
+    }
+
+    /// The states reported by the given algorithm with the given kind of
+    /// callback, in order.
+    pub fn states(&self, kind: TraceKind, algorithm: &str) -> Vec<Vec<u8>> {
+        self.events
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|e| e.kind == kind && e.algorithm == algorithm)
+            .map(|e| e.state.clone())
+            .collect()
+    }
+
+    /// Forget all events recorded so far.
+    pub fn clear(&self) {
+        self.events.lock().unwrap().clear();
+    }
+
+    fn record(&self, kind: TraceKind, algorithm: &str, index: usize, state: &[u8]) {
+        self.events.lock().unwrap().push(TraceEvent {
+            kind,
+            algorithm: algorithm.to_owned(),
+            index,
+            state: state.to_vec(),
+        });
+    }
+}
+
+impl Trace for CollectingTracer {
+    fn on_round(&self, algorithm: &str, round: usize, state: &[u8]) {
+        self.record(TraceKind::Round, algorithm, round, state);
+    }
+
+    fn on_block(&self, algorithm: &str, block: usize, state: &[u8]) {
+        self.record(TraceKind::Block, algorithm, block, state);
+    }
+}
+
+/// A tracer which prints every event as hex to standard output, for exploring
+/// an algorithm by hand.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct PrintTracer;
+
+impl Trace for PrintTracer {
+    fn on_round(&self, algorithm: &str, round: usize, state: &[u8]) {
+        println!("{algorithm} round {round}: {}", hex::encode(state));
+    }
+
+    fn on_block(&self, algorithm: &str, block: usize, state: &[u8]) {
+        println!("{algorithm} block {block}: {}", hex::encode(state));
+    }
+}
@@ -1,10 +1,93 @@
+use {
+    crate::{key_fingerprint, Hash},
+    std::fmt,
+};
+
+pub mod base64;
+pub mod canonical;
+pub mod codec;
+mod ct;
+pub mod hex;
 mod iter;
+mod small_buf;
+mod zeroize;
+
+pub use ct::{Choice, CtSelect};
+pub(crate) use {
+    iter::CollectVec,
+    small_buf::SmallBuf,
+    zeroize::{Zeroize, Zeroizing},
+};
 
-pub(crate) use iter::{CollectVec, IterChunks};
+/// Capacity of the stack buffer which [`hash_concat`] and
+/// [`canonical::hash_fields`] assemble preimages in.
+pub(crate) const STACK_PREIMAGE: usize = 512;
+
+/// Widen an array to a length of at least its own, appending the default
+/// value. Asking for a shorter array fails to build.
+pub(crate) fn widen<T: Default + Copy, const N: usize, const R: usize>(a: [T; N]) -> [T; R] {
+    #[allow(clippy::let_unit_value)]
+    let () = Resize::<N, R>::WIDENS;
+    let mut result = [Default::default(); R];
+    result[..N].copy_from_slice(&a);
+    result
+}
 
-/// Resize an array by either appending the default value or truncating.
-pub fn resize<T: Default + Copy, const N: usize, const R: usize>(num: [T; N]) -> [T; R] {
+/// Truncate an array to a length of at most its own, dropping the last
+/// elements. Asking for a longer array fails to build.
+///
+/// Since the dropped elements are lost without a trace, this should only be
+/// called where they are known to be irrelevant, e.g. the most significant
+/// words of a number which is known to fit into fewer words.
+pub(crate) fn truncate<T: Default + Copy, const N: usize, const R: usize>(a: [T; N]) -> [T; R] {
+    #[allow(clippy::let_unit_value)]
+    let () = Resize::<N, R>::TRUNCATES;
     let mut result = [Default::default(); R];
-    result.iter_mut().zip(num.iter()).for_each(|(a, b)| *a = *b);
+    result.copy_from_slice(&a[..R]);
     result
 }
+
+/// Resizing an array from length `N` to length `R`. The constants are
+/// evaluated when [`widen`] or [`truncate`] is instantiated, so a call in the
+/// wrong direction is a compile error rather than silent truncation.
+struct Resize<const N: usize, const R: usize>;
+
+impl<const N: usize, const R: usize> Resize<N, R> {
+    const WIDENS: () = assert!(R >= N, "widen would truncate the array");
+    const TRUNCATES: () = assert!(R <= N, "truncate would widen the array");
+}
+
+/// Compare two tags without exiting early on the first differing byte.
+pub(crate) fn tags_equal(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+/// Stand-in for secret material in [`Debug`](fmt::Debug) output.
+///
+/// Prints the [fingerprint](key_fingerprint) of the secret instead of the
+/// secret itself, so that logs can still tell whether two values hold the same
+/// secret without revealing it.
+pub(crate) struct Redacted<'a>(pub &'a [u8]);
+
+impl fmt::Debug for Redacted<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "<redacted {}>", hex::encode(&key_fingerprint(self.0)))
+    }
+}
+
+/// Hash the concatenation of the parts.
+///
+/// The preimage is assembled on the stack if it fits into
+/// [`STACK_PREIMAGE`] bytes, so that hashing short inputs doesn't allocate.
+/// Long inputs, such as whole messages, fall back to the heap.
+pub(crate) fn hash_concat<H: Hash>(hash: &H, parts: &[&[u8]]) -> H::Digest {
+    let mut buf = SmallBuf::<STACK_PREIMAGE>::new();
+    if parts
+        .iter()
+        .all(|part| buf.try_extend_from_slice(part).is_ok())
+    {
+        hash.hash(&buf)
+    } else {
+        hash.hash(&parts.concat())
+    }
+}
@@ -0,0 +1,162 @@
+//! Base64 encoding of bytes, as specified in RFC 4648.
+//!
+//! Every 3 bytes of input are split into 4 groups of 6 bits, and each group is
+//! written as one of 64 characters. When the input length is not a multiple of
+//! 3, the last group is filled up with zero bits and the output is padded with
+//! `=` to a multiple of 4 characters. The padding carries no information, so
+//! some formats (for example JWS) leave it out.
+//!
+//! There are two common [alphabets](Alphabet), which only differ in the last
+//! two characters.
+//!
+//! Like [hex](super::hex), this module doesn't branch on or index a table with
+//! the encoded data, since it is used to encode and decode keys.
+
+use {super::ct, std::fmt};
+
+/// The base64 alphabet.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Alphabet {
+    /// The standard alphabet `A-Z`, `a-z`, `0-9`, `+`, `/`.
+    Standard,
+    /// The URL and filename safe alphabet `A-Z`, `a-z`, `0-9`, `-`, `_`.
+    UrlSafe,
+}
+
+impl Alphabet {
+    /// The characters for the values 62 and 63.
+    fn last(self) -> [u8; 2] {
+        match self {
+            Self::Standard => [b'+', b'/'],
+            Self::UrlSafe => [b'-', b'_'],
+        }
+    }
+}
+
+/// Encode the bytes with the given alphabet, optionally padding the output
+/// with `=` to a multiple of 4 characters.
+pub fn encode(bytes: &[u8], alphabet: Alphabet, pad: bool) -> String {
+    let mut result = String::new();
+    for chunk in bytes.chunks(3) {
+        let mut block = [0; 3];
+        block[..chunk.len()].copy_from_slice(chunk);
+        let n = u32::from_be_bytes([0, block[0], block[1], block[2]]);
+        // A chunk of k bytes is encoded with k + 1 characters.
+        (0..=chunk.len())
+            .map(|i| encode_char((n >> (18 - 6 * i)) as u8 & 0x3f, alphabet))
+            .for_each(|c| result.push(char::from(c)));
+        if pad {
+            (chunk.len()..3).for_each(|_| result.push('='));
+        }
+    }
+    result
+}
+
+/// Decode base64 with the given alphabet. The padding is optional, but if it
+/// is present it must be correct. The unused bits of the last character must
+/// be zero, so that every byte sequence has only one valid encoding.
+pub fn decode(base64: &str, alphabet: Alphabet) -> Result<Vec<u8>, Base64Error> {
+    let input = base64.as_bytes();
+    let data_len = input.iter().rposition(|&c| c != b'=').map_or(0, |i| i + 1);
+    let padded = data_len != input.len();
+    if (padded && input.len() % 4 != 0) || data_len % 4 == 1 {
+        return Err(Base64Error::InvalidLength { len: input.len() });
+    }
+    if padded && input.len() - data_len != (4 - data_len % 4) % 4 {
+        return Err(Base64Error::InvalidPadding { index: data_len });
+    }
+
+    let mut invalid = 0;
+    let mut result = Vec::new();
+    for chunk in input[..data_len].chunks(4) {
+        let mut n = 0u32;
+        for (i, &c) in chunk.iter().enumerate() {
+            let (value, valid) = decode_char(c, alphabet);
+            invalid |= !valid;
+            n |= u32::from(value) << (18 - 6 * i);
+        }
+        // A chunk of k characters holds k - 1 bytes.
+        result.extend(&n.to_be_bytes()[1..chunk.len()]);
+        // Any bits left over in the last character must be zero.
+        let leftover = n & (0xffffff >> (8 * (chunk.len() - 1)));
+        invalid |= u8::from(leftover != 0);
+    }
+    if invalid == 0 {
+        return Ok(result);
+    }
+
+    // Decoding failed, so it is fine to branch on the data from here on.
+    if let Some(index) = input[..data_len]
+        .iter()
+        .position(|&c| decode_char(c, alphabet).1 == 0)
+    {
+        return Err(Base64Error::InvalidCharacter { index });
+    }
+    Err(Base64Error::NonCanonical {
+        index: data_len - 1,
+    })
+}
+
+/// Convert a number between 0 and 63 into a base64 character.
+fn encode_char(n: u8, alphabet: Alphabet) -> u8 {
+    let [c62, c63] = alphabet.last().map(i16::from);
+    let n = i16::from(n);
+    // Start from 'A' and shift the character whenever the value crosses into
+    // the next range of the alphabet.
+    let mut c = n + i16::from(b'A');
+    c += ct::ge(n, 26) & (i16::from(b'a') - i16::from(b'A') - 26);
+    c += ct::ge(n, 52) & (i16::from(b'0') - i16::from(b'a') - 26);
+    c += ct::ge(n, 62) & (c62 - i16::from(b'0') - 10);
+    c += ct::ge(n, 63) & (c63 - c62 - 1);
+    c as u8
+}
+
+/// Convert a base64 character into a number. The second value is `0xff` if the
+/// character is valid and `0` otherwise.
+fn decode_char(c: u8, alphabet: Alphabet) -> (u8, u8) {
+    let [c62, c63] = alphabet.last();
+    let c = i16::from(c);
+    let upper = ct::in_range(c, b'A', b'Z');
+    let lower = ct::in_range(c, b'a', b'z');
+    let digit = ct::in_range(c, b'0', b'9');
+    let is62 = ct::in_range(c, c62, c62);
+    let is63 = ct::in_range(c, c63, c63);
+    let value = (upper & (c - i16::from(b'A')))
+        | (lower & (c - i16::from(b'a') + 26))
+        | (digit & (c - i16::from(b'0') + 52))
+        | (is62 & 62)
+        | (is63 & 63);
+    (value as u8, (upper | lower | digit | is62 | is63) as u8)
+}
+
+/// Error returned when [decoding](decode) base64 fails.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Base64Error {
+    /// The input length is not valid base64: padded input must be a multiple
+    /// of 4 characters, and no input can leave a single character in the last
+    /// group.
+    InvalidLength { len: usize },
+    /// The padding starting at the given offset has the wrong length.
+    InvalidPadding { index: usize },
+    /// The byte at the given offset is not in the alphabet.
+    InvalidCharacter { index: usize },
+    /// The character at the given offset has unused bits which are not zero.
+    NonCanonical { index: usize },
+}
+
+impl fmt::Display for Base64Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::InvalidLength { len } => write!(f, "invalid base64 length: {len}"),
+            Self::InvalidPadding { index } => write!(f, "invalid base64 padding at offset {index}"),
+            Self::InvalidCharacter { index } => {
+                write!(f, "invalid base64 character at offset {index}")
+            }
+            Self::NonCanonical { index } => {
+                write!(f, "non-zero trailing bits in base64 at offset {index}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for Base64Error {}
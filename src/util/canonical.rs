@@ -0,0 +1,66 @@
+//! Canonical encoding of hash inputs made of several fields.
+//!
+//! When the input of a hash function is built by simply concatenating
+//! variable-length fields, the boundaries between the fields are lost: the
+//! fields `"ab"` and `"c"` give the same input as `"a"` and `"bc"`. If an
+//! attacker can move bytes from one field to its neighbour, a hash computed
+//! for one meaning of the input also vouches for another.
+//!
+//! The canonical encoding prefixes every field with its length as a 64-bit
+//! little-endian number. Since the length of every field is known, the
+//! encoding can be split back into its fields in exactly one way, so distinct
+//! lists of fields always give distinct encodings.
+//!
+//! All hash inputs which the crate itself builds out of several fields, such as
+//! the challenges of the [Schnorr](crate::Schnorr) signature schemes and the
+//! nonce seeds, use this encoding. Inputs whose layout is fixed by a
+//! standard, such as [HMAC](crate::Hmac), are left as specified.
+
+use crate::{
+    util::{SmallBuf, STACK_PREIMAGE},
+    Hash,
+    IncrementalHash,
+};
+
+/// Encode the fields, each prefixed with its length.
+pub fn encode_fields(fields: &[&[u8]]) -> Vec<u8> {
+    let len = fields.iter().map(|field| 8 + field.len()).sum();
+    let mut result = Vec::with_capacity(len);
+    for field in fields {
+        result.extend(prefix(field));
+        result.extend(*field);
+    }
+    result
+}
+
+/// Hash the [encoding](encode_fields) of the fields.
+///
+/// Short encodings are assembled on the stack, so this doesn't allocate
+/// unless one of the fields is long, e.g. a whole message.
+pub(crate) fn hash_fields<H: Hash>(hash: &H, fields: &[&[u8]]) -> H::Digest {
+    let mut buf = SmallBuf::<STACK_PREIMAGE>::new();
+    let fits = fields.iter().all(|field| {
+        buf.try_extend_from_slice(&prefix(field)).is_ok()
+            && buf.try_extend_from_slice(field).is_ok()
+    });
+    if fits {
+        hash.hash(&buf)
+    } else {
+        hash.hash(&encode_fields(fields))
+    }
+}
+
+/// Feed a single field into an [incremental hash](IncrementalHash), prefixed
+/// with its length.
+///
+/// Feeding the fields one by one gives the same digest as hashing their
+/// [encoding](encode_fields) at once.
+pub fn update_field<H: IncrementalHash>(hash: &H, state: &mut H::State, field: &[u8]) {
+    hash.update(state, &prefix(field));
+    hash.update(state, field);
+}
+
+/// The length prefix of a field.
+fn prefix(field: &[u8]) -> [u8; 8] {
+    u64::try_from(field.len()).unwrap().to_le_bytes()
+}
@@ -0,0 +1,145 @@
+//! Framing of the binary formats defined by this crate.
+//!
+//! Every binary structure which this crate defines itself, such as an
+//! [ECIES ciphertext](crate::EciesCiphertext) or a [signing
+//! session](crate::SigningSession), starts with a one-byte format version.
+//! Standardized encodings, such as SEC1 points, are left as they are. The
+//! version lets a later release change a format while still recognizing data
+//! in the old one, instead of misinterpreting it. Data with a version which
+//! the decoder doesn't know is rejected with
+//! [`CodecError::UnknownVersion`].
+//!
+//! Decoding goes through a [`Reader`], a cursor whose reads fail with
+//! [`CodecError::Truncated`] instead of panicking when the input runs out.
+//! Encoding goes through a [`Writer`], which writes the version first.
+//! Numbers are big-endian.
+
+use std::fmt;
+
+/// A cursor over encoded bytes, with bounds-checked reads.
+#[derive(Debug, Clone)]
+pub struct Reader<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    pub fn new(bytes: &'a [u8]) -> Self {
+        Self { bytes }
+    }
+
+    /// Read the format version, failing unless it is the expected one.
+    pub fn read_version(&mut self, version: u8) -> Result<(), CodecError> {
+        match self.read_u8()? {
+            v if v == version => Ok(()),
+            v => Err(CodecError::UnknownVersion(v)),
+        }
+    }
+
+    pub fn read_u8(&mut self) -> Result<u8, CodecError> {
+        Ok(self.read_array::<1>()?[0])
+    }
+
+    pub fn read_u32(&mut self) -> Result<u32, CodecError> {
+        Ok(u32::from_be_bytes(self.read_array()?))
+    }
+
+    /// Read the next `n` bytes.
+    pub fn read_bytes(&mut self, n: usize) -> Result<&'a [u8], CodecError> {
+        if n > self.bytes.len() {
+            return Err(CodecError::Truncated);
+        }
+        let (bytes, rest) = self.bytes.split_at(n);
+        self.bytes = rest;
+        Ok(bytes)
+    }
+
+    pub fn read_array<const N: usize>(&mut self) -> Result<[u8; N], CodecError> {
+        Ok(self.read_bytes(N)?.try_into().unwrap())
+    }
+
+    /// Read a length written by [`Writer::write_len_prefixed`] followed by that
+    /// many bytes.
+    pub fn read_len_prefixed(&mut self) -> Result<&'a [u8], CodecError> {
+        let len = usize::try_from(self.read_u32()?).map_err(|_| CodecError::Truncated)?;
+        self.read_bytes(len)
+    }
+
+    /// The number of bytes left to read.
+    pub fn remaining(&self) -> usize {
+        self.bytes.len()
+    }
+
+    /// Finish reading, failing if there are bytes left over.
+    pub fn finish(self) -> Result<(), CodecError> {
+        if self.bytes.is_empty() {
+            Ok(())
+        } else {
+            Err(CodecError::TrailingBytes)
+        }
+    }
+}
+
+/// A buffer which encoded bytes are appended to.
+#[derive(Debug, Clone)]
+pub struct Writer {
+    bytes: Vec<u8>,
+}
+
+impl Writer {
+    /// Start encoding a structure with the given format version.
+    pub fn new(version: u8) -> Self {
+        Self {
+            bytes: vec![version],
+        }
+    }
+
+    pub fn write_u8(&mut self, n: u8) {
+        self.bytes.push(n);
+    }
+
+    pub fn write_u32(&mut self, n: u32) {
+        self.bytes.extend(n.to_be_bytes());
+    }
+
+    pub fn write_bytes(&mut self, bytes: &[u8]) {
+        self.bytes.extend_from_slice(bytes);
+    }
+
+    /// Write the length of the bytes as a `u32`, followed by the bytes
+    /// themselves.
+    ///
+    /// # Panics
+    ///
+    /// If there are more than `u32::MAX` bytes.
+    pub fn write_len_prefixed(&mut self, bytes: &[u8]) {
+        self.write_u32(u32::try_from(bytes.len()).expect("too long to encode"));
+        self.write_bytes(bytes);
+    }
+
+    pub fn into_bytes(self) -> Vec<u8> {
+        self.bytes
+    }
+}
+
+/// Error returned when decoding a binary format fails.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CodecError {
+    /// The input ended before the structure was complete.
+    Truncated,
+    /// The structure was complete, but more input followed.
+    TrailingBytes,
+    /// The format version is not supported.
+    UnknownVersion(u8),
+}
+
+impl fmt::Display for CodecError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Truncated => write!(f, "input is truncated"),
+            Self::TrailingBytes => write!(f, "input has trailing bytes"),
+            Self::UnknownVersion(v) => write!(f, "unknown format version {v}"),
+        }
+    }
+}
+
+impl std::error::Error for CodecError {}
@@ -0,0 +1,134 @@
+//! Branch-free comparisons and selection for secret data.
+//!
+//! The comparisons used for encoding and decoding work on small signed
+//! integers, between -256 and 255, and return a mask: `-1` (all bits set) if
+//! the condition holds and `0` otherwise. The mask can then be ANDed with a
+//! value to select it without branching. Both work the same way: when $x \ge
+//! t$, the difference $t - 1 - x$ is negative, and shifting a negative number
+//! right by 8 bits (with sign extension) gives $-1$, whereas shifting a
+//! non-negative number smaller than $2^8$ gives 0.
+//!
+//! Larger values, like [numbers](crate::ecc::Num) and [curve
+//! points](crate::ecc::Point), are selected with a [`Choice`] through the
+//! [`CtSelect`] trait.
+
+use {docext::docext, std::ops};
+
+/// Mask which is set if $x \ge t$.
+#[docext]
+pub(crate) fn ge(x: i16, t: i16) -> i16 {
+    (t - 1 - x) >> 8
+}
+
+/// Mask which is set if $lo \le x \le hi$.
+#[docext]
+pub(crate) fn in_range(x: i16, lo: u8, hi: u8) -> i16 {
+    ge(x, i16::from(lo)) & ge(i16::from(hi), x)
+}
+
+/// A secret condition, for selecting between values without branching.
+///
+/// Unlike a `bool`, which the compiler is free to turn into a branch, a choice
+/// is only ever used as a mask: $0$ or $1$, stretched into a word of all zeros
+/// or all ones, and ANDed with the values to choose from. Choices are combined
+/// with the bitwise operators, which don't short-circuit like `&&` and `||`.
+///
+/// A choice should only be turned back into a `bool` once the result is
+/// public, for example after [comparing](CtSelect::ct_eq) a computed tag with
+/// the expected one.
+#[docext]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Choice(u8);
+
+impl Choice {
+    pub const FALSE: Self = Self(0);
+    pub const TRUE: Self = Self(1);
+
+    /// Create a choice from a bit, which must be 0 or 1.
+    pub fn from_bit(bit: u8) -> Self {
+        debug_assert!(bit <= 1, "a choice is 0 or 1");
+        Self(bit & 1)
+    }
+
+    /// The choice as a mask: all bits set if the choice is true, none
+    /// otherwise.
+    pub fn mask(self) -> u64 {
+        u64::from(self.0).wrapping_neg()
+    }
+}
+
+impl From<Choice> for bool {
+    fn from(choice: Choice) -> Self {
+        choice.0 == 1
+    }
+}
+
+impl ops::BitAnd for Choice {
+    type Output = Self;
+
+    fn bitand(self, rhs: Self) -> Self {
+        Self(self.0 & rhs.0)
+    }
+}
+
+impl ops::BitOr for Choice {
+    type Output = Self;
+
+    fn bitor(self, rhs: Self) -> Self {
+        Self(self.0 | rhs.0)
+    }
+}
+
+impl ops::BitXor for Choice {
+    type Output = Self;
+
+    fn bitxor(self, rhs: Self) -> Self {
+        Self(self.0 ^ rhs.0)
+    }
+}
+
+impl ops::Not for Choice {
+    type Output = Self;
+
+    fn not(self) -> Self {
+        Self(self.0 ^ 1)
+    }
+}
+
+/// Values which can be selected, swapped and compared without branching on a
+/// secret [choice](Choice).
+///
+/// Every bit of the selected value is computed as $(a \land \lnot m) \lor (b
+/// \land m)$ from the [mask](Choice::mask) $m$, so the same instructions run
+/// whichever value is chosen. Algorithms like the [Montgomery
+/// ladder](crate::ecc::Group::scale_ct) are written against this trait rather
+/// than an `if` on the secret.
+#[docext]
+pub trait CtSelect: Copy {
+    /// Select `a` if the choice is false, and `b` if it is true.
+    fn ct_select(a: &Self, b: &Self, choice: Choice) -> Self;
+
+    /// Compare two values, looking at every part of both.
+    fn ct_eq(&self, other: &Self) -> Choice;
+
+    /// Swap `a` and `b` if the choice is true.
+    fn ct_swap(a: &mut Self, b: &mut Self, choice: Choice) {
+        let (x, y) = (*a, *b);
+        *a = Self::ct_select(&x, &y, choice);
+        *b = Self::ct_select(&y, &x, choice);
+    }
+}
+
+impl CtSelect for u64 {
+    fn ct_select(a: &Self, b: &Self, choice: Choice) -> Self {
+        a ^ ((a ^ b) & choice.mask())
+    }
+
+    /// The difference $d = a \oplus b$ is zero exactly when $d \lor -d$ has
+    /// its top bit clear.
+    #[docext]
+    fn ct_eq(&self, other: &Self) -> Choice {
+        let d = self ^ other;
+        Choice::from_bit(((d | d.wrapping_neg()) >> 63) as u8 ^ 1)
+    }
+}
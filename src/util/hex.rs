@@ -0,0 +1,88 @@
+//! Hexadecimal (base 16) encoding of bytes, as specified in RFC 4648.
+//!
+//! Each byte is written as two hexadecimal digits, the most significant digit
+//! first. Encoding produces lowercase digits, and decoding accepts both cases.
+//!
+//! Since this module is used for key material, neither direction branches on
+//! or indexes a table with the encoded data. Instead, the digits are computed
+//! with arithmetic on [masks](super::ct). The only data-dependent branch is
+//! taken after decoding has failed, to find the position of the first invalid
+//! character.
+
+use {super::ct, std::fmt};
+
+/// Encode the bytes as lowercase hexadecimal digits.
+pub fn encode(bytes: &[u8]) -> String {
+    bytes
+        .iter()
+        .flat_map(|b| [b >> 4, b & 0xf])
+        .map(|n| char::from(encode_digit(n)))
+        .collect()
+}
+
+/// Decode hexadecimal digits into bytes. Both lowercase and uppercase digits
+/// are accepted.
+pub fn decode(hex: &str) -> Result<Vec<u8>, HexError> {
+    let hex = hex.as_bytes();
+    if hex.len() % 2 != 0 {
+        return Err(HexError::OddLength { len: hex.len() });
+    }
+    let mut invalid = 0;
+    let bytes = hex
+        .chunks_exact(2)
+        .map(|pair| {
+            let (hi, hi_valid) = decode_digit(pair[0]);
+            let (lo, lo_valid) = decode_digit(pair[1]);
+            invalid |= !hi_valid | !lo_valid;
+            (hi << 4) | lo
+        })
+        .collect();
+    if invalid == 0 {
+        Ok(bytes)
+    } else {
+        // Some pair contained an invalid digit, so there is one to find.
+        let index = hex.iter().position(|&c| decode_digit(c).1 == 0).unwrap();
+        Err(HexError::InvalidCharacter { index })
+    }
+}
+
+/// Convert a number between 0 and 15 into a lowercase hexadecimal digit.
+fn encode_digit(n: u8) -> u8 {
+    // The digits 0-9 start at b'0', and the digits a-f start 39 characters
+    // after b'0' + 10.
+    let n = i16::from(n);
+    (n + i16::from(b'0') + (ct::ge(n, 10) & 39)) as u8
+}
+
+/// Convert a hexadecimal digit into a number. The second value is `0xff` if
+/// the digit is valid and `0` otherwise.
+fn decode_digit(c: u8) -> (u8, u8) {
+    let c = i16::from(c);
+    let digit = ct::in_range(c, b'0', b'9');
+    let upper = ct::in_range(c, b'A', b'F');
+    let lower = ct::in_range(c, b'a', b'f');
+    let value = (digit & (c - i16::from(b'0')))
+        | (upper & (c - i16::from(b'A') + 10))
+        | (lower & (c - i16::from(b'a') + 10));
+    (value as u8, (digit | upper | lower) as u8)
+}
+
+/// Error returned when [decoding](decode) hexadecimal digits fails.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HexError {
+    /// The input has an odd number of digits.
+    OddLength { len: usize },
+    /// The byte at the given offset is not a hexadecimal digit.
+    InvalidCharacter { index: usize },
+}
+
+impl fmt::Display for HexError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::OddLength { len } => write!(f, "odd number of hex digits: {len}"),
+            Self::InvalidCharacter { index } => write!(f, "invalid hex digit at offset {index}"),
+        }
+    }
+}
+
+impl std::error::Error for HexError {}
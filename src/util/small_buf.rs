@@ -0,0 +1,90 @@
+use std::{fmt, ops::Deref};
+
+/// A byte buffer of at most `N` bytes, stored inline instead of on the heap.
+///
+/// Many hash inputs are short concatenations whose size is bounded in advance,
+/// e.g. a padded key followed by a digest. Building them in a `Vec` costs a
+/// heap allocation every time, which adds up in inner loops. This buffer lives
+/// on the stack and fails loudly if it would ever grow past its capacity.
+#[derive(Clone, Copy)]
+pub(crate) struct SmallBuf<const N: usize> {
+    buf: [u8; N],
+    len: usize,
+}
+
+impl<const N: usize> SmallBuf<N> {
+    pub const fn new() -> Self {
+        Self {
+            buf: [0; N],
+            len: 0,
+        }
+    }
+
+    /// Append the bytes, or return an error and leave the buffer unchanged if
+    /// they don't fit.
+    pub fn try_extend_from_slice(&mut self, data: &[u8]) -> Result<(), CapacityError> {
+        let required = self.len + data.len();
+        if required > N {
+            return Err(CapacityError {
+                capacity: N,
+                required,
+            });
+        }
+        self.buf[self.len..required].copy_from_slice(data);
+        self.len = required;
+        Ok(())
+    }
+
+    /// Append the bytes.
+    ///
+    /// Panics if they don't fit. Use this only where the size of the contents
+    /// is known not to exceed the capacity.
+    pub fn extend_from_slice(&mut self, data: &[u8]) {
+        if let Err(e) = self.try_extend_from_slice(data) {
+            panic!("{e}");
+        }
+    }
+
+    pub fn as_slice(&self) -> &[u8] {
+        &self.buf[..self.len]
+    }
+}
+
+impl<const N: usize> Default for SmallBuf<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const N: usize> Deref for SmallBuf<N> {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        self.as_slice()
+    }
+}
+
+impl<const N: usize> AsRef<[u8]> for SmallBuf<N> {
+    fn as_ref(&self) -> &[u8] {
+        self.as_slice()
+    }
+}
+
+/// The contents of a [`SmallBuf`] would exceed its capacity.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct CapacityError {
+    pub capacity: usize,
+    pub required: usize,
+}
+
+impl fmt::Display for CapacityError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "buffer of {} bytes cannot hold {} bytes",
+            self.capacity, self.required
+        )
+    }
+}
+
+impl std::error::Error for CapacityError {}
@@ -0,0 +1,61 @@
+use std::{
+    hint,
+    ops::{Deref, DerefMut},
+};
+
+/// Overwrite secret bytes, such as a key or a seed, with zeros once they are
+/// no longer needed.
+///
+/// Rust moves and drops values without clearing the memory they occupied, so a
+/// key which went out of scope can still be read back from the stack or the
+/// heap, e.g. from a core dump. Since the crate forbids `unsafe`, the store
+/// can't use volatile writes. Instead it goes through
+/// [`black_box`](hint::black_box), so that the compiler can't remove it as
+/// dead even though the bytes are about to be freed.
+///
+/// Wiping is best-effort: copies made by earlier moves, or left behind when a
+/// `Vec` reallocated, are out of reach. Secret [numbers](crate::ecc::Num) are
+/// wiped by `Num::wipe` instead.
+pub(crate) trait Zeroize {
+    fn zeroize(&mut self);
+}
+
+impl<T: AsMut<[u8]> + ?Sized> Zeroize for T {
+    fn zeroize(&mut self) {
+        let bytes = self.as_mut();
+        bytes.fill(0);
+        hint::black_box(&*bytes);
+    }
+}
+
+/// A value which is [zeroized](Zeroize) when it is dropped.
+///
+/// This is for secrets which are otherwise easy to forget about, such as a key
+/// captured by a closure which outlives the function that created it.
+pub(crate) struct Zeroizing<T: Zeroize>(T);
+
+impl<T: Zeroize> Zeroizing<T> {
+    pub fn new(value: T) -> Self {
+        Self(value)
+    }
+}
+
+impl<T: Zeroize> Deref for Zeroizing<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.0
+    }
+}
+
+impl<T: Zeroize> DerefMut for Zeroizing<T> {
+    fn deref_mut(&mut self) -> &mut T {
+        &mut self.0
+    }
+}
+
+impl<T: Zeroize> Drop for Zeroizing<T> {
+    fn drop(&mut self) {
+        self.0.zeroize();
+    }
+}
@@ -0,0 +1,595 @@
+//! A toy mutually authenticated handshake, loosely modelled on TLS 1.3 with a
+//! pre-shared key, built only from the public API of the crate.
+//!
+//! Both sides know each other's ECDSA identity key and share a PSK. The
+//! handshake takes three messages:
+//!
+//! ```text
+//! ClientHello    = version || client nonce || ephemeral public key
+//! ServerHello    = version || server nonce || KEM ciphertext || signature || finished
+//! ClientFinished = version || signature || finished
+//! ```
+//!
+//! The server encapsulates a secret to the client's ephemeral key with the
+//! [DHKEM](EciesKem), which is an ephemeral ECDH exchange. The shared secret
+//! and the PSK are combined by HKDF into a handshake secret, from which the
+//! traffic secrets and the _finished_ keys are expanded. The running transcript
+//! hash covers every message, and each side signs it with its identity key and
+//! MACs it with its finished key, so that a tampered message, a wrong identity
+//! or a wrong PSK is detected before any application data is sent.
+//!
+//! Application data is protected with [Encrypt-then-MAC](EncryptThenMac) over
+//! AES-128 in CTR mode and HMAC-SHA256. Every record is sealed under its own
+//! key, expanded from the traffic secret and the record's sequence number, so
+//! reordered or replayed records fail to open.
+//!
+//! Each state of the handshake is its own type, and every transition consumes
+//! the state it starts from, so that e.g. sending application data before the
+//! handshake completes, or finishing a handshake twice, doesn't compile.
+//!
+//! Composing the protocol from outside the crate needs a few helpers which the
+//! crate doesn't provide publicly yet: generating a random private key and
+//! encoding a public key. The secrets are derived with the crate's [`Hkdf`].
+
+use literate_crypto::{
+    codec::{CodecError, Reader, Writer},
+    ecc::{Num, Point, PrivateKey, PublicKey},
+    Aes128,
+    Aes256,
+    Csprng,
+    Ctr,
+    Ecdsa,
+    EcdsaSignature,
+    EciesKem,
+    EncryptThenMac,
+    Entropy,
+    EnvelopeError,
+    Fortuna,
+    Hkdf,
+    Hmac,
+    IncrementalHash,
+    Kem,
+    Mac,
+    Prk,
+    Secp256k1,
+    Sha256,
+    SignatureScheme,
+};
+
+/// Format version of every handshake message.
+const VERSION: u8 = 1;
+
+const NONCE_SIZE: usize = 32;
+const DIGEST_SIZE: usize = 32;
+/// An uncompressed SEC1 public key, as produced by the [KEM](EciesKem).
+const KEY_SIZE: usize = 65;
+const SIGNATURE_SIZE: usize = 64;
+
+/// Signature contexts, so that a signature of one side can't be reflected as
+/// one of the other.
+const SERVER_CONTEXT: &[u8] = b"toy handshake server signature";
+const CLIENT_CONTEXT: &[u8] = b"toy handshake client signature";
+
+type TranscriptState = <Sha256 as IncrementalHash>::State;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum HandshakeError {
+    /// A message couldn't be decoded.
+    Malformed(CodecError),
+    /// A public key or KEM ciphertext is not a valid point.
+    InvalidKey,
+    /// The transcript wasn't signed by the expected identity.
+    BadSignature,
+    /// The peer derived a different handshake secret, e.g. from another PSK.
+    BadFinished,
+    /// An application record was tampered with, replayed or reordered.
+    Record(EnvelopeError),
+}
+
+impl From<CodecError> for HandshakeError {
+    fn from(e: CodecError) -> Self {
+        Self::Malformed(e)
+    }
+}
+
+/// The long-term configuration of an endpoint.
+struct Endpoint {
+    identity: PrivateKey<Secp256k1>,
+    peer: PublicKey<Secp256k1>,
+    psk: Vec<u8>,
+}
+
+/// A client which hasn't sent anything yet.
+struct Client(Endpoint);
+
+/// A client waiting for the [`ServerHello`](Server::receive_hello).
+struct ClientHelloSent {
+    endpoint: Endpoint,
+    ephemeral: PrivateKey<Secp256k1>,
+    transcript: TranscriptState,
+}
+
+/// A server waiting for the `ClientHello`.
+struct Server(Endpoint);
+
+/// A server waiting for the
+/// [`ClientFinished`](ClientHelloSent::receive_hello).
+struct ServerHelloSent {
+    endpoint: Endpoint,
+    secrets: Secrets,
+    transcript: TranscriptState,
+}
+
+/// An endpoint which completed the handshake, and can exchange application
+/// data.
+struct Established {
+    send: Direction,
+    receive: Direction,
+}
+
+/// The secrets expanded from the handshake secret.
+struct Secrets {
+    client_finished: [u8; DIGEST_SIZE],
+    server_finished: [u8; DIGEST_SIZE],
+    client_traffic: Prk<DIGEST_SIZE>,
+    server_traffic: Prk<DIGEST_SIZE>,
+}
+
+/// The traffic secret and sequence number of one direction.
+struct Direction {
+    secret: Prk<DIGEST_SIZE>,
+    seq: u64,
+}
+
+impl Client {
+    fn hello(self, rng: &mut impl Csprng) -> (ClientHelloSent, Vec<u8>) {
+        let ephemeral = random_key(rng);
+        let mut msg = Writer::new(VERSION);
+        msg.write_bytes(&random_bytes::<NONCE_SIZE>(rng));
+        msg.write_bytes(&encode_key(ephemeral.derive()));
+        let msg = msg.into_bytes();
+
+        let mut transcript = Sha256::default().init();
+        Sha256::default().update(&mut transcript, &msg);
+        let state = ClientHelloSent {
+            endpoint: self.0,
+            ephemeral,
+            transcript,
+        };
+        (state, msg)
+    }
+}
+
+impl Server {
+    /// Receive the `ClientHello` and answer with the `ServerHello`.
+    fn receive_hello(
+        self,
+        client_hello: &[u8],
+        rng: &mut impl Csprng,
+    ) -> Result<(ServerHelloSent, Vec<u8>), HandshakeError> {
+        let mut reader = Reader::new(client_hello);
+        reader.read_version(VERSION)?;
+        reader.read_array::<NONCE_SIZE>()?;
+        let ephemeral = decode_key(reader.read_array()?)?;
+        reader.finish()?;
+
+        let (ciphertext, shared) = EciesKem::secp256k1().encapsulate(&ephemeral, rng);
+        let mut body = Writer::new(VERSION);
+        body.write_bytes(&random_bytes::<NONCE_SIZE>(rng));
+        body.write_bytes(&ciphertext);
+        let body = body.into_bytes();
+
+        let hash = Sha256::default();
+        let mut transcript = hash.init();
+        hash.update(&mut transcript, client_hello);
+        hash.update(&mut transcript, &body);
+        let th = hash.finalize(transcript.clone());
+        let secrets = Secrets::new(&self.0.psk, shared.as_bytes(), &th);
+
+        let sig = ecdsa().sign_with_context(self.0.identity.clone(), SERVER_CONTEXT, &th);
+        let finished = hmac(&secrets.server_finished, &th);
+        let mut msg = body;
+        msg.extend(encode_signature(sig));
+        msg.extend(finished);
+        hash.update(
+            &mut transcript,
+            &msg[msg.len() - SIGNATURE_SIZE - DIGEST_SIZE..],
+        );
+
+        let state = ServerHelloSent {
+            endpoint: self.0,
+            secrets,
+            transcript,
+        };
+        Ok((state, msg))
+    }
+}
+
+impl ClientHelloSent {
+    /// Receive the `ServerHello`, and answer with the `ClientFinished`.
+    fn receive_hello(self, server_hello: &[u8]) -> Result<(Established, Vec<u8>), HandshakeError> {
+        let mut reader = Reader::new(server_hello);
+        reader.read_version(VERSION)?;
+        reader.read_array::<NONCE_SIZE>()?;
+        let ciphertext = reader.read_array::<KEY_SIZE>()?;
+        let body_len = server_hello.len() - reader.remaining();
+        let sig = decode_signature(reader.read_array()?)?;
+        let finished = reader.read_array::<DIGEST_SIZE>()?;
+        reader.finish()?;
+
+        let shared = EciesKem::secp256k1()
+            .decapsulate(&self.ephemeral, &ciphertext)
+            .map_err(|_| HandshakeError::InvalidKey)?;
+        let hash = Sha256::default();
+        let mut transcript = self.transcript;
+        hash.update(&mut transcript, &server_hello[..body_len]);
+        let th = hash.finalize(transcript.clone());
+        ecdsa()
+            .verify_with_context(self.endpoint.peer, SERVER_CONTEXT, &th, &sig)
+            .map_err(|_| HandshakeError::BadSignature)?;
+        let secrets = Secrets::new(&self.endpoint.psk, shared.as_bytes(), &th);
+        if hmac(&secrets.server_finished, &th) != finished {
+            return Err(HandshakeError::BadFinished);
+        }
+
+        hash.update(&mut transcript, &server_hello[body_len..]);
+        let th = hash.finalize(transcript);
+        let sig = ecdsa().sign_with_context(self.endpoint.identity, CLIENT_CONTEXT, &th);
+        let mut msg = Writer::new(VERSION);
+        msg.write_bytes(&encode_signature(sig));
+        msg.write_bytes(&hmac(&secrets.client_finished, &th));
+
+        let established = Established::new(secrets.client_traffic, secrets.server_traffic);
+        Ok((established, msg.into_bytes()))
+    }
+}
+
+impl ServerHelloSent {
+    /// Receive the `ClientFinished`, completing the handshake.
+    fn receive_finished(self, client_finished: &[u8]) -> Result<Established, HandshakeError> {
+        let mut reader = Reader::new(client_finished);
+        reader.read_version(VERSION)?;
+        let sig = decode_signature(reader.read_array()?)?;
+        let finished = reader.read_array::<DIGEST_SIZE>()?;
+        reader.finish()?;
+
+        let th = Sha256::default().finalize(self.transcript);
+        ecdsa()
+            .verify_with_context(self.endpoint.peer, CLIENT_CONTEXT, &th, &sig)
+            .map_err(|_| HandshakeError::BadSignature)?;
+        if hmac(&self.secrets.client_finished, &th) != finished {
+            return Err(HandshakeError::BadFinished);
+        }
+        Ok(Established::new(
+            self.secrets.server_traffic,
+            self.secrets.client_traffic,
+        ))
+    }
+}
+
+impl Secrets {
+    /// Extract the handshake secret from the PSK and the KEM secret, and
+    /// expand it, bound to the transcript hash.
+    fn new(psk: &[u8], shared: &[u8], th: &[u8]) -> Self {
+        let mut hkdf = hkdf();
+        let secret = hkdf.extract(psk, shared);
+        let mut expand = |label: &[u8]| -> [u8; DIGEST_SIZE] {
+            hkdf.expand(&secret, &[label, th].concat(), DIGEST_SIZE)
+                .unwrap()
+                .try_into()
+                .unwrap()
+        };
+        Self {
+            client_finished: expand(b"c finished"),
+            server_finished: expand(b"s finished"),
+            client_traffic: Prk::from_bytes(expand(b"c ap traffic")),
+            server_traffic: Prk::from_bytes(expand(b"s ap traffic")),
+        }
+    }
+}
+
+impl Established {
+    fn new(send: Prk<DIGEST_SIZE>, receive: Prk<DIGEST_SIZE>) -> Self {
+        Self {
+            send: Direction {
+                secret: send,
+                seq: 0,
+            },
+            receive: Direction {
+                secret: receive,
+                seq: 0,
+            },
+        }
+    }
+
+    /// Seal the next outgoing record.
+    fn seal(&mut self, data: &[u8]) -> Vec<u8> {
+        let key = self.send.record_key();
+        self.send.seq += 1;
+        record_cipher().seal(data, &key).unwrap()
+    }
+
+    /// Open the next incoming record. A record which fails to open doesn't
+    /// advance the sequence number, so the genuine record can still follow.
+    fn open(&mut self, record: &[u8]) -> Result<Vec<u8>, HandshakeError> {
+        let key = self.receive.record_key();
+        let data = record_cipher()
+            .open(record, &key)
+            .map_err(HandshakeError::Record)?;
+        self.receive.seq += 1;
+        Ok(data)
+    }
+}
+
+impl Direction {
+    /// The key of the record with the current sequence number.
+    fn record_key(&self) -> Vec<u8> {
+        let info = [b"record".as_slice(), &self.seq.to_be_bytes()].concat();
+        hkdf().expand(&self.secret, &info, DIGEST_SIZE).unwrap()
+    }
+}
+
+fn ecdsa() -> Ecdsa<Secp256k1, Sha256> {
+    Ecdsa::new(Secp256k1::default(), Sha256::default())
+}
+
+/// Every record key is used once, so the CTR nonce can be fixed.
+fn record_cipher() -> EncryptThenMac<Ctr<Aes128>, Hmac<Sha256>> {
+    EncryptThenMac::new(
+        Ctr::new(Aes128::default(), 0).unwrap(),
+        Hmac::new(Sha256::default()),
+    )
+}
+
+fn hmac(key: &[u8], msg: &[u8]) -> [u8; DIGEST_SIZE] {
+    Hmac::new(Sha256::default()).mac(msg, key)
+}
+
+fn hkdf() -> Hkdf<Sha256> {
+    Hkdf::new(Sha256::default())
+}
+
+/// Draw random bytes until they form a valid private key.
+fn random_key(rng: &mut impl Csprng) -> PrivateKey<Secp256k1> {
+    loop {
+        if let Ok(key) = PrivateKey::new(Num::from_be_bytes(random_bytes(rng))) {
+            return key;
+        }
+    }
+}
+
+fn random_bytes<const N: usize>(rng: &mut impl Csprng) -> [u8; N] {
+    let mut bytes = [0; N];
+    rng.fill_bytes(&mut bytes);
+    bytes
+}
+
+/// Encode the public key in the uncompressed SEC1 encoding.
+fn encode_key(key: PublicKey<Secp256k1>) -> [u8; KEY_SIZE] {
+    let mut bytes = [0x04; KEY_SIZE];
+    bytes[1..33].copy_from_slice(&key.x().to_be_bytes());
+    bytes[33..].copy_from_slice(&key.y().to_be_bytes());
+    bytes
+}
+
+fn decode_key(bytes: [u8; KEY_SIZE]) -> Result<PublicKey<Secp256k1>, HandshakeError> {
+    let coordinate =
+        |range: std::ops::Range<usize>| Num::from_be_bytes(bytes[range].try_into().unwrap());
+    if bytes[0] != 0x04 {
+        return Err(HandshakeError::InvalidKey);
+    }
+    Point::new(coordinate(1..33), coordinate(33..65))
+        .ok()
+        .and_then(|point| PublicKey::new(point).ok())
+        .ok_or(HandshakeError::InvalidKey)
+}
+
+fn encode_signature(sig: EcdsaSignature<Secp256k1, Sha256>) -> [u8; SIGNATURE_SIZE] {
+    let mut bytes = [0; SIGNATURE_SIZE];
+    bytes[..32].copy_from_slice(&sig.r().to_be_bytes());
+    bytes[32..].copy_from_slice(&sig.s().to_be_bytes());
+    bytes
+}
+
+fn decode_signature(
+    bytes: [u8; SIGNATURE_SIZE],
+) -> Result<EcdsaSignature<Secp256k1, Sha256>, HandshakeError> {
+    let r = Num::from_be_bytes(bytes[..32].try_into().unwrap());
+    let s = Num::from_be_bytes(bytes[32..].try_into().unwrap());
+    EcdsaSignature::new(r, s).map_err(|_| HandshakeError::BadSignature)
+}
+
+/// Deterministic "entropy", so that the test is reproducible.
+struct Seed(u8);
+
+impl Entropy for Seed {
+    fn get(&mut self, buf: &mut [u8]) {
+        buf.fill(self.0);
+    }
+}
+
+fn rng(seed: u8) -> Fortuna<Seed> {
+    Fortuna::new(Seed(seed), Aes256::default(), Sha256::default()).unwrap()
+}
+
+/// The identity keys of the client and the server.
+struct Identities {
+    client: PrivateKey<Secp256k1>,
+    server: PrivateKey<Secp256k1>,
+}
+
+impl Identities {
+    fn new() -> Self {
+        let mut rng = rng(1);
+        Self {
+            client: random_key(&mut rng),
+            server: random_key(&mut rng),
+        }
+    }
+
+    fn client(&self) -> Client {
+        Client(Endpoint {
+            identity: self.client.clone(),
+            peer: self.server.derive(),
+            psk: b"pre-shared key".to_vec(),
+        })
+    }
+
+    fn server(&self) -> Server {
+        Server(Endpoint {
+            identity: self.server.clone(),
+            peer: self.client.derive(),
+            psk: b"pre-shared key".to_vec(),
+        })
+    }
+}
+
+/// The messages of a handshake, with the chance to modify each one in transit.
+struct Handshake {
+    client_hello: Vec<u8>,
+    server_hello: Vec<u8>,
+    client_finished: Vec<u8>,
+}
+
+/// Run a handshake between the client and the server, letting `tamper`
+/// modify the messages in transit.
+fn handshake(
+    client: Client,
+    server: Server,
+    tamper: impl Fn(&mut Handshake),
+) -> (
+    Result<Established, HandshakeError>,
+    Result<Established, HandshakeError>,
+) {
+    let (mut client_rng, mut server_rng) = (rng(2), rng(3));
+    let mut msgs = Handshake {
+        client_hello: Vec::new(),
+        server_hello: Vec::new(),
+        client_finished: Vec::new(),
+    };
+
+    let (client, hello) = client.hello(&mut client_rng);
+    msgs.client_hello = hello;
+    tamper(&mut msgs);
+    let (server, hello) = match server.receive_hello(&msgs.client_hello, &mut server_rng) {
+        Ok(result) => result,
+        Err(e) => return (Err(e), Err(e)),
+    };
+    msgs.server_hello = hello;
+    tamper(&mut msgs);
+    let (client, finished) = match client.receive_hello(&msgs.server_hello) {
+        Ok(result) => result,
+        Err(e) => return (Err(e), Err(e)),
+    };
+    msgs.client_finished = finished;
+    tamper(&mut msgs);
+    (Ok(client), server.receive_finished(&msgs.client_finished))
+}
+
+/// Complete a handshake and exchange application data in both directions.
+/// A tampered or replayed record is rejected, and the exchange resumes with
+/// the genuine record afterwards.
+#[test]
+fn handshake_and_records() {
+    let ids = Identities::new();
+    let (client, server) = handshake(ids.client(), ids.server(), |_| {});
+    let (mut client, mut server) = (client.unwrap(), server.unwrap());
+
+    let record = client.seal(b"GET /index.html");
+    assert_eq!(server.open(&record).unwrap(), b"GET /index.html");
+    let record = server.seal(b"200 OK");
+    assert_eq!(client.open(&record).unwrap(), b"200 OK");
+
+    // A replayed record was sealed under the key of an earlier sequence
+    // number.
+    assert_eq!(
+        client.open(&record),
+        Err(HandshakeError::Record(EnvelopeError::WrongKey))
+    );
+
+    let record = client.seal(b"GET /favicon.ico");
+    let mut tampered = record.clone();
+    tampered[10] ^= 1;
+    assert_eq!(
+        server.open(&tampered),
+        Err(HandshakeError::Record(EnvelopeError::Tampered))
+    );
+    assert_eq!(server.open(&record).unwrap(), b"GET /favicon.ico");
+
+    // Records sent out of order are rejected until their turn comes.
+    let first = server.seal(b"first");
+    let second = server.seal(b"second");
+    assert!(client.open(&second).is_err());
+    assert_eq!(client.open(&first).unwrap(), b"first");
+    assert_eq!(client.open(&second).unwrap(), b"second");
+}
+
+/// Flipping a bit of any handshake message makes the handshake fail.
+#[test]
+fn tampered_handshake() {
+    let ids = Identities::new();
+
+    // The client nonce: the server signs a different transcript than the
+    // client has.
+    let (client, _) = handshake(ids.client(), ids.server(), |msgs| {
+        if msgs.server_hello.is_empty() {
+            msgs.client_hello[5] ^= 1;
+        }
+    });
+    assert_eq!(client.err(), Some(HandshakeError::BadSignature));
+
+    // The server nonce, and the server signature.
+    for i in [5, 1 + NONCE_SIZE + KEY_SIZE + 5] {
+        let (client, _) = handshake(ids.client(), ids.server(), |msgs| {
+            if msgs.client_finished.is_empty() && !msgs.server_hello.is_empty() {
+                msgs.server_hello[i] ^= 1;
+            }
+        });
+        assert_eq!(client.err(), Some(HandshakeError::BadSignature), "byte {i}");
+    }
+
+    // The finished MAC of the client.
+    let (client, server) = handshake(ids.client(), ids.server(), |msgs| {
+        if let Some(b) = msgs.client_finished.last_mut() {
+            *b ^= 1;
+        }
+    });
+    assert!(client.is_ok());
+    assert_eq!(server.err(), Some(HandshakeError::BadFinished));
+
+    // A truncated message.
+    let (client, _) = handshake(ids.client(), ids.server(), |msgs| {
+        if msgs.server_hello.is_empty() {
+            msgs.client_hello.pop();
+        }
+    });
+    assert_eq!(
+        client.err(),
+        Some(HandshakeError::Malformed(CodecError::Truncated))
+    );
+}
+
+/// A peer with another identity key, or another PSK, is rejected.
+#[test]
+fn wrong_identity() {
+    let ids = Identities::new();
+    let impostor = random_key(&mut rng(4));
+
+    // The server isn't the one the client expects.
+    let Server(mut endpoint) = ids.server();
+    endpoint.identity = impostor.clone();
+    let (client, _) = handshake(ids.client(), Server(endpoint), |_| {});
+    assert_eq!(client.err(), Some(HandshakeError::BadSignature));
+
+    // The client isn't the one the server expects.
+    let Client(mut endpoint) = ids.client();
+    endpoint.identity = impostor;
+    let (_, server) = handshake(Client(endpoint), ids.server(), |_| {});
+    assert_eq!(server.err(), Some(HandshakeError::BadSignature));
+
+    // The identities are right, but the PSK isn't.
+    let Client(mut endpoint) = ids.client();
+    endpoint.psk = b"another key".to_vec();
+    let (client, _) = handshake(Client(endpoint), ids.server(), |_| {});
+    assert_eq!(client.err(), Some(HandshakeError::BadFinished));
+}
@@ -0,0 +1,65 @@
+//! Hashing a large preimage must not allocate per block, and computing a MAC
+//! of a short message must not allocate at all. This lives in an integration
+//! test because counting allocations requires a global allocator, which needs
+//! `unsafe` code, and the library forbids it.
+
+use {
+    literate_crypto::{
+        testing::{count_allocations, record_allocation},
+        Hash,
+        Hmac,
+        Mac,
+        Sha256,
+    },
+    std::alloc::{GlobalAlloc, Layout, System},
+};
+
+struct CountingAlloc;
+
+unsafe impl GlobalAlloc for CountingAlloc {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        record_allocation();
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static ALLOC: CountingAlloc = CountingAlloc;
+
+/// Hash 64 MiB with SHA-256 without any heap allocations.
+#[test]
+fn sha256_large_preimage() {
+    let preimage: Vec<u8> = (0..64 << 20).map(|i: u32| (i % 251) as u8).collect();
+    let hash = Sha256::default();
+
+    let (digest, allocations) = count_allocations(|| hash.hash(&preimage));
+
+    assert_eq!(allocations, 0);
+    assert_eq!(
+        digest
+            .iter()
+            .fold(String::new(), |s, b| s + &format!("{b:02x}")),
+        "98dc891b284e4d84ac25b0c0a24fdbe39a7f0dbd643ad5e8aa06e02fc6258254"
+    );
+}
+
+/// HMAC-SHA256 of a short message assembles both preimages on the stack. The
+/// expected tag is test case 2 from RFC 4231.
+#[test]
+fn hmac_short_message() {
+    let mut hmac = Hmac::new(Sha256::default());
+
+    let (tag, allocations) =
+        count_allocations(|| hmac.mac(b"what do ya want for nothing?", b"Jefe"));
+
+    assert_eq!(allocations, 0);
+    assert_eq!(
+        tag.iter()
+            .fold(String::new(), |s, b| s + &format!("{b:02x}")),
+        "5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843"
+    );
+}